@@ -0,0 +1,280 @@
+//! Files users send (photos, documents), and the retention policy that
+//! keeps them from piling up.
+//!
+//! Every channel keeps them in the same internal directory, out of the
+//! agent's workspace, named by the SHA-256 of their content so a file sent
+//! twice is stored once.
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+use crate::config::{self, AttachmentsConfig, Paths};
+
+/// What a cleanup removed, or would remove
+#[derive(Debug, Default, PartialEq)]
+pub struct CleanReport {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// A downloaded attachment
+#[derive(Debug, Clone, PartialEq)]
+struct StoredFile {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+/// Store a file a user sent, with `extension` (e.g. "jpg"), and return
+/// its path. Identical content is stored once.
+pub fn store(content: &[u8], extension: &str) -> Result<PathBuf> {
+    store_in(&config::paths()?.attachments_dir, content, extension)
+}
+
+/// Move a file a channel saved elsewhere (e.g. signal-cli) into the store
+pub fn store_file(path: &Path) -> Result<PathBuf> {
+    let content =
+        std::fs::read(path).with_context(|| format!("Could not read {}", path.display()))?;
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let stored = store(&content, extension)?;
+    if let Err(e) = std::fs::remove_file(path) {
+        warn!(
+            "Failed to remove {} after storing it: {}",
+            path.display(),
+            e
+        );
+    }
+    Ok(stored)
+}
+
+/// How an attachment is referenced in a prompt (Claude Code reads `@path`)
+pub fn prompt_reference(path: &Path) -> String {
+    format!("@{}", path.display())
+}
+
+fn store_in(dir: &Path, content: &[u8], extension: &str) -> Result<PathBuf> {
+    let hash: String = Sha256::digest(content)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    let path = dir.join(format!("{}.{}", hash, clean_extension(extension)));
+
+    if path.exists() {
+        // Sent again, so it counts as new for the retention policy
+        if let Err(e) = File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|f| f.set_modified(SystemTime::now()))
+        {
+            debug!("Couldn't update the time of {}: {}", path.display(), e);
+        }
+        debug!("Attachment already stored: {}", path.display());
+        return Ok(path);
+    }
+
+    std::fs::create_dir_all(dir)?;
+    let partial = path.with_extension("part");
+    std::fs::write(&partial, content)?;
+    std::fs::rename(&partial, &path)?;
+    Ok(path)
+}
+
+/// A file extension safe to put in a path, "bin" if there's none
+fn clean_extension(extension: &str) -> String {
+    let extension: String = extension
+        .trim_start_matches('.')
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .take(8)
+        .collect::<String>()
+        .to_lowercase();
+    if extension.is_empty() {
+        "bin".to_string()
+    } else {
+        extension
+    }
+}
+
+/// Where attachments are kept: the store, and where older versions and
+/// signal-cli left them
+pub fn dirs(paths: &Paths) -> Vec<PathBuf> {
+    vec![
+        paths.attachments_dir.clone(),
+        paths.internal_dir.join("telegram_attachments"),
+        paths.internal_dir.join("slack_attachments"),
+        paths.signal_data_dir.join("attachments"),
+    ]
+}
+
+/// Total size of the stored attachments
+pub fn total_size() -> Result<u64> {
+    let paths = config::paths()?;
+    Ok(list_files(&dirs(&paths)).iter().map(|f| f.size).sum())
+}
+
+/// Remove attachments older than the configured age, then the oldest ones
+/// until the rest fit the size limit. With `dry_run`, only report them.
+pub fn clean(config: &AttachmentsConfig, dry_run: bool) -> Result<CleanReport> {
+    let paths = config::paths()?;
+    let files = list_files(&dirs(&paths));
+
+    let mut report = CleanReport::default();
+    for file in expired(
+        files,
+        config.max_age(),
+        config.max_bytes(),
+        SystemTime::now(),
+    ) {
+        if !dry_run && let Err(e) = std::fs::remove_file(&file.path) {
+            warn!("Failed to remove {}: {}", file.path.display(), e);
+            continue;
+        }
+        report.files += 1;
+        report.bytes += file.size;
+    }
+    Ok(report)
+}
+
+/// The files under `dirs`, including subdirectories
+fn list_files(dirs: &[PathBuf]) -> Vec<StoredFile> {
+    let mut files = Vec::new();
+    let mut pending = dirs.to_vec();
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                pending.push(entry.path());
+            } else if meta.is_file() {
+                files.push(StoredFile {
+                    path: entry.path(),
+                    size: meta.len(),
+                    modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                });
+            }
+        }
+    }
+    files
+}
+
+/// The files to remove: those older than `max_age`, then the oldest of the
+/// rest until they add up to no more than `max_bytes`
+fn expired(
+    mut files: Vec<StoredFile>,
+    max_age: Option<Duration>,
+    max_bytes: Option<u64>,
+    now: SystemTime,
+) -> Vec<StoredFile> {
+    files.sort_by_key(|f| f.modified);
+    let mut total: u64 = files.iter().map(|f| f.size).sum();
+
+    files
+        .into_iter()
+        .filter(|file| {
+            let age = now.duration_since(file.modified).unwrap_or_default();
+            let too_old = max_age.is_some_and(|max| age > max);
+            let over_size = max_bytes.is_some_and(|max| total > max);
+            if too_old || over_size {
+                total -= file.size;
+            }
+            too_old || over_size
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn file(name: &str, size: u64, days_old: u32) -> StoredFile {
+        StoredFile {
+            path: PathBuf::from(name),
+            size,
+            modified: SystemTime::UNIX_EPOCH + DAY * (100 - days_old),
+        }
+    }
+
+    fn names(files: &[StoredFile]) -> Vec<&str> {
+        files.iter().map(|f| f.path.to_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_expired() {
+        let now = SystemTime::UNIX_EPOCH + DAY * 100;
+        let files = vec![
+            file("new.jpg", 300, 1),
+            file("old.jpg", 100, 40),
+            file("week.jpg", 500, 7),
+            file("month.jpg", 200, 29),
+        ];
+
+        assert!(expired(files.clone(), None, None, now).is_empty());
+        assert_eq!(
+            names(&expired(files.clone(), Some(DAY * 30), None, now)),
+            ["old.jpg"]
+        );
+        // The oldest go first until the rest fit
+        assert_eq!(
+            names(&expired(files.clone(), Some(DAY * 30), Some(800), now)),
+            ["old.jpg", "month.jpg"]
+        );
+        assert_eq!(
+            names(&expired(files, None, Some(0), now)),
+            ["old.jpg", "month.jpg", "week.jpg", "new.jpg"]
+        );
+    }
+
+    #[test]
+    fn test_store_dedupes_by_content() {
+        let dir = std::env::temp_dir().join(format!("cica-store-{}", std::process::id()));
+
+        let first = store_in(&dir, b"photo", "JPG").unwrap();
+        let again = store_in(&dir, b"photo", "jpg").unwrap();
+        let other = store_in(&dir, b"other photo", ".jpg").unwrap();
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+        assert_eq!(std::fs::read(&first).unwrap(), b"photo");
+        // The SHA-256 of the content, with the extension
+        let name = first.file_name().unwrap().to_str().unwrap();
+        assert_eq!(name.len(), 64 + ".jpg".len());
+        assert!(name.ends_with(".jpg"));
+        assert_eq!(list_files(std::slice::from_ref(&dir)).len(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_clean_extension() {
+        assert_eq!(clean_extension("JPG"), "jpg");
+        assert_eq!(clean_extension(".pdf"), "pdf");
+        assert_eq!(clean_extension("../x"), "x");
+        assert_eq!(clean_extension(""), "bin");
+    }
+
+    #[test]
+    fn test_list_files() {
+        let dir = std::env::temp_dir().join(format!("cica-attachments-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("a.jpg"), "abc").unwrap();
+        std::fs::write(dir.join("nested/b.pdf"), "de").unwrap();
+
+        let mut files = list_files(&[dir.clone(), dir.join("missing")]);
+        files.sort_by_key(|f| f.size);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, dir.join("nested/b.pdf"));
+        assert_eq!(files[1].size, 3);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,568 @@
+//! Claude Code integration
+
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command;
+use tracing::{debug, info, warn};
+
+use super::OnToolUse;
+use crate::config::{self, Config, Effort};
+use crate::setup;
+use crate::skills::{self, Skill, SkillPermissions};
+
+pub const MODELS: &[(&str, &str)] = &[
+    ("claude-opus-4-6", "Claude Opus 4.6"),
+    ("claude-opus-4-5", "Claude Opus 4.5"),
+    ("claude-sonnet-4-5", "Claude Sonnet 4.5"),
+];
+
+/// Tools denied in read-only mode (deny rules apply even when skipping permission prompts)
+const READ_ONLY_DISALLOWED_TOOLS: &[&str] = &["Bash", "Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// A line of `--output-format stream-json` output
+#[derive(Debug, Deserialize)]
+struct StreamEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    result: Option<String>,
+    session_id: Option<String>,
+    duration_ms: Option<u64>,
+    message: Option<StreamMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamMessage {
+    #[serde(default)]
+    content: Vec<ContentBlock>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentBlock {
+    ToolUse {
+        name: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// What has been read from Claude's output stream so far
+#[derive(Debug, Default)]
+pub(super) struct StreamState {
+    pub(super) session_id: Option<String>,
+    /// Names of the tools Claude used, in order
+    tools_used: Vec<String>,
+    /// Whether Claude has started replying (or using tools)
+    pub(super) replied: bool,
+    pub(super) result: Option<String>,
+    /// Whether the turn's result event has been seen
+    pub(super) finished: bool,
+    /// Lines that weren't stream events, for error messages
+    pub(super) other_output: String,
+}
+
+impl StreamState {
+    /// Handle a line, passing any newly used tools to `on_tool_use`
+    pub(super) fn handle_line_reporting(&mut self, line: &str, on_tool_use: Option<&OnToolUse>) {
+        let seen = self.tools_used.len();
+        self.handle_line(line);
+        if let Some(on_tool_use) = on_tool_use {
+            for name in &self.tools_used[seen..] {
+                on_tool_use(name);
+            }
+        }
+    }
+
+    fn handle_line(&mut self, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+
+        let Ok(event) = serde_json::from_str::<StreamEvent>(line) else {
+            self.other_output.push_str(line);
+            self.other_output.push('\n');
+            return;
+        };
+
+        if self.session_id.is_none()
+            && let Some(session_id) = event.session_id
+        {
+            debug!("Claude session: {}", session_id);
+            self.session_id = Some(session_id);
+        }
+
+        match event.event_type.as_str() {
+            "assistant" => {
+                self.replied = true;
+                let blocks = event.message.map(|m| m.content).unwrap_or_default();
+                for block in blocks {
+                    if let ContentBlock::ToolUse { name } = block {
+                        info!("Claude is using {}", name);
+                        self.tools_used.push(name);
+                    }
+                }
+            }
+            "result" => {
+                self.finished = true;
+                if let Some(result) = event.result {
+                    info!(
+                        "Claude response received ({}ms)",
+                        event.duration_ms.unwrap_or(0)
+                    );
+                    self.result = Some(result);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct QueryOptions {
+    pub system_prompt: Option<String>,
+    pub resume_session: Option<String>,
+    pub cwd: Option<String>,
+    pub skip_permissions: bool,
+    /// Deny tools that modify files or run commands
+    pub read_only: bool,
+    /// Directories to deny edits in (and Bash, which could edit them)
+    pub read_only_dirs: Vec<PathBuf>,
+    /// Model alias ("sonnet", "opus") or full model ID (e.g. "claude-sonnet-4-5-20250929")
+    pub model: Option<String>,
+    pub on_tool_use: Option<OnToolUse>,
+    pub effort: Option<Effort>,
+    /// The skill the message invokes, which gets the permissions it declares
+    pub skill: Option<String>,
+    /// Run a process just for this query even with `keep_alive`
+    pub one_shot: bool,
+}
+
+#[allow(dead_code)]
+pub async fn query(prompt: &str) -> Result<String> {
+    let (result, _) = query_with_options(prompt, QueryOptions::default()).await?;
+    Ok(result)
+}
+
+pub async fn query_with_options(prompt: &str, options: QueryOptions) -> Result<(String, String)> {
+    query_with_config(prompt, options, &Config::load()?).await
+}
+
+/// Query using the given config rather than the saved one (e.g. to test unsaved settings)
+pub async fn query_with_config(
+    prompt: &str,
+    options: QueryOptions,
+    config: &Config,
+) -> Result<(String, String)> {
+    if config.claude.keep_alive && !options.one_shot {
+        return super::keep_alive::query(prompt, options, config).await;
+    }
+
+    info!("Querying Claude: {}", prompt);
+
+    let mut cmd = command(&options, config)?;
+    cmd.arg(prompt);
+
+    // A query that times out or is interrupted is dropped, which has to stop
+    // the process too
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("Failed to start Claude Code")?;
+
+    let stdout = child.stdout.take().context("Claude stdout not captured")?;
+    let mut stderr = child.stderr.take().context("Claude stderr not captured")?;
+    let stderr_task = tokio::spawn(async move {
+        let mut output = String::new();
+        let _ = stderr.read_to_string(&mut output).await;
+        output
+    });
+
+    // Read events as they come rather than waiting for the whole run
+    let mut state = StreamState::default();
+    let mut lines = BufReader::new(stdout).lines();
+    while let Some(line) = lines.next_line().await? {
+        state.handle_line_reporting(&line, options.on_tool_use.as_ref());
+    }
+
+    let status = child.wait().await?;
+    let stderr = stderr_task.await.unwrap_or_default();
+
+    if !status.success() {
+        warn!("Claude CLI failed. stdout: {}", state.other_output);
+        warn!("Claude CLI failed. stderr: {}", stderr);
+        let err = anyhow!(
+            "Claude CLI failed (exit {:?}): {}{}",
+            status.code(),
+            stderr,
+            if stderr.is_empty() {
+                state.other_output.as_str()
+            } else {
+                ""
+            }
+        );
+        return Err(super::partway(err, state.replied));
+    }
+
+    match state.result {
+        Some(result) => Ok((result, state.session_id.unwrap_or_default())),
+        None => Err(super::partway(
+            anyhow!("No result found in Claude output"),
+            state.replied,
+        )),
+    }
+}
+
+/// The Claude Code command for `options`, without the prompt or stdio set up
+pub(super) fn command(options: &QueryOptions, config: &Config) -> Result<Command> {
+    let paths = config::paths()?;
+
+    let use_vertex = config.claude.use_vertex;
+    let vertex_project_id = config.claude.vertex_project_id.as_deref();
+    let credential = config.claude.api_key.as_deref();
+
+    if use_vertex {
+        let project_id = vertex_project_id
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| anyhow!("Vertex AI is enabled but no project ID is set. Run `cica init` to configure Vertex AI."))?;
+        debug!("Using Vertex AI project: {}", project_id);
+    } else {
+        credential.ok_or_else(|| {
+            anyhow!("No credential configured. Run `cica init` to set up Claude.")
+        })?;
+    }
+
+    let bun = setup::find_bun()
+        .ok_or_else(|| anyhow!("Bun not found. Run `cica init` to set up Claude."))?;
+
+    let claude_code = setup::find_claude_code()
+        .ok_or_else(|| anyhow!("Claude Code not found. Run `cica init` to set up Claude."))?;
+
+    debug!("Using bun: {:?}", bun);
+    debug!("Using claude_code: {:?}", claude_code);
+
+    let mut cmd = Command::new(&bun);
+    cmd.arg("run")
+        .arg(&claude_code)
+        .args(["-p", "--output-format", "stream-json", "--verbose"])
+        .env("HOME", &paths.claude_home);
+
+    if options.skip_permissions {
+        cmd.arg("--dangerously-skip-permissions");
+    }
+
+    let mcp = &config.mcp;
+    if !mcp.servers.is_empty() {
+        // A file rather than an argument, so the servers' tokens aren't in `ps`
+        let mcp_config = paths.internal_dir.join("mcp.json");
+        mcp.write_to(&mcp_config)?;
+        cmd.arg("--mcp-config").arg(&mcp_config);
+    }
+
+    // Skills only get what they declare, and only when invoked
+    let skill_scope = skill_scope(
+        &skills::discover_skills().unwrap_or_default(),
+        options.skill.as_deref(),
+    );
+    for var in &skill_scope.hidden_env {
+        cmd.env_remove(var);
+    }
+
+    let sandbox = &config.sandbox;
+    let mut disallowed: Vec<String> = sandbox.denied_tools.clone();
+    disallowed.extend(denied_path_rules(&sandbox.denied_paths));
+    disallowed.extend(skill_scope.disallowed);
+    if !options.read_only {
+        disallowed.extend(read_only_dir_rules(&options.read_only_dirs));
+    }
+    if options.read_only {
+        disallowed.extend(READ_ONLY_DISALLOWED_TOOLS.iter().map(|t| t.to_string()));
+        // MCP tools can change anything, so every server is denied
+        disallowed.extend(mcp.servers.keys().map(|name| format!("mcp__{}", name)));
+    }
+    if !disallowed.is_empty() {
+        cmd.args(["--disallowedTools", &disallowed.join(",")]);
+    }
+    let mut allowed = sandbox.allowed_tools.clone();
+    allowed.extend(skill_scope.allowed);
+    if !options.read_only {
+        // MCP servers are configured by the owner, so all their tools are allowed
+        allowed.extend(mcp.servers.keys().map(|name| format!("mcp__{}", name)));
+    }
+    if !allowed.is_empty() {
+        cmd.args(["--allowedTools", &allowed.join(",")]);
+    }
+
+    if let Some(ref system_prompt) = options.system_prompt {
+        if options.resume_session.is_none() {
+            // New session: full system prompt
+            cmd.args(["--system-prompt", system_prompt]);
+        } else {
+            // Resuming: append as reminder
+            cmd.args(["--append-system-prompt", system_prompt]);
+        }
+    }
+
+    if let Some(ref session_id) = options.resume_session {
+        cmd.args(["--resume", session_id]);
+    }
+
+    if let Some(ref model) = options.model {
+        cmd.args(["--model", model]);
+    }
+
+    if let Some(effort) = options.effort {
+        cmd.env("MAX_THINKING_TOKENS", effort.thinking_tokens().to_string());
+    }
+
+    if let Some(ref cwd) = options.cwd {
+        cmd.current_dir(cwd);
+    } else {
+        cmd.current_dir(&paths.base);
+    }
+
+    // Set auth env vars: either Vertex AI (GCP) or Anthropic API key / OAuth
+    if use_vertex {
+        cmd.env("CLAUDE_CODE_USE_VERTEX", "1");
+        cmd.env(
+            "ANTHROPIC_VERTEX_PROJECT_ID",
+            vertex_project_id.unwrap_or(""),
+        );
+        cmd.env(
+            "CLOUD_ML_REGION",
+            config
+                .claude
+                .vertex_region
+                .as_deref()
+                .unwrap_or("europe-west1"),
+        );
+        // Long-lived auth: service account key file (recommended for servers; no gcloud expiry)
+        if let Some(ref cred_path) = config.claude.vertex_credentials_path {
+            let path = std::path::Path::new(cred_path);
+            let abs = if path.is_relative() {
+                paths.base.join(cred_path)
+            } else {
+                path.to_path_buf()
+            };
+            if abs.exists() {
+                cmd.env("GOOGLE_APPLICATION_CREDENTIALS", &abs);
+            }
+        }
+        // Otherwise Vertex uses gcloud ADC or existing GOOGLE_APPLICATION_CREDENTIALS env
+    } else if let Some(cred) = credential {
+        match setup::detect_credential_type(cred) {
+            setup::CredentialType::ApiKey => {
+                cmd.env("ANTHROPIC_API_KEY", cred);
+            }
+            setup::CredentialType::OAuthToken => {
+                cmd.env("CLAUDE_CODE_OAUTH_TOKEN", cred);
+                cmd.env("ANTHROPIC_OAUTH_TOKEN", cred);
+            }
+        }
+    }
+
+    Ok(cmd)
+}
+
+/// Permission rules covering Claude's file tools on `paths`.
+/// Claude reads "/x" relative to its settings, so absolute paths become "//x".
+fn path_rules(paths: &[String]) -> Vec<String> {
+    paths
+        .iter()
+        .flat_map(|path| {
+            let path = if path.starts_with('/') {
+                format!("/{}", path)
+            } else {
+                path.clone()
+            };
+            [format!("Read({})", path), format!("Edit({})", path)]
+        })
+        .collect()
+}
+
+/// Rules keeping the agent out of `paths`. Shell commands could read them
+/// anyway, so Bash is denied too when there are any.
+fn denied_path_rules(paths: &[String]) -> Vec<String> {
+    let mut rules = path_rules(paths);
+    if !paths.is_empty() {
+        rules.push("Bash".to_string());
+    }
+    rules
+}
+
+/// Rules keeping the agent from changing `dirs` (absolute), which it can
+/// still read. Shell commands could write there anyway, so Bash is denied
+/// too when there are any.
+fn read_only_dir_rules(dirs: &[PathBuf]) -> Vec<String> {
+    let mut rules: Vec<String> = dirs
+        .iter()
+        .map(|dir| format!("Edit(/{}/**)", dir.display()))
+        .collect();
+    if !dirs.is_empty() {
+        rules.push("Bash".to_string());
+    }
+    rules
+}
+
+/// Permission rules covering what a skill declares: web fetches from its
+/// hosts, and reading and editing its paths
+fn skill_rules(permissions: &SkillPermissions) -> Vec<String> {
+    let mut rules: Vec<String> = permissions
+        .network
+        .iter()
+        .map(|host| format!("WebFetch(domain:{})", host))
+        .collect();
+    rules.extend(path_rules(&permissions.paths));
+    rules
+}
+
+/// What skills may use in one query
+#[derive(Debug, Default, PartialEq)]
+struct SkillScope {
+    /// Rules for what the invoked skill declares
+    allowed: Vec<String>,
+    /// Rules for what only the other skills declare
+    disallowed: Vec<String>,
+    /// Environment variables only the other skills read
+    hidden_env: Vec<String>,
+}
+
+/// Grant the invoked skill what it declares, and deny what only the other
+/// skills declare. Deny rules hold even when skipping permission prompts.
+fn skill_scope(skills: &[Skill], invoked: Option<&str>) -> SkillScope {
+    let granted = skills
+        .iter()
+        .find(|skill| Some(skill.name.as_str()) == invoked)
+        .map(|skill| skill.permissions.clone())
+        .unwrap_or_default();
+    let allowed = skill_rules(&granted);
+
+    let mut scope = SkillScope::default();
+    for skill in skills.iter().filter(|s| Some(s.name.as_str()) != invoked) {
+        for rule in skill_rules(&skill.permissions) {
+            if !allowed.contains(&rule) && !scope.disallowed.contains(&rule) {
+                scope.disallowed.push(rule);
+            }
+        }
+        for var in &skill.permissions.env {
+            if !granted.env.contains(var) && !scope.hidden_env.contains(var) {
+                scope.hidden_env.push(var.clone());
+            }
+        }
+    }
+    scope.allowed = allowed;
+    scope
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_state() {
+        let mut state = StreamState::default();
+        for line in [
+            r#"{"type":"system","subtype":"init","session_id":"abc","tools":["Bash"]}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Let me check."},{"type":"tool_use","id":"t1","name":"WebSearch","input":{}}]},"session_id":"abc"}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"..."}]},"session_id":"abc"}"#,
+            "",
+            "not json",
+            r#"{"type":"result","subtype":"success","result":"Sunny","session_id":"abc","duration_ms":1200}"#,
+        ] {
+            state.handle_line(line);
+        }
+
+        assert_eq!(state.session_id.as_deref(), Some("abc"));
+        assert_eq!(state.tools_used, vec!["WebSearch"]);
+        assert_eq!(state.result.as_deref(), Some("Sunny"));
+        assert!(state.finished);
+        assert_eq!(state.other_output, "not json\n");
+    }
+
+    #[test]
+    fn test_path_rules() {
+        let paths = vec!["/etc/**".to_string(), "~/.ssh/**".to_string()];
+        assert_eq!(
+            path_rules(&paths),
+            vec![
+                "Read(//etc/**)",
+                "Edit(//etc/**)",
+                "Read(~/.ssh/**)",
+                "Edit(~/.ssh/**)",
+            ]
+        );
+
+        assert!(denied_path_rules(&[]).is_empty());
+        assert_eq!(
+            denied_path_rules(&paths[1..]),
+            vec!["Read(~/.ssh/**)", "Edit(~/.ssh/**)", "Bash"]
+        );
+        assert!(read_only_dir_rules(&[]).is_empty());
+        assert_eq!(
+            read_only_dir_rules(&[PathBuf::from("/data/cica/skills")]),
+            vec!["Edit(//data/cica/skills/**)", "Bash"]
+        );
+    }
+
+    fn skill(name: &str, network: &[&str], env: &[&str]) -> Skill {
+        Skill {
+            name: name.to_string(),
+            description: String::new(),
+            location: format!("/skills/{}/SKILL.md", name).into(),
+            permissions: SkillPermissions {
+                network: network.iter().map(|s| s.to_string()).collect(),
+                env: env.iter().map(|s| s.to_string()).collect(),
+                paths: Vec::new(),
+            },
+            healthcheck: None,
+        }
+    }
+
+    #[test]
+    fn test_skill_rules() {
+        let permissions = SkillPermissions {
+            network: vec!["api.weather.gov".to_string()],
+            env: vec!["WEATHER_API_KEY".to_string()],
+            paths: vec!["~/weather".to_string()],
+        };
+        assert_eq!(
+            skill_rules(&permissions),
+            vec![
+                "WebFetch(domain:api.weather.gov)",
+                "Read(~/weather)",
+                "Edit(~/weather)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skill_scope() {
+        let skills = [
+            skill("weather", &["api.weather.gov"], &["WEATHER_API_KEY"]),
+            skill("github", &["api.github.com"], &["GITHUB_TOKEN"]),
+            skill("gists", &["api.github.com"], &["GITHUB_TOKEN"]),
+        ];
+
+        let scope = skill_scope(&skills, Some("github"));
+        assert_eq!(scope.allowed, vec!["WebFetch(domain:api.github.com)"]);
+        assert_eq!(scope.disallowed, vec!["WebFetch(domain:api.weather.gov)"]);
+        assert_eq!(scope.hidden_env, vec!["WEATHER_API_KEY"]);
+
+        // No skill invoked: nothing granted, every declared host denied
+        let scope = skill_scope(&skills, None);
+        assert!(scope.allowed.is_empty());
+        assert_eq!(
+            scope.disallowed,
+            vec![
+                "WebFetch(domain:api.weather.gov)",
+                "WebFetch(domain:api.github.com)"
+            ]
+        );
+        assert_eq!(scope.hidden_env, vec!["WEATHER_API_KEY", "GITHUB_TOKEN"]);
+    }
+}
@@ -98,6 +98,14 @@ struct CursorEvent {
     is_error: Option<bool>,
 }
 
+/// Whether Cursor's output shows it started replying or using tools
+fn replied(stdout: &str) -> bool {
+    stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CursorEvent>(line).ok())
+        .any(|event| matches!(event.event_type.as_str(), "assistant" | "tool_call"))
+}
+
 #[derive(Default)]
 pub struct QueryOptions {
     pub context: Option<String>,
@@ -114,10 +122,18 @@ pub async fn query(prompt: &str) -> Result<String> {
 }
 
 pub async fn query_with_options(prompt: &str, options: QueryOptions) -> Result<(String, String)> {
-    let config = Config::load()?;
+    query_with_config(prompt, options, &Config::load()?).await
+}
+
+/// Query using the given config rather than the saved one (e.g. to test unsaved settings)
+pub async fn query_with_config(
+    prompt: &str,
+    options: QueryOptions,
+    config: &Config,
+) -> Result<(String, String)> {
     let paths = config::paths()?;
 
-    let api_key = config.cursor.api_key.ok_or_else(|| {
+    let api_key = config.cursor.api_key.clone().ok_or_else(|| {
         anyhow!("No Cursor API key configured. Run `cica init` to set up Cursor.")
     })?;
 
@@ -133,6 +149,7 @@ pub async fn query_with_options(prompt: &str, options: QueryOptions) -> Result<(
     debug!("Using cursor_cli: {:?}", cursor_cli);
 
     ensure_keychain(&paths.cursor_home).await?;
+    write_mcp_config(&paths.cursor_home, config)?;
 
     let mut cmd = Command::new(&cursor_cli);
     cmd.args(["-p", "--output-format", "stream-json"])
@@ -146,7 +163,7 @@ pub async fn query_with_options(prompt: &str, options: QueryOptions) -> Result<(
 
     let model = options
         .model
-        .or(config.cursor.model)
+        .or_else(|| config.cursor.model.clone())
         .unwrap_or_else(|| DEFAULT_MODEL.to_string());
     cmd.args(["--model", &model]);
 
@@ -162,10 +179,12 @@ pub async fn query_with_options(prompt: &str, options: QueryOptions) -> Result<(
 
     cmd.arg(&full_prompt);
 
+    // A query that times out is dropped, which has to stop the process too
     let output = cmd
         .stdin(Stdio::null())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
+        .kill_on_drop(true)
         .output()
         .await?;
 
@@ -175,12 +194,13 @@ pub async fn query_with_options(prompt: &str, options: QueryOptions) -> Result<(
     if !output.status.success() {
         warn!("Cursor CLI failed. stdout: {}", stdout);
         warn!("Cursor CLI failed. stderr: {}", stderr);
-        bail!(
+        let err = anyhow!(
             "Cursor CLI failed (exit {:?}): {}{}",
             output.status.code(),
             stderr,
             if stderr.is_empty() { &stdout } else { "" }
         );
+        return Err(super::partway(err, replied(&stdout)));
     }
 
     debug!("Cursor raw output: {}", stdout);
@@ -277,3 +297,10 @@ async fn ensure_keychain(cursor_home: &Path) -> Result<()> {
 async fn ensure_keychain(_cursor_home: &Path) -> Result<()> {
     Ok(())
 }
+/// Write the MCP servers from config.toml where the Cursor CLI reads them.
+/// Its home is Cica's own, so the file is ours to replace.
+fn write_mcp_config(cursor_home: &Path, config: &Config) -> Result<()> {
+    config
+        .mcp
+        .write_to(&cursor_home.join(".cursor").join("mcp.json"))
+}
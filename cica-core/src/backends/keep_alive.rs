@@ -0,0 +1,318 @@
+//! Kept-alive Claude Code processes (`keep_alive` under [claude]).
+//!
+//! Instead of starting Claude Code for every message, a process is started with
+//! `--input-format stream-json` and kept running per conversation, keyed by its
+//! session ID. Messages are written to its stdin and its events read until the
+//! turn's result. Idle processes are stopped after `keep_alive_minutes`; a
+//! process that has died is replaced by a fresh one resuming the session.
+
+use anyhow::{Context, Result, anyhow};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex, Once, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout};
+use tracing::{debug, info, warn};
+
+use super::OnToolUse;
+use super::claude::{self, QueryOptions, StreamState};
+use crate::config::Config;
+
+/// How often idle processes are looked for
+const REAP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How much of a process's stderr is kept for error messages
+const STDERR_TAIL: usize = 2000;
+
+/// Idle processes by session ID. A process is taken out while it answers, so
+/// a query that is dropped (e.g. timed out) kills its process.
+static SESSIONS: OnceLock<Mutex<HashMap<String, LiveSession>>> = OnceLock::new();
+
+static REAPER: Once = Once::new();
+
+struct LiveSession {
+    // Killed on drop
+    _child: Child,
+    stdin: ChildStdin,
+    lines: Lines<BufReader<ChildStdout>>,
+    stderr: Arc<Mutex<String>>,
+    /// The flags the process was started with; other options need a new process
+    flags: String,
+    /// The latest context prompt Claude has seen in this process
+    context: Option<String>,
+    last_used: Instant,
+}
+
+impl LiveSession {
+    fn spawn(options: &QueryOptions, config: &Config, flags: String) -> Result<Self> {
+        let mut cmd = claude::command(options, config)?;
+        cmd.args(["--input-format", "stream-json"]);
+        Self::start(cmd, options.system_prompt.clone(), flags)
+    }
+
+    /// Start `cmd` with piped stdio; `context` is the system prompt it was given
+    fn start(
+        mut cmd: tokio::process::Command,
+        context: Option<String>,
+        flags: String,
+    ) -> Result<Self> {
+        let mut child = cmd
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to start Claude Code")?;
+
+        let stdin = child.stdin.take().context("Claude stdin not captured")?;
+        let stdout = child.stdout.take().context("Claude stdout not captured")?;
+        let stderr = child.stderr.take().context("Claude stderr not captured")?;
+
+        // Drain stderr for as long as the process lives, keeping the end of it
+        let stderr_tail = Arc::new(Mutex::new(String::new()));
+        let tail = stderr_tail.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                debug!("Claude stderr: {}", line);
+                if let Ok(mut tail) = tail.lock() {
+                    tail.push_str(&line);
+                    tail.push('\n');
+                    if tail.len() > STDERR_TAIL {
+                        let mut cut = tail.len() - STDERR_TAIL;
+                        while !tail.is_char_boundary(cut) {
+                            cut += 1;
+                        }
+                        tail.drain(..cut);
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            _child: child,
+            stdin,
+            lines: BufReader::new(stdout).lines(),
+            stderr: stderr_tail,
+            flags,
+            context,
+            last_used: Instant::now(),
+        })
+    }
+
+    /// Send one message and read events until its result
+    async fn send(
+        &mut self,
+        message: &str,
+        on_tool_use: Option<&OnToolUse>,
+    ) -> Result<(String, String)> {
+        let event = serde_json::json!({
+            "type": "user",
+            "message": {
+                "role": "user",
+                "content": [{ "type": "text", "text": message }],
+            },
+        });
+        self.stdin
+            .write_all(format!("{}\n", event).as_bytes())
+            .await
+            .context("Claude Code isn't accepting messages")?;
+        self.stdin.flush().await?;
+
+        let mut state = StreamState::default();
+        while let Some(line) = self.lines.next_line().await? {
+            state.handle_line_reporting(&line, on_tool_use);
+            if state.finished {
+                self.last_used = Instant::now();
+                let session_id = state.session_id.unwrap_or_default();
+                return match state.result {
+                    Some(result) => Ok((result, session_id)),
+                    None => Err(super::partway(
+                        anyhow!("No result found in Claude output"),
+                        state.replied,
+                    )),
+                };
+            }
+        }
+
+        let stderr = self.stderr.lock().map(|s| s.clone()).unwrap_or_default();
+        Err(super::partway(
+            anyhow!("Claude Code exited: {}{}", stderr, state.other_output),
+            state.replied,
+        ))
+    }
+}
+
+/// `prompt`, with the context prompt as a reminder if it isn't the one Claude
+/// saw last (a running process can't change its system prompt)
+fn with_context(prompt: &str, context: Option<&str>, seen: &mut Option<String>) -> String {
+    match context {
+        Some(context) if seen.as_deref() != Some(context) => {
+            *seen = Some(context.to_string());
+            format!(
+                "<system-reminder>\n{}\n</system-reminder>\n\n{}",
+                context, prompt
+            )
+        }
+        _ => prompt.to_string(),
+    }
+}
+
+/// Query Claude through a kept-alive process, starting one if needed
+pub(super) async fn query(
+    prompt: &str,
+    options: QueryOptions,
+    config: &Config,
+) -> Result<(String, String)> {
+    start_reaper(config.claude.keep_alive_idle());
+    info!("Querying Claude (kept alive): {}", prompt);
+
+    let flags = flags(&options, config);
+    let live = options
+        .resume_session
+        .as_deref()
+        .and_then(take)
+        .filter(|live| live.flags == flags);
+
+    if let Some(mut live) = live {
+        let message = with_context(prompt, options.system_prompt.as_deref(), &mut live.context);
+        match live.send(&message, options.on_tool_use.as_ref()).await {
+            Ok((result, session_id)) => {
+                put(session_id.clone(), live, config.queries.max_concurrent);
+                return Ok((result, session_id));
+            }
+            // Sending the message again could repeat what Claude already did
+            Err(e) if e.is::<super::FailedPartway>() => return Err(e),
+            // Most likely the process crashed; resume the session in a new one
+            Err(e) => warn!("Kept-alive Claude Code failed, restarting it: {}", e),
+        }
+    }
+
+    let mut live = LiveSession::spawn(&options, config, flags)?;
+    let (result, session_id) = live.send(prompt, options.on_tool_use.as_ref()).await?;
+    put(session_id.clone(), live, config.queries.max_concurrent);
+    Ok((result, session_id))
+}
+
+/// Everything a process is started with besides the prompt and session
+fn flags(options: &QueryOptions, config: &Config) -> String {
+    format!(
+        "{:?}|{}|{}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}",
+        options.cwd,
+        options.skip_permissions,
+        options.read_only,
+        options.read_only_dirs,
+        options.model,
+        options.effort,
+        options.skill,
+        config.sandbox,
+        config.mcp
+    )
+}
+
+fn sessions() -> &'static Mutex<HashMap<String, LiveSession>> {
+    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn take(session_id: &str) -> Option<LiveSession> {
+    sessions().lock().ok()?.remove(session_id)
+}
+
+/// Keep `live` for the next message, stopping the least recently used
+/// process if more than `max` would be left running
+fn put(session_id: String, live: LiveSession, max: usize) {
+    if session_id.is_empty() {
+        return;
+    }
+    let Ok(mut sessions) = sessions().lock() else {
+        return;
+    };
+
+    sessions.insert(session_id, live);
+    while sessions.len() > max.max(1) {
+        let Some(oldest) = sessions
+            .iter()
+            .min_by_key(|(_, live)| live.last_used)
+            .map(|(id, _)| id.clone())
+        else {
+            break;
+        };
+        debug!("Stopping kept-alive Claude Code for {}", oldest);
+        sessions.remove(&oldest);
+    }
+}
+
+/// Stop processes that have been idle for longer than `idle`
+fn start_reaper(idle: Duration) {
+    REAPER.call_once(|| {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(REAP_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Ok(mut sessions) = sessions().lock() {
+                    reap(&mut sessions, idle);
+                }
+            }
+        });
+    });
+}
+
+/// Drop (and so kill) the processes in `sessions` idle for `idle` or longer
+fn reap(sessions: &mut HashMap<String, LiveSession>, idle: Duration) {
+    sessions.retain(|session_id, live| {
+        let keep = live.last_used.elapsed() < idle;
+        if !keep {
+            debug!("Stopping idle Claude Code for {}", session_id);
+        }
+        keep
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_context() {
+        let mut seen = Some("You are Cica.".to_string());
+        assert_eq!(with_context("Hi", Some("You are Cica."), &mut seen), "Hi");
+        assert_eq!(
+            with_context("Hi", Some("You are Cica. It's Monday."), &mut seen),
+            "<system-reminder>\nYou are Cica. It's Monday.\n</system-reminder>\n\nHi"
+        );
+        assert_eq!(seen.as_deref(), Some("You are Cica. It's Monday."));
+        assert_eq!(with_context("Hi", None, &mut seen), "Hi");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_idle_process_is_stopped() {
+        let cat = || {
+            LiveSession::start(tokio::process::Command::new("cat"), None, String::new()).unwrap()
+        };
+        let mut idle = cat();
+        idle.last_used -= Duration::from_secs(120);
+        let pid = idle._child.id().unwrap();
+
+        let mut sessions = HashMap::from([("idle".to_string(), idle), ("busy".to_string(), cat())]);
+        reap(&mut sessions, Duration::from_secs(60));
+        assert_eq!(sessions.keys().collect::<Vec<_>>(), vec!["busy"]);
+
+        // Dropping it killed the process
+        let alive = || {
+            std::process::Command::new("kill")
+                .args(["-0", &pid.to_string()])
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success())
+        };
+        for _ in 0..50 {
+            if !alive() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("idle process {} is still running", pid);
+    }
+}
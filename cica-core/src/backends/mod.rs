@@ -0,0 +1,471 @@
+//! AI Backend abstraction for Claude Code, Cursor CLI and OpenAI-compatible APIs
+
+pub mod claude;
+#[cfg(feature = "cursor")]
+pub mod cursor;
+mod keep_alive;
+pub mod openai;
+
+use std::hash::{BuildHasher, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tracing::{info, warn};
+
+use crate::config::{AiBackend, Config, Effort};
+use crate::metrics;
+use crate::skills;
+
+/// Prompt for the post-configuration self-test: cheap, and needs no tools
+const TEST_PROMPT: &str = "Reply with just the word OK.";
+
+/// How long a query waits for a free slot before the user is told it's queued
+const QUEUED_NOTICE_AFTER: Duration = Duration::from_secs(5);
+
+/// Wait before the first retry of a query that failed transiently
+const RETRY_INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Upper bound for the wait between retries
+const RETRY_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Parts of error messages that mean the failure will probably pass (status
+/// codes with a space after them, so they don't match inside IDs)
+const TRANSIENT_ERRORS: &[&str] = &[
+    "overloaded",
+    "529 ",
+    "rate limit",
+    "429 ",
+    "internal server error",
+    "500 ",
+    "502 ",
+    "bad gateway",
+    "503 ",
+    "service unavailable",
+    "504 ",
+    "gateway timeout",
+    "econnreset",
+    "connection reset",
+    "econnrefused",
+    "connection refused",
+    "etimedout",
+    "connection timed out",
+    "operation timed out",
+    "socket hang up",
+    "fetch failed",
+];
+
+/// Limits how many queries run at once, sized from the config on first use
+static QUERY_SLOTS: OnceLock<Semaphore> = OnceLock::new();
+
+/// Called when a query has to wait for a free slot
+pub type OnQueued = Box<dyn FnOnce() + Send + Sync>;
+
+/// Called with the name of each tool the backend uses, as it uses it
+pub type OnToolUse = Arc<dyn Fn(&str) + Send + Sync>;
+
+#[derive(Default)]
+pub struct QueryOptions {
+    pub system_prompt: Option<String>,
+    pub resume_session: Option<String>,
+    pub cwd: Option<String>,
+    pub skip_permissions: bool,
+    /// Only allow tools that read; no file edits or shell commands
+    pub read_only: bool,
+    /// Directories the agent can read but not change, e.g. skills/ for
+    /// roles without the skills permission
+    pub read_only_dirs: Vec<PathBuf>,
+    /// Give up after this long (default: `timeout_minutes` under [queries])
+    pub timeout: Option<Duration>,
+    /// Called once if the query waits a while for another one to finish
+    pub on_queued: Option<OnQueued>,
+    /// Not every backend reports tool use (Cursor CLI doesn't)
+    pub on_tool_use: Option<OnToolUse>,
+    /// Overrides the backend's configured effort; Cursor CLI has no setting for it
+    pub effort: Option<Effort>,
+    /// Overrides the configured backend
+    pub backend: Option<AiBackend>,
+    /// Overrides the backend's configured model
+    pub model: Option<String>,
+    /// The conversation won't get another message soon (cron jobs,
+    /// summaries), so no kept-alive process is kept for it
+    pub one_shot: bool,
+}
+
+/// A query stopped because it ran past its timeout
+#[derive(Debug)]
+pub struct QueryTimedOut(pub Duration);
+
+impl std::fmt::Display for QueryTimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The AI backend didn't answer within {} minute(s)",
+            self.0.as_secs().div_ceil(60)
+        )
+    }
+}
+
+impl std::error::Error for QueryTimedOut {}
+
+/// A query failed after the backend had started replying or used a tool.
+/// These aren't retried, since a retry could repeat what it already did.
+#[derive(Debug)]
+pub struct FailedPartway(String);
+
+impl std::fmt::Display for FailedPartway {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for FailedPartway {}
+
+/// `err`, marked as [`FailedPartway`] if the backend had `started` replying
+fn partway(err: anyhow::Error, started: bool) -> anyhow::Error {
+    if started {
+        FailedPartway(format!("{:#}", err)).into()
+    } else {
+        err
+    }
+}
+
+/// Query the configured AI backend, returning (response, session_id).
+pub async fn query_with_options(prompt: &str, options: QueryOptions) -> Result<(String, String)> {
+    query_with_config(prompt, options, &Config::load()?).await
+}
+
+/// Query the backend selected in `config`, which need not be saved yet.
+/// Transient failures before any reply or tool use are retried with backoff,
+/// up to `retries` under [queries]. Fails with [`QueryTimedOut`] if the
+/// backend takes too long, retries included; dropping the query kills the
+/// backend process.
+pub async fn query_with_config(
+    prompt: &str,
+    mut options: QueryOptions,
+    config: &Config,
+) -> Result<(String, String)> {
+    let _slot = acquire_slot(config, options.on_queued.take()).await?;
+
+    // A query that used a tool isn't retried, even if the backend didn't say
+    let used_tool = Arc::new(AtomicBool::new(false));
+    let on_tool_use = options.on_tool_use.take();
+    options.on_tool_use = Some(Arc::new({
+        let used_tool = used_tool.clone();
+        move |name: &str| {
+            used_tool.store(true, Ordering::Relaxed);
+            if let Some(on_tool_use) = &on_tool_use {
+                on_tool_use(name);
+            }
+        }
+    }));
+
+    let timeout = options.timeout.unwrap_or_else(|| config.queries.timeout());
+    let backend = options.backend.unwrap_or(config.backend).as_str();
+    let started = Instant::now();
+    let query = async {
+        let mut backoff = RETRY_INITIAL_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            let err = match query_backend(prompt, &options, config).await {
+                Ok(reply) => return Ok(reply),
+                Err(e) => e,
+            };
+
+            attempt += 1;
+            if attempt > config.queries.retries
+                || used_tool.load(Ordering::Relaxed)
+                || !is_transient(&err)
+            {
+                return Err(err);
+            }
+
+            let wait = jitter(backoff);
+            warn!(
+                "AI backend query failed (attempt {}), retrying in {:?}: {}",
+                attempt, wait, err
+            );
+            tokio::time::sleep(wait).await;
+            backoff = next_backoff(backoff);
+        }
+    };
+
+    let result = match tokio::time::timeout(timeout, query).await {
+        Ok(result) => result,
+        Err(_) => {
+            warn!("AI backend query timed out after {:?}", timeout);
+            Err(QueryTimedOut(timeout).into())
+        }
+    };
+
+    metrics::increment(&format!("backend.queries.{}", backend));
+    metrics::add_to(
+        &format!("backend.query_ms.{}", backend),
+        started.elapsed().as_millis() as u64,
+    );
+    if result.is_err() {
+        metrics::increment(&format!("backend.errors.{}", backend));
+    }
+    result
+}
+
+async fn query_backend(
+    prompt: &str,
+    options: &QueryOptions,
+    config: &Config,
+) -> Result<(String, String)> {
+    match options.backend.unwrap_or(config.backend) {
+        AiBackend::Claude => query_claude(prompt, options, config).await,
+        #[cfg(feature = "cursor")]
+        AiBackend::Cursor => query_cursor(prompt, options, config).await,
+        #[cfg(not(feature = "cursor"))]
+        AiBackend::Cursor => {
+            anyhow::bail!("This build doesn't include the Cursor CLI backend")
+        }
+        AiBackend::OpenAi => query_openai(prompt, options, config).await,
+    }
+}
+
+/// Whether a failed query is worth trying again
+fn is_transient(err: &anyhow::Error) -> bool {
+    if err.is::<QueryTimedOut>() || err.is::<FailedPartway>() {
+        return false;
+    }
+    let message = format!("{:#}", err).to_lowercase();
+    // A missing session won't come back; the caller starts a new one
+    if message.contains("no conversation found") {
+        return false;
+    }
+    TRANSIENT_ERRORS.iter().any(|e| message.contains(e))
+}
+
+/// The wait before the retry after one that waited `backoff`
+fn next_backoff(backoff: Duration) -> Duration {
+    (backoff * 2).min(RETRY_MAX_BACKOFF)
+}
+
+/// A random duration between half of `wait` and `wait`, so that queries
+/// failing together don't retry together
+fn jitter(wait: Duration) -> Duration {
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    let fraction = (random % 1000) as f64 / 1000.0;
+    wait.mul_f64(0.5 + fraction / 2.0)
+}
+
+/// Wait for a free query slot, calling `on_queued` if that takes a while
+async fn acquire_slot(
+    config: &Config,
+    on_queued: Option<OnQueued>,
+) -> Result<SemaphorePermit<'static>> {
+    let slots = QUERY_SLOTS.get_or_init(|| Semaphore::new(config.queries.max_concurrent.max(1)));
+    wait_for_slot(slots, QUEUED_NOTICE_AFTER, on_queued).await
+}
+
+/// Wait for a permit from `slots`, calling `on_queued` after `notice_after`
+async fn wait_for_slot(
+    slots: &Semaphore,
+    notice_after: Duration,
+    on_queued: Option<OnQueued>,
+) -> Result<SemaphorePermit<'_>> {
+    let acquire = slots.acquire();
+    tokio::pin!(acquire);
+    if let Ok(permit) = tokio::time::timeout(notice_after, &mut acquire).await {
+        return Ok(permit?);
+    }
+
+    info!("All query slots are busy, waiting for one");
+    if let Some(on_queued) = on_queued {
+        on_queued();
+    }
+    Ok(acquire.await?)
+}
+
+async fn query_claude(
+    prompt: &str,
+    options: &QueryOptions,
+    config: &Config,
+) -> Result<(String, String)> {
+    let claude_options = claude::QueryOptions {
+        system_prompt: options.system_prompt.clone(),
+        resume_session: options.resume_session.clone(),
+        cwd: options.cwd.clone(),
+        skip_permissions: options.skip_permissions,
+        read_only: options.read_only,
+        read_only_dirs: options.read_only_dirs.clone(),
+        model: options
+            .model
+            .clone()
+            .or_else(|| config.claude.model.clone()),
+        on_tool_use: options.on_tool_use.clone(),
+        effort: options.effort.or(config.claude.effort),
+        skill: skills::invoked(prompt, &skills::discover_skills().unwrap_or_default())
+            .map(|skill| skill.name.clone()),
+        one_shot: options.one_shot,
+    };
+
+    claude::query_with_config(prompt, claude_options, config).await
+}
+
+#[cfg(feature = "cursor")]
+async fn query_cursor(
+    prompt: &str,
+    options: &QueryOptions,
+    config: &Config,
+) -> Result<(String, String)> {
+    let cursor_options = cursor::QueryOptions {
+        context: options.system_prompt.clone(),
+        resume_session: options.resume_session.clone(),
+        cwd: options.cwd.clone(),
+        // Without --force, Cursor won't run commands or write files
+        // unattended. It has no narrower rule, so that's how read-only
+        // directories are kept.
+        force: options.skip_permissions && !options.read_only && options.read_only_dirs.is_empty(),
+        model: options
+            .model
+            .clone()
+            .or_else(|| config.cursor.model.clone()),
+    };
+
+    cursor::query_with_config(prompt, cursor_options, config).await
+}
+
+async fn query_openai(
+    prompt: &str,
+    options: &QueryOptions,
+    config: &Config,
+) -> Result<(String, String)> {
+    let openai_options = openai::QueryOptions {
+        system_prompt: options.system_prompt.clone(),
+        resume_session: options.resume_session.clone(),
+        cwd: options.cwd.clone(),
+        read_only: options.read_only,
+        read_only_dirs: options.read_only_dirs.clone(),
+        model: options
+            .model
+            .clone()
+            .or_else(|| config.openai.model.clone()),
+        on_tool_use: options.on_tool_use.clone(),
+        effort: options.effort.or(config.openai.effort),
+    };
+
+    openai::query_with_config(prompt, openai_options, config).await
+}
+
+/// Send a tiny query with `config`'s backend and model, returning how long it took.
+/// Used to catch unusable settings (e.g. a model the key can't access) before saving.
+pub async fn test_query(config: &Config) -> Result<Duration> {
+    let started = Instant::now();
+    let options = QueryOptions {
+        read_only: true,
+        one_shot: true,
+        ..Default::default()
+    };
+    query_with_config(TEST_PROMPT, options, config).await?;
+    Ok(started.elapsed())
+}
+
+#[allow(dead_code)]
+pub fn current_backend_name() -> Result<&'static str> {
+    let config = Config::load()?;
+    Ok(config.backend.display_name())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[tokio::test]
+    async fn test_queries_beyond_the_limit_queue() {
+        let slots = Semaphore::new(2);
+        let running = AtomicUsize::new(0);
+        let most_running = AtomicUsize::new(0);
+        let queued = Arc::new(AtomicUsize::new(0));
+
+        let query = || async {
+            let notify = queued.clone();
+            let on_queued: OnQueued = Box::new(move || {
+                notify.fetch_add(1, Ordering::SeqCst);
+            });
+            let _permit = wait_for_slot(&slots, Duration::from_millis(20), Some(on_queued))
+                .await
+                .unwrap();
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            most_running.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+        };
+
+        // Three queries with two slots: one waits for another to finish
+        tokio::join!(query(), query(), query());
+        assert_eq!(most_running.load(Ordering::SeqCst), 2);
+        assert_eq!(queued.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient(&anyhow::anyhow!(
+            "Claude CLI failed (exit Some(1)): API Error: 529 {{\"type\":\"overloaded_error\"}}"
+        )));
+        assert!(is_transient(&anyhow::anyhow!(
+            "API request failed (503 Service Unavailable): upstream"
+        )));
+        assert!(!is_transient(&anyhow::anyhow!(
+            "API request failed (401 Unauthorized): invalid key"
+        )));
+        assert!(!is_transient(&anyhow::anyhow!(
+            "No conversation found with session ID: abc"
+        )));
+        assert!(!is_transient(
+            &QueryTimedOut(Duration::from_secs(600)).into()
+        ));
+        assert!(is_transient(&anyhow::anyhow!("read ECONNRESET")));
+        assert!(is_transient(&anyhow::anyhow!(
+            "connect ETIMEDOUT 1.2.3.4:443"
+        )));
+        assert!(is_transient(&anyhow::anyhow!(
+            "API request failed (504 Gateway Timeout): upstream"
+        )));
+        assert!(is_transient(&anyhow::anyhow!(
+            "error sending request: operation timed out"
+        )));
+        assert!(!is_transient(&anyhow::anyhow!(
+            "Claude CLI failed (exit Some(1)): Invalid API key · network access denied"
+        )));
+        assert!(!is_transient(&anyhow::anyhow!(
+            "Claude CLI failed (exit Some(1)): Unknown network mode"
+        )));
+
+        // Nothing is retried once the backend started replying
+        let overloaded = anyhow::anyhow!("API Error: 529 overloaded");
+        assert!(!is_transient(&partway(overloaded, true)));
+        assert!(is_transient(&partway(
+            anyhow::anyhow!("API Error: 529 overloaded"),
+            false
+        )));
+    }
+
+    #[test]
+    fn test_next_backoff() {
+        let mut backoff = RETRY_INITIAL_BACKOFF;
+        let mut waits = Vec::new();
+        for _ in 0..6 {
+            waits.push(backoff.as_secs());
+            backoff = next_backoff(backoff);
+        }
+        assert_eq!(waits, [2, 4, 8, 16, 30, 30]);
+    }
+
+    #[test]
+    fn test_jitter() {
+        for _ in 0..100 {
+            let wait = jitter(Duration::from_secs(10));
+            assert!(wait >= Duration::from_secs(5) && wait <= Duration::from_secs(10));
+        }
+    }
+}
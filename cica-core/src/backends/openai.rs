@@ -0,0 +1,754 @@
+//! OpenAI-compatible chat completions API (OpenAI, OpenRouter, vLLM, LM Studio)
+//!
+//! These servers don't keep conversations, so each session's messages are
+//! stored locally and sent again with every query. The model gets a few file
+//! tools, confined to Cica's data directory, so it can keep identities,
+//! profiles and memories like the CLI backends do, and a web search tool when
+//! [search] is set up.
+
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use rusqlite::{Connection, params};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tracing::{debug, info};
+
+use super::OnToolUse;
+use crate::config::{self, Config, Effort, SearchConfig};
+use crate::{search, storage};
+
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+const DEFAULT_MODEL: &str = "gpt-4o";
+
+/// Earlier messages beyond this many are left out of the request
+const MAX_HISTORY_MESSAGES: usize = 100;
+
+/// Tool calls the model may make before it has to answer
+const MAX_TOOL_ROUNDS: usize = 20;
+
+/// Longest file a tool call returns, in bytes
+const MAX_FILE_BYTES: usize = 100_000;
+
+/// Symlinks followed in a tool call's path before giving up, as the OS does
+const MAX_SYMLINKS: usize = 40;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Default)]
+pub struct QueryOptions {
+    pub system_prompt: Option<String>,
+    pub resume_session: Option<String>,
+    pub cwd: Option<String>,
+    /// Only offer the tools that read files
+    pub read_only: bool,
+    /// Directories the file tools can read but not write
+    pub read_only_dirs: Vec<PathBuf>,
+    pub model: Option<String>,
+    pub on_tool_use: Option<OnToolUse>,
+    pub effort: Option<Effort>,
+}
+
+/// A message kept in a session's history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ChatMessage {
+    role: String,
+    content: String,
+}
+
+impl ChatMessage {
+    fn new(role: &str, content: &str) -> Self {
+        Self {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: ResponseMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseMessage {
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<ToolCall>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ToolCall {
+    id: String,
+    #[serde(rename = "type", default = "ToolCall::default_type")]
+    call_type: String,
+    function: FunctionCall,
+}
+
+impl ToolCall {
+    fn default_type() -> String {
+        "function".to_string()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FunctionCall {
+    name: String,
+    arguments: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolArgs {
+    path: String,
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchArgs {
+    query: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    data: Vec<ModelInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelInfo {
+    id: String,
+    owned_by: Option<String>,
+}
+
+/// List the models the server offers as (id, owner)
+pub async fn list_models(base_url: &str, api_key: Option<&str>) -> Result<Vec<(String, String)>> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()?;
+    let mut request = client.get(format!("{}/models", base_url.trim_end_matches('/')));
+    if let Some(key) = api_key.filter(|k| !k.is_empty()) {
+        request = request.bearer_auth(key);
+    }
+
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        bail!("{} ({})", status, response.text().await.unwrap_or_default());
+    }
+
+    let mut models: Vec<(String, String)> = response
+        .json::<ModelList>()
+        .await?
+        .data
+        .into_iter()
+        .map(|m| (m.id, m.owned_by.unwrap_or_default()))
+        .collect();
+    models.sort();
+    Ok(models)
+}
+
+pub async fn query_with_options(prompt: &str, options: QueryOptions) -> Result<(String, String)> {
+    query_with_config(prompt, options, &Config::load()?).await
+}
+
+/// Query using the given config rather than the saved one (e.g. to test unsaved settings)
+pub async fn query_with_config(
+    prompt: &str,
+    options: QueryOptions,
+    config: &Config,
+) -> Result<(String, String)> {
+    let paths = config::paths()?;
+
+    let base_url = config.openai.base_url.as_deref().ok_or_else(|| {
+        anyhow!("No OpenAI-compatible API configured. Run `cica init` to set one up.")
+    })?;
+    let url = format!("{}/chat/completions", base_url.trim_end_matches('/'));
+    let model = options
+        .model
+        .or_else(|| config.openai.model.clone())
+        .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+
+    let (session_id, history) = match options.resume_session {
+        Some(session_id) => {
+            let history = storage::read(|conn| load_history(conn, &session_id))?;
+            if history.is_empty() {
+                bail!("No conversation found with session ID {}", session_id);
+            }
+            (session_id, history)
+        }
+        None => (uuid::Uuid::new_v4().to_string(), Vec::new()),
+    };
+
+    let mut new_messages = Vec::new();
+    if let Some(system_prompt) = &options.system_prompt {
+        new_messages.push(ChatMessage::new("system", system_prompt));
+    }
+    new_messages.push(ChatMessage::new("user", prompt));
+
+    let mut messages: Vec<Value> = build_messages(&history, &new_messages)
+        .into_iter()
+        .map(|m| json!(m))
+        .collect();
+
+    let cwd = options
+        .cwd
+        .map(PathBuf::from)
+        .unwrap_or_else(|| paths.base.clone());
+    let tools = tool_definitions(options.read_only, config.search.is_configured());
+    let limits = ToolLimits {
+        denied: &config.sandbox.denied_paths,
+        read_only: options.read_only,
+        read_only_dirs: &options.read_only_dirs,
+    };
+
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()?;
+    let api_key = config.openai.api_key.as_deref().filter(|k| !k.is_empty());
+
+    info!("Querying {} ({}): {}", base_url, model, prompt);
+
+    let mut reply = None;
+    for _ in 0..MAX_TOOL_ROUNDS {
+        let mut body = json!({
+            "model": model,
+            "messages": messages,
+            "tools": tools,
+        });
+        if let Some(effort) = options.effort {
+            body["reasoning_effort"] = json!(effort.as_str());
+        }
+        let mut request = client.post(&url).json(&body);
+        if let Some(key) = api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let response = request.send().await.context("Request to the API failed")?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            bail!("API request failed ({}): {}", status, body);
+        }
+        debug!("API response: {}", body);
+
+        let message = serde_json::from_str::<ChatResponse>(&body)
+            .context("Unexpected response from the API")?
+            .choices
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("The API returned no choices"))?
+            .message;
+
+        if message.tool_calls.is_empty() {
+            reply = Some(message.content.unwrap_or_default());
+            break;
+        }
+
+        messages.push(json!({
+            "role": "assistant",
+            "content": message.content,
+            "tool_calls": message.tool_calls,
+        }));
+        for call in &message.tool_calls {
+            if let Some(on_tool_use) = &options.on_tool_use {
+                on_tool_use(&call.function.name);
+            }
+            let result = if call.function.name == "web_search" {
+                run_search(call, &config.search).await
+            } else {
+                run_tool(call, &paths.base, &cwd, &limits)
+            };
+            messages.push(json!({
+                "role": "tool",
+                "tool_call_id": call.id,
+                "content": result,
+            }));
+        }
+    }
+
+    let reply = reply.ok_or_else(|| anyhow!("The model kept calling tools without answering"))?;
+    info!("API response received");
+
+    // Tool calls are left out of the history; the reply is what matters later.
+    // So is the system prompt, since every query sends the current one.
+    new_messages.retain(|m| m.role != "system");
+    new_messages.push(ChatMessage::new("assistant", &reply));
+    storage::write(|conn| append_history(conn, &session_id, &new_messages))?;
+
+    Ok((reply, session_id))
+}
+
+/// Messages for a request: the latest system prompt first (many servers only
+/// accept one, at the start), then the most recent of the conversation
+fn build_messages(history: &[ChatMessage], new_messages: &[ChatMessage]) -> Vec<ChatMessage> {
+    let all = || history.iter().chain(new_messages);
+
+    let system = history
+        .iter()
+        .chain(new_messages)
+        .rev()
+        .find(|m| m.role == "system");
+    let conversation: Vec<&ChatMessage> = all().filter(|m| m.role != "system").collect();
+    let skip = conversation.len().saturating_sub(MAX_HISTORY_MESSAGES);
+
+    let mut messages: Vec<ChatMessage> = system.into_iter().cloned().collect();
+    // Context is sent once per session, so it's kept when older messages aren't
+    messages.extend(
+        conversation[..skip]
+            .iter()
+            .filter(|m| is_context(m))
+            .map(|m| (*m).clone()),
+    );
+    messages.extend(conversation[skip..].iter().map(|m| (*m).clone()));
+    messages
+}
+
+/// Whether a user message carries context (see `context_sync`)
+fn is_context(message: &ChatMessage) -> bool {
+    message.role == "user"
+        && (message.content.starts_with("<context>")
+            || message.content.starts_with("<context_update>"))
+}
+
+fn load_history(conn: &Connection, session_id: &str) -> Result<Vec<ChatMessage>> {
+    let mut stmt =
+        conn.prepare("SELECT role, content FROM chat_history WHERE session_id = ?1 ORDER BY id")?;
+    let rows = stmt.query_map([session_id], |row| {
+        Ok(ChatMessage {
+            role: row.get(0)?,
+            content: row.get(1)?,
+        })
+    })?;
+
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+fn append_history(conn: &Connection, session_id: &str, messages: &[ChatMessage]) -> Result<()> {
+    for message in messages {
+        conn.execute(
+            "INSERT INTO chat_history (session_id, role, content) VALUES (?1, ?2, ?3)",
+            params![session_id, message.role, message.content],
+        )?;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// File Tools
+// ============================================================================
+
+fn tool_definitions(read_only: bool, web_search: bool) -> Vec<Value> {
+    let path_param = json!({
+        "type": "string",
+        "description": "Absolute path, or relative to the workspace directory",
+    });
+    let function = |name: &str, description: &str, parameters: Value| {
+        json!({
+            "type": "function",
+            "function": {
+                "name": name,
+                "description": description,
+                "parameters": parameters,
+            },
+        })
+    };
+
+    let mut tools = vec![
+        function(
+            "read_file",
+            "Read a text file",
+            json!({
+                "type": "object",
+                "properties": { "path": path_param },
+                "required": ["path"],
+            }),
+        ),
+        function(
+            "list_files",
+            "List the files and directories in a directory",
+            json!({
+                "type": "object",
+                "properties": { "path": path_param },
+                "required": ["path"],
+            }),
+        ),
+    ];
+    if !read_only {
+        tools.push(function(
+            "write_file",
+            "Create or overwrite a text file, creating missing directories",
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": path_param,
+                    "content": { "type": "string" },
+                },
+                "required": ["path", "content"],
+            }),
+        ));
+    }
+    if web_search {
+        tools.push(function(
+            "web_search",
+            "Search the web for current information; returns titles, URLs and snippets",
+            json!({
+                "type": "object",
+                "properties": { "query": { "type": "string" } },
+                "required": ["query"],
+            }),
+        ));
+    }
+    tools
+}
+
+/// Run a web_search call, returning the results or the error for the model
+async fn run_search(call: &ToolCall, config: &SearchConfig) -> String {
+    info!("Tool call: web_search {}", call.function.arguments);
+    let result = async {
+        let args: SearchArgs =
+            serde_json::from_str(&call.function.arguments).context("Invalid arguments")?;
+        search::search(config, &args.query).await
+    }
+    .await;
+    match result {
+        Ok(results) => search::format_results(&results),
+        Err(e) => format!("Error: {:#}", e),
+    }
+}
+
+/// Run a tool call, returning what to tell the model (including errors)
+/// What the file tools may not do
+struct ToolLimits<'a> {
+    /// [sandbox] denied_paths
+    denied: &'a [String],
+    read_only: bool,
+    /// Directories the tools can read but not write, e.g. skills/ for roles
+    /// without the skills permission
+    read_only_dirs: &'a [PathBuf],
+}
+
+fn run_tool(call: &ToolCall, root: &Path, cwd: &Path, limits: &ToolLimits) -> String {
+    info!(
+        "Tool call: {} {}",
+        call.function.name, call.function.arguments
+    );
+    match try_run_tool(call, root, cwd, limits) {
+        Ok(result) => result,
+        Err(e) => format!("Error: {:#}", e),
+    }
+}
+
+fn try_run_tool(call: &ToolCall, root: &Path, cwd: &Path, limits: &ToolLimits) -> Result<String> {
+    let args: ToolArgs =
+        serde_json::from_str(&call.function.arguments).context("Invalid arguments")?;
+    let path = resolve_path(root, cwd, &args.path)?;
+    if is_denied(&path, root, limits.denied) {
+        bail!("{} is in [sandbox] denied_paths", args.path);
+    }
+
+    match call.function.name.as_str() {
+        "read_file" => {
+            let content = std::fs::read_to_string(&path)?;
+            if content.len() > MAX_FILE_BYTES {
+                let mut end = MAX_FILE_BYTES;
+                while !content.is_char_boundary(end) {
+                    end -= 1;
+                }
+                return Ok(format!("{}\n[truncated]", &content[..end]));
+            }
+            Ok(content)
+        }
+        "list_files" => {
+            let mut names = Vec::new();
+            for entry in std::fs::read_dir(&path)? {
+                let entry = entry?;
+                let mut name = entry.file_name().to_string_lossy().into_owned();
+                if entry.file_type()?.is_dir() {
+                    name.push('/');
+                }
+                names.push(name);
+            }
+            names.sort();
+            Ok(names.join("\n"))
+        }
+        "write_file" if !limits.read_only => {
+            if limits
+                .read_only_dirs
+                .iter()
+                .any(|dir| path.starts_with(canonicalize_existing(dir)))
+            {
+                bail!("This user's role can't change {}", args.path);
+            }
+            let content = args.content.unwrap_or_default();
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, &content)?;
+            Ok(format!(
+                "Wrote {} bytes to {}",
+                content.len(),
+                path.display()
+            ))
+        }
+        name => bail!("Unknown tool: {}", name),
+    }
+}
+
+/// Resolve a path from a tool call, following symlinks, and refuse
+/// anything outside `root` or holding Cica's own state
+fn resolve_path(root: &Path, cwd: &Path, path: &str) -> Result<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in cwd.join(path).components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+
+    let root = canonicalize_existing(root);
+    let resolved = canonicalize_existing(&normalized);
+    let Ok(relative) = resolved.strip_prefix(&root) else {
+        bail!("{} is outside the workspace", path);
+    };
+    if is_protected(relative) {
+        bail!("{} is Cica's own data and can't be used", path);
+    }
+    Ok(resolved)
+}
+
+/// `path` with symlinks resolved as far as it exists (a file about to be
+/// written doesn't yet), including a link to a file that doesn't exist
+fn canonicalize_existing(path: &Path) -> PathBuf {
+    canonicalize_within(path, MAX_SYMLINKS)
+}
+
+fn canonicalize_within(path: &Path, links_left: usize) -> PathBuf {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        let resolved = match existing.canonicalize() {
+            Ok(canonical) => Some(canonical),
+            // A dangling link: where writing through it would create a file
+            Err(_) if links_left > 0 => std::fs::read_link(existing).ok().map(|target| {
+                let target = existing.parent().unwrap_or(existing).join(target);
+                canonicalize_within(&target, links_left - 1)
+            }),
+            Err(_) => None,
+        };
+        if let Some(resolved) = resolved {
+            return rest
+                .iter()
+                .rev()
+                .fold(resolved, |path, name| path.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+/// Whether `path` (resolved) matches one of [sandbox] `denied_paths`, or is
+/// inside one. As for Claude, "~/" is the home directory and relative
+/// patterns are from the data directory.
+fn is_denied(path: &Path, root: &Path, denied: &[String]) -> bool {
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    let root = canonicalize_existing(root);
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    denied.iter().any(|pattern| {
+        let pattern = pattern.trim_end_matches('/');
+        let full = match (pattern.strip_prefix("~/"), &home) {
+            (Some(rest), Some(home)) => home.join(rest),
+            _ => root.join(pattern),
+        };
+        let full = full.to_string_lossy();
+        [full.to_string(), format!("{}/**", full)]
+            .iter()
+            .filter_map(|p| glob::Pattern::new(p).ok())
+            .any(|p| p.matches_path_with(path, options))
+    })
+}
+
+/// Whether a path in the data directory is config, the database or
+/// internal files (tokens, dependencies), apart from attachments users sent
+fn is_protected(relative: &Path) -> bool {
+    let mut components = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy());
+    let Some(first) = components.next() else {
+        return false;
+    };
+    match first.as_ref() {
+        "config.toml" | "pairing.json" | "cron.json" | "memory.db" => true,
+        "internal" => components.next().as_deref() != Some("attachments"),
+        name => name.starts_with("cica.db") || name.starts_with("config.toml."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_messages() {
+        let history = vec![
+            ChatMessage::new("system", "You are Cica."),
+            ChatMessage::new("user", "Hi"),
+            ChatMessage::new("assistant", "Hello!"),
+        ];
+        let new_messages = vec![
+            ChatMessage::new("system", "The user moved to Oslo."),
+            ChatMessage::new("user", "Weather?"),
+        ];
+
+        assert_eq!(
+            build_messages(&history, &new_messages),
+            vec![
+                ChatMessage::new("system", "The user moved to Oslo."),
+                ChatMessage::new("user", "Hi"),
+                ChatMessage::new("assistant", "Hello!"),
+                ChatMessage::new("user", "Weather?"),
+            ]
+        );
+
+        let long: Vec<ChatMessage> = (0..MAX_HISTORY_MESSAGES + 10)
+            .map(|i| ChatMessage::new("user", &i.to_string()))
+            .collect();
+        let messages = build_messages(&long, &[]);
+        assert_eq!(messages.len(), MAX_HISTORY_MESSAGES);
+        assert_eq!(messages[0].content, "10");
+
+        // The context block outlives the messages around it
+        let mut long = long;
+        long.insert(
+            0,
+            ChatMessage::new("user", "<context>\n## Profile\n</context>\n\nHi"),
+        );
+        let messages = build_messages(&long, &[]);
+        assert_eq!(messages.len(), MAX_HISTORY_MESSAGES + 1);
+        assert!(messages[0].content.starts_with("<context>"));
+        assert_eq!(messages[1].content, "10");
+    }
+
+    #[test]
+    fn test_resolve_path_stays_in_root() {
+        let root = Path::new("/data/cica");
+        let cwd = Path::new("/data/cica/users/telegram_1");
+
+        assert_eq!(
+            resolve_path(root, cwd, "memories/pets.md").unwrap(),
+            Path::new("/data/cica/users/telegram_1/memories/pets.md")
+        );
+        assert_eq!(
+            resolve_path(root, cwd, "/data/cica/skills/x.md").unwrap(),
+            Path::new("/data/cica/skills/x.md")
+        );
+        assert!(resolve_path(root, cwd, "../../../../etc/passwd").is_err());
+        assert!(resolve_path(root, cwd, "/etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_resolve_path_refuses_cica_state() {
+        let root = Path::new("/data/cica");
+        for path in [
+            "/data/cica/config.toml",
+            "../../cica.db",
+            "/data/cica/cica.db-wal",
+            "/data/cica/internal/mcp.json",
+            "/data/cica/internal/signal-data/data/accounts.json",
+        ] {
+            assert!(resolve_path(root, root, path).is_err(), "{}", path);
+        }
+        assert!(resolve_path(root, root, "/data/cica/internal/attachments/a.jpg").is_ok());
+    }
+
+    #[test]
+    fn test_is_denied() {
+        let root = Path::new("/data/cica");
+        let denied = vec![
+            "users/*/memories/private.md".to_string(),
+            "/data/cica/skills/secret/".to_string(),
+            "**/*.key".to_string(),
+        ];
+        for path in [
+            "/data/cica/users/telegram_1/memories/private.md",
+            "/data/cica/skills/secret",
+            "/data/cica/skills/secret/SKILL.md",
+            "/data/cica/users/telegram_1/tls.key",
+        ] {
+            assert!(is_denied(Path::new(path), root, &denied), "{}", path);
+        }
+        for path in [
+            "/data/cica/users/telegram_1/memories/pets.md",
+            "/data/cica/users/telegram_1/nested/memories/private.md",
+            "/data/cica/skills/secretive/SKILL.md",
+        ] {
+            assert!(!is_denied(Path::new(path), root, &denied), "{}", path);
+        }
+        assert!(!is_denied(Path::new("/data/cica/a.key"), root, &[]));
+    }
+
+    #[test]
+    fn test_read_only_dir() {
+        let root = std::env::temp_dir().join(format!("cica-openai-skills-{}", std::process::id()));
+        let skills_dir = root.join("skills");
+        std::fs::create_dir_all(&skills_dir).unwrap();
+        std::fs::write(skills_dir.join("SKILL.md"), "---\nname: weather\n---").unwrap();
+        let limits = ToolLimits {
+            denied: &[],
+            read_only: false,
+            read_only_dirs: std::slice::from_ref(&skills_dir),
+        };
+        let call = |name: &str, path: &str| ToolCall {
+            id: "1".to_string(),
+            call_type: ToolCall::default_type(),
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: json!({ "path": path, "content": "changed" }).to_string(),
+            },
+        };
+
+        assert!(try_run_tool(&call("read_file", "skills/SKILL.md"), &root, &root, &limits).is_ok());
+        assert!(
+            try_run_tool(
+                &call("write_file", "skills/SKILL.md"),
+                &root,
+                &root,
+                &limits
+            )
+            .is_err()
+        );
+        assert!(try_run_tool(&call("write_file", "notes.md"), &root, &root, &limits).is_ok());
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_path_follows_symlinks() {
+        let root = std::env::temp_dir().join(format!("cica-openai-root-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("users")).unwrap();
+        std::os::unix::fs::symlink("/etc", root.join("users/etc")).unwrap();
+        std::os::unix::fs::symlink(root.join("config.toml"), root.join("users/settings")).unwrap();
+
+        assert!(resolve_path(&root, &root, "users/etc/passwd").is_err());
+        assert!(resolve_path(&root, &root, "users/settings").is_err());
+        assert!(resolve_path(&root, &root, "users/new/notes.md").is_ok());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,262 @@
+//! Backups of Cica's data as one .tar.gz archive, for moving to a new host.
+//!
+//! An archive holds config.toml, a snapshot of the database (pairing data,
+//! sessions, cron jobs, transcripts, facts) and the files in the data
+//! directory: users' identities and memories, shared memories, skills and
+//! PERSONA.md. Downloaded dependencies, channel state under internal/ and the
+//! memory index (rebuilt on startup) are left out.
+
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::config::{self, Config};
+use crate::storage;
+
+/// Describes the backup; its presence marks an archive as a Cica backup
+const MANIFEST_NAME: &str = "cica-backup.json";
+
+/// Data directory entries that are never backed up
+const EXCLUDED: &[&str] = &[
+    MANIFEST_NAME,
+    "internal",
+    "config.toml",
+    "cica.db",
+    "cica.db-wal",
+    "cica.db-shm",
+    "memory.db",
+    "memory.db-wal",
+    "memory.db-shm",
+];
+
+/// What a backup contains
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Cica version that made the backup
+    pub version: String,
+    pub created_at: u64, // Unix timestamp
+    /// Whether config.toml includes tokens and API keys
+    pub with_secrets: bool,
+}
+
+/// Write a backup of the data directory to `path`
+pub fn create(path: &Path, with_secrets: bool) -> Result<Manifest> {
+    let paths = config::paths()?;
+    let manifest = Manifest {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        with_secrets,
+    };
+
+    // Snapshot the database so writes from a running Cica can't tear it. It
+    // holds tokens and transcripts, so it's created private (VACUUM INTO
+    // accepts an empty file) next to the rest of Cica's data.
+    let snapshot = paths
+        .internal_dir
+        .join(format!("backup-{}.db", std::process::id()));
+    std::fs::create_dir_all(&paths.internal_dir)?;
+    storage::create_private(&snapshot)?;
+    storage::read(|conn| {
+        conn.execute("VACUUM INTO ?1", [snapshot.to_string_lossy()])?;
+        Ok(())
+    })?;
+
+    let config_toml = if Config::exists()? {
+        Some(Config::backup_toml(with_secrets)?)
+    } else {
+        None
+    };
+    let result = write_archive(
+        path,
+        &paths.base,
+        &manifest,
+        config_toml.as_deref(),
+        &snapshot,
+    );
+    let _ = std::fs::remove_file(&snapshot);
+    result?;
+
+    info!("Backed up {:?} to {:?}", paths.base, path);
+    Ok(manifest)
+}
+
+fn write_archive(
+    path: &Path,
+    base: &Path,
+    manifest: &Manifest,
+    config_toml: Option<&str>,
+    db_snapshot: &Path,
+) -> Result<()> {
+    let file = storage::create_private(path)?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    append_bytes(
+        &mut archive,
+        MANIFEST_NAME,
+        serde_json::to_string_pretty(manifest)?.as_bytes(),
+    )?;
+    if let Some(config_toml) = config_toml {
+        append_bytes(&mut archive, "config.toml", config_toml.as_bytes())?;
+    }
+    archive.append_path_with_name(db_snapshot, "cica.db")?;
+
+    for entry in std::fs::read_dir(base)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if EXCLUDED.contains(&name.as_ref()) || name.contains(".tmp-") {
+            continue;
+        }
+
+        if entry.file_type()?.is_dir() {
+            archive.append_dir_all(name.as_ref(), entry.path())?;
+        } else {
+            archive.append_path_with_name(entry.path(), name.as_ref())?;
+        }
+    }
+
+    archive.into_inner()?.finish()?;
+    Ok(())
+}
+
+fn append_bytes(
+    archive: &mut tar::Builder<impl std::io::Write>,
+    name: &str,
+    data: &[u8],
+) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o600);
+    header.set_cksum();
+    archive.append_data(&mut header, name, data)?;
+    Ok(())
+}
+
+/// Restore a backup into the data directory. Refuses to overwrite an existing
+/// setup unless `force` is set.
+pub fn restore(path: &Path, force: bool) -> Result<Manifest> {
+    let paths = config::paths()?;
+    let manifest = read_manifest(path)?;
+
+    if !force && (paths.config_file.exists() || paths.db_file.exists()) {
+        bail!(
+            "Cica is already set up in {:?}. Use --force to replace it with the backup.",
+            paths.base
+        );
+    }
+
+    // The old database's journal would be replayed over the restored one
+    for stale in [
+        "cica.db-wal",
+        "cica.db-shm",
+        "memory.db",
+        "memory.db-wal",
+        "memory.db-shm",
+    ] {
+        let _ = std::fs::remove_file(paths.base.join(stale));
+    }
+
+    unpack(path, &paths.base)?;
+
+    info!("Restored {:?} into {:?}", path, paths.base);
+    Ok(manifest)
+}
+
+fn unpack(path: &Path, base: &Path) -> Result<()> {
+    std::fs::create_dir_all(base)?;
+    let mut archive = open_archive(path)?;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.path()?.as_os_str() == MANIFEST_NAME {
+            continue;
+        }
+        // unpack_in refuses paths outside the data directory
+        entry.unpack_in(base)?;
+    }
+    Ok(())
+}
+
+/// Read the manifest of a backup, checking that it is one
+pub fn read_manifest(path: &Path) -> Result<Manifest> {
+    let mut archive = open_archive(path)?;
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if entry.path()?.as_os_str() == MANIFEST_NAME {
+            return serde_json::from_reader(entry).context("Invalid backup manifest");
+        }
+    }
+
+    bail!("{:?} is not a Cica backup", path)
+}
+
+fn open_archive(path: &Path) -> Result<tar::Archive<GzDecoder<File>>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    Ok(tar::Archive::new(GzDecoder::new(file)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_round_trip() {
+        let dir = std::env::temp_dir().join(format!("cica-backup-test-{}", std::process::id()));
+        let base = dir.join("base");
+        let memories = base.join("users").join("telegram_1").join("memories");
+        std::fs::create_dir_all(&memories).unwrap();
+        std::fs::create_dir_all(base.join("internal").join("deps")).unwrap();
+        std::fs::write(memories.join("pets.md"), "A cat called Miso").unwrap();
+        std::fs::write(base.join("internal").join("deps").join("bun"), "").unwrap();
+        std::fs::write(base.join("memory.db"), "index").unwrap();
+        let snapshot = dir.join("snapshot.db");
+        std::fs::write(&snapshot, "db").unwrap();
+
+        let manifest = Manifest {
+            version: "1.0.0".to_string(),
+            created_at: 0,
+            with_secrets: false,
+        };
+        let archive = dir.join("backup.tar.gz");
+        write_archive(
+            &archive,
+            &base,
+            &manifest,
+            Some("backend = \"claude\"\n"),
+            &snapshot,
+        )
+        .unwrap();
+        assert!(!read_manifest(&archive).unwrap().with_secrets);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&archive).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let restored = dir.join("restored");
+        unpack(&archive, &restored).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(restored.join("users/telegram_1/memories/pets.md")).unwrap(),
+            "A cat called Miso"
+        );
+        assert_eq!(
+            std::fs::read_to_string(restored.join("cica.db")).unwrap(),
+            "db"
+        );
+        assert!(restored.join("config.toml").exists());
+        assert!(!restored.join("internal").exists());
+        assert!(!restored.join("memory.db").exists());
+        assert!(!restored.join(MANIFEST_NAME).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
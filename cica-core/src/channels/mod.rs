@@ -0,0 +1,4845 @@
+#[cfg(feature = "signal")]
+pub mod signal;
+#[cfg(feature = "slack")]
+pub mod slack;
+pub mod telegram;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Local;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, oneshot};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::attachments;
+use crate::backends::{self, QueryOptions};
+use crate::config::{
+    AiBackend, ChannelSettings, Config, Effort, OwnerConfig, RolePermissions, WhileBusy,
+};
+use crate::context_sync::ContextUpdate;
+use crate::cron::{
+    self, CronSchedule, CronStore, format_timestamp, parse_add_command, truncate_for_name,
+};
+use crate::digest;
+use crate::export::{self, ExportKind};
+use crate::facts;
+use crate::feedback::{self, Rating};
+use crate::i18n;
+use crate::memory::{MemoryIndex, shared_memories_dir};
+use crate::metrics;
+use crate::notify;
+use crate::onboarding;
+use crate::pairing::{self, PairingStore, Role};
+use crate::quiet::{self, QuietHours};
+use crate::shutdown;
+use crate::skills;
+use crate::transcript::{self, Speaker, TranscriptMessage};
+
+// ============================================================================
+// Channel Abstraction
+// ============================================================================
+
+/// Abstraction over channel-specific transport operations.
+///
+/// Each channel (Telegram, Signal, etc.) implements this trait to provide
+/// a unified interface for sending messages and showing typing indicators.
+#[async_trait]
+pub trait Channel: Send + Sync + 'static {
+    /// Channel identifier (e.g., "telegram", "signal")
+    fn name(&self) -> &'static str;
+
+    /// Display name for user-facing messages (e.g., "Telegram", "Signal")
+    fn display_name(&self) -> &'static str;
+
+    /// Send a text message to the user
+    async fn send_message(&self, message: &str) -> Result<()>;
+
+    /// Send a message with attachments (images, files, etc.)
+    async fn send_message_with_attachments(
+        &self,
+        message: &str,
+        _attachment_paths: &[PathBuf],
+    ) -> Result<()> {
+        // Default implementation: just send the text message (ignore attachments)
+        // Channels that support attachments should override this
+        self.send_message(message).await
+    }
+
+    /// Send a file as a document. Channels that can't send files say where
+    /// it is instead.
+    async fn send_file(&self, path: &Path) -> Result<()> {
+        self.send_message(&format!("File: {}", path.display()))
+            .await
+    }
+
+    /// Start a typing indicator. Returns a guard that stops the indicator when dropped.
+    fn start_typing(&self) -> TypingGuard;
+}
+
+/// RAII guard for typing indicators.
+///
+/// The typing indicator runs until this guard is dropped.
+pub struct TypingGuard {
+    cancel: Option<oneshot::Sender<()>>,
+}
+
+impl TypingGuard {
+    /// Create a new typing guard with a cancel channel
+    pub fn new(cancel: oneshot::Sender<()>) -> Self {
+        Self {
+            cancel: Some(cancel),
+        }
+    }
+
+    /// Create a no-op guard (for testing or when typing indicators aren't supported)
+    #[allow(dead_code)]
+    pub fn noop() -> Self {
+        Self { cancel: None }
+    }
+}
+
+impl Drop for TypingGuard {
+    fn drop(&mut self) {
+        if let Some(cancel) = self.cancel.take() {
+            let _ = cancel.send(());
+        }
+    }
+}
+
+// ============================================================================
+// Message Actions
+// ============================================================================
+
+/// Actions that can result from processing an incoming message.
+///
+/// This enum represents "what to do" without "how to do it", enabling
+/// pure logic in `determine_action()` that's easy to test.
+pub enum MessageAction {
+    /// Send a simple response (command output, error message, etc.)
+    SendResponse(String),
+
+    /// Execute a cron job immediately
+    ExecuteCronJob { job_id: String },
+
+    /// Summarize recent conversations (/recap)
+    Recap { period: RecapPeriod },
+
+    /// Send the daily digest now (/digest now)
+    DailyDigest,
+
+    /// Send the conversation or memories as a file (/export)
+    Export { kind: ExportKind },
+
+    /// Run skill healthchecks and list the skills (/skills)
+    SkillStatus { is_owner: bool },
+
+    /// Stop the reply being worked on (/cancel)
+    Cancel,
+
+    /// Run onboarding flow with Claude
+    Onboarding { message: String },
+
+    /// Query Claude with the user's message
+    QueryClaude { text: String },
+
+    /// User not approved - send pairing instructions.
+    /// `new_requester` is set when the request was just created, so the owner can be told.
+    /// `previous_expired` is set when the user's earlier code expired.
+    /// `language` is the language their message seems to be in.
+    NeedsPairing {
+        code: String,
+        new_requester: Option<String>,
+        previous_expired: bool,
+        language: Option<&'static str>,
+    },
+
+    /// No action needed (empty message, /start after onboarding, etc.)
+    Ignore,
+}
+
+/// Structured content attached to an incoming message
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageAttachment {
+    /// A downloaded image file
+    Image(PathBuf),
+    /// A shared location (optionally a named venue)
+    Location {
+        latitude: f64,
+        longitude: f64,
+        /// Venue name or other label from the sender
+        label: Option<String>,
+        /// Street address, from the venue or reverse geocoding
+        address: Option<String>,
+    },
+    /// A shared contact card
+    Contact {
+        name: String,
+        phone_numbers: Vec<String>,
+        emails: Vec<String>,
+        /// Raw vCard, when the channel provides one
+        vcard: Option<String>,
+    },
+    /// The message was forwarded; describes the original sender
+    ForwardedFrom(String),
+}
+
+impl MessageAttachment {
+    /// Wrap a list of image paths as attachments
+    #[cfg_attr(not(feature = "slack"), allow(dead_code))]
+    pub fn images(paths: Vec<PathBuf>) -> Vec<Self> {
+        paths.into_iter().map(Self::Image).collect()
+    }
+}
+
+/// Fill in street addresses for shared locations if reverse geocoding is enabled.
+/// Only called for approved senders, so strangers can't make us send lookups.
+pub async fn geocode_locations(attachments: &[MessageAttachment]) -> Vec<MessageAttachment> {
+    let mut attachments = attachments.to_vec();
+
+    let reverse_geocode = crate::config::Config::load()
+        .map(|c| c.reverse_geocode)
+        .unwrap_or(false);
+    if !reverse_geocode {
+        return attachments;
+    }
+
+    for attachment in &mut attachments {
+        if let MessageAttachment::Location {
+            latitude,
+            longitude,
+            address: address @ None,
+            ..
+        } = attachment
+        {
+            *address = lookup_address(*latitude, *longitude).await;
+        }
+    }
+
+    attachments
+}
+
+/// Reverse geocode coordinates to a street address using OpenStreetMap Nominatim
+async fn lookup_address(latitude: f64, longitude: f64) -> Option<String> {
+    #[derive(serde::Deserialize)]
+    struct NominatimResponse {
+        display_name: Option<String>,
+    }
+
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("cica/", env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(5))
+        .build()
+        .ok()?;
+
+    let response = client
+        .get("https://nominatim.openstreetmap.org/reverse")
+        .query(&[
+            ("format", "jsonv2".to_string()),
+            ("lat", latitude.to_string()),
+            ("lon", longitude.to_string()),
+        ])
+        .send()
+        .await;
+
+    match response {
+        Ok(r) => r.json::<NominatimResponse>().await.ok()?.display_name,
+        Err(e) => {
+            warn!("Reverse geocoding failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Message content Cica can't process yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnsupportedContent {
+    Sticker,
+    Poll,
+    VideoNote,
+    Voice,
+    Audio,
+    Video,
+    File,
+}
+
+impl UnsupportedContent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnsupportedContent::Sticker => "sticker",
+            UnsupportedContent::Poll => "poll",
+            UnsupportedContent::VideoNote => "video_note",
+            UnsupportedContent::Voice => "voice",
+            UnsupportedContent::Audio => "audio",
+            UnsupportedContent::Video => "video",
+            UnsupportedContent::File => "file",
+        }
+    }
+
+    /// What to tell the user instead
+    fn reply(&self) -> &'static str {
+        match self {
+            UnsupportedContent::Sticker => {
+                "I can't see stickers yet. Tell me in words what you meant, or send a photo."
+            }
+            UnsupportedContent::Poll => {
+                "I can't read polls yet. Send me the question and options as text."
+            }
+            UnsupportedContent::VideoNote | UnsupportedContent::Video => {
+                "I can't watch videos yet. Describe what's in it, or send a screenshot as a photo."
+            }
+            UnsupportedContent::Voice | UnsupportedContent::Audio => {
+                "I can't listen to audio yet. Please send your message as text."
+            }
+            UnsupportedContent::File => {
+                "I can't open files yet. Paste the relevant part as text, or send it as a photo."
+            }
+        }
+    }
+}
+
+/// Count content Cica couldn't process, and tell approved users what to send
+/// instead. Anyone else gets no reply, as if the message had been dropped.
+pub async fn reply_unsupported(
+    channel: &dyn Channel,
+    user_id: &str,
+    content: UnsupportedContent,
+) -> Result<()> {
+    info!(
+        "Unsupported {} from {} on {}",
+        content.as_str(),
+        user_id,
+        channel.name()
+    );
+    metrics::increment(&format!(
+        "unsupported.{}.{}",
+        channel.name(),
+        content.as_str()
+    ));
+
+    if PairingStore::load()?.is_approved(channel.name(), user_id) {
+        channel.send_message(content.reply()).await?;
+    }
+    Ok(())
+}
+
+/// Run the sender's cron jobs that are triggered by this message (see
+/// `CronSchedule::OnMessage`). They're picked up on the scheduler's next
+/// tick; the message itself is handled as usual.
+pub fn fire_message_triggers(channel: &str, user_id: &str, text: &str, store: &PairingStore) {
+    let text = text.trim();
+    if text.is_empty() || text.starts_with('/') || !store.is_approved(channel, user_id) {
+        return;
+    }
+
+    // Most messages trigger nothing, so only write when a job matches
+    let triggered = CronStore::load().and_then(|mut cron_store| {
+        if !cron_store.has_message_trigger(channel, user_id, text) {
+            return Ok(Vec::new());
+        }
+        cron_store
+            .update(|s| Ok(s.trigger_on_message(channel, user_id, text, cron::store::now_millis())))
+    });
+    match triggered {
+        Ok(names) if !names.is_empty() => {
+            info!(
+                "Message from {}:{} triggered: {}",
+                channel,
+                user_id,
+                names.join(", ")
+            );
+        }
+        Ok(_) => {}
+        Err(e) => warn!("Failed to check message triggers: {}", e),
+    }
+}
+
+/// Determine what action to take for an incoming message.
+///
+/// This is a pure function with no side effects - it only reads state and
+/// returns what should happen. This makes it easy to test.
+pub fn determine_action(
+    channel: &str,
+    user_id: &str,
+    text: &str,
+    attachments: &[MessageAttachment],
+    store: &mut PairingStore,
+    username: Option<String>,
+    display_name: Option<String>,
+) -> Result<MessageAction> {
+    let text = text.trim();
+
+    // Blocked users get no reply at all, not even a pairing code
+    if store.is_blocked(channel, user_id) {
+        return Ok(MessageAction::Ignore);
+    }
+
+    // Check if user is approved
+    if !store.is_approved(channel, user_id) {
+        let config = Config::load().unwrap_or_default();
+        let settings = config.channel_settings(channel);
+
+        if claims_first_owner(&config, store) {
+            store.auto_approve(channel, user_id, username, display_name)?;
+            make_owner(config, channel, user_id)?;
+        } else if settings.auto_approve {
+            store.auto_approve(channel, user_id, username, display_name)?;
+        } else if store.is_denied(channel, user_id) {
+            // Denied senders get no new code until their wait is over
+            return Ok(MessageAction::Ignore);
+        } else {
+            let requester = display_name
+                .clone()
+                .or_else(|| username.clone())
+                .unwrap_or_else(|| user_id.to_string());
+            let (code, is_new) = store.get_or_create_pending(
+                channel,
+                user_id,
+                username,
+                display_name,
+                config.pairing.max_prompts,
+            )?;
+
+            // Stop repeating the code to senders who keep messaging
+            if !store.record_pairing_prompt(channel, user_id, config.pairing.max_prompts)? {
+                return Ok(MessageAction::Ignore);
+            }
+
+            // Tell returning users why they're getting a different code
+            let previous_expired = is_new && store.take_expired(channel, user_id)?.is_some();
+
+            return Ok(MessageAction::NeedsPairing {
+                code,
+                new_requester: is_new.then_some(requester),
+                previous_expired,
+                language: i18n::detect(text),
+            });
+        }
+    }
+
+    // Check if onboarding is complete
+    let onboarding_complete = onboarding::is_complete_for_user(channel, user_id)?;
+
+    // Process commands (work even during onboarding)
+    match process_command(store, channel, user_id, text, onboarding_complete)? {
+        CommandResult::Response(response) => {
+            return Ok(MessageAction::SendResponse(response));
+        }
+        CommandResult::CronRun(job_id) => {
+            return Ok(MessageAction::ExecuteCronJob { job_id });
+        }
+        CommandResult::Recap(period) => {
+            return Ok(MessageAction::Recap { period });
+        }
+        CommandResult::DailyDigest => {
+            return Ok(MessageAction::DailyDigest);
+        }
+        CommandResult::Export(kind) => {
+            return Ok(MessageAction::Export { kind });
+        }
+        CommandResult::SkillStatus { is_owner } => {
+            return Ok(MessageAction::SkillStatus { is_owner });
+        }
+        CommandResult::Retry(text) => {
+            return Ok(MessageAction::QueryClaude { text });
+        }
+        CommandResult::Cancel => {
+            return Ok(MessageAction::Cancel);
+        }
+        CommandResult::NotACommand => {}
+    }
+
+    // Answer in the language of the first messages, until the user picks one
+    if store.language(channel, user_id).is_none()
+        && let Some(language) = i18n::detect(text)
+    {
+        info!("Detected {} for {}:{}", language, channel, user_id);
+        store.set_language(channel, user_id, Some(language))?;
+    }
+
+    // Handle onboarding if not complete
+    if !onboarding_complete {
+        // Treat /start as "hi" for onboarding
+        let message = if text == "/start" { "hi" } else { text };
+        return Ok(MessageAction::Onboarding {
+            message: build_text_with_attachments(message, attachments),
+        });
+    }
+
+    // Ignore /start after onboarding
+    if text == "/start" {
+        return Ok(MessageAction::Ignore);
+    }
+
+    // Empty message with no attachments - ignore
+    if text.is_empty() && attachments.is_empty() {
+        return Ok(MessageAction::Ignore);
+    }
+
+    // Normal message - query Claude
+    Ok(MessageAction::QueryClaude {
+        text: text.to_string(),
+    })
+}
+
+/// Whether the next unknown sender should become the owner: the option is on
+/// and nobody has been approved or made owner yet.
+fn claims_first_owner(config: &Config, store: &PairingStore) -> bool {
+    config.pairing.auto_approve_first_user
+        && config.owner.is_none()
+        && store.approved.values().all(|ids| ids.is_empty())
+}
+
+/// Record a user as the owner and start sending them notifications
+fn make_owner(mut config: Config, channel: &str, user_id: &str) -> Result<()> {
+    let owner = OwnerConfig {
+        channel: channel.to_string(),
+        user_id: user_id.to_string(),
+    };
+    config.owner = Some(owner.clone());
+    config.save()?;
+
+    info!("First user {}:{} approved as owner", channel, user_id);
+    notify::set_owner(owner);
+    notify::send_to_user(
+        channel,
+        user_id,
+        "You're the first person to message me, so you've been approved as my owner. \
+         You'll get pairing requests and system alerts here.",
+    );
+
+    Ok(())
+}
+
+/// Build a message combining text and attachments.
+///
+/// Locations, contacts and forwarding info are described as text so Claude
+/// can reason about them, and images are referenced using @path syntax which
+/// Claude Code understands.
+pub fn build_text_with_attachments(text: &str, attachments: &[MessageAttachment]) -> String {
+    let mut headers = Vec::new();
+    let mut context = Vec::new();
+    let mut images = Vec::new();
+
+    for attachment in attachments {
+        match attachment {
+            MessageAttachment::Image(path) => {
+                images.push(attachments::prompt_reference(path));
+            }
+            MessageAttachment::Location {
+                latitude,
+                longitude,
+                label,
+                address,
+            } => {
+                let mut line = format!("[Shared location: {:.6}, {:.6}", latitude, longitude);
+                if let Some(label) = label {
+                    line.push_str(&format!(" - {}", label));
+                }
+                if let Some(address) = address {
+                    line.push_str(&format!(" ({})", address));
+                }
+                line.push(']');
+                context.push(line);
+            }
+            MessageAttachment::Contact {
+                name,
+                phone_numbers,
+                emails,
+                vcard,
+            } => {
+                let details: Vec<&str> = std::iter::once(name.as_str())
+                    .chain(phone_numbers.iter().map(String::as_str))
+                    .chain(emails.iter().map(String::as_str))
+                    .collect();
+                let mut block = format!("[Shared contact: {}]", details.join(", "));
+                if let Some(vcard) = vcard {
+                    block.push_str(&format!("\n[vCard]\n{}", vcard.trim()));
+                }
+                context.push(block);
+            }
+            MessageAttachment::ForwardedFrom(origin) => {
+                headers.push(format!("[Forwarded from {}]", origin));
+            }
+        }
+    }
+
+    // Forwarded headers sit directly above the forwarded text
+    let body = if headers.is_empty() {
+        text.to_string()
+    } else if text.is_empty() {
+        headers.join("\n")
+    } else {
+        format!("{}\n{}", headers.join("\n"), text)
+    };
+
+    [body, context.join("\n"), images.join(" ")]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Execute an action that doesn't require the task manager.
+///
+/// Returns `Some(text)` if the action is QueryClaude (needs task_manager handling),
+/// otherwise executes the action and returns `None`.
+pub async fn execute_action(
+    channel: &dyn Channel,
+    user_id: &str,
+    action: MessageAction,
+) -> Result<Option<String>> {
+    if !matches!(action, MessageAction::Ignore) {
+        metrics::increment(&format!("messages.{}", channel.name()));
+
+        if shutdown::is_shutting_down() {
+            let language = i18n::user_language(channel.name(), user_id);
+            channel
+                .send_message(i18n::tr(
+                    language.as_deref(),
+                    "I'm restarting right now and can't take this. \
+                     Please send it again in a minute.",
+                ))
+                .await?;
+            return Ok(None);
+        }
+    }
+
+    match action {
+        MessageAction::SendResponse(response) => {
+            channel.send_message(&response).await?;
+            Ok(None)
+        }
+
+        MessageAction::NeedsPairing {
+            code,
+            new_requester,
+            previous_expired,
+            language,
+        } => {
+            if let Some(requester) = new_requester {
+                let channel_display = get_channel_info(channel.name())
+                    .map(|c| c.display_name)
+                    .unwrap_or(channel.name());
+                notify::notify_owner_with_actions(
+                    format!(
+                        "New {} pairing request from {}.\n\n\
+                         Reply /approve {} or /deny {}",
+                        channel_display, requester, code, code
+                    ),
+                    vec![
+                        ReplyAction::new("Approve", format!("/approve {}", code)),
+                        ReplyAction::new("Deny", format!("/deny {}", code)),
+                    ],
+                );
+            }
+
+            let greeting = if previous_expired {
+                "Your previous pairing code expired, so here's a new one."
+            } else {
+                "Hi! I don't recognize you yet."
+            };
+            let response = format!(
+                "{}\n\n{}",
+                i18n::tr(language, greeting),
+                i18n::tr_args(
+                    language,
+                    "Pairing code: {}\n\
+                     (valid for {} minutes)\n\n\
+                     Ask the owner to run:\n\
+                     cica approve {}",
+                    &[&code, &(pairing::code_ttl().as_secs() / 60), &code],
+                )
+            );
+            channel.send_message(&response).await?;
+            Ok(None)
+        }
+
+        MessageAction::ExecuteCronJob { job_id } => {
+            let language = i18n::user_language(channel.name(), user_id);
+            let language = language.as_deref();
+            channel
+                .send_message(i18n::tr(language, "Running job..."))
+                .await?;
+            let _typing = channel.start_typing();
+            // The result comes like a scheduled run's
+            if let Err(e) = cron::run_now(&job_id, channel.name(), user_id).await {
+                let response = i18n::tr_args(language, "Job failed: {}", &[&e]);
+                channel.send_message(&response).await?;
+            }
+            Ok(None)
+        }
+
+        MessageAction::Recap { period } => {
+            let _in_flight = shutdown::track(channel.name(), user_id, "your recap");
+            let _typing = channel.start_typing();
+            let response = recap(channel.name(), user_id, period)
+                .await
+                .unwrap_or_else(|e| format!("Couldn't build the recap: {}", e));
+            channel.send_message(&response).await?;
+            Ok(None)
+        }
+
+        MessageAction::DailyDigest => {
+            let _in_flight = shutdown::track(channel.name(), user_id, "your digest");
+            let _typing = channel.start_typing();
+            channel
+                .send_message(&daily_digest_now(channel.name(), user_id).await)
+                .await?;
+            Ok(None)
+        }
+
+        MessageAction::Export { kind } => {
+            let _in_flight = shutdown::track(channel.name(), user_id, "your export");
+            let _typing = channel.start_typing();
+            match build_export(channel.name(), user_id, kind).await {
+                Ok(path) => {
+                    let sent = channel.send_file(&path).await;
+                    let _ = std::fs::remove_file(&path);
+                    sent?;
+                }
+                Err(e) => channel.send_message(&format!("{:#}", e)).await?,
+            }
+            Ok(None)
+        }
+
+        MessageAction::SkillStatus { is_owner } => {
+            let _typing = channel.start_typing();
+            channel.send_message(&skill_status(is_owner).await).await?;
+            Ok(None)
+        }
+
+        MessageAction::Cancel => {
+            channel
+                .send_message(&cancel_response(channel.name(), user_id).await)
+                .await?;
+            Ok(None)
+        }
+
+        MessageAction::Onboarding { message } => {
+            let _in_flight = shutdown::track(channel.name(), user_id, "your message");
+            let _typing = channel.start_typing();
+            transcript::record(channel.name(), user_id, Speaker::User, &message);
+            let response = handle_onboarding(channel.name(), user_id, &message).await?;
+            transcript::record(channel.name(), user_id, Speaker::Assistant, &response);
+            channel.send_message(&response).await?;
+            Ok(None)
+        }
+
+        MessageAction::QueryClaude { text } => {
+            // Return the text so caller can handle with task_manager
+            Ok(Some(text))
+        }
+
+        MessageAction::Ignore => Ok(None),
+    }
+}
+
+/// Extract media file paths from Claude's response text.
+///
+/// Looks for file paths in the response that point to image or video files.
+fn extract_media_attachments(response: &str) -> Vec<PathBuf> {
+    let mut attachments = Vec::new();
+
+    // Look for file paths that end in media extensions
+    let media_extensions = [
+        // Images
+        ".png", ".jpg", ".jpeg", ".gif", ".webp", // Videos
+        ".mp4", ".mov", ".webm", ".avi",
+    ];
+
+    for line in response.lines() {
+        let line = line.trim();
+
+        // Check if line contains a file path
+        for ext in &media_extensions {
+            if line.contains(ext) {
+                // Try to extract the path - look for paths starting with /Users/
+                if let Some(start) = line.find("/Users/") {
+                    // Find the end of the extension (not whitespace, since paths can have spaces)
+                    if let Some(ext_pos) = line[start..].find(ext) {
+                        let end_pos = start + ext_pos + ext.len();
+                        let path_str = &line[start..end_pos];
+                        if std::path::Path::new(path_str).exists() {
+                            attachments.push(PathBuf::from(path_str));
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    attachments
+}
+
+/// Remove lines from the response that contain file paths.
+///
+/// This cleans up responses to avoid showing technical file paths to the user
+/// when media files are being sent as attachments.
+fn remove_file_path_lines(response: &str) -> String {
+    let lines: Vec<&str> = response
+        .lines()
+        .filter(|line| {
+            let trimmed = line.trim();
+            let lower = trimmed.to_lowercase();
+            // Skip lines that contain file paths or mention saving files
+            !trimmed.contains("/Users/")
+                && !lower.contains("saved to")
+                && !lower.contains("image has been saved")
+                && !lower.contains("video has been saved")
+                && !lower.contains("file has been saved")
+                && !trimmed.is_empty()
+        })
+        .collect();
+
+    lines.join("\n").trim().to_string()
+}
+
+/// The context prompt sent along with `message`: the user's context, plus a
+/// reminder of their limits if their role is restricted
+pub fn context_prompt_for_user(
+    store: &PairingStore,
+    channel: &str,
+    user_id: &str,
+    message: &str,
+) -> Result<String> {
+    let channel_display = get_channel_info(channel).map(|c| c.display_name);
+    let context_prompt = onboarding::build_context_prompt_for_user(
+        channel_display,
+        Some(channel),
+        Some(user_id),
+        Some(message).filter(|m| !m.is_empty()),
+    )?;
+
+    let (role, permissions) = user_permissions(store, channel, user_id);
+    Ok(match permissions_prompt(role, permissions) {
+        Some(note) => format!("{}\n\n{}", context_prompt, note),
+        None => context_prompt,
+    })
+}
+
+/// Execute a Claude query for the user.
+///
+/// This is called from within the task_manager callback after messages
+/// have been debounced and batched.
+pub async fn execute_claude_query(channel: Arc<dyn Channel>, user_id: &str, messages: Vec<String>) {
+    let combined_text = messages.join("\n\n");
+    let _in_flight = shutdown::track(channel.name(), user_id, "your message");
+    let _typing = channel.start_typing();
+
+    // Load pairing store for session management
+    let mut store = match PairingStore::load() {
+        Ok(s) => s,
+        Err(e) => {
+            warn!("Failed to load pairing store: {}", e);
+            let _ = channel
+                .send_message(&format!("Sorry, I encountered an error: {}", e))
+                .await;
+            return;
+        }
+    };
+
+    // "/think" asks for more thinking on this message only
+    let (effort, text) = parse_think_prefix(&combined_text);
+
+    // Restricted roles get a read-only workspace and a reminder of their limits
+    let (_, permissions) = user_permissions(&store, channel.name(), user_id);
+    let context_prompt = match context_prompt_for_user(&store, channel.name(), user_id, text) {
+        Ok(p) => p,
+        Err(e) => {
+            warn!("Failed to build context prompt: {}", e);
+            let _ = channel
+                .send_message(&format!("Sorry, I encountered an error: {}", e))
+                .await;
+            return;
+        }
+    };
+
+    transcript::record(channel.name(), user_id, Speaker::User, &combined_text);
+
+    // Collect the tools used for the footer of users who asked for it
+    let verbose = store
+        .get_user_profile(channel.name(), user_id)
+        .is_some_and(|p| p.verbose);
+    let tools_used = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let on_tool_use = verbose.then(|| {
+        let tools_used = tools_used.clone();
+        Arc::new(move |name: &str| {
+            if let Ok(mut tools_used) = tools_used.lock() {
+                tools_used.push(name.to_string());
+            }
+        }) as backends::OnToolUse
+    });
+
+    // Query AI backend with session
+    let (response, _session_id) = match query_ai_with_session(
+        &mut store,
+        channel.name(),
+        user_id,
+        text,
+        context_prompt,
+        permissions,
+        Some(queued_notice(channel.clone())),
+        on_tool_use,
+        effort,
+    )
+    .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            warn!("AI query failed: {}", e);
+            let _ = channel
+                .send_message(&format!("Sorry, I encountered an error: {}", e))
+                .await;
+            return;
+        }
+    };
+
+    // Save any facts Claude learned, without showing the tags to the user
+    let response = facts::apply_from_reply(channel.name(), user_id, &response);
+
+    transcript::record(channel.name(), user_id, Speaker::Assistant, &response);
+
+    // Extract any media attachments (images, videos) from the response
+    let attachments = extract_media_attachments(&response);
+
+    let footer = tools_used.lock().ok().and_then(|tools| tool_footer(&tools));
+    let response = match footer {
+        Some(footer) => format!("{}\n\n{}", response, footer),
+        None => response,
+    };
+
+    // Send response with attachments if any
+    if !attachments.is_empty() {
+        debug!("Sending response with {} attachment(s)", attachments.len());
+
+        // Clean up the response text - remove lines that mention the file paths
+        let cleaned_response = remove_file_path_lines(&response);
+
+        if let Err(e) = channel
+            .send_message_with_attachments(&cleaned_response, &attachments)
+            .await
+        {
+            warn!("Failed to send message with attachments: {}", e);
+        }
+    } else {
+        // Send regular text message
+        if let Err(e) = channel.send_message(&response).await {
+            warn!("Failed to send message: {}", e);
+        }
+    }
+
+    // Re-index memories in case Claude saved new ones
+    reindex_user_memories(channel.name(), user_id);
+}
+
+// ============================================================================
+// Send Queue
+// ============================================================================
+
+/// Maximum failed attempts (excluding rate limits) before a message is dropped
+const SEND_MAX_ATTEMPTS: u32 = 5;
+
+/// Maximum total time to wait out rate limits for a single message
+const SEND_MAX_THROTTLE: Duration = Duration::from_secs(600);
+
+/// Initial delay for exponential backoff on transient send failures
+const SEND_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Upper bound for exponential backoff between send attempts
+const SEND_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Outgoing rate limits for a channel
+#[derive(Debug, Clone, Copy)]
+pub struct SendLimits {
+    /// Minimum gap between any two sends on the channel
+    pub global_interval: Duration,
+    /// Minimum gap between two sends to the same chat
+    pub per_chat_interval: Duration,
+}
+
+/// How a failed send should be handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendRetry {
+    /// Rate limited - the server told us how long to wait (e.g. HTTP 429)
+    After(Duration),
+    /// Transient failure (5xx, network) - retry with exponential backoff
+    Backoff,
+    /// Permanent failure - retrying won't help
+    Never,
+}
+
+/// Split a long message into chunks of at most `max_chars` characters,
+/// preferring paragraph, then line, then word boundaries.
+pub fn split_message(text: &str, max_chars: usize) -> Vec<String> {
+    split_message_by(text, max_chars, |_| 1)
+}
+
+/// Like [`split_message`], counting UTF-16 code units the way Telegram does,
+/// so emoji and other astral characters count twice.
+pub fn split_message_utf16(text: &str, max_units: usize) -> Vec<String> {
+    split_message_by(text, max_units, char::len_utf16)
+}
+
+/// Split `text` into chunks whose `width`s add up to at most `max`
+fn split_message_by(text: &str, max: usize, width: fn(char) -> usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+
+    // Byte index of the first character that no longer fits, if any
+    let overflow = |rest: &str| {
+        let mut used = 0;
+        rest.char_indices().find_map(|(i, c)| {
+            used += width(c);
+            (used > max).then_some(i)
+        })
+    };
+
+    while let Some(limit) = overflow(rest) {
+        // Always make progress, even if a single character is too wide
+        let limit = if limit == 0 {
+            rest.chars().next().map_or(rest.len(), char::len_utf8)
+        } else {
+            limit
+        };
+        let window = &rest[..limit];
+
+        let split_at = window
+            .rfind("\n\n")
+            .or_else(|| window.rfind('\n'))
+            .or_else(|| window.rfind(' '))
+            .filter(|&i| i > 0)
+            .unwrap_or(limit);
+
+        chunks.push(rest[..split_at].trim_end().to_string());
+        rest = rest[split_at..].trim_start();
+    }
+
+    if !rest.is_empty() || chunks.is_empty() {
+        chunks.push(rest.to_string());
+    }
+
+    chunks
+}
+
+/// Reserved send slots, tracked globally and per chat
+struct SendSlots {
+    next_global: Instant,
+    next_per_chat: HashMap<String, Instant>,
+}
+
+impl SendSlots {
+    /// Reserve the earliest slot for `chat` at or after `now`, returning when it starts
+    fn reserve(&mut self, chat: &str, now: Instant, limits: &SendLimits) -> Instant {
+        // Forget chats whose slots have already passed
+        self.next_per_chat.retain(|_, next| *next > now);
+
+        let chat_next = self.next_per_chat.get(chat).copied().unwrap_or(now);
+        let slot = now.max(self.next_global).max(chat_next);
+
+        self.next_global = slot + limits.global_interval;
+        self.next_per_chat
+            .insert(chat.to_string(), slot + limits.per_chat_interval);
+
+        slot
+    }
+}
+
+/// Shared outgoing message queue for a channel.
+///
+/// All sends go through here so that per-channel rate limits are respected
+/// across users, transient failures are retried with exponential backoff,
+/// and messages that can't be delivered are logged instead of vanishing.
+pub struct SendQueue {
+    channel: &'static str,
+    limits: SendLimits,
+    classify: fn(&anyhow::Error) -> SendRetry,
+    slots: Mutex<SendSlots>,
+}
+
+impl SendQueue {
+    pub fn new(
+        channel: &'static str,
+        limits: SendLimits,
+        classify: fn(&anyhow::Error) -> SendRetry,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            channel,
+            limits,
+            classify,
+            slots: Mutex::new(SendSlots {
+                next_global: Instant::now(),
+                next_per_chat: HashMap::new(),
+            }),
+        })
+    }
+
+    /// Run `op` to deliver a message to `chat`, waiting for a free slot and
+    /// retrying on rate limits or transient errors.
+    pub async fn send<F, Fut>(&self, chat: &str, mut op: F) -> Result<()>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<()>>,
+    {
+        let mut backoff = SEND_INITIAL_BACKOFF;
+        let mut attempts = 0;
+        let mut failures = 0;
+        let mut throttled = Duration::ZERO;
+
+        loop {
+            attempts += 1;
+            self.wait_for_slot(chat).await;
+
+            let err = match op().await {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+
+            let delay = match (self.classify)(&err) {
+                SendRetry::Never => None,
+                // Throttling isn't a failure - wait as long as the platform asks, within reason
+                SendRetry::After(wait) if throttled + wait <= SEND_MAX_THROTTLE => {
+                    throttled += wait;
+                    // Rate limits apply to the whole bot, so hold back every send
+                    self.delay_all(wait).await;
+                    Some(wait)
+                }
+                SendRetry::After(_) => None,
+                SendRetry::Backoff => {
+                    failures += 1;
+                    (failures < SEND_MAX_ATTEMPTS).then(|| {
+                        let wait = backoff;
+                        backoff = (backoff * 2).min(SEND_MAX_BACKOFF);
+                        wait
+                    })
+                }
+            };
+
+            match delay {
+                Some(wait) => {
+                    warn!(
+                        "{} send to {} failed (attempt {}), retrying in {:?}: {}",
+                        self.channel, chat, attempts, wait, err
+                    );
+                    tokio::time::sleep(wait).await;
+                }
+                None => {
+                    warn!(
+                        "Dropped {} message to {} after {} attempt(s): {:#}",
+                        self.channel, chat, attempts, err
+                    );
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    /// Sleep until the next send slot for `chat`
+    async fn wait_for_slot(&self, chat: &str) {
+        let slot = {
+            let mut slots = self.slots.lock().await;
+            slots.reserve(chat, Instant::now(), &self.limits)
+        };
+        tokio::time::sleep_until(slot.into()).await;
+    }
+
+    /// Push back the next global slot by `wait`
+    async fn delay_all(&self, wait: Duration) {
+        let mut slots = self.slots.lock().await;
+        slots.next_global = slots.next_global.max(Instant::now() + wait);
+    }
+}
+
+// ============================================================================
+// Task Manager
+// ============================================================================
+
+/// How long a typing indicator counts without being refreshed; Signal
+/// clients resend it every few seconds while the user types
+const TYPING_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Longest a batch waits for a user who keeps typing
+const MAX_TYPING_WAIT: Duration = Duration::from_secs(60);
+
+/// Runs a batch of a user's messages through the AI
+type MessageHandler =
+    Box<dyn FnOnce(Vec<String>) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>;
+
+/// Active task for a user
+struct ActiveTask {
+    handle: JoinHandle<()>,
+}
+
+/// A message waiting for a user's next turn
+struct PendingMessage {
+    text: String,
+    handler: MessageHandler,
+}
+
+static TASK_MANAGER: std::sync::OnceLock<Arc<UserTaskManager>> = std::sync::OnceLock::new();
+
+/// Manages per-user message processing with debouncing and follow-up turns
+pub struct UserTaskManager {
+    tasks: Mutex<HashMap<String, ActiveTask>>,
+    pending: Mutex<HashMap<String, Vec<PendingMessage>>>,
+    /// When each user was last seen typing, for channels that report it
+    typing: Mutex<HashMap<String, Instant>>,
+}
+
+impl UserTaskManager {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            tasks: Mutex::new(HashMap::new()),
+            pending: Mutex::new(HashMap::new()),
+            typing: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The task manager every channel shares, so /cancel can reach any
+    /// user's running task
+    pub fn global() -> Arc<Self> {
+        TASK_MANAGER.get_or_init(Self::new).clone()
+    }
+
+    /// Note that a user started or stopped typing, so a batch waits for
+    /// the message they're writing
+    pub async fn set_typing(&self, user_key: &str, typing: bool) {
+        let mut users = self.typing.lock().await;
+        if typing {
+            users.insert(user_key.to_string(), Instant::now());
+        } else {
+            users.remove(user_key);
+        }
+    }
+
+    async fn is_typing(&self, user_key: &str) -> bool {
+        self.typing
+            .lock()
+            .await
+            .get(user_key)
+            .is_some_and(|since| since.elapsed() < TYPING_TIMEOUT)
+    }
+
+    async fn pending_count(&self, user_key: &str) -> usize {
+        self.pending.lock().await.get(user_key).map_or(0, Vec::len)
+    }
+
+    /// Process a message for a user.
+    /// Messages are debounced - if more arrive within `debounce_ms` under
+    /// [queries], or while the user is still typing, they're batched, up to
+    /// `max_batch` at a time. Messages that arrive while a reply is being
+    /// worked on wait for a follow-up turn, or abort it if
+    /// `while_busy = "interrupt"`.
+    pub async fn process_message<F, Fut>(
+        self: &Arc<Self>,
+        user_key: String,
+        message: String,
+        handler: F,
+    ) where
+        F: FnOnce(Vec<String>) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = ()> + Send + 'static,
+    {
+        debug!("Queueing message for {}: {}", user_key, message);
+
+        let queries = Config::load().map(|c| c.queries).unwrap_or_default();
+        let handler: MessageHandler = Box::new(move |messages| Box::pin(handler(messages)));
+        self.pending
+            .lock()
+            .await
+            .entry(user_key.clone())
+            .or_default()
+            .push(PendingMessage {
+                text: message,
+                handler,
+            });
+
+        let mut tasks = self.tasks.lock().await;
+
+        if let Some(existing) = tasks.get(&user_key)
+            && !existing.handle.is_finished()
+        {
+            if queries.while_busy == WhileBusy::Queue {
+                debug!("{} is busy, message waits for a follow-up turn", user_key);
+                return;
+            }
+            // Start fresh with all pending messages
+            debug!("Aborting existing task for {}", user_key);
+            existing.handle.abort();
+        }
+
+        let debounce = Duration::from_millis(queries.debounce_ms);
+        let max_batch = queries.max_batch.max(1);
+        let manager = Arc::clone(self);
+        let key = user_key.clone();
+        let handle = tokio::spawn(async move {
+            // Each turn takes the messages that arrived before it, so the
+            // ones sent during a reply get a follow-up turn
+            loop {
+                // Debounce: wait a bit for more messages, longer while the
+                // user is still typing
+                let waiting_since = Instant::now();
+                loop {
+                    tokio::time::sleep(debounce).await;
+                    if manager.pending_count(&key).await >= max_batch
+                        || waiting_since.elapsed() >= MAX_TYPING_WAIT
+                        || !manager.is_typing(&key).await
+                    {
+                        break;
+                    }
+                    debug!("{} is still typing, waiting for more", key);
+                }
+
+                // Checked with the task list locked, so a message can't
+                // arrive unnoticed between the last turn and leaving
+                let batch = {
+                    let mut tasks = manager.tasks.lock().await;
+                    let mut pending = manager.pending.lock().await;
+                    let Some(waiting) = pending.get_mut(&key) else {
+                        tasks.remove(&key);
+                        return;
+                    };
+                    let batch: Vec<_> = waiting.drain(..waiting.len().min(max_batch)).collect();
+                    if waiting.is_empty() {
+                        pending.remove(&key);
+                    }
+                    batch
+                };
+                let mut texts = Vec::with_capacity(batch.len());
+                let mut latest = None;
+                for message in batch {
+                    texts.push(message.text);
+                    latest = Some(message.handler);
+                }
+                let Some(handler) = latest else {
+                    continue;
+                };
+                debug!("Processing {} message(s) for {}", texts.len(), key);
+                handler(texts).await;
+            }
+        });
+
+        tasks.insert(user_key, ActiveTask { handle });
+    }
+
+    /// Stop a user's running tasks and drop their waiting messages,
+    /// including those of their Slack threads (keyed "<user_key>:<thread>").
+    /// Returns whether anything was stopped.
+    pub async fn cancel(&self, user_key: &str) -> bool {
+        let thread_prefix = format!("{}:", user_key);
+        let matches = |key: &String| key == user_key || key.starts_with(&thread_prefix);
+
+        let mut tasks = self.tasks.lock().await;
+        let mut pending = self.pending.lock().await;
+        let waiting = pending.len();
+        pending.retain(|key, _| !matches(key));
+        let mut cancelled = pending.len() < waiting;
+
+        tasks.retain(|key, task| {
+            if !matches(key) {
+                return true;
+            }
+            if !task.handle.is_finished() {
+                task.handle.abort();
+                cancelled = true;
+            }
+            false
+        });
+        if cancelled {
+            info!("Cancelled the running task for {}", user_key);
+        }
+        cancelled
+    }
+}
+
+/// Result of processing a command
+pub enum CommandResult {
+    /// Not a command, continue with normal message processing
+    NotACommand,
+    /// Command was handled, return this response to the user
+    Response(String),
+    /// Trigger async cron job execution (job_id)
+    CronRun(String),
+    /// Trigger an async recap of recent conversations
+    Recap(RecapPeriod),
+    /// Build and send the daily digest now
+    DailyDigest,
+    /// Send the conversation or memories as a file
+    Export(ExportKind),
+    /// Check installed skills and report their status (/skills); owners
+    /// also see why a skill is broken
+    SkillStatus { is_owner: bool },
+    /// Send this earlier message to the AI again
+    Retry(String),
+    /// Stop the reply being worked on (/cancel)
+    Cancel,
+}
+
+/// A chat command, as listed by /help and in the clients' command menus
+pub struct CommandInfo {
+    /// Name without the slash, e.g. "recap"
+    pub name: &'static str,
+    /// Arguments, e.g. "[today|week]"
+    pub args: &'static str,
+    /// What it does, in a line
+    pub description: &'static str,
+    /// Details and examples shown by /help <command>
+    pub help: &'static str,
+}
+
+impl CommandInfo {
+    /// The command with its arguments, e.g. "/recap [today|week]"
+    pub fn usage(&self) -> String {
+        if self.args.is_empty() {
+            format!("/{}", self.name)
+        } else {
+            format!("/{} {}", self.name, self.args)
+        }
+    }
+
+    /// Arguments and description on one line for client command menus,
+    /// e.g. "[today|week] - Summarize what we talked about"
+    pub fn menu_description(&self, language: Option<&str>) -> String {
+        let description = i18n::tr(language, self.description);
+        if self.args.is_empty() {
+            description.to_string()
+        } else {
+            format!("{} - {}", self.args, description)
+        }
+    }
+}
+
+/// Commands for a client's menu: one entry per name, the first one listed
+pub fn menu_commands<'a>(
+    commands: impl IntoIterator<Item = &'a CommandInfo>,
+) -> Vec<&'a CommandInfo> {
+    let mut menu: Vec<&CommandInfo> = Vec::new();
+    for info in commands {
+        if !menu.iter().any(|m| m.name == info.name) {
+            menu.push(info);
+        }
+    }
+    menu
+}
+
+/// Available commands
+pub const COMMANDS: &[CommandInfo] = &[
+    CommandInfo {
+        name: "help",
+        args: "[command]",
+        description: "Show available commands, or how to use one",
+        help: "Examples:\n/help\n/help cron",
+    },
+    CommandInfo {
+        name: "status",
+        args: "",
+        description: "Show which model you're talking to, and your session, memories and jobs",
+        help: "Also shows how long I've been running.",
+    },
+    CommandInfo {
+        name: "new",
+        args: "",
+        description: "Start a new conversation",
+        help: "Clears our conversation so far. Memories, facts and your profile stay.",
+    },
+    CommandInfo {
+        name: "retry",
+        args: "",
+        description: "Send your last message again",
+        help: "Handy when a reply failed or took too long.",
+    },
+    CommandInfo {
+        name: "cancel",
+        args: "",
+        description: "Stop the reply I'm working on",
+        help: "Messages you sent meanwhile are dropped too.",
+    },
+    CommandInfo {
+        name: "good",
+        args: "[note]",
+        description: "Tell me my last reply was helpful",
+        help: "A 👍 message or reaction does the same.",
+    },
+    CommandInfo {
+        name: "bad",
+        args: "[what to change]",
+        description: "Tell me my last reply missed, and what to do instead",
+        help: "What you ask for is kept in memories/feedback.md and followed from then on. \
+               A 👎 message or reaction rates the reply without a note.\n\n\
+               Example:\n\
+               /bad Use metric units",
+    },
+    CommandInfo {
+        name: "think",
+        args: "[hard] <message>",
+        description: "Think longer before answering this message",
+        help: "Only this message gets the extra thinking time.\n\n\
+               Examples:\n\
+               /think What's the fastest route through these five stops?\n\
+               /think hard Check this proof for mistakes",
+    },
+    CommandInfo {
+        name: "verbose",
+        args: "[on|off]",
+        description: "List the tools I used under each reply",
+        help: "Without an argument, shows the current setting.\n\n\
+               Examples:\n/verbose on\n/verbose off",
+    },
+    CommandInfo {
+        name: "recap",
+        args: "[today|week]",
+        description: "Summarize what we talked about",
+        help: "Without an argument, summarizes today.\n\nExamples:\n/recap\n/recap week",
+    },
+    CommandInfo {
+        name: "digest",
+        args: "[<time>|now|off]",
+        description: "Get a daily digest of job results, open questions and new memories",
+        help: "The digest covers the last 24 hours and is skipped when nothing happened. \
+               Times are the server's local time. Without an argument, shows when yours \
+               goes out.\n\n\
+               Examples:\n\
+               /digest 08:00\n\
+               /digest now\n\
+               /digest off",
+    },
+    CommandInfo {
+        name: "history",
+        args: "[count]",
+        description: "Show our last messages",
+        help: "Shows the last 10 messages without a count, and at most 50.\n\n\
+               Examples:\n/history\n/history 30",
+    },
+    CommandInfo {
+        name: "export",
+        args: "[memories]",
+        description: "Get our conversation, or your memories, as a document",
+        help: "Without an argument, exports our conversation.\n\n\
+               Examples:\n/export\n/export memories",
+    },
+    CommandInfo {
+        name: "skills",
+        args: "",
+        description: "List available skills and check they work",
+        help: "Runs each skill's healthcheck and shows whether it passed.",
+    },
+    CommandInfo {
+        name: "persona",
+        args: "[show]",
+        description: "Show the persona I follow here",
+        help: "The persona is how I behave for everyone on this channel.",
+    },
+    CommandInfo {
+        name: "language",
+        args: "[<code>|auto]",
+        description: "Set the language I answer in",
+        help: "Without an argument, shows the current language.\n\n\
+               Examples:\n\
+               /language hu\n\
+               /language auto (pick it up from your messages again)",
+    },
+    CommandInfo {
+        name: "dnd",
+        args: "[<from>-<to>|off]",
+        description: "Set quiet hours, when I hold cron results and notifications",
+        help: "What was held arrives as one message when they end. Times are the \
+               server's local time. Without an argument, shows your quiet hours.\n\n\
+               Examples:\n\
+               /dnd 22:00-07:00\n\
+               /dnd off",
+    },
+    CommandInfo {
+        name: "onboard",
+        args: "restart",
+        description: "Forget who I am and who you are, and set us up again",
+        help: "Your old identity and profile are put aside, not deleted.",
+    },
+    CommandInfo {
+        name: "remind",
+        args: "me in <duration>|at <time> to <something>",
+        description: "Remind you once",
+        help: "Examples:\n\
+               /remind me in 20m to take the pizza out\n\
+               /remind me at 18:00 to call mom\n\
+               /remind me at 9:00 to check the flight status",
+    },
+    CommandInfo {
+        name: "cron",
+        args: "<subcommand>",
+        description: "Manage scheduled jobs",
+        help: "Examples:\n\
+               /cron add every 1h Check my emails\n\
+               /cron list\n\
+               /cron help (every subcommand)",
+    },
+];
+
+/// Commands only the owner can use
+pub const OWNER_COMMANDS: &[CommandInfo] = &[
+    CommandInfo {
+        name: "approve",
+        args: "<code>",
+        description: "Approve a pairing request",
+        help: "The code is the one the new user was sent, e.g. /approve ABCD1234",
+    },
+    CommandInfo {
+        name: "deny",
+        args: "<code>",
+        description: "Deny a pairing request",
+        help: "The sender gets no new code for a while, and longer each time they're denied.\n\n\
+               Example:\n/deny ABCD1234",
+    },
+    CommandInfo {
+        name: "import",
+        args: "<channel>:<user_id> identity|profile",
+        description: "Set a user's IDENTITY.md or USER.md to the lines that follow",
+        help: "Example:\n/import telegram:123456 profile\n# User\n- **Name:** Alex",
+    },
+    CommandInfo {
+        name: "persona",
+        args: "set <text>",
+        description: "Replace the persona for this channel (the lines may follow)",
+        help: "Example:\n/persona set Be brief. Answer in bullet points.",
+    },
+    CommandInfo {
+        name: "skill",
+        args: "install <git-url or name>|confirm <name>",
+        description: "Install a skill for everyone, once you've seen what it asks for",
+        help: "Example:\n/skill install https://github.com/someone/weather-skill\n\
+               I'll show what it needs; then /skill confirm weather-skill installs it.",
+    },
+    CommandInfo {
+        name: "cron",
+        args: "pause all|resume all",
+        description: "Stop or restart every cron job, keeping their settings",
+        help: "Handy during maintenance; every user's jobs are paused.",
+    },
+];
+
+/// The /help reply: every command, or the details of `command`
+fn help_response(command: &str, is_owner: bool, language: Option<&str>) -> String {
+    let visible = COMMANDS
+        .iter()
+        .chain(OWNER_COMMANDS.iter().filter(|_| is_owner));
+
+    let command = command.trim().trim_start_matches('/');
+    if command.is_empty() {
+        let mut response = format!("{}\n", i18n::tr(language, "Available commands:"));
+        for info in visible {
+            response.push_str(&format!(
+                "\n{} - {}",
+                info.usage(),
+                i18n::tr(language, info.description)
+            ));
+        }
+        response.push_str(&format!(
+            "\n\n{}",
+            i18n::tr(
+                language,
+                "Send /help <command> for details, e.g. /help cron"
+            )
+        ));
+        return response;
+    }
+
+    let matching: Vec<&CommandInfo> = visible.filter(|info| info.name == command).collect();
+    if matching.is_empty() {
+        return i18n::tr_args(
+            language,
+            "Unknown command: /{}. Send /help to see them all.",
+            &[&command],
+        );
+    }
+    matching
+        .iter()
+        .map(|info| {
+            format!(
+                "{}\n{}\n\n{}",
+                info.usage(),
+                i18n::tr(language, info.description),
+                info.help
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Whether a user may use owner-only commands: the owner in config.toml,
+/// who gets system notifications, or anyone with the owner role
+pub fn is_owner(store: &PairingStore, channel: &str, user_id: &str) -> bool {
+    notify::is_owner(channel, user_id) || store.is_owner(channel, user_id)
+}
+
+/// Reply to someone who isn't an owner using an owner-only command
+fn owner_only(channel: &str, user_id: &str, action: &str) -> CommandResult {
+    CommandResult::Response(format!(
+        "Only the owner can {}. The owner can make you one with: cica owner set {} {}",
+        action, channel, user_id
+    ))
+}
+
+/// Effective role and permissions for an approved user.
+/// The configured owner always has the owner role.
+pub fn user_permissions(
+    store: &PairingStore,
+    channel: &str,
+    user_id: &str,
+) -> (Role, RolePermissions) {
+    let role = if is_owner(store, channel, user_id) {
+        Role::Owner
+    } else {
+        store.role(channel, user_id)
+    };
+
+    let permissions = Config::load()
+        .map(|c| c.role_permissions(role))
+        .unwrap_or_else(|_| Config::default().role_permissions(role));
+
+    (role, permissions)
+}
+
+/// System prompt note describing what a restricted user may not do
+fn permissions_prompt(role: Role, permissions: RolePermissions) -> Option<String> {
+    let mut rules = Vec::new();
+    if !permissions.write_workspace {
+        rules.push("You can read files but cannot modify files or run commands.");
+    }
+    if !permissions.skills {
+        rules.push("Do not create or edit skills; suggest asking the owner instead.");
+    }
+    if !permissions.cron {
+        rules.push("Do not create scheduled jobs; suggest asking the owner instead.");
+    }
+    if !permissions.shared_memories {
+        rules.push(
+            "Do not save shared memories; save to the user's own memories, or suggest asking the owner.",
+        );
+    }
+
+    if rules.is_empty() {
+        return None;
+    }
+
+    Some(format!(
+        "## Permissions\nThis user has the {} role.\n- {}",
+        role.as_str(),
+        rules.join("\n- ")
+    ))
+}
+
+/// Directories a role can read but not change: skills/ without the skills
+/// permission, and the shared memories without that one
+fn read_only_dirs(permissions: RolePermissions) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if !permissions.skills
+        && let Ok(paths) = crate::config::paths()
+    {
+        dirs.push(paths.skills_dir);
+    }
+    if !permissions.shared_memories
+        && let Ok(dir) = shared_memories_dir()
+    {
+        dirs.push(dir);
+    }
+    dirs
+}
+
+/// Parse `/approve <code>` or `/deny <code>` into (command, code)
+fn parse_pairing_command(text: &str) -> Option<(&str, &str)> {
+    let (command, code) = text.split_once(char::is_whitespace)?;
+    match command {
+        "/approve" | "/deny" => Some((command, code.trim())),
+        _ => None,
+    }
+}
+
+/// Parse `/import <channel>:<user_id> <identity|profile>` followed by the
+/// file content on the next lines. Returns (target, file, content).
+fn parse_import_command(text: &str) -> Option<(&str, &str, &str)> {
+    let rest = text.strip_prefix("/import")?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let (header, content) = rest.split_once('\n').unwrap_or((rest, ""));
+    let mut args = header.split_whitespace();
+    let target = args.next().unwrap_or("");
+    let file = args.next().unwrap_or("");
+
+    Some((target, file, content.trim()))
+}
+
+/// Write an imported onboarding file for a user, returning the reply for the owner
+fn process_import_command(target: &str, file: &str, content: &str) -> String {
+    const USAGE: &str = "Usage: /import <channel>:<user_id> identity|profile\n\
+                         followed by the file content on the next lines";
+
+    let Some((channel, user_id)) = target.split_once(':') else {
+        return USAGE.to_string();
+    };
+    let Ok(file) = file.parse::<onboarding::UserFile>() else {
+        return USAGE.to_string();
+    };
+    let Some(info) = get_channel_info(channel) else {
+        return format!("Unknown channel: {}", channel);
+    };
+
+    match onboarding::import_user_file(channel, user_id, file, content) {
+        Ok(path) => {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            format!(
+                "Imported {} for {} user {}",
+                name, info.display_name, user_id
+            )
+        }
+        Err(e) => format!("Import failed: {}", e),
+    }
+}
+
+/// Approve or deny a pending pairing request, returning the reply for the owner
+fn process_pairing_command(store: &mut PairingStore, command: &str, code: &str) -> String {
+    let result = if command == "/approve" {
+        store.approve(code)
+    } else {
+        store.deny(code)
+    };
+
+    let request = match result {
+        Ok(request) => request,
+        Err(e) => return e.to_string(),
+    };
+
+    let channel_display = get_channel_info(&request.channel)
+        .map(|c| c.display_name)
+        .unwrap_or(&request.channel);
+    let user_display = request
+        .display_name
+        .as_deref()
+        .or(request.username.as_deref())
+        .unwrap_or(&request.user_id);
+
+    info!(
+        "Owner {} {} user {} ({})",
+        if command == "/approve" {
+            "approved"
+        } else {
+            "denied"
+        },
+        request.channel,
+        request.user_id,
+        user_display
+    );
+
+    if command == "/approve" {
+        notify::send_to_user(
+            &request.channel,
+            &request.user_id,
+            "You're approved! Say hello to get started.",
+        );
+        format!("Approved {} user: {}", channel_display, user_display)
+    } else {
+        format!("Denied {} user: {}", channel_display, user_display)
+    }
+}
+
+/// Process a command if the message is one.
+pub fn process_command(
+    store: &mut PairingStore,
+    channel: &str,
+    user_id: &str,
+    text: &str,
+    onboarding_complete: bool,
+) -> Result<CommandResult> {
+    let text = text.trim();
+
+    let is_owner = is_owner(store, channel, user_id);
+    let language = store.language(channel, user_id).map(str::to_string);
+    let language = language.as_deref();
+
+    // /commands is the old name of /help
+    if let Some(command) = parse_help_command(text) {
+        return Ok(CommandResult::Response(help_response(
+            command, is_owner, language,
+        )));
+    }
+
+    if let Some(setting) = parse_language_command(text) {
+        return Ok(CommandResult::Response(process_language_command(
+            store, channel, user_id, setting,
+        )?));
+    }
+
+    if let Some(setting) = parse_dnd_command(text) {
+        return Ok(CommandResult::Response(process_dnd_command(
+            store, channel, user_id, setting,
+        )?));
+    }
+
+    // Pairing approval from chat
+    if let Some((command, code)) = parse_pairing_command(text) {
+        if !is_owner {
+            return Ok(owner_only(
+                channel,
+                user_id,
+                "approve or deny pairing requests",
+            ));
+        }
+        return Ok(CommandResult::Response(process_pairing_command(
+            store, command, code,
+        )));
+    }
+
+    // Pre-provisioned onboarding files from chat
+    if let Some((target, file, content)) = parse_import_command(text) {
+        if !is_owner {
+            return Ok(owner_only(channel, user_id, "import user files"));
+        }
+        return Ok(CommandResult::Response(process_import_command(
+            target, file, content,
+        )));
+    }
+
+    if let Some(new_persona) = parse_persona_command(text) {
+        let Some(content) = new_persona else {
+            return Ok(CommandResult::Response(show_persona(channel)));
+        };
+        if !is_owner {
+            return Ok(owner_only(channel, user_id, "change the persona"));
+        }
+        if content.is_empty() {
+            return Ok(CommandResult::Response(
+                "Usage: /persona set <text>".to_string(),
+            ));
+        }
+        return Ok(CommandResult::Response(
+            match onboarding::save_persona(Some(channel), content) {
+                Ok(path) => {
+                    info!("Owner replaced persona {}", path.display());
+                    "Persona updated. It applies from the next message.".to_string()
+                }
+                Err(e) => format!("Couldn't save the persona: {}", e),
+            },
+        ));
+    }
+
+    // "/think" with nothing to think about
+    if let (Some(_), "") = parse_think_prefix(text) {
+        return Ok(CommandResult::Response(
+            "Usage: /think [hard] <message>\n\n\
+             Example: /think hard Check this proof for mistakes"
+                .to_string(),
+        ));
+    }
+
+    if let Some((subcommand, args)) = parse_skill_command(text) {
+        if !is_owner {
+            return Ok(owner_only(channel, user_id, "install skills"));
+        }
+        return Ok(CommandResult::Response(match (subcommand, args) {
+            ("install", "") => "Usage: /skill install <git-url or name>".to_string(),
+            ("install", source) => start_skill_review(source),
+            ("confirm", "") => "Usage: /skill confirm <name>".to_string(),
+            ("confirm", name) => start_skill_install(name),
+            _ => "Usage: /skill install <git-url or name>|confirm <name>".to_string(),
+        }));
+    }
+
+    if text == "/new" {
+        if !onboarding_complete {
+            return Ok(CommandResult::Response(
+                i18n::tr(
+                    language,
+                    "Please complete the onboarding first. Say \"hello\" to get started!",
+                )
+                .to_string(),
+            ));
+        }
+        store.reset_session(channel, user_id)?;
+        return Ok(CommandResult::Response(
+            i18n::tr(
+                language,
+                "Starting fresh! Our previous conversation has been cleared.",
+            )
+            .to_string(),
+        ));
+    }
+
+    if let Some(rest) = text.strip_prefix("/onboard")
+        && (rest.is_empty() || rest.starts_with(char::is_whitespace))
+    {
+        if rest.trim() != "restart" {
+            return Ok(CommandResult::Response(
+                "Usage: /onboard restart".to_string(),
+            ));
+        }
+        let archived = onboarding::restart_onboarding(channel, user_id, true)?;
+        store.reset_session(channel, user_id)?;
+        store.set_onboarded(channel, user_id, false)?;
+        info!("{}:{} restarted onboarding", channel, user_id);
+        return Ok(CommandResult::Response(match archived {
+            Some(_) => "Starting over! I've put our old identity and profile aside \
+                        (the owner can still find them). Say \"hello\" to begin."
+                .to_string(),
+            None => "Starting over! Say \"hello\" to begin.".to_string(),
+        }));
+    }
+
+    if let Some((rating, note)) = parse_feedback_command(text) {
+        return Ok(CommandResult::Response(process_feedback(
+            channel, user_id, rating, note, language,
+        )?));
+    }
+
+    if text == "/retry" {
+        if !onboarding_complete {
+            return Ok(CommandResult::Response(
+                i18n::tr(
+                    language,
+                    "Please complete the onboarding first. Say \"hello\" to get started!",
+                )
+                .to_string(),
+            ));
+        }
+        return Ok(match transcript::last_from_user(channel, user_id)? {
+            Some(last) => CommandResult::Retry(last),
+            None => CommandResult::Response(
+                i18n::tr(language, "There's no message to retry yet.").to_string(),
+            ),
+        });
+    }
+
+    if text == "/cancel" {
+        return Ok(CommandResult::Cancel);
+    }
+
+    if let Some(setting) = parse_verbose_command(text) {
+        let enabled = match setting {
+            Some(enabled) => {
+                let mut profile = store
+                    .get_user_profile(channel, user_id)
+                    .cloned()
+                    .unwrap_or_default();
+                profile.verbose = enabled;
+                store.update_user_profile(channel, user_id, profile)?;
+                enabled
+            }
+            None => store
+                .get_user_profile(channel, user_id)
+                .is_some_and(|p| p.verbose),
+        };
+        return Ok(CommandResult::Response(if enabled {
+            "Verbose is on: I'll list the tools I used under each reply.".to_string()
+        } else {
+            "Verbose is off. Turn it on with /verbose on".to_string()
+        }));
+    }
+
+    if let Some(count) = parse_history_command(text) {
+        let Some(count) = count else {
+            return Ok(CommandResult::Response(format!(
+                "Usage: /history [count] (up to {})",
+                HISTORY_MAX_COUNT
+            )));
+        };
+        let messages = transcript::recent(channel, user_id, count)?;
+        return Ok(CommandResult::Response(format_history(&messages)));
+    }
+
+    if let Some(period) = parse_recap_command(text) {
+        if !onboarding_complete {
+            return Ok(CommandResult::Response(
+                i18n::tr(
+                    language,
+                    "Please complete the onboarding first. Say \"hello\" to get started!",
+                )
+                .to_string(),
+            ));
+        }
+        return Ok(match period {
+            Some(period) => CommandResult::Recap(period),
+            None => CommandResult::Response("Usage: /recap [today|week]".to_string()),
+        });
+    }
+
+    if let Some(kind) = parse_export_command(text) {
+        return Ok(match kind {
+            Some(kind) => CommandResult::Export(kind),
+            None => CommandResult::Response("Usage: /export [memories]".to_string()),
+        });
+    }
+
+    if let Some(setting) = parse_digest_command(text) {
+        if !onboarding_complete {
+            return Ok(CommandResult::Response(
+                i18n::tr(
+                    language,
+                    "Please complete the onboarding first. Say \"hello\" to get started!",
+                )
+                .to_string(),
+            ));
+        }
+        if setting == "now" {
+            return Ok(CommandResult::DailyDigest);
+        }
+        return Ok(CommandResult::Response(process_digest_command(
+            store, channel, user_id, setting,
+        )?));
+    }
+
+    if text == "/skills" {
+        return Ok(CommandResult::SkillStatus { is_owner });
+    }
+
+    if text == "/status" {
+        return Ok(CommandResult::Response(status_response(
+            store, channel, user_id,
+        )?));
+    }
+
+    if let Some(args) = text
+        .strip_prefix("/remind")
+        .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+    {
+        let (role, permissions) = user_permissions(store, channel, user_id);
+        if !permissions.cron {
+            return Ok(CommandResult::Response(i18n::tr_args(
+                language,
+                "Sorry, your role ({}) can't create scheduled jobs.",
+                &[&role.as_str()],
+            )));
+        }
+        return process_remind_command(channel, user_id, args);
+    }
+
+    // Handle /cron commands
+    if text.starts_with("/cron") {
+        let args = text.strip_prefix("/cron").unwrap_or("").trim();
+
+        if matches!(args, "pause all" | "resume all") {
+            if !is_owner {
+                return Ok(owner_only(channel, user_id, "pause or resume all jobs"));
+            }
+            return Ok(CommandResult::Response(process_pause_all(
+                args == "pause all",
+            )?));
+        }
+
+        let (role, permissions) = user_permissions(store, channel, user_id);
+        if !permissions.cron && cron_needs_permission(args) {
+            return Ok(CommandResult::Response(i18n::tr_args(
+                language,
+                "Sorry, your role ({}) can't create or change scheduled jobs.",
+                &[&role.as_str()],
+            )));
+        }
+
+        return process_cron_command(channel, user_id, args, language);
+    }
+
+    Ok(CommandResult::NotACommand)
+}
+
+/// Whether a /cron command needs the role's cron permission: everything but
+/// looking at jobs and templates, and stopping jobs (so a user whose role
+/// loses it can still stop theirs)
+fn cron_needs_permission(args: &str) -> bool {
+    let mut words = args.split_whitespace();
+    match words.next().unwrap_or("help") {
+        "list" | "ls" | "preview" | "log" | "history" | "help" | "remove" | "rm" | "delete"
+        | "pause" | "disable" => false,
+        "template" | "templates" => !matches!(words.next(), None | Some("list" | "ls")),
+        _ => true,
+    }
+}
+
+/// Process /cron template subcommands
+fn process_template_command(
+    channel: &str,
+    user_id: &str,
+    args: &str,
+    language: Option<&str>,
+) -> Result<CommandResult> {
+    let user_key = format!("{}:{}", channel, user_id);
+    let mut words = args.split_whitespace();
+
+    match words.next().unwrap_or("list") {
+        "list" | "ls" => {
+            let templates = cron::templates::list()?;
+            if templates.is_empty() {
+                return Ok(CommandResult::Response(
+                    "No templates yet. Save one with /cron template save <template> <job>."
+                        .to_string(),
+                ));
+            }
+
+            let mut response = String::from("Job templates:\n");
+            for template in templates {
+                response.push_str(&format!(
+                    "\n{} ({})\n  {}\n",
+                    template.name,
+                    template.job.schedule.description(),
+                    truncate_for_name(&template.job.prompt, 80)
+                ));
+            }
+            response.push_str("\nCreate a job from one with /cron add from <template>.");
+            Ok(CommandResult::Response(response))
+        }
+
+        "save" => {
+            // Job names may contain spaces; the template name can't
+            let rest = args.trim_start().trim_start_matches("save").trim();
+            let Some((name, id)) = rest
+                .split_once(' ')
+                .map(|(name, id)| (name, id.trim()))
+                .filter(|(_, id)| !id.is_empty())
+            else {
+                return Ok(CommandResult::Response(
+                    "Usage: /cron template save <template> <name or job-id>".to_string(),
+                ));
+            };
+
+            let store = CronStore::load()?;
+            let job_id = find_job_id(&store, channel, user_id, id)?;
+            let Some(job) = store.get(&job_id, channel, user_id) else {
+                return Ok(CommandResult::Response(i18n::tr_args(
+                    language,
+                    "Job not found: {}",
+                    &[&id],
+                )));
+            };
+
+            match cron::templates::save(name, job, &user_key) {
+                Ok(()) => Ok(CommandResult::Response(format!(
+                    "Saved \"{}\" as template {}. Anyone can now use /cron add from {}",
+                    job.name, name, name
+                ))),
+                Err(e) => Ok(CommandResult::Response(i18n::tr_args(
+                    language,
+                    "Error: {}",
+                    &[&e],
+                ))),
+            }
+        }
+
+        "delete" | "remove" | "rm" => {
+            let Some(name) = words.next() else {
+                return Ok(CommandResult::Response(
+                    "Usage: /cron template delete <template>".to_string(),
+                ));
+            };
+
+            match cron::templates::delete(name, &user_key) {
+                Ok(true) => Ok(CommandResult::Response(format!(
+                    "Deleted template {}",
+                    name
+                ))),
+                Ok(false) => Ok(CommandResult::Response(format!(
+                    "Template not found: {}",
+                    name
+                ))),
+                Err(e) => Ok(CommandResult::Response(i18n::tr_args(
+                    language,
+                    "Error: {}",
+                    &[&e],
+                ))),
+            }
+        }
+
+        _ => Ok(CommandResult::Response(
+            "Usage: /cron template list|save <template> <job>|delete <template>".to_string(),
+        )),
+    }
+}
+
+/// Process `/cron add from <template> [<schedule>]`
+fn add_from_template(
+    channel: &str,
+    user_id: &str,
+    args: &str,
+    language: Option<&str>,
+) -> Result<CommandResult> {
+    let (name, schedule) = args.trim().split_once(' ').unwrap_or((args.trim(), ""));
+    let Some(template) = cron::templates::get(name)? else {
+        return Ok(CommandResult::Response(format!(
+            "Template not found: {}. See /cron template list.",
+            name
+        )));
+    };
+
+    let mut store = CronStore::load()?;
+    let job_name = store.unique_name(channel, user_id, &template.job.name);
+    let mut job = template.job.duplicate(job_name, channel, user_id);
+    if !schedule.trim().is_empty() {
+        match CronSchedule::parse(schedule) {
+            Ok(schedule) => job.apply(cron::JobEdit::Schedule(schedule), cron::store::now_millis()),
+            Err(e) => {
+                return Ok(CommandResult::Response(i18n::tr_args(
+                    language,
+                    "Error: {}",
+                    &[&e],
+                )));
+            }
+        }
+    }
+
+    let response = created_response(&job);
+    store.add(job)?;
+    Ok(CommandResult::Response(response))
+}
+
+/// Reply to a job made by /cron copy or /cron add from
+fn created_response(job: &cron::CronJob) -> String {
+    let next = job
+        .state
+        .next_run_at
+        .map(format_timestamp)
+        .unwrap_or_else(|| "—".to_string());
+    format!(
+        "Created job [{}] \"{}\"\nSchedule: {}\nNext run: {}\nPrompt: {}\n\n\
+         Change it with /cron edit {} schedule|prompt|name <new>",
+        job.short_id(),
+        job.name,
+        job.schedule.description(),
+        next,
+        job.prompt,
+        job.short_id()
+    )
+}
+
+/// The user's timezone from their profile, if set to one we know
+fn user_timezone(channel: &str, user_id: &str) -> Option<chrono_tz::Tz> {
+    match PairingStore::load() {
+        Ok(store) => store.timezone(channel, user_id),
+        Err(e) => {
+            warn!("Failed to load pairing store: {}", e);
+            None
+        }
+    }
+}
+
+/// Process /remind: a one-shot job, delivered as is unless it needs the agent
+fn process_remind_command(channel: &str, user_id: &str, args: &str) -> Result<CommandResult> {
+    let zone = user_timezone(channel, user_id);
+    let reminder = match cron::parse_remind_command(args, cron::store::now_millis(), zone) {
+        Ok(reminder) => reminder,
+        Err(e) => return Ok(CommandResult::Response(e.to_string())),
+    };
+
+    let mut store = CronStore::load()?;
+    let name = store.unique_name(
+        channel,
+        user_id,
+        &format!("remind: {}", truncate_for_name(&reminder.text, 30)),
+    );
+    let mut job = cron::CronJob::new(
+        name,
+        reminder.prompt(),
+        CronSchedule::At(reminder.at),
+        channel.to_string(),
+        user_id.to_string(),
+    );
+    job.reminder = !reminder.needs_agent;
+    store.add(job)?;
+
+    Ok(CommandResult::Response(format!(
+        "OK, I'll remind you at {}: {}",
+        cron::format_timestamp_in(reminder.at, zone, "%Y-%m-%d %H:%M"),
+        reminder.text
+    )))
+}
+
+/// Stop the user's running reply (/cancel) and say whether there was one
+pub async fn cancel_response(channel: &str, user_id: &str) -> String {
+    let language = i18n::user_language(channel, user_id);
+    let cancelled = UserTaskManager::global()
+        .cancel(&format!("{}:{}", channel, user_id))
+        .await;
+    let response = if cancelled {
+        "Stopped. Send your message again when you're ready."
+    } else {
+        "There's nothing to cancel."
+    };
+    i18n::tr(language.as_deref(), response).to_string()
+}
+
+/// List installed skills, with the result of their healthchecks. Only
+/// owners see why a skill is broken, since that can be command output.
+pub async fn skill_status(is_owner: bool) -> String {
+    let checked = skills::check_all_cached().await.unwrap_or_default();
+    if checked.is_empty() {
+        return "No skills installed.".to_string();
+    }
+    let mut response = String::from("Available skills:\n");
+    for (skill, health) in checked {
+        response.push_str(&format!("\n• {} - {}", skill.name, skill.description));
+        if let Some(label) = health.label(is_owner) {
+            response.push_str(&format!(" ({})", label));
+        }
+    }
+    response
+}
+
+/// How long a skill from /skill install waits for /skill confirm
+const PENDING_SKILL_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Skills cloned by /skill install that the owner hasn't confirmed, with
+/// when they were reviewed
+static PENDING_SKILLS: std::sync::Mutex<Vec<(Instant, skills::ReviewedSkill)>> =
+    std::sync::Mutex::new(Vec::new());
+
+/// Clone a skill in the background and show the owner what it asks for,
+/// keeping it for /skill confirm
+fn start_skill_review(source: &str) -> String {
+    let source = source.to_string();
+    let reply = format!("Fetching {}, I'll show you what it needs.", source);
+    tokio::spawn(async move {
+        let message = match skills::review(&source).await {
+            Ok(reviewed) => {
+                let message = format!(
+                    "{}\n\nSend /skill confirm {} within 10 minutes to install it.",
+                    reviewed.describe(),
+                    reviewed.dir_name()
+                );
+                if let Ok(mut pending) = PENDING_SKILLS.lock() {
+                    pending.retain(|(at, skill)| {
+                        at.elapsed() < PENDING_SKILL_TTL && skill.dir_name() != reviewed.dir_name()
+                    });
+                    pending.push((Instant::now(), reviewed));
+                }
+                message
+            }
+            Err(e) => format!("Couldn't install skill {}: {:#}", source, e),
+        };
+        notify::notify_owner(message);
+    });
+    reply
+}
+
+/// Install a skill the owner reviewed in the background, telling them how
+/// it went.
+fn start_skill_install(name: &str) -> String {
+    let reviewed = PENDING_SKILLS.lock().ok().and_then(|mut pending| {
+        pending.retain(|(at, _)| at.elapsed() < PENDING_SKILL_TTL);
+        let index = pending
+            .iter()
+            .position(|(_, skill)| skill.dir_name() == name)?;
+        Some(pending.remove(index).1)
+    });
+    let Some(reviewed) = reviewed else {
+        return format!(
+            "No skill {} is waiting to be installed. Send /skill install first.",
+            name
+        );
+    };
+
+    let reply = format!("Installing {}, I'll let you know when it's done.", name);
+    let name = name.to_string();
+    tokio::spawn(async move {
+        let message = match reviewed.install().await {
+            Ok(installed) => {
+                let mut message = format!(
+                    "Installed skill {}: {}{}",
+                    installed.skill.name,
+                    installed.skill.description,
+                    if installed.dependencies {
+                        " (dependencies installed with Bun)"
+                    } else {
+                        ""
+                    },
+                );
+                if !installed.missing_env.is_empty() {
+                    message.push_str(&format!("\nNot set: {}", installed.missing_env.join(", ")));
+                }
+                message
+            }
+            Err(e) => format!("Couldn't install skill {}: {:#}", name, e),
+        };
+        notify::notify_owner(message);
+    });
+    reply
+}
+
+/// Process /cron pause all and /cron resume all
+fn process_pause_all(pause: bool) -> Result<String> {
+    let now = cron::store::now_millis();
+    if pause {
+        return Ok(if cron::store::pause_all(now)? {
+            "Paused all cron jobs. They keep their settings; /cron resume all to start them again."
+                .to_string()
+        } else {
+            "All cron jobs are already paused. /cron resume all to start them again.".to_string()
+        });
+    }
+
+    Ok(match cron::store::resume_all(now)? {
+        None => "Cron jobs aren't paused.".to_string(),
+        Some(0) => "Resumed all cron jobs.".to_string(),
+        Some(missed) => format!(
+            "Resumed all cron jobs. {} run(s) missed while paused will wait for their next time.",
+            missed
+        ),
+    })
+}
+
+/// Process /cron subcommands
+fn process_cron_command(
+    channel: &str,
+    user_id: &str,
+    args: &str,
+    language: Option<&str>,
+) -> Result<CommandResult> {
+    let parts: Vec<&str> = args.splitn(2, ' ').collect();
+    let subcommand = parts.first().copied().unwrap_or("help");
+    let rest = parts.get(1).copied().unwrap_or("");
+
+    match subcommand {
+        "list" | "ls" => {
+            let store = CronStore::load()?;
+            let jobs = store.list_for_user(channel, user_id);
+
+            if jobs.is_empty() {
+                return Ok(CommandResult::Response(
+                    "No scheduled jobs.\n\nUse /cron add to create one. Try /cron help for usage."
+                        .to_string(),
+                ));
+            }
+
+            let mut response = String::from("Your scheduled jobs:\n");
+            if let Some(since) = cron::store::paused_since()? {
+                response.insert_str(
+                    0,
+                    &format!(
+                        "All jobs have been paused by the owner since {}.\n\n",
+                        format_timestamp(since)
+                    ),
+                );
+            }
+            for job in jobs {
+                let status = job.state.last_status.as_str();
+                let next = job
+                    .state
+                    .next_run_at
+                    .map(format_timestamp)
+                    .unwrap_or_else(|| "—".to_string());
+                let enabled = if job.enabled { "" } else { " (paused)" };
+
+                response.push_str(&format!(
+                    "\n[{}] {}{}\n  Schedule: {}\n  Status: {} | Next: {}\n",
+                    job.short_id(),
+                    job.name,
+                    enabled,
+                    job.schedule.description(),
+                    status,
+                    next
+                ));
+            }
+            Ok(CommandResult::Response(response))
+        }
+
+        "add" => {
+            if rest.is_empty() {
+                return Ok(CommandResult::Response(
+                    "Usage: /cron add [--name <name>] [--dry-run] [backend=<backend>] [model=<model>] [ingest=<dir>] <schedule> <prompt>\n\n\
+                     Examples:\n\
+                     /cron add --name morning-brief 0 9 * * * Summarize my day\n\
+                     /cron add model=haiku every 15m Check the build status\n\
+                     /cron add every 1h Check my emails\n\
+                     /cron add every 10s Say hello\n\
+                     /cron add on /receipt/ Log the expense in expenses.csv\n\
+                     /cron add ics https://example.com/work.ics 30m before Prep notes for this meeting\n\
+                     /cron add 0 9 * * * Good morning!"
+                        .to_string(),
+                ));
+            }
+
+            if let Some(rest) = rest.strip_prefix("from ") {
+                return add_from_template(channel, user_id, rest, language);
+            }
+
+            let (options, rest) = split_add_options(rest);
+            let explicit_name = options.name;
+            let backend = match options.backend.map(str::parse::<AiBackend>).transpose() {
+                Ok(backend) => backend,
+                Err(e) => return Ok(CommandResult::Response(i18n::tr_args(language, "Error: {}", &[&e]))),
+            };
+            let ingest = match options.ingest.map(cron::parse_ingest_dir).transpose() {
+                Ok(ingest) => ingest,
+                Err(e) => return Ok(CommandResult::Response(i18n::tr_args(language, "Error: {}", &[&e]))),
+            };
+            let (schedule, prompt) = match parse_add_command(rest) {
+                Ok(result) => result,
+                Err(e) => return Ok(CommandResult::Response(i18n::tr_args(language, "Error: {}", &[&e]))),
+            };
+
+            if options.dry_run {
+                return Ok(CommandResult::Response(format!(
+                    "{}\nPrompt: {}\n\nNothing was created; send it again without --dry-run to create the job.",
+                    cron::preview_schedule(
+                        &schedule,
+                        cron::store::now_millis(),
+                        user_timezone(channel, user_id)
+                    ),
+                    prompt
+                )));
+            }
+
+            let mut store = CronStore::load()?;
+            let name = match explicit_name {
+                Some(name) => {
+                    if store.find_by_name(channel, user_id, name).is_some() {
+                        return Ok(CommandResult::Response(format!(
+                            "You already have a job named \"{}\". Pick another name.",
+                            name
+                        )));
+                    }
+                    name.to_string()
+                }
+                None => store.unique_name(channel, user_id, &truncate_for_name(&prompt, 30)),
+            };
+            let mut job = cron::CronJob::new(
+                name.clone(),
+                prompt,
+                schedule.clone(),
+                channel.to_string(),
+                user_id.to_string(),
+            );
+            job.backend = backend;
+            job.model = options.model.map(str::to_string);
+            job.ingest = ingest;
+            let id = store.add(job)?;
+
+            let next = match &schedule {
+                CronSchedule::At(ts) => format_timestamp(*ts),
+                CronSchedule::OnMessage(_) => "when a message matches".to_string(),
+                CronSchedule::Ics(..) => "once the calendar is fetched".to_string(),
+                CronSchedule::Every(_) | CronSchedule::Cron(_) => {
+                    let store = CronStore::load()?;
+                    store
+                        .jobs
+                        .get(&id)
+                        .and_then(|j| j.state.next_run_at)
+                        .map(format_timestamp)
+                        .unwrap_or_else(|| "soon".to_string())
+                }
+            };
+
+            let handle = if explicit_name.is_some() {
+                name.as_str()
+            } else {
+                &id[..8]
+            };
+
+            Ok(CommandResult::Response(format!(
+                "Created job [{}] \"{}\"\nSchedule: {}\nNext run: {}\n\nUse /cron run {} to test it now!",
+                &id[..8],
+                name,
+                schedule.description(),
+                next,
+                handle
+            )))
+        }
+
+        "preview" => {
+            if rest.is_empty() {
+                return Ok(CommandResult::Response(
+                    "Usage: /cron preview <schedule>\n\n\
+                     Example: /cron preview 0 9 * * 1-5"
+                        .to_string(),
+                ));
+            }
+
+            match CronSchedule::parse(rest) {
+                Ok(schedule) => Ok(CommandResult::Response(cron::preview_schedule(
+                    &schedule,
+                    cron::store::now_millis(),
+                    user_timezone(channel, user_id),
+                ))),
+                Err(e) => Ok(CommandResult::Response(i18n::tr_args(language, "Error: {}", &[&e]))),
+            }
+        }
+
+        "remove" | "rm" | "delete" => {
+            let id = rest.trim();
+            if id.is_empty() {
+                return Ok(CommandResult::Response(
+                    "Usage: /cron remove <name or job-id>".to_string(),
+                ));
+            }
+
+            let mut store = CronStore::load()?;
+
+            // Find job by full ID or prefix
+            let job_id = find_job_id(&store, channel, user_id, id)?;
+
+            match store.remove(&job_id, channel, user_id)? {
+                Some(job) => Ok(CommandResult::Response(format!(
+                    "Removed job [{}] \"{}\"",
+                    job.short_id(),
+                    job.name
+                ))),
+                None => Ok(CommandResult::Response(i18n::tr_args(language, "Job not found: {}", &[&id]))),
+            }
+        }
+
+        "run" => {
+            let id = rest.trim();
+            if id.is_empty() {
+                return Ok(CommandResult::Response(
+                    "Usage: /cron run <name or job-id>".to_string(),
+                ));
+            }
+
+            let store = CronStore::load()?;
+            let job_id = find_job_id(&store, channel, user_id, id)?;
+
+            // Return special variant for async execution by the channel handler
+            Ok(CommandResult::CronRun(job_id))
+        }
+
+        "edit" | "rename" => {
+            let usage = "Usage: /cron edit <job> schedule <schedule>\n\
+                         /cron edit <job> prompt <prompt>\n\
+                         /cron edit <job> name <name>\n\
+                         /cron edit <job> retries <count>\n\
+                         /cron edit <job> backoff <duration, e.g. 5m>\n\
+                         /cron edit <job> pause-after <failures, 0 for never>\n\
+                         /cron edit <job> notify always|failure|change|noteworthy|off\n\
+                         /cron edit <job> overlap skip|queue|kill-previous\n\
+                         /cron edit <job> max-runtime <duration, e.g. 30m, or off>\n\
+                         /cron edit <job> priority low|normal|high\n\
+                         /cron edit <job> session fresh|continue\n\
+                         /cron edit <job> backend claude|cursor|openai|default\n\
+                         /cron edit <job> model <model, or default>\n\
+                         /cron edit <job> ingest <memories dir, or off>";
+            let edit = if subcommand == "rename" {
+                rest.trim()
+                    .rsplit_once(' ')
+                    .map(|(id, name)| (id, "name", name))
+            } else {
+                split_job_edit(rest)
+            };
+            let Some((id, field, value)) = edit else {
+                return Ok(CommandResult::Response(usage.to_string()));
+            };
+
+            let edit = match field {
+                "schedule" => match CronSchedule::parse(value) {
+                    Ok(schedule) => cron::JobEdit::Schedule(schedule),
+                    Err(e) => return Ok(CommandResult::Response(i18n::tr_args(language, "Error: {}", &[&e]))),
+                },
+                "prompt" => cron::JobEdit::Prompt(value.to_string()),
+                "retries" | "pause-after" => {
+                    let Ok(count) = value.parse::<u32>() else {
+                        return Ok(CommandResult::Response(format!(
+                            "Error: expected a number, got: {}",
+                            value
+                        )));
+                    };
+                    if field == "retries" {
+                        cron::JobEdit::Retries(count)
+                    } else {
+                        cron::JobEdit::PauseAfter(count)
+                    }
+                }
+                "session" => match value.parse::<cron::SessionMode>() {
+                    Ok(session) => cron::JobEdit::Session(session),
+                    Err(e) => return Ok(CommandResult::Response(i18n::tr_args(language, "Error: {}", &[&e]))),
+                },
+                "backend" if value.eq_ignore_ascii_case("default") => cron::JobEdit::Backend(None),
+                "backend" => match value.parse::<AiBackend>() {
+                    Ok(backend) => cron::JobEdit::Backend(Some(backend)),
+                    Err(e) => return Ok(CommandResult::Response(i18n::tr_args(language, "Error: {}", &[&e]))),
+                },
+                "model" if value.eq_ignore_ascii_case("default") => cron::JobEdit::Model(None),
+                "model" => cron::JobEdit::Model(Some(value.to_string())),
+                "ingest" if value.eq_ignore_ascii_case("off") => cron::JobEdit::Ingest(None),
+                "ingest" => match cron::parse_ingest_dir(value) {
+                    Ok(dir) => cron::JobEdit::Ingest(Some(dir)),
+                    Err(e) => return Ok(CommandResult::Response(i18n::tr_args(language, "Error: {}", &[&e]))),
+                },
+                "priority" => match value.parse::<cron::Priority>() {
+                    Ok(priority) => cron::JobEdit::Priority(priority),
+                    Err(e) => return Ok(CommandResult::Response(i18n::tr_args(language, "Error: {}", &[&e]))),
+                },
+                "overlap" => match value.parse::<cron::OverlapPolicy>() {
+                    Ok(overlap) => cron::JobEdit::Overlap(overlap),
+                    Err(e) => return Ok(CommandResult::Response(i18n::tr_args(language, "Error: {}", &[&e]))),
+                },
+                "max-runtime" if value.eq_ignore_ascii_case("off") => {
+                    cron::JobEdit::MaxRuntime(None)
+                }
+                "max-runtime" => match cron::parse_duration(value) {
+                    Ok(ms) => cron::JobEdit::MaxRuntime(Some(ms)),
+                    Err(e) => return Ok(CommandResult::Response(i18n::tr_args(language, "Error: {}", &[&e]))),
+                },
+                "notify" if value.eq_ignore_ascii_case("off") => cron::JobEdit::Notify(None),
+                "notify" => match value.parse::<cron::NotifyWhen>() {
+                    Ok(when) => cron::JobEdit::Notify(Some(when)),
+                    Err(e) => return Ok(CommandResult::Response(i18n::tr_args(language, "Error: {}", &[&e]))),
+                },
+                "backoff" => match cron::parse_duration(value) {
+                    Ok(ms) => cron::JobEdit::Backoff(ms),
+                    Err(e) => return Ok(CommandResult::Response(i18n::tr_args(language, "Error: {}", &[&e]))),
+                },
+                _ => cron::JobEdit::Name(value.to_string()),
+            };
+
+            let mut store = CronStore::load()?;
+            let job_id = find_job_id(&store, channel, user_id, id)?;
+
+            match store.edit(&job_id, channel, user_id, edit) {
+                Ok(Some(job)) => {
+                    let next = job
+                        .state
+                        .next_run_at
+                        .map(format_timestamp)
+                        .unwrap_or_else(|| "—".to_string());
+                    let pause = match job.retry.pause_after {
+                        0 => "never paused".to_string(),
+                        n => format!("paused after {} failures in a row", n),
+                    };
+                    let notify = if job.notify {
+                        job.notify_when.as_str()
+                    } else {
+                        "off"
+                    };
+                    Ok(CommandResult::Response(format!(
+                        "Updated job [{}] \"{}\"\nSchedule: {}\nNext run: {}\nPrompt: {}\n\
+                         Notify: {}\nOn failure: {} retries, {}s apart at first, {}\n\
+                         If still running when due: {}\nMax runtime: {}\nPriority: {}\n\
+                         Session: {}\nModel: {}\nSaved to memories: {}",
+                        job.short_id(),
+                        job.name,
+                        job.schedule.description(),
+                        next,
+                        job.prompt,
+                        notify,
+                        job.retry.max_retries,
+                        job.retry.backoff_ms / 1000,
+                        pause,
+                        job.overlap.as_str(),
+                        job.max_runtime_ms
+                            .map(|ms| format!("{}s", ms / 1000))
+                            .unwrap_or_else(|| "the query timeout".to_string()),
+                        job.priority.as_str(),
+                        job.session.as_str(),
+                        job.model_description(),
+                        job.ingest
+                            .as_deref()
+                            .map(|dir| format!("memories/{}/", dir))
+                            .unwrap_or_else(|| "no".to_string())
+                    )))
+                }
+                Ok(None) => Ok(CommandResult::Response(i18n::tr_args(language, "Job not found: {}", &[&id]))),
+                Err(e) => Ok(CommandResult::Response(i18n::tr_args(language, "Error: {}", &[&e]))),
+            }
+        }
+
+        "copy" | "duplicate" => {
+            let id = rest.trim();
+            if id.is_empty() {
+                return Ok(CommandResult::Response(
+                    "Usage: /cron copy <name or job-id>".to_string(),
+                ));
+            }
+
+            let mut store = CronStore::load()?;
+            let job_id = find_job_id(&store, channel, user_id, id)?;
+            let Some(job) = store.get(&job_id, channel, user_id) else {
+                return Ok(CommandResult::Response(i18n::tr_args(language, "Job not found: {}", &[&id])));
+            };
+
+            let name = store.unique_name(channel, user_id, &job.name);
+            let copy = job.duplicate(name, channel, user_id);
+            let response = created_response(&copy);
+            store.add(copy)?;
+            Ok(CommandResult::Response(response))
+        }
+
+        "template" | "templates" => process_template_command(channel, user_id, rest, language),
+
+        "log" | "history" => {
+            let id = rest.trim();
+            if id.is_empty() {
+                return Ok(CommandResult::Response(
+                    "Usage: /cron log <name or job-id>".to_string(),
+                ));
+            }
+
+            let store = CronStore::load()?;
+            let job_id = find_job_id(&store, channel, user_id, id)?;
+            match store.get(&job_id, channel, user_id) {
+                Some(job) => Ok(CommandResult::Response(cron::format_runs(job))),
+                None => Ok(CommandResult::Response(i18n::tr_args(language, "Job not found: {}", &[&id]))),
+            }
+        }
+
+        "pause" | "disable" => {
+            let id = rest.trim();
+            if id.is_empty() {
+                return Ok(CommandResult::Response(
+                    "Usage: /cron pause <name or job-id>".to_string(),
+                ));
+            }
+
+            let mut store = CronStore::load()?;
+            let job_id = find_job_id(&store, channel, user_id, id)?;
+
+            let result = store.update(|store| {
+                let Some(job) = store
+                    .get_mut(&job_id)
+                    .filter(|job| job.channel == channel && job.user_id == user_id)
+                else {
+                    return Ok(None);
+                };
+                job.enabled = false;
+                job.state.next_run_at = None;
+                Ok(Some((job.short_id().to_string(), job.name.clone())))
+            })?;
+
+            if let Some((short_id, name)) = result {
+                Ok(CommandResult::Response(format!(
+                    "Paused job [{}] \"{}\"",
+                    short_id, name
+                )))
+            } else {
+                Ok(CommandResult::Response(i18n::tr_args(language, "Job not found: {}", &[&id])))
+            }
+        }
+
+        "resume" | "enable" => {
+            let id = rest.trim();
+            if id.is_empty() {
+                return Ok(CommandResult::Response(
+                    "Usage: /cron resume <name or job-id>".to_string(),
+                ));
+            }
+
+            let mut store = CronStore::load()?;
+            let job_id = find_job_id(&store, channel, user_id, id)?;
+
+            let result = store.update(|store| {
+                let Some(job) = store
+                    .get_mut(&job_id)
+                    .filter(|job| job.channel == channel && job.user_id == user_id)
+                else {
+                    return Ok(None);
+                };
+                job.enabled = true;
+                // A job paused for failing gets a fresh run of chances
+                job.state.failure_count = 0;
+                job.update_next_run(cron::store::now_millis());
+                let next = job
+                    .state
+                    .next_run_at
+                    .map(format_timestamp)
+                    .unwrap_or_else(|| "soon".to_string());
+                Ok(Some((job.short_id().to_string(), job.name.clone(), next)))
+            })?;
+
+            if let Some((short_id, name, next)) = result {
+                Ok(CommandResult::Response(format!(
+                    "Resumed job [{}] \"{}\"\nNext run: {}",
+                    short_id, name, next
+                )))
+            } else {
+                Ok(CommandResult::Response(i18n::tr_args(language, "Job not found: {}", &[&id])))
+            }
+        }
+
+        _ => Ok(CommandResult::Response(
+            "Cron job commands:\n\n\
+             /cron list - List your scheduled jobs\n\
+             /cron add [--name <name>] <schedule> <prompt> - Create a new job\n\
+             /cron add model=<model> <schedule> <prompt> - Run it with another model (or backend=)\n\
+             /cron add ingest=memories/news/ <schedule> <prompt> - Save each result as a dated memory\n\
+             /cron add --dry-run <schedule> <prompt> - Check a job without creating it\n\
+             /cron preview <schedule> - Show when a schedule would run\n\
+             /cron add from <template> [<schedule>] - Create a job from a template\n\
+             /cron copy <job> - Create a job like another one\n\
+             /cron template save <template> <job> - Let everyone create jobs like this one\n\
+             /cron template list|delete - See or delete templates\n\
+             /cron remove <job> - Delete a job\n\
+             /cron edit <job> schedule|prompt|name <new> - Change a job\n\
+             /cron edit <job> retries|backoff|pause-after <new> - Change what happens when it fails\n\
+             /cron edit <job> notify always|failure|change|noteworthy|off - Choose which results you get\n\
+             /cron edit <job> overlap|max-runtime <new> - Limit long runs\n\
+             /cron edit <job> priority low|normal|high - Who goes first when jobs pile up\n\
+             /cron edit <job> session continue - Let each run build on the previous ones\n\
+             /cron edit <job> backend|model <new, or default> - Run it elsewhere\n\
+             /cron run <job> - Run immediately (for testing)\n\
+             /cron log <job> - Show its recent runs\n\
+             /cron pause <job> - Pause a job\n\
+             /cron resume <job> - Resume a paused job\n\n\
+             Jobs can be referred to by name or ID prefix.\n\n\
+             Schedule formats:\n\
+             • every 10s / every 5m / every 1h - Recurring interval\n\
+             • at 2024-01-28 14:00 - One-time execution\n\
+             • 0 9 * * * - Cron expression (9 AM daily)\n\
+             • on /receipt/ - Whenever your message matches\n\
+             • ics <url> 30m before - Around each event in a calendar feed\n\n\
+             Examples:\n\
+             /cron add every 1h Check my inbox\n\
+             /cron add every 10s Say hello\n\
+             /cron add --name morning-brief 0 9 * * * Good morning!"
+                .to_string(),
+        )),
+    }
+}
+
+/// Split "<job> <field> <value>" from /cron edit. The job comes
+/// first and may be a name with spaces, so the first field word after it splits.
+fn split_job_edit(args: &str) -> Option<(&str, &str, &str)> {
+    let args = args.trim();
+    let mut offset = 0;
+    for word in args.split(' ') {
+        if offset > 0
+            && matches!(
+                word,
+                "schedule"
+                    | "prompt"
+                    | "name"
+                    | "retries"
+                    | "backoff"
+                    | "pause-after"
+                    | "notify"
+                    | "overlap"
+                    | "max-runtime"
+                    | "priority"
+                    | "session"
+                    | "backend"
+                    | "model"
+                    | "ingest"
+            )
+        {
+            let id = args[..offset].trim();
+            let value = args[offset + word.len()..].trim();
+            return (!value.is_empty()).then_some((id, word, value));
+        }
+        offset += word.len() + 1;
+    }
+    None
+}
+
+/// Options given before the schedule of a /cron add command
+#[derive(Debug, Default, PartialEq)]
+struct AddOptions<'a> {
+    name: Option<&'a str>,
+    dry_run: bool,
+    backend: Option<&'a str>,
+    model: Option<&'a str>,
+    ingest: Option<&'a str>,
+}
+
+/// Split leading `--name <name>`, `--dry-run`, `backend=<backend>`,
+/// `model=<model>` and `ingest=<dir>` options off a /cron add command, in any order
+fn split_add_options(args: &str) -> (AddOptions<'_>, &str) {
+    let mut options = AddOptions::default();
+    let mut args = args.trim();
+    loop {
+        let (word, rest) = args.split_once(' ').unwrap_or((args, ""));
+        let rest = rest.trim_start();
+        if word == "--name" && !rest.is_empty() {
+            let (name, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+            options.name = Some(name);
+            args = rest.trim_start();
+        } else if word == "--dry-run" {
+            options.dry_run = true;
+            args = rest;
+        } else if let Some(backend) = word.strip_prefix("backend=")
+            && !backend.is_empty()
+        {
+            options.backend = Some(backend);
+            args = rest;
+        } else if let Some(model) = word.strip_prefix("model=")
+            && !model.is_empty()
+        {
+            options.model = Some(model);
+            args = rest;
+        } else if let Some(dir) = word.strip_prefix("ingest=")
+            && !dir.is_empty()
+        {
+            options.ingest = Some(dir);
+            args = rest;
+        } else {
+            return (options, args);
+        }
+    }
+}
+
+/// Period covered by /recap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecapPeriod {
+    /// Since local midnight
+    Today,
+    /// The last seven days
+    Week,
+}
+
+impl RecapPeriod {
+    /// Start of the period as a Unix timestamp; "today" starts at midnight in
+    /// `zone` if the user set one, otherwise at server midnight
+    fn start(&self, now: chrono::DateTime<chrono::Utc>, zone: Option<chrono_tz::Tz>) -> u64 {
+        let start = match self {
+            RecapPeriod::Today => match zone {
+                Some(zone) => midnight(now, &zone),
+                None => midnight(now, &Local),
+            },
+            RecapPeriod::Week => now - chrono::Duration::days(7),
+        };
+        start.timestamp().max(0) as u64
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            RecapPeriod::Today => "today",
+            RecapPeriod::Week => "the past week",
+        }
+    }
+}
+
+/// The last midnight in `zone` before `now`
+fn midnight<Z: chrono::TimeZone>(
+    now: chrono::DateTime<chrono::Utc>,
+    zone: &Z,
+) -> chrono::DateTime<chrono::Utc> {
+    now.with_timezone(zone)
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(zone.clone()).earliest())
+        .map(|midnight| midnight.to_utc())
+        .unwrap_or(now)
+}
+
+/// Parse `/recap [today|week]`. Returns `Some(None)` for an unknown period.
+fn parse_recap_command(text: &str) -> Option<Option<RecapPeriod>> {
+    let rest = text.strip_prefix("/recap")?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    Some(match rest.trim().to_lowercase().as_str() {
+        "" | "today" => Some(RecapPeriod::Today),
+        "week" => Some(RecapPeriod::Week),
+        _ => None,
+    })
+}
+
+/// Parse `/export [memories]`. Returns `Some(None)` for an unknown argument.
+/// Split `/skill <subcommand> <args>`; None for other messages, such as
+/// /skills
+fn parse_skill_command(text: &str) -> Option<(&str, &str)> {
+    let rest = text.strip_prefix("/skill")?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let rest = rest.trim();
+    let (subcommand, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    Some((subcommand, args.trim()))
+}
+
+fn parse_export_command(text: &str) -> Option<Option<ExportKind>> {
+    let rest = text.strip_prefix("/export")?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    Some(match rest.trim().to_lowercase().as_str() {
+        "" | "conversation" | "chat" => Some(ExportKind::Conversation),
+        "memories" | "memory" => Some(ExportKind::Memories),
+        _ => None,
+    })
+}
+
+/// Write an export off the async runtime; typesetting takes a moment
+pub async fn build_export(channel: &str, user_id: &str, kind: ExportKind) -> Result<PathBuf> {
+    let (channel, user_id) = (channel.to_string(), user_id.to_string());
+    tokio::task::spawn_blocking(move || export::export(&channel, &user_id, kind)).await?
+}
+
+/// Messages shown by /history without a count, and the most it will show
+const HISTORY_DEFAULT_COUNT: usize = 10;
+const HISTORY_MAX_COUNT: usize = 50;
+
+/// Characters of each message shown by /history
+const HISTORY_PREVIEW_CHARS: usize = 300;
+
+/// Parse `/history [count]`. Returns `Some(None)` for an invalid count.
+fn parse_history_command(text: &str) -> Option<Option<usize>> {
+    let rest = text.strip_prefix("/history")?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let rest = rest.trim();
+    if rest.is_empty() {
+        return Some(Some(HISTORY_DEFAULT_COUNT));
+    }
+    Some(
+        rest.parse()
+            .ok()
+            .filter(|count| (1..=HISTORY_MAX_COUNT).contains(count)),
+    )
+}
+
+/// Split a leading "/think" or "/think hard" off a message, returning the
+/// effort it asks for and the rest of the message
+fn parse_think_prefix(text: &str) -> (Option<Effort>, &str) {
+    fn word<'a>(text: &'a str, word: &str) -> Option<&'a str> {
+        let rest = text.strip_prefix(word)?;
+        (rest.is_empty() || rest.starts_with(char::is_whitespace)).then(|| rest.trim_start())
+    }
+
+    match word(text, "/think") {
+        Some(rest) => match word(rest, "hard") {
+            Some(rest) => (Some(Effort::High), rest),
+            None => (Some(Effort::Medium), rest),
+        },
+        None => (None, text),
+    }
+}
+
+/// Parse "/persona [show]" or "/persona set <text>": None if not the
+/// command, Some(None) to show the persona, Some(Some(text)) to replace it
+fn parse_persona_command(text: &str) -> Option<Option<&str>> {
+    let rest = text.strip_prefix("/persona")?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    match rest.trim_start().strip_prefix("set") {
+        Some(content) if content.is_empty() || content.starts_with(char::is_whitespace) => {
+            Some(Some(content.trim()))
+        }
+        _ => Some(None),
+    }
+}
+
+/// The persona a channel's users get, for /persona
+fn show_persona(channel: &str) -> String {
+    let name = onboarding::persona_path(Some(channel))
+        .ok()
+        .and_then(|path| path.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "PERSONA.md".to_string());
+    match onboarding::load_persona(Some(channel)) {
+        Ok(Some(content)) if !content.trim().is_empty() => {
+            format!("{}:\n\n{}", name, content.trim())
+        }
+        Ok(_) => format!("There's no persona yet ({} is empty or missing).", name),
+        Err(e) => format!("Couldn't read the persona: {}", e),
+    }
+}
+
+/// Parse "/verbose [on|off]": None if not the command, Some(None) to show the setting
+fn parse_verbose_command(text: &str) -> Option<Option<bool>> {
+    let rest = text.strip_prefix("/verbose")?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    match rest.trim() {
+        "on" => Some(Some(true)),
+        "off" => Some(Some(false)),
+        _ => Some(None),
+    }
+}
+
+/// The /status reply: uptime, backend and model, and the user's session,
+/// memories and pending cron jobs
+fn status_response(store: &PairingStore, channel: &str, user_id: &str) -> Result<String> {
+    let config = Config::load().unwrap_or_default();
+    let mut lines = vec![
+        format!(
+            "Cica {}, up {}",
+            env!("CARGO_PKG_VERSION"),
+            format_age(crate::monitor::uptime().as_secs())
+        ),
+        format!(
+            "Backend: {} ({})",
+            config.backend.display_name(),
+            config.model().unwrap_or("default model")
+        ),
+    ];
+
+    let now = chrono::Utc::now().timestamp().max(0) as u64;
+    lines.push(
+        match (
+            store
+                .sessions
+                .contains_key(&format!("{}:{}", channel, user_id)),
+            store.session_started(channel, user_id),
+        ) {
+            (false, _) => "Session: none yet, your next message starts one".to_string(),
+            (true, Some(started)) => format!(
+                "Session: started {} ago (/new starts over)",
+                format_age(now.saturating_sub(started))
+            ),
+            (true, None) => "Session: ongoing (/new starts over)".to_string(),
+        },
+    );
+
+    lines.push(format!(
+        "Memories: {}",
+        crate::memory::count_user_memories(channel, user_id)?
+    ));
+
+    let cron_store = CronStore::load()?;
+    let mut pending: Vec<&cron::CronJob> = cron_store
+        .list_for_user(channel, user_id)
+        .into_iter()
+        .filter(|job| job.enabled && job.state.next_run_at.is_some())
+        .collect();
+    pending.sort_by_key(|job| job.state.next_run_at);
+    lines.push(match pending.first() {
+        None => "Cron jobs: none pending".to_string(),
+        Some(next) => format!(
+            "Cron jobs: {} pending, next \"{}\" at {}{}",
+            pending.len(),
+            next.name,
+            next.state
+                .next_run_at
+                .map(format_timestamp)
+                .unwrap_or_default(),
+            if cron::store::paused_since()?.is_some() {
+                " (all paused by the owner)"
+            } else {
+                ""
+            }
+        ),
+    });
+
+    if let Some(quiet_hours) = store.quiet_hours(channel, user_id) {
+        lines.push(format!("Quiet hours: {} (/dnd)", quiet_hours));
+    }
+
+    Ok(lines.join("\n"))
+}
+
+/// Format a number of seconds in its two largest units, e.g. "2d 3h" or "5m"
+fn format_age(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86_400, secs % 86_400 / 3_600, secs % 3_600 / 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        "less than a minute".to_string()
+    }
+}
+
+/// Parse "/help [command]" (or "/commands"): None if not the command
+fn parse_help_command(text: &str) -> Option<&str> {
+    let rest = text
+        .strip_prefix("/help")
+        .or_else(|| text.strip_prefix("/commands"))?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.trim())
+}
+
+/// Parse "/language [<code>|auto]": None if not the command, Some("") to show the setting
+fn parse_language_command(text: &str) -> Option<&str> {
+    let rest = text.strip_prefix("/language")?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.trim())
+}
+
+/// Parse `/dnd [<from>-<to>|off]`, returning the trimmed argument
+fn parse_dnd_command(text: &str) -> Option<&str> {
+    let rest = text.strip_prefix("/dnd")?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.trim())
+}
+
+/// Parse `/good [note]`, `/bad [what to change]`, or a message that's just
+/// a 👍 or 👎
+fn parse_feedback_command(text: &str) -> Option<(Rating, Option<&str>)> {
+    let is_thumb = |c: char| matches!(c, '👍' | '👎' | '\u{1F3FB}'..='\u{1F3FF}');
+    if !text.is_empty() && text.chars().all(is_thumb) {
+        return Some((Rating::from_reaction(text)?, None));
+    }
+
+    let (rating, rest) = if let Some(rest) = text.strip_prefix("/good") {
+        (Rating::Good, rest)
+    } else {
+        (Rating::Bad, text.strip_prefix("/bad")?)
+    };
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let note = rest.trim();
+    Some((rating, (!note.is_empty()).then_some(note)))
+}
+
+/// Rate the user's last reply, keeping what to change on a bad one
+fn process_feedback(
+    channel: &str,
+    user_id: &str,
+    rating: Rating,
+    note: Option<&str>,
+    language: Option<&str>,
+) -> Result<String> {
+    if !feedback::record(channel, user_id, rating, note)? {
+        return Ok(i18n::tr(language, "There's no reply of mine to rate yet.").to_string());
+    }
+    let response = match (rating, note) {
+        (Rating::Good, _) => "Thanks for the feedback!",
+        (Rating::Bad, None) => FEEDBACK_ASK_WHAT_TO_CHANGE,
+        (Rating::Bad, Some(_)) => "Got it, I'll keep that in mind from now on.",
+    };
+    Ok(i18n::tr(language, response).to_string())
+}
+
+/// Reply to a thumbs-down without a correction
+const FEEDBACK_ASK_WHAT_TO_CHANGE: &str = "Thanks for telling me. What should I do differently? \
+                                           Tell me with /bad <what to change> and I'll keep it in mind.";
+
+/// Record a 👍/👎 reaction to one of Cica's messages as feedback on the
+/// user's last reply. Returns what to tell the user: a thumbs-down asks
+/// what to change.
+pub fn process_reaction(channel: &str, user_id: &str, reaction: &str) -> Option<String> {
+    let rating = Rating::from_reaction(reaction)?;
+    let store = PairingStore::load().ok()?;
+    if !store.is_approved(channel, user_id) {
+        return None;
+    }
+    match feedback::record(channel, user_id, rating, None) {
+        Ok(true) => {}
+        Ok(false) => return None,
+        Err(e) => {
+            warn!("Failed to record feedback: {}", e);
+            return None;
+        }
+    }
+    let language = store.language(channel, user_id);
+    (rating == Rating::Bad).then(|| i18n::tr(language, FEEDBACK_ASK_WHAT_TO_CHANGE).to_string())
+}
+
+/// Parse `/digest [<time>|now|off]`, returning the trimmed argument
+fn parse_digest_command(text: &str) -> Option<&str> {
+    let rest = text.strip_prefix("/digest")?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.trim())
+}
+
+/// Show, set or turn off (`off`) when a user's daily digest goes out
+fn process_digest_command(
+    store: &mut PairingStore,
+    channel: &str,
+    user_id: &str,
+    setting: &str,
+) -> Result<String> {
+    let language = store.language(channel, user_id).map(str::to_string);
+    let language = language.as_deref();
+
+    if setting.is_empty() {
+        return Ok(match store.daily_digest(channel, user_id) {
+            Some(at) => i18n::tr_args(
+                language,
+                "Your daily digest goes out at {}. /digest off turns it off.",
+                &[&at.format("%H:%M")],
+            ),
+            None => i18n::tr(
+                language,
+                "No daily digest yet. Turn it on with e.g. /digest 08:00.",
+            )
+            .to_string(),
+        });
+    }
+
+    if setting == "off" {
+        store.set_daily_digest(channel, user_id, None)?;
+        return Ok(i18n::tr(language, "Daily digest turned off.").to_string());
+    }
+
+    let at = match quiet::parse_time(setting) {
+        Ok(at) => at,
+        Err(e) => {
+            return Ok(i18n::tr_args(
+                language,
+                "Couldn't set the digest: {}. Try e.g. /digest 08:00, /digest now or /digest off.",
+                &[&e],
+            ));
+        }
+    };
+    store.set_daily_digest(channel, user_id, Some(at))?;
+    info!("{}:{} set their daily digest to {}", channel, user_id, at);
+    Ok(i18n::tr_args(
+        language,
+        "I'll send your daily digest at {}: job results, open questions and new memories from the last 24 hours.",
+        &[&at.format("%H:%M")],
+    ))
+}
+
+/// Build a user's daily digest on request (/digest now)
+pub async fn daily_digest_now(channel: &str, user_id: &str) -> String {
+    let language = i18n::user_language(channel, user_id);
+    match digest::build(channel, user_id).await {
+        Ok(Some(text)) => text,
+        Ok(None) => i18n::tr(
+            language.as_deref(),
+            "Nothing happened in the last 24 hours, so there's no digest.",
+        )
+        .to_string(),
+        Err(e) => i18n::tr_args(language.as_deref(), "Error: {}", &[&e]),
+    }
+}
+
+/// Show, set or turn off (`off`) a user's quiet hours
+fn process_dnd_command(
+    store: &mut PairingStore,
+    channel: &str,
+    user_id: &str,
+    setting: &str,
+) -> Result<String> {
+    let language = store.language(channel, user_id).map(str::to_string);
+    let language = language.as_deref();
+
+    if setting.is_empty() {
+        return Ok(match store.quiet_hours(channel, user_id) {
+            Some(quiet_hours) => i18n::tr_args(
+                language,
+                "Quiet hours: {}. I hold cron results and notifications until they end. /dnd off turns them off.",
+                &[&quiet_hours],
+            ),
+            None => i18n::tr(
+                language,
+                "No quiet hours set. Set them with e.g. /dnd 22:00-07:00.",
+            )
+            .to_string(),
+        });
+    }
+
+    if setting == "off" {
+        store.set_quiet_hours(channel, user_id, None)?;
+        return Ok(i18n::tr(
+            language,
+            "Quiet hours are off. Anything I held comes shortly.",
+        )
+        .to_string());
+    }
+
+    let quiet_hours = match QuietHours::parse(setting) {
+        Ok(quiet_hours) => quiet_hours,
+        Err(e) => {
+            return Ok(i18n::tr_args(
+                language,
+                "Couldn't set quiet hours: {}. Try e.g. /dnd 22:00-07:00, or /dnd off.",
+                &[&e],
+            ));
+        }
+    };
+    store.set_quiet_hours(channel, user_id, Some(quiet_hours))?;
+    info!("{}:{} set quiet hours to {}", channel, user_id, quiet_hours);
+    Ok(i18n::tr_args(
+        language,
+        "Quiet hours set to {}. I'll hold cron results and notifications until they end, then send them together.",
+        &[&quiet_hours],
+    ))
+}
+
+/// Show, set or clear (`auto`) a user's language, replying in the new one
+fn process_language_command(
+    store: &mut PairingStore,
+    channel: &str,
+    user_id: &str,
+    setting: &str,
+) -> Result<String> {
+    if setting.is_empty() {
+        let language = store.language(channel, user_id);
+        return Ok(match language.and_then(i18n::name) {
+            Some(name) => i18n::tr_args(
+                language,
+                "Language: {}. Change it with /language <code>, or /language auto to detect it again.",
+                &[&name],
+            ),
+            None => i18n::tr(
+                language,
+                "I'll pick up your language from your next messages.",
+            )
+            .to_string(),
+        });
+    }
+
+    if setting == "auto" {
+        let previous = store.language(channel, user_id).map(str::to_string);
+        store.set_language(channel, user_id, None)?;
+        return Ok(i18n::tr(
+            previous.as_deref(),
+            "I'll pick up your language from your next messages.",
+        )
+        .to_string());
+    }
+
+    let Some(code) = i18n::normalize(setting) else {
+        return Ok(i18n::tr_args(
+            store.language(channel, user_id),
+            "Unknown language: {}. Use a code like en, de or hu.",
+            &[&setting],
+        ));
+    };
+    store.set_language(channel, user_id, Some(code))?;
+    info!("{}:{} set language to {}", channel, user_id, code);
+    Ok(i18n::tr_args(
+        Some(code),
+        "Language set to {}.",
+        &[&i18n::name(code).unwrap_or(code)],
+    ))
+}
+
+/// Summarize the tools used for a reply, e.g. "Tools: searched the web, read 2 files"
+fn tool_footer(tools_used: &[String]) -> Option<String> {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for tool in tools_used {
+        let kind = match tool.as_str() {
+            "Read" | "read_file" => "read",
+            "Edit" | "MultiEdit" | "Write" | "NotebookEdit" | "write_file" => "edit",
+            "Bash" => "command",
+            "WebSearch" => "search",
+            "WebFetch" => "fetch",
+            "Grep" | "Glob" | "LS" | "list_files" => "find",
+            other => other,
+        };
+        match counts.iter_mut().find(|(k, _)| *k == kind) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((kind, 1)),
+        }
+    }
+    if counts.is_empty() {
+        return None;
+    }
+
+    let plural = |n: usize, what: &str| {
+        if n == 1 {
+            format!("1 {}", what)
+        } else {
+            format!("{} {}s", n, what)
+        }
+    };
+    let parts: Vec<String> = counts
+        .into_iter()
+        .map(|(kind, n)| match kind {
+            "read" => format!("read {}", plural(n, "file")),
+            "edit" => format!("edited {}", plural(n, "file")),
+            "command" => format!("ran {}", plural(n, "command")),
+            "search" => "searched the web".to_string(),
+            "fetch" => format!("fetched {}", plural(n, "page")),
+            "find" => "looked through files".to_string(),
+            other if n == 1 => other.to_string(),
+            other => format!("{} ({}x)", other, n),
+        })
+        .collect();
+
+    Some(format!("Tools: {}", parts.join(", ")))
+}
+
+/// Format transcript messages for /history, shortening long ones
+fn format_history(messages: &[TranscriptMessage]) -> String {
+    if messages.is_empty() {
+        return "No messages yet.".to_string();
+    }
+
+    let mut response = String::from("Our last messages:\n");
+    for message in messages {
+        let speaker = match message.speaker {
+            Speaker::User => "You",
+            Speaker::Assistant => "Me",
+        };
+        let mut text: String = message.text.chars().take(HISTORY_PREVIEW_CHARS).collect();
+        if text.len() < message.text.len() {
+            text.push_str("...");
+        }
+        response.push_str(&format!(
+            "\n[{}] {}: {}\n",
+            format_timestamp(message.created_at * 1000),
+            speaker,
+            text
+        ));
+    }
+    response
+}
+
+/// Longest transcript excerpt sent for a recap; older messages are dropped first
+const RECAP_MAX_CHARS: usize = 30_000;
+
+/// Format transcript messages for a summarization prompt, keeping the newest
+/// ones that fit in `max_chars`
+pub(crate) fn format_transcript(messages: &[TranscriptMessage], max_chars: usize) -> String {
+    let mut lines = Vec::new();
+    let mut chars = 0;
+    for message in messages.iter().rev() {
+        let speaker = match message.speaker {
+            Speaker::User => "User",
+            Speaker::Assistant => "Assistant",
+        };
+        let line = format!(
+            "[{}] {}: {}",
+            format_timestamp(message.created_at * 1000),
+            speaker,
+            message.text
+        );
+        chars += line.len();
+        if chars > max_chars && !lines.is_empty() {
+            break;
+        }
+        lines.push(line);
+    }
+    lines.reverse();
+    lines.join("\n")
+}
+
+/// Build the summarization prompt for a recap from the period's messages and
+/// the names and schedules of cron jobs created in it
+fn build_recap_prompt(
+    period: RecapPeriod,
+    messages: &[TranscriptMessage],
+    new_jobs: &[String],
+) -> String {
+    let transcript = format_transcript(messages, RECAP_MAX_CHARS);
+
+    let mut prompt = format!(
+        "Write a short recap of my conversations with you {} so I can pick up \
+         where we left off. Cover the main topics, anything we decided or you did \
+         for me, and open threads or follow-ups. Use a few bullet points and no \
+         preamble.\n",
+        period.label()
+    );
+    if transcript.is_empty() {
+        prompt.push_str("\nWe didn't chat in this period.\n");
+    } else {
+        prompt.push_str(&format!("\nTranscript:\n{}\n", transcript));
+    }
+    if !new_jobs.is_empty() {
+        prompt.push_str(&format!(
+            "\nScheduled jobs created in this period:\n{}\n",
+            new_jobs.join("\n")
+        ));
+    }
+
+    prompt
+}
+
+/// Summarize a user's conversations and new cron jobs over a period
+pub async fn recap(channel: &str, user_id: &str, period: RecapPeriod) -> Result<String> {
+    let start = period.start(chrono::Utc::now(), user_timezone(channel, user_id));
+    let messages = transcript::since(channel, user_id, start)?;
+
+    let store = CronStore::load()?;
+    let mut new_jobs: Vec<_> = store
+        .list_for_user(channel, user_id)
+        .into_iter()
+        .filter(|job| job.created_at >= start * 1000)
+        .collect();
+    new_jobs.sort_by_key(|job| job.created_at);
+    let new_jobs: Vec<String> = new_jobs
+        .into_iter()
+        .map(|job| format!("- {} ({})", job.name, job.schedule.description()))
+        .collect();
+
+    if messages.is_empty() && new_jobs.is_empty() {
+        return Ok(format!("Nothing to recap for {}.", period.label()));
+    }
+
+    let (summary, _session_id) = backends::query_with_options(
+        &build_recap_prompt(period, &messages, &new_jobs),
+        QueryOptions {
+            skip_permissions: skip_permissions(channel),
+            read_only: true,
+            one_shot: true,
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    Ok(summary)
+}
+
+/// Find a job ID by name, full ID or prefix match
+fn find_job_id(
+    store: &CronStore,
+    channel: &str,
+    user_id: &str,
+    name_or_id: &str,
+) -> Result<String> {
+    let id = name_or_id.trim();
+
+    // Names take precedence over IDs
+    if let Some(job) = store.find_by_name(channel, user_id, id) {
+        return Ok(job.id.clone());
+    }
+
+    // Then try exact ID match
+    if store.get(id, channel, user_id).is_some() {
+        return Ok(id.to_string());
+    }
+
+    // Try prefix match
+    let matches: Vec<_> = store
+        .list_for_user(channel, user_id)
+        .into_iter()
+        .filter(|j| j.id.starts_with(id))
+        .collect();
+
+    match matches.len() {
+        0 => anyhow::bail!("Job not found: {}", id),
+        1 => Ok(matches[0].id.clone()),
+        _ => anyhow::bail!(
+            "Ambiguous job ID '{}'. Matches: {}",
+            id,
+            matches
+                .iter()
+                .map(|j| j.short_id())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Whether queries for a channel's users run tools without asking
+pub(crate) fn skip_permissions(channel: &str) -> bool {
+    Config::load().unwrap_or_default().skip_permissions(channel)
+}
+
+/// Query AI backend with automatic session recovery.
+///
+/// If the session has expired, clears it and retries with a fresh conversation.
+/// Returns the response text and the new session ID.
+#[allow(clippy::too_many_arguments)]
+pub async fn query_ai_with_session(
+    store: &mut PairingStore,
+    channel: &str,
+    user_id: &str,
+    text: &str,
+    context_prompt: String,
+    permissions: RolePermissions,
+    on_queued: Option<backends::OnQueued>,
+    on_tool_use: Option<backends::OnToolUse>,
+    effort: Option<Effort>,
+) -> Result<(String, String)> {
+    let session_key = format!("{}:{}", channel, user_id);
+    let mut existing_session = store.sessions.get(&session_key).cloned();
+    let backend = Config::load().map(|c| c.backend).unwrap_or_default();
+
+    // Sessions can't move between backends, so the new one gets a summary instead
+    let mut context_prompt = context_prompt;
+    if let Some(previous) = store.session_backends.get(&session_key)
+        && existing_session.is_some()
+        && *previous != backend
+    {
+        info!(
+            "Backend for {} changed from {} to {}, handing over the conversation",
+            session_key,
+            previous.as_str(),
+            backend.as_str()
+        );
+        match handover_summary(channel, user_id).await {
+            Ok(Some(summary)) => context_prompt = format!("{}\n\n{}", context_prompt, summary),
+            Ok(None) => {}
+            Err(e) => warn!("Failed to summarize conversation for handover: {}", e),
+        }
+        existing_session = None;
+    }
+
+    // Resumed sessions already have most of the context from earlier messages
+    let context = match existing_session.as_deref() {
+        Some(session_id) => ContextUpdate::resumed_session(&context_prompt, session_id)
+            .unwrap_or_else(|e| {
+                warn!("Failed to load sent context, sending all of it: {}", e);
+                ContextUpdate::new_session(&context_prompt)
+            }),
+        None => ContextUpdate::new_session(&context_prompt),
+    };
+    let options = backends::QueryOptions {
+        system_prompt: Some(context.preamble.clone()),
+        resume_session: existing_session,
+        skip_permissions: skip_permissions(channel),
+        read_only: !permissions.write_workspace,
+        read_only_dirs: read_only_dirs(permissions),
+        on_queued,
+        on_tool_use: on_tool_use.clone(),
+        effort,
+        ..Default::default()
+    };
+
+    let (response, session_id, context) =
+        match backends::query_with_options(&context.message(text), options).await {
+            Ok((response, session_id)) => (response, session_id, context),
+            Err(e) => {
+                let error_msg = e.to_string();
+                // If session not found, clear it and retry without resuming
+                if error_msg.contains("No conversation found with session ID")
+                    || error_msg.contains("session")
+                {
+                    warn!("Session expired, starting fresh conversation");
+                    store.reset_session(channel, user_id)?;
+
+                    let context = ContextUpdate::new_session(&context_prompt);
+                    let retry_options = backends::QueryOptions {
+                        system_prompt: Some(context.preamble.clone()),
+                        resume_session: None,
+                        skip_permissions: skip_permissions(channel),
+                        read_only: !permissions.write_workspace,
+                        read_only_dirs: read_only_dirs(permissions),
+                        on_tool_use,
+                        effort,
+                        ..Default::default()
+                    };
+
+                    match backends::query_with_options(&context.message(text), retry_options).await
+                    {
+                        Ok((response, session_id)) => (response, session_id, context),
+                        Err(e) => {
+                            warn!("AI backend error on retry: {}", e);
+                            (error_reply(&e), String::new(), context)
+                        }
+                    }
+                } else {
+                    warn!("AI backend error: {}", e);
+                    (error_reply(&e), String::new(), context)
+                }
+            }
+        };
+
+    if !session_id.is_empty()
+        && let Err(e) = context.mark_sent(channel, user_id, &session_id)
+    {
+        warn!("Failed to record sent context: {}", e);
+    }
+
+    // Save session ID for future messages
+    if !session_id.is_empty()
+        && (store.sessions.get(&session_key).map(|s| s.as_str()) != Some(&session_id)
+            || store.session_backends.get(&session_key) != Some(&backend))
+    {
+        store.set_session(channel, user_id, &session_id, backend)?;
+    }
+
+    Ok((response, session_id))
+}
+
+/// Let the user know their message waits behind other queries
+fn queued_notice(channel: Arc<dyn Channel>) -> backends::OnQueued {
+    Box::new(move || {
+        tokio::spawn(async move {
+            if let Err(e) = channel
+                .send_message("I'm busy with other requests right now. Yours is queued, and I'll get to it shortly.")
+                .await
+            {
+                warn!("Failed to send queued notice: {}", e);
+            }
+        });
+    })
+}
+
+/// What to tell the user when their query failed
+fn error_reply(e: &anyhow::Error) -> String {
+    match e.downcast_ref::<backends::QueryTimedOut>() {
+        Some(timed_out) => format!(
+            "Sorry, I stopped because that was taking too long (over {} minute(s)). \
+             Send /retry to try again.",
+            timed_out.0.as_secs().div_ceil(60)
+        ),
+        None => format!("Sorry, I encountered an error: {}", e),
+    }
+}
+
+/// Recent messages summarized when a conversation moves to another backend
+const HANDOVER_MESSAGES: usize = 40;
+
+/// Longest transcript excerpt sent for a handover summary
+const HANDOVER_MAX_CHARS: usize = 20_000;
+
+/// Summarize the recent conversation for a session on a new backend, as a
+/// system prompt section. Returns `None` if there is nothing to hand over.
+async fn handover_summary(channel: &str, user_id: &str) -> Result<Option<String>> {
+    let messages = transcript::recent(channel, user_id, HANDOVER_MESSAGES)?;
+    if messages.is_empty() {
+        return Ok(None);
+    }
+
+    let prompt = format!(
+        "Another assistant is taking over this conversation. Write a compact \
+         handover note (under 200 words): what the user is working on, what was \
+         decided or done, open questions and promised follow-ups, and anything \
+         about tone or preferences worth keeping. No preamble.\n\n\
+         Transcript:\n{}",
+        format_transcript(&messages, HANDOVER_MAX_CHARS)
+    );
+    let (summary, _session_id) = backends::query_with_options(
+        &prompt,
+        QueryOptions {
+            skip_permissions: skip_permissions(channel),
+            read_only: true,
+            one_shot: true,
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    Ok(Some(format!(
+        "## Conversation So Far\n\
+         This conversation continues from an earlier session you can't see. \
+         Pick up naturally from this handover note:\n\n{}",
+        summary.trim()
+    )))
+}
+
+/// Handle onboarding flow - AI drives the conversation
+pub async fn handle_onboarding(channel: &str, user_id: &str, message: &str) -> Result<String> {
+    let phase = onboarding::current_phase_for_user(channel, user_id)?;
+    let system_prompt = onboarding::system_prompt_for_user(channel, user_id)?;
+
+    let options = backends::QueryOptions {
+        system_prompt: Some(system_prompt.clone()),
+        skip_permissions: skip_permissions(channel),
+        one_shot: true,
+        ..Default::default()
+    };
+
+    let (mut response, _) = backends::query_with_options(message, options).await?;
+
+    // A file missing required fields doesn't finish the phase; give the model
+    // one more go at it before the user is asked again
+    if let Some(problem) = onboarding::reject_invalid_file(channel, user_id, phase)? {
+        warn!(
+            "Rejected {:?} onboarding file for {}:{}, retrying",
+            phase, channel, user_id
+        );
+        let options = backends::QueryOptions {
+            system_prompt: Some(format!("{}\n\n{}", system_prompt, problem)),
+            skip_permissions: skip_permissions(channel),
+            one_shot: true,
+            ..Default::default()
+        };
+        response = backends::query_with_options(message, options).await?.0;
+        if onboarding::reject_invalid_file(channel, user_id, phase)?.is_some() {
+            warn!(
+                "Rejected {:?} onboarding file for {}:{} again",
+                phase, channel, user_id
+            );
+        }
+    }
+
+    if onboarding::is_complete_for_user(channel, user_id)? {
+        PairingStore::load()?.set_onboarded(channel, user_id, true)?;
+        info!("{}:{} finished onboarding", channel, user_id);
+    }
+    Ok(response)
+}
+
+/// Re-index memories for a user and the shared ones (called after Claude responds)
+pub fn reindex_user_memories(channel: &str, user_id: &str) {
+    match MemoryIndex::open() {
+        Ok(mut index) => {
+            if let Err(e) = index.index_user_memories(channel, user_id) {
+                warn!(
+                    "Failed to re-index memories for {}:{}: {}",
+                    channel, user_id, e
+                );
+            }
+            if let Err(e) = index.index_shared_memories() {
+                warn!("Failed to re-index shared memories: {}", e);
+            }
+        }
+        Err(e) => {
+            warn!("Failed to open memory index: {}", e);
+        }
+    }
+}
+
+// ============================================================================
+// Channel Registry
+// ============================================================================
+
+/// Boxed future returned by channel registry hooks
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// Registry hook opening a channel to a user outside a conversation:
+/// (config, user_id, send queue)
+pub type OpenChannel = fn(Config, String, Arc<SendQueue>) -> BoxFuture<Result<Arc<dyn Channel>>>;
+
+/// Registry hook checking a channel's secrets and storing them in the config:
+/// returns the updated config and what to show, e.g. the bot's name
+pub type ConfigureChannel = fn(Config, Vec<String>) -> BoxFuture<Result<(Config, String)>>;
+
+/// Registry hook telling the user what to do for a test message
+pub type TestInstructions = fn(Config) -> BoxFuture<Result<String>>;
+
+/// Setting a channel up from its secrets alone (`cica init --channel`)
+pub struct SecretSetup {
+    /// The `cica init` flags holding the secrets, in the order `configure`
+    /// takes them
+    pub flags: &'static [&'static str],
+    pub configure: ConfigureChannel,
+}
+
+/// A test message sent after setup to check messages flow both ways
+pub struct TestMessage {
+    /// Shown before asking who to send it to
+    pub recipient_help: Option<&'static str>,
+    /// Asks who to send it to, for channels that can't tell
+    pub recipient_prompt: Option<&'static str>,
+    /// What the user should do, worked out from the config
+    pub instructions: Option<TestInstructions>,
+    /// Shown while waiting for the user's reply
+    pub waiting: &'static str,
+    /// Send or wait for the message: (config, recipient, timeout), true if
+    /// the user answered in time
+    pub run: fn(Config, String, Duration) -> BoxFuture<Result<bool>>,
+}
+
+/// Cleanup offered when a channel is removed, e.g. unregistering the bot
+pub struct RemoveCleanup {
+    pub prompt: &'static str,
+    /// Labels for doing and skipping it
+    pub choices: [&'static str; 2],
+    pub default_yes: bool,
+    /// Shown while it runs
+    pub progress: &'static str,
+    /// Shown after it worked
+    pub note: Option<&'static str>,
+    /// Run it with the config from before the channel was removed
+    pub run: fn(Config) -> BoxFuture<Result<()>>,
+}
+
+/// A quick-reply button; tapping it sends `command` as if the user typed it
+#[derive(Debug, Clone)]
+pub struct ReplyAction {
+    pub label: String,
+    pub command: String,
+}
+
+impl ReplyAction {
+    pub fn new(label: impl Into<String>, command: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            command: command.into(),
+        }
+    }
+}
+
+/// A channel transport and the hooks Cica uses to configure, start and
+/// message through it.
+///
+/// New transports register by adding an entry to [`SUPPORTED_CHANNELS`];
+/// startup, the init wizard and config helpers are driven from this list.
+pub struct ChannelInfo {
+    /// Channel identifier, also used as its config section name
+    pub name: &'static str,
+    pub display_name: &'static str,
+    /// Pairing/onboarding settings, or None if the channel isn't configured
+    pub settings: fn(&Config) -> Option<ChannelSettings>,
+    /// Remove the channel's config section, returning whether it existed
+    pub remove_config: fn(&mut Config) -> bool,
+    /// Required fields left empty in the channel's config section
+    pub missing_fields: fn(&Config) -> Vec<&'static str>,
+    /// Create the outgoing send queue shared by everything that sends on this channel
+    pub send_queue: fn() -> Arc<SendQueue>,
+    /// Run the channel until it stops (only called when configured)
+    pub run: fn(Config, Arc<SendQueue>) -> BoxFuture<Result<()>>,
+    /// Send a standalone message to a user (cron results, notifications).
+    /// Channels without buttons can ignore the actions.
+    pub send_to_user: fn(Config, String, String, Vec<ReplyAction>) -> BoxFuture<Result<()>>,
+    /// Open a channel to a user, for sending files outside a conversation
+    /// (cron outputs, exports)
+    pub open: OpenChannel,
+    /// Set up without prompts, or None if the channel needs them (Signal
+    /// linking needs a phone to scan a code)
+    pub secret_setup: Option<SecretSetup>,
+    /// Offered at the end of `cica init`
+    pub test_message: Option<TestMessage>,
+    /// Offered when the channel is removed
+    pub remove_cleanup: Option<RemoveCleanup>,
+}
+
+impl ChannelInfo {
+    /// Check whether the channel has a config section
+    pub fn is_configured(&self, config: &Config) -> bool {
+        (self.settings)(config).is_some()
+    }
+}
+
+/// All channels compiled into this build, in the order shown to users
+pub const SUPPORTED_CHANNELS: &[ChannelInfo] = &[
+    telegram::CHANNEL,
+    #[cfg(feature = "signal")]
+    signal::CHANNEL,
+    #[cfg(feature = "slack")]
+    slack::CHANNEL,
+];
+
+/// Get channel info by name
+pub fn get_channel_info(name: &str) -> Option<&'static ChannelInfo> {
+    SUPPORTED_CHANNELS.iter().find(|c| c.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_text_with_attachments() {
+        let images = MessageAttachment::images(vec![
+            PathBuf::from("/tmp/a.jpg"),
+            PathBuf::from("/tmp/b.jpg"),
+        ]);
+        assert_eq!(
+            build_text_with_attachments("look", &images),
+            "look\n\n@/tmp/a.jpg @/tmp/b.jpg"
+        );
+        assert_eq!(
+            build_text_with_attachments("", &images),
+            "@/tmp/a.jpg @/tmp/b.jpg"
+        );
+
+        let location = MessageAttachment::Location {
+            latitude: 52.52,
+            longitude: 13.405,
+            label: Some("Cafe".to_string()),
+            address: None,
+        };
+        assert_eq!(
+            build_text_with_attachments("coffee near me?", &[location]),
+            "coffee near me?\n\n[Shared location: 52.520000, 13.405000 - Cafe]"
+        );
+    }
+
+    #[test]
+    fn test_build_text_with_forward_and_contact() {
+        let forwarded = [MessageAttachment::ForwardedFrom("Tech News".to_string())];
+        assert_eq!(
+            build_text_with_attachments("Big launch today", &forwarded),
+            "[Forwarded from Tech News]\nBig launch today"
+        );
+
+        let contact = [MessageAttachment::Contact {
+            name: "Ada Lovelace".to_string(),
+            phone_numbers: vec!["+441234567890".to_string()],
+            emails: vec![],
+            vcard: Some("BEGIN:VCARD\nFN:Ada Lovelace\nEND:VCARD\n".to_string()),
+        }];
+        assert_eq!(
+            build_text_with_attachments("", &contact),
+            "[Shared contact: Ada Lovelace, +441234567890]\n[vCard]\nBEGIN:VCARD\nFN:Ada Lovelace\nEND:VCARD"
+        );
+    }
+
+    const LIMITS: SendLimits = SendLimits {
+        global_interval: Duration::from_millis(100),
+        per_chat_interval: Duration::from_secs(1),
+    };
+
+    #[test]
+    fn test_send_slots_spacing() {
+        let now = Instant::now();
+        let mut slots = SendSlots {
+            next_global: now,
+            next_per_chat: HashMap::new(),
+        };
+
+        // First send goes out immediately
+        assert_eq!(slots.reserve("a", now, &LIMITS), now);
+        // Another chat only waits for the global interval
+        assert_eq!(
+            slots.reserve("b", now, &LIMITS),
+            now + Duration::from_millis(100)
+        );
+        // Same chat waits for the per-chat interval
+        assert_eq!(
+            slots.reserve("a", now, &LIMITS),
+            now + Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn test_send_slots_prunes_expired_chats() {
+        let now = Instant::now();
+        let mut slots = SendSlots {
+            next_global: now,
+            next_per_chat: HashMap::new(),
+        };
+
+        slots.reserve("a", now, &LIMITS);
+        let later = now + Duration::from_secs(5);
+        assert_eq!(slots.reserve("b", later, &LIMITS), later);
+        assert!(!slots.next_per_chat.contains_key("a"));
+    }
+
+    #[test]
+    fn test_channel_registry() {
+        let mut config = crate::config::Config::default();
+        assert!(config.configured_channels().is_empty());
+
+        // Telegram is always built in; the other channels are behind features
+        config.channels.telegram = Some(crate::config::TelegramConfig::default());
+        config.onboarding_prompt = Some("global".to_string());
+        assert_eq!(config.configured_channels(), vec!["telegram"]);
+        assert_eq!(
+            config
+                .channel_settings("telegram")
+                .onboarding_prompt
+                .as_deref(),
+            Some("global")
+        );
+
+        assert!(config.remove_channel("telegram"));
+        assert!(!config.remove_channel("telegram"));
+        assert!(!config.remove_channel("unknown"));
+
+        // Names double as config section keys, so they must be unique
+        for info in SUPPORTED_CHANNELS {
+            assert_eq!(get_channel_info(info.name).unwrap().name, info.name);
+        }
+    }
+
+    #[test]
+    fn test_error_reply() {
+        let timed_out = anyhow::Error::new(backends::QueryTimedOut(Duration::from_secs(600)));
+        assert_eq!(
+            error_reply(&timed_out),
+            "Sorry, I stopped because that was taking too long (over 10 minute(s)). \
+             Send /retry to try again."
+        );
+
+        let other = anyhow::anyhow!("boom");
+        assert_eq!(error_reply(&other), "Sorry, I encountered an error: boom");
+    }
+
+    #[test]
+    fn test_is_owner() {
+        let mut store = PairingStore::default();
+        store.approved.insert(
+            "telegram".to_string(),
+            vec!["1".to_string(), "2".to_string()],
+        );
+        store.roles.insert("telegram:1".to_string(), Role::Owner);
+
+        assert!(is_owner(&store, "telegram", "1"));
+        assert!(!is_owner(&store, "telegram", "2"));
+        assert_eq!(user_permissions(&store, "telegram", "1").0, Role::Owner);
+        assert!(matches!(
+            owner_only("telegram", "2", "install skills"),
+            CommandResult::Response(reply) if reply.ends_with("cica owner set telegram 2")
+        ));
+    }
+
+    #[test]
+    fn test_claims_first_owner() {
+        let mut config = Config::default();
+        let mut store = PairingStore::default();
+        assert!(!claims_first_owner(&config, &store));
+
+        config.pairing.auto_approve_first_user = true;
+        assert!(claims_first_owner(&config, &store));
+
+        store
+            .approved
+            .insert("telegram".to_string(), vec!["1".to_string()]);
+        assert!(!claims_first_owner(&config, &store));
+
+        store.approved.clear();
+        config.owner = Some(OwnerConfig {
+            channel: "telegram".to_string(),
+            user_id: "1".to_string(),
+        });
+        assert!(!claims_first_owner(&config, &store));
+    }
+
+    #[test]
+    fn test_parse_import_command() {
+        assert_eq!(
+            parse_import_command("/import telegram:123 profile\n# USER.md\nName: Mom"),
+            Some(("telegram:123", "profile", "# USER.md\nName: Mom"))
+        );
+        assert_eq!(parse_import_command("/import"), Some(("", "", "")));
+        assert_eq!(parse_import_command("/imports"), None);
+    }
+
+    #[test]
+    fn test_split_job_edit() {
+        assert_eq!(
+            split_job_edit("morning-brief schedule every 2h"),
+            Some(("morning-brief", "schedule", "every 2h"))
+        );
+        assert_eq!(
+            split_job_edit("Check my emails prompt Check my work emails"),
+            Some(("Check my emails", "prompt", "Check my work emails"))
+        );
+        assert_eq!(split_job_edit("a1b2c3d4 name"), None);
+        assert_eq!(split_job_edit("prompt foo"), None);
+    }
+
+    #[test]
+    fn test_parse_think_prefix() {
+        assert_eq!(
+            parse_think_prefix("/think hard why is the sky blue?"),
+            (Some(Effort::High), "why is the sky blue?")
+        );
+        assert_eq!(
+            parse_think_prefix("/think plan my week"),
+            (Some(Effort::Medium), "plan my week")
+        );
+        assert_eq!(
+            parse_think_prefix("/think hardware ideas"),
+            (Some(Effort::Medium), "hardware ideas")
+        );
+        assert_eq!(parse_think_prefix("/think hard"), (Some(Effort::High), ""));
+        assert_eq!(parse_think_prefix("/think"), (Some(Effort::Medium), ""));
+        assert_eq!(parse_think_prefix("/thinking"), (None, "/thinking"));
+        assert_eq!(parse_think_prefix("hello"), (None, "hello"));
+    }
+
+    #[test]
+    fn test_parse_verbose_command() {
+        assert_eq!(parse_verbose_command("/verbose on"), Some(Some(true)));
+        assert_eq!(parse_verbose_command("/verbose off"), Some(Some(false)));
+        assert_eq!(parse_verbose_command("/verbose"), Some(None));
+        assert_eq!(parse_verbose_command("/verbosely"), None);
+    }
+
+    #[test]
+    fn test_parse_help_command() {
+        assert_eq!(parse_help_command("/help"), Some(""));
+        assert_eq!(parse_help_command("/help cron"), Some("cron"));
+        assert_eq!(parse_help_command("/commands"), Some(""));
+        assert_eq!(parse_help_command("/helpful"), None);
+    }
+
+    #[test]
+    fn test_help_response() {
+        let all = help_response("", false, None);
+        assert!(all.contains("/recap [today|week] - Summarize what we talked about"));
+        assert!(!all.contains("/approve"));
+        assert!(help_response("", true, None).contains("/approve <code>"));
+
+        let cron = help_response("/cron", false, None);
+        assert!(cron.starts_with("/cron <subcommand>\nManage scheduled jobs"));
+        assert!(!cron.contains("pause all"));
+        assert!(help_response("cron", true, None).contains("pause all"));
+
+        assert!(help_response("approve", false, None).starts_with("Unknown command: /approve"));
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(30), "less than a minute");
+        assert_eq!(format_age(5 * 60 + 10), "5m");
+        assert_eq!(format_age(3 * 3_600 + 20 * 60), "3h 20m");
+        assert_eq!(format_age(2 * 86_400 + 3 * 3_600 + 59), "2d 3h");
+    }
+
+    #[test]
+    fn test_menu_commands() {
+        let menu = menu_commands(COMMANDS.iter().chain(OWNER_COMMANDS));
+        let cron: Vec<_> = menu.iter().filter(|info| info.name == "cron").collect();
+        assert_eq!(cron.len(), 1);
+        assert_eq!(cron[0].args, "<subcommand>");
+
+        let recap = menu.iter().find(|info| info.name == "recap").unwrap();
+        assert_eq!(
+            recap.menu_description(None),
+            "[today|week] - Summarize what we talked about"
+        );
+        assert_eq!(
+            recap.menu_description(Some("de")),
+            "[today|week] - Zusammenfassen, worüber wir gesprochen haben"
+        );
+    }
+
+    #[test]
+    fn test_parse_language_command() {
+        assert_eq!(parse_language_command("/language hu"), Some("hu"));
+        assert_eq!(parse_language_command("/language  auto "), Some("auto"));
+        assert_eq!(parse_language_command("/language"), Some(""));
+        assert_eq!(parse_language_command("/languages"), None);
+    }
+
+    #[test]
+    fn test_parse_dnd_command() {
+        assert_eq!(parse_dnd_command("/dnd 22:00-07:00"), Some("22:00-07:00"));
+        assert_eq!(parse_dnd_command("/dnd off "), Some("off"));
+        assert_eq!(parse_dnd_command("/dnd"), Some(""));
+        assert_eq!(parse_dnd_command("/dndx"), None);
+    }
+
+    #[test]
+    fn test_parse_digest_command() {
+        assert_eq!(parse_digest_command("/digest 8:00"), Some("8:00"));
+        assert_eq!(parse_digest_command("/digest now"), Some("now"));
+        assert_eq!(parse_digest_command("/digest"), Some(""));
+        assert_eq!(parse_digest_command("/digests"), None);
+    }
+
+    #[test]
+    fn test_parse_feedback_command() {
+        assert_eq!(parse_feedback_command("/good"), Some((Rating::Good, None)));
+        assert_eq!(
+            parse_feedback_command("/bad  Use metric units "),
+            Some((Rating::Bad, Some("Use metric units")))
+        );
+        assert_eq!(parse_feedback_command("👍"), Some((Rating::Good, None)));
+        assert_eq!(parse_feedback_command("👎🏾"), Some((Rating::Bad, None)));
+        assert_eq!(parse_feedback_command("👍 thanks"), None);
+        assert_eq!(parse_feedback_command("/goodbye"), None);
+        assert_eq!(parse_feedback_command("hello"), None);
+    }
+
+    #[test]
+    fn test_parse_persona_command() {
+        assert_eq!(parse_persona_command("/persona"), Some(None));
+        assert_eq!(parse_persona_command("/persona show"), Some(None));
+        assert_eq!(
+            parse_persona_command("/persona set Be brief.\nUse emoji."),
+            Some(Some("Be brief.\nUse emoji."))
+        );
+        assert_eq!(
+            parse_persona_command("/persona set\nBe brief."),
+            Some(Some("Be brief."))
+        );
+        assert_eq!(parse_persona_command("/persona set"), Some(Some("")));
+        assert_eq!(parse_persona_command("/personas"), None);
+    }
+
+    #[test]
+    fn test_tool_footer() {
+        let tools: Vec<String> = ["WebSearch", "Read", "Read", "Bash", "mcp__calendar__list"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        assert_eq!(
+            tool_footer(&tools).as_deref(),
+            Some("Tools: searched the web, read 2 files, ran 1 command, mcp__calendar__list")
+        );
+        assert_eq!(tool_footer(&[]), None);
+    }
+
+    #[test]
+    fn test_parse_history_command() {
+        assert_eq!(
+            parse_history_command("/history"),
+            Some(Some(HISTORY_DEFAULT_COUNT))
+        );
+        assert_eq!(parse_history_command("/history 3"), Some(Some(3)));
+        assert_eq!(parse_history_command("/history 0"), Some(None));
+        assert_eq!(parse_history_command("/history lots"), Some(None));
+        assert_eq!(parse_history_command("/historyx"), None);
+    }
+
+    #[test]
+    fn test_parse_recap_command() {
+        assert_eq!(
+            parse_recap_command("/recap"),
+            Some(Some(RecapPeriod::Today))
+        );
+        assert_eq!(
+            parse_recap_command("/recap Week"),
+            Some(Some(RecapPeriod::Week))
+        );
+        assert_eq!(parse_recap_command("/recap month"), Some(None));
+        assert_eq!(parse_recap_command("/recaps"), None);
+    }
+
+    #[test]
+    fn test_recap_today_starts_at_users_midnight() {
+        // 2025-01-15 03:00 UTC is already noon in Tokyo
+        let now = chrono::DateTime::from_timestamp(1_736_910_000, 0).unwrap();
+        assert_eq!(
+            RecapPeriod::Today.start(now, Some(chrono_tz::Asia::Tokyo)),
+            1_736_866_800
+        );
+        assert_eq!(
+            RecapPeriod::Week.start(now, Some(chrono_tz::Asia::Tokyo)),
+            1_736_910_000 - 7 * 86_400
+        );
+    }
+
+    #[test]
+    fn test_parse_skill_command() {
+        assert_eq!(
+            parse_skill_command("/skill install https://example.com/weather.git"),
+            Some(("install", "https://example.com/weather.git"))
+        );
+        assert_eq!(
+            parse_skill_command("/skill  confirm weather "),
+            Some(("confirm", "weather"))
+        );
+        assert_eq!(
+            parse_skill_command("/skill installer x"),
+            Some(("installer", "x"))
+        );
+        assert_eq!(parse_skill_command("/skill"), Some(("", "")));
+        assert_eq!(parse_skill_command("/skills"), None);
+        assert_eq!(parse_skill_command("/skillinstall x"), None);
+    }
+
+    #[test]
+    fn test_parse_export_command() {
+        assert_eq!(
+            parse_export_command("/export"),
+            Some(Some(ExportKind::Conversation))
+        );
+        assert_eq!(
+            parse_export_command("/export Memories"),
+            Some(Some(ExportKind::Memories))
+        );
+        assert_eq!(parse_export_command("/export photos"), Some(None));
+        assert_eq!(parse_export_command("/exports"), None);
+    }
+
+    #[test]
+    fn test_recap_prompt_keeps_newest_messages() {
+        let message = |text: String| TranscriptMessage {
+            channel: "telegram".to_string(),
+            user_id: "1".to_string(),
+            speaker: Speaker::User,
+            text,
+            created_at: 1_700_000_000,
+        };
+        let messages = vec![
+            message("oldest".repeat(RECAP_MAX_CHARS / 6)),
+            message("newest".to_string()),
+        ];
+
+        let prompt = build_recap_prompt(
+            RecapPeriod::Today,
+            &messages,
+            &["- morning-brief (daily at 09:00)".to_string()],
+        );
+
+        assert!(prompt.contains("User: newest"));
+        assert!(!prompt.contains("oldest"));
+        assert!(prompt.contains("morning-brief"));
+    }
+
+    #[test]
+    fn test_split_add_options() {
+        assert_eq!(
+            split_add_options("--name morning-brief 0 9 * * * Summarize"),
+            (
+                AddOptions {
+                    name: Some("morning-brief"),
+                    ..Default::default()
+                },
+                "0 9 * * * Summarize"
+            )
+        );
+        assert_eq!(
+            split_add_options("model=haiku --name builds --dry-run backend=claude every 15m Check"),
+            (
+                AddOptions {
+                    name: Some("builds"),
+                    dry_run: true,
+                    backend: Some("claude"),
+                    model: Some("haiku"),
+                    ingest: None,
+                },
+                "every 15m Check"
+            )
+        );
+        assert_eq!(
+            split_add_options("ingest=memories/news/ every 1d Summarize the news"),
+            (
+                AddOptions {
+                    ingest: Some("memories/news/"),
+                    ..Default::default()
+                },
+                "every 1d Summarize the news"
+            )
+        );
+        assert_eq!(
+            split_add_options("every 1h Check email"),
+            (AddOptions::default(), "every 1h Check email")
+        );
+    }
+
+    #[test]
+    fn test_parse_pairing_command() {
+        assert_eq!(
+            parse_pairing_command("/approve ABCD2345"),
+            Some(("/approve", "ABCD2345"))
+        );
+        assert_eq!(
+            parse_pairing_command("/deny  abcd2345 "),
+            Some(("/deny", "abcd2345"))
+        );
+        assert_eq!(parse_pairing_command("/approve"), None);
+        assert_eq!(parse_pairing_command("/approved ABCD2345"), None);
+        assert_eq!(parse_pairing_command("please /approve ABCD2345"), None);
+    }
+
+    #[test]
+    fn test_cron_needs_permission() {
+        for args in [
+            "add every 1h Check my inbox",
+            "add from briefing",
+            "copy morning",
+            "edit morning prompt Hi",
+            "run morning",
+            "resume morning",
+            "template save briefing morning",
+            "template delete briefing",
+        ] {
+            assert!(cron_needs_permission(args), "{}", args);
+        }
+        for args in [
+            "",
+            "list",
+            "log morning",
+            "preview 0 9 * * *",
+            "pause morning",
+            "remove morning",
+            "template",
+            "template list",
+        ] {
+            assert!(!cron_needs_permission(args), "{}", args);
+        }
+    }
+
+    #[test]
+    fn test_permissions_prompt() {
+        assert_eq!(
+            permissions_prompt(Role::Member, RolePermissions::full()),
+            None
+        );
+
+        let prompt = permissions_prompt(Role::Guest, RolePermissions::restricted()).unwrap();
+        assert!(prompt.contains("guest role"));
+        assert!(prompt.contains("cannot modify files"));
+        assert!(prompt.contains("scheduled jobs"));
+        assert!(prompt.contains("shared memories"));
+    }
+
+    #[test]
+    fn test_split_message() {
+        assert_eq!(split_message("short", 10), vec!["short"]);
+        assert_eq!(split_message("", 10), vec![""]);
+
+        // Prefers paragraph breaks, then words
+        assert_eq!(
+            split_message("first para\n\nsecond para", 15),
+            vec!["first para", "second para"]
+        );
+        assert_eq!(
+            split_message("one two three four", 9),
+            vec!["one two", "three", "four"]
+        );
+
+        // Hard split when there is no boundary, without breaking characters
+        assert_eq!(split_message("ééééé", 2), vec!["éé", "éé", "é"]);
+
+        // Emoji are two UTF-16 units each
+        let emoji = "😀".repeat(5);
+        assert_eq!(split_message(&emoji, 5), vec![emoji.clone()]);
+        assert_eq!(split_message_utf16(&emoji, 4), vec!["😀😀", "😀😀", "😀"]);
+        assert!(
+            split_message_utf16(&"😀 ".repeat(3000), 4096)
+                .iter()
+                .all(|chunk| chunk.encode_utf16().count() <= 4096)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_queue_does_not_retry_permanent_errors() {
+        let queue = SendQueue::new("test", LIMITS, |_| SendRetry::Never);
+        let mut calls = 0;
+
+        let result = queue
+            .send("a", || {
+                calls += 1;
+                async { Err(anyhow::anyhow!("bad request")) }
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[tokio::test]
+    async fn test_send_queue_retries_rate_limits() {
+        let limits = SendLimits {
+            global_interval: Duration::ZERO,
+            per_chat_interval: Duration::ZERO,
+        };
+        let queue = SendQueue::new("test", limits, |_| SendRetry::After(Duration::ZERO));
+        let mut calls = 0;
+
+        let result = queue
+            .send("a", || {
+                calls += 1;
+                let fail = calls < 3;
+                async move {
+                    if fail {
+                        Err(anyhow::anyhow!("429 Too Many Requests"))
+                    } else {
+                        Ok(())
+                    }
+                }
+            })
+            .await;
+
+        assert!(result.is_ok());
+        assert_eq!(calls, 3);
+    }
+
+    #[tokio::test]
+    async fn test_task_manager_cancel() {
+        let manager = UserTaskManager::new();
+        for key in ["slack:U1:1700000000.1", "slack:U12"] {
+            manager
+                .process_message(key.to_string(), "hi".to_string(), |_| {
+                    std::future::pending()
+                })
+                .await;
+        }
+        tokio::time::sleep(Duration::from_millis(
+            crate::config::QueryConfig::default().debounce_ms * 2,
+        ))
+        .await;
+
+        // Thread tasks are the user's too; other users' aren't
+        assert!(manager.cancel("slack:U1").await);
+        assert!(!manager.cancel("slack:U1").await);
+        assert!(manager.tasks.lock().await.contains_key("slack:U12"));
+    }
+
+    #[tokio::test]
+    async fn test_task_manager_waits_while_typing() {
+        let debounce = Duration::from_millis(crate::config::QueryConfig::default().debounce_ms);
+        let manager = UserTaskManager::new();
+        let (done_tx, mut done_rx) = oneshot::channel();
+
+        manager.set_typing("signal:+1", true).await;
+        manager
+            .process_message(
+                "signal:+1".to_string(),
+                "one".to_string(),
+                |messages| async move {
+                    let _ = done_tx.send(messages);
+                },
+            )
+            .await;
+        tokio::time::sleep(debounce * 2).await;
+        assert!(done_rx.try_recv().is_err());
+
+        manager.set_typing("signal:+1", false).await;
+        tokio::time::sleep(debounce * 2).await;
+        assert_eq!(done_rx.try_recv().unwrap(), vec!["one"]);
+    }
+}
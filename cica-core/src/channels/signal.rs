@@ -7,7 +7,7 @@ use jsonrpsee::core::params::ObjectParams;
 use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
 use serde::Deserialize;
 use serde_json::Value;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::Arc;
 use std::time::Duration;
@@ -17,26 +17,143 @@ use tokio::time::sleep;
 use tracing::{debug, error, info, warn};
 
 use super::{
-    Channel, TypingGuard, UserTaskManager, build_text_with_images, determine_action,
-    execute_action, execute_claude_query,
+    Channel, ChannelInfo, MessageAttachment, RemoveCleanup, SendLimits, SendQueue, SendRetry,
+    TestMessage, TypingGuard, UnsupportedContent, UserTaskManager, build_text_with_attachments,
+    determine_action, execute_action, execute_claude_query, fire_message_triggers,
+    geocode_locations, process_reaction, reply_unsupported,
 };
-use crate::config::{self, SignalConfig};
+use crate::attachments;
+use crate::config::{self, ChannelSettings, SignalConfig};
 use crate::pairing::PairingStore;
+use crate::redact;
 use crate::setup;
 
 // ============================================================================
 // Channel Implementation
 // ============================================================================
 
+/// Signal rate-limits aggressive senders, so keep a modest pace
+const SEND_LIMITS: SendLimits = SendLimits {
+    global_interval: Duration::from_millis(200),
+    per_chat_interval: Duration::from_millis(500),
+};
+
 /// Signal channel implementation
 pub struct SignalChannel {
     client: Arc<HttpClient>,
     recipient: String,
+    send_queue: Arc<SendQueue>,
 }
 
 impl SignalChannel {
-    pub fn new(client: Arc<HttpClient>, recipient: String) -> Self {
-        Self { client, recipient }
+    pub fn new(client: Arc<HttpClient>, recipient: String, send_queue: Arc<SendQueue>) -> Self {
+        Self {
+            client,
+            recipient,
+            send_queue,
+        }
+    }
+}
+
+/// Create the outgoing send queue for Signal.
+/// Share one queue between everything that sends so rate limits apply across all recipients.
+pub fn send_queue() -> Arc<SendQueue> {
+    SendQueue::new("signal", SEND_LIMITS, classify_send_error)
+}
+
+/// Signal's entry in the channel registry
+pub const CHANNEL: ChannelInfo = ChannelInfo {
+    name: "signal",
+    display_name: "Signal",
+    settings: |config| {
+        config.channels.signal.as_ref().map(|c| ChannelSettings {
+            auto_approve: c.auto_approve,
+            shared_identity: c.shared_identity,
+            onboarding_prompt: c.onboarding_prompt.clone(),
+            skip_permissions: c.skip_permissions,
+            persona: c.persona.clone(),
+        })
+    },
+    remove_config: |config| config.channels.signal.take().is_some(),
+    missing_fields: |config| match &config.channels.signal {
+        Some(c) if c.phone_number.trim().is_empty() => vec!["phone_number"],
+        _ => Vec::new(),
+    },
+    send_queue,
+    run: |config, send_queue| {
+        let signal_config = config.channels.signal.unwrap_or_default();
+        Box::pin(run(signal_config, send_queue))
+    },
+    send_to_user: |config, recipient, message, _actions| {
+        let configured = config.channels.signal.is_some();
+        Box::pin(async move {
+            if !configured {
+                bail!("Signal not configured");
+            }
+
+            // Goes through the daemon started by the running channel
+            let url = format!("http://127.0.0.1:{}/api/v1/rpc", DAEMON_PORT);
+            let client = HttpClientBuilder::default().build(url)?;
+
+            let mut params = ObjectParams::new();
+            params.insert("recipient", vec![recipient])?;
+            params.insert("message", message)?;
+
+            let _: Value = client.request("send", params).await?;
+            Ok(())
+        })
+    },
+    open: |config, recipient, send_queue| {
+        let configured = config.channels.signal.is_some();
+        Box::pin(async move {
+            if !configured {
+                bail!("Signal not configured");
+            }
+
+            let url = format!("http://127.0.0.1:{}/api/v1/rpc", DAEMON_PORT);
+            let client = HttpClientBuilder::default().build(url)?;
+            let channel: Arc<dyn Channel> =
+                Arc::new(SignalChannel::new(Arc::new(client), recipient, send_queue));
+            Ok(channel)
+        })
+    },
+    // Linking needs a phone to scan a code, registering an SMS code
+    secret_setup: None,
+    test_message: Some(TestMessage {
+        recipient_help: None,
+        recipient_prompt: Some("Your phone number to send the test message to"),
+        instructions: None,
+        waiting: "Sending a test message and waiting for your reply... ",
+        run: |config, recipient, timeout| {
+            let account = config.channels.signal.map(|c| c.phone_number);
+            Box::pin(async move {
+                let account = account.ok_or_else(|| anyhow!("Signal not configured"))?;
+                send_test_message(&account, &recipient, timeout).await
+            })
+        },
+    }),
+    remove_cleanup: Some(RemoveCleanup {
+        prompt: "Also delete the local signal-cli account data?",
+        choices: ["Yes", "No, keep it for re-linking later"],
+        default_yes: false,
+        progress: "Removing Signal account data... ",
+        note: Some("You may also want to remove \"Cica\" from Linked Devices in the Signal app."),
+        run: |_config| Box::pin(async { remove_account_data() }),
+    }),
+};
+
+/// Decide whether a failed signal-cli send should be retried
+fn classify_send_error(err: &anyhow::Error) -> SendRetry {
+    use jsonrpsee::core::client::Error as RpcError;
+
+    match err.downcast_ref::<RpcError>() {
+        // Daemon briefly unreachable
+        Some(RpcError::Transport(_)) => SendRetry::Backoff,
+        // signal-cli reports server-side throttling as a RateLimitException
+        Some(RpcError::Call(obj)) if obj.message().to_lowercase().contains("rate limit") => {
+            SendRetry::Backoff
+        }
+        _ => SendRetry::Never,
     }
 }
 
@@ -61,7 +178,7 @@ impl Channel for SignalChannel {
     ) -> Result<()> {
         let mut params = ObjectParams::new();
         params.insert("recipient", vec![self.recipient.as_str()])?;
-        params.insert("message", message)?;
+        params.insert("message", redact::redact(message))?;
 
         // Add attachments if any
         if !attachment_paths.is_empty() {
@@ -72,13 +189,21 @@ impl Channel for SignalChannel {
             params.insert("attachments", attachment_strings)?;
         }
 
-        let _: Value = self
-            .client
-            .request("send", params)
+        self.send_queue
+            .send(&self.recipient, || async {
+                let _: Value = self
+                    .client
+                    .request("send", params.clone())
+                    .await
+                    .context("Failed to send message")?;
+                Ok(())
+            })
             .await
-            .context("Failed to send message")?;
+    }
 
-        Ok(())
+    async fn send_file(&self, path: &Path) -> Result<()> {
+        self.send_message_with_attachments("", &[path.to_path_buf()])
+            .await
     }
 
     fn start_typing(&self) -> TypingGuard {
@@ -354,12 +479,82 @@ struct Envelope {
     source_name: Option<String>,
     #[serde(rename = "dataMessage")]
     data_message: Option<DataMessage>,
+    #[serde(rename = "typingMessage")]
+    typing_message: Option<TypingMessage>,
+}
+
+/// The sender started or stopped typing
+#[derive(Debug, Deserialize)]
+struct TypingMessage {
+    /// "STARTED" or "STOPPED"
+    action: String,
 }
 
 #[derive(Debug, Deserialize)]
 struct DataMessage {
     message: Option<String>,
     attachments: Option<Vec<Attachment>>,
+    #[serde(rename = "sharedContacts")]
+    shared_contacts: Option<Vec<SharedContact>>,
+    sticker: Option<Value>,
+    reaction: Option<Reaction>,
+}
+
+/// An emoji reaction to a message
+#[derive(Debug, Deserialize)]
+struct Reaction {
+    emoji: String,
+    #[serde(rename = "isRemove", default)]
+    is_remove: bool,
+}
+
+/// Contact card shared in a message
+#[derive(Debug, Deserialize)]
+struct SharedContact {
+    name: Option<SharedContactName>,
+    phone: Option<Vec<SharedContactValue>>,
+    email: Option<Vec<SharedContactValue>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SharedContactName {
+    display: Option<String>,
+    given: Option<String>,
+    family: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SharedContactValue {
+    value: Option<String>,
+}
+
+impl SharedContact {
+    /// Convert into a channel-agnostic contact attachment
+    fn into_attachment(self) -> MessageAttachment {
+        let name = self
+            .name
+            .and_then(|n| {
+                n.display.or_else(|| {
+                    let parts: Vec<String> = [n.given, n.family].into_iter().flatten().collect();
+                    (!parts.is_empty()).then(|| parts.join(" "))
+                })
+            })
+            .unwrap_or_else(|| "Unknown".to_string());
+
+        let values = |list: Option<Vec<SharedContactValue>>| -> Vec<String> {
+            list.unwrap_or_default()
+                .into_iter()
+                .filter_map(|v| v.value)
+                .collect()
+        };
+
+        MessageAttachment::Contact {
+            name,
+            phone_numbers: values(self.phone),
+            emails: values(self.email),
+            vcard: None,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -378,11 +573,11 @@ struct Attachment {
 // ============================================================================
 
 /// Run the Signal bot
-pub async fn run(config: SignalConfig) -> Result<()> {
+pub async fn run(config: SignalConfig, send_queue: Arc<SendQueue>) -> Result<()> {
     info!("Starting Signal bot for {}...", config.phone_number);
 
     // Create shared task manager for per-user message handling (persists across restarts)
-    let task_manager = UserTaskManager::new();
+    let task_manager = UserTaskManager::global();
 
     // Outer loop for daemon recovery
     loop {
@@ -408,7 +603,8 @@ pub async fn run(config: SignalConfig) -> Result<()> {
         info!("Signal bot running. Listening for messages...");
 
         // Run message loop until it signals a restart is needed
-        let needs_restart = run_message_loop(client, Arc::clone(&task_manager)).await;
+        let needs_restart =
+            run_message_loop(client, Arc::clone(&task_manager), Arc::clone(&send_queue)).await;
 
         // Shutdown daemon gracefully
         daemon.shutdown().await;
@@ -434,7 +630,11 @@ const MAX_CONSECUTIVE_FAILURES: u32 = 10;
 
 /// Main message polling loop
 /// Returns true if daemon should be restarted, false for clean exit
-async fn run_message_loop(client: Arc<HttpClient>, task_manager: Arc<UserTaskManager>) -> bool {
+async fn run_message_loop(
+    client: Arc<HttpClient>,
+    task_manager: Arc<UserTaskManager>,
+    send_queue: Arc<SendQueue>,
+) -> bool {
     let mut consecutive_failures: u32 = 0;
 
     loop {
@@ -444,8 +644,13 @@ async fn run_message_loop(client: Arc<HttpClient>, task_manager: Arc<UserTaskMan
                 consecutive_failures = 0;
 
                 for msg in messages {
-                    if let Err(e) =
-                        handle_message(client.clone(), msg, Arc::clone(&task_manager)).await
+                    if let Err(e) = handle_message(
+                        client.clone(),
+                        msg,
+                        Arc::clone(&task_manager),
+                        Arc::clone(&send_queue),
+                    )
+                    .await
                     {
                         error!("Error handling message: {}", e);
                     }
@@ -490,6 +695,24 @@ async fn receive_messages(client: &HttpClient) -> Result<Vec<SignalMessage>> {
     Ok(messages)
 }
 
+/// Extract coordinates from a shared-location map link
+/// (e.g. "https://maps.google.com/maps?q=52.520008%2C13.404954")
+fn parse_location_url(text: &str) -> Option<(f64, f64)> {
+    let start = text.find("maps.google.com/maps?q=")? + "maps.google.com/maps?q=".len();
+    let query = text[start..]
+        .split(|c: char| c.is_whitespace() || c == '&')
+        .next()?
+        .replace("%2C", ",")
+        .replace("%2c", ",");
+
+    let (lat, long) = query.split_once(',')?;
+    let latitude: f64 = lat.trim().parse().ok()?;
+    let longitude: f64 = long.trim().parse().ok()?;
+
+    ((-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude))
+        .then_some((latitude, longitude))
+}
+
 /// Get the path where signal-cli stores attachments
 fn get_attachment_path(attachment_id: &str) -> Option<PathBuf> {
     let paths = config::paths().ok()?;
@@ -512,11 +735,25 @@ fn is_image_content_type(content_type: &str) -> bool {
     )
 }
 
+/// Kind of non-image attachment Cica can't process
+fn unsupported_content_type(content_type: &str) -> Option<UnsupportedContent> {
+    if is_image_content_type(content_type) {
+        None
+    } else if content_type.starts_with("audio/") {
+        Some(UnsupportedContent::Audio)
+    } else if content_type.starts_with("video/") {
+        Some(UnsupportedContent::Video)
+    } else {
+        Some(UnsupportedContent::File)
+    }
+}
+
 /// Handle an incoming message
 async fn handle_message(
     client: Arc<HttpClient>,
     msg: SignalMessage,
     task_manager: Arc<UserTaskManager>,
+    send_queue: Arc<SendQueue>,
 ) -> Result<()> {
     let envelope = match msg.envelope {
         Some(e) => e,
@@ -534,17 +771,44 @@ async fn handle_message(
         return Ok(());
     }
 
+    // Typing lets the task manager wait for the message being written
+    if let Some(typing) = &envelope.typing_message {
+        let user_key = format!("signal:{}", sender);
+        task_manager
+            .set_typing(&user_key, typing.action == "STARTED")
+            .await;
+    }
+
     // Extract message content and attachments
     let data_message = match envelope.data_message {
         Some(dm) => dm,
         None => return Ok(()),
     };
 
+    // 👍/👎 reactions are feedback on the last reply
+    if let Some(reaction) = &data_message.reaction {
+        if !reaction.is_remove
+            && let Some(response) = process_reaction("signal", &sender, &reaction.emoji)
+        {
+            SignalChannel::new(client, sender.clone(), send_queue)
+                .send_message(&response)
+                .await?;
+        }
+        return Ok(());
+    }
+
     let text = data_message.message.clone().unwrap_or_default();
-    let attachments = data_message.attachments.unwrap_or_default();
+    let signal_attachments = data_message.attachments.unwrap_or_default();
+    let unsupported = if data_message.sticker.is_some() {
+        Some(UnsupportedContent::Sticker)
+    } else {
+        signal_attachments
+            .iter()
+            .find_map(|a| unsupported_content_type(a.content_type.as_deref()?))
+    };
 
     // Collect image attachment paths
-    let image_paths: Vec<PathBuf> = attachments
+    let image_paths: Vec<PathBuf> = signal_attachments
         .iter()
         .filter(|a| {
             a.content_type
@@ -553,34 +817,63 @@ async fn handle_message(
                 .unwrap_or(false)
         })
         .filter_map(|a| a.id.as_ref().and_then(|id| get_attachment_path(id)))
+        .map(|path| {
+            attachments::store_file(&path).unwrap_or_else(|e| {
+                warn!("Failed to store Signal attachment: {}", e);
+                path
+            })
+        })
         .collect();
 
-    // Skip if no text and no images
-    if text.is_empty() && image_paths.is_empty() {
+    let mut attachments = MessageAttachment::images(image_paths);
+
+    // Shared contact cards
+    attachments.extend(
+        data_message
+            .shared_contacts
+            .unwrap_or_default()
+            .into_iter()
+            .map(SharedContact::into_attachment),
+    );
+
+    // Signal shares locations as a map link in the message body
+    if let Some((latitude, longitude)) = parse_location_url(&text) {
+        attachments.push(MessageAttachment::Location {
+            latitude,
+            longitude,
+            label: None,
+            address: None,
+        });
+    }
+
+    // Skip if no text and no attachments, pointing out anything we can't handle
+    if text.is_empty() && attachments.is_empty() {
+        if let Some(content) = unsupported {
+            let channel = SignalChannel::new(client, sender.clone(), send_queue);
+            return reply_unsupported(&channel, &sender, content).await;
+        }
         return Ok(());
     }
 
     let display_name = envelope.source_name;
 
     info!("Message from {}: {}", sender, text);
-    if !image_paths.is_empty() {
-        info!(
-            "Message includes {} image(s): {:?}",
-            image_paths.len(),
-            image_paths
-        );
+    if !attachments.is_empty() {
+        info!("Message includes attachment(s): {:?}", attachments);
     }
 
     // Create channel wrapper
-    let channel: Arc<dyn Channel> = Arc::new(SignalChannel::new(client, sender.clone()));
+    let channel: Arc<dyn Channel> =
+        Arc::new(SignalChannel::new(client, sender.clone(), send_queue));
 
     // Determine what action to take
     let mut store = PairingStore::load()?;
+    fire_message_triggers(channel.name(), &sender, &text, &store);
     let action = determine_action(
         channel.name(),
         &sender,
         &text,
-        &image_paths,
+        &attachments,
         &mut store,
         None, // Signal doesn't have usernames
         display_name,
@@ -589,13 +882,14 @@ async fn handle_message(
     // Execute the action
     if let Some(query_text) = execute_action(channel.as_ref(), &sender, action).await? {
         // QueryClaude action - queue with task manager for debouncing
-        let text_with_images = build_text_with_images(&query_text, &image_paths);
+        let attachments = geocode_locations(&attachments).await;
+        let full_text = build_text_with_attachments(&query_text, &attachments);
         let user_key = format!("{}:{}", channel.name(), sender);
         let channel_clone = channel.clone();
         let sender_clone = sender.clone();
 
         task_manager
-            .process_message(user_key, text_with_images, move |messages| async move {
+            .process_message(user_key, full_text, move |messages| async move {
                 execute_claude_query(channel_clone, &sender_clone, messages).await;
             })
             .await;
@@ -728,6 +1022,95 @@ pub async fn register_account(
     bail!("Registration failed: {}", combined.trim());
 }
 
+/// Send a test message from `account` to `recipient` and wait for them to reply.
+/// Used at the end of setup to verify the account can send and receive messages.
+/// Messages to the account's own number land in "Note to Self", so no reply is awaited.
+/// Returns true if the round trip completed before the timeout.
+pub async fn send_test_message(account: &str, recipient: &str, timeout: Duration) -> Result<bool> {
+    let mut daemon = SignalDaemon::start(account).await?;
+    let result = exchange_test_message(&daemon.rpc_url(), account, recipient, timeout).await;
+    daemon.shutdown().await;
+    result
+}
+
+/// Send the setup test message and poll for a reply from the recipient
+async fn exchange_test_message(
+    rpc_url: &str,
+    account: &str,
+    recipient: &str,
+    timeout: Duration,
+) -> Result<bool> {
+    let client = HttpClientBuilder::default()
+        .request_timeout(Duration::from_secs(30))
+        .build(rpc_url)
+        .context("Failed to create JSON-RPC client")?;
+
+    let send = |message: &'static str| {
+        let client = &client;
+        async move {
+            let mut params = ObjectParams::new();
+            params.insert("recipient", vec![recipient])?;
+            params.insert("message", message)?;
+            let _: Value = client
+                .request("send", params)
+                .await
+                .context("Failed to send test message")?;
+            Ok::<_, anyhow::Error>(())
+        }
+    };
+
+    send("Hi! This is a setup check from Cica. Reply to this message to finish setup.").await?;
+
+    if recipient == account {
+        return Ok(true);
+    }
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    while tokio::time::Instant::now() < deadline {
+        for msg in receive_messages(&client).await? {
+            let Some(envelope) = msg.envelope else {
+                continue;
+            };
+
+            let from_recipient = [
+                &envelope.source_number,
+                &envelope.source_uuid,
+                &envelope.source,
+            ]
+            .iter()
+            .any(|s| s.as_deref() == Some(recipient));
+
+            if from_recipient && envelope.data_message.is_some() {
+                send("Got it! Setup check passed.").await?;
+                return Ok(true);
+            }
+        }
+
+        sleep(Duration::from_secs(1)).await;
+    }
+
+    Ok(false)
+}
+
+/// Remove signal-cli's local account data (keys, linked device state, attachments)
+pub fn remove_account_data() -> Result<()> {
+    if SignalDaemon::check_existing().is_some() {
+        bail!("signal-cli daemon is still running. Stop Cica first.");
+    }
+
+    let paths = config::paths()?;
+    if paths.signal_data_dir.exists() {
+        std::fs::remove_dir_all(&paths.signal_data_dir).with_context(|| {
+            format!(
+                "Failed to remove Signal data: {}",
+                paths.signal_data_dir.display()
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
 /// Verify a Signal account with SMS code (called during setup)
 pub async fn verify_account(phone_number: &str, code: &str) -> Result<()> {
     let paths = config::paths()?;
@@ -770,3 +1153,157 @@ pub async fn verify_account(phone_number: &str, code: &str) -> Result<()> {
 
     Ok(())
 }
+
+// ============================================================================
+// Account Management
+// ============================================================================
+
+/// signal-cli's index of local accounts (data/accounts.json)
+#[derive(Debug, Deserialize)]
+struct AccountsFile {
+    #[serde(default)]
+    accounts: Vec<AccountEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountEntry {
+    path: String,
+    number: Option<String>,
+    uuid: Option<String>,
+}
+
+/// The subset of a signal-cli account file we care about
+#[derive(Debug, Deserialize)]
+struct AccountFile {
+    #[serde(default)]
+    registered: bool,
+    #[serde(rename = "deviceId")]
+    device_id: Option<u32>,
+}
+
+/// Primary devices always have device ID 1; linked devices get higher IDs
+const PRIMARY_DEVICE_ID: u32 = 1;
+
+/// Local state of a signal-cli account
+pub struct AccountStatus {
+    pub uuid: Option<String>,
+    /// Device ID (1 = primary/registered, >1 = linked device)
+    pub device_id: Option<u32>,
+    /// False once Signal has revoked the registration or link
+    pub registered: bool,
+}
+
+impl AccountStatus {
+    /// Whether this is a linked (secondary) device rather than a registered primary
+    pub fn is_linked_device(&self) -> bool {
+        self.device_id.is_some_and(|id| id != PRIMARY_DEVICE_ID)
+    }
+}
+
+/// Read the local signal-cli account state for a phone number.
+/// Returns None if signal-cli has no data for it.
+pub fn account_status(phone_number: &str) -> Result<Option<AccountStatus>> {
+    let data_dir = config::paths()?.signal_data_dir.join("data");
+    let accounts_path = data_dir.join("accounts.json");
+
+    if !accounts_path.exists() {
+        return Ok(None);
+    }
+
+    let accounts: AccountsFile = serde_json::from_str(&std::fs::read_to_string(&accounts_path)?)
+        .with_context(|| format!("Failed to parse {}", accounts_path.display()))?;
+
+    let Some(entry) = accounts
+        .accounts
+        .into_iter()
+        .find(|a| a.number.as_deref() == Some(phone_number))
+    else {
+        return Ok(None);
+    };
+
+    let account_path = data_dir.join(&entry.path);
+    let account: AccountFile = serde_json::from_str(&std::fs::read_to_string(&account_path)?)
+        .with_context(|| format!("Failed to parse {}", account_path.display()))?;
+
+    Ok(Some(AccountStatus {
+        uuid: entry.uuid,
+        device_id: account.device_id,
+        registered: account.registered,
+    }))
+}
+
+/// PID of the signal-cli daemon, if one is running
+pub fn daemon_pid() -> Option<u32> {
+    SignalDaemon::check_existing()
+}
+
+/// Unregister a primary account from the Signal server.
+/// Other devices will stop receiving messages for this number.
+pub async fn unregister_account(phone_number: &str) -> Result<()> {
+    if SignalDaemon::check_existing().is_some() {
+        bail!("signal-cli daemon is still running. Stop Cica first.");
+    }
+
+    let paths = config::paths()?;
+    let java = setup::find_java().ok_or_else(|| anyhow!("Java not found"))?;
+    let signal_cli = setup::find_signal_cli().ok_or_else(|| anyhow!("signal-cli not found"))?;
+
+    let java_home = java
+        .parent()
+        .and_then(|p| p.parent())
+        .ok_or_else(|| anyhow!("Could not determine JAVA_HOME"))?;
+
+    info!("Unregistering Signal account {}...", phone_number);
+
+    let output = Command::new(&signal_cli)
+        .args([
+            "-a",
+            phone_number,
+            "--config",
+            paths.signal_data_dir.to_str().unwrap(),
+            "unregister",
+        ])
+        .env("JAVA_HOME", java_home)
+        .env(
+            "PATH",
+            format!(
+                "{}:{}",
+                java.parent().unwrap().display(),
+                std::env::var("PATH").unwrap_or_default()
+            ),
+        )
+        .output()
+        .await
+        .context("Failed to run signal-cli unregister")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("Unregister failed: {}", stderr.trim());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_location_url() {
+        assert_eq!(
+            parse_location_url("https://maps.google.com/maps?q=52.520008%2C13.404954"),
+            Some((52.520008, 13.404954))
+        );
+        assert_eq!(
+            parse_location_url(
+                "I'm here: https://maps.google.com/maps?q=-33.8688,151.2093 see you"
+            ),
+            Some((-33.8688, 151.2093))
+        );
+        assert_eq!(parse_location_url("no location here"), None);
+        assert_eq!(
+            parse_location_url("https://maps.google.com/maps?q=coffee"),
+            None
+        );
+    }
+}
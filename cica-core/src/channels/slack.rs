@@ -0,0 +1,1456 @@
+use anyhow::{Context, Result, anyhow, bail};
+use async_trait::async_trait;
+use slack_morphism::errors::SlackClientError;
+use slack_morphism::prelude::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use super::{
+    COMMANDS, Channel, ChannelInfo, CommandResult, MessageAttachment, SecretSetup, SendLimits,
+    SendQueue, SendRetry, TestMessage, TypingGuard, UnsupportedContent, UserTaskManager,
+    build_export, build_text_with_attachments, cancel_response, daily_digest_now, determine_action,
+    execute_action, execute_claude_query, fire_message_triggers, menu_commands, process_command,
+    process_reaction, recap, reply_unsupported, skill_status, split_message,
+};
+use crate::attachments;
+use crate::config::{ChannelSettings, SlackConfig};
+use crate::cron;
+use crate::i18n;
+use crate::pairing::PairingStore;
+use crate::redact;
+use crate::skills;
+
+// ============================================================================
+// File/Image Handling
+// ============================================================================
+
+/// Download a file from Slack into the attachment store
+/// Requires the bot token for authentication
+async fn download_slack_file(file: &SlackFile, bot_token: &str) -> Result<PathBuf> {
+    let url = file
+        .url_private_download
+        .as_ref()
+        .or(file.url_private.as_ref())
+        .ok_or_else(|| anyhow::anyhow!("No download URL for file"))?;
+
+    let extension = file
+        .name
+        .as_deref()
+        .and_then(|name| name.rsplit_once('.'))
+        .map_or("", |(_, ext)| ext);
+
+    // Download with authorization header
+    let client = reqwest::Client::new();
+    let response = client
+        .get(url.as_str())
+        .header("Authorization", format!("Bearer {}", bot_token))
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        anyhow::bail!("Failed to download file: {}", response.status());
+    }
+
+    let bytes = response.bytes().await?;
+    let local_path = attachments::store(&bytes, extension)?;
+
+    info!("Downloaded Slack file to {:?}", local_path);
+    Ok(local_path)
+}
+
+/// Check if a file is an image based on mimetype
+fn is_image_file(file: &SlackFile) -> bool {
+    file.mimetype
+        .as_ref()
+        .map(|m| m.to_string().starts_with("image/"))
+        .unwrap_or(false)
+}
+
+/// Set suggested prompts for a new thread based on available skills
+async fn set_suggested_prompts(
+    client: &Arc<SlackHyperClient>,
+    token: &SlackApiToken,
+    channel_id: &SlackChannelId,
+    thread_ts: &SlackTs,
+) {
+    let session = client.open_session(token);
+
+    // Build prompts from available skills (up to 4, Slack's limit)
+    let mut prompts = Vec::new();
+
+    // Add a default "What can you do?" prompt
+    prompts.push(SlackAssistantPrompt::new(
+        "What can you help me with?".to_string(),
+        "What can you help me with?".to_string(),
+    ));
+
+    // Add skills as prompts titled by their descriptions, calling them by
+    // /name so they get their permissions
+    if let Ok(available_skills) = skills::discover_skills() {
+        for skill in available_skills.iter().take(3) {
+            // Leave room for default prompt
+            prompts.push(SlackAssistantPrompt::new(
+                skill.description.clone(),
+                format!("/{} {}", skill.name, skill.description),
+            ));
+        }
+    }
+
+    let request = SlackApiAssistantThreadsSetSuggestedPromptsRequest::new(
+        channel_id.clone(),
+        thread_ts.clone(),
+        prompts,
+    );
+
+    if let Err(e) = session
+        .assistant_threads_set_suggested_prompts(&request)
+        .await
+    {
+        warn!("Failed to set suggested prompts: {}", e);
+    }
+}
+
+// ============================================================================
+// Markdown to Slack mrkdwn conversion
+// ============================================================================
+
+/// Convert standard Markdown to Slack's mrkdwn format
+fn markdown_to_mrkdwn(text: &str) -> String {
+    let mut result = text.to_string();
+
+    // Convert bold: **text** -> *text*
+    // Be careful not to convert already-correct single asterisks
+    // Use a simple approach: replace ** with a placeholder, then convert
+    result = result.replace("**", "\x00BOLD\x00");
+    result = result.replace("\x00BOLD\x00", "*");
+
+    // Convert italic: *text* -> _text_ (but only single asterisks not part of bold)
+    // This is tricky because * is used for bold in mrkdwn
+    // Skip this for now as it can conflict with bullet points
+
+    // Convert links: [text](url) -> <url|text>
+    let link_re = regex_lite::Regex::new(r"\[([^\]]+)\]\(([^)]+)\)").unwrap();
+    result = link_re.replace_all(&result, "<$2|$1>").to_string();
+
+    // Convert inline code: `code` stays the same in Slack
+    // Convert code blocks: ```code``` stays the same in Slack
+
+    result
+}
+
+// ============================================================================
+// Channel Implementation
+// ============================================================================
+
+/// Slack allows roughly one message per second per channel
+const SEND_LIMITS: SendLimits = SendLimits {
+    global_interval: Duration::from_millis(100),
+    per_chat_interval: Duration::from_secs(1),
+};
+
+/// Fallback wait when Slack rate-limits us without a Retry-After header
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Slack truncates very long messages; it recommends keeping text under 4,000 characters
+const MAX_MESSAGE_CHARS: usize = 4000;
+
+/// Slack channel implementation for AI Assistant threads
+pub struct SlackChannel {
+    client: Arc<SlackHyperClient>,
+    token: SlackApiToken,
+    /// The DM channel ID
+    channel_id: SlackChannelId,
+    /// Thread timestamp - required for AI Assistant apps to reply in the correct thread
+    thread_ts: Option<SlackTs>,
+    send_queue: Arc<SendQueue>,
+}
+
+impl SlackChannel {
+    pub fn new(
+        client: Arc<SlackHyperClient>,
+        token: SlackApiToken,
+        channel_id: SlackChannelId,
+        thread_ts: Option<SlackTs>,
+        send_queue: Arc<SendQueue>,
+    ) -> Self {
+        Self {
+            client,
+            token,
+            channel_id,
+            thread_ts,
+            send_queue,
+        }
+    }
+}
+
+/// Create the outgoing send queue for Slack.
+/// Share one queue between everything that sends so rate limits apply across all channels.
+pub fn send_queue() -> Arc<SendQueue> {
+    SendQueue::new("slack", SEND_LIMITS, classify_send_error)
+}
+
+/// Slack's entry in the channel registry
+pub const CHANNEL: ChannelInfo = ChannelInfo {
+    name: "slack",
+    display_name: "Slack",
+    settings: |config| {
+        config.channels.slack.as_ref().map(|c| ChannelSettings {
+            auto_approve: c.auto_approve,
+            shared_identity: c.shared_identity,
+            onboarding_prompt: c.onboarding_prompt.clone(),
+            skip_permissions: c.skip_permissions,
+            persona: c.persona.clone(),
+        })
+    },
+    remove_config: |config| config.channels.slack.take().is_some(),
+    missing_fields: |config| {
+        let Some(c) = &config.channels.slack else {
+            return Vec::new();
+        };
+        [("bot_token", &c.bot_token), ("app_token", &c.app_token)]
+            .into_iter()
+            .filter(|(_, value)| value.trim().is_empty())
+            .map(|(name, _)| name)
+            .collect()
+    },
+    send_queue,
+    run: |config, send_queue| {
+        let slack_config = config.channels.slack.unwrap_or_default();
+        Box::pin(run(slack_config, send_queue))
+    },
+    send_to_user: |config, channel_id, message, _actions| {
+        let token = config.channels.slack.map(|c| c.bot_token);
+        Box::pin(async move {
+            let Some(token) = token else {
+                bail!("Slack not configured");
+            };
+
+            let client = SlackClient::new(SlackClientHyperConnector::new()?);
+            let token = SlackApiToken::new(token.into());
+            let session = client.open_session(&token);
+
+            let request = SlackApiChatPostMessageRequest::new(
+                channel_id.into(),
+                SlackMessageContent::new().with_text(message),
+            );
+
+            session.chat_post_message(&request).await?;
+            Ok(())
+        })
+    },
+    open: |config, user_id, send_queue| {
+        let token = config.channels.slack.map(|c| c.bot_token);
+        Box::pin(async move {
+            let Some(token) = token else {
+                bail!("Slack not configured");
+            };
+
+            let client = SlackClient::new(SlackClientHyperConnector::new()?);
+            let token = SlackApiToken::new(token.into());
+
+            // Files can only be shared to a conversation, not a user ID
+            let request = SlackApiConversationsOpenRequest::new().with_users(vec![user_id.into()]);
+            let channel_id = client
+                .open_session(&token)
+                .conversations_open(&request)
+                .await
+                .context("Failed to open a DM")?
+                .channel
+                .id;
+
+            let channel: Arc<dyn Channel> = Arc::new(SlackChannel::new(
+                Arc::new(client),
+                token,
+                channel_id,
+                None,
+                send_queue,
+            ));
+            Ok(channel)
+        })
+    },
+    secret_setup: Some(SecretSetup {
+        flags: &["bot-token", "app-token"],
+        configure: |mut config, secrets| {
+            Box::pin(async move {
+                let [bot_token, app_token] = <[String; 2]>::try_from(secrets)
+                    .map_err(|_| anyhow!("Slack needs a bot token and an app-level token"))?;
+                let bot_user_id = validate_credentials(&bot_token, &app_token)
+                    .await
+                    .map_err(|e| anyhow!("Invalid credentials: {}", e))?;
+                config.channels.slack = Some(SlackConfig::new(bot_token, app_token));
+                Ok((config, bot_user_id))
+            })
+        },
+    }),
+    test_message: Some(TestMessage {
+        recipient_help: Some(
+            "To find your member ID: click your profile picture → Profile → ⋮ → Copy member ID",
+        ),
+        recipient_prompt: Some("Your Slack member ID (e.g., U0123456789)"),
+        instructions: None,
+        waiting: "Sending you a DM and waiting for your reply... ",
+        run: |config, user_id, timeout| {
+            let token = config.channels.slack.map(|c| c.bot_token);
+            Box::pin(async move {
+                let token = token.ok_or_else(|| anyhow!("Slack not configured"))?;
+                send_test_message(&token, &user_id, timeout).await
+            })
+        },
+    }),
+    remove_cleanup: None,
+};
+
+/// Upload a file, to be shared in a channel with `files_complete_upload_external`
+async fn upload_file(
+    client: &SlackHyperClient,
+    token: &SlackApiToken,
+    path: &Path,
+) -> Result<SlackApiFilesComplete> {
+    let session = client.open_session(token);
+
+    let file_bytes = std::fs::read(path)?;
+    let filename = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("file")
+        .to_string();
+
+    let get_url_req =
+        SlackApiFilesGetUploadUrlExternalRequest::new(filename.clone(), file_bytes.len());
+
+    let get_url_resp = session
+        .get_upload_url_external(&get_url_req)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to get upload URL: {}", e))?;
+
+    let content_type = mime_guess::from_path(path)
+        .first_or_octet_stream()
+        .to_string();
+
+    let upload_req =
+        SlackApiFilesUploadViaUrlRequest::new(get_url_resp.upload_url, file_bytes, content_type);
+
+    session
+        .files_upload_via_url(&upload_req)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to upload file: {}", e))?;
+
+    Ok(SlackApiFilesComplete::new(get_url_resp.file_id).with_title(filename))
+}
+
+/// Decide whether a failed Slack API call should be retried
+fn classify_send_error(err: &anyhow::Error) -> SendRetry {
+    match err.downcast_ref::<SlackClientError>() {
+        Some(SlackClientError::RateLimitError(e)) => {
+            SendRetry::After(e.retry_after.unwrap_or(DEFAULT_RETRY_AFTER))
+        }
+        Some(SlackClientError::HttpError(e)) if e.status_code.is_server_error() => {
+            SendRetry::Backoff
+        }
+        Some(SlackClientError::HttpProtocolError(_)) => SendRetry::Backoff,
+        _ => SendRetry::Never,
+    }
+}
+
+#[async_trait]
+impl Channel for SlackChannel {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Slack"
+    }
+
+    async fn send_message(&self, message: &str) -> Result<()> {
+        info!(
+            "Sending message to channel {} (thread: {:?})",
+            self.channel_id, self.thread_ts
+        );
+        let session = self.client.open_session(&self.token);
+
+        // Convert markdown to Slack's mrkdwn format
+        let mrkdwn_message = markdown_to_mrkdwn(&redact::redact(message));
+
+        for chunk in split_message(&mrkdwn_message, MAX_MESSAGE_CHARS) {
+            // Build request with thread_ts if available (required for AI Assistant apps)
+            let mut request = SlackApiChatPostMessageRequest::new(
+                self.channel_id.clone(),
+                SlackMessageContent::new().with_text(chunk),
+            );
+
+            // Reply in the thread if we have a thread_ts
+            if let Some(ts) = &self.thread_ts {
+                request = request.with_thread_ts(ts.clone());
+            }
+
+            debug!("Request: {:?}", request);
+
+            self.send_queue
+                .send(self.channel_id.as_ref(), || async {
+                    let response = session.chat_post_message(&request).await?;
+                    info!("Message sent successfully, ts: {:?}", response.ts);
+                    Ok(())
+                })
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn send_message_with_attachments(
+        &self,
+        message: &str,
+        attachment_paths: &[PathBuf],
+    ) -> Result<()> {
+        // If no attachments, just send the text message
+        if attachment_paths.is_empty() {
+            return self.send_message(message).await;
+        }
+
+        let session = self.client.open_session(&self.token);
+
+        let mut uploaded_files = Vec::new();
+
+        for path in attachment_paths {
+            if !path.exists() {
+                warn!("Attachment path does not exist: {:?}", path);
+                continue;
+            }
+
+            uploaded_files.push(upload_file(&self.client, &self.token, path).await?);
+        }
+
+        if uploaded_files.is_empty() {
+            if !message.is_empty() {
+                return self.send_message(message).await;
+            }
+            return Ok(());
+        }
+
+        let mut complete_req = SlackApiFilesCompleteUploadExternalRequest::new(uploaded_files)
+            .with_channel_id(self.channel_id.clone());
+
+        if !message.is_empty() {
+            let mrkdwn_message = markdown_to_mrkdwn(&redact::redact(message));
+            complete_req = complete_req.with_initial_comment(mrkdwn_message);
+        }
+
+        if let Some(ts) = &self.thread_ts {
+            complete_req = complete_req.with_thread_ts(ts.clone());
+        }
+
+        self.send_queue
+            .send(self.channel_id.as_ref(), || async {
+                session
+                    .files_complete_upload_external(&complete_req)
+                    .await
+                    .context("Failed to complete file upload")?;
+                Ok(())
+            })
+            .await?;
+
+        info!("Sent message with attachments to Slack");
+        Ok(())
+    }
+
+    async fn send_file(&self, path: &Path) -> Result<()> {
+        self.send_message_with_attachments("", &[path.to_path_buf()])
+            .await
+    }
+
+    fn start_typing(&self) -> TypingGuard {
+        // For Slack AI assistants, we use assistant.threads.setStatus
+        // to show a "thinking" indicator
+        if let Some(thread_ts) = &self.thread_ts {
+            let client = self.client.clone();
+            let token = self.token.clone();
+            let channel_id = self.channel_id.clone();
+            let thread_ts = thread_ts.clone();
+
+            // Set the status to show we're working
+            let client_clone = client.clone();
+            let token_clone = token.clone();
+            let channel_id_clone = channel_id.clone();
+            let thread_ts_clone = thread_ts.clone();
+
+            tokio::spawn(async move {
+                let session = client_clone.open_session(&token_clone);
+                let request = SlackApiAssistantThreadsSetStatusRequest::new(
+                    channel_id_clone,
+                    "is thinking...".to_string(),
+                    thread_ts_clone,
+                );
+                if let Err(e) = session.assistant_threads_set_status(&request).await {
+                    warn!("Failed to set assistant status: {}", e);
+                }
+            });
+
+            // Return a guard that will clear the status when dropped
+            // We use a custom approach since TypingGuard expects a oneshot channel
+            let (cancel_tx, cancel_rx) = tokio::sync::oneshot::channel();
+
+            // Spawn a task that clears status when cancelled
+            tokio::spawn(async move {
+                // Wait for the guard to be dropped
+                let _ = cancel_rx.await;
+
+                // Clear the status
+                let session = client.open_session(&token);
+                let request = SlackApiAssistantThreadsSetStatusRequest::new(
+                    channel_id,
+                    String::new(),
+                    thread_ts,
+                );
+                let _ = session.assistant_threads_set_status(&request).await;
+            });
+
+            TypingGuard::new(cancel_tx)
+        } else {
+            TypingGuard::noop()
+        }
+    }
+}
+
+// ============================================================================
+// User State for Socket Mode
+// ============================================================================
+
+/// State passed to socket mode event handlers
+struct SlackUserState {
+    bot_token: SlackApiToken,
+    /// Raw bot token string for file downloads (requires auth header)
+    bot_token_str: String,
+    bot_user_id: SlackUserId,
+    task_manager: Arc<UserTaskManager>,
+    /// Track the last thread_ts per user to detect "New Chat" clicks
+    /// When thread_ts changes, we clear the Claude session
+    user_threads: Arc<RwLock<HashMap<String, String>>>,
+    /// Channel IDs where Cica may respond outside DMs
+    allowed_channels: Arc<Vec<String>>,
+    send_queue: Arc<SendQueue>,
+}
+
+/// Check if a Slack channel ID refers to a direct message (includes AI Assistant threads)
+fn is_dm_channel(channel_id: &SlackChannelId) -> bool {
+    channel_id.to_string().starts_with('D')
+}
+
+/// Check if a non-DM channel is in the allowlist
+fn is_allowed_channel(allowed_channels: &[String], channel_id: &SlackChannelId) -> bool {
+    let id = channel_id.to_string();
+    allowed_channels.contains(&id)
+}
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Validate Slack credentials by calling auth.test
+/// Returns the bot user ID on success
+pub async fn validate_credentials(bot_token: &str, app_token: &str) -> Result<String> {
+    // Ensure rustls crypto provider is installed
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+
+    // Validate bot token
+    let client = Arc::new(SlackClient::new(SlackClientHyperConnector::new()?));
+    let token = SlackApiToken::new(bot_token.into());
+    let session = client.open_session(&token);
+
+    let response = session.auth_test().await?;
+    let bot_user_id = response.user_id.to_string();
+
+    // Validate app token format (basic check)
+    if !app_token.starts_with("xapp-") {
+        anyhow::bail!("App token should start with 'xapp-'");
+    }
+
+    Ok(bot_user_id)
+}
+
+/// How often to poll the DM for a reply during the setup check
+const TEST_REPLY_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Send a test DM to a user and wait for them to reply.
+/// Used at the end of setup to verify the bot can send and receive messages.
+/// Returns true if the user replied before the timeout.
+pub async fn send_test_message(bot_token: &str, user_id: &str, timeout: Duration) -> Result<bool> {
+    // Ensure rustls crypto provider is installed
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+
+    let client = SlackClient::new(SlackClientHyperConnector::new()?);
+    let token = SlackApiToken::new(bot_token.into());
+    let session = client.open_session(&token);
+    let user_id = SlackUserId::new(user_id.to_string());
+
+    // Open (or reuse) the DM with the user
+    let open_req = SlackApiConversationsOpenRequest::new().with_users(vec![user_id.clone()]);
+    let channel_id = session.conversations_open(&open_req).await?.channel.id;
+
+    let sent = session
+        .chat_post_message(&SlackApiChatPostMessageRequest::new(
+            channel_id.clone(),
+            SlackMessageContent::new().with_text(
+                "Hi! This is a setup check from Cica. Reply to this message to finish setup."
+                    .to_string(),
+            ),
+        ))
+        .await?;
+
+    // Poll the DM history for a reply from the user
+    let history_req = SlackApiConversationsHistoryRequest::new()
+        .with_channel(channel_id.clone())
+        .with_oldest(sent.ts);
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    while tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(TEST_REPLY_POLL_INTERVAL).await;
+
+        let history = session.conversations_history(&history_req).await?;
+        if history
+            .messages
+            .iter()
+            .any(|m| m.sender.user.as_ref() == Some(&user_id))
+        {
+            session
+                .chat_post_message(&SlackApiChatPostMessageRequest::new(
+                    channel_id,
+                    SlackMessageContent::new().with_text("Got it! Setup check passed.".to_string()),
+                ))
+                .await?;
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Run the Slack bot using Socket Mode
+pub async fn run(config: SlackConfig, send_queue: Arc<SendQueue>) -> Result<()> {
+    // Ensure rustls crypto provider is installed
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+
+    info!("Starting Slack bot...");
+
+    let client = Arc::new(SlackClient::new(SlackClientHyperConnector::new()?));
+    let bot_token = SlackApiToken::new(config.bot_token.clone().into());
+    let app_token = SlackApiToken::new(config.app_token.clone().into());
+
+    // Get bot user ID to filter out own messages
+    let session = client.open_session(&bot_token);
+    let auth_response = session.auth_test().await?;
+    let bot_user_id = auth_response.user_id.clone();
+    info!("Connected as bot user: {}", bot_user_id);
+
+    // Create shared task manager for per-user message handling
+    let task_manager = UserTaskManager::global();
+
+    // Create user state
+    let user_state = SlackUserState {
+        bot_token: bot_token.clone(),
+        bot_token_str: config.bot_token.clone(),
+        bot_user_id,
+        task_manager,
+        user_threads: Arc::new(RwLock::new(HashMap::new())),
+        allowed_channels: Arc::new(config.allowed_channels.clone()),
+        send_queue,
+    };
+
+    // Set up Socket Mode client with callbacks
+    let socket_mode_callbacks = SlackSocketModeListenerCallbacks::new()
+        .with_push_events(handle_push_events)
+        .with_interaction_events(handle_interaction_events)
+        .with_command_events(handle_command_events);
+
+    let listener_environment = Arc::new(
+        SlackClientEventsListenerEnvironment::new(client.clone()).with_user_state(user_state),
+    );
+
+    let socket_mode_listener = SlackClientSocketModeListener::new(
+        &SlackClientSocketModeConfig::new(),
+        listener_environment,
+        socket_mode_callbacks,
+    );
+
+    socket_mode_listener.listen_for(&app_token).await?;
+    socket_mode_listener.serve().await;
+
+    Ok(())
+}
+
+// ============================================================================
+// Event Handlers
+// ============================================================================
+
+async fn handle_push_events(
+    event: SlackPushEventCallback,
+    client: Arc<SlackHyperClient>,
+    user_state_storage: SlackClientEventsUserState,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let SlackPushEventCallback { event, .. } = event;
+
+    match event {
+        SlackEventCallbackBody::Message(msg_event) => {
+            // Get user state
+            let states = user_state_storage.read().await;
+            let user_state = states
+                .get_user_state::<SlackUserState>()
+                .ok_or("Missing user state")?;
+
+            // Spawn message handling in background so we ack the event immediately
+            // This prevents Slack from retrying delivery
+            let bot_token = user_state.bot_token.clone();
+            let bot_token_str = user_state.bot_token_str.clone();
+            let bot_user_id = user_state.bot_user_id.clone();
+            let task_manager = user_state.task_manager.clone();
+            let user_threads = user_state.user_threads.clone();
+            let allowed_channels = user_state.allowed_channels.clone();
+            let send_queue = user_state.send_queue.clone();
+
+            tokio::spawn(async move {
+                if let Err(e) = handle_message_event(
+                    msg_event,
+                    client,
+                    bot_token,
+                    bot_token_str,
+                    bot_user_id,
+                    task_manager,
+                    user_threads,
+                    allowed_channels,
+                    send_queue,
+                )
+                .await
+                {
+                    warn!("Error handling Slack message: {}", e);
+                }
+            });
+        }
+        SlackEventCallbackBody::AssistantThreadStarted(thread_event) => {
+            // User opened the assistant - send suggested prompts immediately
+            let states = user_state_storage.read().await;
+            let user_state = states
+                .get_user_state::<SlackUserState>()
+                .ok_or("Missing user state")?;
+
+            let token = user_state.bot_token.clone();
+            let channel_id = thread_event.assistant_thread.channel_id.clone();
+            let thread_ts = thread_event.assistant_thread.thread_ts.clone();
+
+            tokio::spawn(async move {
+                set_suggested_prompts(&client, &token, &channel_id, &thread_ts).await;
+            });
+        }
+        SlackEventCallbackBody::AppMention(mention_event) => {
+            let states = user_state_storage.read().await;
+            let user_state = states
+                .get_user_state::<SlackUserState>()
+                .ok_or("Missing user state")?;
+
+            let bot_token = user_state.bot_token.clone();
+            let bot_token_str = user_state.bot_token_str.clone();
+            let task_manager = user_state.task_manager.clone();
+            let user_threads = user_state.user_threads.clone();
+            let allowed_channels = user_state.allowed_channels.clone();
+            let send_queue = user_state.send_queue.clone();
+
+            tokio::spawn(async move {
+                if let Err(e) = handle_app_mention_event(
+                    mention_event,
+                    client,
+                    bot_token,
+                    bot_token_str,
+                    task_manager,
+                    user_threads,
+                    allowed_channels,
+                    send_queue,
+                )
+                .await
+                {
+                    warn!("Error handling Slack app mention: {}", e);
+                }
+            });
+        }
+        SlackEventCallbackBody::ReactionAdded(reaction_event) => {
+            let states = user_state_storage.read().await;
+            let user_state = states
+                .get_user_state::<SlackUserState>()
+                .ok_or("Missing user state")?;
+
+            // Only reactions to Cica's own messages are feedback
+            if reaction_event.item_user.as_ref() != Some(&user_state.bot_user_id) {
+                return Ok(());
+            }
+            let SlackReactionsItem::Message(message) = reaction_event.item else {
+                return Ok(());
+            };
+            let Some(channel_id) = message.origin.channel else {
+                return Ok(());
+            };
+
+            let token = user_state.bot_token.clone();
+            let send_queue = user_state.send_queue.clone();
+            let user_id = reaction_event.user.to_string();
+            let reaction = reaction_event.reaction.0;
+
+            tokio::spawn(async move {
+                if let Some(response) = process_reaction("slack", &user_id, &reaction) {
+                    let channel = SlackChannel::new(
+                        client,
+                        token,
+                        channel_id,
+                        message.origin.thread_ts,
+                        send_queue,
+                    );
+                    if let Err(e) = channel.send_message(&response).await {
+                        warn!("Failed to answer Slack reaction: {}", e);
+                    }
+                }
+            });
+        }
+        _ => {
+            debug!("Ignoring event type: {:?}", event);
+        }
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn handle_message_event(
+    event: SlackMessageEvent,
+    client: Arc<SlackHyperClient>,
+    token: SlackApiToken,
+    bot_token_str: String,
+    bot_user_id: SlackUserId,
+    task_manager: Arc<UserTaskManager>,
+    user_threads: Arc<RwLock<HashMap<String, String>>>,
+    allowed_channels: Arc<Vec<String>>,
+    send_queue: Arc<SendQueue>,
+) -> Result<()> {
+    // Skip messages from bots (including ourselves)
+    if event.sender.bot_id.is_some() {
+        return Ok(());
+    }
+
+    // Get user ID - skip if none
+    let user_id = match &event.sender.user {
+        Some(id) => id.clone(),
+        None => return Ok(()),
+    };
+
+    // Skip own messages
+    if user_id == bot_user_id {
+        return Ok(());
+    }
+
+    // Get channel ID
+    let channel_id = match &event.origin.channel {
+        Some(id) => id.clone(),
+        None => return Ok(()),
+    };
+
+    // Outside DMs / AI Assistant threads, only respond in allowlisted channels
+    if !is_dm_channel(&channel_id) && !is_allowed_channel(&allowed_channels, &channel_id) {
+        debug!("Ignoring message in non-allowlisted channel {}", channel_id);
+        return Ok(());
+    }
+
+    // Get thread_ts - this is crucial for AI Assistant apps
+    // For AI apps, messages come with a thread_ts that we must reply to
+    let thread_ts = event.origin.thread_ts.clone();
+
+    // Get message text
+    let text = match &event.content {
+        Some(content) => content.text.clone().unwrap_or_default(),
+        None => String::new(),
+    };
+
+    // Download any image files in the message
+    let mut image_paths: Vec<PathBuf> = Vec::new();
+    if let Some(content) = &event.content
+        && let Some(files) = &content.files
+    {
+        for file in files {
+            if is_image_file(file) {
+                match download_slack_file(file, &bot_token_str).await {
+                    Ok(path) => image_paths.push(path),
+                    Err(e) => warn!("Failed to download Slack file: {}", e),
+                }
+            }
+        }
+    }
+
+    // Skip if no text and no images, pointing out files we can't handle
+    if text.is_empty() && image_paths.is_empty() {
+        let has_other_files = event
+            .content
+            .as_ref()
+            .and_then(|c| c.files.as_ref())
+            .is_some_and(|files| files.iter().any(|f| !is_image_file(f)));
+        if has_other_files {
+            let channel = SlackChannel::new(client, token, channel_id, thread_ts, send_queue);
+            return reply_unsupported(&channel, user_id.as_ref(), UnsupportedContent::File).await;
+        }
+        return Ok(());
+    }
+
+    info!(
+        "Message from {} in channel {} (thread: {:?}, ts: {}, subtype: {:?}): {}{}",
+        user_id,
+        channel_id,
+        thread_ts,
+        event.origin.ts,
+        event.subtype,
+        text,
+        if image_paths.is_empty() {
+            String::new()
+        } else {
+            format!(" [{} image(s)]", image_paths.len())
+        }
+    );
+
+    // For Slack AI apps, we key Claude sessions by thread_ts, not just user ID
+    // This allows users to have multiple conversations (threads) with separate contexts
+    // When they return to an old thread via History, we load that thread's Claude session
+    if let Some(ref ts) = thread_ts {
+        let ts_str = ts.to_string();
+
+        // Track current thread for this user (for logging/debugging)
+        let mut threads = user_threads.write().await;
+        let previous_thread = threads.insert(user_id.to_string(), ts_str.clone());
+
+        let is_new_thread = previous_thread.as_ref() != Some(&ts_str);
+        if is_new_thread {
+            if previous_thread.is_some() {
+                info!(
+                    "User {} switched to thread {} (was: {:?})",
+                    user_id, ts_str, previous_thread
+                );
+            } else {
+                info!("User {} started thread {}", user_id, ts_str);
+            }
+        }
+    }
+
+    // Get user info for display name
+    let (username, display_name) = get_user_info(&client, &token, &user_id).await;
+
+    // Create channel wrapper with thread_ts for proper threading
+    let channel: Arc<dyn Channel> = Arc::new(SlackChannel::new(
+        client.clone(),
+        token.clone(),
+        channel_id.clone(),
+        thread_ts.clone(),
+        send_queue,
+    ));
+
+    // For Slack, we use a composite user key that includes thread_ts
+    // This allows each thread to have its own Claude session/context
+    // Format: "user_id" for pairing/approval, "user_id:thread_ts" for sessions
+    let user_id_str = user_id.to_string();
+    let session_user_id = match &thread_ts {
+        Some(ts) => format!("{}:{}", user_id, ts),
+        None => user_id_str.clone(),
+    };
+
+    // Determine what action to take
+    let mut store = PairingStore::load()?;
+    let attachments = MessageAttachment::images(image_paths);
+
+    // Use base user_id for pairing/approval checks (not thread-specific)
+    fire_message_triggers(channel.name(), &user_id_str, &text, &store);
+    let action = determine_action(
+        channel.name(),
+        &user_id_str,
+        &text,
+        &attachments,
+        &mut store,
+        username,
+        display_name,
+    )?;
+
+    // Execute the action - use session_user_id (includes thread) for Claude queries
+    if let Some(query_text) = execute_action(channel.as_ref(), &user_id_str, action).await? {
+        // QueryClaude action - queue with task manager for debouncing
+        let full_text = build_text_with_attachments(&query_text, &attachments);
+        // Use thread-aware key for task manager too
+        let user_key = format!("{}:{}", channel.name(), session_user_id);
+        let channel_clone = channel.clone();
+        let session_user_id_clone = session_user_id.clone();
+
+        task_manager
+            .process_message(user_key, full_text, move |messages| async move {
+                // Use session_user_id so each thread gets its own Claude session
+                execute_claude_query(channel_clone, &session_user_id_clone, messages).await;
+            })
+            .await;
+    }
+
+    Ok(())
+}
+
+/// Handle @mention events in channels
+#[allow(clippy::too_many_arguments)]
+async fn handle_app_mention_event(
+    event: SlackAppMentionEvent,
+    client: Arc<SlackHyperClient>,
+    token: SlackApiToken,
+    bot_token_str: String,
+    task_manager: Arc<UserTaskManager>,
+    user_threads: Arc<RwLock<HashMap<String, String>>>,
+    allowed_channels: Arc<Vec<String>>,
+    send_queue: Arc<SendQueue>,
+) -> Result<()> {
+    let user_id = event.user.clone();
+    let channel_id = event.channel.clone();
+
+    // An empty allowlist means mentions are answered everywhere
+    if !allowed_channels.is_empty() && !is_allowed_channel(&allowed_channels, &channel_id) {
+        debug!("Ignoring mention in non-allowlisted channel {}", channel_id);
+        return Ok(());
+    }
+
+    // Get message text and strip the @mention
+    let text = event
+        .content
+        .text
+        .clone()
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    // Skip empty messages
+    if text.is_empty() {
+        return Ok(());
+    }
+
+    // Determine thread_ts: if this is a reply in a thread, use that thread
+    // Otherwise, use this message's ts to start a new thread
+    let thread_ts = event
+        .origin
+        .thread_ts
+        .clone()
+        .unwrap_or_else(|| event.origin.ts.clone());
+
+    info!(
+        "App mention from {} in channel {} (thread: {}): {}",
+        user_id, channel_id, thread_ts, text
+    );
+
+    // Check if user is approved before proceeding
+    let user_id_str = user_id.to_string();
+    let mut store = PairingStore::load()?;
+
+    if store.is_blocked("slack", &user_id_str) {
+        return Ok(());
+    }
+
+    if !store.is_approved("slack", &user_id_str) {
+        let settings = crate::config::Config::load()
+            .map(|c: crate::config::Config| c.channel_settings("slack"))
+            .unwrap_or_default();
+
+        if !settings.auto_approve {
+            send_ephemeral_message(
+                &client,
+                &token,
+                &channel_id,
+                &user_id,
+                "Hi! I don't recognize you yet. Please send me a direct message to get started.",
+            )
+            .await;
+            return Ok(());
+        }
+
+        // Auto-approve the user
+        let (username, display_name) = get_user_info(&client, &token, &user_id).await;
+        store.auto_approve("slack", &user_id_str, username, display_name)?;
+    }
+
+    let onboarding_complete = crate::onboarding::is_complete_for_user("slack", &user_id_str)?;
+    if !onboarding_complete {
+        send_ephemeral_message(
+            &client,
+            &token,
+            &channel_id,
+            &user_id,
+            "Tip: Send me a direct message to set up your profile for more personalized responses.",
+        )
+        .await;
+    }
+
+    // Track thread for session management
+    {
+        let ts_str = thread_ts.to_string();
+        let mut threads = user_threads.write().await;
+        let previous_thread = threads.insert(user_id.to_string(), ts_str.clone());
+
+        if previous_thread.as_ref() != Some(&ts_str) {
+            info!(
+                "User {} started/joined thread {} in channel {}",
+                user_id, ts_str, channel_id
+            );
+        }
+    }
+
+    // Download any image files in the message
+    let mut image_paths: Vec<PathBuf> = Vec::new();
+    if let Some(files) = &event.content.files {
+        for file in files {
+            if is_image_file(file) {
+                match download_slack_file(file, &bot_token_str).await {
+                    Ok(path) => image_paths.push(path),
+                    Err(e) => warn!("Failed to download Slack file: {}", e),
+                }
+            }
+        }
+    }
+
+    // Create channel wrapper - always reply in thread
+    let channel: Arc<dyn Channel> = Arc::new(SlackChannel::new(
+        client.clone(),
+        token.clone(),
+        channel_id.clone(),
+        Some(thread_ts.clone()),
+        send_queue,
+    ));
+
+    // Session key includes thread for continuity
+    let session_user_id = format!("{}:{}", user_id, thread_ts);
+
+    let full_text = build_text_with_attachments(&text, &MessageAttachment::images(image_paths));
+    let user_key = format!("{}:{}", channel.name(), session_user_id);
+    let channel_clone = channel.clone();
+    let session_user_id_clone = session_user_id.clone();
+
+    task_manager
+        .process_message(user_key, full_text, move |messages| async move {
+            execute_claude_query(channel_clone, &session_user_id_clone, messages).await;
+        })
+        .await;
+
+    Ok(())
+}
+
+/// Send an ephemeral message visible only to a specific user
+async fn send_ephemeral_message(
+    client: &Arc<SlackHyperClient>,
+    token: &SlackApiToken,
+    channel_id: &SlackChannelId,
+    user_id: &SlackUserId,
+    message: &str,
+) {
+    let session = client.open_session(token);
+
+    let request = SlackApiChatPostEphemeralRequest::new(
+        channel_id.clone(),
+        user_id.clone(),
+        SlackMessageContent::new().with_text(redact::redact(message).into_owned()),
+    );
+
+    if let Err(e) = session.chat_post_ephemeral(&request).await {
+        warn!("Failed to send ephemeral message: {}", e);
+    }
+}
+
+/// Get user info from Slack API
+async fn get_user_info(
+    client: &Arc<SlackHyperClient>,
+    token: &SlackApiToken,
+    user_id: &SlackUserId,
+) -> (Option<String>, Option<String>) {
+    let session = client.open_session(token);
+
+    match session
+        .users_info(&SlackApiUsersInfoRequest::new(user_id.clone()))
+        .await
+    {
+        Ok(response) => {
+            let username = response.user.name.clone();
+            let display_name = response
+                .user
+                .profile
+                .as_ref()
+                .and_then(|p| p.display_name.clone())
+                .or_else(|| {
+                    response
+                        .user
+                        .profile
+                        .as_ref()
+                        .and_then(|p| p.real_name.clone())
+                });
+            (username, display_name)
+        }
+        Err(e) => {
+            warn!("Failed to get user info for {}: {}", user_id, e);
+            (None, None)
+        }
+    }
+}
+
+async fn handle_interaction_events(
+    _event: SlackInteractionEvent,
+    _client: Arc<SlackHyperClient>,
+    _user_state_storage: SlackClientEventsUserState,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Handle interactive components (buttons, menus, etc.) if needed
+    debug!("Received interaction event");
+    Ok(())
+}
+
+async fn handle_command_events(
+    event: SlackCommandEvent,
+    client: Arc<SlackHyperClient>,
+    user_state_storage: SlackClientEventsUserState,
+) -> Result<SlackCommandEventResponse, Box<dyn std::error::Error + Send + Sync>> {
+    let (bot_token, send_queue) = {
+        let states = user_state_storage.read().await;
+        let user_state = states
+            .get_user_state::<SlackUserState>()
+            .ok_or("Missing user state")?;
+        (user_state.bot_token.clone(), user_state.send_queue.clone())
+    };
+
+    let user_id = event.user_id.to_string();
+    let text = slash_command_to_text(event.text.as_deref().unwrap_or_default());
+
+    info!(
+        "Slash command {} from {} in channel {}: {}",
+        event.command, user_id, event.channel_id, text
+    );
+
+    let mut store = PairingStore::load()?;
+
+    if store.is_blocked("slack", &user_id) {
+        return Ok(ephemeral_response(""));
+    }
+
+    if !store.is_approved("slack", &user_id) {
+        let settings = crate::config::Config::load()
+            .map(|c: crate::config::Config| c.channel_settings("slack"))
+            .unwrap_or_default();
+
+        if !settings.auto_approve {
+            return Ok(ephemeral_response(
+                "Hi! I don't recognize you yet. Please send me a direct message to get started.",
+            ));
+        }
+
+        let (username, display_name) = get_user_info(&client, &bot_token, &event.user_id).await;
+        store.auto_approve("slack", &user_id, username, display_name)?;
+    }
+
+    let onboarding_complete = crate::onboarding::is_complete_for_user("slack", &user_id)?;
+
+    match process_command(&mut store, "slack", &user_id, &text, onboarding_complete)? {
+        CommandResult::Response(response) => Ok(ephemeral_response(&response)),
+        CommandResult::CronRun(job_id) => {
+            let language = store.language("slack", &user_id).map(str::to_string);
+            let running = i18n::tr(language.as_deref(), "Running job...");
+            // Job runs can take a while; reply via the response URL when done
+            let response_url = event.response_url.clone();
+            tokio::spawn(async move {
+                // The result comes like a scheduled run's
+                let Err(e) = cron::run_now(&job_id, "slack", &user_id).await else {
+                    return;
+                };
+                let response = i18n::tr_args(language.as_deref(), "Job failed: {}", &[&e]);
+
+                let request = SlackApiPostWebhookMessageRequest::new(
+                    SlackMessageContent::new().with_text(markdown_to_mrkdwn(&response)),
+                );
+                if let Err(e) = client.respond_to_event(&response_url, &request).await {
+                    warn!("Failed to send slash command result: {}", e);
+                }
+            });
+            Ok(ephemeral_response(running))
+        }
+        CommandResult::Recap(period) => {
+            let response_url = event.response_url.clone();
+            tokio::spawn(async move {
+                let response = recap("slack", &user_id, period)
+                    .await
+                    .unwrap_or_else(|e| format!("Couldn't build the recap: {}", e));
+
+                let request = SlackApiPostWebhookMessageRequest::new(
+                    SlackMessageContent::new().with_text(markdown_to_mrkdwn(&response)),
+                );
+                if let Err(e) = client.respond_to_event(&response_url, &request).await {
+                    warn!("Failed to send slash command result: {}", e);
+                }
+            });
+            Ok(ephemeral_response("Putting together your recap..."))
+        }
+        CommandResult::DailyDigest => {
+            let response_url = event.response_url.clone();
+            tokio::spawn(async move {
+                let response = daily_digest_now("slack", &user_id).await;
+                let request = SlackApiPostWebhookMessageRequest::new(
+                    SlackMessageContent::new().with_text(markdown_to_mrkdwn(&response)),
+                );
+                if let Err(e) = client.respond_to_event(&response_url, &request).await {
+                    warn!("Failed to send slash command result: {}", e);
+                }
+            });
+            Ok(ephemeral_response("Putting together your digest..."))
+        }
+        CommandResult::SkillStatus { is_owner } => {
+            let response_url = event.response_url.clone();
+            tokio::spawn(async move {
+                let response = skill_status(is_owner).await;
+                let request = SlackApiPostWebhookMessageRequest::new(
+                    SlackMessageContent::new().with_text(markdown_to_mrkdwn(&response)),
+                );
+                if let Err(e) = client.respond_to_event(&response_url, &request).await {
+                    warn!("Failed to send slash command result: {}", e);
+                }
+            });
+            Ok(ephemeral_response("Checking skills..."))
+        }
+        CommandResult::Export(kind) => {
+            let response_url = event.response_url.clone();
+            tokio::spawn(async move {
+                // Sent to the DM, even when asked for in a shared channel
+                let sent = async {
+                    let path = build_export("slack", &user_id, kind).await?;
+                    let channel =
+                        (CHANNEL.open)(crate::config::Config::load()?, user_id.clone(), send_queue)
+                            .await?;
+                    let sent = channel.send_file(&path).await;
+                    let _ = std::fs::remove_file(&path);
+                    sent
+                };
+                let Err(e) = sent.await else {
+                    return;
+                };
+                let request = SlackApiPostWebhookMessageRequest::new(
+                    SlackMessageContent::new().with_text(format!("{:#}", e)),
+                );
+                if let Err(e) = client.respond_to_event(&response_url, &request).await {
+                    warn!("Failed to send slash command result: {}", e);
+                }
+            });
+            Ok(ephemeral_response("Putting together your export..."))
+        }
+        CommandResult::Cancel => Ok(ephemeral_response(
+            &cancel_response("slack", &user_id).await,
+        )),
+        // Slash commands aren't part of a conversation thread to retry in
+        CommandResult::Retry(_) => Ok(ephemeral_response(
+            "To try again, send your message again in our conversation.",
+        )),
+        CommandResult::NotACommand => Ok(ephemeral_response(&format!(
+            "Unknown command: {}\n\nTry `{} commands` to see what's available.",
+            text, event.command
+        ))),
+    }
+}
+
+/// Convert slash command text (e.g. "cron list") into a chat command ("/cron list").
+/// An empty invocation lists the available commands.
+fn slash_command_to_text(text: &str) -> String {
+    let text = text.trim();
+    if text.is_empty() {
+        "/help".to_string()
+    } else if text.starts_with('/') {
+        text.to_string()
+    } else {
+        format!("/{}", text)
+    }
+}
+
+/// The slash command Cica's commands are run through, e.g. "/cica cron list"
+pub const SLASH_COMMAND: &str = "/cica";
+
+/// Slack app manifest for Cica, with every chat command in the slash
+/// command's usage hint so Slack can show them while typing. Paste it under
+/// "Create New App" -> "From an app manifest", or into an existing app's
+/// "App Manifest" page after upgrading.
+pub fn app_manifest(name: &str) -> serde_json::Value {
+    serde_json::json!({
+        "display_information": { "name": name },
+        "features": {
+            "app_home": {
+                "messages_tab_enabled": true,
+                "messages_tab_read_only_enabled": false
+            },
+            "bot_user": { "display_name": name, "always_online": true },
+            "slash_commands": [{
+                "command": SLASH_COMMAND,
+                "description": "Run a command, e.g. help, new or cron list",
+                "usage_hint": slash_usage_hint(),
+                "should_escape": false
+            }]
+        },
+        "oauth_config": {
+            "scopes": {
+                "bot": [
+                    "app_mentions:read",
+                    "chat:write",
+                    "commands",
+                    "files:read",
+                    "files:write",
+                    "im:history",
+                    "im:read",
+                    "im:write",
+                    "reactions:read",
+                    "users:read"
+                ]
+            }
+        },
+        "settings": {
+            "event_subscriptions": { "bot_events": ["app_mention", "message.im", "reaction_added"] },
+            "interactivity": { "is_enabled": true },
+            "socket_mode_enabled": true,
+            "org_deploy_enabled": false,
+            "token_rotation_enabled": false
+        }
+    })
+}
+
+/// Every command with its arguments, as typed after the slash command
+fn slash_usage_hint() -> String {
+    menu_commands(COMMANDS)
+        .iter()
+        .map(|info| info.usage().trim_start_matches('/').to_string())
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// Build an ephemeral slash command response (only visible to the invoking user)
+fn ephemeral_response(message: &str) -> SlackCommandEventResponse {
+    SlackCommandEventResponse::new(
+        SlackMessageContent::new().with_text(markdown_to_mrkdwn(message)),
+    )
+    .with_response_type(SlackMessageResponseType::Ephemeral)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slash_command_to_text() {
+        assert_eq!(slash_command_to_text(""), "/help");
+        assert_eq!(slash_command_to_text("new"), "/new");
+        assert_eq!(slash_command_to_text("  cron list "), "/cron list");
+        assert_eq!(slash_command_to_text("/skills"), "/skills");
+    }
+
+    #[test]
+    fn test_app_manifest() {
+        let manifest = app_manifest("Cica");
+        let command = &manifest["features"]["slash_commands"][0];
+        assert_eq!(command["command"], SLASH_COMMAND);
+
+        let hint = command["usage_hint"].as_str().unwrap();
+        assert!(hint.starts_with("help [command] | status | new | retry"));
+        assert!(hint.contains("recap [today|week]"));
+        // Owner-only commands stay out of the shared hint
+        assert!(!hint.contains("approve"));
+    }
+}
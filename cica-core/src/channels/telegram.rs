@@ -0,0 +1,837 @@
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use teloxide::net::Download;
+use teloxide::prelude::*;
+use teloxide::types::{
+    AllowedUpdate, BotCommand, BotCommandScope, ChatAction, InlineKeyboardButton,
+    InlineKeyboardMarkup, MessageOrigin, MessageReactionUpdated, PhotoSize, ReactionType,
+    Recipient,
+};
+use teloxide::update_listeners::Polling;
+use tokio::sync::oneshot;
+use tracing::{debug, info, warn};
+
+use super::{
+    COMMANDS, Channel, ChannelInfo, MessageAttachment, OWNER_COMMANDS, RemoveCleanup, ReplyAction,
+    SecretSetup, SendLimits, SendQueue, SendRetry, TestMessage, TypingGuard, UnsupportedContent,
+    UserTaskManager, build_text_with_attachments, determine_action, execute_action,
+    execute_claude_query, fire_message_triggers, geocode_locations, menu_commands,
+    process_reaction, reply_unsupported, split_message_utf16,
+};
+use crate::attachments;
+use crate::config::{self, ChannelSettings, TelegramConfig};
+use crate::i18n;
+use crate::pairing::PairingStore;
+use crate::redact;
+
+// ============================================================================
+// Channel Implementation
+// ============================================================================
+
+/// Update kinds Cica handles; everything else is filtered out server-side
+const ALLOWED_UPDATES: &[AllowedUpdate] = &[
+    AllowedUpdate::Message,
+    AllowedUpdate::CallbackQuery,
+    AllowedUpdate::MessageReaction,
+];
+
+/// Telegram allows ~30 messages/s per bot and about one message/s per chat
+const SEND_LIMITS: SendLimits = SendLimits {
+    global_interval: Duration::from_millis(34),
+    per_chat_interval: Duration::from_secs(1),
+};
+
+/// Longest command description Telegram accepts
+const MAX_COMMAND_DESCRIPTION_CHARS: usize = 256;
+
+/// Maximum message length, in the UTF-16 code units Telegram counts
+const MAX_MESSAGE_UNITS: usize = 4096;
+
+/// Maximum media caption length, in UTF-16 code units
+const MAX_CAPTION_UNITS: usize = 1024;
+
+/// Telegram channel implementation
+pub struct TelegramChannel {
+    bot: Bot,
+    chat_id: ChatId,
+    send_queue: Arc<SendQueue>,
+}
+
+impl TelegramChannel {
+    pub fn new(bot: Bot, chat_id: ChatId, send_queue: Arc<SendQueue>) -> Self {
+        Self {
+            bot,
+            chat_id,
+            send_queue,
+        }
+    }
+}
+
+/// Create the outgoing send queue for Telegram.
+/// Share one queue between everything that sends so rate limits apply across all chats.
+pub fn send_queue() -> Arc<SendQueue> {
+    SendQueue::new("telegram", SEND_LIMITS, classify_send_error)
+}
+
+/// Telegram's entry in the channel registry
+pub const CHANNEL: ChannelInfo = ChannelInfo {
+    name: "telegram",
+    display_name: "Telegram",
+    settings: |config| {
+        config.channels.telegram.as_ref().map(|c| ChannelSettings {
+            auto_approve: c.auto_approve,
+            shared_identity: c.shared_identity,
+            onboarding_prompt: c.onboarding_prompt.clone(),
+            skip_permissions: c.skip_permissions,
+            persona: c.persona.clone(),
+        })
+    },
+    remove_config: |config| config.channels.telegram.take().is_some(),
+    missing_fields: |config| match &config.channels.telegram {
+        Some(c) if c.bot_token.trim().is_empty() => vec!["bot_token"],
+        _ => Vec::new(),
+    },
+    send_queue,
+    run: |config, send_queue| {
+        let telegram_config = config.channels.telegram.unwrap_or_default();
+        Box::pin(run(telegram_config, send_queue))
+    },
+    send_to_user: |config, user_id, message, actions| {
+        let token = config.channels.telegram.map(|c| c.bot_token);
+        Box::pin(async move {
+            let Some(token) = token else {
+                bail!("Telegram not configured");
+            };
+            let chat_id: i64 = user_id.parse()?;
+            let mut request = Bot::new(token).send_message(ChatId(chat_id), message);
+            if !actions.is_empty() {
+                request = request.reply_markup(reply_keyboard(&actions));
+            }
+            request.await?;
+            Ok(())
+        })
+    },
+    open: |config, user_id, send_queue| {
+        let token = config.channels.telegram.map(|c| c.bot_token);
+        Box::pin(async move {
+            let Some(token) = token else {
+                bail!("Telegram not configured");
+            };
+            let chat_id: i64 = user_id.parse()?;
+            let channel: Arc<dyn Channel> = Arc::new(TelegramChannel::new(
+                Bot::new(token),
+                ChatId(chat_id),
+                send_queue,
+            ));
+            Ok(channel)
+        })
+    },
+    secret_setup: Some(SecretSetup {
+        flags: &["bot-token"],
+        configure: |mut config, secrets| {
+            Box::pin(async move {
+                let token = secrets.into_iter().next().unwrap_or_default();
+                let username = validate_token(&token)
+                    .await
+                    .map_err(|e| anyhow!("Invalid token: {}", e))?;
+                if let Err(e) = configure_bot(&token).await {
+                    warn!("Couldn't configure the bot's commands: {}", e);
+                }
+                config.channels.telegram = Some(TelegramConfig::new(token));
+                Ok((config, format!("@{}", username)))
+            })
+        },
+    }),
+    test_message: Some(TestMessage {
+        recipient_help: None,
+        recipient_prompt: None,
+        instructions: Some(|config| {
+            let token = config.channels.telegram.map(|c| c.bot_token);
+            Box::pin(async move {
+                let token = token.ok_or_else(|| anyhow!("Telegram not configured"))?;
+                let username = validate_token(&token).await?;
+                Ok(format!(
+                    "Open https://t.me/{} and send your bot any message.",
+                    username
+                ))
+            })
+        }),
+        waiting: "Waiting for your message... ",
+        run: |config, _recipient, timeout| {
+            let token = config.channels.telegram.map(|c| c.bot_token);
+            Box::pin(async move {
+                let token = token.ok_or_else(|| anyhow!("Telegram not configured"))?;
+                Ok(wait_for_test_message(&token, timeout).await?.is_some())
+            })
+        },
+    }),
+    remove_cleanup: Some(RemoveCleanup {
+        prompt: "Also clear the bot's webhook and command menu on Telegram?",
+        choices: ["Yes", "No"],
+        default_yes: true,
+        progress: "Unregistering... ",
+        note: None,
+        run: |config| {
+            let token = config.channels.telegram.map(|c| c.bot_token);
+            Box::pin(async move {
+                let token = token.ok_or_else(|| anyhow!("Telegram not configured"))?;
+                unregister(&token).await
+            })
+        },
+    }),
+};
+
+/// Inline keyboard with one row of quick-reply buttons
+fn reply_keyboard(actions: &[ReplyAction]) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new([actions
+        .iter()
+        .map(|a| InlineKeyboardButton::callback(a.label.clone(), a.command.clone()))
+        .collect::<Vec<_>>()])
+}
+
+/// Decide whether a failed Telegram request should be retried
+fn classify_send_error(err: &anyhow::Error) -> SendRetry {
+    match err.downcast_ref::<teloxide::RequestError>() {
+        Some(teloxide::RequestError::RetryAfter(secs)) => SendRetry::After(secs.duration()),
+        // Gateway errors come back as HTML, which shows up as invalid JSON
+        Some(
+            teloxide::RequestError::Network(_)
+            | teloxide::RequestError::Io(_)
+            | teloxide::RequestError::InvalidJson { .. },
+        ) => SendRetry::Backoff,
+        _ => SendRetry::Never,
+    }
+}
+
+#[async_trait]
+impl Channel for TelegramChannel {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Telegram"
+    }
+
+    async fn send_message(&self, message: &str) -> Result<()> {
+        let message = redact::redact(message);
+        for chunk in split_message_utf16(&message, MAX_MESSAGE_UNITS) {
+            self.send_queue
+                .send(&self.chat_id.to_string(), || async {
+                    self.bot.send_message(self.chat_id, chunk.as_str()).await?;
+                    Ok(())
+                })
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn send_message_with_attachments(
+        &self,
+        message: &str,
+        attachment_paths: &[PathBuf],
+    ) -> Result<()> {
+        use teloxide::types::InputFile;
+
+        let message = &*redact::redact(message);
+
+        // If no attachments, just send the text message
+        if attachment_paths.is_empty() {
+            return self.send_message(message).await;
+        }
+
+        let is_first_attachment = |path: &PathBuf| -> bool {
+            attachment_paths.first().map(|p| p == path).unwrap_or(false)
+        };
+
+        // Long text doesn't fit in a caption, so send it separately afterwards
+        let fits_caption = message.encode_utf16().count() <= MAX_CAPTION_UNITS;
+
+        // Send each attachment using the appropriate Telegram method
+        for path in attachment_paths {
+            if !path.exists() {
+                warn!("Attachment path does not exist: {:?}", path);
+                continue;
+            }
+
+            let caption = if is_first_attachment(path) && !message.is_empty() && fits_caption {
+                Some(message)
+            } else {
+                None
+            };
+
+            self.send_queue
+                .send(&self.chat_id.to_string(), || async {
+                    let input_file = InputFile::file(path);
+                    if is_video_file(path) {
+                        let mut req = self.bot.send_video(self.chat_id, input_file);
+                        if let Some(caption) = caption {
+                            req = req.caption(caption);
+                        }
+                        req.await?;
+                    } else {
+                        let mut req = self.bot.send_photo(self.chat_id, input_file);
+                        if let Some(caption) = caption {
+                            req = req.caption(caption);
+                        }
+                        req.await?;
+                    }
+                    Ok(())
+                })
+                .await?;
+        }
+
+        // If the text didn't go out as a caption, send it on its own
+        let captioned = fits_caption && attachment_paths.first().is_some_and(|p| p.exists());
+        if !message.is_empty() && !captioned {
+            self.send_message(message).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn send_file(&self, path: &Path) -> Result<()> {
+        self.send_queue
+            .send(&self.chat_id.to_string(), || async {
+                self.bot
+                    .send_document(self.chat_id, teloxide::types::InputFile::file(path))
+                    .await?;
+                Ok(())
+            })
+            .await
+    }
+
+    fn start_typing(&self) -> TypingGuard {
+        let (cancel_tx, mut cancel_rx) = oneshot::channel();
+        let bot = self.bot.clone();
+        let chat_id = self.chat_id;
+
+        tokio::spawn(async move {
+            loop {
+                // Send typing indicator
+                let _ = bot.send_chat_action(chat_id, ChatAction::Typing).await;
+
+                // Wait 4 seconds or until cancelled (typing indicator lasts ~5s)
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_secs(4)) => {
+                        // Continue loop, send typing again
+                    }
+                    _ = &mut cancel_rx => {
+                        // Cancelled, stop the loop
+                        break;
+                    }
+                }
+            }
+        });
+
+        TypingGuard::new(cancel_tx)
+    }
+}
+
+// ============================================================================
+// Media Handling
+// ============================================================================
+
+/// Video file extensions supported for sending
+const VIDEO_EXTENSIONS: &[&str] = &[".mp4", ".mov", ".webm", ".avi"];
+
+/// Check if a file path points to a video based on its extension
+fn is_video_file(path: &std::path::Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| {
+            let dot_ext = format!(".{}", ext.to_lowercase());
+            VIDEO_EXTENSIONS.contains(&dot_ext.as_str())
+        })
+        .unwrap_or(false)
+}
+
+/// Download a photo from Telegram into the attachment store
+/// Returns the local file path on success
+async fn download_photo(bot: &Bot, photo: &PhotoSize) -> Result<PathBuf> {
+    let file = bot.get_file(&photo.file.id).await?;
+
+    // Determine extension from the file path
+    let extension = file.path.rsplit_once('.').map_or("jpg", |(_, ext)| ext);
+
+    let mut content = Vec::new();
+    bot.download_file(&file.path, &mut content).await?;
+
+    let local_path = attachments::store(&content, extension)?;
+    info!("Downloaded photo to {:?}", local_path);
+    Ok(local_path)
+}
+
+/// Describe the original sender of a forwarded message
+fn describe_forward_origin(origin: &MessageOrigin) -> String {
+    match origin {
+        MessageOrigin::User { sender_user, .. } => match &sender_user.username {
+            Some(username) => format!("{} (@{})", sender_user.full_name(), username),
+            None => sender_user.full_name(),
+        },
+        MessageOrigin::HiddenUser {
+            sender_user_name, ..
+        } => sender_user_name.clone(),
+        MessageOrigin::Chat { sender_chat, .. } => {
+            sender_chat.title().unwrap_or("a group chat").to_string()
+        }
+        MessageOrigin::Channel {
+            chat, message_id, ..
+        } => {
+            let title = chat.title().unwrap_or("a channel");
+            match chat.username() {
+                Some(username) => format!("{} (https://t.me/{}/{})", title, username, message_id.0),
+                None => title.to_string(),
+            }
+        }
+    }
+}
+
+/// Get the largest photo from a list of photo sizes
+fn get_largest_photo(photos: &[PhotoSize]) -> Option<&PhotoSize> {
+    photos.iter().max_by_key(|p| p.width * p.height)
+}
+
+// ============================================================================
+// Public API
+// ============================================================================
+
+/// Validate a Telegram bot token by calling getMe
+/// Returns the bot username on success
+pub async fn validate_token(token: &str) -> Result<String> {
+    let bot = Bot::new(token);
+    let me = bot.get_me().await?;
+    Ok(me.username().to_string())
+}
+
+/// Prepare the bot for long polling with only the update kinds Cica handles.
+/// Returns true if group privacy mode is enabled.
+///
+/// Privacy mode can only be changed via @BotFather, so callers should advise
+/// the user when it is disabled.
+pub async fn configure_bot(token: &str) -> Result<bool> {
+    let bot = Bot::new(token);
+
+    // Polling doesn't work while a webhook is set
+    bot.delete_webhook().await?;
+
+    // Telegram remembers allowed_updates from the last getUpdates call
+    bot.get_updates()
+        .allowed_updates(ALLOWED_UPDATES.to_vec())
+        .limit(1)
+        .timeout(0)
+        .await?;
+
+    let me = bot.get_me().await?;
+    Ok(!me.can_read_all_group_messages)
+}
+
+/// Unregister the bot from Telegram-side state (webhook and command menu)
+pub async fn unregister(token: &str) -> Result<()> {
+    let bot = Bot::new(token);
+    bot.delete_webhook().await?;
+    bot.delete_my_commands().await?;
+    Ok(())
+}
+
+/// Wait for someone to message the bot, then reply to confirm the round trip.
+/// Used at the end of setup to verify the bot can receive and send messages.
+/// Returns the sender's name, or None if nothing arrived before the timeout.
+pub async fn wait_for_test_message(token: &str, timeout: Duration) -> Result<Option<String>> {
+    use teloxide::types::UpdateKind;
+
+    let bot = Bot::new(token);
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut offset = 0;
+
+    while tokio::time::Instant::now() < deadline {
+        let updates = bot.get_updates().offset(offset).timeout(5).await?;
+
+        for update in updates {
+            offset = update.id.as_offset();
+
+            if let UpdateKind::Message(msg) = update.kind {
+                let name = msg
+                    .from
+                    .as_ref()
+                    .map(|u| u.full_name())
+                    .unwrap_or_else(|| "unknown".to_string());
+
+                bot.send_message(
+                    msg.chat.id,
+                    "Setup check passed! Once Cica is running, send me another message to get started.",
+                )
+                .await?;
+
+                // Acknowledge the update so it isn't delivered again on first run
+                bot.get_updates().offset(offset).timeout(0).await?;
+
+                return Ok(Some(name));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Register the command menu clients autocomplete from: everyone's commands,
+/// plus the owner's in their own chat, in English and each translated language
+async fn register_commands(bot: &Bot) {
+    let owner_chat = config::Config::load()
+        .ok()
+        .and_then(|c| c.owner)
+        .filter(|owner| owner.channel == "telegram")
+        .and_then(|owner| owner.user_id.parse::<i64>().ok());
+
+    let mut scopes = vec![(BotCommandScope::Default, menu_commands(COMMANDS))];
+    if let Some(chat) = owner_chat {
+        scopes.push((
+            BotCommandScope::Chat {
+                chat_id: Recipient::Id(ChatId(chat)),
+            },
+            menu_commands(COMMANDS.iter().chain(OWNER_COMMANDS)),
+        ));
+    }
+
+    let languages = std::iter::once(None).chain(i18n::TRANSLATED.iter().copied().map(Some));
+    for language in languages {
+        for (scope, commands) in &scopes {
+            let commands = commands.iter().map(|info| {
+                let description: String = info
+                    .menu_description(language)
+                    .chars()
+                    .take(MAX_COMMAND_DESCRIPTION_CHARS)
+                    .collect();
+                BotCommand::new(info.name, description)
+            });
+            let mut request = bot.set_my_commands(commands).scope(scope.clone());
+            if let Some(language) = language {
+                request = request.language_code(language);
+            }
+            if let Err(e) = request.await {
+                warn!("Failed to set bot commands: {}", e);
+            }
+        }
+    }
+}
+
+/// Run the Telegram bot
+pub async fn run(config: TelegramConfig, send_queue: Arc<SendQueue>) -> Result<()> {
+    let bot = Bot::new(&config.bot_token);
+
+    info!("Starting Telegram bot...");
+
+    register_commands(&bot).await;
+
+    // Create shared task manager for per-user message handling
+    let task_manager = UserTaskManager::global();
+
+    // Only request the update kinds we handle to reduce long-poll noise
+    let listener = Polling::builder(bot.clone())
+        .allowed_updates(ALLOWED_UPDATES.to_vec())
+        .delete_webhook()
+        .await
+        .build();
+
+    let handler = dptree::entry()
+        .branch(Update::filter_message().endpoint(
+            |bot: Bot,
+             msg: Message,
+             task_manager: Arc<UserTaskManager>,
+             send_queue: Arc<SendQueue>| async move {
+                if let Err(e) = handle_message(&bot, &msg, task_manager, send_queue).await {
+                    warn!("Error handling message: {}", e);
+                }
+                respond(())
+            },
+        ))
+        .branch(Update::filter_callback_query().endpoint(
+            |bot: Bot,
+             query: CallbackQuery,
+             task_manager: Arc<UserTaskManager>,
+             send_queue: Arc<SendQueue>| async move {
+                if let Err(e) = handle_callback_query(&bot, &query, task_manager, send_queue).await
+                {
+                    warn!("Error handling button press: {}", e);
+                }
+                respond(())
+            },
+        ))
+        .branch(Update::filter_message_reaction_updated().endpoint(
+            |bot: Bot, reaction: MessageReactionUpdated, send_queue: Arc<SendQueue>| async move {
+                if let Err(e) = handle_reaction(&bot, &reaction, send_queue).await {
+                    warn!("Error handling reaction: {}", e);
+                }
+                respond(())
+            },
+        ));
+
+    Dispatcher::builder(bot, handler)
+        .dependencies(dptree::deps![task_manager, send_queue])
+        .build()
+        .dispatch_with_listener(
+            listener,
+            LoggingErrorHandler::with_custom_text("Error from the update listener"),
+        )
+        .await;
+
+    Ok(())
+}
+
+// ============================================================================
+// Message Handling
+// ============================================================================
+
+/// Handle an incoming message
+async fn handle_message(
+    bot: &Bot,
+    msg: &Message,
+    task_manager: Arc<UserTaskManager>,
+    send_queue: Arc<SendQueue>,
+) -> Result<()> {
+    // Extract user info
+    let Some(user) = msg.from.as_ref() else {
+        return Ok(());
+    };
+    let sender = Sender::from_user(user);
+
+    // Get text (either from text message or photo caption)
+    let text = msg.text().or(msg.caption()).unwrap_or_default();
+
+    let mut attachments: Vec<MessageAttachment> = Vec::new();
+
+    // Keep track of where forwarded messages came from
+    if let Some(origin) = msg.forward_origin() {
+        attachments.push(MessageAttachment::ForwardedFrom(describe_forward_origin(
+            origin,
+        )));
+    }
+
+    // Download any photos in the message
+    if let Some(photos) = msg.photo()
+        && let Some(largest) = get_largest_photo(photos)
+    {
+        match download_photo(bot, largest).await {
+            Ok(path) => attachments.push(MessageAttachment::Image(path)),
+            Err(e) => warn!("Failed to download photo: {}", e),
+        }
+    }
+
+    // Shared locations and venues
+    if let Some(location) = msg.location() {
+        attachments.push(MessageAttachment::Location {
+            latitude: location.latitude,
+            longitude: location.longitude,
+            label: None,
+            address: None,
+        });
+    } else if let Some(venue) = msg.venue() {
+        attachments.push(MessageAttachment::Location {
+            latitude: venue.location.latitude,
+            longitude: venue.location.longitude,
+            label: Some(venue.title.clone()),
+            address: Some(venue.address.clone()),
+        });
+    }
+
+    // Shared contact cards
+    if let Some(contact) = msg.contact() {
+        let name = match &contact.last_name {
+            Some(last) => format!("{} {}", contact.first_name, last),
+            None => contact.first_name.clone(),
+        };
+        attachments.push(MessageAttachment::Contact {
+            name,
+            phone_numbers: vec![contact.phone_number.clone()],
+            emails: Vec::new(),
+            vcard: contact.vcard.clone(),
+        });
+    }
+
+    // Skip if no text and no attachments, pointing out anything we can't handle
+    if text.is_empty() && attachments.is_empty() {
+        if let Some(content) = unsupported_content(msg) {
+            let channel = TelegramChannel::new(bot.clone(), msg.chat.id, send_queue);
+            return reply_unsupported(&channel, &sender.user_id, content).await;
+        }
+        return Ok(());
+    }
+
+    info!("Message from {}: {}", sender.user_id, text);
+    if !attachments.is_empty() {
+        info!("Message includes attachment(s): {:?}", attachments);
+    }
+
+    dispatch_message(
+        bot,
+        msg.chat.id,
+        sender,
+        text,
+        &attachments,
+        task_manager,
+        send_queue,
+    )
+    .await
+}
+
+/// Kind of content in a message that Cica can't process
+fn unsupported_content(msg: &Message) -> Option<UnsupportedContent> {
+    if msg.sticker().is_some() {
+        Some(UnsupportedContent::Sticker)
+    } else if msg.poll().is_some() {
+        Some(UnsupportedContent::Poll)
+    } else if msg.video_note().is_some() {
+        Some(UnsupportedContent::VideoNote)
+    } else if msg.voice().is_some() {
+        Some(UnsupportedContent::Voice)
+    } else if msg.audio().is_some() {
+        Some(UnsupportedContent::Audio)
+    } else if msg.video().is_some() || msg.animation().is_some() {
+        Some(UnsupportedContent::Video)
+    } else if msg.document().is_some() {
+        Some(UnsupportedContent::File)
+    } else {
+        None
+    }
+}
+
+/// Who sent an incoming message or button press
+struct Sender {
+    user_id: String,
+    username: Option<String>,
+    display_name: Option<String>,
+}
+
+impl Sender {
+    fn from_user(user: &teloxide::types::User) -> Self {
+        Self {
+            user_id: user.id.0.to_string(),
+            username: user.username.clone(),
+            display_name: Some(match &user.last_name {
+                Some(last) => format!("{} {}", user.first_name, last),
+                None => user.first_name.clone(),
+            }),
+        }
+    }
+}
+
+/// Handle a quick-reply button press as if the user had typed its command
+async fn handle_callback_query(
+    bot: &Bot,
+    query: &CallbackQuery,
+    task_manager: Arc<UserTaskManager>,
+    send_queue: Arc<SendQueue>,
+) -> Result<()> {
+    // Stop the button's loading spinner
+    bot.answer_callback_query(query.id.clone()).await?;
+
+    let Some(command) = query.data.as_deref() else {
+        return Ok(());
+    };
+    let Some(message) = &query.message else {
+        return Ok(());
+    };
+
+    info!("Button press from {}: {}", query.from.id, command);
+
+    // Buttons are single-use, so remove them once pressed
+    if let Err(e) = bot
+        .edit_message_reply_markup(message.chat().id, message.id())
+        .await
+    {
+        debug!("Failed to remove buttons: {}", e);
+    }
+
+    dispatch_message(
+        bot,
+        message.chat().id,
+        Sender::from_user(&query.from),
+        command,
+        &[],
+        task_manager,
+        send_queue,
+    )
+    .await
+}
+
+/// Take a 👍/👎 reaction as feedback on the last reply
+async fn handle_reaction(
+    bot: &Bot,
+    reaction: &MessageReactionUpdated,
+    send_queue: Arc<SendQueue>,
+) -> Result<()> {
+    let Some(user) = reaction.user() else {
+        return Ok(());
+    };
+
+    // Only reactions just added count
+    let added = reaction
+        .new_reaction
+        .iter()
+        .filter(|r| !reaction.old_reaction.contains(r));
+    for added in added {
+        if let ReactionType::Emoji { emoji } = added
+            && let Some(response) = process_reaction("telegram", &user.id.0.to_string(), emoji)
+        {
+            TelegramChannel::new(bot.clone(), reaction.chat.id, send_queue.clone())
+                .send_message(&response)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Run an incoming message through pairing, commands and the AI backend
+async fn dispatch_message(
+    bot: &Bot,
+    chat_id: ChatId,
+    sender: Sender,
+    text: &str,
+    attachments: &[MessageAttachment],
+    task_manager: Arc<UserTaskManager>,
+    send_queue: Arc<SendQueue>,
+) -> Result<()> {
+    let Sender {
+        user_id,
+        username,
+        display_name,
+    } = sender;
+
+    // Create channel wrapper
+    let channel: Arc<dyn Channel> =
+        Arc::new(TelegramChannel::new(bot.clone(), chat_id, send_queue));
+
+    // Determine what action to take
+    let mut store = PairingStore::load()?;
+    fire_message_triggers(channel.name(), &user_id, text, &store);
+    let action = determine_action(
+        channel.name(),
+        &user_id,
+        text,
+        attachments,
+        &mut store,
+        username,
+        display_name,
+    )?;
+
+    // Execute the action
+    if let Some(query_text) = execute_action(channel.as_ref(), &user_id, action).await? {
+        // QueryClaude action - queue with task manager for debouncing
+        let attachments = geocode_locations(attachments).await;
+        let full_text = build_text_with_attachments(&query_text, &attachments);
+        let user_key = format!("{}:{}", channel.name(), user_id);
+        let channel_clone = channel.clone();
+        let user_id_clone = user_id.clone();
+
+        task_manager
+            .process_message(user_key, full_text, move |messages| async move {
+                execute_claude_query(channel_clone, &user_id_clone, messages).await;
+            })
+            .await;
+    }
+
+    Ok(())
+}
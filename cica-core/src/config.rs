@@ -0,0 +1,1414 @@
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::channels;
+use crate::pairing::Role;
+use crate::secrets::{self, SecretStore};
+use crate::setup;
+use crate::storage;
+
+// ============================================================================
+// Paths
+// ============================================================================
+
+/// All paths used by Cica
+pub struct Paths {
+    pub base: PathBuf,
+    pub config_file: PathBuf,
+    /// SQLite database with pairing data, sessions and cron jobs
+    pub db_file: PathBuf,
+    /// Pairing store of older versions, imported into the database
+    pub pairing_file: PathBuf,
+    pub memory_dir: PathBuf,
+    pub skills_dir: PathBuf,
+    /// Files cron jobs write, in a directory per job
+    pub cron_dir: PathBuf,
+    // Internal paths (hidden from user)
+    pub internal_dir: PathBuf,
+    /// Files users send, named by their content hash
+    pub attachments_dir: PathBuf,
+    pub deps_dir: PathBuf,
+    pub bun_dir: PathBuf,
+    pub java_dir: PathBuf,
+    pub signal_cli_dir: PathBuf,
+    pub claude_code_dir: PathBuf,
+    pub claude_home: PathBuf,
+    #[cfg_attr(not(feature = "signal"), allow(dead_code))]
+    pub signal_data_dir: PathBuf,
+    // Cursor CLI paths
+    pub cursor_cli_dir: PathBuf,
+    pub cursor_home: PathBuf,
+}
+
+/// Environment variable that moves all of Cica's data, e.g. to a mounted volume
+pub const HOME_ENV: &str = "CICA_HOME";
+
+/// Environment variable that turns on container mode: dependencies the image
+/// provides are used as they are, and backend credentials are read from the
+/// environment when config.toml has none
+pub const CONTAINER_ENV: &str = "CICA_CONTAINER";
+
+/// Whether Cica runs in a container built for it
+pub fn in_container() -> bool {
+    std::env::var_os(CONTAINER_ENV).is_some_and(|v| !v.is_empty() && v != "0")
+}
+
+/// Get all Cica paths
+pub fn paths() -> Result<Paths> {
+    if let Some(home) = std::env::var_os(HOME_ENV).filter(|h| !h.is_empty()) {
+        return Ok(paths_at(PathBuf::from(home)));
+    }
+    let base = ProjectDirs::from("", "", "cica")
+        .map(|dirs| dirs.config_dir().to_path_buf())
+        .context("Could not determine config directory")?;
+    Ok(paths_at(base))
+}
+
+/// The paths for data kept under `base`
+fn paths_at(base: PathBuf) -> Paths {
+    let internal_dir = base.join("internal");
+    let deps_dir = internal_dir.join("deps");
+
+    Paths {
+        config_file: base.join("config.toml"),
+        db_file: base.join("cica.db"),
+        pairing_file: base.join("pairing.json"),
+        memory_dir: base.join("memory"),
+        skills_dir: base.join("skills"),
+        cron_dir: base.join("cron"),
+        // Internal paths
+        internal_dir: internal_dir.clone(),
+        attachments_dir: internal_dir.join("attachments"),
+        deps_dir: deps_dir.clone(),
+        bun_dir: deps_dir.join("bun"),
+        java_dir: deps_dir.join("java"),
+        signal_cli_dir: deps_dir.join("signal-cli"),
+        claude_code_dir: deps_dir.join("claude-code"),
+        claude_home: internal_dir.join("claude-home"),
+        signal_data_dir: internal_dir.join("signal-data"),
+        // Cursor CLI paths
+        cursor_cli_dir: deps_dir.join("cursor-cli"),
+        cursor_home: internal_dir.join("cursor-home"),
+        base,
+    }
+}
+
+impl Paths {
+    /// Create all necessary directories and default files
+    pub fn ensure_dirs(&self) -> Result<()> {
+        std::fs::create_dir_all(&self.base)?;
+        std::fs::create_dir_all(&self.memory_dir)?;
+        std::fs::create_dir_all(&self.skills_dir)?;
+        std::fs::create_dir_all(&self.deps_dir)?;
+        std::fs::create_dir_all(&self.claude_home)?;
+
+        // Create default PERSONA.md if it doesn't exist
+        let persona_path = self.base.join("PERSONA.md");
+        if !persona_path.exists() {
+            let content = r#"# PERSONA.md - Persona & Boundaries
+
+## Tone & Style
+- Keep replies concise and direct.
+- Ask clarifying questions when needed.
+- Be helpful but honest about limitations.
+
+## Capabilities
+You are a personal assistant running on the user's machine. You can:
+- Answer questions and have conversations
+- Help with writing, brainstorming, and thinking through problems
+
+You do NOT have direct access to:
+- Calendars, email, or external services
+- The user's files or system (unless given explicit access)
+- Real-time information
+
+## Skills
+When the user asks for something you can't do directly, suggest creating a **skill** for it.
+Skills are custom extensions that live in the skills/ folder. Each skill has:
+- A SKILL.md file describing what it does
+- Optional scripts to execute actions
+
+Example: "I can't access your calendar directly, but we could create a calendar skill that connects to your calendar service. Want me to help set that up?"
+"#;
+            std::fs::write(&persona_path, content)?;
+        }
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Config Types
+// ============================================================================
+
+/// How much the model thinks before answering
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Effort {
+    Low,
+    Medium,
+    High,
+}
+
+impl Effort {
+    /// Value for `reasoning_effort` in OpenAI-compatible APIs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Effort::Low => "low",
+            Effort::Medium => "medium",
+            Effort::High => "high",
+        }
+    }
+
+    /// Thinking budget for Claude Code (MAX_THINKING_TOKENS)
+    pub fn thinking_tokens(&self) -> u32 {
+        match self {
+            Effort::Low => 4_000,
+            Effort::Medium => 10_000,
+            Effort::High => 31_999,
+        }
+    }
+}
+
+/// Which AI backend to use
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AiBackend {
+    #[default]
+    Claude,
+    Cursor,
+    /// Any OpenAI-compatible chat completions API (OpenAI, OpenRouter, vLLM, LM Studio)
+    #[serde(rename = "openai")]
+    OpenAi,
+}
+
+impl AiBackend {
+    pub const ALL: [AiBackend; 3] = [AiBackend::Claude, AiBackend::Cursor, AiBackend::OpenAi];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AiBackend::Claude => "claude",
+            AiBackend::Cursor => "cursor",
+            AiBackend::OpenAi => "openai",
+        }
+    }
+
+    /// Name shown to people
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            AiBackend::Claude => "Claude Code",
+            AiBackend::Cursor => "Cursor CLI",
+            AiBackend::OpenAi => "OpenAI-compatible API",
+        }
+    }
+}
+
+impl std::str::FromStr for AiBackend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "claude" => Ok(AiBackend::Claude),
+            "cursor" => Ok(AiBackend::Cursor),
+            "openai" => Ok(AiBackend::OpenAi),
+            _ => Err(anyhow::anyhow!(
+                "Unknown backend: {} (expected claude, cursor or openai)",
+                s
+            )),
+        }
+    }
+}
+
+/// Root configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Config {
+    #[serde(default)]
+    pub channels: ChannelsConfig,
+
+    #[serde(default)]
+    pub claude: ClaudeConfig,
+
+    #[serde(default)]
+    pub cursor: CursorConfig,
+
+    #[serde(default)]
+    pub openai: OpenAiConfig,
+
+    /// Which AI backend to use (claude, cursor or openai)
+    #[serde(default)]
+    pub backend: AiBackend,
+
+    /// Global onboarding prompt (can be overridden per channel)
+    pub onboarding_prompt: Option<String>,
+
+    /// Look up street addresses for shared locations (sends coordinates to OpenStreetMap)
+    #[serde(default)]
+    pub reverse_geocode: bool,
+
+    /// Who receives system notifications (e.g. repeated channel crashes)
+    pub owner: Option<OwnerConfig>,
+
+    /// What members and guests are allowed to do
+    #[serde(default)]
+    pub roles: RolesConfig,
+
+    /// Pairing code settings
+    #[serde(default)]
+    pub pairing: PairingConfig,
+
+    /// Memory search settings
+    #[serde(default)]
+    pub memory: MemoryConfig,
+
+    /// Limits for queries to the AI backend
+    #[serde(default)]
+    pub queries: QueryConfig,
+
+    /// What the agent may do on the host
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+
+    /// Where tokens and API keys are kept
+    #[serde(default)]
+    pub secrets: SecretsConfig,
+
+    /// Where skills are installed from
+    #[serde(default)]
+    pub skills: SkillsConfig,
+
+    /// MCP servers the agent can use
+    #[serde(default)]
+    pub mcp: McpConfig,
+
+    /// Built-in web search, for backends without their own
+    #[serde(default)]
+    pub search: SearchConfig,
+
+    /// HTTP endpoint for health checks and Prometheus metrics
+    #[serde(default)]
+    pub monitor: MonitorConfig,
+
+    /// Secrets masked in outgoing messages and logs
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+
+    /// How long files users send are kept
+    #[serde(default)]
+    pub attachments: AttachmentsConfig,
+
+    /// Secrets read from environment variables (`env:<VAR>` in config.toml),
+    /// by name, so saving writes the reference back instead of the value
+    #[serde(skip)]
+    env_secrets: BTreeMap<&'static str, String>,
+}
+
+/// Where skills are installed from
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SkillsConfig {
+    /// Git repository with a directory per skill, for installing skills by
+    /// name (`cica skill install <name>`)
+    pub registry: Option<String>,
+}
+
+/// MCP servers passed to Claude Code and Cursor, under [mcp.servers.<name>]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct McpConfig {
+    #[serde(default)]
+    pub servers: BTreeMap<String, McpServer>,
+}
+
+impl McpConfig {
+    /// Write the servers to `path` for Claude Code or Cursor. Only Cica's
+    /// user can read it, since server environments hold tokens.
+    pub fn write_to(&self, path: &Path) -> Result<()> {
+        storage::write_private(path, &serde_json::to_string_pretty(&self.to_json())?)
+    }
+
+    /// The servers in the format Claude Code and Cursor read MCP config in
+    pub fn to_json(&self) -> serde_json::Value {
+        let servers: serde_json::Map<String, serde_json::Value> = self
+            .servers
+            .iter()
+            .map(|(name, server)| {
+                (
+                    name.clone(),
+                    serde_json::json!({
+                        "command": server.command,
+                        "args": server.args,
+                        "env": server.env,
+                    }),
+                )
+            })
+            .collect();
+        serde_json::json!({ "mcpServers": servers })
+    }
+
+    /// Names that work as an MCP server name in tool names (mcp__<name>__<tool>)
+    pub fn is_valid_name(name: &str) -> bool {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+    }
+}
+
+/// An MCP server started over stdio
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct McpServer {
+    /// Program to run, e.g. "npx"
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// What it's for, told to the agent
+    pub description: Option<String>,
+    /// Its tools and what they do, as listed when it was added
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tools: BTreeMap<String, String>,
+}
+
+/// Web search service behind the built-in web_search tool
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchProvider {
+    /// A SearxNG instance, with the JSON format enabled
+    Searxng,
+    /// The Brave Search API
+    Brave,
+}
+
+/// Built-in web search, used by the OpenAI-compatible backend (Claude Code
+/// and Cursor search the web themselves)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SearchConfig {
+    /// searxng or brave; unset turns the search off
+    pub provider: Option<SearchProvider>,
+    /// Base URL of the SearxNG instance (e.g. "http://localhost:8080")
+    pub url: Option<String>,
+    /// Brave Search API key
+    pub api_key: Option<String>,
+}
+
+impl SearchConfig {
+    /// Whether the configured provider has what it needs
+    pub fn is_configured(&self) -> bool {
+        match self.provider {
+            Some(SearchProvider::Searxng) => self.url.as_deref().is_some_and(|u| !u.is_empty()),
+            Some(SearchProvider::Brave) => self.api_key.as_deref().is_some_and(|k| !k.is_empty()),
+            None => false,
+        }
+    }
+}
+
+/// HTTP endpoint serving /healthz and /metrics
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MonitorConfig {
+    /// Address to listen on, e.g. "127.0.0.1:9464"; unset turns it off
+    pub listen: Option<String>,
+}
+
+/// Secrets masked in outgoing messages and logs, so a skill or cron job
+/// echoing one doesn't post it to a chat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    /// Mask API keys, tokens, private keys and card numbers, along with
+    /// Cica's own tokens and `secrets`
+    #[serde(default = "RedactionConfig::default_enabled")]
+    pub enabled: bool,
+
+    /// More strings to mask, e.g. a password; `env:<VAR>` masks what the
+    /// variable holds
+    #[serde(default)]
+    pub secrets: Vec<String>,
+}
+
+impl RedactionConfig {
+    fn default_enabled() -> bool {
+        true
+    }
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: Self::default_enabled(),
+            secrets: Vec::new(),
+        }
+    }
+}
+
+/// How long files users send are kept. Cleanup runs hourly, and on
+/// `cica clean`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentsConfig {
+    /// Remove attachments older than this many days; 0 keeps them regardless of age
+    #[serde(default = "AttachmentsConfig::default_max_age_days")]
+    pub max_age_days: u64,
+
+    /// Remove the oldest attachments once they take up more than this many
+    /// MB; 0 for no limit
+    #[serde(default = "AttachmentsConfig::default_max_size_mb")]
+    pub max_size_mb: u64,
+}
+
+impl AttachmentsConfig {
+    fn default_max_age_days() -> u64 {
+        30
+    }
+
+    fn default_max_size_mb() -> u64 {
+        1024
+    }
+
+    pub fn max_age(&self) -> Option<Duration> {
+        (self.max_age_days > 0).then(|| Duration::from_secs(self.max_age_days * 24 * 60 * 60))
+    }
+
+    pub fn max_bytes(&self) -> Option<u64> {
+        (self.max_size_mb > 0).then(|| self.max_size_mb * 1024 * 1024)
+    }
+}
+
+impl Default for AttachmentsConfig {
+    fn default() -> Self {
+        Self {
+            max_age_days: Self::default_max_age_days(),
+            max_size_mb: Self::default_max_size_mb(),
+        }
+    }
+}
+
+/// Where tokens and API keys are kept
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SecretsConfig {
+    /// plaintext (default), keyring, or passphrase
+    #[serde(default)]
+    pub store: SecretStore,
+    /// Salt for deriving the encryption key (passphrase store only)
+    pub salt: Option<String>,
+}
+
+/// Pairing code settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairingConfig {
+    /// How long a pairing code stays valid, in minutes
+    #[serde(default = "PairingConfig::default_code_ttl_minutes")]
+    pub code_ttl_minutes: u64,
+
+    /// Stop replying to an unknown sender after this many pairing prompts for
+    /// one code (0 never stops). Each time a sender is silenced, their next
+    /// code lasts twice as long, so they wait longer before getting another.
+    #[serde(default = "PairingConfig::default_max_prompts")]
+    pub max_prompts: u32,
+
+    /// Approve the first person to message the bot and make them the owner
+    /// (only while no one is approved and no owner is configured)
+    #[serde(default)]
+    pub auto_approve_first_user: bool,
+}
+
+impl PairingConfig {
+    fn default_code_ttl_minutes() -> u64 {
+        60
+    }
+
+    fn default_max_prompts() -> u32 {
+        3
+    }
+
+    pub fn code_ttl(&self) -> Duration {
+        Duration::from_secs(self.code_ttl_minutes.max(1) * 60)
+    }
+}
+
+impl Default for PairingConfig {
+    fn default() -> Self {
+        Self {
+            code_ttl_minutes: Self::default_code_ttl_minutes(),
+            max_prompts: Self::default_max_prompts(),
+            auto_approve_first_user: false,
+        }
+    }
+}
+
+/// Limits for queries to the AI backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryConfig {
+    /// Stop a query (killing the backend process) after this many minutes
+    #[serde(default = "QueryConfig::default_timeout_minutes")]
+    pub timeout_minutes: u64,
+
+    /// Most queries (each a backend process) running at once; the rest wait
+    /// their turn. Takes effect on restart.
+    #[serde(default = "QueryConfig::default_max_concurrent")]
+    pub max_concurrent: usize,
+
+    /// Try a query this many more times when the backend fails in a way
+    /// that usually passes (overloaded, rate limited, network errors)
+    /// before it replied or used a tool
+    #[serde(default = "QueryConfig::default_retries")]
+    pub retries: u32,
+
+    /// Most cron jobs running at once, so scheduled work leaves slots free
+    /// for chat; due jobs wait their turn by priority. Plain reminders
+    /// don't count.
+    #[serde(default = "QueryConfig::default_max_concurrent_jobs")]
+    pub max_concurrent_jobs: usize,
+
+    /// What a message sent while a reply is being worked on does: waits for
+    /// a follow-up turn ("queue"), or stops the reply so both are answered
+    /// together ("interrupt"). /cancel always stops it.
+    #[serde(default)]
+    pub while_busy: WhileBusy,
+
+    /// How long to wait for more messages before answering, so a few sent
+    /// in quick succession are answered together. On Signal the wait also
+    /// lasts while the user is typing.
+    #[serde(default = "QueryConfig::default_debounce_ms")]
+    pub debounce_ms: u64,
+
+    /// Most messages answered together; the rest get a follow-up turn
+    #[serde(default = "QueryConfig::default_max_batch")]
+    pub max_batch: usize,
+}
+
+/// What a message sent during a reply does
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WhileBusy {
+    #[default]
+    Queue,
+    Interrupt,
+}
+
+impl QueryConfig {
+    fn default_timeout_minutes() -> u64 {
+        10
+    }
+
+    fn default_max_concurrent() -> usize {
+        3
+    }
+
+    fn default_retries() -> u32 {
+        2
+    }
+
+    fn default_max_concurrent_jobs() -> usize {
+        2
+    }
+
+    fn default_debounce_ms() -> u64 {
+        1000
+    }
+
+    fn default_max_batch() -> usize {
+        10
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_minutes.max(1) * 60)
+    }
+}
+
+impl Default for QueryConfig {
+    fn default() -> Self {
+        Self {
+            timeout_minutes: Self::default_timeout_minutes(),
+            max_concurrent: Self::default_max_concurrent(),
+            retries: Self::default_retries(),
+            max_concurrent_jobs: Self::default_max_concurrent_jobs(),
+            while_busy: WhileBusy::default(),
+            debounce_ms: Self::default_debounce_ms(),
+            max_batch: Self::default_max_batch(),
+        }
+    }
+}
+
+/// What the agent may do on the host. Tool and path rules apply to Claude Code,
+/// `denied_paths` to the OpenAI-compatible file tools too; Cursor has neither.
+/// Guests are kept read-only by `write_workspace` under [roles].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxConfig {
+    /// Run tools without asking for permission (there's no one to ask in a
+    /// chat). When off, only `allowed_tools` can run.
+    #[serde(default = "SandboxConfig::default_skip_permissions")]
+    pub skip_permissions: bool,
+
+    /// Tools that run without permission, e.g. "Read", "WebSearch", "Bash(git log:*)"
+    #[serde(default)]
+    pub allowed_tools: Vec<String>,
+
+    /// Tools that never run, even when skipping permissions
+    #[serde(default)]
+    pub denied_tools: Vec<String>,
+
+    /// Files the agent can't read or edit, as paths or globs (e.g. "~/.ssh/**").
+    /// Setting any also denies Bash, since shell commands could read them.
+    #[serde(default)]
+    pub denied_paths: Vec<String>,
+}
+
+impl SandboxConfig {
+    fn default_skip_permissions() -> bool {
+        true
+    }
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            skip_permissions: Self::default_skip_permissions(),
+            allowed_tools: Vec::new(),
+            denied_tools: Vec::new(),
+            denied_paths: Vec::new(),
+        }
+    }
+}
+
+/// Memory search settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryConfig {
+    /// Embedding model for memory search, by its fastembed model code
+    /// (e.g. "intfloat/multilingual-e5-small" for non-English memories).
+    /// Changing it rebuilds the memory index. Default: "Xenova/bge-small-en-v1.5"
+    pub embedding_model: Option<String>,
+
+    /// Most memories added to the context for a message
+    #[serde(default = "MemoryConfig::default_max_results")]
+    pub max_results: usize,
+
+    /// Lowest score a memory needs to be added (embedding similarity, 0-1, plus boosts)
+    #[serde(default = "MemoryConfig::default_min_score")]
+    pub min_score: f32,
+
+    /// Score added to the memory that best matches the message's words
+    #[serde(default = "MemoryConfig::default_keyword_weight")]
+    pub keyword_weight: f32,
+
+    /// Score added to a memory changed just now, halving every 30 days
+    #[serde(default = "MemoryConfig::default_recency_weight")]
+    pub recency_weight: f32,
+}
+
+impl MemoryConfig {
+    fn default_max_results() -> usize {
+        3
+    }
+
+    fn default_min_score() -> f32 {
+        0.3
+    }
+
+    fn default_keyword_weight() -> f32 {
+        0.2
+    }
+
+    fn default_recency_weight() -> f32 {
+        0.05
+    }
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            embedding_model: None,
+            max_results: Self::default_max_results(),
+            min_score: Self::default_min_score(),
+            keyword_weight: Self::default_keyword_weight(),
+            recency_weight: Self::default_recency_weight(),
+        }
+    }
+}
+
+/// The owner's chat identity, used for system notifications
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OwnerConfig {
+    /// Channel name (e.g., "telegram")
+    pub channel: String,
+    /// User ID on that channel
+    pub user_id: String,
+}
+
+/// Permissions for each non-owner role (the owner can always do everything)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolesConfig {
+    #[serde(default = "RolePermissions::full")]
+    pub member: RolePermissions,
+    #[serde(default = "RolePermissions::restricted")]
+    pub guest: RolePermissions,
+}
+
+impl Default for RolesConfig {
+    fn default() -> Self {
+        Self {
+            member: RolePermissions::full(),
+            guest: RolePermissions::restricted(),
+        }
+    }
+}
+
+/// What a role is allowed to do
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RolePermissions {
+    /// Create, change and run scheduled jobs (/cron, /remind); anyone can
+    /// list, pause or remove their own
+    pub cron: bool,
+    /// Create and edit skills. Without it, Claude Code can't edit files under
+    /// skills/, or use Bash when the workspace is writable; OpenAI-compatible
+    /// backends can't write there, and Cursor can't write anywhere.
+    pub skills: bool,
+    /// Save shared memories (memory/shared/), which every user's assistant
+    /// finds and reads; kept like skills without it
+    #[serde(default)]
+    pub shared_memories: bool,
+    /// Let the assistant modify files and run commands in the workspace
+    pub write_workspace: bool,
+}
+
+impl RolePermissions {
+    pub fn full() -> Self {
+        Self {
+            cron: true,
+            skills: true,
+            shared_memories: true,
+            write_workspace: true,
+        }
+    }
+
+    pub fn restricted() -> Self {
+        Self {
+            cron: false,
+            skills: false,
+            shared_memories: false,
+            write_workspace: false,
+        }
+    }
+}
+
+/// All channel configurations
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChannelsConfig {
+    pub telegram: Option<TelegramConfig>,
+    pub signal: Option<SignalConfig>,
+    pub slack: Option<SlackConfig>,
+}
+
+/// Telegram-specific configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TelegramConfig {
+    #[serde(default)]
+    pub bot_token: String,
+    #[serde(default)]
+    pub auto_approve: bool,
+    #[serde(default)]
+    pub shared_identity: bool,
+    pub onboarding_prompt: Option<String>,
+    /// Overrides `skip_permissions` under [sandbox] for this channel
+    pub skip_permissions: Option<bool>,
+    /// Persona file, relative to the data directory, used instead of
+    /// PERSONA.md for this channel's users
+    pub persona: Option<String>,
+}
+
+impl TelegramConfig {
+    pub fn new(bot_token: String) -> Self {
+        Self {
+            bot_token,
+            ..Default::default()
+        }
+    }
+}
+
+/// Signal-specific configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SignalConfig {
+    #[serde(default)]
+    pub phone_number: String,
+    #[serde(default)]
+    pub auto_approve: bool,
+    #[serde(default)]
+    pub shared_identity: bool,
+    pub onboarding_prompt: Option<String>,
+    /// Overrides `skip_permissions` under [sandbox] for this channel
+    pub skip_permissions: Option<bool>,
+    /// Persona file, relative to the data directory, used instead of
+    /// PERSONA.md for this channel's users
+    pub persona: Option<String>,
+}
+
+#[cfg(feature = "signal")]
+impl SignalConfig {
+    pub fn new(phone_number: String) -> Self {
+        Self {
+            phone_number,
+            ..Default::default()
+        }
+    }
+}
+
+/// Slack-specific configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SlackConfig {
+    #[serde(default)]
+    pub bot_token: String,
+    #[serde(default)]
+    pub app_token: String,
+    #[serde(default)]
+    pub auto_approve: bool,
+    #[serde(default)]
+    pub shared_identity: bool,
+    pub onboarding_prompt: Option<String>,
+    /// Overrides `skip_permissions` under [sandbox] for this channel
+    pub skip_permissions: Option<bool>,
+    /// Persona file, relative to the data directory, used instead of
+    /// PERSONA.md for this channel's users
+    pub persona: Option<String>,
+    /// Channel IDs where Cica responds outside DMs / AI Assistant threads.
+    /// Plain channel messages are only answered here; when non-empty,
+    /// @mentions are also restricted to these channels.
+    #[serde(default)]
+    pub allowed_channels: Vec<String>,
+}
+
+#[cfg(feature = "slack")]
+impl SlackConfig {
+    pub fn new(bot_token: String, app_token: String) -> Self {
+        Self {
+            bot_token,
+            app_token,
+            ..Default::default()
+        }
+    }
+}
+
+/// Channel settings relevant to pairing/onboarding
+#[derive(Debug, Clone, Default)]
+pub struct ChannelSettings {
+    pub auto_approve: bool,
+    pub shared_identity: bool,
+    pub onboarding_prompt: Option<String>,
+    pub skip_permissions: Option<bool>,
+    pub persona: Option<String>,
+}
+
+impl Config {
+    /// Permissions for a role
+    pub fn role_permissions(&self, role: Role) -> RolePermissions {
+        match role {
+            Role::Owner => RolePermissions::full(),
+            Role::Member => self.roles.member,
+            Role::Guest => self.roles.guest,
+        }
+    }
+
+    /// The model configured for the active backend, if not its default
+    pub fn model(&self) -> Option<&str> {
+        match self.backend {
+            AiBackend::Claude => self.claude.model.as_deref(),
+            AiBackend::Cursor => self.cursor.model.as_deref(),
+            AiBackend::OpenAi => self.openai.model.as_deref(),
+        }
+    }
+
+    pub fn channel_settings(&self, channel: &str) -> ChannelSettings {
+        channels::get_channel_info(channel)
+            .and_then(|info| (info.settings)(self))
+            .map(|settings| ChannelSettings {
+                onboarding_prompt: settings
+                    .onboarding_prompt
+                    .or_else(|| self.onboarding_prompt.clone()),
+                ..settings
+            })
+            .unwrap_or_default()
+    }
+
+    /// The persona file for a channel's users: its `persona` override, or
+    /// PERSONA.md
+    pub fn persona_path(&self, paths: &Paths, channel: Option<&str>) -> PathBuf {
+        channel
+            .and_then(|channel| self.channel_settings(channel).persona)
+            .filter(|file| !file.trim().is_empty())
+            .map(|file| paths.base.join(file.trim()))
+            .unwrap_or_else(|| paths.base.join("PERSONA.md"))
+    }
+
+    /// Whether queries for a channel's users run tools without asking
+    pub fn skip_permissions(&self, channel: &str) -> bool {
+        self.channel_settings(channel)
+            .skip_permissions
+            .unwrap_or(self.sandbox.skip_permissions)
+    }
+}
+
+/// Claude configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ClaudeConfig {
+    /// Anthropic API key or OAuth token (used when not using Vertex AI)
+    pub api_key: Option<String>,
+    /// Model to use: an alias ("sonnet", "opus") or full model ID from the API (e.g. "claude-sonnet-4-5-20250929")
+    pub model: Option<String>,
+    /// Use Google Vertex AI instead of Anthropic API
+    #[serde(default)]
+    pub use_vertex: bool,
+    /// GCP project ID for Vertex AI (required when use_vertex is true)
+    pub vertex_project_id: Option<String>,
+    /// GCP region for Vertex AI (e.g. "europe-west1", "us-east5"). Defaults to "europe-west1" if unset.
+    pub vertex_region: Option<String>,
+    /// Path to GCP service account JSON key file (long-lived auth; recommended for servers).
+    /// When set, GOOGLE_APPLICATION_CREDENTIALS is set for Claude so gcloud login is not needed.
+    pub vertex_credentials_path: Option<String>,
+    /// Keep a Claude Code process running per conversation instead of starting
+    /// one per message, saving the startup time. One-shot queries (cron jobs,
+    /// onboarding, summaries) still get a process of their own.
+    #[serde(default)]
+    pub keep_alive: bool,
+    /// Stop a kept-alive process after this many idle minutes (default: 10)
+    pub keep_alive_minutes: Option<u64>,
+    /// Thinking budget: low, medium or high (default: Claude Code's own)
+    pub effort: Option<Effort>,
+    /// Claude Code version to install instead of the one this release pins
+    /// (set by `cica deps update --latest`)
+    pub code_version: Option<String>,
+}
+
+impl ClaudeConfig {
+    /// How long a kept-alive Claude Code process may sit idle
+    pub fn keep_alive_idle(&self) -> Duration {
+        Duration::from_secs(self.keep_alive_minutes.unwrap_or(10).max(1) * 60)
+    }
+}
+
+/// Cursor CLI configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CursorConfig {
+    /// Cursor API key (from dashboard)
+    pub api_key: Option<String>,
+    /// Model to use (default: claude-sonnet-4-20250514)
+    pub model: Option<String>,
+}
+
+/// OpenAI-compatible API configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct OpenAiConfig {
+    /// Base URL of the API, up to and including the version (e.g. "https://openrouter.ai/api/v1")
+    pub base_url: Option<String>,
+    /// API key; local servers like LM Studio usually don't need one
+    pub api_key: Option<String>,
+    /// Model to use (default: gpt-4o)
+    pub model: Option<String>,
+    /// Reasoning effort for reasoning models: low, medium or high (default: the API's)
+    pub effort: Option<Effort>,
+}
+
+// ============================================================================
+// Config Operations
+// ============================================================================
+
+impl Config {
+    /// Load config from the standard location
+    pub fn load() -> Result<Self> {
+        let path = paths()?.config_file;
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Could not read config file: {:?}", path))?;
+
+        let mut config: Config = toml::from_str(&content)
+            .with_context(|| format!("Could not parse config file: {:?}", path))?;
+        if in_container() {
+            config.use_env_credentials();
+        }
+
+        // Secrets still in plaintext get moved to the configured store
+        let mut needs_migration = false;
+        let store = config.secrets.store;
+        let salt = config.secrets.salt.clone();
+        let mut env_secrets = BTreeMap::new();
+        config.for_each_secret(|name, value| {
+            needs_migration |= store != SecretStore::Plaintext && !secrets::is_reference(value);
+            if let Some(var) = secrets::env_reference(value) {
+                env_secrets.insert(name, var.to_string());
+            }
+            *value = secrets::resolve(name, value, salt.as_deref())?;
+            Ok(())
+        })?;
+        config.env_secrets = env_secrets;
+
+        if needs_migration {
+            config.save()?;
+            tracing::info!(
+                "Moved secrets in config.toml to the {} store",
+                store.as_str()
+            );
+        }
+
+        Ok(config)
+    }
+
+    /// Save config to the standard location
+    pub fn save(&self) -> Result<()> {
+        let paths = paths()?;
+        paths.ensure_dirs()?;
+
+        let mut stored = self.clone();
+        if stored.secrets.store == SecretStore::Passphrase && stored.secrets.salt.is_none() {
+            stored.secrets.salt = Some(secrets::generate_salt());
+        }
+        let store = stored.secrets.store;
+        let salt = stored.secrets.salt.clone();
+        let env_secrets = std::mem::take(&mut stored.env_secrets);
+        stored.for_each_secret(|name, value| {
+            // Still what the variable holds, so not changed since loading
+            if let Some(var) = env_secrets.get(name)
+                && std::env::var(var).is_ok_and(|env| env == *value)
+            {
+                *value = secrets::env_reference_to(var);
+                return Ok(());
+            }
+            *value = secrets::store(store, name, value, salt.as_deref())?;
+            Ok(())
+        })?;
+
+        let content = toml::to_string_pretty(&stored)?;
+        storage::write_atomic(&paths.config_file, &content)?;
+
+        Ok(())
+    }
+
+    /// config.toml as it goes into a backup. Without secrets, tokens and API
+    /// keys are left empty; with them, they're in plaintext, since keyring
+    /// entries and the passphrase don't travel with the backup.
+    pub fn backup_toml(with_secrets: bool) -> Result<String> {
+        let mut config = if with_secrets {
+            Self::load()?
+        } else {
+            // Parse without resolving, so no passphrase or keyring is needed
+            let path = paths()?.config_file;
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Could not read config file: {:?}", path))?;
+            let mut config: Config = toml::from_str(&content)
+                .with_context(|| format!("Could not parse config file: {:?}", path))?;
+            config.for_each_secret(|_, value| {
+                value.clear();
+                Ok(())
+            })?;
+            config
+        };
+        config.secrets = SecretsConfig::default();
+
+        Ok(toml::to_string_pretty(&config)?)
+    }
+
+    /// Names of the tokens and API keys this config has
+    pub fn secret_names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        let _ = self.clone().for_each_secret(|name, _| {
+            names.push(name);
+            Ok(())
+        });
+        names
+    }
+
+    /// Values of the tokens and API keys this config has
+    pub fn secret_values(&self) -> Vec<String> {
+        let mut values = Vec::new();
+        let _ = self.clone().for_each_secret(|_, value| {
+            if !value.is_empty() {
+                values.push(std::mem::take(value));
+            }
+            Ok(())
+        });
+        values
+    }
+
+    /// Point backend credentials missing from config.toml at the variables
+    /// the backends' own tools read, when those are set
+    fn use_env_credentials(&mut self) {
+        let from_env = |value: &mut Option<String>, vars: &[&str]| {
+            if value.as_deref().is_some_and(|v| !v.is_empty()) {
+                return;
+            }
+            if let Some(var) = vars.iter().find(|var| std::env::var_os(var).is_some()) {
+                *value = Some(secrets::env_reference_to(var));
+            }
+        };
+        if !self.claude.use_vertex {
+            from_env(
+                &mut self.claude.api_key,
+                &[
+                    "ANTHROPIC_API_KEY",
+                    "CLAUDE_CODE_OAUTH_TOKEN",
+                    "ANTHROPIC_OAUTH_TOKEN",
+                ],
+            );
+        }
+        from_env(&mut self.cursor.api_key, &["CURSOR_API_KEY"]);
+        if self.openai.base_url.is_some() {
+            from_env(&mut self.openai.api_key, &["OPENAI_API_KEY"]);
+        }
+    }
+
+    /// Visit every token and API key with a stable name (e.g. "telegram.bot_token")
+    fn for_each_secret(
+        &mut self,
+        mut f: impl FnMut(&'static str, &mut String) -> Result<()>,
+    ) -> Result<()> {
+        if let Some(telegram) = &mut self.channels.telegram {
+            f("telegram.bot_token", &mut telegram.bot_token)?;
+        }
+        if let Some(slack) = &mut self.channels.slack {
+            f("slack.bot_token", &mut slack.bot_token)?;
+            f("slack.app_token", &mut slack.app_token)?;
+        }
+        if let Some(api_key) = &mut self.claude.api_key {
+            f("claude.api_key", api_key)?;
+        }
+        if let Some(api_key) = &mut self.cursor.api_key {
+            f("cursor.api_key", api_key)?;
+        }
+        if let Some(api_key) = &mut self.openai.api_key {
+            f("openai.api_key", api_key)?;
+        }
+        if let Some(api_key) = &mut self.search.api_key {
+            f("search.api_key", api_key)?;
+        }
+        Ok(())
+    }
+
+    /// Check config.toml for mistakes that would otherwise go unnoticed:
+    /// unknown keys (typos are otherwise silently ignored), configured
+    /// channels missing required fields, and referenced paths that don't
+    /// exist. Returns a line per problem; none means the config is fine.
+    pub fn validate() -> Result<Vec<String>> {
+        let paths = paths()?;
+        let content = std::fs::read_to_string(&paths.config_file)
+            .with_context(|| format!("Could not read config file: {:?}", paths.config_file))?;
+        Ok(Self::validate_toml(&content, &paths))
+    }
+
+    fn validate_toml(content: &str, paths: &Paths) -> Vec<String> {
+        let config: Config = match toml::from_str(content) {
+            Ok(config) => config,
+            Err(e) => return vec![format!("Couldn't parse config.toml: {}", e)],
+        };
+
+        let mut problems: Vec<String> = unknown_keys(content, &config)
+            .into_iter()
+            .map(|key| format!("Unknown key {}", key))
+            .collect();
+
+        for info in channels::SUPPORTED_CHANNELS {
+            for field in (info.missing_fields)(&config) {
+                problems.push(format!("[channels.{}] is missing {}", info.name, field));
+            }
+        }
+
+        for info in channels::SUPPORTED_CHANNELS {
+            let persona = config.persona_path(paths, Some(info.name));
+            if persona != paths.base.join("PERSONA.md") && !persona.exists() {
+                problems.push(format!(
+                    "[channels.{}] persona file not found: {}",
+                    info.name,
+                    persona.display()
+                ));
+            }
+        }
+
+        if config.claude.use_vertex
+            && let Some(path) = &config.claude.vertex_credentials_path
+            && !path.trim().is_empty()
+            && let Err(e) = setup::validate_vertex_credentials_path(path, &paths.base)
+        {
+            problems.push(format!("claude.vertex_credentials_path: {}", e));
+        }
+
+        problems.extend(config.unenforced_sandbox());
+
+        if paths.skills_dir.exists() && !paths.skills_dir.is_dir() {
+            problems.push(format!(
+                "The skills directory {} isn't a directory",
+                paths.skills_dir.display()
+            ));
+        }
+
+        problems
+    }
+
+    /// Check if config file exists
+    pub fn exists() -> Result<bool> {
+        Ok(paths()?.config_file.exists())
+    }
+
+    /// Get list of configured channel names
+    pub fn configured_channels(&self) -> Vec<&'static str> {
+        channels::SUPPORTED_CHANNELS
+            .iter()
+            .filter(|info| info.is_configured(self))
+            .map(|info| info.name)
+            .collect()
+    }
+
+    /// Remove a channel's configuration section.
+    /// Returns true if the channel was configured.
+    pub fn remove_channel(&mut self, channel: &str) -> bool {
+        channels::get_channel_info(channel).is_some_and(|info| (info.remove_config)(self))
+    }
+
+    /// Check if Claude is configured (Anthropic API key or Vertex AI)
+    pub fn is_claude_configured(&self) -> bool {
+        if self.claude.use_vertex {
+            self.claude
+                .vertex_project_id
+                .as_ref()
+                .is_some_and(|s| !s.is_empty())
+        } else {
+            self.claude.api_key.is_some()
+        }
+    }
+
+    /// Check if Cursor is configured
+    pub fn is_cursor_configured(&self) -> bool {
+        self.cursor.api_key.is_some()
+    }
+
+    /// Check if an OpenAI-compatible API is configured
+    pub fn is_openai_configured(&self) -> bool {
+        self.openai.base_url.is_some()
+    }
+
+    /// Check if the selected backend is configured
+    pub fn is_backend_configured(&self) -> bool {
+        self.configured_backends().contains(&self.backend)
+    }
+
+    /// [sandbox] rules a configured backend can't enforce. The Cursor CLI has
+    /// no tool or path permissions, so it would run with none of them.
+    pub fn unenforced_sandbox(&self) -> Option<String> {
+        let sandbox = &self.sandbox;
+        let has_rules = !sandbox.allowed_tools.is_empty()
+            || !sandbox.denied_tools.is_empty()
+            || !sandbox.denied_paths.is_empty();
+        let cursor = self.backend == AiBackend::Cursor || self.is_cursor_configured();
+        (has_rules && cursor).then(|| {
+            "[sandbox] tool and path rules can't be enforced on the Cursor backend; \
+             remove them or the [cursor] section"
+                .to_string()
+        })
+    }
+
+    /// Backends that are set up and can be switched to
+    pub fn configured_backends(&self) -> Vec<AiBackend> {
+        AiBackend::ALL
+            .into_iter()
+            .filter(|backend| match backend {
+                AiBackend::Claude => self.is_claude_configured(),
+                AiBackend::Cursor => self.is_cursor_configured(),
+                AiBackend::OpenAi => self.is_openai_configured(),
+            })
+            .collect()
+    }
+}
+
+/// Keys in `content` that `config` (parsed from it) doesn't have, as dotted
+/// paths. Serde skips unknown keys, so they're what's missing when the config
+/// is written back out.
+fn unknown_keys(content: &str, config: &Config) -> Vec<String> {
+    let (Ok(toml::Value::Table(written)), Ok(toml::Value::Table(known))) = (
+        content.parse::<toml::Value>(),
+        toml::Value::try_from(config),
+    ) else {
+        return Vec::new();
+    };
+    let mut unknown = Vec::new();
+    collect_unknown_keys("", &written, &known, &mut unknown);
+    unknown
+}
+
+fn collect_unknown_keys(
+    prefix: &str,
+    written: &toml::Table,
+    known: &toml::Table,
+    unknown: &mut Vec<String>,
+) {
+    for (key, value) in written {
+        let path = format!("{}{}", prefix, key);
+        match (value, known.get(key)) {
+            (toml::Value::Table(written), Some(toml::Value::Table(known))) => {
+                collect_unknown_keys(&format!("{}.", path), written, known, unknown);
+            }
+            (_, Some(_)) => {}
+            // Empty lists and tables may be left out when written back
+            (toml::Value::Array(a), None) if a.is_empty() => {}
+            (toml::Value::Table(t), None) if t.is_empty() => {}
+            (_, None) => unknown.push(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_toml() {
+        let dir = std::env::temp_dir().join(format!("cica-config-test-{}", std::process::id()));
+        let paths = paths_at(dir.clone());
+        let content = r#"
+backend = "claude"
+reverse_geocde = true
+
+[claude]
+api_key = "sk-test"
+use_vertex = true
+vertex_project_id = "project"
+vertex_credentials_path = "missing.json"
+
+[channels.telegram]
+bot_token = ""
+auto_aprove = true
+persona = "PERSONA-work.md"
+
+[channels.slack]
+bot_token = "xoxb-1"
+app_token = "xapp-1"
+
+[mcp.servers.github]
+command = "npx"
+args = []
+
+[mcp.servers.github.env]
+GITHUB_TOKEN = "token"
+
+[cursor]
+api_key = "key"
+
+[sandbox]
+denied_paths = ["~/.ssh/**"]
+"#;
+
+        let problems = Config::validate_toml(content, &paths);
+        assert!(problems.contains(&"Unknown key reverse_geocde".to_string()));
+        assert!(problems.contains(&"Unknown key channels.telegram.auto_aprove".to_string()));
+        assert!(problems.contains(&"[channels.telegram] is missing bot_token".to_string()));
+        assert!(
+            problems.iter().any(
+                |p| p.starts_with("claude.vertex_credentials_path: Credentials file not found")
+            )
+        );
+        assert!(problems.contains(&format!(
+            "[channels.telegram] persona file not found: {}",
+            dir.join("PERSONA-work.md").display()
+        )));
+        assert!(problems.iter().any(|p| p.contains("the Cursor backend")));
+        assert_eq!(problems.len(), 6, "{:?}", problems);
+
+        let problems = Config::validate_toml("backend = 3", &paths);
+        assert!(problems[0].starts_with("Couldn't parse config.toml"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
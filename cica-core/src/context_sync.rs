@@ -0,0 +1,195 @@
+//! Send each session only the context it hasn't seen.
+//!
+//! The context prompt (identity, profile, persona, skills, memories...) is
+//! split into sections at its `#`/`##` headings. A session gets every section
+//! in its first message; resumed messages carry only sections that are new or
+//! changed since, so chatty users don't pay for the full context every time.
+//! The preamble before the first heading (who the assistant is, current time)
+//! goes out with every message as the system prompt.
+
+use anyhow::Result;
+use rusqlite::params;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::storage;
+
+/// Context to send with one message
+#[derive(Debug)]
+pub struct ContextUpdate {
+    /// Sent with every message
+    pub preamble: String,
+    /// Sections the session hasn't seen, or that changed since
+    pub changed: Vec<String>,
+    /// Whether the session has seen any sections before
+    resumed: bool,
+    /// (heading, hash) of every section in the current context
+    hashes: Vec<(String, String)>,
+}
+
+impl ContextUpdate {
+    /// Everything, for a session's first message
+    pub fn new_session(prompt: &str) -> Self {
+        Self::diff(prompt, &HashMap::new())
+    }
+
+    /// What a resumed session hasn't seen yet
+    pub fn resumed_session(prompt: &str, session_id: &str) -> Result<Self> {
+        Ok(Self::diff(prompt, &sent_hashes(session_id)?))
+    }
+
+    fn diff(prompt: &str, sent: &HashMap<String, String>) -> Self {
+        let (preamble, sections) = split_sections(prompt);
+
+        let mut changed = Vec::new();
+        let mut hashes = Vec::new();
+        for (heading, content) in sections {
+            let hash = hex_digest(&content);
+            if sent.get(&heading) != Some(&hash) {
+                changed.push(content);
+            }
+            hashes.push((heading, hash));
+        }
+
+        Self {
+            preamble,
+            changed,
+            resumed: !sent.is_empty(),
+            hashes,
+        }
+    }
+
+    /// The user's message with any new context in front of it
+    pub fn message(&self, text: &str) -> String {
+        if self.changed.is_empty() {
+            return text.to_string();
+        }
+
+        let sections = self.changed.join("\n\n");
+        if self.resumed {
+            format!(
+                "<context_update>\n\
+                 These sections replace the ones with the same heading from earlier in our conversation.\n\n\
+                 {}\n</context_update>\n\n{}",
+                sections, text
+            )
+        } else {
+            format!("<context>\n{}\n</context>\n\n{}", sections, text)
+        }
+    }
+
+    /// Remember what a session has now seen, forgetting the user's older sessions
+    pub fn mark_sent(&self, channel: &str, user_id: &str, session_id: &str) -> Result<()> {
+        storage::write(|conn| {
+            conn.execute(
+                "DELETE FROM context_sections
+                 WHERE channel = ?1 AND user_id = ?2 AND session_id != ?3",
+                params![channel, user_id, session_id],
+            )?;
+
+            let mut stmt = conn.prepare(
+                "INSERT OR REPLACE INTO context_sections (channel, user_id, session_id, heading, hash)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for (heading, hash) in &self.hashes {
+                stmt.execute(params![channel, user_id, session_id, heading, hash])?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Hashes of the sections a session has seen, by heading
+fn sent_hashes(session_id: &str) -> Result<HashMap<String, String>> {
+    storage::read(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT heading, hash FROM context_sections WHERE session_id = ?1")?;
+        let rows = stmt.query_map([session_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    })
+}
+
+/// Split a prompt into the preamble and (heading, section) pairs.
+/// Repeated headings get a numeric suffix so each section has its own key.
+fn split_sections(prompt: &str) -> (String, Vec<(String, String)>) {
+    let mut preamble = Vec::new();
+    let mut sections: Vec<(String, Vec<&str>)> = Vec::new();
+
+    for line in prompt.lines() {
+        if line.starts_with("# ") || line.starts_with("## ") {
+            sections.push((line.trim().to_string(), vec![line]));
+        } else if let Some((_, lines)) = sections.last_mut() {
+            lines.push(line);
+        } else {
+            preamble.push(line);
+        }
+    }
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let sections = sections
+        .into_iter()
+        .map(|(heading, lines)| {
+            let count = seen.entry(heading.clone()).or_default();
+            *count += 1;
+            let key = if *count == 1 {
+                heading
+            } else {
+                format!("{} ({})", heading, count)
+            };
+            (key, lines.join("\n").trim_end().to_string())
+        })
+        .collect();
+
+    (preamble.join("\n").trim().to_string(), sections)
+}
+
+fn hex_digest(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROMPT: &str = "You are Cica.\nCurrent date and time: now\n\n\
+                          ## Capabilities\nYou can chat.\n\n\
+                          # Project Context\n\n\
+                          ## PERSONA.md\nBe kind.";
+
+    #[test]
+    fn test_split_sections() {
+        let (preamble, sections) = split_sections(PROMPT);
+
+        assert_eq!(preamble, "You are Cica.\nCurrent date and time: now");
+        let headings: Vec<_> = sections.iter().map(|(h, _)| h.as_str()).collect();
+        assert_eq!(
+            headings,
+            vec!["## Capabilities", "# Project Context", "## PERSONA.md"]
+        );
+        assert_eq!(sections[2].1, "## PERSONA.md\nBe kind.");
+    }
+
+    #[test]
+    fn test_only_changed_sections_are_resent() {
+        let first = ContextUpdate::diff(PROMPT, &HashMap::new());
+        assert_eq!(first.changed.len(), 3);
+        assert!(
+            first
+                .message("hi")
+                .starts_with("<context>\n## Capabilities")
+        );
+
+        let sent: HashMap<_, _> = first.hashes.iter().cloned().collect();
+        let unchanged = ContextUpdate::diff(PROMPT, &sent);
+        assert!(unchanged.changed.is_empty());
+        assert_eq!(unchanged.message("hi"), "hi");
+
+        let edited = PROMPT.replace("Be kind.", "Be brief.");
+        let update = ContextUpdate::diff(&edited, &sent);
+        assert_eq!(update.changed, vec!["## PERSONA.md\nBe brief."]);
+        assert!(update.message("hi").starts_with("<context_update>"));
+    }
+}
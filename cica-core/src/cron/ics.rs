@@ -0,0 +1,959 @@
+//! Calendar (ICS) feeds for `CronSchedule::Ics` jobs.
+
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::rc::Rc;
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+use chrono::{
+    Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc,
+    Weekday,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// How far ahead a feed's events are scheduled
+const HORIZON_MS: u64 = 30 * 86_400_000;
+
+/// Events that started this long ago are kept, for jobs running after them
+const LOOKBACK_MS: u64 = 86_400_000;
+
+/// Most events kept per feed
+const MAX_EVENTS: usize = 100;
+
+/// Most periods a recurring event is expanded for, to bound runaway rules
+const MAX_PERIODS: u32 = 100_000;
+
+/// Longest event description kept
+const MAX_DESCRIPTION_CHARS: usize = 500;
+
+/// Largest feed downloaded
+const MAX_FEED_BYTES: usize = 5 * 1024 * 1024;
+
+/// Redirects followed to a feed
+const MAX_REDIRECTS: usize = 5;
+
+/// One occurrence of a calendar event.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CalendarEvent {
+    /// Start time (Unix millis).
+    pub start_at: u64,
+
+    pub summary: String,
+
+    #[serde(default)]
+    pub location: Option<String>,
+
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl CalendarEvent {
+    /// The event's details, for a job's prompt.
+    pub fn describe(&self) -> String {
+        let mut text = format!(
+            "{}\nStarts: {}",
+            self.summary,
+            super::format_timestamp(self.start_at)
+        );
+        if let Some(location) = &self.location {
+            text.push_str(&format!("\nLocation: {}", location));
+        }
+        if let Some(description) = &self.description {
+            text.push_str(&format!("\nDescription: {}", description));
+        }
+        text
+    }
+}
+
+/// Download a feed and return its events around `now_ms`, earliest first.
+pub async fn fetch(url: &str, now_ms: u64) -> Result<Vec<CalendarEvent>> {
+    // webcal:// is how calendar apps link to feeds served over HTTPS
+    let mut url = reqwest::Url::parse(&match url.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_string(),
+    })?;
+
+    // Redirects are followed here, so each hop's address is checked too
+    let mut redirects = 0;
+    let mut response = loop {
+        let response = public_client(&url).await?.get(url.clone()).send().await?;
+        if !response.status().is_redirection() {
+            break response.error_for_status()?;
+        }
+        let Some(location) = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+        else {
+            bail!("The calendar feed redirected without saying where");
+        };
+        redirects += 1;
+        if redirects > MAX_REDIRECTS {
+            bail!("The calendar feed redirected too many times");
+        }
+        url = url.join(location)?;
+    };
+    if response
+        .content_length()
+        .is_some_and(|len| len > MAX_FEED_BYTES as u64)
+    {
+        bail!("The calendar feed is larger than {} bytes", MAX_FEED_BYTES);
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > MAX_FEED_BYTES {
+            bail!("The calendar feed is larger than {} bytes", MAX_FEED_BYTES);
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let ics = String::from_utf8_lossy(&body);
+
+    Ok(parse_events(
+        &ics,
+        now_ms.saturating_sub(LOOKBACK_MS),
+        now_ms + HORIZON_MS,
+    ))
+}
+
+/// A client that only reaches `url`'s host at the public addresses it
+/// resolves to now, so a feed can't point Cica at its own machine or network
+/// (or switch to such an address after the check)
+async fn public_client(url: &reqwest::Url) -> Result<reqwest::Client> {
+    if !matches!(url.scheme(), "http" | "https") {
+        bail!("Calendar feeds must be http, https or webcal URLs");
+    }
+    let Some(host) = url.host_str() else {
+        bail!("The calendar feed URL has no host");
+    };
+    let port = url.port_or_known_default().unwrap_or(443);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.trim_matches(['[', ']']), port))
+        .await?
+        .collect();
+    if addrs.is_empty() {
+        bail!("Couldn't find {}", host);
+    }
+    if addrs.iter().any(|addr| !is_public(addr.ip())) {
+        bail!(
+            "{} is a local or private address, which calendar feeds can't use",
+            host
+        );
+    }
+
+    Ok(reqwest::Client::builder()
+        .user_agent(concat!("cica/", env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(30))
+        .redirect(reqwest::redirect::Policy::none())
+        .resolve_to_addrs(host, &addrs)
+        .build()?)
+}
+
+/// Whether an address is on the internet, rather than loopback, link-local
+/// (including cloud metadata services), private or otherwise reserved
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                // Carrier-grade NAT (100.64.0.0/10) and "this network" (0.0.0.0/8)
+                || (a == 100 && (b & 0xc0) == 64)
+                || a == 0)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    // Unique local (fc00::/7) and link-local (fe80::/10)
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+/// Occurrences of the events in an ICS document starting between `from_ms`
+/// and `until_ms`, earliest first.
+///
+/// Recurring events are expanded for FREQ, INTERVAL, COUNT, UNTIL and weekly
+/// BYDAY; events with other rules only count their first occurrence. A
+/// TZID is looked up as an IANA name, then in the feed's VTIMEZONEs; times
+/// without one, or with one that's neither, are taken as local time.
+pub fn parse_events(ics: &str, from_ms: u64, until_ms: u64) -> Vec<CalendarEvent> {
+    let lines = unfold(ics);
+    let zones = read_vtimezones(&lines);
+    let raw = read_vevents(&lines, &zones);
+
+    // Occurrences moved or cancelled one at a time replace the recurring event's
+    let overridden: HashSet<(&str, u64)> = raw
+        .iter()
+        .filter_map(|e| Some((e.uid.as_str(), e.recurrence_id?)))
+        .collect();
+
+    let mut events = Vec::new();
+    for event in raw.iter().filter(|e| !e.cancelled) {
+        let Some(start) = &event.start else {
+            continue;
+        };
+        for at in occurrences(start, event.rule.as_deref(), until_ms) {
+            if at < from_ms
+                || event.exdates.contains(&at)
+                || (event.recurrence_id.is_none() && overridden.contains(&(event.uid.as_str(), at)))
+            {
+                continue;
+            }
+            events.push(CalendarEvent {
+                start_at: at,
+                summary: event.summary.clone(),
+                location: event.location.clone(),
+                description: event.description.clone(),
+            });
+        }
+    }
+
+    events.sort_by_key(|e| e.start_at);
+    events.truncate(MAX_EVENTS);
+    events
+}
+
+/// A VEVENT as written in the feed
+#[derive(Default)]
+struct RawEvent {
+    uid: String,
+    start: Option<Time>,
+    rule: Option<String>,
+    exdates: Vec<u64>,
+    recurrence_id: Option<u64>,
+    cancelled: bool,
+    summary: String,
+    location: Option<String>,
+    description: Option<String>,
+}
+
+fn read_vevents(lines: &[String], zones: &HashMap<String, Rc<ZoneRules>>) -> Vec<RawEvent> {
+    let mut events = Vec::new();
+    let mut current: Option<RawEvent> = None;
+    // Depth of components inside the current event (e.g. VALARM), whose
+    // properties aren't the event's
+    let mut nested = 0usize;
+
+    for line in lines {
+        let Some((head, value)) = line.split_once(':') else {
+            continue;
+        };
+        let name = head.split(';').next().unwrap_or_default();
+        let zone = || Zone::of(head, zones);
+
+        if current.is_some() {
+            match name {
+                "BEGIN" => {
+                    nested += 1;
+                    continue;
+                }
+                "END" if nested > 0 => {
+                    nested -= 1;
+                    continue;
+                }
+                _ if nested > 0 => continue,
+                _ => {}
+            }
+        }
+
+        match (name, &mut current) {
+            ("BEGIN", None) if value == "VEVENT" => current = Some(RawEvent::default()),
+            ("END", Some(_)) if value == "VEVENT" => events.extend(current.take()),
+            ("UID", Some(event)) => event.uid = value.to_string(),
+            ("DTSTART", Some(event)) => event.start = Time::parse(value, zone()),
+            ("RRULE", Some(event)) => event.rule = Some(value.to_string()),
+            ("EXDATE", Some(event)) => event.exdates.extend(
+                value
+                    .split(',')
+                    .filter_map(|value| Time::parse(value, zone()))
+                    .filter_map(|t| t.to_millis(t.time)),
+            ),
+            ("RECURRENCE-ID", Some(event)) => {
+                event.recurrence_id = Time::parse(value, zone()).and_then(|t| t.to_millis(t.time))
+            }
+            ("STATUS", Some(event)) => event.cancelled = value == "CANCELLED",
+            ("SUMMARY", Some(event)) => event.summary = unescape(value),
+            ("LOCATION", Some(event)) => {
+                event.location = Some(unescape(value)).filter(|s| !s.is_empty())
+            }
+            ("DESCRIPTION", Some(event)) => {
+                let description: String = unescape(value)
+                    .chars()
+                    .take(MAX_DESCRIPTION_CHARS)
+                    .collect();
+                event.description = Some(description).filter(|s| !s.trim().is_empty());
+            }
+            _ => {}
+        }
+    }
+
+    events
+}
+
+/// Join folded lines (continuations start with a space or tab).
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        if let Some(rest) = line.strip_prefix([' ', '\t'])
+            && let Some(last) = lines.last_mut()
+        {
+            last.push_str(rest);
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+    lines
+}
+
+fn unescape(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push('\n'),
+            Some(other) => text.push(other),
+            None => {}
+        }
+    }
+    text.trim().to_string()
+}
+
+/// The zone a feed's date-time is in
+#[derive(Debug, Clone)]
+enum Zone {
+    Utc,
+    /// No TZID ("floating" time), or one that isn't known
+    Local,
+    Named(Tz),
+    /// A VTIMEZONE in the feed, for TZIDs that aren't IANA names (Outlook's
+    /// "W. Europe Standard Time")
+    Defined(Rc<ZoneRules>),
+}
+
+impl Zone {
+    /// The zone named by a property's TZID parameter
+    fn of(head: &str, zones: &HashMap<String, Rc<ZoneRules>>) -> Self {
+        let Some(tzid) = head
+            .split(';')
+            .skip(1)
+            .find_map(|param| param.strip_prefix("TZID="))
+        else {
+            return Zone::Local;
+        };
+        let tzid = tzid.trim_matches('"');
+        if let Ok(tz) = tzid.trim_start_matches('/').parse::<Tz>() {
+            return Zone::Named(tz);
+        }
+        zones
+            .get(tzid)
+            .map_or(Zone::Local, |rules| Zone::Defined(rules.clone()))
+    }
+}
+
+/// A date-time as written in a feed, with its zone
+#[derive(Debug, Clone)]
+struct Time {
+    time: NaiveDateTime,
+    zone: Zone,
+}
+
+impl Time {
+    /// Parse "20250115T090000Z", "20250115T090000" or an all-day "20250115";
+    /// all but the first are in `zone`.
+    fn parse(value: &str, zone: Zone) -> Option<Self> {
+        let value = value.trim();
+        let (value, zone) = match value.strip_suffix('Z') {
+            Some(value) => (value, Zone::Utc),
+            None => (value, zone),
+        };
+        Some(Self {
+            time: parse_naive(value)?,
+            zone,
+        })
+    }
+
+    /// `time` in this time's zone as Unix millis.
+    fn to_millis(&self, time: NaiveDateTime) -> Option<u64> {
+        let ms = match &self.zone {
+            Zone::Utc => Utc.from_utc_datetime(&time).timestamp_millis(),
+            Zone::Local => Local
+                .from_local_datetime(&time)
+                .earliest()?
+                .timestamp_millis(),
+            Zone::Named(tz) => tz.from_local_datetime(&time).earliest()?.timestamp_millis(),
+            Zone::Defined(rules) => (time - TimeDelta::seconds(rules.offset_at(time).into()))
+                .and_utc()
+                .timestamp_millis(),
+        };
+        u64::try_from(ms).ok()
+    }
+}
+
+/// A date-time or date without its zone
+fn parse_naive(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y%m%d")
+                .ok()
+                .map(|date| date.and_time(NaiveTime::MIN))
+        })
+}
+
+/// A VTIMEZONE: its STANDARD and DAYLIGHT observances
+#[derive(Debug, Default)]
+struct ZoneRules {
+    observances: Vec<Observance>,
+}
+
+impl ZoneRules {
+    /// Seconds the zone is ahead of UTC at local time `time`: the offset of
+    /// the observance that began last
+    fn offset_at(&self, time: NaiveDateTime) -> i32 {
+        self.observances
+            .iter()
+            .filter_map(|o| Some((o.last_onset(time)?, o.offset_to)))
+            .max_by_key(|(onset, _)| *onset)
+            .map(|(_, offset)| offset)
+            .or_else(|| {
+                self.observances
+                    .iter()
+                    .min_by_key(|o| o.start)
+                    .map(|o| o.offset_from)
+            })
+            .unwrap_or(0)
+    }
+}
+
+/// When a zone switches to an offset, e.g. daylight time from the last
+/// Sunday in March
+#[derive(Debug)]
+struct Observance {
+    /// The first onset, in local time
+    start: NaiveDateTime,
+    offset_from: i32,
+    offset_to: i32,
+    /// For yearly onsets: the month, and the nth weekday in it (negative
+    /// counts from the end); without a weekday it's `start`'s day
+    month: Option<u32>,
+    by_day: Option<(i32, Weekday)>,
+    until: Option<NaiveDateTime>,
+    yearly: bool,
+}
+
+impl Observance {
+    /// The latest onset at or before `time`
+    fn last_onset(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        if !self.yearly {
+            return Some(self.start).filter(|&start| start <= time);
+        }
+        let month = self.month.unwrap_or(self.start.month());
+        [time.year(), time.year() - 1]
+            .into_iter()
+            .filter_map(|year| {
+                let date = match self.by_day {
+                    Some((n, weekday)) => nth_weekday(year, month, n, weekday)?,
+                    None => NaiveDate::from_ymd_opt(year, month, self.start.day())?,
+                };
+                Some(date.and_time(self.start.time()))
+            })
+            .find(|&onset| {
+                onset >= self.start
+                    && onset <= time
+                    && self.until.is_none_or(|until| onset <= until)
+            })
+    }
+}
+
+/// The `n`th `weekday` of a month, counting from the end when negative
+fn nth_weekday(year: i32, month: u32, n: i32, weekday: Weekday) -> Option<NaiveDate> {
+    if n > 0 {
+        return NaiveDate::from_weekday_of_month_opt(year, month, weekday, u8::try_from(n).ok()?);
+    }
+    let next_month = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)?
+    };
+    let last = next_month.pred_opt()?;
+    let back = (7 + last.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+    let weeks = u64::try_from(-n - 1).ok()?;
+    last.checked_sub_days(Days::new(u64::from(back) + weeks * 7))
+        .filter(|date| date.month() == month)
+}
+
+/// A UTC offset such as "+0100", "-0500" or "+053000", in seconds
+fn parse_offset(value: &str) -> Option<i32> {
+    let value = value.trim();
+    let (sign, digits) = match value.split_at_checked(1)? {
+        ("+", digits) => (1, digits),
+        ("-", digits) => (-1, digits),
+        _ => return None,
+    };
+    if !matches!(digits.len(), 4 | 6) || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let part = |range: std::ops::Range<usize>| digits.get(range)?.parse::<i32>().ok();
+    let seconds = if digits.len() == 6 { part(4..6)? } else { 0 };
+    Some(sign * (part(0..2)? * 3600 + part(2..4)? * 60 + seconds))
+}
+
+/// The feed's VTIMEZONEs by TZID
+fn read_vtimezones(lines: &[String]) -> HashMap<String, Rc<ZoneRules>> {
+    let mut zones = HashMap::new();
+    let mut current: Option<(String, ZoneRules)> = None;
+    let mut observance: Option<Observance> = None;
+
+    for line in lines {
+        let Some((head, value)) = line.split_once(':') else {
+            continue;
+        };
+        let name = head.split(';').next().unwrap_or_default();
+        match (name, value, &mut current, &mut observance) {
+            ("BEGIN", "VTIMEZONE", None, _) => {
+                current = Some((String::new(), ZoneRules::default()))
+            }
+            ("END", "VTIMEZONE", Some(_), _) => {
+                if let Some((tzid, rules)) = current.take()
+                    && !rules.observances.is_empty()
+                {
+                    zones.insert(tzid, Rc::new(rules));
+                }
+            }
+            ("TZID", _, Some((tzid, _)), None) => *tzid = value.trim().to_string(),
+            ("BEGIN", "STANDARD" | "DAYLIGHT", Some(_), None) => {
+                observance = Some(Observance {
+                    start: NaiveDateTime::MIN,
+                    offset_from: 0,
+                    offset_to: 0,
+                    month: None,
+                    by_day: None,
+                    until: None,
+                    yearly: false,
+                })
+            }
+            ("END", "STANDARD" | "DAYLIGHT", Some((_, rules)), Some(_)) => {
+                rules.observances.extend(observance.take())
+            }
+            ("DTSTART", _, _, Some(o)) => {
+                o.start = parse_naive(value.trim()).unwrap_or(NaiveDateTime::MIN)
+            }
+            ("TZOFFSETFROM", _, _, Some(o)) => o.offset_from = parse_offset(value).unwrap_or(0),
+            ("TZOFFSETTO", _, _, Some(o)) => o.offset_to = parse_offset(value).unwrap_or(0),
+            ("RRULE", _, _, Some(o)) => {
+                for (key, value) in value.split(';').filter_map(|part| part.split_once('=')) {
+                    match key {
+                        "FREQ" => o.yearly = value == "YEARLY",
+                        "BYMONTH" => o.month = value.parse().ok(),
+                        "BYDAY" => o.by_day = ordinal_weekday(value),
+                        "UNTIL" => o.until = parse_naive(value.trim_end_matches('Z')),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    zones
+}
+
+/// A BYDAY with an ordinal ("-1SU", "2SU"); a bare weekday is the first
+fn ordinal_weekday(value: &str) -> Option<(i32, Weekday)> {
+    let split = value.len().checked_sub(2)?;
+    let (n, day) = value.split_at_checked(split)?;
+    let n = match n {
+        "" | "+" => 1,
+        n => n.parse().ok().filter(|&n: &i32| n != 0)?,
+    };
+    Some((n, weekday(day)?))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The parts of an RRULE that are supported
+struct Rule {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<usize>,
+    until: Option<u64>,
+    by_day: Vec<Weekday>,
+}
+
+impl Rule {
+    /// None for rules using parts that aren't supported.
+    fn parse(rule: &str) -> Option<Self> {
+        let mut parsed = Rule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+        };
+        let mut frequency = None;
+
+        for part in rule.split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part.split_once('=')?;
+            match key {
+                "FREQ" => {
+                    frequency = Some(match value {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return None,
+                    })
+                }
+                "INTERVAL" => parsed.interval = value.parse().ok().filter(|&n| n > 0)?,
+                "COUNT" => parsed.count = Some(value.parse().ok()?),
+                "UNTIL" => {
+                    parsed.until = Time::parse(value, Zone::Local).and_then(|t| t.to_millis(t.time))
+                }
+                "BYDAY" => parsed.by_day = value.split(',').map(weekday).collect::<Option<_>>()?,
+                "WKST" => {}
+                _ => return None,
+            }
+        }
+
+        parsed.frequency = frequency?;
+        // Only weekly rules are expanded by day ("every Monday and Thursday")
+        if !parsed.by_day.is_empty() && parsed.frequency != Frequency::Weekly {
+            return None;
+        }
+        parsed.by_day.sort_by_key(|day| day.num_days_from_monday());
+        Some(parsed)
+    }
+
+    /// Start times of the occurrences in the `period`th period after `start`;
+    /// None once the period is too far away to represent.
+    fn period(&self, start: NaiveDateTime, period: u32) -> Option<Vec<NaiveDateTime>> {
+        let step = period.checked_mul(self.interval)?;
+        Some(match self.frequency {
+            Frequency::Daily => start
+                .checked_add_days(Days::new(step.into()))
+                .into_iter()
+                .collect(),
+            Frequency::Weekly if self.by_day.is_empty() => start
+                .checked_add_days(Days::new(u64::from(step) * 7))
+                .into_iter()
+                .collect(),
+            Frequency::Weekly => {
+                let monday = start
+                    .date()
+                    .checked_sub_days(Days::new(start.weekday().num_days_from_monday().into()))?
+                    .checked_add_days(Days::new(u64::from(step) * 7))?;
+                self.by_day
+                    .iter()
+                    .filter_map(|day| {
+                        monday.checked_add_days(Days::new(day.num_days_from_monday().into()))
+                    })
+                    .map(|date| date.and_time(start.time()))
+                    .filter(|&time| time >= start)
+                    .collect()
+            }
+            // Months without the start's day (e.g. the 31st) are skipped
+            Frequency::Monthly | Frequency::Yearly => {
+                let months = if self.frequency == Frequency::Yearly {
+                    step.checked_mul(12)?
+                } else {
+                    step
+                };
+                start
+                    .checked_add_months(Months::new(months))
+                    .filter(|time| time.day() == start.day())
+                    .into_iter()
+                    .collect()
+            }
+        })
+    }
+}
+
+/// A BYDAY weekday ("MO"); None for ones with an ordinal ("1MO").
+fn weekday(day: &str) -> Option<Weekday> {
+    Some(match day {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+/// Start times of an event's occurrences up to `until_ms`.
+fn occurrences(start: &Time, rule: Option<&str>, until_ms: u64) -> Vec<u64> {
+    let Some(rule) = rule.and_then(Rule::parse) else {
+        return start.to_millis(start.time).into_iter().collect();
+    };
+    let until_ms = rule.until.map_or(until_ms, |until| until.min(until_ms));
+
+    let mut found = Vec::new();
+    let mut counted = 0;
+    for period in 0..MAX_PERIODS {
+        let Some(times) = rule.period(start.time, period) else {
+            return found;
+        };
+        for time in times {
+            let Some(at) = start.to_millis(time) else {
+                continue;
+            };
+            if at > until_ms || rule.count.is_some_and(|count| counted >= count) {
+                return found;
+            }
+            counted += 1;
+            found.push(at);
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32) -> u64 {
+        Utc.with_ymd_and_hms(y, m, d, h, 0, 0)
+            .unwrap()
+            .timestamp_millis() as u64
+    }
+
+    const FEED: &str = "BEGIN:VCALENDAR\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:review\r\n\
+        DTSTART:20250106T090000Z\r\n\
+        RRULE:FREQ=WEEKLY;BYDAY=MO,TH;COUNT=6\r\n\
+        EXDATE:20250109T090000Z\r\n\
+        SUMMARY:Design review\\, weekly\r\n\
+        LOCATION:Room 4\r\n\
+        DESCRIPTION:Bring the\\nslides and the long\r\n  agenda\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:review\r\n\
+        RECURRENCE-ID:20250113T090000Z\r\n\
+        DTSTART:20250113T150000Z\r\n\
+        SUMMARY:Design review (moved)\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:dentist\r\n\
+        DTSTART:20250110T140000Z\r\n\
+        STATUS:CANCELLED\r\n\
+        SUMMARY:Dentist\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    #[test]
+    fn test_parse_events() {
+        let events = parse_events(FEED, 0, u64::MAX);
+        let starts: Vec<u64> = events.iter().map(|e| e.start_at).collect();
+        assert_eq!(
+            starts,
+            vec![
+                utc(2025, 1, 6, 9),
+                // Thursday the 9th is excluded, Monday the 13th moved to 15:00
+                utc(2025, 1, 13, 15),
+                utc(2025, 1, 16, 9),
+                utc(2025, 1, 20, 9),
+                utc(2025, 1, 23, 9),
+            ]
+        );
+
+        assert_eq!(events[0].summary, "Design review, weekly");
+        assert_eq!(events[0].location.as_deref(), Some("Room 4"));
+        assert_eq!(
+            events[0].description.as_deref(),
+            Some("Bring the\nslides and the long agenda")
+        );
+        assert_eq!(events[1].summary, "Design review (moved)");
+
+        // Only what starts in the window
+        let window = parse_events(FEED, utc(2025, 1, 14, 0), utc(2025, 1, 20, 9));
+        assert_eq!(window.len(), 2);
+    }
+
+    #[test]
+    fn test_tzid() {
+        // Pinned so the zone differs from local time wherever the test runs
+        let new_york = "America/New_York";
+        let local = Local::now().offset().local_minus_utc();
+        let zone = if local == -5 * 3600 || local == -4 * 3600 {
+            "Asia/Tokyo"
+        } else {
+            new_york
+        };
+        let offset_hours = if zone == new_york { -5 } else { 9 };
+
+        let feed = format!(
+            "BEGIN:VEVENT\nUID:a\nDTSTART;TZID={}:20250106T090000\n\
+             RRULE:FREQ=DAILY;COUNT=2\nEXDATE;TZID=\"{}\":20250107T090000\n\
+             SUMMARY:Standup\nEND:VEVENT\n",
+            zone, zone
+        );
+        let events = parse_events(&feed, 0, u64::MAX);
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].start_at as i64,
+            utc(2025, 1, 6, 9) as i64 - offset_hours * 3_600_000
+        );
+
+        // New York moves to daylight time in March
+        let feed = "BEGIN:VEVENT\nUID:b\nDTSTART;TZID=America/New_York:20250701T090000\n\
+                    SUMMARY:Summer\nEND:VEVENT\n";
+        assert_eq!(
+            parse_events(feed, 0, u64::MAX)[0].start_at,
+            utc(2025, 7, 1, 13)
+        );
+    }
+
+    #[test]
+    fn test_vtimezone() {
+        // How Outlook writes Eastern time, under a name that isn't IANA's
+        let feed = "BEGIN:VCALENDAR\n\
+            BEGIN:VEVENT\nUID:a\nDTSTART;TZID=Eastern Standard Time:20250106T090000\n\
+            RRULE:FREQ=MONTHLY;COUNT=7\nSUMMARY:Review\nEND:VEVENT\n\
+            BEGIN:VTIMEZONE\nTZID:Eastern Standard Time\n\
+            BEGIN:STANDARD\nDTSTART:16010101T020000\nTZOFFSETFROM:-0400\nTZOFFSETTO:-0500\n\
+            RRULE:FREQ=YEARLY;INTERVAL=1;BYDAY=1SU;BYMONTH=11\nEND:STANDARD\n\
+            BEGIN:DAYLIGHT\nDTSTART:16010101T020000\nTZOFFSETFROM:-0500\nTZOFFSETTO:-0400\n\
+            RRULE:FREQ=YEARLY;INTERVAL=1;BYDAY=2SU;BYMONTH=3\nEND:DAYLIGHT\n\
+            END:VTIMEZONE\nEND:VCALENDAR\n";
+        let starts: Vec<u64> = parse_events(feed, 0, u64::MAX)
+            .iter()
+            .map(|e| e.start_at)
+            .collect();
+        assert_eq!(
+            starts,
+            vec![
+                utc(2025, 1, 6, 14),
+                utc(2025, 2, 6, 14),
+                utc(2025, 3, 6, 14),
+                // Daylight time from Sunday March 9th
+                utc(2025, 4, 6, 13),
+                utc(2025, 5, 6, 13),
+                utc(2025, 6, 6, 13),
+                utc(2025, 7, 6, 13),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nth_weekday() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+        assert_eq!(nth_weekday(2025, 3, -1, Weekday::Sun), date(2025, 3, 30));
+        assert_eq!(nth_weekday(2025, 10, -1, Weekday::Sun), date(2025, 10, 26));
+        assert_eq!(nth_weekday(2025, 12, -1, Weekday::Wed), date(2025, 12, 31));
+        assert_eq!(nth_weekday(2025, 3, 2, Weekday::Sun), date(2025, 3, 9));
+        assert_eq!(nth_weekday(2025, 2, 5, Weekday::Sun), None);
+        assert_eq!(parse_offset("+0530"), Some(19_800));
+        assert_eq!(parse_offset("-0500"), Some(-18_000));
+        assert_eq!(parse_offset("0500"), None);
+    }
+
+    #[test]
+    fn test_is_public() {
+        for ip in ["93.184.216.34", "2606:2800:220:1::1", "100.128.0.1"] {
+            assert!(is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.10",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_refuses_local_addresses() {
+        for url in [
+            "http://127.0.0.1:8080/cal.ics",
+            "webcal://localhost/cal.ics",
+            "http://[::1]/cal.ics",
+            "http://169.254.169.254/latest/meta-data",
+        ] {
+            let error = fetch(url, 0).await.unwrap_err().to_string();
+            assert!(
+                error.contains("local or private address"),
+                "{}: {}",
+                url,
+                error
+            );
+        }
+        assert!(fetch("file:///etc/passwd", 0).await.is_err());
+    }
+
+    #[test]
+    fn test_unsupported_rule_keeps_first_occurrence() {
+        let feed = "BEGIN:VEVENT\nUID:a\nDTSTART:20250101T080000Z\n\
+                    RRULE:FREQ=MONTHLY;BYDAY=1MO\nSUMMARY:Planning\nEND:VEVENT\n";
+        let events = parse_events(feed, 0, u64::MAX);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].start_at, utc(2025, 1, 1, 8));
+    }
+
+    #[test]
+    fn test_ignores_alarm_properties() {
+        let feed = "BEGIN:VEVENT\nUID:a\nDTSTART:20250101T080000Z\nSUMMARY:Standup\n\
+                    BEGIN:VALARM\nACTION:DISPLAY\nDESCRIPTION:Reminder\nEND:VALARM\n\
+                    LOCATION:Office\nEND:VEVENT\n";
+        let events = parse_events(feed, 0, u64::MAX);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].description, None);
+        assert_eq!(events[0].location.as_deref(), Some("Office"));
+    }
+
+    #[test]
+    fn test_huge_interval_stops_expanding() {
+        let feed = "BEGIN:VEVENT\nUID:a\nDTSTART:20250101T080000Z\n\
+                    RRULE:FREQ=YEARLY;INTERVAL=4000000000\nSUMMARY:Rare\nEND:VEVENT\n";
+        let events = parse_events(feed, 0, u64::MAX);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].start_at, utc(2025, 1, 1, 8));
+    }
+
+    #[test]
+    fn test_monthly_skips_short_months() {
+        let feed = "BEGIN:VEVENT\nUID:a\nDTSTART:20250131T080000Z\n\
+                    RRULE:FREQ=MONTHLY;UNTIL=20250601T000000Z\nSUMMARY:Invoices\nEND:VEVENT\n";
+        let starts: Vec<u64> = parse_events(feed, 0, u64::MAX)
+            .iter()
+            .map(|e| e.start_at)
+            .collect();
+        assert_eq!(
+            starts,
+            vec![
+                utc(2025, 1, 31, 8),
+                utc(2025, 3, 31, 8),
+                utc(2025, 5, 31, 8)
+            ]
+        );
+    }
+}
@@ -0,0 +1,1417 @@
+//! Cron job scheduling system for automated Claude Code tasks.
+
+mod clock;
+mod ics;
+mod schedule;
+pub mod store;
+pub mod templates;
+
+pub use clock::{Clock, SystemClock};
+pub use ics::CalendarEvent;
+pub use schedule::CronSchedule;
+pub(crate) use schedule::parse_duration;
+use schedule::{parse_datetime_in, parse_time_of_day};
+pub use store::{
+    CronJob, CronStore, JobEdit, JobId, JobRun, JobStatus, NotifyWhen, OverlapPolicy, Priority,
+    QUIET_REPLY, RetryPolicy, RunOutcome, SessionMode,
+};
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Component, Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use chrono_tz::Tz;
+use tokio::sync::{Mutex, mpsc};
+use tokio::task::AbortHandle;
+use tracing::{debug, info, warn};
+
+use crate::backends::{self, QueryOptions};
+use crate::channels::{self, get_channel_info};
+use crate::facts;
+use crate::i18n;
+use crate::memory;
+use crate::metrics;
+use crate::notify;
+use crate::onboarding;
+use crate::shutdown;
+use crate::transcript::{self, Speaker};
+
+/// Configuration for the cron service.
+#[derive(Clone)]
+pub struct CronConfig {
+    /// Longest the scheduler sleeps between checks for due jobs (default: 60
+    /// seconds). It wakes earlier when a job is due or the jobs change.
+    pub tick_interval: Duration,
+    /// Most jobs running at once (default: 2); the rest wait, by priority.
+    pub max_concurrent: usize,
+}
+
+impl Default for CronConfig {
+    fn default() -> Self {
+        Self {
+            tick_interval: Duration::from_secs(60),
+            max_concurrent: 2,
+        }
+    }
+}
+
+/// How often the scheduler looks for changes made by other processes (the CLI)
+const EXTERNAL_CHANGE_POLL: Duration = Duration::from_secs(2);
+
+/// Most output files sent with one result
+const MAX_OUTPUT_FILES: usize = 10;
+
+/// Runs listed by a schedule preview
+const PREVIEW_RUNS: usize = 5;
+
+/// Runs a preview looks at to describe the spacing between them; enough
+/// for a day of runs every minute
+const PREVIEW_SAMPLE: usize = 2_000;
+
+/// Runs closer together than this get a warning in a preview
+const FREQUENT_RUNS_MS: u64 = 5 * 60_000;
+
+/// Type alias for the result sender callback.
+/// (channel, user_id, message, files) -> Result<()>
+pub type ResultSender = Arc<
+    dyn Fn(String, String, String, Vec<PathBuf>) -> Pin<Box<dyn Future<Output = Result<()>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// Runs a job now for its owner (/cron run), through the running scheduler
+type RunNow = Arc<
+    dyn Fn(String, String, String) -> Pin<Box<dyn Future<Output = Result<()>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// Set while a scheduler is running
+static RUN_NOW: RwLock<Option<RunNow>> = RwLock::new(None);
+
+/// Run one of a user's jobs now, like the scheduler would; its result is
+/// delivered like any other run's, whether or not the job notifies
+pub async fn run_now(id: &str, channel: &str, user_id: &str) -> Result<()> {
+    let run_now = RUN_NOW
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("The cron scheduler isn't running"))?;
+    run_now(id.to_string(), channel.to_string(), user_id.to_string()).await
+}
+
+/// A run the scheduler started
+struct RunningJob {
+    handle: AbortHandle,
+    /// Plain reminders don't count against `max_concurrent`
+    reminder: bool,
+}
+
+/// The cron service - manages scheduled job execution.
+pub struct CronService<C: Clock> {
+    clock: C,
+    store: Arc<Mutex<CronStore>>,
+    config: CronConfig,
+    shutdown_tx: Option<mpsc::Sender<()>>,
+}
+
+impl<C: Clock> CronService<C> {
+    /// Create a new cron service.
+    pub fn new(clock: C, config: CronConfig) -> Result<Self> {
+        let mut store = CronStore::load()?;
+
+        let recovered = store.update(|store| Ok(store.recover_stuck_jobs(clock.now_millis())))?;
+        if recovered > 0 {
+            info!(
+                "Recovered {} stuck cron job(s) from previous run",
+                recovered
+            );
+        }
+
+        Ok(Self {
+            clock,
+            store: Arc::new(Mutex::new(store)),
+            config,
+            shutdown_tx: None,
+        })
+    }
+
+    /// Start the scheduler loop (spawns background task).
+    /// Returns a JoinHandle that can be awaited for shutdown.
+    pub fn start(&mut self, result_sender: ResultSender) -> tokio::task::JoinHandle<()> {
+        let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
+        self.shutdown_tx = Some(shutdown_tx);
+
+        let (store, clock, sender) = (
+            Arc::clone(&self.store),
+            self.clock.clone(),
+            result_sender.clone(),
+        );
+        *RUN_NOW.write().unwrap_or_else(|e| e.into_inner()) =
+            Some(Arc::new(move |id, channel, user_id| {
+                let (store, clock, sender) = (Arc::clone(&store), clock.clone(), sender.clone());
+                Box::pin(async move {
+                    run_job_now(&id, &channel, &user_id, store, sender, &clock).await
+                })
+            }));
+
+        let clock = self.clock.clone();
+        let store = Arc::clone(&self.store);
+        let tick_interval = self.config.tick_interval;
+        let max_concurrent = self.config.max_concurrent.max(1);
+
+        tokio::spawn(async move {
+            info!(
+                "Cron scheduler started (checking at least every {:?})",
+                tick_interval
+            );
+
+            // Runs in progress, to apply each job's overlap policy
+            let mut running: HashMap<JobId, RunningJob> = HashMap::new();
+
+            // Check for due jobs when the next one is due (at least every
+            // tick), or as soon as the jobs change
+            let mut check_at: u64 = 0;
+            let mut version = None;
+            // Paused as a whole (`cica cron pause-all`); jobs stay due
+            let mut paused = false;
+            loop {
+                let until_check =
+                    Duration::from_millis(check_at.saturating_sub(clock.now_millis()));
+                let notified = tokio::select! {
+                    _ = shutdown_rx.recv() => {
+                        info!("Cron scheduler shutting down");
+                        break;
+                    }
+                    _ = clock.sleep(until_check.min(EXTERNAL_CHANGE_POLL)) => false,
+                    _ = store::changed().notified() => true,
+                };
+
+                // Saved in this process, or by another one (the CLI)
+                let latest = store::version()
+                    .inspect_err(|e| warn!("Failed to read the cron version: {}", e))
+                    .ok();
+                let changed = notified || latest != version;
+                version = latest;
+
+                if changed {
+                    // Merge database changes while preserving in-memory state
+                    // for running jobs
+                    let mut store_guard = store.lock().await;
+                    match CronStore::load() {
+                        Ok(fresh) => store_guard.merge_from_disk(fresh),
+                        Err(e) => warn!("Failed to reload cron store: {}", e),
+                    }
+
+                    match store::paused_since() {
+                        Ok(since) if since.is_some() != paused => {
+                            paused = since.is_some();
+                            if paused {
+                                info!("Cron scheduler paused; no jobs will start until resumed");
+                            } else {
+                                info!("Cron scheduler resumed");
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(e) => warn!("Failed to read whether cron is paused: {}", e),
+                    }
+                } else if clock.now_millis() < check_at
+                    && running.values().all(|run| !run.handle.is_finished())
+                {
+                    continue;
+                }
+
+                // Runs that ended without saving a result (e.g. a panic)
+                // would otherwise stay Running forever
+                let ended: Vec<JobId> = running
+                    .iter()
+                    .filter(|(_, run)| run.handle.is_finished())
+                    .map(|(id, _)| id.clone())
+                    .collect();
+                for id in &ended {
+                    running.remove(id);
+                }
+                recover_ended_runs(&store, &ended, clock.now_millis()).await;
+                refresh_calendars(&store, &clock).await;
+
+                // Check for due jobs
+                let now = clock.now_millis();
+                let due_jobs = if paused || shutdown::is_shutting_down() {
+                    Vec::new()
+                } else {
+                    let store = store.lock().await;
+                    store
+                        .get_due_jobs(now)
+                        .iter()
+                        .map(|j| (*j).clone())
+                        .collect::<Vec<_>>()
+                };
+
+                if !due_jobs.is_empty() {
+                    debug!("Found {} due cron jobs", due_jobs.len());
+                }
+
+                let mut waiting = 0;
+                for job in due_jobs {
+                    if let Some(previous) = running.get(&job.id) {
+                        match job.overlap {
+                            OverlapPolicy::Skip => {
+                                info!(
+                                    "Cron job {} is still running, skipping this run",
+                                    job.short_id()
+                                );
+                                skip_trigger(&store, &job.id, now).await;
+                                continue;
+                            }
+                            // Stays due until the previous run ends
+                            OverlapPolicy::Queue => continue,
+                            OverlapPolicy::KillPrevious => {
+                                warn!(
+                                    "Cron job {} is still running, stopping it for a new run",
+                                    job.short_id()
+                                );
+                                previous.handle.abort();
+                            }
+                        }
+                    } else if !job.reminder
+                        && running.values().filter(|run| !run.reminder).count() >= max_concurrent
+                    {
+                        // Stays due; due jobs come highest priority first
+                        waiting += 1;
+                        continue;
+                    }
+
+                    let id = job.id.clone();
+                    let reminder = job.reminder;
+                    let store = Arc::clone(&store);
+                    let result_sender = result_sender.clone();
+                    let clock = clock.clone();
+
+                    let handle = tokio::spawn(async move {
+                        execute_job(job, store, result_sender, &clock, false).await;
+                    });
+                    running.insert(
+                        id,
+                        RunningJob {
+                            handle: handle.abort_handle(),
+                            reminder,
+                        },
+                    );
+                }
+
+                if waiting > 0 {
+                    debug!(
+                        "{} due cron job(s) waiting for a free slot ({} running)",
+                        waiting,
+                        running.len()
+                    );
+                }
+
+                check_at = {
+                    let store = store.lock().await;
+                    let now = clock.now_millis();
+                    let latest = now + tick_interval.as_millis() as u64;
+                    store
+                        .next_run_after(now)
+                        .map_or(latest, |at| at.min(latest))
+                };
+            }
+        })
+    }
+
+    /// Stop the scheduler.
+    pub async fn stop(&mut self) {
+        *RUN_NOW.write().unwrap_or_else(|e| e.into_inner()) = None;
+        if let Some(tx) = self.shutdown_tx.take() {
+            let _ = tx.send(()).await;
+        }
+    }
+
+    /// Add a new job.
+    #[allow(dead_code)]
+    pub async fn add(
+        &self,
+        name: String,
+        prompt: String,
+        schedule: CronSchedule,
+        channel: String,
+        user_id: String,
+    ) -> Result<JobId> {
+        let job = CronJob::new(name, prompt, schedule, channel, user_id);
+        let mut store = self.store.lock().await;
+        store.add(job)
+    }
+
+    /// Remove a job.
+    #[allow(dead_code)]
+    pub async fn remove(&self, id: &str, channel: &str, user_id: &str) -> Result<Option<CronJob>> {
+        let mut store = self.store.lock().await;
+        store.remove(id, channel, user_id)
+    }
+
+    /// List jobs for a user.
+    #[allow(dead_code)]
+    pub async fn list(&self, channel: &str, user_id: &str) -> Vec<CronJob> {
+        let store = self.store.lock().await;
+        store
+            .list_for_user(channel, user_id)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Manually trigger a job (/cron run).
+    pub async fn run_now(
+        &self,
+        id: &str,
+        channel: &str,
+        user_id: &str,
+        result_sender: ResultSender,
+    ) -> Result<()> {
+        let store = Arc::clone(&self.store);
+        run_job_now(id, channel, user_id, store, result_sender, &self.clock).await
+    }
+
+    /// Get job status.
+    #[allow(dead_code)]
+    pub async fn status(&self, id: &str, channel: &str, user_id: &str) -> Option<CronJob> {
+        let store = self.store.lock().await;
+        store.get(id, channel, user_id).cloned()
+    }
+
+    /// Toggle job enabled state.
+    #[allow(dead_code)]
+    pub async fn toggle(&self, id: &str, channel: &str, user_id: &str) -> Result<bool> {
+        let mut store = self.store.lock().await;
+
+        let now = self.clock.now_millis();
+        store.update(|store| {
+            // Verify ownership first
+            store
+                .get(id, channel, user_id)
+                .ok_or_else(|| anyhow::anyhow!("Job not found: {}", id))?;
+            let job = store
+                .get_mut(id)
+                .ok_or_else(|| anyhow::anyhow!("Job not found: {}", id))?;
+
+            job.enabled = !job.enabled;
+            if job.enabled {
+                job.update_next_run(now);
+            } else {
+                job.state.next_run_at = None;
+            }
+            Ok(job.enabled)
+        })
+    }
+}
+
+/// Drop a trigger that came while the job was still running.
+async fn skip_trigger(store: &Mutex<CronStore>, job_id: &str, now_ms: u64) {
+    let mut store = store.lock().await;
+    let result = store.update(|store| {
+        if let Some(job) = store.get_mut(job_id) {
+            job.update_next_run(now_ms);
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        warn!("Failed to skip cron run: {}", e);
+    }
+}
+
+/// Fetch the feeds of calendar jobs that weren't fetched lately, in the
+/// background; each job is rescheduled once its feed arrives.
+async fn refresh_calendars<C: Clock>(store: &Arc<Mutex<CronStore>>, clock: &C) {
+    let now = clock.now_millis();
+    let feeds = {
+        let mut store = store.lock().await;
+        if !store
+            .jobs
+            .values()
+            .any(|job| job.calendar_refresh_due(now).is_some())
+        {
+            return;
+        }
+        match store.update(|store| Ok(store.claim_calendar_refreshes(now))) {
+            Ok(feeds) => feeds,
+            Err(e) => {
+                warn!("Failed to claim calendar refreshes: {}", e);
+                return;
+            }
+        }
+    };
+
+    for (job_id, url) in feeds {
+        let store = Arc::clone(store);
+        let clock = clock.clone();
+        tokio::spawn(async move {
+            let events = match ics::fetch(&url, clock.now_millis()).await {
+                Ok(events) => events,
+                Err(e) => {
+                    warn!("Failed to fetch calendar {}: {}", url, e);
+                    return;
+                }
+            };
+            debug!("Fetched {} event(s) from calendar {}", events.len(), url);
+
+            let mut store = store.lock().await;
+            let result = store.update(|store| {
+                if let Some(job) = store.get_mut(&job_id) {
+                    job.set_calendar(events, clock.now_millis());
+                }
+                Ok(())
+            });
+            if let Err(e) = result {
+                warn!("Failed to save calendar events: {}", e);
+            }
+        });
+    }
+}
+
+/// Mark runs that ended without saving their result as failed.
+async fn recover_ended_runs(store: &Mutex<CronStore>, job_ids: &[JobId], now_ms: u64) {
+    if job_ids.is_empty() {
+        return;
+    }
+
+    let mut store = store.lock().await;
+    let result = store.update(|store| {
+        for id in job_ids {
+            if let Some(job) = store.get_mut(id)
+                && job.state.last_status == JobStatus::Running
+            {
+                warn!("Cron job {} ended without a result", job.short_id());
+                job.state.last_status = JobStatus::Failed("The run ended unexpectedly".to_string());
+                job.after_run(false, now_ms);
+            }
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        warn!("Failed to recover cron runs: {}", e);
+    }
+}
+
+async fn run_job_now<C: Clock>(
+    id: &str,
+    channel: &str,
+    user_id: &str,
+    store: Arc<Mutex<CronStore>>,
+    result_sender: ResultSender,
+    clock: &C,
+) -> Result<()> {
+    let job = {
+        let store = store.lock().await;
+        store
+            .get(id, channel, user_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Job not found: {}", id))?
+    };
+    execute_job(job, store, result_sender, clock, true).await;
+    Ok(())
+}
+
+/// Execute a single job. A `manual` run (/cron run) always reports back.
+async fn execute_job<C: Clock>(
+    job: CronJob,
+    store: Arc<Mutex<CronStore>>,
+    result_sender: ResultSender,
+    clock: &C,
+    manual: bool,
+) {
+    let job_id = job.id.clone();
+    info!("Executing cron job: {} ({})", job.name, job.short_id());
+    let _in_flight = shutdown::track(
+        &job.channel,
+        &job.user_id,
+        format!("the cron job \"{}\"", job.name),
+    );
+
+    let start_time = clock.now_millis();
+
+    // Mark as running; the schedule moves on to the next trigger
+    {
+        let mut store = store.lock().await;
+        let _ = store.update(|store| {
+            if let Some(job) = store.get_mut(&job_id) {
+                job.start_run(start_time);
+            }
+            Ok(())
+        });
+    }
+
+    // Build context prompt so the job has access to skills, configs, etc.
+    let channel_display = get_channel_info(&job.channel).map(|c| c.display_name);
+    let context_prompt = onboarding::build_context_prompt_for_user(
+        channel_display,
+        Some(&job.channel),
+        Some(&job.user_id),
+        Some(&job.prompt),
+    );
+
+    // Let the agent skip results that aren't worth a message
+    let mut prompt = if job.notify && job.notify_when == NotifyWhen::Noteworthy {
+        format!(
+            "{}\n\nIf there's nothing new or worth telling the user, reply with just {}.",
+            job.prompt, QUIET_REPLY
+        )
+    } else {
+        job.prompt.clone()
+    };
+
+    match job.state.trigger_messages.as_slice() {
+        [] => {}
+        [message] => prompt.push_str(&format!(
+            "\n\nThis run was triggered by a message from the user:\n{}",
+            message
+        )),
+        messages => prompt.push_str(&format!(
+            "\n\nThis run was triggered by these messages from the user, oldest first:\n{}",
+            messages
+                .iter()
+                .map(|m| format!("- {}", m))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )),
+    }
+
+    if let Some(event) = &job.state.calendar_event {
+        prompt.push_str(&format!(
+            "\n\nThis run is for an event in the user's calendar:\n{}",
+            event.describe()
+        ));
+    }
+
+    // Files the run writes here are sent along with the result
+    let outputs = if job.reminder {
+        None
+    } else {
+        match prepare_outputs_dir(&job_id) {
+            Ok(dir) => Some(dir),
+            Err(e) => {
+                warn!(
+                    "Failed to prepare outputs of cron job {}: {}",
+                    job.short_id(),
+                    e
+                );
+                None
+            }
+        }
+    };
+    if let Some(dir) = &outputs {
+        prompt.push_str(&format!(
+            "\n\nSave any files meant for the user (e.g. reports, CSVs) in {}; \
+             they'll be sent along with your reply.",
+            dir.display()
+        ));
+    }
+
+    // Execute the AI backend prompt; plain reminders only need delivering
+    let result = match context_prompt {
+        _ if job.reminder => Ok((job.prompt.clone(), String::new())),
+        Ok(ctx) => {
+            let query = |resume_session: Option<String>| {
+                backends::query_with_options(
+                    &prompt,
+                    QueryOptions {
+                        system_prompt: Some(ctx.clone()),
+                        resume_session,
+                        skip_permissions: channels::skip_permissions(&job.channel),
+                        // Past this, the backend process is killed and the run fails
+                        timeout: job.max_runtime_ms.map(Duration::from_millis),
+                        backend: job.backend,
+                        model: job.model.clone(),
+                        one_shot: true,
+                        ..Default::default()
+                    },
+                )
+            };
+
+            let resume = match job.session {
+                SessionMode::Continue => job.state.session_id.clone(),
+                SessionMode::Fresh => None,
+            };
+            match query(resume.clone()).await {
+                Err(e) if resume.is_some() && e.to_string().contains("No conversation found") => {
+                    warn!(
+                        "Session of cron job {} is gone, starting a new one",
+                        job.short_id()
+                    );
+                    query(None).await
+                }
+                result => result,
+            }
+        }
+        Err(e) => Err(e),
+    };
+
+    // Save any facts the agent learned, as chat replies do, without
+    // showing the tags to the user
+    let result = result.map(|(response, session_id)| {
+        if job.reminder {
+            (response, session_id)
+        } else {
+            let response = facts::apply_from_reply(&job.channel, &job.user_id, &response);
+            (response, session_id)
+        }
+    });
+
+    let end_time = clock.now_millis();
+    let duration_ms = end_time - start_time;
+    metrics::increment(if result.is_ok() {
+        "cron.runs.success"
+    } else {
+        "cron.runs.failed"
+    });
+
+    // Update job state
+    let state = {
+        let mut store = store.lock().await;
+        store.update(|store| {
+            if let Some(stored_job) = store.get_mut(&job_id) {
+                stored_job.state.last_run_at = Some(end_time);
+                stored_job.state.last_duration_ms = Some(duration_ms);
+                stored_job.record_run(JobRun {
+                    started_at: start_time,
+                    duration_ms,
+                    status: match &result {
+                        Ok(_) => JobStatus::Success,
+                        Err(e) => JobStatus::Failed(e.to_string()),
+                    },
+                    output: match &result {
+                        Ok((response, _)) => response.trim().to_string(),
+                        Err(e) => e.to_string(),
+                    },
+                });
+
+                stored_job.state.last_status = match &result {
+                    Ok(_) => JobStatus::Success,
+                    Err(e) => JobStatus::Failed(e.to_string()),
+                };
+
+                if let Ok((_, session_id)) = &result
+                    && stored_job.session == SessionMode::Continue
+                    && !session_id.is_empty()
+                {
+                    stored_job.state.session_id = Some(session_id.clone());
+                }
+
+                let changed = match &result {
+                    Ok((response, _)) => {
+                        let hash = store::output_hash(response);
+                        stored_job.state.last_output_hash.replace(hash) != Some(hash)
+                    }
+                    Err(_) => true,
+                };
+
+                // Schedule the next run, a retry, or pause after repeated failures
+                let outcome = stored_job.after_run(result.is_ok(), end_time);
+
+                // A retry needs the message or event that triggered this run
+                if let RunOutcome::Retrying(_) = outcome {
+                    let newer = std::mem::take(&mut stored_job.state.trigger_messages);
+                    stored_job.state.trigger_messages = job.state.trigger_messages.clone();
+                    stored_job.state.trigger_messages.extend(newer);
+                    stored_job.state.calendar_event = job.state.calendar_event.clone();
+                }
+
+                Ok((outcome, changed))
+            } else {
+                Ok((RunOutcome::Scheduled, true))
+            }
+        })
+    };
+    let (outcome, changed) = state.unwrap_or_else(|e| {
+        warn!("Failed to save cron job state: {}", e);
+        (RunOutcome::Scheduled, true)
+    });
+
+    // Results with something to say go into the owner's memories
+    if let (Ok((response, _)), Some(dir)) = (&result, &job.ingest)
+        && response.trim() != QUIET_REPLY
+    {
+        match ingest_result(&job, dir, response).await {
+            Ok(path) => debug!(
+                "Saved result of cron job {} to {}",
+                job.short_id(),
+                path.display()
+            ),
+            Err(e) => warn!(
+                "Failed to save result of cron job {} to memories: {}",
+                job.short_id(),
+                e
+            ),
+        }
+    }
+
+    // A retry is coming, so hold off on telling anyone
+    if let (Err(e), RunOutcome::Retrying(wait), false) = (&result, outcome, manual) {
+        warn!(
+            "Cron job {} failed, retrying in {}s: {}",
+            job.short_id(),
+            wait / 1000,
+            e
+        );
+        return;
+    }
+
+    let paused_note = (outcome == RunOutcome::Paused).then(|| {
+        format!(
+            "It failed {} times in a row and has been paused; /cron resume {} to restart it.",
+            job.retry.pause_after.max(1),
+            job.name
+        )
+    });
+
+    // Let the owner know about failures they wouldn't otherwise see
+    if let Err(e) = &result
+        && !(job.notify && notify::is_owner(&job.channel, &job.user_id))
+    {
+        notify::notify_owner(format!(
+            "Cron job '{}' ({}) for {}:{} failed: {}{}",
+            job.name,
+            job.short_id(),
+            job.channel,
+            job.user_id,
+            e,
+            paused_note
+                .as_ref()
+                .map(|note| format!("\n{}", note))
+                .unwrap_or_default()
+        ));
+    }
+
+    // Send the result to the user if they want this one
+    let response = result.as_ref().map(|(r, _)| r.as_str()).unwrap_or_default();
+    if manual || job.should_notify(result.is_ok(), changed, response) {
+        let files = match (&result, &outputs) {
+            (Ok(_), Some(dir)) => output_files(dir),
+            _ => Vec::new(),
+        };
+
+        let message = match result {
+            Ok((response, _session_id)) if job.reminder => {
+                format!("[Reminder]\n\n{}", response)
+            }
+            Ok((response, _session_id)) => {
+                format!("[Cron: {}]\n\n{}", job.name, response)
+            }
+            Err(e) => {
+                let language = i18n::user_language(&job.channel, &job.user_id);
+                format!(
+                    "[Cron: {} FAILED]\n\n{}{}",
+                    job.name,
+                    i18n::tr_args(language.as_deref(), "Error: {}", &[&e]),
+                    paused_note
+                        .map(|note| format!("\n\n{}", note))
+                        .unwrap_or_default()
+                )
+            }
+        };
+
+        transcript::record(&job.channel, &job.user_id, Speaker::Assistant, &message);
+        if let Err(e) =
+            result_sender(job.channel.clone(), job.user_id.clone(), message, files).await
+        {
+            warn!("Failed to send cron result to user: {}", e);
+        }
+    }
+
+    info!("Cron job {} completed in {}ms", job.short_id(), duration_ms);
+}
+
+/// Directory a job's runs write their output files to.
+pub fn outputs_dir(job_id: &str) -> Result<PathBuf> {
+    Ok(crate::config::paths()?
+        .cron_dir
+        .join(job_id)
+        .join("outputs"))
+}
+
+/// Create the job's outputs directory, clearing files from the previous run.
+fn prepare_outputs_dir(job_id: &str) -> Result<PathBuf> {
+    let dir = outputs_dir(job_id)?;
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Files directly in `dir`, by name, at most `MAX_OUTPUT_FILES` of them.
+fn output_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    if files.len() > MAX_OUTPUT_FILES {
+        warn!(
+            "{} output files in {}, sending the first {}",
+            files.len(),
+            dir.display(),
+            MAX_OUTPUT_FILES
+        );
+        files.truncate(MAX_OUTPUT_FILES);
+    }
+    files
+}
+
+/// Check a job's ingest directory, given as `memories/news/` or `news`, and
+/// return it relative to the memories directory.
+pub fn parse_ingest_dir(value: &str) -> Result<String> {
+    let dir = value
+        .trim()
+        .trim_start_matches("memories/")
+        .trim_end_matches('/');
+    let valid = !dir.is_empty()
+        && Path::new(dir)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+    if !valid {
+        anyhow::bail!(
+            "Expected a directory under memories/, e.g. memories/news/, got: {}",
+            value
+        );
+    }
+    Ok(dir.to_string())
+}
+
+/// Save a result as a memory file named after the day in `dir`, or after the
+/// time too when there's already one for the day.
+fn write_ingest_file(
+    dir: &Path,
+    job_name: &str,
+    response: &str,
+    at: DateTime<Local>,
+) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let date = at.format("%Y-%m-%d");
+    let mut path = dir.join(format!("{}.md", date));
+    if path.exists() {
+        path = dir.join(format!("{}-{}.md", date, at.format("%H%M%S")));
+    }
+    std::fs::write(
+        &path,
+        format!(
+            "# {} ({})\n\n{}\n",
+            job_name,
+            at.format("%Y-%m-%d %H:%M"),
+            response.trim()
+        ),
+    )?;
+    Ok(path)
+}
+
+/// Save a job's result to its ingest directory and index it with the owner's
+/// other memories.
+async fn ingest_result(job: &CronJob, dir: &str, response: &str) -> Result<PathBuf> {
+    let dir = memory::memories_dir(&job.channel, &job.user_id)?.join(dir);
+    let path = write_ingest_file(&dir, &job.name, response, Local::now())?;
+
+    let (channel, user_id) = (job.channel.clone(), job.user_id.clone());
+    tokio::task::spawn_blocking(move || channels::reindex_user_memories(&channel, &user_id))
+        .await?;
+    Ok(path)
+}
+
+/// Format a timestamp for display.
+pub fn format_timestamp(ms: u64) -> String {
+    format_timestamp_in(ms, None, "%Y-%m-%d %H:%M")
+}
+
+/// Format a timestamp in the user's timezone, or server time without one.
+pub fn format_timestamp_in(ms: u64, zone: Option<Tz>, format: &str) -> String {
+    let Some(d) = DateTime::from_timestamp_millis(ms as i64) else {
+        return "unknown".to_string();
+    };
+    match zone {
+        Some(zone) => d.with_timezone(&zone).format(format).to_string(),
+        None => d.with_timezone(&Local).format(format).to_string(),
+    }
+}
+
+/// Parse a /cron add command and return (schedule, prompt).
+pub fn parse_add_command(input: &str) -> Result<(CronSchedule, String)> {
+    let input = input.trim();
+
+    if input.is_empty() {
+        anyhow::bail!("Usage: /cron add <schedule> <prompt>");
+    }
+
+    // Try to find where schedule ends and prompt begins
+    // Patterns: "every Xunit", "at DATETIME", or cron "* * * * *"
+
+    if let Some(rest) = input.strip_prefix("on /") {
+        // "on /pattern/ prompt here" - the pattern may contain spaces
+        let Some((pattern, prompt)) = rest.split_once("/ ") else {
+            anyhow::bail!("Usage: /cron add on /<pattern>/ <prompt>");
+        };
+        let schedule =
+            CronSchedule::parse(&format!("on /{}/", pattern)).map_err(|e| anyhow::anyhow!(e))?;
+
+        return Ok((schedule, prompt.trim().to_string()));
+    }
+
+    if input.starts_with("ics ") {
+        // "ics <url> [30m before] prompt here"
+        let parts: Vec<&str> = input.splitn(5, ' ').collect();
+        let words = if parts
+            .get(3)
+            .is_some_and(|word| *word == "before" || *word == "after")
+        {
+            4
+        } else {
+            2
+        };
+        if parts.len() <= words {
+            anyhow::bail!("Usage: /cron add ics <url> [<duration> before|after] <prompt>");
+        }
+        let schedule =
+            CronSchedule::parse(&parts[..words].join(" ")).map_err(|e| anyhow::anyhow!(e))?;
+        let prompt = parts[words..].join(" ");
+
+        return Ok((schedule, prompt));
+    }
+
+    if input.starts_with("every ") {
+        // "every 1h prompt here"
+        let parts: Vec<&str> = input.splitn(3, ' ').collect();
+        if parts.len() < 3 {
+            anyhow::bail!("Usage: /cron add every <interval> <prompt>");
+        }
+        let schedule_str = format!("{} {}", parts[0], parts[1]);
+        let schedule = CronSchedule::parse(&schedule_str).map_err(|e| anyhow::anyhow!(e))?;
+        let prompt = parts[2].to_string();
+
+        return Ok((schedule, prompt));
+    }
+
+    if input.starts_with("at ") {
+        // "at 2024-01-28 14:00 prompt here" - datetime is 2 words
+        let parts: Vec<&str> = input.splitn(4, ' ').collect();
+        if parts.len() < 4 {
+            anyhow::bail!("Usage: /cron add at <date> <time> <prompt>");
+        }
+        let schedule_str = format!("{} {} {}", parts[0], parts[1], parts[2]);
+        let schedule = CronSchedule::parse(&schedule_str).map_err(|e| anyhow::anyhow!(e))?;
+        let prompt = parts[3].to_string();
+
+        return Ok((schedule, prompt));
+    }
+
+    // Try cron expression (5 fields separated by spaces)
+    let parts: Vec<&str> = input.splitn(6, ' ').collect();
+    if parts.len() >= 6 {
+        let cron_expr = format!(
+            "{} {} {} {} {}",
+            parts[0], parts[1], parts[2], parts[3], parts[4]
+        );
+        if let Ok(schedule) = CronSchedule::parse(&cron_expr) {
+            let prompt = parts[5].to_string();
+            return Ok((schedule, prompt));
+        }
+    }
+
+    anyhow::bail!(
+        "Could not parse schedule. Use:\n\
+         - every <interval> (e.g., every 1h, every 10s)\n\
+         - at <datetime> (e.g., at 2024-01-28 14:00)\n\
+         - <cron expression> (e.g., 0 9 * * *)\n\
+         - on /<pattern>/ (e.g., on /receipt/, when your messages match)\n\
+         - ics <url> [<duration> before|after] (at each event in a calendar feed)"
+    )
+}
+
+/// Describe when a schedule would run after `now_ms`: its next few runs (in
+/// `zone` if the user set one) and the spacing between them, so mistakes show
+/// before a job is created.
+pub fn preview_schedule(schedule: &CronSchedule, now_ms: u64, zone: Option<Tz>) -> String {
+    let mut text = format!("Schedule: {}\n", schedule.description());
+    let runs = schedule.upcoming(now_ms, PREVIEW_SAMPLE);
+    if runs.is_empty() {
+        text.push_str(match schedule {
+            CronSchedule::OnMessage(_) => {
+                "It runs whenever your message matches, not at set times."
+            }
+            CronSchedule::Ics(..) => {
+                "It runs around the calendar's events, once the feed is fetched."
+            }
+            _ => "It would never run: that time has passed.",
+        });
+        return text;
+    }
+
+    match zone {
+        Some(zone) => text.push_str(&format!("Next runs (your time, {}):\n", zone.name())),
+        None => text.push_str(&format!(
+            "Next runs (server time, UTC{}):\n",
+            Local::now().format("%:z")
+        )),
+    }
+    for at in runs.iter().take(PREVIEW_RUNS) {
+        let run = format_timestamp_in(*at, zone, "%a %Y-%m-%d %H:%M");
+        text.push_str(&format!("• {}\n", run));
+    }
+
+    let gaps: Vec<u64> = runs.windows(2).map(|pair| pair[1] - pair[0]).collect();
+    if let (Some(&min), Some(&max)) = (gaps.iter().min(), gaps.iter().max()) {
+        if min == max {
+            text.push_str(&format!("Between runs: {}\n", format_gap(min)));
+        } else {
+            text.push_str(&format!(
+                "Between runs: {} to {}\n",
+                format_gap(min),
+                format_gap(max)
+            ));
+        }
+        if min < FREQUENT_RUNS_MS {
+            text.push_str("\nThat's often: every run is a full query to the AI backend.\n");
+        }
+    }
+
+    // A common slip: "* 9 * * *" is every minute from 9:00 to 9:59
+    if let CronSchedule::Cron(expr) = schedule {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.first() == Some(&"*") && fields.get(1).is_some_and(|hour| *hour != "*") {
+            text.push_str(
+                "\nThe minute field is *, so it runs every minute of those hours. \
+                 Use 0 to run once at the start of each hour.\n",
+            );
+        }
+    }
+
+    text.trim_end().to_string()
+}
+
+/// Format the time between runs in its two largest units, e.g. "23h 1m".
+fn format_gap(ms: u64) -> String {
+    let units = [
+        (86_400_000, "d"),
+        (3_600_000, "h"),
+        (60_000, "m"),
+        (1_000, "s"),
+    ];
+    let parts: Vec<String> = units
+        .iter()
+        .scan(ms, |left, &(size, unit)| {
+            let count = *left / size;
+            *left %= size;
+            Some((count, unit))
+        })
+        .skip_while(|&(count, _)| count == 0)
+        .take(2)
+        .filter(|&(count, _)| count > 0)
+        .map(|(count, unit)| format!("{}{}", count, unit))
+        .collect();
+    if parts.is_empty() {
+        format!("{}ms", ms)
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Openings that make a reminder a task for the agent rather than a nudge
+const AGENT_REMINDERS: &[&str] = &[
+    "check ",
+    "look up ",
+    "look into ",
+    "search ",
+    "find out ",
+    "summarize ",
+    "summarise ",
+    "research ",
+    "fetch ",
+    "tell me ",
+    "let me know ",
+    "see if ",
+    "see whether ",
+    "what ",
+    "how ",
+];
+
+/// A one-shot reminder from /remind.
+#[derive(Debug, PartialEq)]
+pub struct Reminder {
+    pub at: u64,
+    pub text: String,
+    /// Whether the agent has to do something, rather than just pass it on
+    pub needs_agent: bool,
+}
+
+impl Reminder {
+    /// The job prompt: the text itself, or a task for the agent.
+    pub fn prompt(&self) -> String {
+        if self.needs_agent {
+            format!(
+                "The user asked to be reminded to {}. Do what that takes and reply with the reminder.",
+                self.text
+            )
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
+/// Parse a /remind command: `[me] in <duration>|at [<date>] <HH:MM> [to] <text>`.
+/// Times are read in `zone` if the user set one, otherwise in server time.
+pub fn parse_remind_command(input: &str, now_ms: u64, zone: Option<Tz>) -> Result<Reminder> {
+    let usage = "Usage: /remind me in <duration>|at <time> to <something>\n\n\
+                 Examples:\n\
+                 /remind me in 20m to take the pizza out\n\
+                 /remind me at 18:00 to call mom\n\
+                 /remind me at 2025-01-28 09:00 to check the flight status";
+
+    let input = input.trim();
+    let input = input.strip_prefix("me ").unwrap_or(input).trim_start();
+    let (at, rest) = if let Some(rest) = input.strip_prefix("in ") {
+        let (duration, rest) = rest.trim_start().split_once(' ').unwrap_or((rest, ""));
+        let ms = parse_duration(duration).map_err(|e| anyhow::anyhow!(e))?;
+        (now_ms + ms, rest)
+    } else if let Some(rest) = input.strip_prefix("at ") {
+        let words: Vec<&str> = rest.trim_start().splitn(3, ' ').collect();
+        let date_time = words.get(..2).map(|w| w.join(" "));
+        let parsed = date_time.as_deref().map(|date_time| match zone {
+            Some(zone) => parse_datetime_in(date_time, &zone),
+            None => parse_datetime_in(date_time, &Local),
+        });
+        match parsed {
+            Some(Ok(ts)) => (ts, words.get(2).copied().unwrap_or("")),
+            _ => {
+                let (time, rest) = rest.trim_start().split_once(' ').unwrap_or((rest, ""));
+                let at = match zone {
+                    Some(zone) => parse_time_of_day(time, now_ms, &zone),
+                    None => parse_time_of_day(time, now_ms, &Local),
+                };
+                (at.map_err(|e| anyhow::anyhow!(e))?, rest)
+            }
+        }
+    } else {
+        anyhow::bail!(usage);
+    };
+
+    if at <= now_ms {
+        anyhow::bail!("That time has already passed");
+    }
+
+    let rest = rest.trim();
+    let text = rest.strip_prefix("to ").unwrap_or(rest).trim();
+    if text.is_empty() {
+        anyhow::bail!(usage);
+    }
+
+    let lower = format!("{} ", text.to_lowercase());
+    Ok(Reminder {
+        at,
+        text: text.to_string(),
+        needs_agent: AGENT_REMINDERS.iter().any(|start| lower.starts_with(start)),
+    })
+}
+
+/// Format a job's run history for display, most recent first.
+pub fn format_runs(job: &CronJob) -> String {
+    if job.state.runs.is_empty() {
+        return format!("[{}] {} hasn't run yet.", job.short_id(), job.name);
+    }
+
+    let mut out = format!("Recent runs of [{}] {}:\n", job.short_id(), job.name);
+    for run in job.state.runs.iter().rev() {
+        out.push_str(&format!(
+            "\n{} - {} ({:.1}s)\n{}\n",
+            format_timestamp(run.started_at),
+            run.status.as_str(),
+            run.duration_ms as f64 / 1000.0,
+            run.output
+        ));
+    }
+    out
+}
+
+/// Truncate a string for use as a job name.
+pub fn truncate_for_name(s: &str, max_len: usize) -> String {
+    let s = s.trim();
+    if s.len() <= max_len {
+        s.to_string()
+    } else {
+        format!("{}...", &s[..max_len - 3])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_add_every() {
+        let (schedule, prompt) = parse_add_command("every 1h Check my emails").unwrap();
+        assert!(matches!(schedule, CronSchedule::Every(3_600_000)));
+        assert_eq!(prompt, "Check my emails");
+    }
+
+    #[test]
+    fn test_parse_add_every_short() {
+        let (schedule, prompt) = parse_add_command("every 10s Say hello").unwrap();
+        assert!(matches!(schedule, CronSchedule::Every(10_000)));
+        assert_eq!(prompt, "Say hello");
+    }
+
+    #[test]
+    fn test_parse_add_cron() {
+        let (schedule, prompt) = parse_add_command("0 9 * * * Good morning!").unwrap();
+        assert!(matches!(schedule, CronSchedule::Cron(_)));
+        assert_eq!(prompt, "Good morning!");
+    }
+
+    #[test]
+    fn test_preview_schedule() {
+        let preview = preview_schedule(&CronSchedule::Every(3_600_000), 0, None);
+        assert_eq!(preview.matches("\n• ").count(), PREVIEW_RUNS);
+        assert!(preview.contains("Between runs: 1h"));
+        assert!(!preview.contains("often"));
+
+        let preview = preview_schedule(&CronSchedule::Cron("* 9 * * *".to_string()), 0, None);
+        assert!(preview.contains("Between runs: 1m to 23h 1m"));
+        assert!(preview.contains("That's often"));
+        assert!(preview.contains("The minute field is *"));
+
+        assert_eq!(format_gap(90_061_000), "1d 1h");
+        assert_eq!(format_gap(259_200_000), "3d");
+
+        let preview = preview_schedule(&CronSchedule::At(1_000), 2_000, None);
+        assert!(preview.contains("never run"));
+
+        let preview = preview_schedule(
+            &CronSchedule::Every(3_600_000),
+            0,
+            Some(chrono_tz::Asia::Tokyo),
+        );
+        assert!(preview.contains("your time, Asia/Tokyo"));
+        assert!(preview.contains("Thu 1970-01-01 10:00"));
+    }
+
+    #[test]
+    fn test_parse_add_ics() {
+        let (schedule, prompt) =
+            parse_add_command("ics https://example.com/work.ics 30m before Prep notes").unwrap();
+        assert_eq!(
+            schedule,
+            CronSchedule::Ics("https://example.com/work.ics".to_string(), -1_800_000)
+        );
+        assert_eq!(prompt, "Prep notes");
+
+        let (schedule, prompt) =
+            parse_add_command("ics webcal://example.com/work.ics Summarize the meeting").unwrap();
+        assert_eq!(
+            schedule,
+            CronSchedule::Ics("webcal://example.com/work.ics".to_string(), 0)
+        );
+        assert_eq!(prompt, "Summarize the meeting");
+
+        assert!(parse_add_command("ics https://example.com/work.ics").is_err());
+    }
+
+    #[test]
+    fn test_parse_remind() {
+        let reminder =
+            parse_remind_command("me in 20m to take the pizza out", 1_000, None).unwrap();
+        assert_eq!(
+            reminder,
+            Reminder {
+                at: 1_201_000,
+                text: "take the pizza out".to_string(),
+                needs_agent: false,
+            }
+        );
+        assert_eq!(reminder.prompt(), "take the pizza out");
+
+        let reminder =
+            parse_remind_command("me in 1h to check the flight status", 0, None).unwrap();
+        assert!(reminder.needs_agent);
+        assert!(reminder.prompt().contains("check the flight status"));
+
+        assert!(
+            parse_remind_command("me at 2001-01-01 09:00 to vote", 4_000_000_000_000, None)
+                .is_err()
+        );
+        assert!(parse_remind_command("me in 20m", 0, None).is_err());
+        assert!(parse_remind_command("me tomorrow to call mom", 0, None).is_err());
+
+        // 09:00 in Tokyo is midnight UTC
+        let reminder = parse_remind_command(
+            "me at 2025-01-28 09:00 to vote",
+            0,
+            Some(chrono_tz::Asia::Tokyo),
+        )
+        .unwrap();
+        assert_eq!(reminder.at, 1_738_022_400_000);
+    }
+
+    #[test]
+    fn test_parse_ingest_dir() {
+        assert_eq!(parse_ingest_dir("memories/news/").unwrap(), "news");
+        assert_eq!(
+            parse_ingest_dir("research/papers").unwrap(),
+            "research/papers"
+        );
+        assert!(parse_ingest_dir("memories/").is_err());
+        assert!(parse_ingest_dir("../secrets").is_err());
+        assert!(parse_ingest_dir("/etc").is_err());
+    }
+
+    #[test]
+    fn test_write_ingest_file() {
+        use chrono::TimeZone;
+
+        let dir = std::env::temp_dir().join(format!("cica-ingest-{}", std::process::id()));
+        let at = Local.with_ymd_and_hms(2026, 3, 14, 7, 30, 0).unwrap();
+
+        let first = write_ingest_file(&dir, "news", " Rates held steady.\n", at).unwrap();
+        assert_eq!(first, dir.join("2026-03-14.md"));
+        assert_eq!(
+            std::fs::read_to_string(&first).unwrap(),
+            "# news (2026-03-14 07:30)\n\nRates held steady.\n"
+        );
+
+        let second = write_ingest_file(&dir, "news", "More news", at).unwrap();
+        assert_eq!(second, dir.join("2026-03-14-073000.md"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_output_files() {
+        let dir = std::env::temp_dir().join(format!("cica-outputs-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("b.csv"), "b").unwrap();
+        std::fs::write(dir.join("a.txt"), "a").unwrap();
+
+        assert_eq!(output_files(&dir), [dir.join("a.txt"), dir.join("b.csv")]);
+        assert!(output_files(&dir.join("missing")).is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_add_on_message() {
+        let (schedule, prompt) =
+            parse_add_command("on /receipt|paid for/ Log the expense in expenses.csv").unwrap();
+        assert_eq!(
+            schedule,
+            CronSchedule::OnMessage("receipt|paid for".to_string())
+        );
+        assert_eq!(prompt, "Log the expense in expenses.csv");
+    }
+
+    #[test]
+    fn test_truncate_for_name() {
+        assert_eq!(truncate_for_name("short", 10), "short");
+        assert_eq!(truncate_for_name("this is a long name", 10), "this is...");
+    }
+}
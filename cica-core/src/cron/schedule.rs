@@ -0,0 +1,447 @@
+//! Schedule types and parsing for cron jobs.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Days, Local, NaiveDateTime, NaiveTime, TimeZone};
+use croner::Cron;
+use regex_lite::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Supported schedule types for cron jobs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", content = "value")]
+pub enum CronSchedule {
+    /// One-shot execution at a specific timestamp (Unix milliseconds).
+    /// Example: "at 2024-01-28 14:00"
+    At(u64),
+
+    /// Recurring interval in milliseconds.
+    /// Example: "every 1h", "every 10s"
+    Every(u64),
+
+    /// Standard cron expression.
+    /// Example: "0 9 * * *" (9 AM daily)
+    Cron(String),
+
+    /// Whenever the job's owner sends a message matching this regex
+    /// (case-insensitive), instead of at set times.
+    /// Example: "on /receipt/"
+    OnMessage(String),
+
+    /// At each event in an ICS calendar feed, offset by some millis
+    /// (negative for before the event starts).
+    /// Example: "ics https://example.com/work.ics 30m before"
+    Ics(String, i64),
+}
+
+impl CronSchedule {
+    /// Parse a schedule string into a CronSchedule.
+    ///
+    /// Formats:
+    /// - "at 2024-01-28 14:00" or "at 2024-01-28T14:00:00"
+    /// - "every 10s", "every 5m", "every 1h", "every 2d"
+    /// - "0 9 * * *" (cron expression - 5 fields)
+    /// - "on /receipt|invoice/" (incoming messages matching a regex)
+    /// - "ics <url>", "ics <url> 30m before" (calendar feed events)
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+
+        if let Some(rest) = input.strip_prefix("ics ") {
+            return parse_ics(rest);
+        }
+
+        if input.starts_with("at ") {
+            let datetime_str = input.strip_prefix("at ").unwrap().trim();
+            let timestamp_ms = parse_datetime(datetime_str)?;
+            return Ok(CronSchedule::At(timestamp_ms));
+        }
+
+        if let Some(pattern) = input
+            .strip_prefix("on /")
+            .and_then(|rest| rest.strip_suffix('/'))
+        {
+            message_pattern(pattern)?;
+            return Ok(CronSchedule::OnMessage(pattern.to_string()));
+        }
+
+        if input.starts_with("every ") {
+            let interval_str = input.strip_prefix("every ").unwrap().trim();
+            let duration_ms = parse_duration(interval_str)?;
+            return Ok(CronSchedule::Every(duration_ms));
+        }
+
+        // Assume cron expression - validate it
+        validate_cron_expression(input)?;
+        Ok(CronSchedule::Cron(input.to_string()))
+    }
+
+    /// Calculate next run time from a reference timestamp (in milliseconds).
+    /// Returns None if the schedule has no future runs (e.g., one-shot in the past).
+    pub fn next_run_after(&self, after_ms: u64) -> Option<u64> {
+        match self {
+            CronSchedule::At(ts) => {
+                if *ts > after_ms {
+                    Some(*ts)
+                } else {
+                    None
+                }
+            }
+            CronSchedule::Every(interval) => Some(after_ms + interval),
+            CronSchedule::Cron(expr) => calculate_next_cron(expr, after_ms),
+            // Runs only when a message triggers it
+            CronSchedule::OnMessage(_) => None,
+            // Runs at the events of its feed, kept in the job's state
+            CronSchedule::Ics(..) => None,
+        }
+    }
+
+    /// The next `count` run times after `after_ms`; empty for schedules
+    /// without set times.
+    pub fn upcoming(&self, after_ms: u64, count: usize) -> Vec<u64> {
+        std::iter::successors(self.next_run_after(after_ms), |&at| self.next_run_after(at))
+            .take(count)
+            .collect()
+    }
+
+    /// Whether a message triggers this schedule.
+    pub fn matches_message(&self, text: &str) -> bool {
+        match self {
+            CronSchedule::OnMessage(pattern) => {
+                message_pattern(pattern).is_ok_and(|re| re.is_match(text))
+            }
+            _ => false,
+        }
+    }
+
+    /// Human-readable description of the schedule.
+    pub fn description(&self) -> String {
+        match self {
+            CronSchedule::At(ts) => {
+                let dt = DateTime::from_timestamp_millis(*ts as i64)
+                    .map(|d| d.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| ts.to_string());
+                format!("at {}", dt)
+            }
+            CronSchedule::Every(ms) => format_duration(*ms),
+            CronSchedule::Cron(expr) => expr.clone(),
+            CronSchedule::OnMessage(pattern) => format!("on messages matching /{}/", pattern),
+            CronSchedule::Ics(url, 0) => format!("at events in {}", url),
+            CronSchedule::Ics(url, offset) => format!(
+                "{} {} events in {}",
+                short_duration(offset.unsigned_abs()),
+                if *offset < 0 { "before" } else { "after" },
+                url
+            ),
+        }
+    }
+}
+
+/// Parse the "<url> [<duration> before|after]" of an ICS schedule.
+fn parse_ics(input: &str) -> Result<CronSchedule, String> {
+    let usage = "Use: ics <url> [<duration> before|after], e.g. ics https://example.com/work.ics 30m before";
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let (url, offset) = match words.as_slice() {
+        [url] => (*url, 0),
+        [url, duration, "before"] => (*url, -(parse_duration(duration)? as i64)),
+        [url, duration, "after"] => (*url, parse_duration(duration)? as i64),
+        _ => return Err(usage.to_string()),
+    };
+
+    if !["https://", "http://", "webcal://"]
+        .iter()
+        .any(|scheme| url.starts_with(scheme))
+    {
+        return Err(format!("Invalid calendar URL: {}. {}", url, usage));
+    }
+    Ok(CronSchedule::Ics(url.to_string(), offset))
+}
+
+/// Compile a message trigger's pattern, ignoring case. Compiled patterns are
+/// kept, since every message is checked against them.
+fn message_pattern(pattern: &str) -> Result<Regex, String> {
+    static COMPILED: Mutex<BTreeMap<String, Regex>> = Mutex::new(BTreeMap::new());
+
+    if pattern.is_empty() {
+        return Err("Empty message pattern".to_string());
+    }
+    let mut compiled = COMPILED.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(regex) = compiled.get(pattern) {
+        return Ok(regex.clone());
+    }
+    let regex = regex_lite::RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| format!("Invalid message pattern: {}", e))?;
+    compiled.insert(pattern.to_string(), regex.clone());
+    Ok(regex)
+}
+
+/// Parse duration strings like "10s", "5m", "1h", "2d".
+pub(crate) fn parse_duration(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err("Empty duration string".to_string());
+    }
+
+    // Find where digits end and unit begins
+    let num_end = s
+        .chars()
+        .position(|c| !c.is_ascii_digit())
+        .unwrap_or(s.len());
+
+    if num_end == 0 {
+        return Err(format!("Invalid duration: {}", s));
+    }
+
+    let (num_str, unit) = s.split_at(num_end);
+    let num: u64 = num_str
+        .parse()
+        .map_err(|_| format!("Invalid number: {}", num_str))?;
+
+    let unit = unit.trim();
+    let multiplier = match unit {
+        "s" | "sec" | "secs" | "second" | "seconds" => 1_000,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60_000,
+        "h" | "hr" | "hrs" | "hour" | "hours" => 3_600_000,
+        "d" | "day" | "days" => 86_400_000,
+        _ => return Err(format!("Invalid unit: {}. Use s/m/h/d", unit)),
+    };
+
+    Ok(num * multiplier)
+}
+
+/// Format milliseconds as a human-readable interval.
+fn format_duration(ms: u64) -> String {
+    format!("every {}", short_duration(ms))
+}
+
+/// Format milliseconds in the largest whole unit, e.g. "30m".
+fn short_duration(ms: u64) -> String {
+    if ms >= 86_400_000 && ms.is_multiple_of(86_400_000) {
+        format!("{}d", ms / 86_400_000)
+    } else if ms >= 3_600_000 && ms.is_multiple_of(3_600_000) {
+        format!("{}h", ms / 3_600_000)
+    } else if ms >= 60_000 && ms.is_multiple_of(60_000) {
+        format!("{}m", ms / 60_000)
+    } else if ms >= 1_000 && ms.is_multiple_of(1_000) {
+        format!("{}s", ms / 1_000)
+    } else {
+        format!("{}ms", ms)
+    }
+}
+
+/// Parse datetime string into Unix milliseconds.
+/// Supports: "2024-01-28 14:00", "2024-01-28T14:00:00", etc.
+pub(crate) fn parse_datetime(s: &str) -> Result<u64, String> {
+    parse_datetime_in(s, &Local)
+}
+
+/// Like [`parse_datetime`], reading the time in `zone` instead of server time.
+pub(crate) fn parse_datetime_in<Z: TimeZone>(s: &str, zone: &Z) -> Result<u64, String> {
+    let s = s.trim();
+
+    // Try various formats
+    let formats = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+    ];
+
+    for fmt in &formats {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(s, fmt) {
+            let local = zone.from_local_datetime(&naive).single();
+            if let Some(dt) = local {
+                return Ok(dt.timestamp_millis() as u64);
+            }
+        }
+    }
+
+    Err(format!(
+        "Invalid datetime: {}. Use format: YYYY-MM-DD HH:MM",
+        s
+    ))
+}
+
+/// Parse a time of day like "18:00" in `zone` as its next occurrence after `now_ms`.
+pub(crate) fn parse_time_of_day<Z: TimeZone>(
+    s: &str,
+    now_ms: u64,
+    zone: &Z,
+) -> Result<u64, String> {
+    let time = NaiveTime::parse_from_str(s.trim(), "%H:%M")
+        .map_err(|_| format!("Invalid time: {}. Use format: HH:MM", s.trim()))?;
+    let now = DateTime::from_timestamp_millis(now_ms as i64)
+        .ok_or_else(|| "Invalid current time".to_string())?
+        .with_timezone(zone);
+
+    // Today if it's still ahead, otherwise tomorrow
+    [0, 1]
+        .into_iter()
+        .filter_map(|days| now.date_naive().checked_add_days(Days::new(days)))
+        .filter_map(|date| zone.from_local_datetime(&date.and_time(time)).earliest())
+        .map(|dt| dt.timestamp_millis() as u64)
+        .find(|&ts| ts > now_ms)
+        .ok_or_else(|| format!("Can't schedule for {}", s.trim()))
+}
+
+/// Validate a cron expression.
+fn validate_cron_expression(expr: &str) -> Result<(), String> {
+    Cron::new(expr)
+        .parse()
+        .map_err(|e| format!("Invalid cron expression: {}", e))?;
+    Ok(())
+}
+
+/// Calculate next run time for a cron expression.
+fn calculate_next_cron(expr: &str, after_ms: u64) -> Option<u64> {
+    let cron = Cron::new(expr).parse().ok()?;
+    let after = DateTime::from_timestamp_millis(after_ms as i64)?;
+    let next = cron.find_next_occurrence(&after, false).ok()?;
+    Some(next.timestamp_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn test_message_trigger() {
+        let schedule = CronSchedule::parse("on /receipt|invoice/").unwrap();
+        assert_eq!(
+            schedule,
+            CronSchedule::OnMessage("receipt|invoice".to_string())
+        );
+        assert!(schedule.matches_message("Here's the Receipt from lunch"));
+        assert!(!schedule.matches_message("Lunch was great"));
+        assert_eq!(schedule.next_run_after(0), None);
+
+        assert!(CronSchedule::parse("on /(unclosed/").is_err());
+        assert!(CronSchedule::parse("on //").is_err());
+        assert!(!CronSchedule::Every(60_000).matches_message("receipt"));
+    }
+
+    #[test]
+    fn test_parse_ics() {
+        let url = "https://example.com/work.ics";
+        assert_eq!(
+            CronSchedule::parse(&format!("ics {}", url)),
+            Ok(CronSchedule::Ics(url.to_string(), 0))
+        );
+        let before = CronSchedule::parse(&format!("ics {} 30m before", url)).unwrap();
+        assert_eq!(before, CronSchedule::Ics(url.to_string(), -1_800_000));
+        assert_eq!(
+            before.description(),
+            "30m before events in https://example.com/work.ics"
+        );
+        assert_eq!(
+            CronSchedule::parse(&format!("ics {} 5m after", url)),
+            Ok(CronSchedule::Ics(url.to_string(), 300_000))
+        );
+
+        assert!(CronSchedule::parse("ics work.ics").is_err());
+        assert!(CronSchedule::parse(&format!("ics {} 30m", url)).is_err());
+        assert_eq!(before.next_run_after(0), None);
+    }
+
+    #[test]
+    fn test_upcoming() {
+        assert_eq!(
+            CronSchedule::Every(60_000).upcoming(1_000, 3),
+            vec![61_000, 121_000, 181_000]
+        );
+        assert_eq!(CronSchedule::At(5_000).upcoming(1_000, 3), vec![5_000]);
+        assert!(
+            CronSchedule::OnMessage("receipt".to_string())
+                .upcoming(0, 3)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_parse_time_of_day() {
+        let now = Local
+            .with_ymd_and_hms(2025, 1, 15, 12, 0, 0)
+            .unwrap()
+            .timestamp_millis() as u64;
+        let today = Local.with_ymd_and_hms(2025, 1, 15, 18, 0, 0).unwrap();
+        let tomorrow = Local.with_ymd_and_hms(2025, 1, 16, 9, 30, 0).unwrap();
+
+        assert_eq!(
+            parse_time_of_day("18:00", now, &Local).unwrap(),
+            today.timestamp_millis() as u64
+        );
+        assert_eq!(
+            parse_time_of_day("09:30", now, &Local).unwrap(),
+            tomorrow.timestamp_millis() as u64
+        );
+        assert!(parse_time_of_day("6pm", now, &Local).is_err());
+
+        // 18:00 in Tokyo (UTC+9) is 09:00 UTC
+        let tokyo = chrono_tz::Asia::Tokyo;
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 0, 0, 0).unwrap();
+        assert_eq!(
+            parse_time_of_day("18:00", now.timestamp_millis() as u64, &tokyo).unwrap(),
+            Utc.with_ymd_and_hms(2025, 1, 15, 9, 0, 0)
+                .unwrap()
+                .timestamp_millis() as u64
+        );
+        assert_eq!(
+            parse_datetime_in("2025-01-28 09:00", &tokyo).unwrap(),
+            Utc.with_ymd_and_hms(2025, 1, 28, 0, 0, 0)
+                .unwrap()
+                .timestamp_millis() as u64
+        );
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("10s").unwrap(), 10_000);
+        assert_eq!(parse_duration("5m").unwrap(), 300_000);
+        assert_eq!(parse_duration("1h").unwrap(), 3_600_000);
+        assert_eq!(parse_duration("2d").unwrap(), 172_800_000);
+        assert_eq!(parse_duration("30min").unwrap(), 1_800_000);
+        assert_eq!(parse_duration("24hours").unwrap(), 86_400_000);
+    }
+
+    #[test]
+    fn test_parse_duration_errors() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("abc").is_err());
+        assert!(parse_duration("10x").is_err());
+    }
+
+    #[test]
+    fn test_schedule_parse_every() {
+        assert!(matches!(
+            CronSchedule::parse("every 10s"),
+            Ok(CronSchedule::Every(10_000))
+        ));
+        assert!(matches!(
+            CronSchedule::parse("every 1h"),
+            Ok(CronSchedule::Every(3_600_000))
+        ));
+    }
+
+    #[test]
+    fn test_schedule_parse_cron() {
+        let result = CronSchedule::parse("0 9 * * *");
+        assert!(matches!(result, Ok(CronSchedule::Cron(_))));
+    }
+
+    #[test]
+    fn test_schedule_next_run_every() {
+        let schedule = CronSchedule::Every(60_000);
+        assert_eq!(schedule.next_run_after(1000), Some(61_000));
+    }
+
+    #[test]
+    fn test_schedule_next_run_at() {
+        let schedule = CronSchedule::At(5000);
+        assert_eq!(schedule.next_run_after(1000), Some(5000));
+        assert_eq!(schedule.next_run_after(5000), None);
+        assert_eq!(schedule.next_run_after(6000), None);
+    }
+}
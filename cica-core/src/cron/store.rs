@@ -0,0 +1,1589 @@
+//! Persistent storage for cron jobs.
+
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use crate::config::AiBackend;
+use crate::storage::{self, Table};
+
+use super::ics::CalendarEvent;
+use super::schedule::CronSchedule;
+
+/// Unique identifier for a cron job.
+pub type JobId = String;
+
+/// Status of last job execution.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(tag = "status", content = "error")]
+pub enum JobStatus {
+    #[default]
+    Pending,
+    Running,
+    Success,
+    Failed(String),
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Success => "success",
+            JobStatus::Failed(_) => "failed",
+        }
+    }
+}
+
+/// How many runs are kept per job, for /cron log.
+pub const MAX_RUN_HISTORY: usize = 10;
+
+/// How much of a run's output is kept.
+const MAX_RUN_OUTPUT_CHARS: usize = 500;
+
+/// How many triggering messages are queued for a job's next run.
+const MAX_TRIGGER_MESSAGES: usize = 20;
+
+/// Accept either a single message or a list of them.
+fn one_or_many<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(Option<String>),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(message) => message.into_iter().collect(),
+        OneOrMany::Many(messages) => messages,
+    })
+}
+
+/// A past execution of a job.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JobRun {
+    /// Start timestamp (Unix millis).
+    pub started_at: u64,
+
+    /// Execution duration in milliseconds.
+    pub duration_ms: u64,
+
+    /// How the run ended.
+    pub status: JobStatus,
+
+    /// Start of the response, or the error.
+    pub output: String,
+}
+
+/// Runtime state for a job (mutable between runs).
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CronJobState {
+    /// Next scheduled run time (Unix millis).
+    pub next_run_at: Option<u64>,
+
+    /// Last run timestamp (Unix millis).
+    pub last_run_at: Option<u64>,
+
+    /// Status of last execution.
+    #[serde(default)]
+    pub last_status: JobStatus,
+
+    /// Last execution duration in milliseconds.
+    pub last_duration_ms: Option<u64>,
+
+    /// Count of consecutive failures.
+    #[serde(default)]
+    pub failure_count: u32,
+
+    /// Retries made since the last scheduled run.
+    #[serde(default)]
+    pub retry_attempt: u32,
+
+    /// Fingerprint of the last successful output, for `NotifyWhen::OnChange`.
+    #[serde(default)]
+    pub last_output_hash: Option<u64>,
+
+    /// Most recent runs, oldest first.
+    #[serde(default)]
+    pub runs: Vec<JobRun>,
+
+    /// Backend session the next run resumes, for `SessionMode::Continue`.
+    #[serde(default)]
+    pub session_id: Option<String>,
+
+    /// Messages that triggered the next run, oldest first, for
+    /// `CronSchedule::OnMessage`. Older versions stored just one.
+    #[serde(default, alias = "trigger_message", deserialize_with = "one_or_many")]
+    pub trigger_messages: Vec<String>,
+
+    /// Upcoming events of the feed, for `CronSchedule::Ics`.
+    #[serde(default)]
+    pub calendar: Vec<CalendarEvent>,
+
+    /// When the feed was last fetched (Unix millis).
+    #[serde(default)]
+    pub calendar_fetched_at: Option<u64>,
+
+    /// Event the next run is for, for `CronSchedule::Ics`.
+    #[serde(default)]
+    pub calendar_event: Option<CalendarEvent>,
+}
+
+/// How often calendar feeds are fetched again (millis).
+const CALENDAR_REFRESH_MS: u64 = 15 * 60_000;
+
+/// A scheduled cron job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CronJob {
+    /// Unique job ID.
+    pub id: JobId,
+
+    /// Human-readable name.
+    pub name: String,
+
+    /// The Claude Code prompt to execute.
+    pub prompt: String,
+
+    /// Schedule configuration.
+    pub schedule: CronSchedule,
+
+    /// Owner: channel name (e.g., "telegram", "signal").
+    pub channel: String,
+
+    /// Owner: user ID within the channel.
+    pub user_id: String,
+
+    /// Whether to send results back to the user's chat.
+    #[serde(default = "default_true")]
+    pub notify: bool,
+
+    /// Which results to send, when `notify` is on. Failures are always sent.
+    #[serde(default)]
+    pub notify_when: NotifyWhen,
+
+    /// What to do when the job is due while its previous run is still going.
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+
+    /// Stop a run after this long (millis); defaults to the backend query timeout.
+    #[serde(default)]
+    pub max_runtime_ms: Option<u64>,
+
+    /// Which due jobs get the free slots first.
+    #[serde(default)]
+    pub priority: Priority,
+
+    /// Send the prompt as is instead of running it (plain `/remind` reminders).
+    #[serde(default)]
+    pub reminder: bool,
+
+    /// Whether each run starts a new session or builds on the previous runs.
+    #[serde(default)]
+    pub session: SessionMode,
+
+    /// Backend to run on instead of the configured one.
+    #[serde(default)]
+    pub backend: Option<AiBackend>,
+
+    /// Model to run with instead of the backend's configured one.
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Directory under the owner's memories/ that results are saved to as
+    /// dated memory files, e.g. "news".
+    #[serde(default)]
+    pub ingest: Option<String>,
+
+    /// Job is enabled (can be paused).
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// What to do when a run fails.
+    #[serde(default)]
+    pub retry: RetryPolicy,
+
+    /// Creation timestamp (Unix millis).
+    pub created_at: u64,
+
+    /// Runtime state.
+    #[serde(default)]
+    pub state: CronJobState,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Reply a job's agent gives when `NotifyWhen::Noteworthy` and there's nothing to report.
+pub const QUIET_REPLY: &str = "NOTHING_NEW";
+
+/// Which successful results of a job are sent to the user.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum NotifyWhen {
+    /// Every result.
+    #[default]
+    Always,
+    /// None; only failures.
+    OnFailure,
+    /// Results that differ from the previous one.
+    OnChange,
+    /// Results the agent considers worth telling (it replies QUIET_REPLY otherwise).
+    Noteworthy,
+}
+
+impl NotifyWhen {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotifyWhen::Always => "always",
+            NotifyWhen::OnFailure => "on failure",
+            NotifyWhen::OnChange => "on change",
+            NotifyWhen::Noteworthy => "when noteworthy",
+        }
+    }
+}
+
+impl std::str::FromStr for NotifyWhen {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "always" => Ok(NotifyWhen::Always),
+            "failure" | "on-failure" => Ok(NotifyWhen::OnFailure),
+            "change" | "on-change" => Ok(NotifyWhen::OnChange),
+            "noteworthy" => Ok(NotifyWhen::Noteworthy),
+            _ => Err(anyhow::anyhow!(
+                "Unknown notify mode: {} (expected always, failure, change, noteworthy or off)",
+                s
+            )),
+        }
+    }
+}
+
+/// What to do when a job is due while its previous run is still going.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverlapPolicy {
+    /// Drop the new trigger.
+    #[default]
+    Skip,
+    /// Run again as soon as the previous run ends.
+    Queue,
+    /// Stop the previous run and start a new one.
+    KillPrevious,
+}
+
+impl OverlapPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OverlapPolicy::Skip => "skip",
+            OverlapPolicy::Queue => "queue",
+            OverlapPolicy::KillPrevious => "kill-previous",
+        }
+    }
+}
+
+impl std::str::FromStr for OverlapPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "skip" => Ok(OverlapPolicy::Skip),
+            "queue" => Ok(OverlapPolicy::Queue),
+            "kill" | "kill-previous" => Ok(OverlapPolicy::KillPrevious),
+            _ => Err(anyhow::anyhow!(
+                "Unknown overlap policy: {} (expected skip, queue or kill-previous)",
+                s
+            )),
+        }
+    }
+}
+
+/// Which session a job's runs happen in.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SessionMode {
+    /// A new session every run.
+    #[default]
+    Fresh,
+    /// Resume the job's own session, so runs can build on earlier ones.
+    Continue,
+}
+
+impl SessionMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionMode::Fresh => "fresh",
+            SessionMode::Continue => "continue",
+        }
+    }
+}
+
+impl std::str::FromStr for SessionMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "fresh" | "new" => Ok(SessionMode::Fresh),
+            "continue" => Ok(SessionMode::Continue),
+            _ => Err(anyhow::anyhow!(
+                "Unknown session mode: {} (expected fresh or continue)",
+                s
+            )),
+        }
+    }
+}
+
+/// Which due jobs run first when there are more than free slots.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl Priority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+        }
+    }
+}
+
+impl std::str::FromStr for Priority {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "low" => Ok(Priority::Low),
+            "normal" => Ok(Priority::Normal),
+            "high" => Ok(Priority::High),
+            _ => Err(anyhow::anyhow!(
+                "Unknown priority: {} (expected low, normal or high)",
+                s
+            )),
+        }
+    }
+}
+
+/// Fingerprint of a job's output, to tell whether it changed between runs.
+pub fn output_hash(output: &str) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    output.trim().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// What to do when a job fails.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetryPolicy {
+    /// Extra attempts after a failed run, before waiting for the next scheduled one.
+    #[serde(default)]
+    pub max_retries: u32,
+
+    /// Wait before the first retry (millis); doubles with each further retry.
+    #[serde(default = "RetryPolicy::default_backoff_ms")]
+    pub backoff_ms: u64,
+
+    /// Pause the job after this many failed runs in a row, retries included (0 = never).
+    #[serde(default = "RetryPolicy::default_pause_after")]
+    pub pause_after: u32,
+}
+
+impl RetryPolicy {
+    fn default_backoff_ms() -> u64 {
+        60_000
+    }
+
+    fn default_pause_after() -> u32 {
+        5
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            backoff_ms: Self::default_backoff_ms(),
+            pause_after: Self::default_pause_after(),
+        }
+    }
+}
+
+/// What happens to a job after a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    /// Waits for its next scheduled run (if any).
+    Scheduled,
+    /// Failed and will be tried again after this many millis.
+    Retrying(u64),
+    /// Failed too often in a row and was paused.
+    Paused,
+}
+
+/// A change to an existing job, keeping its ID and run history.
+#[derive(Debug, Clone)]
+pub enum JobEdit {
+    Schedule(CronSchedule),
+    Prompt(String),
+    Name(String),
+    Retries(u32),
+    /// Millis before the first retry.
+    Backoff(u64),
+    PauseAfter(u32),
+    /// None turns notifications off.
+    Notify(Option<NotifyWhen>),
+    Overlap(OverlapPolicy),
+    /// Millis, or None for the backend's query timeout.
+    MaxRuntime(Option<u64>),
+    Priority(Priority),
+    /// Switching to `Fresh` forgets the job's session.
+    Session(SessionMode),
+    /// None for the configured backend.
+    Backend(Option<AiBackend>),
+    /// None for the backend's configured model.
+    Model(Option<String>),
+    /// Memory directory for results, or None to stop saving them.
+    Ingest(Option<String>),
+}
+
+impl CronJob {
+    /// Create a new job with generated ID.
+    pub fn new(
+        name: String,
+        prompt: String,
+        schedule: CronSchedule,
+        channel: String,
+        user_id: String,
+    ) -> Self {
+        let now = now_millis();
+        let mut job = Self {
+            id: generate_job_id(),
+            name,
+            prompt,
+            schedule,
+            channel,
+            user_id,
+            notify: true,
+            notify_when: NotifyWhen::default(),
+            overlap: OverlapPolicy::default(),
+            max_runtime_ms: None,
+            priority: Priority::default(),
+            reminder: false,
+            session: SessionMode::default(),
+            backend: None,
+            model: None,
+            ingest: None,
+            enabled: true,
+            retry: RetryPolicy::default(),
+            created_at: now,
+            state: CronJobState::default(),
+        };
+        job.update_next_run(now);
+        job
+    }
+
+    /// A new job with this one's prompt, schedule and settings, for
+    /// `channel`/`user_id`. Its run history starts empty.
+    pub fn duplicate(&self, name: String, channel: &str, user_id: &str) -> Self {
+        let now = now_millis();
+        let mut job = Self {
+            id: generate_job_id(),
+            name,
+            channel: channel.to_string(),
+            user_id: user_id.to_string(),
+            enabled: true,
+            created_at: now,
+            state: CronJobState::default(),
+            ..self.clone()
+        };
+        job.update_next_run(now);
+        job
+    }
+
+    /// User key for ownership (channel:user_id).
+    #[allow(dead_code)]
+    pub fn user_key(&self) -> String {
+        format!("{}:{}", self.channel, self.user_id)
+    }
+
+    /// Which backend and model runs the job, for showing to the user.
+    pub fn model_description(&self) -> String {
+        match (self.backend, &self.model) {
+            (None, None) => "the configured one".to_string(),
+            (Some(backend), None) => backend.as_str().to_string(),
+            (None, Some(model)) => model.clone(),
+            (Some(backend), Some(model)) => format!("{} on {}", model, backend.as_str()),
+        }
+    }
+
+    /// Calculate and update next_run_at based on given time.
+    pub fn update_next_run(&mut self, now_ms: u64) {
+        if let CronSchedule::Ics(_, offset) = self.schedule {
+            let next = self
+                .state
+                .calendar
+                .iter()
+                .filter(|event| event.start_at.saturating_add_signed(offset) > now_ms)
+                .min_by_key(|event| event.start_at)
+                .cloned();
+            self.state.next_run_at = next
+                .as_ref()
+                .map(|event| event.start_at.saturating_add_signed(offset));
+            self.state.calendar_event = next;
+            return;
+        }
+        self.state.next_run_at = self.schedule.next_run_after(now_ms);
+    }
+
+    /// The feed to fetch, if this is a calendar job not fetched lately.
+    pub fn calendar_refresh_due(&self, now_ms: u64) -> Option<&str> {
+        match &self.schedule {
+            CronSchedule::Ics(url, _)
+                if self.enabled
+                    && self
+                        .state
+                        .calendar_fetched_at
+                        .is_none_or(|at| now_ms >= at + CALENDAR_REFRESH_MS) =>
+            {
+                Some(url)
+            }
+            _ => None,
+        }
+    }
+
+    /// Replace a calendar job's events and reschedule it, unless a run
+    /// is already due or a retry is waiting.
+    pub fn set_calendar(&mut self, events: Vec<CalendarEvent>, now_ms: u64) {
+        self.state.calendar = events;
+        let pending =
+            self.state.retry_attempt > 0 || self.state.next_run_at.is_some_and(|at| at <= now_ms);
+        if self.enabled && !pending {
+            self.update_next_run(now_ms);
+        }
+    }
+
+    /// Apply an edit. A new schedule takes effect from `now_ms`.
+    pub fn apply(&mut self, edit: JobEdit, now_ms: u64) {
+        match edit {
+            JobEdit::Schedule(schedule) => {
+                self.schedule = schedule;
+                // A calendar feed is fetched again from scratch
+                self.state.calendar.clear();
+                self.state.calendar_fetched_at = None;
+                self.state.calendar_event = None;
+                if self.enabled {
+                    self.update_next_run(now_ms);
+                }
+            }
+            JobEdit::Prompt(prompt) => self.prompt = prompt,
+            JobEdit::Name(name) => self.name = name,
+            JobEdit::Retries(max_retries) => self.retry.max_retries = max_retries,
+            JobEdit::Backoff(backoff_ms) => self.retry.backoff_ms = backoff_ms,
+            JobEdit::PauseAfter(pause_after) => self.retry.pause_after = pause_after,
+            JobEdit::Overlap(overlap) => self.overlap = overlap,
+            JobEdit::MaxRuntime(max_runtime_ms) => self.max_runtime_ms = max_runtime_ms,
+            JobEdit::Priority(priority) => self.priority = priority,
+            JobEdit::Session(session) => {
+                self.session = session;
+                if session == SessionMode::Fresh {
+                    self.state.session_id = None;
+                }
+            }
+            JobEdit::Backend(backend) => self.backend = backend,
+            JobEdit::Model(model) => self.model = model,
+            JobEdit::Ingest(dir) => self.ingest = dir,
+            JobEdit::Notify(when) => {
+                self.notify = when.is_some();
+                self.notify_when = when.unwrap_or_default();
+            }
+        }
+    }
+
+    /// Update failure counts and schedule the job after a run ending at `now_ms`.
+    pub fn after_run(&mut self, succeeded: bool, now_ms: u64) -> RunOutcome {
+        if succeeded {
+            self.state.failure_count = 0;
+            self.state.retry_attempt = 0;
+            self.schedule_if_unscheduled(now_ms);
+
+            // One-shot At jobs are done once they succeed
+            if matches!(self.schedule, CronSchedule::At(_)) {
+                self.enabled = false;
+                self.state.next_run_at = None;
+            }
+            return RunOutcome::Scheduled;
+        }
+
+        self.state.failure_count += 1;
+        if self.retry.pause_after > 0 && self.state.failure_count >= self.retry.pause_after {
+            self.enabled = false;
+            self.state.retry_attempt = 0;
+            self.state.next_run_at = None;
+            return RunOutcome::Paused;
+        }
+
+        if self.state.retry_attempt < self.retry.max_retries {
+            let wait = self
+                .retry
+                .backoff_ms
+                .saturating_mul(1 << self.state.retry_attempt.min(16));
+            self.state.retry_attempt += 1;
+            self.state.next_run_at = Some(now_ms + wait);
+            return RunOutcome::Retrying(wait);
+        }
+
+        self.state.retry_attempt = 0;
+        self.schedule_if_unscheduled(now_ms);
+        RunOutcome::Scheduled
+    }
+
+    /// Mark the job as running from `now_ms`. Its schedule keeps going, so
+    /// the next trigger may come while it runs (see `OverlapPolicy`).
+    pub fn start_run(&mut self, now_ms: u64) {
+        self.state.last_status = JobStatus::Running;
+        // Messages that come while this run goes trigger the next one
+        self.state.trigger_messages.clear();
+        match self.schedule {
+            // One-shot jobs only come back for retries
+            CronSchedule::At(_) => self.state.next_run_at = None,
+            _ => self.update_next_run(now_ms),
+        }
+    }
+
+    /// Schedule the next run unless one is set already (e.g. a trigger
+    /// that came while running, which shouldn't be lost).
+    fn schedule_if_unscheduled(&mut self, now_ms: u64) {
+        if self.state.next_run_at.is_none() {
+            self.update_next_run(now_ms);
+        }
+    }
+
+    /// Whether a run's result should be sent to the user. `changed` says
+    /// whether a successful output differs from the previous one.
+    pub fn should_notify(&self, succeeded: bool, changed: bool, response: &str) -> bool {
+        if !self.notify {
+            return false;
+        }
+        if !succeeded {
+            return true;
+        }
+
+        match self.notify_when {
+            NotifyWhen::Always => true,
+            NotifyWhen::OnFailure => false,
+            NotifyWhen::OnChange => changed,
+            NotifyWhen::Noteworthy => response.trim() != QUIET_REPLY,
+        }
+    }
+
+    /// Add a run to the history, dropping the oldest beyond MAX_RUN_HISTORY.
+    pub fn record_run(&mut self, mut run: JobRun) {
+        if let Some((cut, _)) = run.output.char_indices().nth(MAX_RUN_OUTPUT_CHARS) {
+            run.output.truncate(cut);
+            run.output.push_str("...");
+        }
+
+        self.state.runs.push(run);
+        let excess = self.state.runs.len().saturating_sub(MAX_RUN_HISTORY);
+        self.state.runs.drain(..excess);
+    }
+
+    /// Whether `text` from the user triggers this job.
+    fn is_triggered_by(&self, channel: &str, user_id: &str, text: &str) -> bool {
+        self.enabled
+            && self.channel == channel
+            && self.user_id == user_id
+            && self.schedule.matches_message(text)
+    }
+
+    /// Check if this job is due to run.
+    pub fn is_due(&self, now_ms: u64) -> bool {
+        self.enabled && self.state.next_run_at.is_some_and(|t| t <= now_ms)
+    }
+
+    /// Short ID for display (first 8 chars).
+    pub fn short_id(&self) -> &str {
+        if self.id.len() > 8 {
+            &self.id[..8]
+        } else {
+            &self.id
+        }
+    }
+}
+
+/// Persistent storage for cron jobs.
+/// Follows PairingStore pattern, persisted in the cron_jobs table.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CronStore {
+    /// All jobs indexed by ID.
+    pub jobs: HashMap<JobId, CronJob>,
+}
+
+impl CronStore {
+    /// Load cron store from the database.
+    pub fn load() -> Result<Self> {
+        storage::read(Self::read_from)
+    }
+
+    /// Apply a change to the latest jobs in the database and save them.
+    /// See [`crate::pairing::PairingStore::update`].
+    pub fn update<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let result = storage::write(|conn| {
+            *self = Self::read_from(conn)?;
+            let before = self.table()?;
+            let result = f(self)?;
+            self.write_changes(before, conn)?;
+            Ok(result)
+        })?;
+        // Wake the scheduler in this process to look at the new state
+        changed().notify_one();
+        Ok(result)
+    }
+
+    /// Read all jobs from the database.
+    pub(crate) fn read_from(conn: &Connection) -> Result<Self> {
+        let mut stmt = conn.prepare("SELECT job FROM cron_jobs")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+        let mut jobs = HashMap::new();
+        for row in rows {
+            let job: CronJob = serde_json::from_str(&row?)?;
+            jobs.insert(job.id.clone(), job);
+        }
+
+        Ok(Self { jobs })
+    }
+
+    /// Replace the stored jobs with this store's.
+    pub(crate) fn write_to(&self, conn: &Connection) -> Result<()> {
+        self.write_changes(Self::read_from(conn)?.table()?, conn)
+    }
+
+    /// Save the jobs that changed since `before`, row by row
+    fn write_changes(&self, before: Table, conn: &Connection) -> Result<()> {
+        if storage::write_changes(conn, &[before], &[self.table()?])? > 0 {
+            bump_version(conn)?;
+        }
+        Ok(())
+    }
+
+    /// The jobs as database rows
+    fn table(&self) -> Result<Table> {
+        let mut table = Table::new("cron_jobs", &["id"], &["channel", "user_id", "name", "job"]);
+        for job in self.jobs.values() {
+            table.push(vec![
+                job.id.clone().into(),
+                job.channel.clone().into(),
+                job.user_id.clone().into(),
+                job.name.clone().into(),
+                serde_json::to_string(job)?.into(),
+            ]);
+        }
+        Ok(table)
+    }
+
+    /// Add a new job. Names must be unique per user.
+    pub fn add(&mut self, job: CronJob) -> Result<JobId> {
+        self.update(|store| {
+            if store
+                .find_by_name(&job.channel, &job.user_id, &job.name)
+                .is_some()
+            {
+                anyhow::bail!("You already have a job named \"{}\"", job.name);
+            }
+
+            let id = job.id.clone();
+            store.jobs.insert(id.clone(), job);
+
+            Ok(id)
+        })
+    }
+
+    /// Remove a job by ID (only if user owns it).
+    pub fn remove(&mut self, id: &str, channel: &str, user_id: &str) -> Result<Option<CronJob>> {
+        self.update(|store| {
+            // Check ownership first
+            if let Some(job) = store.jobs.get(id)
+                && (job.channel != channel || job.user_id != user_id)
+            {
+                anyhow::bail!("You don't own this job");
+            }
+
+            Ok(store.jobs.remove(id))
+        })
+        .inspect(|removed| {
+            // Output files go with the job
+            if removed.is_some()
+                && let Ok(dir) = super::outputs_dir(id)
+                && let Some(job_dir) = dir.parent()
+            {
+                let _ = std::fs::remove_dir_all(job_dir);
+            }
+        })
+    }
+
+    /// Edit a job (only if user owns it). Names must stay unique per user.
+    pub fn edit(
+        &mut self,
+        id: &str,
+        channel: &str,
+        user_id: &str,
+        edit: JobEdit,
+    ) -> Result<Option<CronJob>> {
+        self.update(|store| {
+            if let JobEdit::Name(name) = &edit
+                && store
+                    .find_by_name(channel, user_id, name)
+                    .is_some_and(|job| job.id != id)
+            {
+                anyhow::bail!("You already have a job named \"{}\"", name);
+            }
+
+            let Some(job) = store
+                .jobs
+                .get_mut(id)
+                .filter(|job| job.channel == channel && job.user_id == user_id)
+            else {
+                return Ok(None);
+            };
+            job.apply(edit, now_millis());
+            Ok(Some(job.clone()))
+        })
+    }
+
+    /// List jobs for a specific user.
+    pub fn list_for_user(&self, channel: &str, user_id: &str) -> Vec<&CronJob> {
+        self.jobs
+            .values()
+            .filter(|j| j.channel == channel && j.user_id == user_id)
+            .collect()
+    }
+
+    /// Get a job by ID (with ownership check).
+    pub fn get(&self, id: &str, channel: &str, user_id: &str) -> Option<&CronJob> {
+        self.jobs
+            .get(id)
+            .filter(|j| j.channel == channel && j.user_id == user_id)
+    }
+
+    /// Find a user's job by name (case-insensitive).
+    pub fn find_by_name(&self, channel: &str, user_id: &str, name: &str) -> Option<&CronJob> {
+        let name = name.trim();
+        self.jobs.values().find(|j| {
+            j.channel == channel && j.user_id == user_id && j.name.eq_ignore_ascii_case(name)
+        })
+    }
+
+    /// Make a name unique for a user by appending a counter if it's taken.
+    pub fn unique_name(&self, channel: &str, user_id: &str, name: &str) -> String {
+        if self.find_by_name(channel, user_id, name).is_none() {
+            return name.to_string();
+        }
+
+        (2..)
+            .map(|n| format!("{} ({})", name, n))
+            .find(|candidate| self.find_by_name(channel, user_id, candidate).is_none())
+            .expect("unbounded counter always finds a free name")
+    }
+
+    /// Get mutable reference (internal use, no ownership check).
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut CronJob> {
+        self.jobs.get_mut(id)
+    }
+
+    /// Whether `text` triggers any of the user's jobs
+    pub fn has_message_trigger(&self, channel: &str, user_id: &str, text: &str) -> bool {
+        self.jobs
+            .values()
+            .any(|job| job.is_triggered_by(channel, user_id, text))
+    }
+
+    /// Make the user's jobs whose trigger matches `text` due now, returning
+    /// their names. Messages that come before a run starts are all passed to
+    /// it, up to `MAX_TRIGGER_MESSAGES`.
+    pub fn trigger_on_message(
+        &mut self,
+        channel: &str,
+        user_id: &str,
+        text: &str,
+        now_ms: u64,
+    ) -> Vec<String> {
+        self.jobs
+            .values_mut()
+            .filter(|job| job.is_triggered_by(channel, user_id, text))
+            .map(|job| {
+                let messages = &mut job.state.trigger_messages;
+                messages.push(text.to_string());
+                if messages.len() > MAX_TRIGGER_MESSAGES {
+                    messages.drain(..messages.len() - MAX_TRIGGER_MESSAGES);
+                }
+                job.state.next_run_at = Some(now_ms);
+                job.name.clone()
+            })
+            .collect()
+    }
+
+    /// Mark the calendar feeds due a refresh as fetched at `now_ms`,
+    /// returning (job ID, feed URL) for each to fetch.
+    pub fn claim_calendar_refreshes(&mut self, now_ms: u64) -> Vec<(JobId, String)> {
+        self.jobs
+            .values_mut()
+            .filter_map(|job| {
+                let url = job.calendar_refresh_due(now_ms)?.to_string();
+                job.state.calendar_fetched_at = Some(now_ms);
+                Some((job.id.clone(), url))
+            })
+            .collect()
+    }
+
+    /// Move the runs of recurring jobs that are overdue (e.g. missed while
+    /// the scheduler was paused) to their next time. One-shot jobs keep
+    /// theirs. Returns how many moved.
+    pub fn reschedule_missed(&mut self, now_ms: u64) -> usize {
+        let mut moved = 0;
+        for job in self.jobs.values_mut() {
+            if job.is_due(now_ms) && !matches!(job.schedule, CronSchedule::At(_)) {
+                job.state.retry_attempt = 0;
+                job.state.trigger_messages.clear();
+                job.update_next_run(now_ms);
+                moved += 1;
+            }
+        }
+        moved
+    }
+
+    /// Earliest time after `now_ms` that an enabled job is due.
+    pub fn next_run_after(&self, now_ms: u64) -> Option<u64> {
+        self.jobs
+            .values()
+            .filter(|j| j.enabled)
+            .filter_map(|j| j.state.next_run_at)
+            .filter(|&at| at > now_ms)
+            .min()
+    }
+
+    /// Get all jobs that are due to run, in the order they should start:
+    /// highest priority first, then the longest overdue.
+    pub fn get_due_jobs(&self, now_ms: u64) -> Vec<&CronJob> {
+        let mut due: Vec<&CronJob> = self.jobs.values().filter(|j| j.is_due(now_ms)).collect();
+        due.sort_by_key(|j| (std::cmp::Reverse(j.priority), j.state.next_run_at));
+        due
+    }
+
+    /// Get all enabled jobs (for scheduler).
+    #[allow(dead_code)]
+    pub fn get_enabled_jobs(&self) -> Vec<&CronJob> {
+        self.jobs.values().filter(|j| j.enabled).collect()
+    }
+
+    /// Reset any jobs stuck in Running state (e.g., after a crash).
+    /// Recalculates next_run_at so they get scheduled again.
+    pub fn recover_stuck_jobs(&mut self, now_ms: u64) -> usize {
+        let stuck_ids: Vec<JobId> = self
+            .jobs
+            .values()
+            .filter(|j| j.state.last_status == JobStatus::Running)
+            .map(|j| j.id.clone())
+            .collect();
+
+        let count = stuck_ids.len();
+        for id in &stuck_ids {
+            if let Some(job) = self.jobs.get_mut(id) {
+                job.state.last_status = JobStatus::Success;
+                job.update_next_run(now_ms);
+            }
+        }
+
+        count
+    }
+
+    /// Merge disk state into the current store, preserving in-flight job states.
+    /// Jobs currently marked as Running in memory keep their in-memory state
+    /// to avoid losing completion updates from concurrent tasks.
+    pub fn merge_from_disk(&mut self, disk: CronStore) {
+        let running_ids: std::collections::HashSet<String> = self
+            .jobs
+            .values()
+            .filter(|j| j.state.last_status == JobStatus::Running)
+            .map(|j| j.id.clone())
+            .collect();
+
+        let disk_ids: std::collections::HashSet<String> = disk.jobs.keys().cloned().collect();
+
+        for (id, disk_job) in disk.jobs {
+            if running_ids.contains(&id) {
+                continue;
+            }
+            self.jobs.insert(id, disk_job);
+        }
+
+        self.jobs
+            .retain(|id, _| disk_ids.contains(id) || running_ids.contains(id));
+    }
+}
+
+/// When the whole scheduler was paused (`cica cron pause-all`), if it is.
+/// Paused, no job runs on its own, but each keeps its settings.
+pub fn paused_since() -> Result<Option<u64>> {
+    storage::read(|conn| {
+        let value: Option<String> = conn
+            .query_row(
+                "SELECT value FROM cron_settings WHERE name = 'paused_at'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value.and_then(|v| v.parse().ok()))
+    })
+}
+
+/// Counts changes to the jobs and the paused state, so schedulers notice
+/// what other processes (the CLI) saved. Cheap enough to poll.
+pub(crate) fn version() -> Result<u64> {
+    storage::read(|conn| {
+        let value: Option<String> = conn
+            .query_row(
+                "SELECT value FROM cron_settings WHERE name = 'version'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value.and_then(|v| v.parse().ok()).unwrap_or(0))
+    })
+}
+
+fn bump_version(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "INSERT INTO cron_settings (name, value) VALUES ('version', '1')
+         ON CONFLICT(name) DO UPDATE SET value = CAST(value AS INTEGER) + 1",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Pause the whole scheduler. False if it was paused already.
+pub fn pause_all(now_ms: u64) -> Result<bool> {
+    let paused = storage::write(|conn| {
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO cron_settings (name, value) VALUES ('paused_at', ?1)",
+            params![now_ms.to_string()],
+        )?;
+        if inserted > 0 {
+            bump_version(conn)?;
+        }
+        Ok(inserted > 0)
+    })?;
+    changed().notify_one();
+    Ok(paused)
+}
+
+/// Resume the scheduler, moving runs missed while it was paused to their
+/// next time so they don't all start at once. Returns how many moved, or
+/// None if it wasn't paused.
+pub fn resume_all(now_ms: u64) -> Result<Option<usize>> {
+    let resumed = storage::write(|conn| {
+        let deleted = conn.execute("DELETE FROM cron_settings WHERE name = 'paused_at'", [])?;
+        if deleted > 0 {
+            bump_version(conn)?;
+        }
+        Ok(deleted > 0)
+    })?;
+    if !resumed {
+        return Ok(None);
+    }
+    let moved = CronStore::load()?.update(|store| Ok(store.reschedule_missed(now_ms)))?;
+    Ok(Some(moved))
+}
+
+/// Notified whenever jobs are saved through [`CronStore::update`].
+pub(crate) fn changed() -> &'static Notify {
+    static CHANGED: OnceLock<Notify> = OnceLock::new();
+    CHANGED.get_or_init(Notify::new)
+}
+
+/// Generate a unique job ID.
+fn generate_job_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// Get current time in milliseconds.
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_creation() {
+        let job = CronJob::new(
+            "Test Job".to_string(),
+            "Test prompt".to_string(),
+            CronSchedule::Every(60_000),
+            "telegram".to_string(),
+            "12345".to_string(),
+        );
+
+        assert!(!job.id.is_empty());
+        assert_eq!(job.name, "Test Job");
+        assert_eq!(job.channel, "telegram");
+        assert!(job.enabled);
+        assert!(job.notify);
+        assert!(job.state.next_run_at.is_some());
+    }
+
+    #[test]
+    fn test_job_due_check() {
+        let mut job = CronJob::new(
+            "Test".to_string(),
+            "Test".to_string(),
+            CronSchedule::Every(60_000),
+            "test".to_string(),
+            "user1".to_string(),
+        );
+
+        // Set next_run to 1000
+        job.state.next_run_at = Some(1000);
+
+        assert!(!job.is_due(500)); // Before
+        assert!(job.is_due(1000)); // Exact
+        assert!(job.is_due(1500)); // After
+
+        // Disabled job should never be due
+        job.enabled = false;
+        assert!(!job.is_due(1500));
+    }
+
+    #[test]
+    fn test_edit_keeps_history() {
+        let mut job = CronJob::new(
+            "Test".to_string(),
+            "Test".to_string(),
+            CronSchedule::Every(60_000),
+            "telegram".to_string(),
+            "12345".to_string(),
+        );
+        job.state.last_status = JobStatus::Success;
+        let id = job.id.clone();
+
+        job.apply(JobEdit::Schedule(CronSchedule::Every(3_600_000)), 1000);
+        assert_eq!(job.state.next_run_at, Some(3_601_000));
+        assert_eq!(job.state.last_status, JobStatus::Success);
+
+        job.apply(JobEdit::Prompt("New prompt".to_string()), 2000);
+        job.apply(JobEdit::Name("renamed".to_string()), 2000);
+        assert_eq!(job.prompt, "New prompt");
+        assert_eq!(job.name, "renamed");
+        assert_eq!(job.id, id);
+
+        // A paused job stays unscheduled until resumed
+        job.enabled = false;
+        job.state.next_run_at = None;
+        job.apply(JobEdit::Schedule(CronSchedule::Every(60_000)), 3000);
+        assert_eq!(job.state.next_run_at, None);
+    }
+
+    #[test]
+    fn test_run_history_is_capped() {
+        let mut job = CronJob::new(
+            "Test".to_string(),
+            "Test".to_string(),
+            CronSchedule::Every(60_000),
+            "telegram".to_string(),
+            "12345".to_string(),
+        );
+
+        for i in 0..MAX_RUN_HISTORY as u64 + 2 {
+            job.record_run(JobRun {
+                started_at: i,
+                duration_ms: 100,
+                status: JobStatus::Success,
+                output: "é".repeat(600),
+            });
+        }
+
+        assert_eq!(job.state.runs.len(), MAX_RUN_HISTORY);
+        assert_eq!(job.state.runs[0].started_at, 2);
+        assert_eq!(job.state.runs[0].output.chars().count(), 503);
+    }
+
+    #[test]
+    fn test_retries_then_pause() {
+        let mut job = CronJob::new(
+            "Test".to_string(),
+            "Test".to_string(),
+            CronSchedule::Every(3_600_000),
+            "telegram".to_string(),
+            "12345".to_string(),
+        );
+        job.retry = RetryPolicy {
+            max_retries: 2,
+            backoff_ms: 1000,
+            pause_after: 4,
+        };
+
+        job.start_run(0);
+        assert_eq!(job.after_run(false, 0), RunOutcome::Retrying(1000));
+        job.start_run(1000);
+        assert_eq!(job.after_run(false, 1000), RunOutcome::Retrying(2000));
+        assert_eq!(job.state.next_run_at, Some(3000));
+        // Out of retries: back to the schedule
+        job.start_run(3000);
+        assert_eq!(job.after_run(false, 3000), RunOutcome::Scheduled);
+        assert_eq!(job.state.next_run_at, Some(3_603_000));
+        job.start_run(3_603_000);
+        assert_eq!(job.after_run(false, 3_603_000), RunOutcome::Paused);
+        assert!(!job.enabled);
+        assert_eq!(job.state.next_run_at, None);
+
+        job.enabled = true;
+        job.state.failure_count = 0;
+        assert_eq!(job.after_run(true, 0), RunOutcome::Scheduled);
+        assert_eq!(job.state.retry_attempt, 0);
+    }
+
+    #[test]
+    fn test_trigger_during_run_is_kept() {
+        let mut job = CronJob::new(
+            "Test".to_string(),
+            "Test".to_string(),
+            CronSchedule::Every(60_000),
+            "telegram".to_string(),
+            "12345".to_string(),
+        );
+
+        job.start_run(0);
+        assert_eq!(job.state.last_status, JobStatus::Running);
+        assert_eq!(job.state.next_run_at, Some(60_000));
+
+        // A run longer than the interval leaves the next trigger due
+        assert_eq!(job.after_run(true, 90_000), RunOutcome::Scheduled);
+        assert!(job.is_due(90_000));
+    }
+
+    #[test]
+    fn test_should_notify() {
+        let mut job = CronJob::new(
+            "Test".to_string(),
+            "Check RSS".to_string(),
+            CronSchedule::Every(3_600_000),
+            "telegram".to_string(),
+            "12345".to_string(),
+        );
+        assert!(job.should_notify(true, false, "Same as before"));
+
+        job.apply(JobEdit::Notify(Some(NotifyWhen::OnChange)), 0);
+        assert!(!job.should_notify(true, false, "Same as before"));
+        assert!(job.should_notify(true, true, "New post"));
+        assert!(job.should_notify(false, false, ""));
+
+        job.apply(JobEdit::Notify(Some(NotifyWhen::Noteworthy)), 0);
+        assert!(!job.should_notify(true, true, " NOTHING_NEW\n"));
+        assert!(job.should_notify(true, true, "New post"));
+
+        job.apply(JobEdit::Notify(None), 0);
+        assert!(!job.should_notify(false, true, ""));
+        assert_eq!(output_hash("a\n"), output_hash("a"));
+    }
+
+    #[test]
+    fn test_due_jobs_by_priority() {
+        let mut store = CronStore::default();
+        for (name, priority, next_run_at) in [
+            ("scraper", Priority::Low, 0),
+            ("digest", Priority::Normal, 2_000),
+            ("briefing", Priority::High, 5_000),
+            ("backup", Priority::Normal, 1_000),
+            ("later", Priority::High, 60_000),
+        ] {
+            let mut job = CronJob::new(
+                name.to_string(),
+                "Test".to_string(),
+                CronSchedule::Every(60_000),
+                "telegram".to_string(),
+                "12345".to_string(),
+            );
+            job.priority = priority;
+            job.state.next_run_at = Some(next_run_at);
+            store.jobs.insert(job.id.clone(), job);
+        }
+
+        let names: Vec<&str> = store
+            .get_due_jobs(10_000)
+            .iter()
+            .map(|j| j.name.as_str())
+            .collect();
+        assert_eq!(names, ["briefing", "backup", "digest", "scraper"]);
+    }
+
+    #[test]
+    fn test_fresh_session_forgets_id() {
+        let mut job = CronJob::new(
+            "journal".to_string(),
+            "Write today's journal entry".to_string(),
+            CronSchedule::Every(86_400_000),
+            "telegram".to_string(),
+            "12345".to_string(),
+        );
+
+        job.apply(JobEdit::Session(SessionMode::Continue), 0);
+        job.state.session_id = Some("abc".to_string());
+        job.apply(JobEdit::Session(SessionMode::Continue), 0);
+        assert_eq!(job.state.session_id.as_deref(), Some("abc"));
+
+        job.apply(JobEdit::Session(SessionMode::Fresh), 0);
+        assert_eq!(job.state.session_id, None);
+    }
+
+    #[test]
+    fn test_trigger_on_message() {
+        let mut store = CronStore::default();
+        let job = CronJob::new(
+            "receipts".to_string(),
+            "Log the expense".to_string(),
+            CronSchedule::OnMessage("receipt".to_string()),
+            "telegram".to_string(),
+            "12345".to_string(),
+        );
+        assert!(!job.is_due(u64::MAX));
+        let id = job.id.clone();
+        store.jobs.insert(id.clone(), job);
+
+        assert!(
+            store
+                .trigger_on_message("telegram", "12345", "hello", 0)
+                .is_empty()
+        );
+        assert!(
+            store
+                .trigger_on_message("telegram", "other", "my receipt", 0)
+                .is_empty()
+        );
+        assert_eq!(
+            store.trigger_on_message("telegram", "12345", "My receipt", 5_000),
+            ["receipts"]
+        );
+
+        let job = &store.jobs[&id];
+        assert!(job.is_due(5_000));
+        assert_eq!(job.state.trigger_messages, ["My receipt"]);
+        assert!(store.has_message_trigger("telegram", "12345", "another receipt"));
+        assert!(!store.has_message_trigger("telegram", "12345", "hello"));
+
+        // Messages before the run starts are queued for it
+        store.trigger_on_message("telegram", "12345", "receipt 2", 5_500);
+        assert_eq!(
+            store.jobs[&id].state.trigger_messages,
+            ["My receipt", "receipt 2"]
+        );
+        for i in 0..MAX_TRIGGER_MESSAGES {
+            store.trigger_on_message("telegram", "12345", &format!("receipt {}", i), 5_500);
+        }
+        let messages = &store.jobs[&id].state.trigger_messages;
+        assert_eq!(messages.len(), MAX_TRIGGER_MESSAGES);
+        assert_eq!(messages[0], "receipt 0");
+
+        let job = store.jobs.get_mut(&id).unwrap();
+        job.start_run(5_000);
+        assert!(job.state.trigger_messages.is_empty());
+        assert_eq!(job.after_run(true, 6_000), RunOutcome::Scheduled);
+        assert!(!job.is_due(u64::MAX));
+
+        // Jobs saved with a single trigger message still load
+        let mut value = serde_json::to_value(&*job).unwrap();
+        value["state"]
+            .as_object_mut()
+            .unwrap()
+            .remove("trigger_messages");
+        value["state"]["trigger_message"] = "old receipt".into();
+        let job: CronJob = serde_json::from_value(value).unwrap();
+        assert_eq!(job.state.trigger_messages, ["old receipt"]);
+    }
+
+    #[test]
+    fn test_calendar_schedule() {
+        let event = |start_at: u64, summary: &str| CalendarEvent {
+            start_at,
+            summary: summary.to_string(),
+            location: None,
+            description: None,
+        };
+        let mut store = CronStore::default();
+        let job = CronJob::new(
+            "prep".to_string(),
+            "Prepare notes".to_string(),
+            CronSchedule::Ics("https://example.com/work.ics".to_string(), -600_000),
+            "telegram".to_string(),
+            "12345".to_string(),
+        );
+        let id = job.id.clone();
+        store.jobs.insert(id.clone(), job);
+
+        // Nothing to run until the feed is fetched, once per refresh
+        assert_eq!(store.jobs[&id].state.next_run_at, None);
+        assert_eq!(store.claim_calendar_refreshes(0).len(), 1);
+        assert!(store.claim_calendar_refreshes(60_000).is_empty());
+        assert_eq!(store.claim_calendar_refreshes(CALENDAR_REFRESH_MS).len(), 1);
+
+        let job = store.get_mut(&id).unwrap();
+        job.set_calendar(
+            vec![event(7_200_000, "Planning"), event(3_600_000, "Standup")],
+            1_000,
+        );
+        assert_eq!(job.state.next_run_at, Some(3_000_000));
+        assert_eq!(
+            job.state.calendar_event.as_ref().unwrap().summary,
+            "Standup"
+        );
+
+        // A run moves on to the next event
+        job.start_run(3_000_000);
+        assert_eq!(job.state.next_run_at, Some(6_600_000));
+        assert_eq!(
+            job.state.calendar_event.as_ref().unwrap().summary,
+            "Planning"
+        );
+
+        // A run that's already due isn't moved by a refresh
+        job.set_calendar(vec![event(9_000_000, "Retro")], 6_700_000);
+        assert_eq!(job.state.next_run_at, Some(6_600_000));
+    }
+
+    #[test]
+    fn test_reschedule_missed() {
+        let mut store = CronStore::default();
+        for (name, schedule) in [
+            ("hourly", CronSchedule::Every(3_600_000)),
+            ("once", CronSchedule::At(5_000)),
+        ] {
+            let mut job = CronJob::new(
+                name.to_string(),
+                "Test".to_string(),
+                schedule,
+                "telegram".to_string(),
+                "12345".to_string(),
+            );
+            job.state.next_run_at = Some(5_000);
+            store.jobs.insert(job.id.clone(), job);
+        }
+
+        assert_eq!(store.reschedule_missed(10_000), 1);
+        let next = |name: &str| {
+            store
+                .find_by_name("telegram", "12345", name)
+                .and_then(|j| j.state.next_run_at)
+        };
+        assert_eq!(next("hourly"), Some(3_610_000));
+        assert_eq!(next("once"), Some(5_000));
+    }
+
+    #[test]
+    fn test_next_run_after() {
+        let mut store = CronStore::default();
+        for (name, next_run_at, enabled) in [
+            ("due", Some(1_000), true),
+            ("soon", Some(30_000), true),
+            ("later", Some(90_000), true),
+            ("paused", Some(20_000), false),
+            ("done", None, true),
+        ] {
+            let mut job = CronJob::new(
+                name.to_string(),
+                "Test".to_string(),
+                CronSchedule::Every(60_000),
+                "telegram".to_string(),
+                "12345".to_string(),
+            );
+            job.enabled = enabled;
+            job.state.next_run_at = next_run_at;
+            store.jobs.insert(job.id.clone(), job);
+        }
+
+        // Jobs already due are waiting on something else, not the clock
+        assert_eq!(store.next_run_after(10_000), Some(30_000));
+        assert_eq!(store.next_run_after(90_000), None);
+    }
+
+    #[test]
+    fn test_duplicate() {
+        let mut job = CronJob::new(
+            "morning-brief".to_string(),
+            "Summarize my day".to_string(),
+            CronSchedule::Every(60_000),
+            "telegram".to_string(),
+            "12345".to_string(),
+        );
+        job.priority = Priority::High;
+        job.enabled = false;
+        job.state.failure_count = 3;
+
+        let copy = job.duplicate("brief".to_string(), "slack", "U1");
+        assert_ne!(copy.id, job.id);
+        assert_eq!(copy.user_key(), "slack:U1");
+        assert_eq!(copy.prompt, job.prompt);
+        assert_eq!(copy.priority, Priority::High);
+        assert!(copy.enabled);
+        assert_eq!(copy.state.failure_count, 0);
+        assert!(copy.state.next_run_at.is_some());
+    }
+
+    #[test]
+    fn test_user_key() {
+        let job = CronJob::new(
+            "Test".to_string(),
+            "Test".to_string(),
+            CronSchedule::Every(60_000),
+            "telegram".to_string(),
+            "12345".to_string(),
+        );
+
+        assert_eq!(job.user_key(), "telegram:12345");
+    }
+
+    #[test]
+    fn test_names_are_unique_per_user() {
+        let mut store = CronStore::default();
+        let job = CronJob::new(
+            "morning-brief".to_string(),
+            "Summarize my day".to_string(),
+            CronSchedule::Every(60_000),
+            "telegram".to_string(),
+            "12345".to_string(),
+        );
+        let id = job.id.clone();
+        store.jobs.insert(id.clone(), job);
+
+        let found = store.find_by_name("telegram", "12345", "Morning-Brief");
+        assert_eq!(found.map(|j| j.id.as_str()), Some(id.as_str()));
+        assert!(
+            store
+                .find_by_name("telegram", "other", "morning-brief")
+                .is_none()
+        );
+
+        assert_eq!(
+            store.unique_name("telegram", "12345", "morning-brief"),
+            "morning-brief (2)"
+        );
+        assert_eq!(
+            store.unique_name("telegram", "other", "morning-brief"),
+            "morning-brief"
+        );
+    }
+}
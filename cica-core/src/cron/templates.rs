@@ -0,0 +1,149 @@
+//! Named job templates, shared by all users, to set up similar jobs without
+//! retyping long prompts (`/cron template save`, `/cron add from`).
+
+use anyhow::{Result, bail};
+use rusqlite::{Connection, OptionalExtension, params};
+
+use super::store::{CronJob, CronJobState};
+use crate::storage;
+
+/// A job saved under a name.
+#[derive(Debug, Clone)]
+pub struct JobTemplate {
+    pub name: String,
+    /// Who saved it (channel:user_id); only they can replace or delete it
+    pub created_by: String,
+    pub job: CronJob,
+}
+
+/// Save `job` as template `name`, replacing one of the same name saved by
+/// the same user. Only the job's settings are saved, not its state (runs,
+/// session, messages that triggered it).
+pub fn save(name: &str, job: &CronJob, created_by: &str) -> Result<()> {
+    storage::write(|conn| save_in(conn, name, job, created_by))
+}
+
+/// The template called `name` (ignoring case)
+pub fn get(name: &str) -> Result<Option<JobTemplate>> {
+    storage::read(|conn| get_in(conn, name))
+}
+
+/// All templates, by name
+pub fn list() -> Result<Vec<JobTemplate>> {
+    storage::read(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT name, created_by, job FROM cron_templates ORDER BY name")?;
+        let rows = stmt.query_map([], row_to_template)?;
+        rows.map(|row| row?).collect()
+    })
+}
+
+/// Delete template `name`, returning whether there was one to delete
+pub fn delete(name: &str, deleted_by: &str) -> Result<bool> {
+    storage::write(|conn| {
+        let Some(template) = get_in(conn, name)? else {
+            return Ok(false);
+        };
+        if template.created_by != deleted_by {
+            bail!("Only whoever saved \"{}\" can delete it", template.name);
+        }
+        conn.execute(
+            "DELETE FROM cron_templates WHERE name = ?1",
+            [&template.name],
+        )?;
+        Ok(true)
+    })
+}
+
+fn save_in(conn: &Connection, name: &str, job: &CronJob, created_by: &str) -> Result<()> {
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        bail!("Template names can't be empty or contain spaces");
+    }
+    if let Some(existing) = get_in(conn, name)?
+        && existing.created_by != created_by
+    {
+        bail!("There's already a template named \"{}\"", existing.name);
+    }
+
+    conn.execute(
+        "DELETE FROM cron_templates WHERE name = ?1 COLLATE NOCASE",
+        [name],
+    )?;
+    conn.execute(
+        "INSERT INTO cron_templates (name, created_by, job) VALUES (?1, ?2, ?3)",
+        params![name, created_by, serde_json::to_string(&definition(job))?],
+    )?;
+    Ok(())
+}
+
+/// `job` without its state, which is private to whoever owns it
+fn definition(job: &CronJob) -> CronJob {
+    CronJob {
+        state: CronJobState::default(),
+        ..job.clone()
+    }
+}
+
+fn get_in(conn: &Connection, name: &str) -> Result<Option<JobTemplate>> {
+    conn.query_row(
+        "SELECT name, created_by, job FROM cron_templates WHERE name = ?1 COLLATE NOCASE",
+        [name],
+        row_to_template,
+    )
+    .optional()?
+    .transpose()
+}
+
+fn row_to_template(row: &rusqlite::Row) -> rusqlite::Result<Result<JobTemplate>> {
+    let name: String = row.get(0)?;
+    let created_by: String = row.get(1)?;
+    let job: String = row.get(2)?;
+    // Templates saved before states were left out still have one
+    Ok(serde_json::from_str(&job)
+        .map(|job| JobTemplate {
+            name,
+            created_by,
+            job: definition(&job),
+        })
+        .map_err(Into::into))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cron::CronSchedule;
+
+    #[test]
+    fn test_save_and_get() {
+        let conn = Connection::open_in_memory().unwrap();
+        storage::init_schema(&conn).unwrap();
+        let job = CronJob::new(
+            "morning-brief".to_string(),
+            "Summarize my calendar, the weather and the news".to_string(),
+            CronSchedule::Cron("0 7 * * *".to_string()),
+            "telegram".to_string(),
+            "1".to_string(),
+        );
+
+        let mut job = job;
+        job.state.session_id = Some("private-session".to_string());
+        job.state.trigger_messages = vec!["my bank PIN is 1234".to_string()];
+        job.state.last_run_at = Some(1);
+
+        save_in(&conn, "briefing", &job, "telegram:1").unwrap();
+        let template = get_in(&conn, "Briefing").unwrap().unwrap();
+        assert_eq!(template.name, "briefing");
+        assert_eq!(template.job.prompt, job.prompt);
+        let saved: String = conn
+            .query_row("SELECT job FROM cron_templates", [], |row| row.get(0))
+            .unwrap();
+        assert!(!saved.contains("private-session") && !saved.contains("PIN"));
+        assert_eq!(template.job.state.last_run_at, None);
+
+        // The same user can replace it, others can't
+        save_in(&conn, "BRIEFING", &job, "telegram:1").unwrap();
+        assert!(save_in(&conn, "briefing", &job, "telegram:2").is_err());
+        assert!(save_in(&conn, "two words", &job, "telegram:1").is_err());
+        assert!(get_in(&conn, "missing").unwrap().is_none());
+    }
+}
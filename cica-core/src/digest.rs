@@ -0,0 +1,219 @@
+//! Daily digest (/digest): at a time each user picks, one message covering
+//! the last day's cron results, messages still waiting for an answer and new
+//! memories, written by the AI backend.
+
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveTime};
+
+use crate::backends::{self, QueryOptions};
+use crate::channels::{format_transcript, skip_permissions};
+use crate::cron::{CronJob, CronStore, JobStatus, format_timestamp};
+use crate::memory;
+use crate::pairing::PairingStore;
+use crate::storage;
+use crate::transcript::{self, Speaker, TranscriptMessage};
+
+/// How far back a digest looks
+const DIGEST_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Most transcript characters in the prompt
+const DIGEST_TRANSCRIPT_CHARS: usize = 20_000;
+
+/// Most characters of each new memory in the prompt
+const MEMORY_EXCERPT_CHARS: usize = 500;
+
+/// Whether a digest for `at` is due at `now`: today's time has come and
+/// the last one went out before it
+pub fn is_due(at: NaiveTime, last_sent: Option<u64>, now: DateTime<Local>) -> bool {
+    let Some(scheduled) = now
+        .date_naive()
+        .and_time(at)
+        .and_local_timezone(Local)
+        .earliest()
+    else {
+        return false;
+    };
+    now >= scheduled && last_sent.is_none_or(|sent| (sent as i64) < scheduled.timestamp())
+}
+
+/// Approved users whose daily digest is due, as (channel, user_id)
+pub fn due_users(store: &PairingStore) -> Vec<(String, String)> {
+    let now = Local::now();
+    let mut users: Vec<_> = store
+        .user_profiles
+        .iter()
+        .filter_map(|(key, profile)| {
+            let at = profile.daily_digest?;
+            let (channel, user_id) = storage::split_user_key(key);
+            (store.is_approved(channel, user_id) && is_due(at, profile.last_digest, now))
+                .then(|| (channel.to_string(), user_id.to_string()))
+        })
+        .collect();
+    users.sort();
+    users
+}
+
+/// Write a user's digest of the last day, or `None` if nothing happened
+pub async fn build(channel: &str, user_id: &str) -> Result<Option<String>> {
+    let since = SystemTime::now() - DIGEST_PERIOD;
+    let since_secs = since
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let messages = transcript::since(channel, user_id, since_secs)?;
+    let cron_results = cron_results(
+        &CronStore::load()?.list_for_user(channel, user_id),
+        since_secs * 1000,
+    );
+    let memories_dir = memory::memories_dir(channel, user_id)?;
+    let memories: Vec<String> = memory::recent_user_memories(channel, user_id, since)?
+        .iter()
+        .filter_map(|path| memory_excerpt(&memories_dir, path))
+        .collect();
+
+    if messages.is_empty() && cron_results.is_empty() && memories.is_empty() {
+        return Ok(None);
+    }
+
+    let (summary, _session_id) = backends::query_with_options(
+        &build_prompt(&messages, &cron_results, &memories),
+        QueryOptions {
+            skip_permissions: skip_permissions(channel),
+            read_only: true,
+            one_shot: true,
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    Ok(Some(format!("[Daily digest]\n\n{}", summary)))
+}
+
+fn build_prompt(
+    messages: &[TranscriptMessage],
+    cron_results: &[String],
+    memories: &[String],
+) -> String {
+    let mut prompt = "Write my daily digest of the last 24 hours. Briefly summarize what my \
+                      scheduled jobs reported, list anything I asked that's still waiting for \
+                      an answer or a follow-up, and mention new things you remembered. Use a \
+                      short section with bullet points for each, skip empty ones, and no \
+                      preamble.\n"
+        .to_string();
+
+    if !cron_results.is_empty() {
+        prompt.push_str(&format!(
+            "\nScheduled job runs:\n{}\n",
+            cron_results.join("\n")
+        ));
+    }
+    let unanswered = unanswered(messages);
+    if !unanswered.is_empty() {
+        prompt.push_str(&format!(
+            "\nMy last messages, which you haven't replied to:\n{}\n",
+            format_transcript(unanswered, DIGEST_TRANSCRIPT_CHARS)
+        ));
+    }
+    if !memories.is_empty() {
+        prompt.push_str(&format!(
+            "\nNew or updated memories:\n{}\n",
+            memories.join("\n\n")
+        ));
+    }
+    let transcript = format_transcript(messages, DIGEST_TRANSCRIPT_CHARS);
+    if !transcript.is_empty() {
+        prompt.push_str(&format!("\nOur conversations:\n{}\n", transcript));
+    }
+
+    prompt
+}
+
+/// Messages after the last reply, which got none
+fn unanswered(messages: &[TranscriptMessage]) -> &[TranscriptMessage] {
+    let first = messages
+        .iter()
+        .rposition(|m| m.speaker == Speaker::Assistant)
+        .map_or(0, |i| i + 1);
+    &messages[first..]
+}
+
+/// One line per job run started since `since_ms`, oldest first
+fn cron_results(jobs: &[&CronJob], since_ms: u64) -> Vec<String> {
+    let mut runs: Vec<_> = jobs
+        .iter()
+        .flat_map(|job| job.state.runs.iter().map(move |run| (job, run)))
+        .filter(|(_, run)| run.started_at >= since_ms)
+        .collect();
+    runs.sort_by_key(|(_, run)| run.started_at);
+    runs.into_iter()
+        .map(|(job, run)| {
+            let status = match &run.status {
+                JobStatus::Failed(_) => "failed",
+                _ => "ok",
+            };
+            format!(
+                "- {} at {} ({}): {}",
+                job.name,
+                format_timestamp(run.started_at),
+                status,
+                run.output
+            )
+        })
+        .collect()
+}
+
+/// A memory file's path under `dir` and the start of its content
+fn memory_excerpt(dir: &Path, path: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let name = path.strip_prefix(dir).unwrap_or(path).display();
+    let excerpt: String = content.chars().take(MEMORY_EXCERPT_CHARS).collect();
+    Some(format!("{}:\n{}", name, excerpt.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn message(speaker: Speaker, text: &str) -> TranscriptMessage {
+        TranscriptMessage {
+            channel: "telegram".to_string(),
+            user_id: "1".to_string(),
+            speaker,
+            text: text.to_string(),
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_is_due() {
+        let at = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+        let now = Local.with_ymd_and_hms(2026, 3, 10, 9, 0, 0).unwrap();
+        let earlier_today = Local.with_ymd_and_hms(2026, 3, 10, 7, 0, 0).unwrap();
+        let yesterday = Local.with_ymd_and_hms(2026, 3, 9, 8, 0, 0).unwrap();
+
+        assert!(is_due(at, Some(yesterday.timestamp() as u64), now));
+        assert!(is_due(at, Some(earlier_today.timestamp() as u64), now));
+        assert!(is_due(at, None, now));
+        assert!(!is_due(at, Some(now.timestamp() as u64), now));
+        assert!(!is_due(at, None, earlier_today));
+    }
+
+    #[test]
+    fn test_unanswered() {
+        let messages = vec![
+            message(Speaker::User, "hi"),
+            message(Speaker::Assistant, "hello"),
+            message(Speaker::User, "can you check my flight?"),
+            message(Speaker::User, "the one on friday"),
+        ];
+        let texts: Vec<_> = unanswered(&messages).iter().map(|m| &m.text).collect();
+        assert_eq!(texts, ["can you check my flight?", "the one on friday"]);
+
+        assert!(unanswered(&messages[..2]).is_empty());
+    }
+}
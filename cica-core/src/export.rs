@@ -0,0 +1,332 @@
+//! Conversation and memory exports (/export), sent to the user as a file.
+//!
+//! With the `pdf` feature the document is typeset with typst, so it can be
+//! shared with people who don't read Markdown; without it, it's Markdown.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use chrono::{Local, TimeZone};
+
+use crate::config;
+use crate::memory;
+use crate::transcript::{self, Speaker};
+
+/// Most messages a conversation export includes, newest kept
+const MAX_MESSAGES: usize = 2000;
+
+/// What to export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportKind {
+    /// The conversation transcript
+    Conversation,
+    /// The user's memory files
+    Memories,
+}
+
+impl ExportKind {
+    fn title(self) -> &'static str {
+        match self {
+            ExportKind::Conversation => "Conversation",
+            ExportKind::Memories => "Memories",
+        }
+    }
+
+    fn file_stem(self) -> &'static str {
+        match self {
+            ExportKind::Conversation => "conversation",
+            ExportKind::Memories => "memories",
+        }
+    }
+}
+
+/// A titled block of text in an export: a message or a memory file
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    heading: String,
+    /// Shown next to the heading, e.g. when a message was sent
+    note: String,
+    text: String,
+}
+
+/// Write a user's conversation or memories to a file for sending. The caller
+/// removes it once it's sent.
+pub fn export(channel: &str, user_id: &str, kind: ExportKind) -> Result<PathBuf> {
+    let entries = match kind {
+        ExportKind::Conversation => conversation_entries(channel, user_id)?,
+        ExportKind::Memories => memory_entries(channel, user_id)?,
+    };
+    if entries.is_empty() {
+        match kind {
+            ExportKind::Conversation => bail!("There's no conversation to export yet."),
+            ExportKind::Memories => bail!("You don't have any memories to export yet."),
+        }
+    }
+
+    let title = format!("{} with Cica", kind.title());
+    let subtitle = format!("Exported {}", Local::now().format("%Y-%m-%d %H:%M"));
+
+    let dir = config::paths()?.internal_dir.join("exports");
+    std::fs::create_dir_all(&dir)?;
+    let stem = format!(
+        "{}-{}",
+        kind.file_stem(),
+        Local::now().format("%Y-%m-%d-%H%M%S")
+    );
+    let (path, bytes) = render(&dir, &stem, &title, &subtitle, &entries)?;
+    std::fs::write(&path, bytes)?;
+    Ok(path)
+}
+
+fn conversation_entries(channel: &str, user_id: &str) -> Result<Vec<Entry>> {
+    let messages = transcript::recent(channel, user_id, MAX_MESSAGES)?;
+    Ok(messages
+        .into_iter()
+        .map(|message| Entry {
+            heading: match message.speaker {
+                Speaker::User => "You",
+                Speaker::Assistant => "Cica",
+            }
+            .to_string(),
+            note: Local
+                .timestamp_opt(message.created_at as i64, 0)
+                .single()
+                .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
+            text: message.text,
+        })
+        .collect())
+}
+
+fn memory_entries(channel: &str, user_id: &str) -> Result<Vec<Entry>> {
+    let dir = memory::memories_dir(channel, user_id)?;
+    let mut entries = Vec::new();
+    for path in memory::user_memory_files(channel, user_id)? {
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        entries.push(Entry {
+            heading: path
+                .strip_prefix(&dir)
+                .unwrap_or(&path)
+                .with_extension("")
+                .display()
+                .to_string(),
+            note: String::new(),
+            text,
+        });
+    }
+    Ok(entries)
+}
+
+/// The export's file path and contents
+#[cfg(feature = "pdf")]
+fn render(
+    dir: &Path,
+    stem: &str,
+    title: &str,
+    subtitle: &str,
+    entries: &[Entry],
+) -> Result<(PathBuf, Vec<u8>)> {
+    let source = pdf::typst_source(title, subtitle, entries);
+    Ok((dir.join(format!("{}.pdf", stem)), pdf::compile(source)?))
+}
+
+/// The export's file path and contents
+#[cfg(not(feature = "pdf"))]
+fn render(
+    dir: &Path,
+    stem: &str,
+    title: &str,
+    subtitle: &str,
+    entries: &[Entry],
+) -> Result<(PathBuf, Vec<u8>)> {
+    let markdown = markdown(title, subtitle, entries);
+    Ok((dir.join(format!("{}.md", stem)), markdown.into_bytes()))
+}
+
+#[cfg_attr(feature = "pdf", allow(dead_code))]
+fn markdown(title: &str, subtitle: &str, entries: &[Entry]) -> String {
+    let mut out = format!("# {}\n\n_{}_\n", title, subtitle);
+    for entry in entries {
+        out.push_str(&format!("\n## {}", entry.heading));
+        if !entry.note.is_empty() {
+            out.push_str(&format!(" ({})", entry.note));
+        }
+        out.push_str(&format!("\n\n{}\n", entry.text.trim()));
+    }
+    out
+}
+
+#[cfg(feature = "pdf")]
+mod pdf {
+    use anyhow::{Result, anyhow};
+    use comemo::Prehashed;
+    use typst::diag::{FileError, FileResult};
+    use typst::eval::Tracer;
+    use typst::foundations::{Bytes, Datetime, Smart};
+    use typst::syntax::{FileId, Source, VirtualPath};
+    use typst::text::{Font, FontBook};
+    use typst::{Library, World};
+
+    use super::Entry;
+
+    /// Page setup and the layout of each entry
+    const PREAMBLE: &str = r#"#set page(paper: "a4", margin: 2cm, numbering: "1")
+#set text(font: "Linux Libertine", size: 11pt)
+#set par(justify: false)
+#let entry(heading, note, lines) = block(breakable: true, above: 1.2em)[
+  #text(weight: "bold", heading)
+  #h(0.6em)
+  #text(size: 9pt, fill: gray.darken(30%), note)
+  #linebreak()
+  #lines.join(linebreak())
+]
+"#;
+
+    /// The export as a typst document. All text goes in as string literals,
+    /// so nothing in a message is read as markup.
+    pub(super) fn typst_source(title: &str, subtitle: &str, entries: &[Entry]) -> String {
+        let mut source = PREAMBLE.to_string();
+        source.push_str(&format!(
+            "#text(size: 20pt, weight: \"bold\", {})\n#linebreak()\n#text(fill: gray, {})\n",
+            literal(title),
+            literal(subtitle)
+        ));
+        for entry in entries {
+            let lines: String = entry
+                .text
+                .trim()
+                .lines()
+                .map(|line| format!("{}, ", literal(line)))
+                .collect();
+            source.push_str(&format!(
+                "#entry({}, {}, ({}))\n",
+                literal(&entry.heading),
+                literal(&entry.note),
+                lines
+            ));
+        }
+        source
+    }
+
+    /// A typst string literal
+    fn literal(text: &str) -> String {
+        let mut out = String::from("\"");
+        for c in text.chars() {
+            match c {
+                '\\' => out.push_str("\\\\"),
+                '"' => out.push_str("\\\""),
+                '\t' => out.push_str("    "),
+                c if c.is_control() => {}
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+        out
+    }
+
+    /// Typeset a document with the bundled fonts
+    pub(super) fn compile(source: String) -> Result<Vec<u8>> {
+        let world = ExportWorld::new(source);
+        let mut tracer = Tracer::new();
+        let document = typst::compile(&world, &mut tracer).map_err(|errors| {
+            let messages: Vec<String> = errors.iter().map(|e| e.message.to_string()).collect();
+            anyhow!("Couldn't typeset the export: {}", messages.join("; "))
+        })?;
+        Ok(typst_pdf::pdf(&document, Smart::Auto, None))
+    }
+
+    /// A single in-memory source file with typst's bundled fonts
+    struct ExportWorld {
+        library: Prehashed<Library>,
+        book: Prehashed<FontBook>,
+        fonts: Vec<Font>,
+        source: Source,
+    }
+
+    impl ExportWorld {
+        fn new(text: String) -> Self {
+            let fonts: Vec<Font> = typst_assets::fonts()
+                .flat_map(|data| Font::iter(Bytes::from_static(data)))
+                .collect();
+            Self {
+                library: Prehashed::new(Library::default()),
+                book: Prehashed::new(FontBook::from_fonts(&fonts)),
+                fonts,
+                source: Source::new(FileId::new(None, VirtualPath::new("export.typ")), text),
+            }
+        }
+    }
+
+    impl World for ExportWorld {
+        fn library(&self) -> &Prehashed<Library> {
+            &self.library
+        }
+
+        fn book(&self) -> &Prehashed<FontBook> {
+            &self.book
+        }
+
+        fn main(&self) -> Source {
+            self.source.clone()
+        }
+
+        fn source(&self, id: FileId) -> FileResult<Source> {
+            if id == self.source.id() {
+                Ok(self.source.clone())
+            } else {
+                Err(FileError::NotFound(id.vpath().as_rootless_path().into()))
+            }
+        }
+
+        fn file(&self, id: FileId) -> FileResult<Bytes> {
+            Err(FileError::NotFound(id.vpath().as_rootless_path().into()))
+        }
+
+        fn font(&self, index: usize) -> Option<Font> {
+            self.fonts.get(index).cloned()
+        }
+
+        fn today(&self, _offset: Option<i64>) -> Option<Datetime> {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> Vec<Entry> {
+        vec![
+            Entry {
+                heading: "You".to_string(),
+                note: "2026-10-17 08:00".to_string(),
+                text: "What's *on* my #calendar? \"quotes\" \\ [brackets]".to_string(),
+            },
+            Entry {
+                heading: "Cica".to_string(),
+                note: String::new(),
+                text: "Two things:\n\n- Dentist at 10\n- Lunch with Ádám 🍜".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_markdown() {
+        let text = markdown("Conversation with Cica", "Exported today", &entries());
+        assert!(text.starts_with("# Conversation with Cica\n\n_Exported today_\n"));
+        assert!(text.contains("\n## You (2026-10-17 08:00)\n\nWhat's *on*"));
+        assert!(text.contains("\n## Cica\n\nTwo things:"));
+    }
+
+    #[cfg(feature = "pdf")]
+    #[test]
+    fn test_pdf() {
+        let source = pdf::typst_source("Conversation with Cica", "Exported today", &entries());
+        assert!(source.contains(r#""What's *on* my #calendar? \"quotes\" \\ [brackets]", "#));
+        let bytes = pdf::compile(source).unwrap();
+        assert!(bytes.starts_with(b"%PDF-"));
+    }
+}
@@ -0,0 +1,241 @@
+//! Key-value facts about each user (birthday, address, preferences).
+//!
+//! Unlike memories, facts aren't searched: all of a user's facts are in every
+//! context prompt. Claude changes them by including tags in its reply, which
+//! are applied and removed before the reply is sent:
+//! - `<fact key="birthday">1990-05-01</fact>` - set a fact
+//! - `<fact key="birthday" />` - forget a fact
+
+use std::time::SystemTime;
+
+use anyhow::Result;
+use rusqlite::{Connection, params};
+use tracing::{info, warn};
+
+use crate::storage;
+
+/// Longest fact key kept
+const MAX_KEY_CHARS: usize = 64;
+
+/// Longest fact value kept
+const MAX_VALUE_CHARS: usize = 500;
+
+/// A fact about a user
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fact {
+    pub key: String,
+    pub value: String,
+}
+
+/// A change to a user's facts requested in a reply
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FactChange {
+    Set { key: String, value: String },
+    Remove { key: String },
+}
+
+/// A user's facts, sorted by key
+pub fn list(channel: &str, user_id: &str) -> Result<Vec<Fact>> {
+    storage::read(|conn| query_facts(conn, channel, user_id))
+}
+
+/// Apply the fact tags in a reply and return the reply without them.
+/// Failures to save are logged, so the reply still goes out.
+pub fn apply_from_reply(channel: &str, user_id: &str, reply: &str) -> String {
+    let (cleaned, changes) = extract_changes(reply);
+    if changes.is_empty() {
+        return reply.to_string();
+    }
+
+    let result = storage::write(|conn| {
+        for change in &changes {
+            apply(conn, channel, user_id, change, now_timestamp())?;
+        }
+        Ok(())
+    });
+    match result {
+        Ok(()) => info!(
+            "Updated {} fact(s) for {}:{}",
+            changes.len(),
+            channel,
+            user_id
+        ),
+        Err(e) => warn!("Failed to save facts: {}", e),
+    }
+
+    cleaned
+}
+
+/// Split fact tags out of a reply
+fn extract_changes(reply: &str) -> (String, Vec<FactChange>) {
+    const OPEN: &str = "<fact key=\"";
+    const CLOSE: &str = "</fact>";
+
+    let mut cleaned = String::new();
+    let mut changes = Vec::new();
+    let mut rest = reply;
+
+    while let Some(start) = rest.find(OPEN) {
+        let after_key = &rest[start + OPEN.len()..];
+        let Some(key_end) = after_key.find('"') else {
+            break;
+        };
+        let key = normalize_key(&after_key[..key_end]);
+        let after_quote = after_key[key_end + 1..].trim_start();
+
+        let (change, consumed) = if let Some(tail) = after_quote.strip_prefix("/>") {
+            (FactChange::Remove { key }, tail)
+        } else if let Some(tail) = after_quote.strip_prefix('>')
+            && let Some(value_end) = tail.find(CLOSE)
+        {
+            let value: String = tail[..value_end]
+                .trim()
+                .chars()
+                .take(MAX_VALUE_CHARS)
+                .collect();
+            let change = if value.is_empty() {
+                FactChange::Remove { key }
+            } else {
+                FactChange::Set { key, value }
+            };
+            (change, &tail[value_end + CLOSE.len()..])
+        } else {
+            // Not a complete tag; leave it in the reply
+            cleaned.push_str(&rest[..start + OPEN.len()]);
+            rest = after_key;
+            continue;
+        };
+
+        cleaned.push_str(&rest[..start]);
+        let (FactChange::Set { key, .. } | FactChange::Remove { key }) = &change;
+        if !key.is_empty() {
+            changes.push(change);
+        }
+
+        // A tag on its own line takes the line with it
+        let line_start = cleaned.is_empty() || cleaned.ends_with('\n');
+        rest = match consumed.strip_prefix('\n') {
+            Some(next_line) if line_start => next_line,
+            _ => consumed,
+        };
+    }
+    cleaned.push_str(rest);
+
+    (cleaned.trim_end().to_string(), changes)
+}
+
+/// Lowercase a key and use underscores between words
+fn normalize_key(key: &str) -> String {
+    key.split_whitespace()
+        .collect::<Vec<_>>()
+        .join("_")
+        .to_lowercase()
+        .chars()
+        .take(MAX_KEY_CHARS)
+        .collect()
+}
+
+fn apply(
+    conn: &Connection,
+    channel: &str,
+    user_id: &str,
+    change: &FactChange,
+    now: u64,
+) -> Result<()> {
+    match change {
+        FactChange::Set { key, value } => {
+            conn.execute(
+                "INSERT OR REPLACE INTO facts (channel, user_id, key, value, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![channel, user_id, key, value, now as i64],
+            )?;
+        }
+        FactChange::Remove { key } => {
+            conn.execute(
+                "DELETE FROM facts WHERE channel = ?1 AND user_id = ?2 AND key = ?3",
+                params![channel, user_id, key],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn query_facts(conn: &Connection, channel: &str, user_id: &str) -> Result<Vec<Fact>> {
+    let mut stmt = conn
+        .prepare("SELECT key, value FROM facts WHERE channel = ?1 AND user_id = ?2 ORDER BY key")?;
+    let rows = stmt.query_map(params![channel, user_id], |row| {
+        Ok(Fact {
+            key: row.get(0)?,
+            value: row.get(1)?,
+        })
+    })?;
+
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_changes() {
+        let reply = "Happy early birthday!\n\
+                     <fact key=\"Birthday\">1990-05-01</fact>\n\
+                     <fact key=\"old address\" />\n\
+                     I'll remember that.";
+
+        let (cleaned, changes) = extract_changes(reply);
+        assert_eq!(cleaned, "Happy early birthday!\nI'll remember that.");
+        assert_eq!(
+            changes,
+            vec![
+                FactChange::Set {
+                    key: "birthday".to_string(),
+                    value: "1990-05-01".to_string()
+                },
+                FactChange::Remove {
+                    key: "old_address".to_string()
+                },
+            ]
+        );
+
+        let (cleaned, changes) = extract_changes("Use <fact key=\"x\"> tags");
+        assert_eq!(cleaned, "Use <fact key=\"x\"> tags");
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_apply_changes() {
+        let conn = Connection::open_in_memory().unwrap();
+        storage::init_schema(&conn).unwrap();
+        let set = |key: &str, value: &str| FactChange::Set {
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+
+        apply(&conn, "telegram", "1", &set("city", "Oslo"), 1).unwrap();
+        apply(&conn, "telegram", "1", &set("city", "Bergen"), 2).unwrap();
+        apply(&conn, "telegram", "1", &set("pet", "cat"), 2).unwrap();
+        apply(&conn, "telegram", "2", &set("city", "Paris"), 2).unwrap();
+        let remove = FactChange::Remove {
+            key: "pet".to_string(),
+        };
+        apply(&conn, "telegram", "1", &remove, 3).unwrap();
+
+        let facts = query_facts(&conn, "telegram", "1").unwrap();
+        assert_eq!(
+            facts,
+            vec![Fact {
+                key: "city".to_string(),
+                value: "Bergen".to_string()
+            }]
+        );
+    }
+}
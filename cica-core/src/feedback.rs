@@ -0,0 +1,211 @@
+//! Feedback on replies (👍/👎 reactions and messages, /good, /bad).
+//!
+//! Each rating is logged with the reply it's about: the user's last one.
+//! Corrections given with `/bad <what to change>` are kept in the user's
+//! `memories/feedback.md`, counting repeats, and the context prompt includes
+//! them so later replies follow them.
+
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use anyhow::Result;
+use rusqlite::params;
+use tracing::info;
+
+use crate::memory::memories_dir;
+use crate::storage;
+use crate::transcript;
+
+/// Most corrections kept; the oldest go first
+const MAX_CORRECTIONS: usize = 30;
+
+/// Most characters of the rated reply kept in the log
+const MAX_REPLY_CHARS: usize = 1000;
+
+/// Heading of the corrections file
+const CORRECTIONS_HEADER: &str = "# Corrections\n\nThings the user asked to be done differently, \
+                                  with how often they asked.\n\n";
+
+/// Whether a reply was good or bad
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rating {
+    Good,
+    Bad,
+}
+
+impl Rating {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Rating::Good => "good",
+            Rating::Bad => "bad",
+        }
+    }
+
+    /// The rating a reaction stands for: 👍/👎 in any skin tone, or Slack's
+    /// names for them
+    pub fn from_reaction(reaction: &str) -> Option<Self> {
+        let reaction = reaction.trim();
+        let name = reaction.split("::").next().unwrap_or(reaction);
+        if reaction.starts_with('👍') || matches!(name, "+1" | "thumbsup") {
+            Some(Rating::Good)
+        } else if reaction.starts_with('👎') || matches!(name, "-1" | "thumbsdown") {
+            Some(Rating::Bad)
+        } else {
+            None
+        }
+    }
+}
+
+/// Log a rating of the user's last reply. A note on a bad one is kept as a
+/// correction for later replies. Returns false when there's no reply to
+/// rate yet.
+pub fn record(channel: &str, user_id: &str, rating: Rating, note: Option<&str>) -> Result<bool> {
+    let Some(reply) = transcript::last_reply(channel, user_id)? else {
+        return Ok(false);
+    };
+    let reply: String = reply.chars().take(MAX_REPLY_CHARS).collect();
+
+    storage::write(|conn| {
+        conn.execute(
+            "INSERT INTO feedback (channel, user_id, rating, note, reply, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                channel,
+                user_id,
+                rating.as_str(),
+                note,
+                reply,
+                now_timestamp()
+            ],
+        )?;
+        Ok(())
+    })?;
+    info!("{}:{} rated a reply {}", channel, user_id, rating.as_str());
+
+    if rating == Rating::Bad
+        && let Some(correction) = note
+    {
+        add_correction(channel, user_id, correction)?;
+    }
+    Ok(true)
+}
+
+/// Where a user's corrections are kept
+pub fn corrections_path(channel: &str, user_id: &str) -> Result<PathBuf> {
+    Ok(memories_dir(channel, user_id)?.join("feedback.md"))
+}
+
+/// Context prompt section with the user's corrections, if they made any
+pub fn corrections_prompt(channel: &str, user_id: &str) -> Option<String> {
+    let path = corrections_path(channel, user_id).ok()?;
+    let corrections = parse_corrections(&std::fs::read_to_string(path).ok()?);
+    if corrections.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = corrections
+        .iter()
+        .map(|(text, _)| format!("- {}", text))
+        .collect();
+    Some(format!(
+        "## Corrections\nThe user gave this feedback on earlier replies (kept in memories/feedback.md). Follow it:\n{}",
+        lines.join("\n")
+    ))
+}
+
+fn add_correction(channel: &str, user_id: &str, correction: &str) -> Result<()> {
+    let path = corrections_path(channel, user_id)?;
+    let existing = std::fs::read_to_string(&path).unwrap_or_default();
+    let corrections = with_correction(parse_corrections(&existing), correction);
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    storage::write_atomic(&path, &format_corrections(&corrections))
+}
+
+/// Add a correction, or count it again if the user already made it
+fn with_correction(mut corrections: Vec<(String, u32)>, correction: &str) -> Vec<(String, u32)> {
+    let correction = correction.trim().replace('\n', " ");
+    match corrections
+        .iter_mut()
+        .find(|(text, _)| text.eq_ignore_ascii_case(&correction))
+    {
+        Some((_, count)) => *count += 1,
+        None => corrections.push((correction, 1)),
+    }
+    let excess = corrections.len().saturating_sub(MAX_CORRECTIONS);
+    corrections.drain(..excess);
+    corrections
+}
+
+/// Read "- text (3x)" lines back as (text, count)
+fn parse_corrections(content: &str) -> Vec<(String, u32)> {
+    content
+        .lines()
+        .filter_map(|line| line.strip_prefix("- "))
+        .map(|line| {
+            line.strip_suffix("x)")
+                .and_then(|rest| rest.rsplit_once(" ("))
+                .and_then(|(text, count)| Some((text.to_string(), count.parse().ok()?)))
+                .unwrap_or_else(|| (line.to_string(), 1))
+        })
+        .collect()
+}
+
+fn format_corrections(corrections: &[(String, u32)]) -> String {
+    let mut content = CORRECTIONS_HEADER.to_string();
+    for (text, count) in corrections {
+        content.push_str(&format!("- {} ({}x)\n", text, count));
+    }
+    content
+}
+
+fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rating_from_reaction() {
+        assert_eq!(Rating::from_reaction("👍"), Some(Rating::Good));
+        assert_eq!(Rating::from_reaction("👍🏽"), Some(Rating::Good));
+        assert_eq!(Rating::from_reaction("+1::skin-tone-3"), Some(Rating::Good));
+        assert_eq!(Rating::from_reaction("👎"), Some(Rating::Bad));
+        assert_eq!(Rating::from_reaction("thumbsdown"), Some(Rating::Bad));
+        assert_eq!(Rating::from_reaction("❤️"), None);
+        assert_eq!(Rating::from_reaction("heart"), None);
+    }
+
+    #[test]
+    fn test_corrections_roundtrip() {
+        let corrections = with_correction(Vec::new(), "Use metric units");
+        let corrections = with_correction(corrections, "Keep answers short");
+        let corrections = with_correction(corrections, "use metric units\n");
+
+        let content = format_corrections(&corrections);
+        assert!(content.starts_with("# Corrections"));
+        assert!(content.contains("- Use metric units (2x)\n- Keep answers short (1x)\n"));
+        assert_eq!(parse_corrections(&content), corrections);
+
+        // Edited by hand, without counts
+        assert_eq!(
+            parse_corrections("- No emoji (from chat)"),
+            [("No emoji (from chat)".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_corrections_are_capped() {
+        let corrections = (0..MAX_CORRECTIONS + 2).fold(Vec::new(), |acc, i| {
+            with_correction(acc, &format!("correction {}", i))
+        });
+        assert_eq!(corrections.len(), MAX_CORRECTIONS);
+        assert_eq!(corrections[0].0, "correction 2");
+    }
+}
@@ -0,0 +1,731 @@
+//! Cica's own messages in each user's language.
+//!
+//! Replies from the AI follow the user's language through the context prompt;
+//! the fixed strings Cica sends itself (pairing prompts, command help, cron
+//! errors) are looked up in a small message catalog instead. Catalog entries
+//! are keyed by the English text, so a message without a translation is just
+//! sent in English. Placeholders are `{}`, filled in order by [`tr_args`].
+//!
+//! A user's language is set with `/language <code>`, or detected from their
+//! first messages (see [`detect`]).
+
+use std::fmt::Display;
+
+use tracing::warn;
+
+use crate::pairing::PairingStore;
+
+/// Languages `/language` accepts, as (ISO 639-1 code, English name)
+pub const LANGUAGES: &[(&str, &str)] = &[
+    ("ar", "Arabic"),
+    ("cs", "Czech"),
+    ("da", "Danish"),
+    ("de", "German"),
+    ("el", "Greek"),
+    ("en", "English"),
+    ("es", "Spanish"),
+    ("fi", "Finnish"),
+    ("fr", "French"),
+    ("he", "Hebrew"),
+    ("hu", "Hungarian"),
+    ("it", "Italian"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("nl", "Dutch"),
+    ("pl", "Polish"),
+    ("pt", "Portuguese"),
+    ("ro", "Romanian"),
+    ("ru", "Russian"),
+    ("sv", "Swedish"),
+    ("tr", "Turkish"),
+    ("uk", "Ukrainian"),
+    ("zh", "Chinese"),
+];
+
+/// The code for a language given as a code or an English name, e.g. "HU" or
+/// "hungarian" -> "hu"
+pub fn normalize(language: &str) -> Option<&'static str> {
+    let language = language.trim();
+    LANGUAGES
+        .iter()
+        .find(|(code, name)| {
+            code.eq_ignore_ascii_case(language) || name.eq_ignore_ascii_case(language)
+        })
+        .map(|(code, _)| *code)
+}
+
+/// English name of a language code, e.g. "hu" -> "Hungarian"
+pub fn name(code: &str) -> Option<&'static str> {
+    LANGUAGES
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, name)| *name)
+}
+
+/// A user's language, if set or detected
+pub fn user_language(channel: &str, user_id: &str) -> Option<String> {
+    match PairingStore::load() {
+        Ok(store) => store.language(channel, user_id).map(str::to_string),
+        Err(e) => {
+            warn!("Failed to load pairing store: {}", e);
+            None
+        }
+    }
+}
+
+/// Context prompt section asking the AI to answer in the user's language
+pub fn language_prompt(channel: &str, user_id: &str) -> Option<String> {
+    let code = user_language(channel, user_id)?;
+    let name = name(&code)?;
+    Some(format!(
+        "## Language\nThe user's language is {}. Reply in {} unless they write in another language or ask otherwise.",
+        name, name
+    ))
+}
+
+/// Languages with a catalog
+pub const TRANSLATED: &[&str] = &["de", "hu"];
+
+/// `english` in `language`, or as is if there's no translation
+pub fn tr(language: Option<&str>, english: &'static str) -> &'static str {
+    catalog(language)
+        .iter()
+        .find(|(key, _)| *key == english)
+        .map(|(_, translated)| *translated)
+        .unwrap_or(english)
+}
+
+/// [`tr`] with each `{}` replaced by the next of `args`
+pub fn tr_args(language: Option<&str>, english: &'static str, args: &[&dyn Display]) -> String {
+    let template = tr(language, english);
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+    let mut out = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            out.push_str(&arg.to_string());
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+fn catalog(language: Option<&str>) -> &'static [(&'static str, &'static str)] {
+    match language {
+        Some("de") => DE,
+        Some("hu") => HU,
+        _ => &[],
+    }
+}
+
+/// Minimum number of a language's common words a message needs before it's
+/// taken as that language
+const DETECT_MIN_HITS: usize = 2;
+
+/// Common short words of languages written in the Latin alphabet, for [`detect`]
+const COMMON_WORDS: &[(&str, &str)] = &[
+    (
+        "en",
+        "the and is are you what how this that with have my to of it please can hello thanks i'm for me",
+    ),
+    (
+        "hu",
+        "és az hogy nem van egy ez meg mit vagy szia kérlek köszönöm mert csak még nekem igen hol mikor holnap mi te én hogyan szeretném nagyon",
+    ),
+    (
+        "de",
+        "und der die das ist nicht ich du ein eine mit was wie bitte danke auch für hallo heute morgen mir mich kannst bin",
+    ),
+    (
+        "fr",
+        "le la les et est je tu vous pas une des pour avec bonjour merci c'est mon moi qui dans",
+    ),
+    (
+        "es",
+        "el los las y es que por para con una hola gracias cómo qué está yo mi pero muy puedes",
+    ),
+    (
+        "it",
+        "il che è di per con una sono ciao grazie come non gli della mi puoi anche questo",
+    ),
+    (
+        "pt",
+        "o os você não é com uma para obrigado obrigada olá que está eu meu muito pode",
+    ),
+    (
+        "nl",
+        "de het een en is niet ik je van dat hallo bedankt wat hoe met mij kun ook",
+    ),
+    (
+        "pl",
+        "i w nie się jest to że na jak co cześć dziękuję proszę mnie czy możesz",
+    ),
+];
+
+/// Guess the language of a message, or `None` if it's too short or unclear.
+///
+/// Scripts that mostly belong to one language (Japanese kana, Hangul, Greek,
+/// Hebrew) decide on their own; Latin-alphabet text is matched against
+/// [`COMMON_WORDS`] and needs a clear winner.
+pub fn detect(text: &str) -> Option<&'static str> {
+    let mut letters = 0;
+    let mut scripts = [0usize; 7];
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        let script = match c {
+            '\u{3040}'..='\u{30ff}' => 0,
+            '\u{ac00}'..='\u{d7af}' | '\u{1100}'..='\u{11ff}' => 1,
+            '\u{4e00}'..='\u{9fff}' => 2,
+            '\u{0400}'..='\u{04ff}' => 3,
+            '\u{0370}'..='\u{03ff}' => 4,
+            '\u{0590}'..='\u{05ff}' => 5,
+            '\u{0600}'..='\u{06ff}' => 6,
+            _ => continue,
+        };
+        scripts[script] += 1;
+    }
+    if letters == 0 {
+        return None;
+    }
+    // Japanese mixes kana with Chinese characters
+    if scripts[0] > 0 {
+        return Some("ja");
+    }
+    let other_scripts = ["ja", "ko", "zh", "ru", "el", "he", "ar"];
+    if let Some((i, &count)) = scripts.iter().enumerate().max_by_key(|(_, count)| **count)
+        && count * 2 > letters
+    {
+        return Some(other_scripts[i]);
+    }
+
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let mut scores: Vec<(&str, usize)> = COMMON_WORDS
+        .iter()
+        .map(|(code, common)| {
+            let hits = words
+                .iter()
+                .filter(|w| common.split_whitespace().any(|c| c == w.as_str()))
+                .count();
+            (*code, hits)
+        })
+        .collect();
+    scores.sort_by_key(|(_, hits)| std::cmp::Reverse(*hits));
+
+    match scores.as_slice() {
+        [(code, best), (_, second), ..] if *best >= DETECT_MIN_HITS && best > second => Some(code),
+        _ => None,
+    }
+}
+
+// ============================================================================
+// Catalogs
+// ============================================================================
+
+const HU: &[(&str, &str)] = &[
+    // Pairing
+    ("Hi! I don't recognize you yet.", "Szia! Még nem ismerlek."),
+    (
+        "Your previous pairing code expired, so here's a new one.",
+        "Az előző párosítási kódod lejárt, ezért itt egy új.",
+    ),
+    (
+        "Pairing code: {}\n(valid for {} minutes)\n\nAsk the owner to run:\ncica approve {}",
+        "Párosítási kód: {}\n({} percig érvényes)\n\nKérd meg a tulajdonost, hogy futtassa:\ncica approve {}",
+    ),
+    (
+        "I'm restarting right now and can't take this. Please send it again in a minute.",
+        "Éppen újraindulok, ezt most nem tudom fogadni. Kérlek, küldd el újra egy perc múlva.",
+    ),
+    // Commands
+    ("Available commands:", "Elérhető parancsok:"),
+    (
+        "Show available commands, or how to use one",
+        "Az elérhető parancsok, vagy egy parancs használata",
+    ),
+    (
+        "Send /help <command> for details, e.g. /help cron",
+        "Részletek: /help <parancs>, pl. /help cron",
+    ),
+    (
+        "Unknown command: /{}. Send /help to see them all.",
+        "Ismeretlen parancs: /{}. Az összeset a /help mutatja.",
+    ),
+    (
+        "Show which model you're talking to, and your session, memories and jobs",
+        "Melyik modellel beszélsz, és a munkameneted, emlékeid és feladataid",
+    ),
+    ("Start a new conversation", "Új beszélgetés indítása"),
+    (
+        "Send your last message again",
+        "Az utolsó üzeneted újraküldése",
+    ),
+    (
+        "Think longer before answering this message",
+        "Hosszabb gondolkodás a válasz előtt",
+    ),
+    (
+        "List the tools I used under each reply",
+        "A használt eszközök listája minden válasz alatt",
+    ),
+    (
+        "Summarize what we talked about",
+        "Összefoglaló arról, miről beszéltünk",
+    ),
+    ("Show our last messages", "Az utolsó üzeneteink"),
+    (
+        "List available skills and check they work",
+        "Az elérhető képességek listája és ellenőrzése",
+    ),
+    (
+        "Show the persona I follow here",
+        "A személyiség, amit itt követek",
+    ),
+    (
+        "Forget who I am and who you are, and set us up again",
+        "Elfelejtem, ki vagyok én és ki vagy te, és újrakezdjük a bemutatkozást",
+    ),
+    (
+        "Set the language I answer in",
+        "A nyelv, amelyen válaszolok",
+    ),
+    ("Remind you once", "Egyszeri emlékeztető"),
+    ("Manage scheduled jobs", "Ütemezett feladatok kezelése"),
+    ("Approve a pairing request", "Párosítási kérés jóváhagyása"),
+    ("Deny a pairing request", "Párosítási kérés elutasítása"),
+    (
+        "Set a user's IDENTITY.md or USER.md to the lines that follow",
+        "Egy felhasználó IDENTITY.md vagy USER.md fájljának cseréje a következő sorokra",
+    ),
+    (
+        "Replace the persona for this channel (the lines may follow)",
+        "A csatorna személyiségének cseréje (a szöveg a következő sorokban is jöhet)",
+    ),
+    (
+        "Install a skill for everyone",
+        "Képesség telepítése mindenkinek",
+    ),
+    (
+        "Stop or restart every cron job, keeping their settings",
+        "Minden ütemezett feladat leállítása vagy újraindítása, a beállításaik megmaradnak",
+    ),
+    (
+        "Please complete the onboarding first. Say \"hello\" to get started!",
+        "Előbb fejezzük be a bemutatkozást. Köszönj, és kezdjük!",
+    ),
+    (
+        "Starting fresh! Our previous conversation has been cleared.",
+        "Tiszta lappal kezdünk! Az előző beszélgetésünket töröltem.",
+    ),
+    (
+        "There's no message to retry yet.",
+        "Még nincs újraküldhető üzenet.",
+    ),
+    (
+        "Stop the reply I'm working on",
+        "Leállítja a készülő választ",
+    ),
+    (
+        "Stopped. Send your message again when you're ready.",
+        "Leállítottam. Küldd el újra az üzeneted, ha készen állsz.",
+    ),
+    ("There's nothing to cancel.", "Nincs mit leállítani."),
+    // Quiet hours
+    (
+        "Set quiet hours, when I hold cron results and notifications",
+        "Csendes órák: ilyenkor visszatartom az ütemezett eredményeket és értesítéseket",
+    ),
+    (
+        "Quiet hours: {}. I hold cron results and notifications until they end. /dnd off turns them off.",
+        "Csendes órák: {}. Addig visszatartom az ütemezett eredményeket és értesítéseket. A /dnd off kikapcsolja.",
+    ),
+    (
+        "No quiet hours set. Set them with e.g. /dnd 22:00-07:00.",
+        "Nincsenek csendes órák. Beállíthatod pl. így: /dnd 22:00-07:00.",
+    ),
+    (
+        "Quiet hours are off. Anything I held comes shortly.",
+        "Csendes órák kikapcsolva. Amit visszatartottam, hamarosan jön.",
+    ),
+    (
+        "Couldn't set quiet hours: {}. Try e.g. /dnd 22:00-07:00, or /dnd off.",
+        "Nem sikerült beállítani a csendes órákat: {}. Próbáld pl. így: /dnd 22:00-07:00, vagy /dnd off.",
+    ),
+    (
+        "Quiet hours set to {}. I'll hold cron results and notifications until they end, then send them together.",
+        "Csendes órák: {}. Addig visszatartom az ütemezett eredményeket és értesítéseket, aztán egyben küldöm őket.",
+    ),
+    (
+        "While your quiet hours were on, {} message(s) came in:",
+        "A csendes órák alatt {} üzenet érkezett:",
+    ),
+    // Daily digest
+    (
+        "Get a daily digest of job results, open questions and new memories",
+        "Napi összefoglaló a feladatok eredményeiről, nyitott kérdésekről és új emlékekről",
+    ),
+    (
+        "Your daily digest goes out at {}. /digest off turns it off.",
+        "A napi összefoglalód {}-kor megy ki. A /digest off kikapcsolja.",
+    ),
+    (
+        "No daily digest yet. Turn it on with e.g. /digest 08:00.",
+        "Még nincs napi összefoglaló. Bekapcsolhatod pl. így: /digest 08:00.",
+    ),
+    ("Daily digest turned off.", "Napi összefoglaló kikapcsolva."),
+    (
+        "Couldn't set the digest: {}. Try e.g. /digest 08:00, /digest now or /digest off.",
+        "Nem sikerült beállítani az összefoglalót: {}. Próbáld pl. így: /digest 08:00, /digest now vagy /digest off.",
+    ),
+    (
+        "I'll send your daily digest at {}: job results, open questions and new memories from the last 24 hours.",
+        "{}-kor küldöm a napi összefoglalót: az elmúlt 24 óra feladateredményei, nyitott kérdései és új emlékei.",
+    ),
+    (
+        "Nothing happened in the last 24 hours, so there's no digest.",
+        "Az elmúlt 24 órában nem történt semmi, így nincs összefoglaló.",
+    ),
+    // Feedback
+    (
+        "Tell me my last reply was helpful",
+        "Jelezd, hogy az utolsó válaszom hasznos volt",
+    ),
+    (
+        "Tell me my last reply missed, and what to do instead",
+        "Jelezd, hogy az utolsó válaszom nem talált, és mit csináljak helyette",
+    ),
+    (
+        "There's no reply of mine to rate yet.",
+        "Még nincs értékelhető válaszom.",
+    ),
+    ("Thanks for the feedback!", "Köszönöm a visszajelzést!"),
+    (
+        "Got it, I'll keep that in mind from now on.",
+        "Értem, mostantól ezt tartom szem előtt.",
+    ),
+    (
+        "Thanks for telling me. What should I do differently? Tell me with /bad <what to change> and I'll keep it in mind.",
+        "Köszönöm, hogy szóltál. Mit csináljak másképp? Írd meg így: /bad <mit változtassak>, és megjegyzem.",
+    ),
+    // Language
+    ("Language set to {}.", "Nyelv beállítva: {}."),
+    (
+        "Language: {}. Change it with /language <code>, or /language auto to detect it again.",
+        "Nyelv: {}. Módosítsd így: /language <kód>, vagy /language auto az újbóli felismeréshez.",
+    ),
+    (
+        "I'll pick up your language from your next messages.",
+        "A következő üzeneteidből ismerem fel a nyelvedet.",
+    ),
+    (
+        "Unknown language: {}. Use a code like en, de or hu.",
+        "Ismeretlen nyelv: {}. Használj egy kódot, pl. en, de vagy hu.",
+    ),
+    // Cron
+    ("Running job...", "Feladat futtatása..."),
+    ("Job failed: {}", "A feladat nem sikerült: {}"),
+    ("Job not found: {}", "Nincs ilyen feladat: {}"),
+    ("Error: {}", "Hiba: {}"),
+    (
+        "Sorry, your role ({}) can't create scheduled jobs.",
+        "Sajnálom, a szerepköröddel ({}) nem hozhatsz létre ütemezett feladatokat.",
+    ),
+    (
+        "Sorry, your role ({}) can't create or change scheduled jobs.",
+        "Sajnálom, a szerepköröddel ({}) nem hozhatsz létre és nem módosíthatsz ütemezett feladatokat.",
+    ),
+];
+
+const DE: &[(&str, &str)] = &[
+    // Pairing
+    (
+        "Hi! I don't recognize you yet.",
+        "Hallo! Ich kenne dich noch nicht.",
+    ),
+    (
+        "Your previous pairing code expired, so here's a new one.",
+        "Dein vorheriger Kopplungscode ist abgelaufen, hier ist ein neuer.",
+    ),
+    (
+        "Pairing code: {}\n(valid for {} minutes)\n\nAsk the owner to run:\ncica approve {}",
+        "Kopplungscode: {}\n(gültig für {} Minuten)\n\nBitte den Besitzer, Folgendes auszuführen:\ncica approve {}",
+    ),
+    (
+        "I'm restarting right now and can't take this. Please send it again in a minute.",
+        "Ich starte gerade neu und kann das nicht annehmen. Bitte schick es in einer Minute noch einmal.",
+    ),
+    // Commands
+    ("Available commands:", "Verfügbare Befehle:"),
+    (
+        "Show available commands, or how to use one",
+        "Verfügbare Befehle anzeigen, oder wie einer funktioniert",
+    ),
+    (
+        "Send /help <command> for details, e.g. /help cron",
+        "Details mit /help <Befehl>, z. B. /help cron",
+    ),
+    (
+        "Unknown command: /{}. Send /help to see them all.",
+        "Unbekannter Befehl: /{}. Alle Befehle zeigt /help.",
+    ),
+    (
+        "Show which model you're talking to, and your session, memories and jobs",
+        "Zeigen, mit welchem Modell du sprichst, dazu Sitzung, Erinnerungen und Aufgaben",
+    ),
+    ("Start a new conversation", "Ein neues Gespräch beginnen"),
+    (
+        "Send your last message again",
+        "Deine letzte Nachricht erneut senden",
+    ),
+    (
+        "Think longer before answering this message",
+        "Vor der Antwort länger nachdenken",
+    ),
+    (
+        "List the tools I used under each reply",
+        "Unter jeder Antwort die verwendeten Werkzeuge auflisten",
+    ),
+    (
+        "Summarize what we talked about",
+        "Zusammenfassen, worüber wir gesprochen haben",
+    ),
+    (
+        "Show our last messages",
+        "Unsere letzten Nachrichten anzeigen",
+    ),
+    (
+        "List available skills and check they work",
+        "Verfügbare Skills auflisten und prüfen",
+    ),
+    (
+        "Show the persona I follow here",
+        "Die Persona anzeigen, der ich hier folge",
+    ),
+    (
+        "Forget who I am and who you are, and set us up again",
+        "Vergessen, wer ich bin und wer du bist, und alles neu einrichten",
+    ),
+    (
+        "Set the language I answer in",
+        "Die Sprache festlegen, in der ich antworte",
+    ),
+    ("Remind you once", "Einmal erinnern"),
+    ("Manage scheduled jobs", "Geplante Aufgaben verwalten"),
+    (
+        "Approve a pairing request",
+        "Eine Kopplungsanfrage genehmigen",
+    ),
+    ("Deny a pairing request", "Eine Kopplungsanfrage ablehnen"),
+    (
+        "Set a user's IDENTITY.md or USER.md to the lines that follow",
+        "IDENTITY.md oder USER.md eines Nutzers durch die folgenden Zeilen ersetzen",
+    ),
+    (
+        "Replace the persona for this channel (the lines may follow)",
+        "Die Persona dieses Kanals ersetzen (der Text kann in den nächsten Zeilen folgen)",
+    ),
+    (
+        "Install a skill for everyone",
+        "Einen Skill für alle installieren",
+    ),
+    (
+        "Stop or restart every cron job, keeping their settings",
+        "Alle Cron-Jobs anhalten oder fortsetzen, ihre Einstellungen bleiben erhalten",
+    ),
+    (
+        "Please complete the onboarding first. Say \"hello\" to get started!",
+        "Bitte schließe zuerst das Onboarding ab. Sag \"Hallo\", um anzufangen!",
+    ),
+    (
+        "Starting fresh! Our previous conversation has been cleared.",
+        "Neuanfang! Unser bisheriges Gespräch wurde gelöscht.",
+    ),
+    (
+        "There's no message to retry yet.",
+        "Es gibt noch keine Nachricht zum Wiederholen.",
+    ),
+    (
+        "Stop the reply I'm working on",
+        "Stoppt die Antwort, an der ich gerade arbeite",
+    ),
+    (
+        "Stopped. Send your message again when you're ready.",
+        "Gestoppt. Schick deine Nachricht erneut, wenn du so weit bist.",
+    ),
+    ("There's nothing to cancel.", "Es gibt nichts abzubrechen."),
+    // Quiet hours
+    (
+        "Set quiet hours, when I hold cron results and notifications",
+        "Ruhezeiten festlegen, in denen ich Cron-Ergebnisse und Benachrichtigungen zurückhalte",
+    ),
+    (
+        "Quiet hours: {}. I hold cron results and notifications until they end. /dnd off turns them off.",
+        "Ruhezeiten: {}. Bis dahin halte ich Cron-Ergebnisse und Benachrichtigungen zurück. /dnd off schaltet sie aus.",
+    ),
+    (
+        "No quiet hours set. Set them with e.g. /dnd 22:00-07:00.",
+        "Keine Ruhezeiten festgelegt. Zum Beispiel mit /dnd 22:00-07:00.",
+    ),
+    (
+        "Quiet hours are off. Anything I held comes shortly.",
+        "Ruhezeiten sind aus. Was ich zurückgehalten habe, kommt gleich.",
+    ),
+    (
+        "Couldn't set quiet hours: {}. Try e.g. /dnd 22:00-07:00, or /dnd off.",
+        "Ruhezeiten konnten nicht festgelegt werden: {}. Versuch z. B. /dnd 22:00-07:00 oder /dnd off.",
+    ),
+    (
+        "Quiet hours set to {}. I'll hold cron results and notifications until they end, then send them together.",
+        "Ruhezeiten: {}. Bis sie enden, halte ich Cron-Ergebnisse und Benachrichtigungen zurück und schicke sie dann zusammen.",
+    ),
+    (
+        "While your quiet hours were on, {} message(s) came in:",
+        "Während deiner Ruhezeiten kamen {} Nachricht(en):",
+    ),
+    // Daily digest
+    (
+        "Get a daily digest of job results, open questions and new memories",
+        "Tägliche Zusammenfassung von Job-Ergebnissen, offenen Fragen und neuen Erinnerungen",
+    ),
+    (
+        "Your daily digest goes out at {}. /digest off turns it off.",
+        "Deine tägliche Zusammenfassung kommt um {}. /digest off schaltet sie aus.",
+    ),
+    (
+        "No daily digest yet. Turn it on with e.g. /digest 08:00.",
+        "Noch keine tägliche Zusammenfassung. Einschalten z. B. mit /digest 08:00.",
+    ),
+    (
+        "Daily digest turned off.",
+        "Tägliche Zusammenfassung ausgeschaltet.",
+    ),
+    (
+        "Couldn't set the digest: {}. Try e.g. /digest 08:00, /digest now or /digest off.",
+        "Zusammenfassung konnte nicht eingestellt werden: {}. Versuch z. B. /digest 08:00, /digest now oder /digest off.",
+    ),
+    (
+        "I'll send your daily digest at {}: job results, open questions and new memories from the last 24 hours.",
+        "Ich schicke dir um {} deine tägliche Zusammenfassung: Job-Ergebnisse, offene Fragen und neue Erinnerungen der letzten 24 Stunden.",
+    ),
+    (
+        "Nothing happened in the last 24 hours, so there's no digest.",
+        "In den letzten 24 Stunden ist nichts passiert, also gibt es keine Zusammenfassung.",
+    ),
+    // Feedback
+    (
+        "Tell me my last reply was helpful",
+        "Sag mir, dass meine letzte Antwort hilfreich war",
+    ),
+    (
+        "Tell me my last reply missed, and what to do instead",
+        "Sag mir, dass meine letzte Antwort danebenlag, und was ich stattdessen tun soll",
+    ),
+    (
+        "There's no reply of mine to rate yet.",
+        "Es gibt noch keine Antwort von mir zu bewerten.",
+    ),
+    ("Thanks for the feedback!", "Danke für das Feedback!"),
+    (
+        "Got it, I'll keep that in mind from now on.",
+        "Verstanden, ab jetzt denke ich daran.",
+    ),
+    (
+        "Thanks for telling me. What should I do differently? Tell me with /bad <what to change> and I'll keep it in mind.",
+        "Danke für den Hinweis. Was soll ich anders machen? Sag es mir mit /bad <was ich ändern soll>, dann merke ich es mir.",
+    ),
+    // Language
+    ("Language set to {}.", "Sprache eingestellt: {}."),
+    (
+        "Language: {}. Change it with /language <code>, or /language auto to detect it again.",
+        "Sprache: {}. Ändern mit /language <Code>, oder /language auto, um sie neu zu erkennen.",
+    ),
+    (
+        "I'll pick up your language from your next messages.",
+        "Ich erkenne deine Sprache an deinen nächsten Nachrichten.",
+    ),
+    (
+        "Unknown language: {}. Use a code like en, de or hu.",
+        "Unbekannte Sprache: {}. Verwende einen Code wie en, de oder hu.",
+    ),
+    // Cron
+    ("Running job...", "Aufgabe läuft..."),
+    ("Job failed: {}", "Aufgabe fehlgeschlagen: {}"),
+    ("Job not found: {}", "Aufgabe nicht gefunden: {}"),
+    ("Error: {}", "Fehler: {}"),
+    (
+        "Sorry, your role ({}) can't create scheduled jobs.",
+        "Tut mir leid, mit deiner Rolle ({}) kannst du keine geplanten Aufgaben erstellen.",
+    ),
+    (
+        "Sorry, your role ({}) can't create or change scheduled jobs.",
+        "Tut mir leid, mit deiner Rolle ({}) kannst du keine geplanten Aufgaben erstellen oder ändern.",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tr() {
+        assert_eq!(tr(Some("hu"), "Running job..."), "Feladat futtatása...");
+        // No catalog, or no entry: English
+        assert_eq!(tr(Some("fr"), "Running job..."), "Running job...");
+        assert_eq!(tr(None, "Running job..."), "Running job...");
+        assert_eq!(tr(Some("de"), "Not in the catalog"), "Not in the catalog");
+
+        assert_eq!(
+            tr_args(Some("de"), "Job not found: {}", &[&"backup"]),
+            "Aufgabe nicht gefunden: backup"
+        );
+        assert_eq!(
+            tr_args(
+                None,
+                "Pairing code: {}\n(valid for {} minutes)\n\nAsk the owner to run:\ncica approve {}",
+                &[&"ABC123", &60, &"ABC123"]
+            ),
+            "Pairing code: ABC123\n(valid for 60 minutes)\n\nAsk the owner to run:\ncica approve ABC123"
+        );
+    }
+
+    #[test]
+    fn test_catalogs_keep_placeholders() {
+        for (english, translated) in HU.iter().chain(DE) {
+            assert_eq!(
+                english.matches("{}").count(),
+                translated.matches("{}").count(),
+                "{}",
+                english
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("hu"), Some("hu"));
+        assert_eq!(normalize(" DE "), Some("de"));
+        assert_eq!(normalize("hungarian"), Some("hu"));
+        assert_eq!(normalize("klingon"), None);
+        assert_eq!(name("hu"), Some("Hungarian"));
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect("Szia! Holnap mit csinálunk, és hol?"), Some("hu"));
+        assert_eq!(detect("Kannst du mir bitte helfen?"), Some("de"));
+        assert_eq!(detect("What is the weather like today?"), Some("en"));
+        assert_eq!(
+            detect("¿Hola, cómo está el tiempo para mañana?"),
+            Some("es")
+        );
+        assert_eq!(detect("明日の天気はどうですか"), Some("ja"));
+        assert_eq!(detect("Привет, как дела?"), Some("ru"));
+        // Too short or mixed to tell
+        assert_eq!(detect("ok"), None);
+        assert_eq!(detect("Hallo"), None);
+        assert_eq!(detect("👍"), None);
+    }
+}
@@ -0,0 +1,70 @@
+//! The assistant engine behind Cica.
+//!
+//! `cica-core` holds everything except the command-line interface: chat
+//! channels, AI backends, pairing, cron scheduling and memory. The `cica`
+//! binary is a thin wrapper around it, and the same pieces can be embedded
+//! in another application.
+//!
+//! - [`config`] loads `config.toml` and exposes where Cica keeps its data.
+//! - [`channels`] holds the [`Channel`](channels::Channel) abstraction, the
+//!   registry of transports ([`channels::SUPPORTED_CHANNELS`]) and the
+//!   shared message pipeline.
+//! - [`backends`] runs prompts through Claude Code or Cursor CLI.
+//! - [`pairing`] tracks approved users, sessions and roles.
+//! - [`cron`] schedules prompts, and [`memory`] indexes user memories.
+//! - [`digest`] writes each user's daily digest.
+//! - [`attachments`] cleans up the files users send.
+//! - [`transcript`] keeps what each user and Cica said, and [`feedback`]
+//!   how they rated Cica's replies.
+//! - [`notify`] delivers system notifications to the owner, held during
+//!   [`quiet`] hours.
+//! - [`redact`] masks secrets in outgoing messages and logs.
+//! - [`i18n`] translates Cica's own messages into each user's language.
+//!
+//! Start every configured channel, the way `cica` does:
+//!
+//! ```no_run
+//! use cica_core::{channels, config::Config};
+//!
+//! # async fn start() -> anyhow::Result<()> {
+//! let config = Config::load()?;
+//! for info in channels::SUPPORTED_CHANNELS {
+//!     if info.is_configured(&config) {
+//!         tokio::spawn((info.run)(config.clone(), (info.send_queue)()));
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Optional parts are behind cargo features: `signal`, `slack`, `cursor`,
+//! `memory`, `keyring` and `pdf` (all enabled by default).
+
+pub mod attachments;
+pub mod backends;
+pub mod backup;
+pub mod channels;
+pub mod config;
+pub mod context_sync;
+pub mod cron;
+pub mod digest;
+pub mod export;
+pub mod facts;
+pub mod feedback;
+pub mod i18n;
+pub mod mcp;
+pub mod memory;
+pub mod metrics;
+pub mod monitor;
+pub mod notify;
+pub mod onboarding;
+pub mod pairing;
+pub mod quiet;
+pub mod redact;
+pub mod search;
+pub mod secrets;
+pub mod setup;
+pub mod shutdown;
+pub mod skills;
+pub mod storage;
+pub mod transcript;
@@ -0,0 +1,149 @@
+//! Asks MCP servers which tools they have, so the agent can be told about
+//! them. Speaks just enough of the protocol (over stdio) to list tools.
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{ChildStdin, ChildStdout, Command};
+
+use crate::config::McpServer;
+
+/// Longest a server may take to start and list its tools
+const LIST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Protocol version offered to the server
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// The tools `server` has, by name, with what they do
+pub async fn list_tools(server: &McpServer) -> Result<BTreeMap<String, String>> {
+    tokio::time::timeout(LIST_TIMEOUT, list_tools_inner(server))
+        .await
+        .map_err(|_| anyhow!("The server didn't list its tools in time"))?
+}
+
+async fn list_tools_inner(server: &McpServer) -> Result<BTreeMap<String, String>> {
+    let mut child = Command::new(&server.command)
+        .args(&server.args)
+        .envs(&server.env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to start {}", server.command))?;
+
+    let mut stdin = child
+        .stdin
+        .take()
+        .context("MCP server stdin not captured")?;
+    let stdout = child
+        .stdout
+        .take()
+        .context("MCP server stdout not captured")?;
+    let mut lines = BufReader::new(stdout).lines();
+
+    request(
+        &mut stdin,
+        &mut lines,
+        1,
+        "initialize",
+        json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": {},
+            "clientInfo": { "name": "cica", "version": env!("CARGO_PKG_VERSION") },
+        }),
+    )
+    .await?;
+    send(
+        &mut stdin,
+        &json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+    )
+    .await?;
+
+    let mut tools = BTreeMap::new();
+    let mut cursor: Option<String> = None;
+    for id in 2.. {
+        let params = match &cursor {
+            Some(cursor) => json!({ "cursor": cursor }),
+            None => json!({}),
+        };
+        let result = request(&mut stdin, &mut lines, id, "tools/list", params).await?;
+        for tool in result["tools"].as_array().into_iter().flatten() {
+            if let Some(name) = tool["name"].as_str() {
+                let description = tool["description"].as_str().unwrap_or_default();
+                tools.insert(name.to_string(), summary(description));
+            }
+        }
+        cursor = result["nextCursor"].as_str().map(String::from);
+        if cursor.is_none() {
+            break;
+        }
+    }
+    Ok(tools)
+}
+
+async fn send(stdin: &mut ChildStdin, message: &Value) -> Result<()> {
+    stdin
+        .write_all(format!("{}\n", message).as_bytes())
+        .await
+        .context("The MCP server isn't accepting messages")?;
+    stdin.flush().await?;
+    Ok(())
+}
+
+/// Send a request and wait for its response, skipping notifications
+async fn request(
+    stdin: &mut ChildStdin,
+    lines: &mut Lines<BufReader<ChildStdout>>,
+    id: u64,
+    method: &str,
+    params: Value,
+) -> Result<Value> {
+    send(
+        stdin,
+        &json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }),
+    )
+    .await?;
+
+    while let Some(line) = lines.next_line().await? {
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        if message["id"].as_u64() != Some(id) {
+            continue;
+        }
+        if let Some(error) = message.get("error") {
+            bail!(
+                "{} failed: {}",
+                method,
+                error["message"].as_str().unwrap_or("unknown error")
+            );
+        }
+        return Ok(message["result"].clone());
+    }
+    bail!("The MCP server exited before answering {}", method)
+}
+
+/// The first line of a tool's description, kept short for the prompt
+fn summary(description: &str) -> String {
+    let line = description.lines().next().unwrap_or_default().trim();
+    match line.char_indices().nth(120) {
+        Some((cut, _)) => format!("{}...", &line[..cut]),
+        None => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(summary("Create an issue.\nArgs: ..."), "Create an issue.");
+        assert_eq!(summary(""), "");
+        assert_eq!(summary(&"x".repeat(200)), format!("{}...", "x".repeat(120)));
+    }
+}
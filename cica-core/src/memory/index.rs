@@ -0,0 +1,754 @@
+//! SQLite vector index over memory files, with local embeddings for semantic search.
+
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OptionalExtension, ffi::sqlite3_auto_extension};
+use std::collections::HashMap;
+use std::ffi::c_char;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, Once};
+use tracing::{debug, info, warn};
+
+use super::{MemorySearchResult, markdown_files, memories_dir, shared_memories_dir};
+use crate::config::{self, MemoryConfig};
+
+// Initialize sqlite-vec extension once
+static SQLITE_VEC_INIT: Once = Once::new();
+
+fn ensure_sqlite_vec_init() {
+    SQLITE_VEC_INIT.call_once(|| unsafe {
+        sqlite3_auto_extension(Some(std::mem::transmute::<
+            *const (),
+            unsafe extern "C" fn(
+                *mut rusqlite::ffi::sqlite3,
+                *mut *mut c_char,
+                *const rusqlite::ffi::sqlite3_api_routines,
+            ) -> i32,
+        >(sqlite_vec::sqlite3_vec_init as *const ())));
+    });
+}
+
+/// Channel name shared memories are indexed under
+const SHARED_CHANNEL: &str = "shared";
+
+/// Embedding model used unless config.toml picks another
+const DEFAULT_EMBEDDING_MODEL: &str = "Xenova/bge-small-en-v1.5";
+
+// Embedding model and its model code - loaded lazily on first use
+static EMBEDDING_MODEL: Mutex<Option<(String, fastembed::TextEmbedding)>> = Mutex::new(None);
+
+/// Get the cache directory for embedding models
+fn embedding_cache_dir() -> Result<PathBuf> {
+    Ok(config::paths()?.internal_dir.join("models"))
+}
+
+/// The configured embedding model: its model code, fastembed model and vector size
+fn configured_model() -> Result<(String, fastembed::EmbeddingModel, usize)> {
+    let code = config::Config::load()
+        .ok()
+        .and_then(|c| c.memory.embedding_model)
+        .unwrap_or_else(|| DEFAULT_EMBEDDING_MODEL.to_string());
+
+    let model: fastembed::EmbeddingModel = code.parse().map_err(|e: String| {
+        anyhow::anyhow!(
+            "{} (check embedding_model under [memory] in config.toml)",
+            e
+        )
+    })?;
+    let dim = fastembed::TextEmbedding::get_model_info(&model)?.dim;
+
+    Ok((code, model, dim))
+}
+
+/// Get or initialize the embedding model, reloading it if the config changed
+fn with_embedding_model<F, R>(f: F) -> Result<R>
+where
+    F: FnOnce(&mut fastembed::TextEmbedding) -> Result<R>,
+{
+    let mut guard = EMBEDDING_MODEL
+        .lock()
+        .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+
+    let (code, model, _dim) = configured_model()?;
+    if guard.as_ref().is_none_or(|(loaded, _)| *loaded != code) {
+        let cache_dir = embedding_cache_dir()?;
+        info!("Loading embedding model {}...", code);
+        let embedding = fastembed::TextEmbedding::try_new(
+            fastembed::InitOptions::new(model)
+                .with_cache_dir(cache_dir)
+                .with_show_download_progress(false),
+        )
+        .context("Failed to initialize embedding model")?;
+        info!("Embedding model ready");
+        *guard = Some((code, embedding));
+    }
+
+    f(&mut guard.as_mut().unwrap().1)
+}
+
+/// Ensure the embedding model is downloaded (called during setup)
+pub fn ensure_model_downloaded() -> Result<()> {
+    with_embedding_model(|_| Ok(()))
+}
+
+/// Get the path to the memory database
+fn memory_db_path() -> Result<PathBuf> {
+    Ok(config::paths()?.base.join("memory.db"))
+}
+
+/// Memory index manager
+pub struct MemoryIndex {
+    db: Connection,
+}
+
+impl MemoryIndex {
+    /// Open or create the memory index database
+    pub fn open() -> Result<Self> {
+        // Ensure sqlite-vec is registered
+        ensure_sqlite_vec_init();
+
+        let db_path = memory_db_path()?;
+
+        // Ensure parent directory exists
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let db = Connection::open(&db_path)?;
+
+        init_schema(&db)?;
+
+        let (code, _model, dim) = configured_model()?;
+        prepare_vectors(&db, &code, dim)?;
+
+        Ok(Self { db })
+    }
+
+    /// Index all memory files for a user
+    pub fn index_user_memories(&mut self, channel: &str, user_id: &str) -> Result<()> {
+        self.index_dir(channel, user_id, &memories_dir(channel, user_id)?)
+    }
+
+    /// Index the memories shared by all users
+    pub fn index_shared_memories(&mut self) -> Result<()> {
+        self.index_dir(SHARED_CHANNEL, "", &shared_memories_dir()?)
+    }
+
+    /// Index the memory files in a directory under a channel and user
+    fn index_dir(&mut self, channel: &str, user_id: &str, memories_path: &Path) -> Result<()> {
+        if !memories_path.exists() {
+            debug!("No memories directory for {}:{}", channel, user_id);
+            return Ok(());
+        }
+
+        // All .md files, including those cron jobs ingest into subdirectories
+        let mut files = Vec::new();
+        markdown_files(memories_path, &mut files)?;
+
+        for path in files {
+            let rel_path = path
+                .strip_prefix(memories_path)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string();
+
+            // Read file content
+            let content = match std::fs::read_to_string(&path) {
+                Ok(c) => c,
+                Err(e) => {
+                    warn!("Failed to read memory file {:?}: {}", path, e);
+                    continue;
+                }
+            };
+
+            // Compute hash to check if file changed
+            let hash = format!("{:x}", md5_hash(&content));
+
+            // Check if already indexed with same hash
+            let existing_hash: Option<String> = self
+                .db
+                .query_row(
+                    "SELECT hash FROM memory_files WHERE channel = ? AND user_id = ? AND path = ?",
+                    [channel, user_id, &rel_path],
+                    |row| row.get(0),
+                )
+                .ok();
+
+            if existing_hash.as_ref() == Some(&hash) {
+                debug!("Memory file {} unchanged, skipping", rel_path);
+                continue;
+            }
+
+            info!("Indexing memory file: {}", rel_path);
+
+            // Delete old entries if they exist
+            self.db.execute(
+                r#"
+                DELETE FROM memory_vectors WHERE chunk_id IN (
+                    SELECT c.id FROM memory_chunks c
+                    JOIN memory_files f ON c.file_id = f.id
+                    WHERE f.channel = ? AND f.user_id = ? AND f.path = ?
+                )
+                "#,
+                [channel, user_id, &rel_path],
+            )?;
+
+            self.db.execute(
+                r#"
+                DELETE FROM memory_chunks_fts WHERE rowid IN (
+                    SELECT c.id FROM memory_chunks c
+                    JOIN memory_files f ON c.file_id = f.id
+                    WHERE f.channel = ? AND f.user_id = ? AND f.path = ?
+                )
+                "#,
+                [channel, user_id, &rel_path],
+            )?;
+
+            self.db.execute(
+                r#"
+                DELETE FROM memory_chunks WHERE file_id IN (
+                    SELECT id FROM memory_files
+                    WHERE channel = ? AND user_id = ? AND path = ?
+                )
+                "#,
+                [channel, user_id, &rel_path],
+            )?;
+
+            self.db.execute(
+                "DELETE FROM memory_files WHERE channel = ? AND user_id = ? AND path = ?",
+                [channel, user_id, &rel_path],
+            )?;
+
+            // Insert file record
+            self.db.execute(
+                "INSERT INTO memory_files (channel, user_id, path, hash, updated_at) VALUES (?, ?, ?, ?, ?)",
+                rusqlite::params![
+                    channel,
+                    user_id,
+                    &rel_path,
+                    &hash,
+                    now_timestamp() as i64
+                ],
+            )?;
+
+            let file_id = self.db.last_insert_rowid();
+
+            // Chunk the content
+            let chunks = chunk_text(&content);
+
+            // Generate embeddings for all chunks
+            let chunk_texts: Vec<String> = chunks.iter().map(|c| c.text.clone()).collect();
+            let embeddings = with_embedding_model(|model| {
+                model
+                    .embed(chunk_texts.clone(), None)
+                    .context("Failed to generate embeddings")
+            })?;
+
+            // Insert chunks and vectors
+            for (i, (chunk, embedding)) in chunks.iter().zip(embeddings.iter()).enumerate() {
+                self.db.execute(
+                    "INSERT INTO memory_chunks (file_id, chunk_index, content, start_line, end_line) VALUES (?, ?, ?, ?, ?)",
+                    rusqlite::params![file_id, i as i64, &chunk.text, chunk.start_line as i64, chunk.end_line as i64],
+                )?;
+
+                let chunk_id = self.db.last_insert_rowid();
+
+                self.db.execute(
+                    "INSERT INTO memory_chunks_fts (rowid, content) VALUES (?, ?)",
+                    rusqlite::params![chunk_id, &chunk.text],
+                )?;
+
+                // Convert embedding to bytes for sqlite-vec
+                let embedding_bytes = embedding_to_bytes(embedding);
+
+                self.db.execute(
+                    "INSERT INTO memory_vectors (chunk_id, embedding) VALUES (?, ?)",
+                    rusqlite::params![chunk_id, embedding_bytes],
+                )?;
+            }
+
+            debug!("Indexed {} chunks from {}", chunks.len(), rel_path);
+        }
+
+        Ok(())
+    }
+
+    /// Search a user's memories and the shared ones, best first.
+    /// Shared results have paths starting with "shared/".
+    ///
+    /// Chunks close to the query's embedding and chunks sharing its words are
+    /// both candidates. Their score is the embedding similarity plus boosts for
+    /// keyword matches and recently updated files (see `MemoryConfig`).
+    pub fn search(
+        &self,
+        channel: &str,
+        user_id: &str,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<MemorySearchResult>> {
+        let settings = config::Config::load().map(|c| c.memory).unwrap_or_default();
+
+        // Generate query embedding
+        let query_bytes = with_embedding_model(|model| {
+            let embeddings = model
+                .embed(vec![query.to_string()], None)
+                .context("Failed to generate query embedding")?;
+            Ok(embedding_to_bytes(&embeddings[0]))
+        })?;
+
+        let pool = (limit * CANDIDATES_PER_RESULT) as i64;
+        let mut candidates: HashMap<i64, Candidate> = HashMap::new();
+
+        // Nearest chunks by embedding (sqlite-vec)
+        let mut stmt = self.db.prepare(&format!(
+            r#"
+            SELECT {}
+            FROM memory_vectors v
+            JOIN memory_chunks c ON v.chunk_id = c.id
+            JOIN memory_files f ON c.file_id = f.id
+            WHERE (f.channel = ?2 AND f.user_id = ?3) OR f.channel = ?4
+            ORDER BY distance ASC
+            LIMIT ?5
+            "#,
+            CANDIDATE_COLUMNS
+        ))?;
+        let rows = stmt.query_map(
+            rusqlite::params![query_bytes, channel, user_id, SHARED_CHANNEL, pool],
+            |row| Candidate::from_row(row, None),
+        )?;
+        for row in rows {
+            let (id, candidate) = row?;
+            candidates.insert(id, candidate);
+        }
+
+        // Best keyword matches (FTS5)
+        if let Some(keywords) = fts_query(query) {
+            let mut stmt = self.db.prepare(&format!(
+                r#"
+                SELECT {}, bm25(memory_chunks_fts) AS rank
+                FROM memory_chunks_fts
+                JOIN memory_chunks c ON c.id = memory_chunks_fts.rowid
+                JOIN memory_files f ON c.file_id = f.id
+                JOIN memory_vectors v ON v.chunk_id = c.id
+                WHERE memory_chunks_fts MATCH ?6
+                    AND ((f.channel = ?2 AND f.user_id = ?3) OR f.channel = ?4)
+                ORDER BY rank
+                LIMIT ?5
+                "#,
+                CANDIDATE_COLUMNS
+            ))?;
+            let rows = stmt.query_map(
+                rusqlite::params![
+                    query_bytes,
+                    channel,
+                    user_id,
+                    SHARED_CHANNEL,
+                    pool,
+                    keywords
+                ],
+                |row| Candidate::from_row(row, Some(row.get(5)?)),
+            )?;
+            for row in rows {
+                let (id, candidate) = row?;
+                candidates.insert(id, candidate);
+            }
+        }
+
+        let mut results = rank(
+            candidates.into_values().collect(),
+            &settings,
+            now_timestamp(),
+        );
+        results.truncate(limit);
+        Ok(results)
+    }
+
+    /// Get all memory file paths for a user (for context building)
+    #[allow(dead_code)]
+    pub fn list_memory_files(&self, channel: &str, user_id: &str) -> Result<Vec<String>> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT path FROM memory_files WHERE channel = ? AND user_id = ?")?;
+
+        let paths = stmt
+            .query_map([channel, user_id], |row| row.get(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(paths)
+    }
+}
+
+/// Create the index tables (except the vector table, see `prepare_vectors`)
+fn init_schema(db: &Connection) -> Result<()> {
+    db.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS memory_files (
+            id INTEGER PRIMARY KEY,
+            channel TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            path TEXT NOT NULL,
+            hash TEXT NOT NULL,
+            updated_at INTEGER NOT NULL,
+            UNIQUE(channel, user_id, path)
+        );
+
+        CREATE TABLE IF NOT EXISTS memory_chunks (
+            id INTEGER PRIMARY KEY,
+            file_id INTEGER NOT NULL REFERENCES memory_files(id) ON DELETE CASCADE,
+            chunk_index INTEGER NOT NULL,
+            content TEXT NOT NULL,
+            start_line INTEGER NOT NULL,
+            end_line INTEGER NOT NULL,
+            UNIQUE(file_id, chunk_index)
+        );
+
+        CREATE TABLE IF NOT EXISTS memory_meta (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+        "#,
+    )?;
+
+    // Keyword index over chunks, filled from chunks indexed before it existed
+    let has_fts_table: bool = db.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type='table' AND name='memory_chunks_fts'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_fts_table {
+        db.execute_batch(
+            r#"
+            CREATE VIRTUAL TABLE memory_chunks_fts USING fts5(content);
+            INSERT INTO memory_chunks_fts (rowid, content) SELECT id, content FROM memory_chunks;
+            "#,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Make sure the vector table holds embeddings from the configured model.
+/// When the model changes, the index is cleared and rebuilt as memories are indexed.
+fn prepare_vectors(db: &Connection, code: &str, dim: usize) -> Result<()> {
+    let has_vec_table: bool = db.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type='table' AND name='memory_vectors'",
+        [],
+        |row| row.get(0),
+    )?;
+    let indexed_model: Option<String> = db
+        .query_row(
+            "SELECT value FROM memory_meta WHERE key = 'embedding_model'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?
+        // Indexes from before the model was configurable used the default
+        .or_else(|| has_vec_table.then(|| DEFAULT_EMBEDDING_MODEL.to_string()));
+
+    if has_vec_table && indexed_model.as_deref() == Some(code) {
+        return Ok(());
+    }
+
+    if has_vec_table {
+        info!(
+            "Embedding model changed from {} to {}, rebuilding memory index",
+            indexed_model.unwrap_or_default(),
+            code
+        );
+    }
+
+    db.execute_batch(&format!(
+        r#"
+        DROP TABLE IF EXISTS memory_vectors;
+        DELETE FROM memory_chunks_fts;
+        DELETE FROM memory_chunks;
+        DELETE FROM memory_files;
+        CREATE VIRTUAL TABLE memory_vectors USING vec0(
+            chunk_id INTEGER PRIMARY KEY,
+            embedding FLOAT[{}]
+        );
+        "#,
+        dim
+    ))?;
+    db.execute(
+        "INSERT OR REPLACE INTO memory_meta (key, value) VALUES ('embedding_model', ?)",
+        [code],
+    )?;
+
+    Ok(())
+}
+
+/// Candidates fetched per result asked for, before ranking
+const CANDIDATES_PER_RESULT: usize = 4;
+
+/// Recency boost halves for every this many days since a file changed
+const RECENCY_HALF_LIFE_DAYS: f32 = 30.0;
+
+/// Columns read into a `Candidate`; `?1` is the query embedding, `?4` the shared channel
+const CANDIDATE_COLUMNS: &str = "c.id,
+    CASE WHEN f.channel = ?4 THEN 'shared/' || f.path ELSE f.path END,
+    c.content,
+    f.updated_at,
+    vec_distance_cosine(v.embedding, ?1) AS distance";
+
+/// A chunk that may be returned by a search
+#[derive(Debug)]
+struct Candidate {
+    path: String,
+    chunk: String,
+    updated_at: u64,
+    /// Cosine similarity to the query embedding
+    similarity: f32,
+    /// FTS5 bm25 rank when the chunk matched the query's words (lower is better)
+    bm25: Option<f64>,
+}
+
+impl Candidate {
+    fn from_row(row: &rusqlite::Row, bm25: Option<f64>) -> rusqlite::Result<(i64, Self)> {
+        Ok((
+            row.get(0)?,
+            Self {
+                path: row.get(1)?,
+                chunk: row.get(2)?,
+                updated_at: row.get::<_, i64>(3)? as u64,
+                similarity: 1.0 - row.get::<_, f32>(4)?, // Convert distance to similarity
+                bm25,
+            },
+        ))
+    }
+}
+
+/// Score candidates and sort them best first
+fn rank(candidates: Vec<Candidate>, settings: &MemoryConfig, now: u64) -> Vec<MemorySearchResult> {
+    // bm25 ranks are negative; the best match gets the full keyword boost
+    let best_bm25 = candidates
+        .iter()
+        .filter_map(|c| c.bm25)
+        .fold(0.0_f64, f64::min);
+
+    let mut results: Vec<MemorySearchResult> = candidates
+        .into_iter()
+        .map(|c| {
+            let keyword = match c.bm25 {
+                Some(bm25) if best_bm25 < 0.0 => (bm25 / best_bm25) as f32,
+                _ => 0.0,
+            };
+            let age_days = now.saturating_sub(c.updated_at) as f32 / 86_400.0;
+            let recency = 0.5_f32.powf(age_days / RECENCY_HALF_LIFE_DAYS);
+
+            MemorySearchResult {
+                path: c.path,
+                chunk: c.chunk,
+                score: c.similarity
+                    + settings.keyword_weight * keyword
+                    + settings.recency_weight * recency,
+            }
+        })
+        .collect();
+
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+    results
+}
+
+/// Turn a message into an FTS5 query matching any of its words
+fn fts_query(text: &str) -> Option<String> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() > 2)
+        .map(|w| format!("\"{}\"", w.to_lowercase()))
+        .collect();
+
+    (!words.is_empty()).then(|| words.join(" OR "))
+}
+
+fn now_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// A chunk of text with line information
+struct TextChunk {
+    text: String,
+    start_line: usize,
+    end_line: usize,
+}
+
+/// Chunk text into smaller pieces for embedding
+/// Uses a simple approach: split by headers or paragraph breaks
+fn chunk_text(content: &str) -> Vec<TextChunk> {
+    let mut chunks = Vec::new();
+    let lines: Vec<&str> = content.lines().collect();
+
+    if lines.is_empty() {
+        return chunks;
+    }
+
+    let mut current_chunk = String::new();
+    let mut chunk_start = 0;
+    let mut in_code_block = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        // Track code blocks
+        if line.starts_with("```") {
+            in_code_block = !in_code_block;
+        }
+
+        // Start new chunk on headers (but not in code blocks)
+        let is_header = !in_code_block && line.starts_with('#');
+        let should_split = is_header && !current_chunk.is_empty();
+
+        if should_split {
+            chunks.push(TextChunk {
+                text: current_chunk.trim().to_string(),
+                start_line: chunk_start + 1,
+                end_line: i,
+            });
+            current_chunk = String::new();
+            chunk_start = i;
+        }
+
+        if !current_chunk.is_empty() {
+            current_chunk.push('\n');
+        }
+        current_chunk.push_str(line);
+
+        // Also split if chunk gets too long (roughly 500 tokens ~ 2000 chars)
+        if current_chunk.len() > 2000 && !in_code_block {
+            chunks.push(TextChunk {
+                text: current_chunk.trim().to_string(),
+                start_line: chunk_start + 1,
+                end_line: i + 1,
+            });
+            current_chunk = String::new();
+            chunk_start = i + 1;
+        }
+    }
+
+    // Don't forget the last chunk
+    if !current_chunk.trim().is_empty() {
+        chunks.push(TextChunk {
+            text: current_chunk.trim().to_string(),
+            start_line: chunk_start + 1,
+            end_line: lines.len(),
+        });
+    }
+
+    chunks
+}
+
+/// Simple MD5 hash for content comparison
+fn md5_hash(content: &str) -> u128 {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish() as u128
+}
+
+/// Convert f32 embedding to bytes for sqlite-vec
+fn embedding_to_bytes(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|f| f.to_le_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_text() {
+        let content = r#"# Title
+
+Some intro text.
+
+## Section 1
+
+Content for section 1.
+
+## Section 2
+
+Content for section 2.
+"#;
+
+        let chunks = chunk_text(content);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[0].text.contains("Title"));
+        assert!(chunks[1].text.contains("Section 1"));
+        assert!(chunks[2].text.contains("Section 2"));
+    }
+
+    #[test]
+    fn test_model_change_rebuilds_index() {
+        ensure_sqlite_vec_init();
+        let db = Connection::open_in_memory().unwrap();
+        init_schema(&db).unwrap();
+        let file_count = |db: &Connection| -> i64 {
+            db.query_row("SELECT COUNT(*) FROM memory_files", [], |row| row.get(0))
+                .unwrap()
+        };
+
+        prepare_vectors(&db, "model-a", 384).unwrap();
+        db.execute(
+            "INSERT INTO memory_files (channel, user_id, path, hash, updated_at)
+             VALUES ('telegram', '1', 'notes.md', 'x', 0)",
+            [],
+        )
+        .unwrap();
+
+        prepare_vectors(&db, "model-a", 384).unwrap();
+        assert_eq!(file_count(&db), 1);
+
+        prepare_vectors(&db, "model-b", 768).unwrap();
+        assert_eq!(file_count(&db), 0);
+        let model: String = db
+            .query_row(
+                "SELECT value FROM memory_meta WHERE key = 'embedding_model'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(model, "model-b");
+    }
+
+    #[test]
+    fn test_rank_boosts_keyword_matches_and_recent_files() {
+        let now = 100 * 86_400;
+        let candidate = |path: &str, similarity: f32, bm25: Option<f64>, age_days: u64| Candidate {
+            path: path.to_string(),
+            chunk: String::new(),
+            updated_at: now - age_days * 86_400,
+            similarity,
+            bm25,
+        };
+        let settings = MemoryConfig::default();
+
+        let results = rank(
+            vec![
+                candidate("similar.md", 0.5, None, 90),
+                candidate("keyword.md", 0.45, Some(-3.0), 90),
+                candidate("recent.md", 0.45, None, 0),
+                candidate("weak-keyword.md", 0.3, Some(-1.0), 90),
+            ],
+            &settings,
+            now,
+        );
+
+        let paths: Vec<_> = results.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["keyword.md", "similar.md", "recent.md", "weak-keyword.md"]
+        );
+        assert!((results[2].score - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(
+            fts_query("What's Anna's wifi password?").as_deref(),
+            Some(r#""what" OR "anna" OR "wifi" OR "password""#)
+        );
+        assert_eq!(fts_query("ok?"), None);
+    }
+}
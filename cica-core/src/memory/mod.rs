@@ -0,0 +1,129 @@
+//! Memory system for storing and retrieving user memories using vector search.
+//!
+//! Memories are stored as markdown files in users/{channel}_{user_id}/memories/,
+//! or memory/shared/ for memories every user can find (household info, project
+//! notes), and indexed in a SQLite database with vector embeddings for semantic search.
+//! Builds without the `memory` feature keep the files but skip the index.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::onboarding::user_dir;
+
+#[cfg(feature = "memory")]
+mod index;
+
+#[cfg(feature = "memory")]
+pub use index::{MemoryIndex, ensure_model_downloaded};
+
+/// Get the memories directory for a user
+pub fn memories_dir(channel: &str, user_id: &str) -> Result<PathBuf> {
+    Ok(user_dir(channel, user_id)?.join("memories"))
+}
+
+/// Get the directory of memories shared by all users
+pub fn shared_memories_dir() -> Result<PathBuf> {
+    Ok(crate::config::paths()?.memory_dir.join("shared"))
+}
+
+/// Number of memory files a user has, including ones in subdirectories
+pub fn count_user_memories(channel: &str, user_id: &str) -> Result<usize> {
+    let dir = memories_dir(channel, user_id)?;
+    if !dir.exists() {
+        return Ok(0);
+    }
+    let mut files = Vec::new();
+    markdown_files(&dir, &mut files)?;
+    Ok(files.len())
+}
+
+/// A user's memory files, sorted by path
+pub fn user_memory_files(channel: &str, user_id: &str) -> Result<Vec<PathBuf>> {
+    let dir = memories_dir(channel, user_id)?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    markdown_files(&dir, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+/// A user's memory files changed since `since`
+pub fn recent_user_memories(
+    channel: &str,
+    user_id: &str,
+    since: SystemTime,
+) -> Result<Vec<PathBuf>> {
+    let dir = memories_dir(channel, user_id)?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    markdown_files(&dir, &mut files)?;
+    files.retain(|path| {
+        std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .is_ok_and(|modified| modified >= since)
+    });
+    files.sort();
+    Ok(files)
+}
+
+/// Collect the .md files under `dir`, recursively
+fn markdown_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)?.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            markdown_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "md") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Memory search result
+#[derive(Debug, Clone)]
+pub struct MemorySearchResult {
+    pub path: String,
+    pub chunk: String,
+    pub score: f32,
+}
+
+/// Stand-in index for builds without the `memory` feature: nothing is
+/// indexed and searches find nothing.
+#[cfg(not(feature = "memory"))]
+pub struct MemoryIndex;
+
+#[cfg(not(feature = "memory"))]
+impl MemoryIndex {
+    pub fn open() -> Result<Self> {
+        Ok(Self)
+    }
+
+    pub fn index_user_memories(&mut self, _channel: &str, _user_id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn index_shared_memories(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn search(
+        &self,
+        _channel: &str,
+        _user_id: &str,
+        _query: &str,
+        _limit: usize,
+    ) -> Result<Vec<MemorySearchResult>> {
+        Ok(Vec::new())
+    }
+}
+
+/// No embedding model to download without the `memory` feature
+#[cfg(not(feature = "memory"))]
+pub fn ensure_model_downloaded() -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,76 @@
+//! Usage counters kept in the database, shown by `cica status`.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use rusqlite::{Connection, params};
+use tracing::warn;
+
+use crate::storage;
+
+/// Add one to a counter. Failures are logged, not returned.
+pub fn increment(name: &str) {
+    if let Err(e) = storage::write(|conn| add(conn, name, 1)) {
+        warn!("Failed to update counter {}: {}", name, e);
+    }
+}
+
+/// Add `amount` to a counter, e.g. milliseconds spent. Failures are logged,
+/// not returned.
+pub fn add_to(name: &str, amount: u64) {
+    if let Err(e) = storage::write(|conn| add(conn, name, amount)) {
+        warn!("Failed to update counter {}: {}", name, e);
+    }
+}
+
+/// Counters whose name starts with `prefix`, keyed by the rest of the name
+pub fn counters(prefix: &str) -> Result<BTreeMap<String, u64>> {
+    storage::read(|conn| read_counters(conn, prefix))
+}
+
+fn add(conn: &Connection, name: &str, amount: u64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO counters (name, value) VALUES (?1, ?2)
+         ON CONFLICT(name) DO UPDATE SET value = value + excluded.value",
+        params![name, amount as i64],
+    )?;
+    Ok(())
+}
+
+fn read_counters(conn: &Connection, prefix: &str) -> Result<BTreeMap<String, u64>> {
+    let mut stmt = conn.prepare("SELECT name, value FROM counters")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+    })?;
+
+    let mut counters = BTreeMap::new();
+    for row in rows {
+        let (name, value) = row?;
+        if let Some(rest) = name.strip_prefix(prefix) {
+            counters.insert(rest.to_string(), value as u64);
+        }
+    }
+
+    Ok(counters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters_by_prefix() {
+        let conn = Connection::open_in_memory().unwrap();
+        storage::init_schema(&conn).unwrap();
+
+        add(&conn, "unsupported.telegram.sticker", 1).unwrap();
+        add(&conn, "unsupported.telegram.sticker", 1).unwrap();
+        add(&conn, "unsupported.signal.video", 1).unwrap();
+        add(&conn, "other", 5).unwrap();
+
+        let counters = read_counters(&conn, "unsupported.").unwrap();
+        assert_eq!(counters.len(), 2);
+        assert_eq!(counters["telegram.sticker"], 2);
+        assert_eq!(counters["signal.video"], 1);
+    }
+}
@@ -0,0 +1,404 @@
+//! Optional HTTP endpoint for monitoring, set with `listen` under [monitor].
+//!
+//! - `/healthz` answers while the process is alive, with 503 when a channel
+//!   is down (crashed and waiting to restart), and a JSON body with each
+//!   channel's state. Errors stay in the logs.
+//! - `/metrics` serves the usage counters in the Prometheus text format.
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+use crate::metrics;
+
+/// Largest request head read; monitoring requests are tiny
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// Longest a client may take to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+static STARTED: OnceLock<Instant> = OnceLock::new();
+static CHANNELS: OnceLock<Mutex<BTreeMap<&'static str, ChannelState>>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ChannelState {
+    Up,
+    /// Stopped with an error and waiting to restart
+    Restarting,
+    /// Stopped because Cica is shutting down
+    Stopped,
+}
+
+impl ChannelState {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Up => "up",
+            Self::Restarting => "restarting",
+            Self::Stopped => "stopped",
+        }
+    }
+}
+
+/// A counter family: counters named `<prefix><label values, dot-separated>`
+struct Family {
+    prefix: &'static str,
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+}
+
+const FAMILIES: &[Family] = &[
+    Family {
+        prefix: "messages.",
+        name: "cica_messages_total",
+        help: "Messages received and handled",
+        labels: &["channel"],
+    },
+    Family {
+        prefix: "backend.queries.",
+        name: "cica_backend_queries_total",
+        help: "Queries sent to the AI backend",
+        labels: &["backend"],
+    },
+    Family {
+        prefix: "backend.errors.",
+        name: "cica_backend_errors_total",
+        help: "Queries to the AI backend that failed",
+        labels: &["backend"],
+    },
+    Family {
+        prefix: "cron.runs.",
+        name: "cica_cron_runs_total",
+        help: "Cron job runs by result",
+        labels: &["status"],
+    },
+    Family {
+        prefix: "channel.restarts.",
+        name: "cica_channel_restarts_total",
+        help: "Times a channel stopped and was restarted",
+        labels: &["channel"],
+    },
+    Family {
+        prefix: "unsupported.",
+        name: "cica_unsupported_messages_total",
+        help: "Messages with content Cica can't handle",
+        labels: &["channel", "kind"],
+    },
+];
+
+/// Record that a channel is running
+pub fn channel_up(channel: &'static str) {
+    set_channel(channel, ChannelState::Up);
+}
+
+/// Record that a channel crashed and is waiting to restart
+pub fn channel_down(channel: &'static str) {
+    set_channel(channel, ChannelState::Restarting);
+}
+
+/// Record that a channel stopped as Cica shuts down
+pub fn channel_stopped(channel: &'static str) {
+    set_channel(channel, ChannelState::Stopped);
+}
+
+/// Forget a channel that was removed from the config
+pub fn channel_removed(channel: &'static str) {
+    if let Ok(mut channels) = channels().lock() {
+        channels.remove(channel);
+    }
+}
+
+fn set_channel(channel: &'static str, state: ChannelState) {
+    if let Ok(mut channels) = channels().lock() {
+        channels.insert(channel, state);
+    }
+}
+
+fn channels() -> &'static Mutex<BTreeMap<&'static str, ChannelState>> {
+    CHANNELS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+fn channel_states() -> BTreeMap<&'static str, ChannelState> {
+    channels().lock().map(|c| c.clone()).unwrap_or_default()
+}
+
+/// Start counting uptime; called as Cica starts
+pub fn mark_started() {
+    STARTED.get_or_init(Instant::now);
+}
+
+/// How long Cica has been running
+pub fn uptime() -> Duration {
+    STARTED.get_or_init(Instant::now).elapsed()
+}
+
+fn uptime_secs() -> u64 {
+    uptime().as_secs()
+}
+
+/// Serve /healthz and /metrics on `listen` (e.g. "127.0.0.1:9464") until the
+/// process exits.
+pub async fn serve(listen: &str) -> Result<()> {
+    mark_started();
+
+    let addr: SocketAddr = listen
+        .parse()
+        .with_context(|| format!("Invalid monitor address: {}", listen))?;
+    if !addr.ip().is_loopback() {
+        warn!(
+            "The monitoring endpoint listens on {}, which other hosts can reach",
+            addr
+        );
+    }
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Couldn't listen on {}", addr))?;
+    info!(
+        "Monitoring endpoint on http://{}/healthz and /metrics",
+        addr
+    );
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        tokio::spawn(async move {
+            if let Err(e) = handle(stream).await {
+                debug!("Monitoring request from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+async fn handle(mut stream: TcpStream) -> Result<()> {
+    let request = tokio::time::timeout(READ_TIMEOUT, read_request(&mut stream))
+        .await
+        .context("Timed out reading the request")??;
+    let request = String::from_utf8_lossy(&request);
+    let mut parts = request.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path = path.split('?').next().unwrap_or("");
+
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/healthz") => {
+            let (healthy, body) = health(&channel_states(), uptime_secs());
+            let status = if healthy {
+                "200 OK"
+            } else {
+                "503 Service Unavailable"
+            };
+            (status, "application/json", body)
+        }
+        ("GET", "/metrics") => {
+            let counters = metrics::counters("").unwrap_or_else(|e| {
+                warn!("Failed to read counters for /metrics: {}", e);
+                BTreeMap::new()
+            });
+            (
+                "200 OK",
+                "text/plain; version=0.0.4",
+                prometheus(&counters, &channel_states(), uptime_secs()),
+            )
+        }
+        ("GET", _) => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method not allowed\n".to_string(),
+        ),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Read the request head, up to `MAX_REQUEST_BYTES`
+async fn read_request(stream: &mut TcpStream) -> Result<Vec<u8>> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST_BYTES {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    Ok(request)
+}
+
+/// Whether every channel is up, and the /healthz body
+fn health(channels: &BTreeMap<&'static str, ChannelState>, uptime: u64) -> (bool, String) {
+    let healthy = channels.values().all(|s| *s == ChannelState::Up);
+    let channels: serde_json::Map<String, serde_json::Value> = channels
+        .iter()
+        .map(|(name, state)| {
+            let value = json!({ "up": *state == ChannelState::Up, "state": state.name() });
+            (name.to_string(), value)
+        })
+        .collect();
+    let body = json!({
+        "status": if healthy { "ok" } else { "degraded" },
+        "uptime_seconds": uptime,
+        "channels": channels,
+    });
+    (healthy, format!("{}\n", body))
+}
+
+/// The counters, channel states and uptime in the Prometheus text format
+fn prometheus(
+    counters: &BTreeMap<String, u64>,
+    channels: &BTreeMap<&'static str, ChannelState>,
+    uptime: u64,
+) -> String {
+    let mut out = String::new();
+    let header = |out: &mut String, name: &str, kind: &str, help: &str| {
+        out.push_str(&format!(
+            "# HELP {} {}\n# TYPE {} {}\n",
+            name, help, name, kind
+        ));
+    };
+
+    header(
+        &mut out,
+        "cica_uptime_seconds",
+        "gauge",
+        "Seconds since Cica started",
+    );
+    out.push_str(&format!("cica_uptime_seconds {}\n", uptime));
+
+    header(
+        &mut out,
+        "cica_channel_up",
+        "gauge",
+        "Whether a channel is running (1) or waiting to restart (0)",
+    );
+    for (name, state) in channels {
+        out.push_str(&format!(
+            "cica_channel_up{{channel=\"{}\"}} {}\n",
+            escape_label(name),
+            u8::from(*state == ChannelState::Up)
+        ));
+    }
+
+    for family in FAMILIES {
+        header(&mut out, family.name, "counter", family.help);
+        for (rest, value) in counters_with_prefix(counters, family.prefix) {
+            // The last label takes whatever dots are left
+            let values: Vec<&str> = rest.splitn(family.labels.len(), '.').collect();
+            if values.len() != family.labels.len() {
+                continue;
+            }
+            let labels: Vec<String> = family
+                .labels
+                .iter()
+                .zip(values)
+                .map(|(label, value)| format!("{}=\"{}\"", label, escape_label(value)))
+                .collect();
+            out.push_str(&format!(
+                "{}{{{}}} {}\n",
+                family.name,
+                labels.join(","),
+                value
+            ));
+        }
+    }
+
+    // Latency as a summary: total time, with the query count
+    header(
+        &mut out,
+        "cica_backend_query_seconds",
+        "summary",
+        "Time spent on AI backend queries, retries included",
+    );
+    for (backend, ms) in counters_with_prefix(counters, "backend.query_ms.") {
+        let count = counters
+            .get(&format!("backend.queries.{}", backend))
+            .copied()
+            .unwrap_or(0);
+        let backend = escape_label(backend);
+        out.push_str(&format!(
+            "cica_backend_query_seconds_sum{{backend=\"{}\"}} {:.3}\n\
+             cica_backend_query_seconds_count{{backend=\"{}\"}} {}\n",
+            backend,
+            ms as f64 / 1000.0,
+            backend,
+            count
+        ));
+    }
+
+    out
+}
+
+fn counters_with_prefix<'a>(
+    counters: &'a BTreeMap<String, u64>,
+    prefix: &'a str,
+) -> impl Iterator<Item = (&'a str, u64)> {
+    counters
+        .iter()
+        .filter_map(move |(name, value)| Some((name.strip_prefix(prefix)?, *value)))
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prometheus() {
+        let counters: BTreeMap<String, u64> = [
+            ("messages.telegram", 12),
+            ("backend.queries.claude", 4),
+            ("backend.errors.claude", 1),
+            ("backend.query_ms.claude", 10_500),
+            ("unsupported.signal.video", 2),
+            ("cron.runs.success", 3),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect();
+        let channels = BTreeMap::from([
+            ("telegram", ChannelState::Up),
+            ("signal", ChannelState::Restarting),
+        ]);
+
+        let text = prometheus(&counters, &channels, 60);
+        for line in [
+            "cica_uptime_seconds 60",
+            "cica_channel_up{channel=\"signal\"} 0",
+            "cica_channel_up{channel=\"telegram\"} 1",
+            "cica_messages_total{channel=\"telegram\"} 12",
+            "cica_backend_errors_total{backend=\"claude\"} 1",
+            "cica_unsupported_messages_total{channel=\"signal\",kind=\"video\"} 2",
+            "cica_cron_runs_total{status=\"success\"} 3",
+            "cica_backend_query_seconds_sum{backend=\"claude\"} 10.500",
+            "cica_backend_query_seconds_count{backend=\"claude\"} 4",
+            "# TYPE cica_messages_total counter",
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {}", line);
+        }
+
+        let (healthy, body) = health(&channels, 60);
+        assert!(!healthy);
+        assert!(body.contains("\"status\":\"degraded\""));
+        assert!(body.contains("\"signal\":{\"state\":\"restarting\",\"up\":false}"));
+    }
+}
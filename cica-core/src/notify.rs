@@ -0,0 +1,170 @@
+//! Owner notifications for system events (pairing requests, channel crashes,
+//! cron failures, low disk space).
+
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+use tracing::{info, warn};
+
+use anyhow::Result;
+
+use crate::channels::{BoxFuture, ReplyAction};
+use crate::config::OwnerConfig;
+
+/// How often to check free disk space
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Warn the owner when free space drops below this
+const LOW_DISK_BYTES: u64 = 1024 * 1024 * 1024; // 1 GiB
+
+/// Delivers a message, with optional quick-reply buttons, to (channel, user_id)
+pub type NotifySender =
+    Arc<dyn Fn(String, String, String, Vec<ReplyAction>) -> BoxFuture<Result<()>> + Send + Sync>;
+
+/// Where notifications go, set once at startup
+struct Notifier {
+    owner: RwLock<Option<OwnerConfig>>,
+    sender: NotifySender,
+}
+
+static NOTIFIER: OnceLock<Notifier> = OnceLock::new();
+
+/// Route notifications through `sender`. Without an owner configured,
+/// owner notifications are only logged.
+pub fn init(owner: Option<OwnerConfig>, sender: NotifySender) {
+    match &owner {
+        Some(owner) => info!(
+            "Owner notifications go to {}:{}",
+            owner.channel, owner.user_id
+        ),
+        None => info!("No owner configured, system notifications are only logged"),
+    }
+    let _ = NOTIFIER.set(Notifier {
+        owner: RwLock::new(owner),
+        sender,
+    });
+}
+
+/// Change who receives owner notifications (e.g. the first user claimed the bot)
+pub fn set_owner(owner: OwnerConfig) {
+    if let Some(notifier) = NOTIFIER.get() {
+        info!(
+            "Owner notifications now go to {}:{}",
+            owner.channel, owner.user_id
+        );
+        *notifier.owner.write().unwrap() = Some(owner);
+    }
+}
+
+/// The current owner, if one is configured
+fn owner() -> Option<OwnerConfig> {
+    NOTIFIER.get().and_then(|n| n.owner.read().unwrap().clone())
+}
+
+/// Check whether a user is the configured owner
+pub fn is_owner(channel: &str, user_id: &str) -> bool {
+    owner().is_some_and(|owner| owner.channel == channel && owner.user_id == user_id)
+}
+
+/// Send a system notification to the owner in the background.
+/// Failures are logged; callers never wait on delivery.
+pub fn notify_owner(message: impl Into<String>) {
+    notify_owner_with_actions(message, Vec::new());
+}
+
+/// Like [`notify_owner`], with quick-reply buttons where the channel supports them
+pub fn notify_owner_with_actions(message: impl Into<String>, actions: Vec<ReplyAction>) {
+    let message = message.into();
+    info!("Owner notification: {}", message);
+
+    if let Some(owner) = owner() {
+        deliver(
+            &owner.channel,
+            &owner.user_id,
+            format!("[Cica] {}", message),
+            actions,
+        );
+    }
+}
+
+/// Send a message to any user in the background (e.g. "you've been approved")
+pub fn send_to_user(channel: &str, user_id: &str, message: impl Into<String>) {
+    deliver(channel, user_id, message.into(), Vec::new());
+}
+
+fn deliver(channel: &str, user_id: &str, message: String, actions: Vec<ReplyAction>) {
+    let Some(notifier) = NOTIFIER.get() else {
+        return;
+    };
+
+    let send = (notifier.sender)(channel.to_string(), user_id.to_string(), message, actions);
+
+    tokio::spawn(async move {
+        if let Err(e) = send.await {
+            warn!("Failed to send notification: {}", e);
+        }
+    });
+}
+
+/// Periodically check free space on the disk holding `path`, notifying the
+/// owner once when it runs low and again only after it has recovered.
+pub async fn watch_disk_space(path: &Path) {
+    let mut warned = false;
+
+    loop {
+        match free_disk_bytes(path).await {
+            Ok(free) if free < LOW_DISK_BYTES => {
+                if !warned {
+                    notify_owner(format!(
+                        "Disk space is running low: {} MB free on the disk holding {}.",
+                        free / (1024 * 1024),
+                        path.display()
+                    ));
+                    warned = true;
+                }
+            }
+            Ok(_) => warned = false,
+            Err(e) => warn!("Failed to check free disk space: {}", e),
+        }
+
+        tokio::time::sleep(DISK_CHECK_INTERVAL).await;
+    }
+}
+
+/// Free bytes on the filesystem holding `path`, as reported by `df`
+async fn free_disk_bytes(path: &Path) -> Result<u64> {
+    let output = tokio::process::Command::new("df")
+        .arg("-Pk")
+        .arg(path)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        anyhow::bail!("df exited with {}", output.status);
+    }
+
+    parse_df_available(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| anyhow::anyhow!("Unexpected df output"))
+}
+
+/// Parse the available space (in bytes) from POSIX `df -Pk` output
+fn parse_df_available(output: &str) -> Option<u64> {
+    let line = output.lines().nth(1)?;
+    let kilobytes: u64 = line.split_whitespace().nth(3)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_df_available() {
+        let output = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                      /dev/sda1        102400000  51200000  51200000      50% /\n";
+        assert_eq!(parse_df_available(output), Some(51200000 * 1024));
+        assert_eq!(parse_df_available("Filesystem\n"), None);
+        assert_eq!(parse_df_available(""), None);
+    }
+}
@@ -0,0 +1,1330 @@
+use anyhow::{Result, anyhow};
+use chrono::NaiveTime;
+use rusqlite::Connection;
+use rusqlite::types::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use crate::config::{self, AiBackend};
+use crate::onboarding;
+use crate::quiet::QuietHours;
+use crate::storage::{self, Table};
+
+/// How long expired requests are remembered, so late approvals and
+/// returning users can be told the code expired
+const EXPIRED_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Characters used for code generation (no ambiguous chars: 0/O, 1/I)
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_LENGTH: usize = 8;
+
+/// Most times a silenced sender's code lifetime is doubled
+const MAX_BACKOFF_DOUBLINGS: u32 = 6;
+
+/// A pending pairing request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingRequest {
+    pub code: String,
+    pub channel: String,
+    pub user_id: String,
+    pub username: Option<String>,
+    pub display_name: Option<String>,
+    pub created_at: u64, // Unix timestamp
+    #[serde(default)]
+    pub prompts: u32, // Pairing prompts sent for this code
+    #[serde(default)]
+    pub silenced: u32, // Earlier codes in a row the sender was silenced on
+    #[serde(default)]
+    pub denied: bool, // The owner denied it with /deny
+}
+
+impl PendingRequest {
+    /// How long the code stays pending; doubles for each earlier silenced code
+    fn lifetime(&self, ttl: Duration) -> Duration {
+        ttl.saturating_mul(1 << self.silenced.min(MAX_BACKOFF_DOUBLINGS))
+    }
+}
+
+/// What an approved user is allowed to do (see `RolesConfig` for per-role permissions)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Owner,
+    #[default]
+    Member,
+    Guest,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Owner => "owner",
+            Role::Member => "member",
+            Role::Guest => "guest",
+        }
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "owner" => Ok(Role::Owner),
+            "member" => Ok(Role::Member),
+            "guest" => Ok(Role::Guest),
+            _ => Err(anyhow!(
+                "Unknown role: {} (expected owner, member or guest)",
+                s
+            )),
+        }
+    }
+}
+
+/// Per-user profile data
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct UserProfile {
+    pub name: Option<String>,
+    pub pronouns: Option<String>,
+    pub location: Option<String>,
+    pub timezone: Option<String>,
+    pub notes: Option<String>,
+    /// Set once onboarding finishes; the onboarding files still decide
+    #[serde(default)]
+    pub onboarding_complete: bool,
+    /// List the tools used under each reply (/verbose)
+    #[serde(default)]
+    pub verbose: bool,
+    /// Language code to answer in (/language), e.g. "hu"
+    #[serde(default)]
+    pub language: Option<String>,
+    /// When cron results and notifications are held (/dnd)
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+    /// Local time the daily digest goes out at (/digest)
+    #[serde(default)]
+    pub daily_digest: Option<NaiveTime>,
+    /// When the last daily digest went out (unix secs)
+    #[serde(default)]
+    pub last_digest: Option<u64>,
+}
+
+/// An approved user, as listed by `cica users list`
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovedUser {
+    pub channel: String,
+    pub user_id: String,
+    pub role: Role,
+    pub session_id: Option<String>,
+    pub onboarded: bool,
+}
+
+/// What `PairingStore::prune_orphans` removed, as "channel:user_id" keys
+#[derive(Debug, Default, PartialEq)]
+pub struct PruneReport {
+    pub expired_requests: usize,
+    pub sessions: Vec<String>,
+    pub profiles: Vec<String>,
+    pub roles: Vec<String>,
+}
+
+impl PruneReport {
+    pub fn is_empty(&self) -> bool {
+        self.expired_requests == 0
+            && self.sessions.is_empty()
+            && self.profiles.is_empty()
+            && self.roles.is_empty()
+    }
+}
+
+/// Storage for all pairing data
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PairingStore {
+    pub pending: Vec<PendingRequest>,
+    pub approved: HashMap<String, Vec<String>>, // channel -> [user_ids]
+    #[serde(default)]
+    pub sessions: HashMap<String, String>, // "channel:user_id" -> session_id (UUID)
+    #[serde(default)]
+    pub session_backends: HashMap<String, AiBackend>, // "channel:user_id" -> backend of the session
+    #[serde(default)]
+    pub session_started: HashMap<String, u64>, // "channel:user_id" -> when the session began (unix secs)
+    #[serde(default)]
+    pub user_profiles: HashMap<String, UserProfile>, // "channel:user_id" -> profile
+    #[serde(default)]
+    pub roles: HashMap<String, Role>, // "channel:user_id" -> role (default: member)
+    #[serde(default)]
+    pub blocked: HashMap<String, Vec<String>>, // channel -> [user_ids]
+    #[serde(default)]
+    pub expired: Vec<PendingRequest>, // Recently expired requests
+}
+
+impl PairingStore {
+    /// Load pairing store from the database
+    pub fn load() -> Result<Self> {
+        storage::read(Self::read_from)
+    }
+
+    /// Apply a change to the latest state in the database and save it.
+    ///
+    /// Runs in a write transaction: `self` is reloaded first, so changes made
+    /// by other tasks since this copy was loaded are kept. Nothing is written
+    /// if `f` fails.
+    pub fn update<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        storage::write(|conn| {
+            *self = Self::read_from(conn)?;
+            let before = self.clone();
+            let result = f(self)?;
+            self.write_changes(&before, conn)?;
+            Ok(result)
+        })
+    }
+
+    /// Read the whole store from the database
+    pub(crate) fn read_from(conn: &Connection) -> Result<Self> {
+        let mut store = Self::default();
+
+        let mut stmt = conn.prepare(
+            "SELECT code, channel, user_id, username, display_name, created_at, prompts, expired,
+                    silenced, denied
+             FROM pairing_requests ORDER BY rowid",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let request = PendingRequest {
+                code: row.get(0)?,
+                channel: row.get(1)?,
+                user_id: row.get(2)?,
+                username: row.get(3)?,
+                display_name: row.get(4)?,
+                created_at: row.get::<_, i64>(5)? as u64,
+                prompts: row.get(6)?,
+                silenced: row.get(8)?,
+                denied: row.get(9)?,
+            };
+            Ok((request, row.get::<_, bool>(7)?))
+        })?;
+        for row in rows {
+            let (request, expired) = row?;
+            if expired {
+                store.expired.push(request);
+            } else {
+                store.pending.push(request);
+            }
+        }
+
+        for (table, users) in [
+            ("approved_users", &mut store.approved),
+            ("blocked_users", &mut store.blocked),
+        ] {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT channel, user_id FROM {} ORDER BY rowid",
+                table
+            ))?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            for row in rows {
+                let (channel, user_id): (String, String) = row?;
+                users.entry(channel).or_default().push(user_id);
+            }
+        }
+
+        for (key, session_id) in read_user_values(conn, "sessions", "session_id")? {
+            store.sessions.insert(key, session_id);
+        }
+        for (key, backend) in read_user_values::<Option<String>>(conn, "sessions", "backend")? {
+            if let Some(backend) = backend {
+                store.session_backends.insert(key, backend.parse()?);
+            }
+        }
+        for (key, started) in read_user_values::<Option<i64>>(conn, "sessions", "started_at")? {
+            if let Some(started) = started {
+                store.session_started.insert(key, started as u64);
+            }
+        }
+        for (key, role) in read_user_values::<String>(conn, "roles", "role")? {
+            store.roles.insert(key, role.parse()?);
+        }
+        for (key, profile) in read_user_values::<String>(conn, "user_profiles", "profile")? {
+            store
+                .user_profiles
+                .insert(key, serde_json::from_str(&profile)?);
+        }
+
+        Ok(store)
+    }
+
+    /// Replace the stored state with this store
+    pub(crate) fn write_to(&self, conn: &Connection) -> Result<()> {
+        self.write_changes(&Self::read_from(conn)?, conn)
+    }
+
+    /// Save what changed since `before` was read, row by row
+    fn write_changes(&self, before: &Self, conn: &Connection) -> Result<()> {
+        storage::write_changes(conn, &before.tables()?, &self.tables()?)?;
+        Ok(())
+    }
+
+    /// The store as database rows
+    fn tables(&self) -> Result<[Table; 6]> {
+        let user = |key: &str| {
+            let (channel, user_id) = storage::split_user_key(key);
+            [
+                Value::from(channel.to_string()),
+                Value::from(user_id.to_string()),
+            ]
+        };
+
+        let mut requests = Table::new(
+            "pairing_requests",
+            &["code", "channel", "user_id", "expired"],
+            &[
+                "username",
+                "display_name",
+                "created_at",
+                "prompts",
+                "silenced",
+                "denied",
+            ],
+        );
+        for (request, expired) in self
+            .pending
+            .iter()
+            .map(|r| (r, false))
+            .chain(self.expired.iter().map(|r| (r, true)))
+        {
+            requests.push(vec![
+                request.code.clone().into(),
+                request.channel.clone().into(),
+                request.user_id.clone().into(),
+                expired.into(),
+                request.username.clone().into(),
+                request.display_name.clone().into(),
+                (request.created_at as i64).into(),
+                request.prompts.into(),
+                request.silenced.into(),
+                request.denied.into(),
+            ]);
+        }
+
+        let users = |name, users: &HashMap<String, Vec<String>>| {
+            let mut table = Table::new(name, &["channel", "user_id"], &[]);
+            for (channel, ids) in users {
+                for user_id in ids {
+                    table.push(vec![channel.clone().into(), user_id.clone().into()]);
+                }
+            }
+            table
+        };
+
+        let mut sessions = Table::new(
+            "sessions",
+            &["channel", "user_id"],
+            &["session_id", "backend", "started_at"],
+        );
+        for (key, session_id) in &self.sessions {
+            let backend = self
+                .session_backends
+                .get(key)
+                .map(|b| b.as_str().to_string());
+            let started = self.session_started.get(key).map(|&t| t as i64);
+            let mut row = user(key).to_vec();
+            row.extend([session_id.clone().into(), backend.into(), started.into()]);
+            sessions.push(row);
+        }
+
+        let mut roles = Table::new("roles", &["channel", "user_id"], &["role"]);
+        for (key, role) in &self.roles {
+            let mut row = user(key).to_vec();
+            row.push(role.as_str().to_string().into());
+            roles.push(row);
+        }
+
+        let mut profiles = Table::new("user_profiles", &["channel", "user_id"], &["profile"]);
+        for (key, profile) in &self.user_profiles {
+            let mut row = user(key).to_vec();
+            row.push(serde_json::to_string(profile)?.into());
+            profiles.push(row);
+        }
+
+        Ok([
+            requests,
+            users("approved_users", &self.approved),
+            users("blocked_users", &self.blocked),
+            sessions,
+            roles,
+            profiles,
+        ])
+    }
+
+    /// Move expired pending requests aside, forgetting them after a while
+    pub fn prune_expired(&mut self) {
+        self.prune_expired_at(now_timestamp(), code_ttl());
+    }
+
+    fn prune_expired_at(&mut self, now: u64, ttl: Duration) {
+        let age = |req: &PendingRequest| now.saturating_sub(req.created_at);
+
+        let (expired, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|req| age(req) >= req.lifetime(ttl).as_secs());
+        self.pending = pending;
+        self.expired.extend(expired);
+
+        self.expired
+            .retain(|req| age(req) < (req.lifetime(ttl) + EXPIRED_RETENTION).as_secs());
+    }
+
+    /// Find a recently expired request by code
+    pub fn find_expired(&self, code: &str) -> Option<&PendingRequest> {
+        let code_upper = code.to_uppercase();
+        self.expired.iter().find(|r| r.code == code_upper)
+    }
+
+    /// Forget a user's expired requests.
+    /// Returns the most recent one, if any, so they can be told it expired.
+    pub fn take_expired(&mut self, channel: &str, user_id: &str) -> Result<Option<PendingRequest>> {
+        if !self
+            .expired
+            .iter()
+            .any(|r| r.channel == channel && r.user_id == user_id)
+        {
+            return Ok(None);
+        }
+
+        self.update(|store| {
+            let (theirs, others): (Vec<_>, Vec<_>) = std::mem::take(&mut store.expired)
+                .into_iter()
+                .partition(|r| r.channel == channel && r.user_id == user_id);
+            store.expired = others;
+
+            Ok(theirs.into_iter().max_by_key(|r| r.created_at))
+        })
+    }
+
+    /// Replace an expired request with a fresh pending one under a new code
+    pub fn renew_expired(&mut self, code: &str) -> Result<PendingRequest> {
+        self.update(|store| {
+            store.prune_expired();
+
+            let code_upper = code.to_uppercase();
+            let idx = store
+                .expired
+                .iter()
+                .position(|r| r.code == code_upper)
+                .ok_or_else(|| anyhow!("No expired request found for code: {}", code))?;
+
+            let mut request = store.expired.remove(idx);
+            store
+                .expired
+                .retain(|r| r.channel != request.channel || r.user_id != request.user_id);
+            store
+                .pending
+                .retain(|r| r.channel != request.channel || r.user_id != request.user_id);
+
+            request.code = generate_unique_code(&store.pending)?;
+            request.created_at = now_timestamp();
+            request.prompts = 0;
+            store.pending.push(request.clone());
+
+            Ok(request)
+        })
+    }
+
+    /// Drop expired requests, and sessions, profiles and roles of users who are
+    /// no longer approved. Does not save; the caller decides whether to persist.
+    pub fn prune_orphans(&mut self) -> PruneReport {
+        let pending_before = self.pending.len();
+        self.prune_expired();
+
+        let approved = |key: &str| {
+            key.split_once(':')
+                .is_some_and(|(channel, user_id)| self.is_approved(channel, user_id))
+        };
+        let orphans = |keys: Vec<&String>| -> Vec<String> {
+            let mut orphans: Vec<String> = keys
+                .into_iter()
+                .filter(|key| !approved(key))
+                .cloned()
+                .collect();
+            orphans.sort();
+            orphans
+        };
+
+        let report = PruneReport {
+            expired_requests: pending_before - self.pending.len(),
+            sessions: orphans(self.sessions.keys().collect()),
+            profiles: orphans(self.user_profiles.keys().collect()),
+            roles: orphans(self.roles.keys().collect()),
+        };
+
+        for key in &report.sessions {
+            self.sessions.remove(key);
+        }
+        for key in &report.profiles {
+            self.user_profiles.remove(key);
+        }
+        for key in &report.roles {
+            self.roles.remove(key);
+        }
+
+        report
+    }
+
+    /// Check if a user is approved for a channel
+    pub fn is_approved(&self, channel: &str, user_id: &str) -> bool {
+        self.approved
+            .get(channel)
+            .map(|ids| ids.contains(&user_id.to_string()))
+            .unwrap_or(false)
+    }
+
+    /// Get or create a pending request for a user
+    /// Returns (code, is_new)
+    pub fn get_or_create_pending(
+        &mut self,
+        channel: &str,
+        user_id: &str,
+        username: Option<String>,
+        display_name: Option<String>,
+        max_prompts: u32,
+    ) -> Result<(String, bool)> {
+        self.update(|store| {
+            store.prune_expired();
+
+            // Check if already has pending request
+            if let Some(existing) = store
+                .pending
+                .iter()
+                .find(|r| r.channel == channel && r.user_id == user_id)
+            {
+                return Ok((existing.code.clone(), false));
+            }
+
+            // Generate new code
+            let code = generate_unique_code(&store.pending)?;
+            let silenced = store.silenced_before(channel, user_id, max_prompts);
+
+            store.pending.push(PendingRequest {
+                code: code.clone(),
+                channel: channel.to_string(),
+                user_id: user_id.to_string(),
+                username,
+                display_name,
+                created_at: now_timestamp(),
+                prompts: 0,
+                silenced,
+                denied: false,
+            });
+
+            Ok((code, true))
+        })
+    }
+
+    /// How many codes in a row a user was silenced or denied on, counting
+    /// their last expired one. Zero once they let a code expire without being
+    /// silenced.
+    fn silenced_before(&self, channel: &str, user_id: &str, max_prompts: u32) -> u32 {
+        self.expired
+            .iter()
+            .filter(|r| r.channel == channel && r.user_id == user_id)
+            .max_by_key(|r| r.created_at)
+            .filter(|r| r.denied || (max_prompts > 0 && r.prompts >= max_prompts))
+            .map_or(0, |r| r.silenced + 1)
+    }
+
+    /// Whether the owner denied a user's request recently enough that they
+    /// still get no new code. The wait doubles like a silenced code's lifetime.
+    pub fn is_denied(&self, channel: &str, user_id: &str) -> bool {
+        self.is_denied_at(channel, user_id, now_timestamp(), code_ttl())
+    }
+
+    fn is_denied_at(&self, channel: &str, user_id: &str, now: u64, ttl: Duration) -> bool {
+        self.expired.iter().any(|r| {
+            r.channel == channel
+                && r.user_id == user_id
+                && r.denied
+                && now.saturating_sub(r.created_at) < r.lifetime(ttl).as_secs()
+        })
+    }
+
+    /// Record a pairing prompt for a user's pending request.
+    /// Returns false once they've had `max_prompts` and should be ignored.
+    pub fn record_pairing_prompt(
+        &mut self,
+        channel: &str,
+        user_id: &str,
+        max_prompts: u32,
+    ) -> Result<bool> {
+        self.update(|store| Ok(store.count_prompt(channel, user_id, max_prompts)))
+    }
+
+    fn count_prompt(&mut self, channel: &str, user_id: &str, max_prompts: u32) -> bool {
+        let Some(request) = self
+            .pending
+            .iter_mut()
+            .find(|r| r.channel == channel && r.user_id == user_id)
+        else {
+            return true;
+        };
+
+        if max_prompts > 0 && request.prompts >= max_prompts {
+            return false;
+        }
+
+        request.prompts += 1;
+        true
+    }
+
+    /// Approve a pending request by code
+    /// Returns the approved request details on success
+    pub fn approve(&mut self, code: &str) -> Result<PendingRequest> {
+        self.update(|store| {
+            let request = store.take_pending(code)?;
+
+            // Add to approved list
+            store
+                .approved
+                .entry(request.channel.clone())
+                .or_default()
+                .push(request.user_id.clone());
+
+            Ok(request)
+        })
+    }
+
+    /// Deny a pending request by code. The sender is silenced for a while
+    /// and their next code backs off further, as if they'd been silenced.
+    /// Returns the denied request details on success
+    pub fn deny(&mut self, code: &str) -> Result<PendingRequest> {
+        self.update(|store| {
+            let request = store.take_pending(code)?;
+            store.record_denial(request.clone(), now_timestamp());
+            Ok(request)
+        })
+    }
+
+    /// Keep a denied request among the expired ones, its wait starting now
+    fn record_denial(&mut self, mut request: PendingRequest, now: u64) {
+        request.created_at = now;
+        request.silenced += 1;
+        request.denied = true;
+        self.expired.push(request);
+    }
+
+    /// Remove and return the pending request for a code
+    fn take_pending(&mut self, code: &str) -> Result<PendingRequest> {
+        self.prune_expired();
+
+        let code_upper = code.to_uppercase();
+
+        let idx = self
+            .pending
+            .iter()
+            .position(|r| r.code == code_upper)
+            .ok_or_else(|| anyhow!("No pending request found for code: {}", code))?;
+
+        Ok(self.pending.remove(idx))
+    }
+
+    /// Automatically approve a user without requiring a pairing code
+    pub fn auto_approve(
+        &mut self,
+        channel: &str,
+        user_id: &str,
+        _username: Option<String>,
+        _display_name: Option<String>,
+    ) -> Result<()> {
+        self.update(|store| {
+            store
+                .approved
+                .entry(channel.to_string())
+                .or_default()
+                .push(user_id.to_string());
+            Ok(())
+        })
+    }
+
+    /// Remove all pairing data (pending, approved, sessions, profiles) for a channel
+    pub fn remove_channel(&mut self, channel: &str) -> Result<()> {
+        let prefix = format!("{}:", channel);
+
+        self.update(|store| {
+            store.pending.retain(|r| r.channel != channel);
+            store.expired.retain(|r| r.channel != channel);
+            store.approved.remove(channel);
+            store.sessions.retain(|key, _| !key.starts_with(&prefix));
+            store
+                .user_profiles
+                .retain(|key, _| !key.starts_with(&prefix));
+            store.roles.retain(|key, _| !key.starts_with(&prefix));
+            store.blocked.remove(channel);
+            Ok(())
+        })
+    }
+
+    /// Revoke an approved user and drop their session.
+    /// Returns true if the user was approved.
+    pub fn revoke(&mut self, channel: &str, user_id: &str) -> Result<bool> {
+        self.update(|store| Ok(store.remove_approval(channel, user_id)))
+    }
+
+    /// Remove a user's approval, session and role without saving.
+    /// Returns true if the user was approved.
+    fn remove_approval(&mut self, channel: &str, user_id: &str) -> bool {
+        let Some(ids) = self.approved.get_mut(channel) else {
+            return false;
+        };
+
+        let before = ids.len();
+        ids.retain(|id| id != user_id);
+        if ids.len() == before {
+            return false;
+        }
+        if ids.is_empty() {
+            self.approved.remove(channel);
+        }
+
+        let key = format!("{}:{}", channel, user_id);
+        self.sessions.remove(&key);
+        self.roles.remove(&key);
+
+        true
+    }
+
+    /// Check if a user is blocked on a channel
+    pub fn is_blocked(&self, channel: &str, user_id: &str) -> bool {
+        self.blocked
+            .get(channel)
+            .map(|ids| ids.iter().any(|id| id == user_id))
+            .unwrap_or(false)
+    }
+
+    /// Block a user: revoke any approval, drop their pending request and ignore
+    /// them from now on. Returns false if they were already blocked.
+    pub fn block(&mut self, channel: &str, user_id: &str) -> Result<bool> {
+        self.update(|store| {
+            if store.is_blocked(channel, user_id) {
+                return Ok(false);
+            }
+
+            store.remove_approval(channel, user_id);
+            store
+                .pending
+                .retain(|r| r.channel != channel || r.user_id != user_id);
+            store
+                .expired
+                .retain(|r| r.channel != channel || r.user_id != user_id);
+            store
+                .blocked
+                .entry(channel.to_string())
+                .or_default()
+                .push(user_id.to_string());
+
+            Ok(true)
+        })
+    }
+
+    /// Unblock a user. Returns false if they weren't blocked.
+    pub fn unblock(&mut self, channel: &str, user_id: &str) -> Result<bool> {
+        self.update(|store| {
+            let Some(ids) = store.blocked.get_mut(channel) else {
+                return Ok(false);
+            };
+
+            let before = ids.len();
+            ids.retain(|id| id != user_id);
+            if ids.len() == before {
+                return Ok(false);
+            }
+            if ids.is_empty() {
+                store.blocked.remove(channel);
+            }
+
+            Ok(true)
+        })
+    }
+
+    /// Get an approved user's role
+    pub fn role(&self, channel: &str, user_id: &str) -> Role {
+        let key = format!("{}:{}", channel, user_id);
+        self.roles.get(&key).copied().unwrap_or_default()
+    }
+
+    /// Set an approved user's role
+    pub fn set_role(&mut self, channel: &str, user_id: &str, role: Role) -> Result<()> {
+        self.update(|store| {
+            if !store.is_approved(channel, user_id) {
+                return Err(anyhow!("{} user {} is not approved", channel, user_id));
+            }
+
+            let key = format!("{}:{}", channel, user_id);
+            if role == Role::default() {
+                store.roles.remove(&key);
+            } else {
+                store.roles.insert(key, role);
+            }
+            Ok(())
+        })
+    }
+
+    /// Whether a user has the owner role, which owner-only commands need
+    pub fn is_owner(&self, channel: &str, user_id: &str) -> bool {
+        self.role(channel, user_id) == Role::Owner
+    }
+
+    /// Users with the owner role, as (channel, user_id), sorted
+    pub fn owners(&self) -> Vec<(String, String)> {
+        let mut owners: Vec<_> = self
+            .roles
+            .iter()
+            .filter(|(_, role)| **role == Role::Owner)
+            .map(|(key, _)| {
+                let (channel, user_id) = storage::split_user_key(key);
+                (channel.to_string(), user_id.to_string())
+            })
+            .collect();
+        owners.sort();
+        owners
+    }
+
+    /// List all pending requests
+    pub fn list_pending(&mut self) -> Vec<&PendingRequest> {
+        self.prune_expired();
+        self.pending.iter().collect()
+    }
+
+    /// List all approved users, sorted by channel and user ID
+    pub fn approved_users(&self) -> Vec<ApprovedUser> {
+        let mut users: Vec<ApprovedUser> = self
+            .approved
+            .iter()
+            .flat_map(|(channel, ids)| ids.iter().map(move |id| (channel, id)))
+            .map(|(channel, user_id)| ApprovedUser {
+                channel: channel.clone(),
+                user_id: user_id.clone(),
+                role: self.role(channel, user_id),
+                session_id: self
+                    .sessions
+                    .get(&format!("{}:{}", channel, user_id))
+                    .cloned(),
+                onboarded: onboarding::is_complete_for_user(channel, user_id).unwrap_or(false),
+            })
+            .collect();
+        users.sort_by(|a, b| (&a.channel, &a.user_id).cmp(&(&b.channel, &b.user_id)));
+        users
+    }
+
+    /// Get or create a session ID for a user
+    #[allow(dead_code)]
+    pub fn get_or_create_session(&mut self, channel: &str, user_id: &str) -> Result<String> {
+        let key = format!("{}:{}", channel, user_id);
+
+        if let Some(session_id) = self.sessions.get(&key) {
+            return Ok(session_id.clone());
+        }
+
+        // Generate a new UUID for the session
+        let session_id = generate_uuid();
+        let backend = config::Config::load()
+            .map(|c| c.backend)
+            .unwrap_or_default();
+        self.set_session(channel, user_id, &session_id, backend)?;
+
+        Ok(session_id)
+    }
+
+    /// Remember a user's session ID and the backend it runs on
+    pub fn set_session(
+        &mut self,
+        channel: &str,
+        user_id: &str,
+        session_id: &str,
+        backend: AiBackend,
+    ) -> Result<()> {
+        let key = format!("{}:{}", channel, user_id);
+        self.update(|store| {
+            let previous = store.sessions.insert(key.clone(), session_id.to_string());
+            if previous.as_deref() != Some(session_id) {
+                store.session_started.insert(key.clone(), now_timestamp());
+            }
+            store.session_backends.insert(key, backend);
+            Ok(())
+        })
+    }
+
+    /// When a user's current session began, if known (unix secs)
+    pub fn session_started(&self, channel: &str, user_id: &str) -> Option<u64> {
+        let key = format!("{}:{}", channel, user_id);
+        self.sessions
+            .contains_key(&key)
+            .then(|| self.session_started.get(&key).copied())
+            .flatten()
+    }
+
+    /// Reset a user's session (start fresh conversation)
+    pub fn reset_session(&mut self, channel: &str, user_id: &str) -> Result<()> {
+        let key = format!("{}:{}", channel, user_id);
+        self.update(|store| {
+            store.sessions.remove(&key);
+            store.session_backends.remove(&key);
+            store.session_started.remove(&key);
+            Ok(())
+        })
+    }
+
+    /// Get a user's profile
+    #[allow(dead_code)]
+    pub fn get_user_profile(&self, channel: &str, user_id: &str) -> Option<&UserProfile> {
+        let key = format!("{}:{}", channel, user_id);
+        self.user_profiles.get(&key)
+    }
+
+    /// Get or create a user's profile
+    #[allow(dead_code)]
+    pub fn get_or_create_user_profile(&mut self, channel: &str, user_id: &str) -> &mut UserProfile {
+        let key = format!("{}:{}", channel, user_id);
+        self.user_profiles.entry(key).or_default()
+    }
+
+    /// Update a user's profile
+    #[allow(dead_code)]
+    pub fn update_user_profile(
+        &mut self,
+        channel: &str,
+        user_id: &str,
+        profile: UserProfile,
+    ) -> Result<()> {
+        let key = format!("{}:{}", channel, user_id);
+        self.update(|store| {
+            store.user_profiles.insert(key, profile);
+            Ok(())
+        })
+    }
+
+    /// Record whether a user has finished onboarding
+    pub fn set_onboarded(&mut self, channel: &str, user_id: &str, complete: bool) -> Result<()> {
+        let key = format!("{}:{}", channel, user_id);
+        self.update(|store| {
+            match store.user_profiles.get_mut(&key) {
+                Some(profile) => profile.onboarding_complete = complete,
+                None if complete => {
+                    store.user_profiles.insert(
+                        key,
+                        UserProfile {
+                            onboarding_complete: true,
+                            ..Default::default()
+                        },
+                    );
+                }
+                None => {}
+            }
+            Ok(())
+        })
+    }
+
+    /// A user's language code, if set or detected
+    pub fn language(&self, channel: &str, user_id: &str) -> Option<&str> {
+        self.get_user_profile(channel, user_id)
+            .and_then(|p| p.language.as_deref())
+    }
+
+    /// A user's timezone, if set to a name chrono-tz knows (e.g. "Europe/Budapest")
+    pub fn timezone(&self, channel: &str, user_id: &str) -> Option<chrono_tz::Tz> {
+        self.get_user_profile(channel, user_id)
+            .and_then(|p| p.timezone.as_deref())
+            .and_then(|tz| tz.trim().parse().ok())
+    }
+
+    /// Set or clear a user's language code
+    pub fn set_language(
+        &mut self,
+        channel: &str,
+        user_id: &str,
+        language: Option<&str>,
+    ) -> Result<()> {
+        let key = format!("{}:{}", channel, user_id);
+        self.update(|store| {
+            store.user_profiles.entry(key).or_default().language = language.map(str::to_string);
+            Ok(())
+        })
+    }
+
+    /// A user's quiet hours, if they set any
+    pub fn quiet_hours(&self, channel: &str, user_id: &str) -> Option<QuietHours> {
+        self.get_user_profile(channel, user_id)
+            .and_then(|p| p.quiet_hours)
+    }
+
+    /// Set or clear a user's quiet hours
+    pub fn set_quiet_hours(
+        &mut self,
+        channel: &str,
+        user_id: &str,
+        quiet_hours: Option<QuietHours>,
+    ) -> Result<()> {
+        let key = format!("{}:{}", channel, user_id);
+        self.update(|store| {
+            store.user_profiles.entry(key).or_default().quiet_hours = quiet_hours;
+            Ok(())
+        })
+    }
+
+    /// When a user's daily digest goes out, if they turned it on
+    pub fn daily_digest(&self, channel: &str, user_id: &str) -> Option<NaiveTime> {
+        self.get_user_profile(channel, user_id)
+            .and_then(|p| p.daily_digest)
+    }
+
+    /// Set or turn off a user's daily digest. Counts as sent now, so one
+    /// set for earlier in the day starts tomorrow.
+    pub fn set_daily_digest(
+        &mut self,
+        channel: &str,
+        user_id: &str,
+        at: Option<NaiveTime>,
+    ) -> Result<()> {
+        let key = format!("{}:{}", channel, user_id);
+        self.update(|store| {
+            let profile = store.user_profiles.entry(key).or_default();
+            profile.daily_digest = at;
+            profile.last_digest = Some(now_timestamp());
+            Ok(())
+        })
+    }
+
+    /// Record that a user's daily digest went out at `at` (unix secs)
+    pub fn set_digest_sent(&mut self, channel: &str, user_id: &str, at: u64) -> Result<()> {
+        let key = format!("{}:{}", channel, user_id);
+        self.update(|store| {
+            store.user_profiles.entry(key).or_default().last_digest = Some(at);
+            Ok(())
+        })
+    }
+
+    /// Check if a user's onboarding is complete
+    pub fn is_user_onboarded(&self, channel: &str, user_id: &str) -> bool {
+        self.get_user_profile(channel, user_id)
+            .map(|p| p.onboarding_complete)
+            .unwrap_or(false)
+    }
+}
+
+/// How long pairing codes stay valid, from config
+pub fn code_ttl() -> Duration {
+    config::Config::load()
+        .map(|c| c.pairing.code_ttl())
+        .unwrap_or_else(|_| config::PairingConfig::default().code_ttl())
+}
+
+/// Read a per-user value column as ("channel:user_id", value) pairs
+fn read_user_values<T: rusqlite::types::FromSql>(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+) -> Result<Vec<(String, T)>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT channel, user_id, {} FROM {}",
+        column, table
+    ))?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            format!("{}:{}", row.get::<_, String>(0)?, row.get::<_, String>(1)?),
+            row.get(2)?,
+        ))
+    })?;
+
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+/// Generate a unique pairing code
+fn generate_unique_code(existing: &[PendingRequest]) -> Result<String> {
+    use std::collections::HashSet;
+
+    let existing_codes: HashSet<&str> = existing.iter().map(|r| r.code.as_str()).collect();
+
+    for _ in 0..100 {
+        let code = generate_code();
+        if !existing_codes.contains(code.as_str()) {
+            return Ok(code);
+        }
+    }
+
+    Err(anyhow!("Failed to generate unique code after 100 attempts"))
+}
+
+/// Generate a random code
+fn generate_code() -> String {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    // Simple randomness from system time + process id
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64
+        ^ std::process::id() as u64;
+
+    let mut rng = SimpleRng::new(seed);
+
+    (0..CODE_LENGTH)
+        .map(|_| {
+            let idx = rng.next() as usize % CODE_ALPHABET.len();
+            CODE_ALPHABET[idx] as char
+        })
+        .collect()
+}
+
+/// Simple PRNG for code generation (no external deps)
+struct SimpleRng(u64);
+
+impl SimpleRng {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        // xorshift64
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// Get current unix timestamp
+fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Generate a UUID v4 (random)
+#[allow(dead_code)]
+fn generate_uuid() -> String {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+
+    let mut rng = SimpleRng::new(now ^ std::process::id() as u64);
+
+    let bytes: Vec<u8> = (0..16).map(|_| rng.next() as u8).collect();
+
+    // Format as UUID with version 4 and variant bits
+    format!(
+        "{:02x}{:02x}{:02x}{:02x}-{:02x}{:02x}-4{:01x}{:02x}-{:01x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+        bytes[0],
+        bytes[1],
+        bytes[2],
+        bytes[3],
+        bytes[4],
+        bytes[5],
+        bytes[6] & 0x0f,
+        bytes[7],
+        (bytes[8] & 0x3f) | 0x80,
+        bytes[9],
+        bytes[10],
+        bytes[11],
+        bytes[12],
+        bytes[13],
+        bytes[14],
+        bytes[15]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prune_orphans() {
+        let mut store = PairingStore::default();
+        store
+            .approved
+            .insert("telegram".to_string(), vec!["1".to_string()]);
+        for key in ["telegram:1", "telegram:2", "slack:1"] {
+            store
+                .sessions
+                .insert(key.to_string(), "session".to_string());
+            store
+                .user_profiles
+                .insert(key.to_string(), UserProfile::default());
+        }
+        store.roles.insert("telegram:2".to_string(), Role::Guest);
+
+        let report = store.prune_orphans();
+
+        assert_eq!(report.sessions, vec!["slack:1", "telegram:2"]);
+        assert_eq!(report.profiles, vec!["slack:1", "telegram:2"]);
+        assert_eq!(report.roles, vec!["telegram:2"]);
+        assert_eq!(
+            store.sessions.keys().collect::<Vec<_>>(),
+            vec!["telegram:1"]
+        );
+        assert_eq!(store.user_profiles.len(), 1);
+        assert!(store.roles.is_empty());
+        assert!(store.prune_orphans().is_empty());
+    }
+
+    fn pending(user_id: &str, created_at: u64, silenced: u32) -> PendingRequest {
+        PendingRequest {
+            code: format!("CODE{}", user_id),
+            channel: "telegram".to_string(),
+            user_id: user_id.to_string(),
+            username: None,
+            display_name: None,
+            created_at,
+            prompts: 0,
+            silenced,
+            denied: false,
+        }
+    }
+
+    #[test]
+    fn test_deny_backs_off() {
+        let ttl = Duration::from_secs(60);
+        let mut store = PairingStore::default();
+        store.record_denial(pending("2", 0, 0), 100);
+
+        // Silenced for twice the code lifetime after the denial
+        assert!(store.is_denied_at("telegram", "2", 219, ttl));
+        assert!(!store.is_denied_at("telegram", "2", 220, ttl));
+        assert!(!store.is_denied_at("telegram", "3", 100, ttl));
+
+        // Their next code backs off further, even without a prompt limit
+        assert_eq!(store.silenced_before("telegram", "2", 0), 2);
+
+        // The denial is remembered as long as the wait lasts
+        store.prune_expired_at(219, ttl);
+        assert!(store.is_denied_at("telegram", "2", 219, ttl));
+    }
+
+    #[test]
+    fn test_pairing_prompt_limit() {
+        let mut store = PairingStore {
+            pending: vec![pending("2", 0, 0)],
+            ..Default::default()
+        };
+
+        for _ in 0..3 {
+            assert!(store.count_prompt("telegram", "2", 3));
+        }
+        assert!(!store.count_prompt("telegram", "2", 3));
+        assert!(!store.count_prompt("telegram", "2", 3));
+        assert_eq!(store.pending[0].prompts, 3);
+
+        // 0 never silences, and senders without a request aren't counted
+        assert!(store.count_prompt("telegram", "2", 0));
+        assert!(store.count_prompt("telegram", "9", 3));
+    }
+
+    #[test]
+    fn test_silence_backoff() {
+        let ttl = Duration::from_secs(60);
+        let mut store = PairingStore {
+            pending: vec![pending("2", 0, 0), pending("3", 0, 2)],
+            ..Default::default()
+        };
+        store.pending[0].prompts = 3;
+        store.pending[1].prompts = 3;
+
+        // A code lasts twice as long for each earlier silenced one
+        store.prune_expired_at(60, ttl);
+        assert_eq!(store.expired.len(), 1);
+        assert_eq!(store.pending[0].user_id, "3");
+        store.prune_expired_at(239, ttl);
+        assert_eq!(store.pending.len(), 1);
+        store.prune_expired_at(240, ttl);
+        assert!(store.pending.is_empty());
+
+        // Their next codes back off further
+        assert_eq!(store.silenced_before("telegram", "2", 3), 1);
+        assert_eq!(store.silenced_before("telegram", "3", 3), 3);
+        assert_eq!(store.silenced_before("telegram", "9", 3), 0);
+        assert_eq!(store.silenced_before("telegram", "2", 0), 0);
+
+        // Letting a code expire without being silenced resets it
+        store.expired[0].prompts = 1;
+        assert_eq!(store.silenced_before("telegram", "2", 3), 0);
+
+        // Expired codes are remembered for the backed-off lifetime too
+        store.prune_expired_at(240 + EXPIRED_RETENTION.as_secs() - 1, ttl);
+        assert_eq!(store.expired.len(), 1);
+        assert_eq!(
+            pending("4", 0, 50).lifetime(ttl),
+            ttl * (1 << MAX_BACKOFF_DOUBLINGS)
+        );
+    }
+
+    #[test]
+    fn test_database_round_trip() {
+        let conn = Connection::open_in_memory().unwrap();
+        storage::init_schema(&conn).unwrap();
+
+        let request = |code: &str, user_id: &str| PendingRequest {
+            code: code.to_string(),
+            channel: "telegram".to_string(),
+            user_id: user_id.to_string(),
+            username: Some("alex".to_string()),
+            display_name: None,
+            created_at: 1_700_000_000,
+            prompts: 2,
+            silenced: 1,
+            denied: true,
+        };
+        let mut store = PairingStore {
+            pending: vec![request("ABCD2345", "2")],
+            expired: vec![request("WXYZ6789", "3")],
+            ..Default::default()
+        };
+        store
+            .approved
+            .insert("telegram".to_string(), vec!["1".to_string()]);
+        store
+            .blocked
+            .insert("slack".to_string(), vec!["U1".to_string()]);
+        store
+            .sessions
+            .insert("telegram:1".to_string(), "session".to_string());
+        store
+            .session_backends
+            .insert("telegram:1".to_string(), AiBackend::Cursor);
+        store
+            .session_started
+            .insert("telegram:1".to_string(), 1_700_000_000);
+        store.roles.insert("telegram:1".to_string(), Role::Guest);
+        store.user_profiles.insert(
+            "telegram:1".to_string(),
+            UserProfile {
+                name: Some("Alex".to_string()),
+                ..Default::default()
+            },
+        );
+
+        store.write_to(&conn).unwrap();
+        store.write_to(&conn).unwrap();
+        let loaded = PairingStore::read_from(&conn).unwrap();
+
+        assert_eq!(loaded.pending.len(), 1);
+        assert_eq!(loaded.pending[0].prompts, 2);
+        assert_eq!(loaded.pending[0].silenced, 1);
+        assert_eq!(loaded.expired[0].code, "WXYZ6789");
+        assert!(loaded.expired[0].denied);
+        assert!(loaded.is_approved("telegram", "1"));
+        assert!(loaded.is_blocked("slack", "U1"));
+        assert_eq!(loaded.role("telegram", "1"), Role::Guest);
+        assert_eq!(loaded.sessions.get("telegram:1").unwrap(), "session");
+        assert_eq!(
+            loaded.session_backends.get("telegram:1"),
+            Some(&AiBackend::Cursor)
+        );
+        assert_eq!(loaded.session_started("telegram", "1"), Some(1_700_000_000));
+        assert_eq!(
+            loaded
+                .get_user_profile("telegram", "1")
+                .unwrap()
+                .name
+                .as_deref(),
+            Some("Alex")
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Quiet hours (/dnd): while they last, a user's cron results and
+//! notifications are held, then delivered together as a digest.
+//!
+//! Times are in the server's local time, like cron schedules.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use anyhow::{Result, anyhow, bail};
+use chrono::{Local, NaiveTime, TimeZone};
+use rusqlite::{Connection, params};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::i18n;
+use crate::pairing::PairingStore;
+use crate::storage;
+
+/// A daily window, e.g. 22:00-07:00. It may wrap past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Parse "22:00-07:00" (or "22-7")
+    pub fn parse(input: &str) -> Result<Self> {
+        let (start, end) = input
+            .split_once('-')
+            .ok_or_else(|| anyhow!("Expected a range like 22:00-07:00"))?;
+        let (start, end) = (parse_time(start)?, parse_time(end)?);
+        if start == end {
+            bail!("Quiet hours can't start and end at the same time");
+        }
+        Ok(Self { start, end })
+    }
+
+    /// Whether `time` falls within the window
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl fmt::Display for QuietHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+/// Parse "7", "07:00" or "7:30"
+pub(crate) fn parse_time(input: &str) -> Result<NaiveTime> {
+    let input = input.trim();
+    let (hour, minute) = input.split_once(':').unwrap_or((input, "0"));
+    hour.parse()
+        .ok()
+        .zip(minute.parse().ok())
+        .and_then(|(hour, minute)| NaiveTime::from_hms_opt(hour, minute, 0))
+        .ok_or_else(|| anyhow!("Not a time: {} (use HH:MM)", input))
+}
+
+/// Whether the user is in their quiet hours right now
+pub fn is_quiet_now(channel: &str, user_id: &str) -> bool {
+    let quiet_hours = PairingStore::load()
+        .ok()
+        .and_then(|store| store.quiet_hours(channel, user_id));
+    quiet_hours.is_some_and(|hours| hours.contains(Local::now().time()))
+}
+
+/// A message held during quiet hours
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeldMessage {
+    pub message: String,
+    pub files: Vec<PathBuf>,
+    /// Unix timestamp of when it would have been sent
+    pub held_at: u64,
+}
+
+/// Messages held for one user, ready to deliver
+#[derive(Debug, Clone, PartialEq)]
+pub struct Digest {
+    pub channel: String,
+    pub user_id: String,
+    pub messages: Vec<HeldMessage>,
+}
+
+impl Digest {
+    /// The held messages as one message, oldest first
+    pub fn text(&self, language: Option<&str>) -> String {
+        let mut text = i18n::tr_args(
+            language,
+            "While your quiet hours were on, {} message(s) came in:",
+            &[&self.messages.len()],
+        );
+        for held in &self.messages {
+            let time = Local
+                .timestamp_opt(held.held_at as i64, 0)
+                .single()
+                .map(|t| t.format("%H:%M").to_string())
+                .unwrap_or_default();
+            text.push_str(&format!("\n\n[{}] {}", time, held.message));
+        }
+        text
+    }
+
+    /// Files from all the held messages
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.messages
+            .iter()
+            .flat_map(|held| held.files.iter().cloned())
+            .collect()
+    }
+}
+
+/// Keep a message until the user's quiet hours end
+pub fn hold(channel: &str, user_id: &str, message: &str, files: &[PathBuf]) -> Result<()> {
+    let files = serde_json::to_string(files)?;
+    storage::write(|conn| {
+        conn.execute(
+            "INSERT INTO held_messages (channel, user_id, message, files, held_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![channel, user_id, message, files, now_timestamp()],
+        )?;
+        Ok(())
+    })
+}
+
+/// Take the held messages of every user whose quiet hours are over
+pub fn take_due_digests() -> Result<Vec<Digest>> {
+    let users = storage::read(held_users)?;
+    let due: Vec<_> = users
+        .into_iter()
+        .filter(|(channel, user_id)| !is_quiet_now(channel, user_id))
+        .collect();
+    if due.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    storage::write(|conn| {
+        let mut digests = Vec::new();
+        for (channel, user_id) in due {
+            let messages = take_held(conn, &channel, &user_id)?;
+            if !messages.is_empty() {
+                digests.push(Digest {
+                    channel,
+                    user_id,
+                    messages,
+                });
+            }
+        }
+        Ok(digests)
+    })
+}
+
+/// Users with held messages
+fn held_users(conn: &Connection) -> Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare("SELECT DISTINCT channel, user_id FROM held_messages")?;
+    let users = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(users)
+}
+
+/// Remove and return a user's held messages, oldest first
+fn take_held(conn: &Connection, channel: &str, user_id: &str) -> Result<Vec<HeldMessage>> {
+    let mut stmt = conn.prepare(
+        "SELECT message, files, held_at FROM held_messages
+         WHERE channel = ?1 AND user_id = ?2 ORDER BY id",
+    )?;
+    let rows: Vec<(String, String, u64)> = stmt
+        .query_map(params![channel, user_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    conn.execute(
+        "DELETE FROM held_messages WHERE channel = ?1 AND user_id = ?2",
+        params![channel, user_id],
+    )?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(message, files, held_at)| HeldMessage {
+            message,
+            files: serde_json::from_str(&files).unwrap_or_else(|e| {
+                warn!("Dropping unreadable file list of a held message: {}", e);
+                Vec::new()
+            }),
+            held_at,
+        })
+        .collect())
+}
+
+fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_quiet_hours() {
+        let hours = QuietHours::parse("22:00-07:00").unwrap();
+        assert_eq!(hours.start, time(22, 0));
+        assert_eq!(hours.end, time(7, 0));
+        assert_eq!(hours.to_string(), "22:00-07:00");
+
+        assert_eq!(
+            QuietHours::parse("22-7:30").unwrap().to_string(),
+            "22:00-07:30"
+        );
+        assert!(QuietHours::parse("22:00").is_err());
+        assert!(QuietHours::parse("25:00-07:00").is_err());
+        assert!(QuietHours::parse("07:00-07:00").is_err());
+    }
+
+    #[test]
+    fn test_quiet_hours_contains() {
+        let overnight = QuietHours::parse("22:00-07:00").unwrap();
+        assert!(overnight.contains(time(23, 30)));
+        assert!(overnight.contains(time(3, 0)));
+        assert!(!overnight.contains(time(7, 0)));
+        assert!(!overnight.contains(time(12, 0)));
+
+        let afternoon = QuietHours::parse("13:00-15:00").unwrap();
+        assert!(afternoon.contains(time(13, 0)));
+        assert!(!afternoon.contains(time(15, 0)));
+        assert!(!afternoon.contains(time(9, 0)));
+    }
+
+    #[test]
+    fn test_take_held() {
+        let conn = Connection::open_in_memory().unwrap();
+        storage::init_schema(&conn).unwrap();
+        for (user, message) in [("1", "first"), ("2", "other"), ("1", "second")] {
+            conn.execute(
+                "INSERT INTO held_messages (channel, user_id, message, files, held_at)
+                 VALUES ('telegram', ?1, ?2, '[\"/tmp/a.csv\"]', 0)",
+                params![user, message],
+            )
+            .unwrap();
+        }
+
+        let held = take_held(&conn, "telegram", "1").unwrap();
+        let messages: Vec<_> = held.iter().map(|h| h.message.as_str()).collect();
+        assert_eq!(messages, ["first", "second"]);
+        assert_eq!(held[0].files, [PathBuf::from("/tmp/a.csv")]);
+
+        assert!(take_held(&conn, "telegram", "1").unwrap().is_empty());
+        assert_eq!(
+            held_users(&conn).unwrap(),
+            [("telegram".to_string(), "2".to_string())]
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Built-in web search
+//!
+//! Claude Code and Cursor search the web with their own tools; the
+//! OpenAI-compatible backend has none, so it gets a `web_search` tool backed
+//! by SearxNG or the Brave Search API, set up under [search] in config.toml.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+use crate::config::{SearchConfig, SearchProvider};
+
+const BRAVE_URL: &str = "https://api.search.brave.com/res/v1/web/search";
+
+/// Results returned per search
+const MAX_RESULTS: usize = 8;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// One web search hit
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+#[derive(Deserialize)]
+struct SearxngResponse {
+    #[serde(default)]
+    results: Vec<SearxngResult>,
+}
+
+#[derive(Deserialize)]
+struct SearxngResult {
+    #[serde(default)]
+    title: String,
+    url: String,
+    #[serde(default)]
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct BraveResponse {
+    web: Option<BraveWeb>,
+}
+
+#[derive(Deserialize)]
+struct BraveWeb {
+    #[serde(default)]
+    results: Vec<BraveResult>,
+}
+
+#[derive(Deserialize)]
+struct BraveResult {
+    #[serde(default)]
+    title: String,
+    url: String,
+    #[serde(default)]
+    description: String,
+}
+
+/// Search the web with the configured provider
+pub async fn search(config: &SearchConfig, query: &str) -> Result<Vec<SearchResult>> {
+    if !config.is_configured() {
+        bail!("Web search isn't set up; configure [search] in config.toml");
+    }
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()?;
+
+    let mut results: Vec<SearchResult> = match config.provider {
+        Some(SearchProvider::Searxng) => {
+            let url = format!(
+                "{}/search",
+                config
+                    .url
+                    .as_deref()
+                    .unwrap_or_default()
+                    .trim_end_matches('/')
+            );
+            let response: SearxngResponse = client
+                .get(&url)
+                .query(&[("q", query), ("format", "json")])
+                .send()
+                .await
+                .context("Request to SearxNG failed")?
+                .error_for_status()
+                .context("SearxNG search failed (is the json format enabled?)")?
+                .json()
+                .await
+                .context("Unexpected response from SearxNG")?;
+            response
+                .results
+                .into_iter()
+                .map(|r| SearchResult {
+                    title: r.title,
+                    url: r.url,
+                    snippet: r.content,
+                })
+                .collect()
+        }
+        Some(SearchProvider::Brave) => {
+            let response: BraveResponse = client
+                .get(BRAVE_URL)
+                .query(&[("q", query), ("count", &MAX_RESULTS.to_string())])
+                .header(
+                    "X-Subscription-Token",
+                    config.api_key.as_deref().unwrap_or_default(),
+                )
+                .header("Accept", "application/json")
+                .send()
+                .await
+                .context("Request to Brave Search failed")?
+                .error_for_status()
+                .context("Brave Search failed")?
+                .json()
+                .await
+                .context("Unexpected response from Brave Search")?;
+            response
+                .web
+                .map(|web| web.results)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|r| SearchResult {
+                    title: r.title,
+                    url: r.url,
+                    snippet: strip_tags(&r.description),
+                })
+                .collect()
+        }
+        None => Vec::new(),
+    };
+
+    results.truncate(MAX_RESULTS);
+    Ok(results)
+}
+
+/// Results as text for the model: title, URL and snippet of each
+pub fn format_results(results: &[SearchResult]) -> String {
+    if results.is_empty() {
+        return "No results".to_string();
+    }
+    results
+        .iter()
+        .enumerate()
+        .map(|(i, r)| {
+            let mut entry = format!("{}. {}\n   {}", i + 1, r.title, r.url);
+            if !r.snippet.is_empty() {
+                entry.push_str(&format!("\n   {}", r.snippet));
+            }
+            entry
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Brave highlights matches with <strong>; drop the markup
+fn strip_tags(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_results() {
+        let results = vec![
+            SearchResult {
+                title: "Rust".to_string(),
+                url: "https://www.rust-lang.org".to_string(),
+                snippet: strip_tags("A language empowering <strong>everyone</strong>"),
+            },
+            SearchResult {
+                title: "Crates".to_string(),
+                url: "https://crates.io".to_string(),
+                snippet: String::new(),
+            },
+        ];
+        assert_eq!(
+            format_results(&results),
+            "1. Rust\n   https://www.rust-lang.org\n   A language empowering everyone\n\n\
+             2. Crates\n   https://crates.io"
+        );
+        assert_eq!(format_results(&[]), "No results");
+    }
+}
@@ -0,0 +1,322 @@
+//! Storage for bot tokens and API keys.
+//!
+//! Secrets live in config.toml in plaintext by default. They can instead be
+//! kept in the OS keyring or encrypted with a passphrase, in which case
+//! config.toml only holds a reference:
+//! - `keyring:<name>` - look up `<name>` in the OS keyring
+//! - `enc:v1:<base64>` - ChaCha20-Poly1305 ciphertext, keyed from the passphrase
+//!
+//! Any secret can also be written as `env:<VAR>` to read it from an
+//! environment variable, e.g. one a container is started with.
+
+#[cfg(feature = "keyring")]
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use anyhow::{Context, Result, anyhow, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+/// Environment variable holding the passphrase for encrypted secrets
+pub const PASSPHRASE_ENV: &str = "CICA_PASSPHRASE";
+
+/// Prefix of secrets stored in the OS keyring
+const KEYRING_PREFIX: &str = "keyring:";
+
+/// Prefix of passphrase-encrypted secrets
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// Prefix of secrets read from an environment variable
+const ENV_PREFIX: &str = "env:";
+
+/// Keyring service name
+#[cfg(feature = "keyring")]
+const KEYRING_SERVICE: &str = "cica";
+
+/// PBKDF2 rounds for deriving the encryption key
+const PBKDF2_ROUNDS: u32 = 600_000;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Where secrets are kept
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretStore {
+    /// In config.toml as-is
+    #[default]
+    Plaintext,
+    /// In the OS keyring (macOS Keychain, Windows Credential Manager, Secret Service)
+    Keyring,
+    /// In config.toml, encrypted with a passphrase
+    Passphrase,
+}
+
+impl SecretStore {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecretStore::Plaintext => "plaintext",
+            SecretStore::Keyring => "keyring",
+            SecretStore::Passphrase => "passphrase",
+        }
+    }
+}
+
+impl std::str::FromStr for SecretStore {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "plaintext" => Ok(SecretStore::Plaintext),
+            "keyring" => Ok(SecretStore::Keyring),
+            "passphrase" => Ok(SecretStore::Passphrase),
+            _ => Err(anyhow!(
+                "Unknown secret store: {} (expected plaintext, keyring or passphrase)",
+                s
+            )),
+        }
+    }
+}
+
+/// Passphrase supplied at startup (otherwise read from `CICA_PASSPHRASE`)
+static PASSPHRASE: OnceLock<String> = OnceLock::new();
+
+/// Derived key for the current salt, so the slow derivation runs once
+static DERIVED_KEY: Mutex<Option<(String, [u8; 32])>> = Mutex::new(None);
+
+/// Secrets already read from the keyring, by name
+#[cfg(feature = "keyring")]
+static KEYRING_CACHE: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+
+/// Provide the passphrase for encrypted secrets (e.g. after prompting for it)
+pub fn set_passphrase(passphrase: String) {
+    let _ = PASSPHRASE.set(passphrase);
+}
+
+/// Check whether a config value is a reference rather than the secret itself
+pub fn is_reference(value: &str) -> bool {
+    value.starts_with(KEYRING_PREFIX)
+        || value.starts_with(ENCRYPTED_PREFIX)
+        || env_reference(value).is_some()
+}
+
+/// The environment variable a config value refers to, if it's `env:<VAR>`
+pub fn env_reference(value: &str) -> Option<&str> {
+    value.strip_prefix(ENV_PREFIX).filter(|var| !var.is_empty())
+}
+
+/// The config value that refers to environment variable `var`
+pub fn env_reference_to(var: &str) -> String {
+    format!("{}{}", ENV_PREFIX, var)
+}
+
+/// Check whether the saved config has encrypted secrets, so a passphrase is needed
+pub fn config_needs_passphrase() -> bool {
+    config::paths()
+        .ok()
+        .and_then(|paths| std::fs::read_to_string(paths.config_file).ok())
+        .is_some_and(|content| content.contains(ENCRYPTED_PREFIX))
+}
+
+/// Generate a random salt for passphrase encryption
+pub fn generate_salt() -> String {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    BASE64.encode(salt)
+}
+
+/// Turn a value from config.toml into the secret it refers to
+pub fn resolve(name: &str, value: &str, salt: Option<&str>) -> Result<String> {
+    if let Some(key) = value.strip_prefix(KEYRING_PREFIX) {
+        return keyring_get(key).with_context(|| format!("Could not read {} from keyring", name));
+    }
+
+    if let Some(var) = env_reference(value) {
+        return std::env::var(var)
+            .with_context(|| format!("{} refers to {}, which isn't set", name, var));
+    }
+
+    if let Some(encoded) = value.strip_prefix(ENCRYPTED_PREFIX) {
+        let salt = salt.ok_or_else(|| anyhow!("{} is encrypted but no salt is set", name))?;
+        return decrypt(encoded, &derive_key(salt)?)
+            .with_context(|| format!("Could not decrypt {} (wrong passphrase?)", name));
+    }
+
+    Ok(value.to_string())
+}
+
+/// Store a secret, returning the value to write to config.toml in its place
+pub fn store(store: SecretStore, name: &str, secret: &str, salt: Option<&str>) -> Result<String> {
+    match store {
+        SecretStore::Plaintext => Ok(secret.to_string()),
+        SecretStore::Keyring => {
+            keyring_set(name, secret)
+                .with_context(|| format!("Could not store {} in keyring", name))?;
+            Ok(format!("{}{}", KEYRING_PREFIX, name))
+        }
+        SecretStore::Passphrase => {
+            let salt = salt.ok_or_else(|| anyhow!("No salt set for encrypted secrets"))?;
+            let encoded = encrypt(secret, &derive_key(salt)?)?;
+            Ok(format!("{}{}", ENCRYPTED_PREFIX, encoded))
+        }
+    }
+}
+
+/// Remove a secret from the keyring (e.g. after moving to another store)
+pub fn forget_keyring(name: &str) -> Result<()> {
+    keyring_delete(name)
+}
+
+// ============================================================================
+// Passphrase Encryption
+// ============================================================================
+
+fn passphrase() -> Result<String> {
+    if let Some(passphrase) = PASSPHRASE.get() {
+        return Ok(passphrase.clone());
+    }
+
+    std::env::var(PASSPHRASE_ENV).map_err(|_| {
+        anyhow!(
+            "Secrets are encrypted with a passphrase. Set {} or run Cica from a terminal.",
+            PASSPHRASE_ENV
+        )
+    })
+}
+
+fn derive_key(salt: &str) -> Result<[u8; 32]> {
+    let mut cached = DERIVED_KEY.lock().unwrap();
+    if let Some((cached_salt, key)) = cached.as_ref()
+        && cached_salt == salt
+    {
+        return Ok(*key);
+    }
+
+    let salt_bytes = BASE64.decode(salt).context("Invalid salt")?;
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<sha2::Sha256>(
+        passphrase()?.as_bytes(),
+        &salt_bytes,
+        PBKDF2_ROUNDS,
+        &mut key,
+    );
+
+    *cached = Some((salt.to_string(), key));
+    Ok(key)
+}
+
+fn encrypt(secret: &str, key: &[u8; 32]) -> Result<String> {
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, secret.as_bytes())
+        .map_err(|_| anyhow!("Encryption failed"))?;
+
+    let mut data = nonce.to_vec();
+    data.extend(ciphertext);
+    Ok(BASE64.encode(data))
+}
+
+fn decrypt(encoded: &str, key: &[u8; 32]) -> Result<String> {
+    let data = BASE64.decode(encoded).context("Invalid encrypted value")?;
+    if data.len() < NONCE_LEN {
+        bail!("Encrypted value is too short");
+    }
+
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("Decryption failed"))?;
+
+    Ok(String::from_utf8(plaintext)?)
+}
+
+// ============================================================================
+// OS Keyring
+// ============================================================================
+
+#[cfg(feature = "keyring")]
+fn keyring_cache() -> &'static Mutex<HashMap<String, String>> {
+    KEYRING_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_get(name: &str) -> Result<String> {
+    if let Some(secret) = keyring_cache().lock().unwrap().get(name) {
+        return Ok(secret.clone());
+    }
+
+    let secret = keyring::Entry::new(KEYRING_SERVICE, name)?.get_password()?;
+    keyring_cache()
+        .lock()
+        .unwrap()
+        .insert(name.to_string(), secret.clone());
+    Ok(secret)
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_set(name: &str, secret: &str) -> Result<()> {
+    keyring::Entry::new(KEYRING_SERVICE, name)?.set_password(secret)?;
+    keyring_cache()
+        .lock()
+        .unwrap()
+        .insert(name.to_string(), secret.to_string());
+    Ok(())
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_delete(name: &str) -> Result<()> {
+    keyring_cache().lock().unwrap().remove(name);
+    match keyring::Entry::new(KEYRING_SERVICE, name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+fn keyring_get(_name: &str) -> Result<String> {
+    bail!("This build doesn't include OS keyring support")
+}
+
+#[cfg(not(feature = "keyring"))]
+fn keyring_set(_name: &str, _secret: &str) -> Result<()> {
+    bail!("This build doesn't include OS keyring support")
+}
+
+#[cfg(not(feature = "keyring"))]
+fn keyring_delete(_name: &str) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let key = [7u8; 32];
+        let encoded = encrypt("123456:bot-token", &key).unwrap();
+
+        assert_ne!(encoded, encrypt("123456:bot-token", &key).unwrap());
+        assert_eq!(decrypt(&encoded, &key).unwrap(), "123456:bot-token");
+        assert!(decrypt(&encoded, &[8u8; 32]).is_err());
+    }
+
+    #[test]
+    fn test_plaintext_values_pass_through() {
+        assert!(!is_reference("sk-ant-123"));
+        assert!(is_reference("keyring:claude.api_key"));
+        assert!(is_reference("enc:v1:AAAA"));
+        assert_eq!(
+            resolve("claude.api_key", "sk-ant-123", None).unwrap(),
+            "sk-ant-123"
+        );
+    }
+}
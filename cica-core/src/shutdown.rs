@@ -0,0 +1,121 @@
+//! Graceful shutdown: stop taking new work, give the work in progress time to
+//! finish, and find out whose requests didn't make it.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+/// Time for messages still waiting out their debounce to start processing
+const SETTLE: Duration = Duration::from_millis(500);
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+static IN_FLIGHT: OnceLock<Mutex<HashMap<u64, Request>>> = OnceLock::new();
+static FINISHED: OnceLock<Notify> = OnceLock::new();
+
+/// Work started on someone's behalf: a reply to a message or a cron run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub channel: String,
+    pub user_id: String,
+    /// What was asked for, e.g. "your message"
+    pub description: String,
+}
+
+/// Keeps a request counted as in progress until dropped
+pub struct InFlight(u64);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if let Ok(mut requests) = in_flight().lock() {
+            requests.remove(&self.0);
+        }
+        finished().notify_waiters();
+    }
+}
+
+fn in_flight() -> &'static Mutex<HashMap<u64, Request>> {
+    IN_FLIGHT.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn finished() -> &'static Notify {
+    FINISHED.get_or_init(Notify::new)
+}
+
+/// Stop accepting new messages and cron runs
+pub fn begin() {
+    SHUTTING_DOWN.store(true, Ordering::SeqCst);
+}
+
+/// Whether Cica is shutting down, so new work should be turned away
+pub fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.load(Ordering::SeqCst)
+}
+
+/// Count a request as in progress until the returned guard is dropped
+pub fn track(channel: &str, user_id: &str, description: impl Into<String>) -> InFlight {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let request = Request {
+        channel: channel.to_string(),
+        user_id: user_id.to_string(),
+        description: description.into(),
+    };
+    if let Ok(mut requests) = in_flight().lock() {
+        requests.insert(id, request);
+    }
+    InFlight(id)
+}
+
+/// Requests in progress right now
+pub fn requests() -> Vec<Request> {
+    in_flight()
+        .lock()
+        .map(|r| r.values().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Wait until no requests are in progress, or `timeout` passes. Returns the
+/// requests that were still running.
+pub async fn drain(timeout: Duration) -> Vec<Request> {
+    let deadline = Instant::now() + timeout;
+    tokio::time::sleep(SETTLE.min(timeout)).await;
+
+    loop {
+        // Register before checking, so a request ending in between isn't missed
+        let notified = finished().notified();
+        let running = requests();
+        if running.is_empty() {
+            return running;
+        }
+        if tokio::time::timeout_at(deadline, notified).await.is_err() {
+            return requests();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain() {
+        let quick = track("telegram", "1", "your message");
+        let slow = track("signal", "2", "the cron job \"digest\"");
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(600)).await;
+            drop(quick);
+        });
+
+        let dropped = drain(Duration::from_secs(2)).await;
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].user_id, "2");
+        assert_eq!(dropped[0].description, "the cron job \"digest\"");
+
+        drop(slow);
+        assert!(drain(Duration::from_secs(1)).await.is_empty());
+    }
+}
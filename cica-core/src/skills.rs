@@ -0,0 +1,824 @@
+//! Skills discovery and management.
+//!
+//! Skills are stored in the skills/ directory as subdirectories containing a SKILL.md file.
+//! The SKILL.md file contains YAML frontmatter with name and description.
+
+use anyhow::{Context, Result, bail};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::process::Command;
+use tracing::info;
+
+use crate::config::{self, Config};
+use crate::setup;
+
+/// Longest a clone or dependency install may take
+const INSTALL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Longest a skill's healthcheck may take
+const HEALTHCHECK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long /skills reuses the last healthcheck results
+const CHECK_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Each skill with the result of its healthcheck
+pub type CheckedSkills = Vec<(Skill, SkillHealth)>;
+
+/// Results of the last [`check_all_cached`], with when they were taken
+static LAST_CHECK: tokio::sync::Mutex<Option<(Instant, CheckedSkills)>> =
+    tokio::sync::Mutex::const_new(None);
+
+/// A discovered skill
+#[derive(Debug, Clone)]
+pub struct Skill {
+    pub name: String,
+    pub description: String,
+    pub location: PathBuf,
+    pub permissions: SkillPermissions,
+    /// Shell command, run in the skill's directory, that fails when the
+    /// skill can't work (e.g. its token expired)
+    pub healthcheck: Option<String>,
+}
+
+/// What a skill says it needs, from the `permissions:` block of its
+/// SKILL.md frontmatter:
+///
+/// ```yaml
+/// permissions:
+///   network: [api.weather.gov]
+///   env: [WEATHER_API_KEY]
+///   paths: [~/notes]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SkillPermissions {
+    /// Hosts it fetches from
+    pub network: Vec<String>,
+    /// Environment variables it reads
+    pub env: Vec<String>,
+    /// Files or directories it reads and writes, as paths or globs
+    pub paths: Vec<String>,
+}
+
+impl SkillPermissions {
+    fn list_mut(&mut self, key: &str) -> Option<&mut Vec<String>> {
+        match key {
+            "network" => Some(&mut self.network),
+            "env" => Some(&mut self.env),
+            "paths" => Some(&mut self.paths),
+            _ => None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.network.is_empty() && self.env.is_empty() && self.paths.is_empty()
+    }
+
+    /// One line per kind of access, for showing the owner
+    pub fn describe(&self) -> String {
+        if self.is_empty() {
+            return "No permissions requested".to_string();
+        }
+        [
+            ("Network", &self.network),
+            ("Environment", &self.env),
+            ("Paths", &self.paths),
+        ]
+        .iter()
+        .filter(|(_, items)| !items.is_empty())
+        .map(|(label, items)| format!("{}: {}", label, items.join(", ")))
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+}
+
+/// Discover all available skills from the skills directory
+pub fn discover_skills() -> Result<Vec<Skill>> {
+    let skills_dir = config::paths()?.skills_dir;
+
+    if !skills_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut skills = Vec::new();
+
+    let entries = std::fs::read_dir(&skills_dir)?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        let skill_file = path.join("SKILL.md");
+        if !skill_file.exists() {
+            continue;
+        }
+
+        if let Ok(skill) = parse_skill(&skill_file) {
+            skills.push(skill);
+        }
+    }
+
+    // Sort by name for consistent ordering
+    skills.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(skills)
+}
+
+/// The skill a message invokes: the first one it calls by `/name`. Only an
+/// explicit call grants a skill its permissions; naming one in passing
+/// ("weather") doesn't.
+pub fn invoked<'a>(message: &str, skills: &'a [Skill]) -> Option<&'a Skill> {
+    message.split_whitespace().find_map(|word| {
+        let name = word
+            .strip_prefix('/')?
+            .trim_end_matches(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'));
+        skills
+            .iter()
+            .find(|skill| skill.name.eq_ignore_ascii_case(name))
+    })
+}
+
+/// Metadata from a SKILL.md's frontmatter
+#[derive(Debug, Default)]
+struct Frontmatter {
+    name: Option<String>,
+    description: Option<String>,
+    permissions: SkillPermissions,
+    healthcheck: Option<String>,
+}
+
+/// The frontmatter of a SKILL.md (between --- markers), if it has one
+fn frontmatter(content: &str) -> Option<&str> {
+    content
+        .strip_prefix("---")
+        .and_then(|rest| rest.find("---").map(|end| &rest[..end]))
+}
+
+/// Parse YAML frontmatter to extract name, description and permissions
+fn parse_frontmatter(frontmatter: &str) -> Frontmatter {
+    let mut parsed = Frontmatter::default();
+    let mut in_permissions = false;
+    // Key whose "- item" lines follow
+    let mut list_key: Option<&str> = None;
+
+    for line in frontmatter.lines() {
+        let indented = line.starts_with([' ', '\t']);
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if !indented {
+            in_permissions = line.trim_end_matches(':') == "permissions";
+            list_key = None;
+            if let Some(value) = line.strip_prefix("name:") {
+                parsed.name = Some(unquote(value).to_string());
+            } else if let Some(value) = line.strip_prefix("description:") {
+                parsed.description = Some(unquote(value).to_string());
+            } else if let Some(value) = line.strip_prefix("healthcheck:") {
+                parsed.healthcheck = Some(unquote(value).to_string()).filter(|c| !c.is_empty());
+            }
+            continue;
+        }
+        if !in_permissions {
+            continue;
+        }
+
+        // "- item" under a key
+        if let Some(item) = line.strip_prefix('-') {
+            if let Some(list) = list_key.and_then(|key| parsed.permissions.list_mut(key)) {
+                push_item(list, item);
+            }
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim();
+        let Some(target) = parsed.permissions.list_mut(key) else {
+            list_key = None;
+            continue;
+        };
+        // "key: [a, b]" or "key: a"
+        let value = value.trim();
+        for item in value
+            .strip_prefix('[')
+            .and_then(|v| v.strip_suffix(']'))
+            .unwrap_or(value)
+            .split(',')
+        {
+            push_item(target, item);
+        }
+        list_key = Some(key);
+    }
+
+    parsed
+}
+
+fn push_item(list: &mut Vec<String>, item: &str) {
+    let item = unquote(item);
+    if !item.is_empty() {
+        list.push(item.to_string());
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim().trim_matches('"').trim_matches('\'')
+}
+
+/// Parse a SKILL.md file to extract skill metadata
+fn parse_skill(path: &PathBuf) -> Result<Skill> {
+    let content = std::fs::read_to_string(path)?;
+    let parsed = frontmatter(&content)
+        .map(parse_frontmatter)
+        .unwrap_or_default();
+
+    // Fall back to directory name if no name in frontmatter
+    let dir_name = path
+        .parent()
+        .and_then(|p| p.file_name())
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    Ok(Skill {
+        name: parsed.name.unwrap_or_else(|| dir_name.clone()),
+        description: parsed
+            .description
+            .unwrap_or_else(|| format!("Skill: {}", dir_name)),
+        location: path.clone(),
+        permissions: parsed.permissions,
+        healthcheck: parsed.healthcheck,
+    })
+}
+
+/// Whether a skill can work right now
+#[derive(Debug, Clone, PartialEq)]
+pub enum SkillHealth {
+    /// Its healthcheck passed
+    Ok,
+    /// A declared environment variable isn't set, or its healthcheck failed
+    Broken(String),
+    /// It has no healthcheck
+    Unchecked,
+}
+
+impl SkillHealth {
+    /// Short status shown next to the skill, if it was checked. The reason
+    /// a skill is broken can include healthcheck output, so it's only shown
+    /// when `detailed`.
+    pub fn label(&self, detailed: bool) -> Option<String> {
+        match self {
+            Self::Ok => Some("OK".to_string()),
+            Self::Broken(reason) if detailed => Some(format!("broken: {}", reason)),
+            Self::Broken(_) => Some("broken".to_string()),
+            Self::Unchecked => None,
+        }
+    }
+}
+
+/// Check that a skill's environment variables are set, then run its healthcheck
+pub async fn check(skill: &Skill) -> SkillHealth {
+    let missing: Vec<&str> = skill
+        .permissions
+        .env
+        .iter()
+        .filter(|name| std::env::var_os(name).is_none())
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        return SkillHealth::Broken(format!("{} not set", missing.join(", ")));
+    }
+
+    let Some(healthcheck) = &skill.healthcheck else {
+        return SkillHealth::Unchecked;
+    };
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(healthcheck);
+    if let Some(dir) = skill.location.parent() {
+        command.current_dir(dir);
+    }
+    let output = tokio::time::timeout(
+        HEALTHCHECK_TIMEOUT,
+        command
+            .kill_on_drop(true)
+            .stdin(std::process::Stdio::null())
+            .output(),
+    )
+    .await;
+
+    match output {
+        Err(_) => SkillHealth::Broken("healthcheck timed out".to_string()),
+        Ok(Err(e)) => SkillHealth::Broken(format!("couldn't run healthcheck: {}", e)),
+        Ok(Ok(output)) if output.status.success() => SkillHealth::Ok,
+        Ok(Ok(output)) => {
+            // The last thing it printed usually says what's wrong
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let reason = [stderr, stdout]
+                .iter()
+                .find_map(|out| out.lines().rev().map(str::trim).find(|l| !l.is_empty()))
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("healthcheck failed ({})", output.status));
+            SkillHealth::Broken(reason)
+        }
+    }
+}
+
+/// Discover all skills and check each of them, in parallel
+pub async fn check_all() -> Result<CheckedSkills> {
+    let handles: Vec<_> = discover_skills()?
+        .into_iter()
+        .map(|skill| {
+            tokio::spawn(async move {
+                let health = check(&skill).await;
+                (skill, health)
+            })
+        })
+        .collect();
+
+    let mut checked = Vec::with_capacity(handles.len());
+    for handle in handles {
+        checked.push(handle.await?);
+    }
+    Ok(checked)
+}
+
+/// Like [`check_all`], reusing results from the last few minutes so
+/// /skills doesn't run every healthcheck each time it's sent
+pub async fn check_all_cached() -> Result<CheckedSkills> {
+    let mut last = LAST_CHECK.lock().await;
+    if let Some((at, checked)) = last.as_ref()
+        && at.elapsed() < CHECK_CACHE_TTL
+    {
+        return Ok(checked.clone());
+    }
+    let checked = check_all().await?;
+    *last = Some((Instant::now(), checked.clone()));
+    Ok(checked)
+}
+
+/// Forget cached healthcheck results, e.g. after a skill is installed
+pub async fn clear_check_cache() {
+    *LAST_CHECK.lock().await = None;
+}
+
+/// What [`ReviewedSkill::install`] put in place
+#[derive(Debug)]
+pub struct InstalledSkill {
+    pub skill: Skill,
+    /// Whether it had a package.json whose dependencies were installed
+    pub dependencies: bool,
+    /// Environment variables it declares that aren't set
+    pub missing_env: Vec<String>,
+}
+
+/// Where a skill is installed from
+#[derive(Debug, PartialEq)]
+struct SkillSource {
+    /// Repository to clone
+    url: String,
+    /// Directory of the skill within the repository (registry skills)
+    subdir: Option<String>,
+    /// Directory it's installed as, under skills/
+    dir_name: String,
+}
+
+impl SkillSource {
+    /// A git URL, or the name of a skill in `registry`.
+    fn resolve(source: &str, registry: Option<&str>) -> Result<Self> {
+        let source = source.trim();
+        let is_url = source.contains("://") || source.starts_with("git@");
+
+        if is_url {
+            let dir_name = source
+                .trim_end_matches('/')
+                .rsplit(['/', ':'])
+                .next()
+                .unwrap_or_default()
+                .trim_end_matches(".git");
+            if !is_valid_dir_name(dir_name) {
+                bail!("Can't tell the skill's name from {}", source);
+            }
+            return Ok(Self {
+                url: source.to_string(),
+                subdir: None,
+                dir_name: dir_name.to_string(),
+            });
+        }
+
+        if !is_valid_dir_name(source) {
+            bail!("Not a git URL or skill name: {}", source);
+        }
+        let Some(registry) = registry else {
+            bail!(
+                "No skill registry is set; give a git URL, or set registry under [skills] in config.toml"
+            );
+        };
+        Ok(Self {
+            url: registry.to_string(),
+            subdir: Some(source.to_string()),
+            dir_name: source.to_string(),
+        })
+    }
+}
+
+/// Names that are safe to use as a directory under skills/
+fn is_valid_dir_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// A skill cloned and checked but not installed yet, so the owner can see
+/// what it asks for first. Dropping it discards the clone.
+#[derive(Debug)]
+pub struct ReviewedSkill {
+    pub skill: Skill,
+    /// Whether it has a package.json whose dependencies would be installed
+    pub dependencies: bool,
+    source: SkillSource,
+    checkout: PathBuf,
+    skill_dir: PathBuf,
+}
+
+impl ReviewedSkill {
+    /// The directory it would be installed as under skills/, which names it
+    /// for confirming
+    pub fn dir_name(&self) -> &str {
+        &self.source.dir_name
+    }
+
+    /// The skill, what it asks for and whether it has dependencies, for the
+    /// owner to confirm
+    pub fn describe(&self) -> String {
+        let mut text = format!(
+            "{}: {}\n\n{}",
+            self.skill.name,
+            self.skill.description,
+            self.skill.permissions.describe()
+        );
+        if self.dependencies {
+            text.push_str("\nDependencies: installed with Bun, without running package scripts");
+        }
+        text
+    }
+
+    /// Move the skill into skills/ and install its dependencies with Bun
+    pub async fn install(self) -> Result<InstalledSkill> {
+        let target = config::paths()?.skills_dir.join(&self.source.dir_name);
+        // Another install may have taken the name since the review
+        if target.exists() {
+            bail!("A skill was installed at {} meanwhile", target.display());
+        }
+        std::fs::rename(&self.skill_dir, &target)
+            .with_context(|| format!("Couldn't move the skill to {}", target.display()))?;
+
+        // From here on the target is ours, so a failure removes it
+        let result = finish_install(&self.source, &target).await;
+        if result.is_ok() {
+            clear_check_cache().await;
+        } else {
+            let _ = std::fs::remove_dir_all(&target);
+        }
+        result
+    }
+}
+
+impl Drop for ReviewedSkill {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.checkout);
+    }
+}
+
+/// Clone a skill from a git URL, or by name from the registry under
+/// [skills] in config.toml, and check its SKILL.md, without installing it.
+/// Nothing is installed until [`ReviewedSkill::install`].
+pub async fn review(source: &str) -> Result<ReviewedSkill> {
+    let config = if Config::exists()? {
+        Config::load()?
+    } else {
+        Config::default()
+    };
+    let source = SkillSource::resolve(source, config.skills.registry.as_deref())?;
+
+    let paths = config::paths()?;
+    let target = paths.skills_dir.join(&source.dir_name);
+    if target.exists() {
+        bail!(
+            "A skill is already installed at {}; remove it to reinstall",
+            target.display()
+        );
+    }
+
+    // Cloned next to skills/ so the move into place is a rename
+    std::fs::create_dir_all(&paths.skills_dir)?;
+    let checkout = create_checkout_dir(&paths.internal_dir)?;
+    match clone_and_check(&source, &checkout).await {
+        Ok((skill_dir, skill)) => Ok(ReviewedSkill {
+            dependencies: skill_dir.join("package.json").exists(),
+            skill,
+            source,
+            checkout,
+            skill_dir,
+        }),
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&checkout);
+            Err(e)
+        }
+    }
+}
+
+/// A new, empty directory for cloning into that no other install uses
+fn create_checkout_dir(parent: &Path) -> Result<PathBuf> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    std::fs::create_dir_all(parent)?;
+    loop {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos();
+        let dir = parent.join(format!(
+            "skill-install-{}-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+            nanos
+        ));
+        match std::fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to create {}", dir.display()));
+            }
+        }
+    }
+}
+
+/// Clone a skill into `checkout`, returning its directory there and its
+/// SKILL.md
+async fn clone_and_check(source: &SkillSource, checkout: &Path) -> Result<(PathBuf, Skill)> {
+    let git = which::which("git").context("git isn't installed")?;
+    info!("Cloning skill from {}", source.url);
+    run(Command::new(git)
+        .args(["clone", "--depth", "1", "--"])
+        .arg(&source.url)
+        .arg(checkout))
+    .await
+    .with_context(|| format!("Couldn't clone {}", source.url))?;
+
+    let skill_dir = match &source.subdir {
+        Some(subdir) => checkout.join(subdir),
+        None => checkout.to_path_buf(),
+    };
+    if !skill_dir.is_dir() {
+        bail!("The registry has no skill named {}", source.dir_name);
+    }
+
+    let skill_file = skill_dir.join("SKILL.md");
+    let content = std::fs::read_to_string(&skill_file)
+        .with_context(|| format!("{} has no SKILL.md", source.url))?;
+    validate_skill_md(&content)?;
+    let skill = parse_skill(&skill_file)?;
+    Ok((skill_dir, skill))
+}
+
+/// Install the moved skill's dependencies and read it back. Package scripts
+/// aren't run, so installing runs none of the skill's code.
+async fn finish_install(source: &SkillSource, target: &Path) -> Result<InstalledSkill> {
+    let dependencies = target.join("package.json").exists();
+    if dependencies {
+        let bun = setup::find_bun().context("Bun isn't installed; run cica init")?;
+        info!("Installing dependencies of skill {}", source.dir_name);
+        run(Command::new(bun)
+            .args(["install", "--ignore-scripts"])
+            .current_dir(target))
+        .await
+        .context("Couldn't install the skill's dependencies")?;
+    }
+
+    let skill = parse_skill(&target.join("SKILL.md"))?;
+    let missing_env = skill
+        .permissions
+        .env
+        .iter()
+        .filter(|name| std::env::var_os(name).is_none())
+        .cloned()
+        .collect();
+    Ok(InstalledSkill {
+        skill,
+        dependencies,
+        missing_env,
+    })
+}
+
+/// Run a command to completion, failing with its output if it fails.
+async fn run(command: &mut Command) -> Result<()> {
+    let output = tokio::time::timeout(
+        INSTALL_TIMEOUT,
+        command
+            .kill_on_drop(true)
+            .stdin(std::process::Stdio::null())
+            .output(),
+    )
+    .await
+    .context("Timed out")??;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("{}", stderr.trim());
+    }
+    Ok(())
+}
+
+/// Check that a SKILL.md has frontmatter with a name and description.
+fn validate_skill_md(content: &str) -> Result<()> {
+    let Some(frontmatter) = frontmatter(content) else {
+        bail!("SKILL.md doesn't start with --- frontmatter");
+    };
+
+    let parsed = parse_frontmatter(frontmatter);
+    if parsed.name.is_none_or(|n| n.is_empty()) {
+        bail!("SKILL.md frontmatter has no name");
+    }
+    if parsed.description.is_none_or(|d| d.is_empty()) {
+        bail!("SKILL.md frontmatter has no description");
+    }
+    Ok(())
+}
+
+/// Format skills as XML for the system prompt
+pub fn format_skills_xml(skills: &[Skill]) -> String {
+    if skills.is_empty() {
+        return String::new();
+    }
+
+    let mut xml = String::from("<available_skills>\n");
+
+    for skill in skills {
+        xml.push_str("  <skill>\n");
+        xml.push_str(&format!("    <name>{}</name>\n", escape_xml(&skill.name)));
+        xml.push_str(&format!(
+            "    <description>{}</description>\n",
+            escape_xml(&skill.description)
+        ));
+        xml.push_str(&format!(
+            "    <location>{}</location>\n",
+            skill.location.display()
+        ));
+        xml.push_str("  </skill>\n");
+    }
+
+    xml.push_str("</available_skills>");
+    xml
+}
+
+/// Escape special XML characters
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invoked() {
+        let skill = |name: &str| Skill {
+            name: name.to_string(),
+            description: String::new(),
+            location: PathBuf::new(),
+            permissions: SkillPermissions::default(),
+            healthcheck: None,
+        };
+        let skills = [skill("weather"), skill("github-issues")];
+
+        let name = |message| invoked(message, &skills).map(|s| s.name.as_str());
+        assert_eq!(name("/weather in Berlin?"), Some("weather"));
+        assert_eq!(
+            name("File this with /github-issues, then /weather"),
+            Some("github-issues")
+        );
+        assert_eq!(name("How's the /Weather?"), Some("weather"));
+        assert_eq!(name("How's the weather?"), None);
+        assert_eq!(name("Use github-issues to file this"), None);
+        assert_eq!(name("/weatherproof jacket"), None);
+        assert_eq!(name("see a/weather"), None);
+    }
+
+    #[test]
+    fn test_resolve_skill_source() {
+        let source =
+            SkillSource::resolve("https://github.com/someone/weather-skill.git", None).unwrap();
+        assert_eq!(source.dir_name, "weather-skill");
+        assert_eq!(source.subdir, None);
+
+        let source = SkillSource::resolve("git@github.com:someone/notes", None).unwrap();
+        assert_eq!(source.dir_name, "notes");
+
+        let source =
+            SkillSource::resolve("weather", Some("https://example.com/skills.git")).unwrap();
+        assert_eq!(source.url, "https://example.com/skills.git");
+        assert_eq!(source.subdir.as_deref(), Some("weather"));
+
+        assert!(SkillSource::resolve("weather", None).is_err());
+        assert!(SkillSource::resolve("../etc", Some("https://example.com/skills.git")).is_err());
+    }
+
+    #[test]
+    fn test_parse_frontmatter_permissions() {
+        let parsed = parse_frontmatter(
+            "name: weather\n\
+             description: Forecasts\n\
+             permissions:\n\
+             \x20 network: [api.weather.gov, \"radar.example.com\"]\n\
+             \x20 env: WEATHER_API_KEY\n\
+             \x20 paths:\n\
+             \x20   - ~/weather\n\
+             \x20   - /tmp/radar/**\n\
+             version: 2\n",
+        );
+        assert_eq!(parsed.name.as_deref(), Some("weather"));
+        assert_eq!(parsed.description.as_deref(), Some("Forecasts"));
+        assert_eq!(parsed.healthcheck, None);
+        assert_eq!(
+            parsed.permissions,
+            SkillPermissions {
+                network: vec!["api.weather.gov".into(), "radar.example.com".into()],
+                env: vec!["WEATHER_API_KEY".into()],
+                paths: vec!["~/weather".into(), "/tmp/radar/**".into()],
+            }
+        );
+        assert_eq!(
+            parsed.permissions.describe(),
+            "Network: api.weather.gov, radar.example.com\n\
+             Environment: WEATHER_API_KEY\n\
+             Paths: ~/weather, /tmp/radar/**"
+        );
+
+        // Without a permissions block, indented keys are ignored
+        let parsed = parse_frontmatter(
+            "name: notes\nhealthcheck: ./check.sh\nmetadata:\n  network: [example.com]\n",
+        );
+        assert!(parsed.permissions.is_empty());
+        assert_eq!(parsed.healthcheck.as_deref(), Some("./check.sh"));
+    }
+
+    #[tokio::test]
+    async fn test_check() {
+        let skill = |healthcheck: &str| Skill {
+            name: "weather".to_string(),
+            description: "Forecasts".to_string(),
+            location: std::env::temp_dir().join("SKILL.md"),
+            permissions: SkillPermissions::default(),
+            healthcheck: Some(healthcheck.to_string()).filter(|c| !c.is_empty()),
+        };
+
+        assert_eq!(check(&skill("")).await, SkillHealth::Unchecked);
+        assert_eq!(check(&skill("true")).await, SkillHealth::Ok);
+        assert_eq!(
+            check(&skill("echo checking; echo 'token expired' >&2; exit 1")).await,
+            SkillHealth::Broken("token expired".to_string())
+        );
+
+        let mut missing_env = skill("true");
+        missing_env.permissions.env = vec!["CICA_TEST_UNSET_VARIABLE".to_string()];
+        assert_eq!(
+            check(&missing_env).await,
+            SkillHealth::Broken("CICA_TEST_UNSET_VARIABLE not set".to_string())
+        );
+    }
+
+    #[test]
+    fn test_health_label() {
+        let broken = SkillHealth::Broken("token expired".to_string());
+        assert_eq!(broken.label(true).as_deref(), Some("broken: token expired"));
+        assert_eq!(broken.label(false).as_deref(), Some("broken"));
+        assert_eq!(SkillHealth::Ok.label(false).as_deref(), Some("OK"));
+        assert_eq!(SkillHealth::Unchecked.label(true), None);
+    }
+
+    #[test]
+    fn test_validate_skill_md() {
+        assert!(
+            validate_skill_md("---\nname: weather\ndescription: Forecasts\n---\n# Weather").is_ok()
+        );
+        assert!(validate_skill_md("---\nname: weather\n---\n").is_err());
+        assert!(validate_skill_md("# Weather").is_err());
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(escape_xml("hello"), "hello");
+        assert_eq!(escape_xml("a < b"), "a &lt; b");
+        assert_eq!(escape_xml("a & b"), "a &amp; b");
+    }
+}
@@ -0,0 +1,601 @@
+//! SQLite database behind the pairing and cron stores, transcripts, user facts,
+//! usage counters and the context already sent to each session (cica.db).
+//!
+//! Several tasks read-modify-write the stores (every message, every cron tick,
+//! the CLI), so changes run in an immediate transaction that reloads the latest
+//! state first, and save only the rows that changed. Stores from older
+//! versions (pairing.json, cron.json) are imported on first use and renamed
+//! to `*.migrated`.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use rusqlite::types::Value;
+use rusqlite::{Connection, TransactionBehavior, params_from_iter};
+use serde::de::DeserializeOwned;
+use tracing::info;
+
+use crate::config;
+use crate::cron::CronStore;
+use crate::pairing::PairingStore;
+
+/// How long to wait for another writer before giving up
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most connections kept open between reads and writes
+const MAX_IDLE_CONNECTIONS: usize = 4;
+
+/// Open connections not in use, by database file
+static IDLE: Mutex<Vec<(PathBuf, Connection)>> = Mutex::new(Vec::new());
+
+/// Open the database, creating tables and importing old JSON stores if needed
+pub fn open() -> Result<Connection> {
+    let paths = config::paths()?;
+    let conn = open_at(&paths.db_file)?;
+
+    let cron_file = paths.base.join("cron.json");
+    if paths.pairing_file.exists() || cron_file.exists() {
+        migrate_json(conn, &paths.pairing_file, &cron_file)
+    } else {
+        Ok(conn)
+    }
+}
+
+/// Read from the database
+pub fn read<T>(f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+    with_connection(|conn| f(conn))
+}
+
+/// Change the database in a transaction that holds the write lock throughout,
+/// so reads inside `f` see the latest state. Nothing is written if `f` fails.
+pub fn write<T>(f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+    with_connection(|conn| in_transaction(conn, f))
+}
+
+/// Move everything in the write-ahead log into the database file, e.g.
+/// before exiting
+pub fn checkpoint() -> Result<()> {
+    with_connection(|conn| {
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        Ok(())
+    })
+}
+
+/// Run `f` with an idle connection, or a new one if none is free (e.g. a
+/// read inside a write), and keep it open for the next caller
+fn with_connection<T>(f: impl FnOnce(&mut Connection) -> Result<T>) -> Result<T> {
+    let db_file = config::paths()?.db_file;
+    let idle = IDLE.lock().ok().and_then(|mut idle| {
+        let position = idle.iter().position(|(path, _)| *path == db_file)?;
+        Some(idle.swap_remove(position).1)
+    });
+    let mut conn = match idle {
+        Some(conn) => conn,
+        None => open()?,
+    };
+
+    let result = f(&mut conn);
+
+    if let Ok(mut idle) = IDLE.lock()
+        && idle.len() < MAX_IDLE_CONNECTIONS
+    {
+        idle.push((db_file, conn));
+    }
+    result
+}
+
+/// A table's rows by key, each with its key columns' values first
+pub(crate) type Rows = BTreeMap<String, Vec<Value>>;
+
+/// A table as a store would save it
+pub(crate) struct Table {
+    pub name: &'static str,
+    /// Columns that identify a row
+    pub keys: &'static [&'static str],
+    /// The other columns
+    pub columns: &'static [&'static str],
+    pub rows: Rows,
+}
+
+impl Table {
+    pub fn new(
+        name: &'static str,
+        keys: &'static [&'static str],
+        columns: &'static [&'static str],
+    ) -> Self {
+        Self {
+            name,
+            keys,
+            columns,
+            rows: Rows::new(),
+        }
+    }
+
+    /// Add a row: its key columns' values, then the other columns'
+    pub fn push(&mut self, row: Vec<Value>) {
+        let key = format!("{:?}", &row[..self.keys.len()]);
+        self.rows.insert(key, row);
+    }
+}
+
+/// Save the changes from `before` to `after` (the same tables, in order):
+/// rows that went are deleted, changed ones updated and new ones inserted.
+/// Returns how many rows changed.
+pub(crate) fn write_changes(conn: &Connection, before: &[Table], after: &[Table]) -> Result<usize> {
+    let mut changed = 0;
+    for (before, after) in before.iter().zip(after) {
+        let key_match = after
+            .keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| format!("{} = ?{}", key, i + 1))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+        let keys = after.keys.len();
+
+        let mut delete =
+            conn.prepare_cached(&format!("DELETE FROM {} WHERE {}", after.name, key_match))?;
+        for (key, row) in &before.rows {
+            if !after.rows.contains_key(key) {
+                changed += delete.execute(params_from_iter(&row[..keys]))?;
+            }
+        }
+
+        let all_columns: Vec<&str> = after.keys.iter().chain(after.columns).copied().collect();
+        let mut insert = conn.prepare_cached(&format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            after.name,
+            all_columns.join(", "),
+            (1..=all_columns.len())
+                .map(|i| format!("?{}", i))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))?;
+        let mut update = (!after.columns.is_empty())
+            .then(|| {
+                let set = after
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| format!("{} = ?{}", column, keys + i + 1))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                conn.prepare_cached(&format!(
+                    "UPDATE {} SET {} WHERE {}",
+                    after.name, set, key_match
+                ))
+            })
+            .transpose()?;
+        for (key, row) in &after.rows {
+            if before.rows.get(key) == Some(row) {
+                continue;
+            }
+            // Updated in place, so rows keep their order
+            let updated = match (&mut update, before.rows.contains_key(key)) {
+                (Some(update), true) => update.execute(params_from_iter(row))?,
+                _ => 0,
+            };
+            if updated == 0 {
+                insert.execute(params_from_iter(row))?;
+            }
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+fn open_at(path: &Path) -> Result<Connection> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let conn =
+        Connection::open(path).with_context(|| format!("Failed to open database: {:?}", path))?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    init_schema(&conn)?;
+
+    Ok(conn)
+}
+
+fn in_transaction<T>(conn: &mut Connection, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let result = f(&tx)?;
+    tx.commit()?;
+    Ok(result)
+}
+
+/// Create the store tables
+pub(crate) fn init_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS pairing_requests (
+            code TEXT NOT NULL,
+            channel TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            username TEXT,
+            display_name TEXT,
+            created_at INTEGER NOT NULL,
+            prompts INTEGER NOT NULL DEFAULT 0,
+            expired INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE TABLE IF NOT EXISTS approved_users (
+            channel TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            PRIMARY KEY (channel, user_id)
+        );
+
+        CREATE TABLE IF NOT EXISTS blocked_users (
+            channel TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            PRIMARY KEY (channel, user_id)
+        );
+
+        CREATE TABLE IF NOT EXISTS sessions (
+            channel TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            PRIMARY KEY (channel, user_id)
+        );
+
+        CREATE TABLE IF NOT EXISTS roles (
+            channel TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            role TEXT NOT NULL,
+            PRIMARY KEY (channel, user_id)
+        );
+
+        CREATE TABLE IF NOT EXISTS user_profiles (
+            channel TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            profile TEXT NOT NULL,
+            PRIMARY KEY (channel, user_id)
+        );
+
+        CREATE TABLE IF NOT EXISTS cron_jobs (
+            id TEXT PRIMARY KEY,
+            channel TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            job TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_cron_jobs_user ON cron_jobs(channel, user_id);
+
+        CREATE TABLE IF NOT EXISTS cron_templates (
+            name TEXT PRIMARY KEY COLLATE NOCASE,
+            created_by TEXT NOT NULL,
+            job TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS cron_settings (
+            name TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS messages (
+            id INTEGER PRIMARY KEY,
+            channel TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            speaker TEXT NOT NULL,
+            text TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_messages_user ON messages(channel, user_id, created_at);
+
+        CREATE TABLE IF NOT EXISTS context_sections (
+            channel TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            heading TEXT NOT NULL,
+            hash TEXT NOT NULL,
+            PRIMARY KEY (session_id, heading)
+        );
+
+        CREATE TABLE IF NOT EXISTS facts (
+            channel TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            key TEXT NOT NULL,
+            value TEXT NOT NULL,
+            updated_at INTEGER NOT NULL,
+            PRIMARY KEY (channel, user_id, key)
+        );
+
+        CREATE TABLE IF NOT EXISTS counters (
+            name TEXT PRIMARY KEY,
+            value INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS chat_history (
+            id INTEGER PRIMARY KEY,
+            session_id TEXT NOT NULL,
+            role TEXT NOT NULL,
+            content TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_chat_history_session ON chat_history(session_id, id);
+
+        CREATE TABLE IF NOT EXISTS feedback (
+            id INTEGER PRIMARY KEY,
+            channel TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            rating TEXT NOT NULL,
+            note TEXT,
+            reply TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS held_messages (
+            id INTEGER PRIMARY KEY,
+            channel TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            message TEXT NOT NULL,
+            files TEXT NOT NULL,
+            held_at INTEGER NOT NULL
+        );
+        "#,
+    )?;
+
+    // Columns added after the table was first created
+    add_column(conn, "sessions", "backend", "TEXT")?;
+    add_column(conn, "sessions", "started_at", "INTEGER")?;
+    add_column(
+        conn,
+        "pairing_requests",
+        "silenced",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column(
+        conn,
+        "pairing_requests",
+        "denied",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+
+    Ok(())
+}
+
+/// Add a column to an existing table unless it's already there
+fn add_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+        [table, column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))?;
+    }
+
+    Ok(())
+}
+
+/// Import pairing.json and cron.json into the database, then rename them
+fn migrate_json(mut conn: Connection, pairing_file: &Path, cron_file: &Path) -> Result<Connection> {
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+    // Another process may have migrated while we waited for the lock
+    let mut migrated = Vec::new();
+    if pairing_file.exists() {
+        read_json::<PairingStore>(pairing_file)?.write_to(&tx)?;
+        migrated.push(pairing_file);
+    }
+    if cron_file.exists() {
+        read_json::<CronStore>(cron_file)?.write_to(&tx)?;
+        migrated.push(cron_file);
+    }
+    tx.commit()?;
+
+    for path in migrated {
+        let mut backup = path.as_os_str().to_os_string();
+        backup.push(".migrated");
+        std::fs::rename(path, &backup)
+            .with_context(|| format!("Failed to rename {:?} after migrating it", path))?;
+        info!("Migrated {:?} into the database", path);
+    }
+
+    Ok(conn)
+}
+
+/// Read a JSON store from an older version
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let content =
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse {:?}", path))
+}
+
+/// Split a "channel:user_id" key
+pub(crate) fn split_user_key(key: &str) -> (&str, &str) {
+    key.split_once(':').unwrap_or((key, ""))
+}
+
+/// Write a file via a temporary file and rename, so readers never see it half-written
+pub fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(format!(".tmp-{}", std::process::id()));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    std::fs::write(&tmp_path, content)
+        .with_context(|| format!("Failed to write {:?}", tmp_path))?;
+    std::fs::rename(&tmp_path, path).with_context(|| format!("Failed to replace {:?}", path))?;
+
+    Ok(())
+}
+
+/// Like [`write_atomic`], for files holding secrets: only the owner can
+/// read them (on Unix)
+pub fn write_private(path: &Path, content: &str) -> Result<()> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // Unique per write, since queries can write the same file at once
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(format!(
+        ".tmp-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&tmp_path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .with_context(|| format!("Failed to write {:?}", tmp_path))?;
+    std::fs::rename(&tmp_path, path).with_context(|| format!("Failed to replace {:?}", path))?;
+
+    Ok(())
+}
+
+/// Create or truncate a file only the owner can read (on Unix), including
+/// one that already existed with wider permissions
+pub fn create_private(path: &Path) -> Result<std::fs::File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options
+        .open(path)
+        .with_context(|| format!("Failed to create {:?}", path))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_json() {
+        let dir = std::env::temp_dir().join(format!("cica-storage-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pairing_file = dir.join("pairing.json");
+        let cron_file = dir.join("cron.json");
+        std::fs::write(
+            &pairing_file,
+            r#"{"pending": [], "approved": {"telegram": ["123"]}, "sessions": {"telegram:123": "abc"}}"#,
+        )
+        .unwrap();
+
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let conn = migrate_json(conn, &pairing_file, &cron_file).unwrap();
+
+        let store = PairingStore::read_from(&conn).unwrap();
+        assert!(store.is_approved("telegram", "123"));
+        assert_eq!(
+            store.sessions.get("telegram:123").map(String::as_str),
+            Some("abc")
+        );
+        assert!(!pairing_file.exists());
+        assert!(dir.join("pairing.json.migrated").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_writers_keep_each_others_changes() {
+        let dir = std::env::temp_dir().join(format!("cica-writers-test-{}", std::process::id()));
+        let db_file = dir.join("cica.db");
+        open_at(&db_file).unwrap();
+
+        // Each writer reads the store, adds a user and writes the whole store back,
+        // like PairingStore::update does for every message on every channel
+        let writers: Vec<_> = (0..8)
+            .map(|i| {
+                let db_file = db_file.clone();
+                std::thread::spawn(move || {
+                    let mut conn = open_at(&db_file).unwrap();
+                    in_transaction(&mut conn, |conn| {
+                        let mut store = PairingStore::read_from(conn)?;
+                        store
+                            .approved
+                            .entry("telegram".to_string())
+                            .or_default()
+                            .push(i.to_string());
+                        store.write_to(conn)
+                    })
+                    .unwrap();
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let store = PairingStore::read_from(&open_at(&db_file).unwrap()).unwrap();
+        assert_eq!(store.approved["telegram"].len(), 8);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_changes() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE notes (id TEXT, body TEXT)")
+            .unwrap();
+        let table = |rows: &[(&str, &str)]| {
+            let mut table = Table::new("notes", &["id"], &["body"]);
+            for (id, body) in rows {
+                table.push(vec![id.to_string().into(), body.to_string().into()]);
+            }
+            table
+        };
+        let stored = || -> Vec<(String, String)> {
+            let mut stmt = conn
+                .prepare("SELECT id, body FROM notes ORDER BY rowid")
+                .unwrap();
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .unwrap()
+                .collect::<rusqlite::Result<_>>()
+                .unwrap()
+        };
+
+        let first = table(&[("a", "1"), ("b", "2"), ("c", "3")]);
+        assert_eq!(write_changes(&conn, &[table(&[])], &[first]).unwrap(), 3);
+
+        let before = table(&[("a", "1"), ("b", "2"), ("c", "3")]);
+        let after = table(&[("a", "changed"), ("c", "3"), ("d", "4")]);
+        assert_eq!(write_changes(&conn, &[before], &[after]).unwrap(), 3);
+        // Updated rows keep their place
+        assert_eq!(
+            stored(),
+            [("a", "changed"), ("c", "3"), ("d", "4")]
+                .map(|(id, body)| (id.to_string(), body.to_string()))
+        );
+
+        let same = table(&[("a", "changed"), ("c", "3"), ("d", "4")]);
+        let again = table(&[("a", "changed"), ("c", "3"), ("d", "4")]);
+        assert_eq!(write_changes(&conn, &[same], &[again]).unwrap(), 0);
+    }
+}
@@ -0,0 +1,215 @@
+//! Conversation transcripts: what each user said and what Cica replied.
+
+use std::time::SystemTime;
+
+use anyhow::Result;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::storage;
+
+/// Who sent a transcript message
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Speaker {
+    User,
+    Assistant,
+}
+
+impl Speaker {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Speaker::User => "user",
+            Speaker::Assistant => "assistant",
+        }
+    }
+}
+
+/// A message in a user's transcript
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptMessage {
+    pub channel: String,
+    pub user_id: String,
+    pub speaker: Speaker,
+    pub text: String,
+    pub created_at: u64, // Unix timestamp
+}
+
+/// Append a message to a user's transcript. Failures are logged, not returned,
+/// so a database problem never stops a reply.
+pub fn record(channel: &str, user_id: &str, speaker: Speaker, text: &str) {
+    let result = storage::write(|conn| {
+        insert(conn, channel, user_id, speaker, text, now_timestamp())?;
+        Ok(())
+    });
+
+    if let Err(e) = result {
+        warn!("Failed to record transcript message: {}", e);
+    }
+}
+
+/// A user's messages since a Unix timestamp, oldest first
+pub fn since(channel: &str, user_id: &str, since: u64) -> Result<Vec<TranscriptMessage>> {
+    storage::read(|conn| query_since(conn, channel, user_id, since))
+}
+
+/// A user's last `limit` messages, oldest first
+pub fn recent(channel: &str, user_id: &str, limit: usize) -> Result<Vec<TranscriptMessage>> {
+    storage::read(|conn| query_recent(conn, channel, user_id, limit))
+}
+
+/// The last thing a user said
+pub fn last_from_user(channel: &str, user_id: &str) -> Result<Option<String>> {
+    storage::read(|conn| query_last(conn, channel, user_id, Speaker::User))
+}
+
+/// The last reply a user got
+pub fn last_reply(channel: &str, user_id: &str) -> Result<Option<String>> {
+    storage::read(|conn| query_last(conn, channel, user_id, Speaker::Assistant))
+}
+
+fn insert(
+    conn: &Connection,
+    channel: &str,
+    user_id: &str,
+    speaker: Speaker,
+    text: &str,
+    created_at: u64,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO messages (channel, user_id, speaker, text, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![channel, user_id, speaker.as_str(), text, created_at as i64],
+    )?;
+    Ok(())
+}
+
+fn query_since(
+    conn: &Connection,
+    channel: &str,
+    user_id: &str,
+    since: u64,
+) -> Result<Vec<TranscriptMessage>> {
+    let mut stmt = conn.prepare(
+        "SELECT speaker, text, created_at FROM messages
+         WHERE channel = ?1 AND user_id = ?2 AND created_at >= ?3
+         ORDER BY id",
+    )?;
+    let rows = stmt.query_map(params![channel, user_id, since as i64], read_row)?;
+
+    collect_messages(channel, user_id, rows)
+}
+
+fn query_recent(
+    conn: &Connection,
+    channel: &str,
+    user_id: &str,
+    limit: usize,
+) -> Result<Vec<TranscriptMessage>> {
+    let mut stmt = conn.prepare(
+        "SELECT speaker, text, created_at FROM messages
+         WHERE channel = ?1 AND user_id = ?2
+         ORDER BY id DESC LIMIT ?3",
+    )?;
+    let rows = stmt.query_map(params![channel, user_id, limit as i64], read_row)?;
+
+    let mut messages = collect_messages(channel, user_id, rows)?;
+    messages.reverse();
+    Ok(messages)
+}
+
+fn query_last(
+    conn: &Connection,
+    channel: &str,
+    user_id: &str,
+    speaker: Speaker,
+) -> Result<Option<String>> {
+    let text = conn
+        .query_row(
+            "SELECT text FROM messages
+             WHERE channel = ?1 AND user_id = ?2 AND speaker = ?3
+             ORDER BY id DESC LIMIT 1",
+            params![channel, user_id, speaker.as_str()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(text)
+}
+
+fn read_row(row: &rusqlite::Row) -> rusqlite::Result<(String, String, i64)> {
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+}
+
+fn collect_messages(
+    channel: &str,
+    user_id: &str,
+    rows: impl Iterator<Item = rusqlite::Result<(String, String, i64)>>,
+) -> Result<Vec<TranscriptMessage>> {
+    let mut messages = Vec::new();
+    for row in rows {
+        let (speaker, text, created_at) = row?;
+        messages.push(TranscriptMessage {
+            channel: channel.to_string(),
+            user_id: user_id.to_string(),
+            speaker: if speaker == "assistant" {
+                Speaker::Assistant
+            } else {
+                Speaker::User
+            },
+            text,
+            created_at: created_at as u64,
+        });
+    }
+
+    Ok(messages)
+}
+
+fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages_since() {
+        let conn = Connection::open_in_memory().unwrap();
+        storage::init_schema(&conn).unwrap();
+
+        insert(&conn, "telegram", "1", Speaker::User, "old", 100).unwrap();
+        insert(&conn, "telegram", "1", Speaker::User, "hi", 200).unwrap();
+        insert(&conn, "telegram", "1", Speaker::Assistant, "hello", 201).unwrap();
+        insert(&conn, "telegram", "2", Speaker::User, "other user", 200).unwrap();
+
+        let messages = query_since(&conn, "telegram", "1", 150).unwrap();
+        let texts: Vec<_> = messages.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, vec!["hi", "hello"]);
+        assert_eq!(messages[1].speaker, Speaker::Assistant);
+
+        let messages = query_recent(&conn, "telegram", "1", 2).unwrap();
+        let texts: Vec<_> = messages.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, vec!["hi", "hello"]);
+
+        assert_eq!(
+            query_last(&conn, "telegram", "1", Speaker::User)
+                .unwrap()
+                .as_deref(),
+            Some("hi")
+        );
+        assert_eq!(
+            query_last(&conn, "telegram", "1", Speaker::Assistant)
+                .unwrap()
+                .as_deref(),
+            Some("hello")
+        );
+        assert_eq!(
+            query_last(&conn, "slack", "1", Speaker::User).unwrap(),
+            None
+        );
+    }
+}
@@ -1,14 +1,27 @@
 use anyhow::Result;
+use dialoguer::{Select, theme::ColorfulTheme};
+use std::time::SystemTime;
 use tracing::info;
 
-use crate::channels;
-use crate::pairing::PairingStore;
+use crate::cmd::pending::format_age;
+use cica_core::channels;
+use cica_core::config::{Config, OwnerConfig};
+use cica_core::pairing::{self, PairingStore, PendingRequest};
 
 /// Run the approve command
-pub fn run(code: &str) -> Result<()> {
+pub fn run(code: &str, make_owner: bool) -> Result<()> {
     let mut store = PairingStore::load()?;
 
-    let request = store.approve(code)?;
+    let request = match store.approve(code) {
+        Ok(request) => request,
+        Err(e) => match store.find_expired(code).cloned() {
+            Some(expired) => match renew_and_approve(&mut store, &expired)? {
+                Some(request) => request,
+                None => return Ok(()),
+            },
+            None => return Err(e),
+        },
+    };
 
     let channel_display = channels::get_channel_info(&request.channel)
         .map(|c| c.display_name)
@@ -28,5 +41,59 @@ pub fn run(code: &str) -> Result<()> {
         request.channel, request.user_id, user_display
     );
 
+    if make_owner {
+        let mut config = Config::load()?;
+        config.owner = Some(OwnerConfig {
+            channel: request.channel.clone(),
+            user_id: request.user_id.clone(),
+        });
+        config.save()?;
+
+        println!("{} will now receive system notifications.", user_display);
+        println!("Restart Cica for this to take effect.");
+    }
+
     Ok(())
 }
+
+/// Explain that a code expired and offer to approve the user under a new one
+fn renew_and_approve(
+    store: &mut PairingStore,
+    expired: &PendingRequest,
+) -> Result<Option<PendingRequest>> {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_secs();
+    let ttl_secs = pairing::code_ttl().as_secs();
+    let expired_for = now.saturating_sub(expired.created_at + ttl_secs);
+
+    let user_display = expired
+        .display_name
+        .as_ref()
+        .or(expired.username.as_ref())
+        .unwrap_or(&expired.user_id);
+
+    println!(
+        "Pairing code {} from {} expired {} (codes are valid for {} minutes).",
+        expired.code,
+        user_display,
+        format_age(expired_for),
+        ttl_secs / 60
+    );
+
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Issue a new code and approve them now?")
+        .items(&["Yes", "No"])
+        .default(0)
+        .interact()?;
+
+    if selection != 0 {
+        println!("They'll get a new code the next time they message Cica.");
+        return Ok(None);
+    }
+
+    let renewed = store.renew_expired(&expired.code)?;
+    println!("Issued new code {}", renewed.code);
+
+    Ok(Some(store.approve(&renewed.code)?))
+}
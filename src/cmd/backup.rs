@@ -0,0 +1,40 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use cica_core::backup;
+
+/// Run the backup command
+pub fn run(path: &Path, with_secrets: bool) -> Result<()> {
+    let manifest = backup::create(path, with_secrets)?;
+
+    println!("Backed up Cica to {}", path.display());
+    if manifest.with_secrets {
+        println!("The backup holds your tokens and API keys in plaintext. Keep it somewhere safe.");
+    } else {
+        println!("Tokens and API keys were left out; add --with-secrets to include them.");
+    }
+
+    Ok(())
+}
+
+/// Run the restore command
+pub fn restore(path: &Path, force: bool) -> Result<()> {
+    let manifest = backup::restore(path, force)?;
+
+    println!(
+        "Restored a backup made by Cica {} from {}",
+        manifest.version,
+        path.display()
+    );
+    if manifest.with_secrets {
+        println!(
+            "Tokens and API keys are in plaintext in config.toml. Move them with `cica secrets keyring` or `cica secrets passphrase`."
+        );
+    } else {
+        println!("The backup has no tokens or API keys. Run `cica init` to enter them again.");
+    }
+    println!("The memory search index is rebuilt the next time Cica starts.");
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use anyhow::{Result, bail};
+use tracing::info;
+
+use cica_core::channels;
+use cica_core::pairing::PairingStore;
+
+/// Run the block command
+pub fn run(channel: &str, user_id: &str) -> Result<()> {
+    let channel_display = channels::get_channel_info(channel)
+        .map(|c| c.display_name)
+        .unwrap_or(channel);
+
+    let mut store = PairingStore::load()?;
+
+    if !store.block(channel, user_id)? {
+        bail!("{} user {} is already blocked", channel_display, user_id);
+    }
+
+    println!("Blocked {} user: {}", channel_display, user_id);
+    println!("Cica will ignore their messages. Undo with `cica unblock`.");
+
+    info!("Blocked {} user {}", channel, user_id);
+
+    Ok(())
+}
+
+/// Run the unblock command
+pub fn unblock(channel: &str, user_id: &str) -> Result<()> {
+    let channel_display = channels::get_channel_info(channel)
+        .map(|c| c.display_name)
+        .unwrap_or(channel);
+
+    let mut store = PairingStore::load()?;
+
+    if !store.unblock(channel, user_id)? {
+        bail!("{} user {} is not blocked", channel_display, user_id);
+    }
+
+    println!("Unblocked {} user: {}", channel_display, user_id);
+    println!("They can now request pairing again.");
+
+    info!("Unblocked {} user {}", channel, user_id);
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use anyhow::Result;
+use tracing::info;
+
+use crate::cmd::deps::format_size;
+use cica_core::attachments;
+use cica_core::config::Config;
+
+/// Run the clean command
+pub fn run(dry_run: bool) -> Result<()> {
+    let policy = Config::load()?.attachments;
+    let report = attachments::clean(&policy, dry_run)?;
+
+    if report.files == 0 {
+        println!("No attachments to clean up.");
+    } else if dry_run {
+        println!(
+            "Would remove {} attachment(s), reclaiming {}",
+            report.files,
+            format_size(report.bytes)
+        );
+    } else {
+        println!(
+            "Removed {} attachment(s), reclaimed {}",
+            report.files,
+            format_size(report.bytes)
+        );
+        info!(
+            "Removed {} old attachment(s), {} bytes",
+            report.files, report.bytes
+        );
+    }
+
+    // What a dry run would leave
+    let kept = attachments::total_size()?.saturating_sub(if dry_run { report.bytes } else { 0 });
+    let age = match policy.max_age_days {
+        0 => "any age".to_string(),
+        days => format!("up to {} days old", days),
+    };
+    let size = match policy.max_size_mb {
+        0 => "no size limit".to_string(),
+        mb => format!("up to {} in total", format_size(mb * 1024 * 1024)),
+    };
+    println!(
+        "{} of attachments kept ({}, {}; see [attachments] in config.toml)",
+        format_size(kept),
+        age,
+        size
+    );
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use anyhow::{Result, bail};
+use clap::Subcommand;
+
+use crate::cmd::features;
+use cica_core::config::{Config, paths};
+
+/// Config subcommands
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Check config.toml for unknown keys, missing fields and paths that don't exist
+    Validate,
+}
+
+/// Run a config subcommand
+pub fn run(command: ConfigCommand) -> Result<()> {
+    match command {
+        ConfigCommand::Validate => validate(),
+    }
+}
+
+fn validate() -> Result<()> {
+    if !Config::exists()? {
+        bail!("Cica isn't set up yet; run cica init first");
+    }
+
+    let mut problems = Config::validate()?;
+    // What's configured that this build can't do
+    if let Ok(config) = Config::load() {
+        problems.extend(features::missing_for_config(&config));
+    }
+
+    let path = paths()?.config_file;
+    if problems.is_empty() {
+        println!("{} is valid", path.display());
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("- {}", problem);
+    }
+    bail!("Found {} problem(s) in {}", problems.len(), path.display())
+}
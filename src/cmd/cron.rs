@@ -0,0 +1,142 @@
+use anyhow::{Result, bail};
+use clap::Subcommand;
+
+use cica_core::cron::{CronJob, CronStore, format_runs, format_timestamp, store};
+
+use super::output::print_json;
+
+/// Cron subcommands
+#[derive(Subcommand)]
+pub enum CronCommand {
+    /// List scheduled jobs
+    List {
+        /// Only show jobs of <channel>:<user_id> (e.g., "telegram:123456")
+        user: Option<String>,
+    },
+    /// Show a job's recent runs
+    Log {
+        /// Job ID or a unique prefix of it
+        id: String,
+    },
+    /// Stop running all jobs, keeping their settings (e.g., during maintenance)
+    PauseAll,
+    /// Start running jobs again; runs missed while paused wait for their next time
+    ResumeAll,
+}
+
+/// Run a cron subcommand
+pub fn run(command: CronCommand, json: bool) -> Result<()> {
+    match command {
+        CronCommand::List { user } => list(user.as_deref(), json),
+        CronCommand::Log { id } => log(&id, json),
+        CronCommand::PauseAll => pause_all(),
+        CronCommand::ResumeAll => resume_all(),
+    }
+}
+
+/// Pause the whole scheduler
+fn pause_all() -> Result<()> {
+    if store::pause_all(store::now_millis())? {
+        println!("Paused all cron jobs. Resume with: cica cron resume-all");
+    } else {
+        println!("Cron jobs are already paused.");
+    }
+    Ok(())
+}
+
+/// Resume the whole scheduler
+fn resume_all() -> Result<()> {
+    match store::resume_all(store::now_millis())? {
+        None => println!("Cron jobs aren't paused."),
+        Some(0) => println!("Resumed all cron jobs."),
+        Some(missed) => println!(
+            "Resumed all cron jobs. {} run(s) missed while paused will wait for their next time.",
+            missed
+        ),
+    }
+    Ok(())
+}
+
+/// Show the recent runs of the job with ID (prefix) `id`
+fn log(id: &str, json: bool) -> Result<()> {
+    let store = CronStore::load()?;
+
+    let matches: Vec<&CronJob> = store
+        .jobs
+        .values()
+        .filter(|job| job.id.starts_with(id))
+        .collect();
+    let job = match matches.as_slice() {
+        [job] => *job,
+        [] => bail!("Job not found: {}", id),
+        _ => bail!("Ambiguous job ID '{}', use more characters", id),
+    };
+
+    if json {
+        return print_json(&job.state.runs);
+    }
+
+    println!("{}", format_runs(job));
+    Ok(())
+}
+
+/// List jobs, optionally for one user
+fn list(user: Option<&str>, json: bool) -> Result<()> {
+    let store = CronStore::load()?;
+
+    let mut jobs: Vec<&CronJob> = match user {
+        Some(user) => {
+            let Some((channel, user_id)) = user.split_once(':') else {
+                bail!("Expected <channel>:<user_id>, got: {}", user);
+            };
+            store.list_for_user(channel, user_id)
+        }
+        None => store.jobs.values().collect(),
+    };
+    jobs.sort_by(|a, b| (a.user_key(), &a.name).cmp(&(b.user_key(), &b.name)));
+
+    if json {
+        return print_json(&jobs);
+    }
+
+    if jobs.is_empty() {
+        println!("No scheduled jobs.");
+        return Ok(());
+    }
+
+    if let Some(since) = store::paused_since()? {
+        println!(
+            "All jobs paused since {} (cica cron resume-all to restart them)\n",
+            format_timestamp(since)
+        );
+    }
+
+    println!(
+        "{:<10} {:<24} {:<24} {:<10} {:<18} SCHEDULE",
+        "ID", "NAME", "USER", "STATUS", "NEXT RUN"
+    );
+    for job in jobs {
+        let status = if job.enabled {
+            job.state.last_status.as_str()
+        } else {
+            "paused"
+        };
+        let next = job
+            .state
+            .next_run_at
+            .map(format_timestamp)
+            .unwrap_or_else(|| "-".to_string());
+
+        println!(
+            "{:<10} {:<24} {:<24} {:<10} {:<18} {}",
+            job.short_id(),
+            job.name,
+            job.user_key(),
+            status,
+            next,
+            job.schedule.description()
+        );
+    }
+
+    Ok(())
+}
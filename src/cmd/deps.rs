@@ -0,0 +1,216 @@
+use anyhow::{Result, bail};
+use clap::Subcommand;
+use serde::Serialize;
+use std::path::PathBuf;
+use tracing::info;
+
+use cica_core::config::{self, Config};
+use cica_core::setup::{self, Dep};
+
+use super::output::print_json;
+
+/// Dependency subcommands
+#[derive(Subcommand)]
+pub enum DepsCommand {
+    /// Show the installed and wanted versions of Bun, Claude Code, Cursor CLI, Java and signal-cli
+    List,
+    /// Install what the config needs at the wanted versions
+    Update {
+        /// Move Claude Code to the newest release on npm (kept in config.toml)
+        #[arg(long, conflicts_with = "pinned")]
+        latest: bool,
+        /// Go back to the Claude Code version this release pins
+        #[arg(long)]
+        pinned: bool,
+    },
+    /// Remove dependencies no configured backend or channel uses
+    Clean {
+        /// Only show what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// A dependency, as printed by `cica deps list --json`
+#[derive(Serialize)]
+struct DepOutput {
+    name: &'static str,
+    status: &'static str,
+    wanted: String,
+    installed: Option<String>,
+    size_bytes: u64,
+    path: Option<PathBuf>,
+}
+
+/// Run a deps subcommand
+pub async fn run(command: DepsCommand, json: bool) -> Result<()> {
+    if !Config::exists()? {
+        bail!("Cica isn't set up yet; run cica init first");
+    }
+    match command {
+        DepsCommand::List => list(json),
+        DepsCommand::Update { latest, pinned } => update(latest, pinned).await,
+        DepsCommand::Clean { dry_run } => clean(dry_run),
+    }
+}
+
+fn list(json: bool) -> Result<()> {
+    let config = Config::load()?;
+    let paths = config::paths()?;
+
+    let deps: Vec<DepOutput> = Dep::ALL
+        .into_iter()
+        .map(|dep| {
+            let wanted = dep.wanted_version(&config).to_string();
+            let installed = dep.installed_version(&paths);
+            let used = dep.is_used(&config);
+            let path = dep.find();
+            let status = match &installed {
+                // Not ours: on PATH, or provided by a container image
+                None if path.is_some() => "external",
+                None if used => "missing",
+                None => "not installed",
+                Some(_) if !used => "unused",
+                Some(version) if *version != wanted => "outdated",
+                Some(_) => "ok",
+            };
+            DepOutput {
+                name: dep.name(),
+                status,
+                wanted,
+                installed,
+                size_bytes: setup::dir_size(&dep.dir(&paths)),
+                path,
+            }
+        })
+        .collect();
+
+    if json {
+        return print_json(&deps);
+    }
+
+    println!(
+        "{:<12} {:<20} {:<20} {:<14} SIZE",
+        "NAME", "INSTALLED", "WANTED", "STATUS"
+    );
+    for dep in &deps {
+        let installed = dep.installed.as_deref().unwrap_or("-");
+        let size = if dep.size_bytes > 0 {
+            format_size(dep.size_bytes)
+        } else {
+            String::new()
+        };
+        println!(
+            "{:<12} {:<20} {:<20} {:<14} {}",
+            dep.name, installed, dep.wanted, dep.status, size
+        );
+    }
+    if deps.iter().any(|dep| dep.status == "unused") {
+        println!();
+        println!("Run cica deps clean to remove unused dependencies");
+    }
+    if deps
+        .iter()
+        .any(|dep| matches!(dep.status, "outdated" | "missing"))
+    {
+        println!();
+        println!("Run cica deps update to install the wanted versions");
+    }
+    Ok(())
+}
+
+async fn update(latest: bool, pinned: bool) -> Result<()> {
+    let mut config = Config::load()?;
+    if latest {
+        let version = setup::latest_claude_code_version().await?;
+        println!("Latest Claude Code is {}", version);
+        config.claude.code_version = Some(version);
+        config.save()?;
+    } else if pinned && config.claude.code_version.take().is_some() {
+        config.save()?;
+    }
+
+    let paths = config::paths()?;
+    let used: Vec<Dep> = Dep::ALL
+        .into_iter()
+        .filter(|dep| dep.is_used(&config))
+        .collect();
+    let before: Vec<Option<String>> = used
+        .iter()
+        .map(|dep| dep.installed_version(&paths))
+        .collect();
+    setup::ensure_concurrently(&config, &used, false, |_| {}).await?;
+
+    let mut changed = false;
+    for (dep, before) in used.into_iter().zip(before) {
+        let after = dep.installed_version(&paths);
+        if before != after {
+            changed = true;
+            println!(
+                "{}: {} -> {}",
+                dep.name(),
+                before.as_deref().unwrap_or("none"),
+                after.as_deref().unwrap_or("external")
+            );
+            info!("Updated {} to {:?}", dep.name(), after);
+        } else {
+            println!("{}: up to date", dep.name());
+        }
+    }
+
+    if changed {
+        println!();
+        println!("Restart Cica to use the new versions");
+    }
+    Ok(())
+}
+
+fn clean(dry_run: bool) -> Result<()> {
+    let config = Config::load()?;
+    let paths = config::paths()?;
+
+    let mut removed = 0;
+    let mut freed = 0;
+    for dep in Dep::ALL {
+        let dir = dep.dir(&paths);
+        if dep.is_used(&config) || !dir.exists() {
+            continue;
+        }
+        removed += 1;
+        if dry_run {
+            let size = setup::dir_size(&dir);
+            freed += size;
+            println!("Would remove {} ({})", dep.name(), format_size(size));
+        } else {
+            let size = dep.remove()?;
+            freed += size;
+            println!("Removed {} ({})", dep.name(), format_size(size));
+            info!("Removed unused dependency {}", dep.name());
+        }
+    }
+
+    if removed == 0 {
+        println!("No unused dependencies");
+    } else if dry_run {
+        println!("{} would be freed", format_size(freed));
+    } else {
+        println!("Freed {}", format_size(freed));
+    }
+    Ok(())
+}
+
+/// Format a byte count for people, e.g. "120.4 MB"
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
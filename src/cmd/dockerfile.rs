@@ -0,0 +1,13 @@
+use anyhow::{Result, bail};
+
+use cica_core::config::Config;
+use cica_core::setup;
+
+/// Run the print-dockerfile command
+pub fn run() -> Result<()> {
+    if !Config::exists()? {
+        bail!("Cica isn't set up yet; run cica init first");
+    }
+    print!("{}", setup::dockerfile(&Config::load()?));
+    Ok(())
+}
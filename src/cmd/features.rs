@@ -0,0 +1,122 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use cica_core::config::{AiBackend, Config};
+use cica_core::secrets::SecretStore;
+
+use super::output::print_json;
+
+/// Output of `cica features --json`
+#[derive(Serialize)]
+struct FeaturesOutput {
+    version: &'static str,
+    features: &'static [Capability],
+    warnings: Vec<String>,
+}
+
+/// An optional part of Cica, gated by a cargo feature
+#[derive(Serialize)]
+pub struct Capability {
+    /// Cargo feature name
+    pub feature: &'static str,
+    pub description: &'static str,
+    pub enabled: bool,
+}
+
+/// Optional features and whether this build includes them
+pub const CAPABILITIES: &[Capability] = &[
+    Capability {
+        feature: "signal",
+        description: "Signal channel",
+        enabled: cfg!(feature = "signal"),
+    },
+    Capability {
+        feature: "slack",
+        description: "Slack channel",
+        enabled: cfg!(feature = "slack"),
+    },
+    Capability {
+        feature: "cursor",
+        description: "Cursor CLI backend",
+        enabled: cfg!(feature = "cursor"),
+    },
+    Capability {
+        feature: "memory",
+        description: "Semantic memory search (local embeddings)",
+        enabled: cfg!(feature = "memory"),
+    },
+    Capability {
+        feature: "keyring",
+        description: "OS keyring for tokens and API keys",
+        enabled: cfg!(feature = "keyring"),
+    },
+];
+
+/// Describe configured features this build can't serve
+pub fn missing_for_config(config: &Config) -> Vec<String> {
+    let mut missing = Vec::new();
+
+    let mut require = |configured: bool, feature: &str, what: &str| {
+        let enabled = CAPABILITIES
+            .iter()
+            .any(|c| c.feature == feature && c.enabled);
+        if configured && !enabled {
+            missing.push(format!(
+                "{} is configured, but this build was compiled without the `{}` feature",
+                what, feature
+            ));
+        }
+    };
+
+    require(config.channels.signal.is_some(), "signal", "Signal");
+    require(config.channels.slack.is_some(), "slack", "Slack");
+    require(
+        config.backend == AiBackend::Cursor,
+        "cursor",
+        "The Cursor CLI backend",
+    );
+    require(
+        config.secrets.store == SecretStore::Keyring,
+        "keyring",
+        "Keyring secret storage",
+    );
+
+    missing
+}
+
+/// Run the features command
+pub fn run(json: bool) -> Result<()> {
+    let warnings = if Config::exists()? {
+        missing_for_config(&Config::load()?)
+    } else {
+        Vec::new()
+    };
+
+    if json {
+        return print_json(&FeaturesOutput {
+            version: env!("CARGO_PKG_VERSION"),
+            features: CAPABILITIES,
+            warnings,
+        });
+    }
+
+    println!("Cica {} optional features:", env!("CARGO_PKG_VERSION"));
+    println!();
+    for capability in CAPABILITIES {
+        println!(
+            "  [{}] {:<8} {}",
+            if capability.enabled { "x" } else { " " },
+            capability.feature,
+            capability.description
+        );
+    }
+
+    if !warnings.is_empty() {
+        println!();
+        for problem in warnings {
+            println!("Warning: {}", problem);
+        }
+    }
+
+    Ok(())
+}
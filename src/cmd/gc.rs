@@ -0,0 +1,60 @@
+use anyhow::Result;
+use tracing::info;
+
+use cica_core::pairing::PairingStore;
+
+/// Run the gc command
+pub fn run(dry_run: bool) -> Result<()> {
+    let mut store = PairingStore::load()?;
+    let report = if dry_run {
+        store.prune_orphans()
+    } else {
+        store.update(|store| Ok(store.prune_orphans()))?
+    };
+
+    if report.is_empty() {
+        println!("Nothing to clean up.");
+        return Ok(());
+    }
+
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+
+    if report.expired_requests > 0 {
+        println!(
+            "{} {} expired pairing request(s)",
+            verb, report.expired_requests
+        );
+    }
+    for (what, keys) in [
+        ("session", &report.sessions),
+        ("profile", &report.profiles),
+        ("role", &report.roles),
+    ] {
+        if keys.is_empty() {
+            continue;
+        }
+        println!(
+            "{} {} {}(s) of users who are no longer approved:",
+            verb,
+            keys.len(),
+            what
+        );
+        for key in keys {
+            println!("  {}", key);
+        }
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    info!(
+        "Pruned pairing store: {} expired requests, {} sessions, {} profiles, {} roles",
+        report.expired_requests,
+        report.sessions.len(),
+        report.profiles.len(),
+        report.roles.len()
+    );
+
+    Ok(())
+}
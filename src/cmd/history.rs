@@ -0,0 +1,40 @@
+use anyhow::Result;
+
+use cica_core::channels;
+use cica_core::cron::format_timestamp;
+use cica_core::transcript::{self, Speaker};
+
+use super::output::print_json;
+
+/// Run the history command
+pub fn run(channel: &str, user_id: &str, limit: usize, json: bool) -> Result<()> {
+    let messages = transcript::recent(channel, user_id, limit)?;
+
+    if json {
+        return print_json(&messages);
+    }
+
+    let channel_display = channels::get_channel_info(channel)
+        .map(|c| c.display_name)
+        .unwrap_or(channel);
+
+    if messages.is_empty() {
+        println!("No messages with {} user {}.", channel_display, user_id);
+        return Ok(());
+    }
+
+    for message in messages {
+        let speaker = match message.speaker {
+            Speaker::User => user_id,
+            Speaker::Assistant => "Cica",
+        };
+        println!(
+            "[{}] {}:\n{}\n",
+            format_timestamp(message.created_at * 1000),
+            speaker,
+            message.text.trim_end()
+        );
+    }
+
+    Ok(())
+}
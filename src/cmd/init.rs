@@ -1,14 +1,94 @@
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, bail};
+use clap::Args;
 use dialoguer::{Input, Password, Select, theme::ColorfulTheme};
+#[cfg(feature = "signal")]
+use qrcode::QrCode;
+#[cfg(feature = "signal")]
+use qrcode::render::unicode;
+use std::time::Duration;
 use tracing::info;
 
-use crate::backends::{claude, cursor};
-use crate::channels::{self, signal, slack, telegram};
-use crate::config::{self, AiBackend, Config, SignalConfig, SlackConfig, TelegramConfig};
-use crate::setup;
+#[cfg(feature = "cursor")]
+use cica_core::backends::cursor;
+use cica_core::backends::{self, claude, openai};
+#[cfg(feature = "signal")]
+use cica_core::channels::signal;
+#[cfg(feature = "slack")]
+use cica_core::channels::slack;
+use cica_core::channels::{self, telegram};
+#[cfg(feature = "signal")]
+use cica_core::config::SignalConfig;
+#[cfg(feature = "slack")]
+use cica_core::config::SlackConfig;
+use cica_core::config::{self, AiBackend, Config, TelegramConfig};
+use cica_core::onboarding;
+use cica_core::pairing::PairingStore;
+use cica_core::setup::{self, Dep};
+
+/// Flags for setting up without prompts, e.g. when provisioning a server
+#[derive(Args)]
+pub struct InitArgs {
+    /// Set up this channel without prompts (telegram or slack)
+    #[arg(long)]
+    channel: Option<String>,
+    /// Bot token (Telegram, Slack)
+    #[arg(long, conflicts_with = "bot_token_env")]
+    bot_token: Option<String>,
+    /// Read the bot token from this environment variable
+    #[arg(long, value_name = "VAR")]
+    bot_token_env: Option<String>,
+    /// App-level token (Slack)
+    #[arg(long, conflicts_with = "app_token_env")]
+    app_token: Option<String>,
+    /// Read the app-level token from this environment variable
+    #[arg(long, value_name = "VAR")]
+    app_token_env: Option<String>,
+    /// Set up this AI backend without prompts and make it active (claude, cursor or openai)
+    #[arg(long)]
+    backend: Option<String>,
+    /// API key or setup token for the backend
+    #[arg(long, conflicts_with = "api_key_env")]
+    api_key: Option<String>,
+    /// Read the backend's API key from this environment variable
+    #[arg(long, value_name = "VAR")]
+    api_key_env: Option<String>,
+    /// Model for the backend (default: the backend's)
+    #[arg(long)]
+    model: Option<String>,
+    /// Use Claude Code through Google Vertex AI in this GCP project
+    #[arg(long, value_name = "PROJECT_ID")]
+    vertex_project: Option<String>,
+    /// Vertex AI region (default: europe-west1)
+    #[arg(long, requires = "vertex_project")]
+    vertex_region: Option<String>,
+    /// Service account JSON key file for Vertex AI
+    #[arg(long, value_name = "PATH", requires = "vertex_project")]
+    vertex_credentials: Option<String>,
+    /// Base URL of an OpenAI-compatible API (default: OpenAI's)
+    #[arg(long)]
+    base_url: Option<String>,
+    /// Approve the first person who messages the bot as its owner
+    #[arg(long)]
+    owner_first_user: bool,
+    /// Change an existing config without asking
+    #[arg(long)]
+    yes: bool,
+}
 
 /// Run the init command
-pub async fn run() -> Result<()> {
+pub async fn run(args: InitArgs) -> Result<()> {
+    if args.channel.is_some() || args.backend.is_some() {
+        return headless_setup(args).await;
+    }
+    if args.bot_token.is_some()
+        || args.bot_token_env.is_some()
+        || args.api_key.is_some()
+        || args.api_key_env.is_some()
+        || args.yes
+    {
+        bail!("Pass --channel and/or --backend to set up without prompts");
+    }
+
     let paths = config::paths()?;
 
     println!();
@@ -20,16 +100,12 @@ pub async fn run() -> Result<()> {
         let config = Config::load()?;
         let configured = config.configured_channels();
 
-        if !configured.is_empty() || config.is_claude_configured() || config.is_cursor_configured()
-        {
+        if !configured.is_empty() || !config.configured_backends().is_empty() {
             let mut status = Vec::new();
             if !configured.is_empty() {
                 status.push(format!("Channels: {}", configured.join(", ")));
             }
-            let backend_name = match config.backend {
-                AiBackend::Claude => "Claude Code",
-                AiBackend::Cursor => "Cursor CLI",
-            };
+            let backend_name = config.backend.display_name();
             if config.is_backend_configured() {
                 status.push(format!("AI Backend: {} (configured)", backend_name));
             } else {
@@ -40,14 +116,18 @@ pub async fn run() -> Result<()> {
 
             let mut choices = vec![
                 "Add/configure a channel",
-                "Configure AI backend (Claude Code or Cursor CLI)",
+                "Configure AI backend (Claude Code, Cursor CLI or an OpenAI-compatible API)",
             ];
 
-            let can_switch = config.is_claude_configured() && config.is_cursor_configured();
+            let can_switch = config.configured_backends().len() > 1;
             if can_switch {
                 choices.push("Switch active AI backend");
             }
 
+            if !configured.is_empty() {
+                choices.push("Remove a channel");
+            }
+
             choices.push("Reconfigure from scratch");
             choices.push("Cancel");
 
@@ -61,10 +141,14 @@ pub async fn run() -> Result<()> {
             if selected == "Add/configure a channel" {
                 add_channel(Some(config)).await?;
                 return Ok(());
-            } else if selected == "Configure AI backend (Claude Code or Cursor CLI)" {
+            } else if selected
+                == "Configure AI backend (Claude Code, Cursor CLI or an OpenAI-compatible API)"
+            {
                 return setup_ai_backend(Some(config)).await;
             } else if selected == "Switch active AI backend" {
                 return switch_ai_backend(config).await;
+            } else if selected == "Remove a channel" {
+                return remove_channel(config).await;
             } else if selected == "Reconfigure from scratch" {
                 // fall through to fresh setup
             } else {
@@ -82,14 +166,37 @@ pub async fn run() -> Result<()> {
 /// Full setup wizard for first-time users
 async fn full_setup() -> Result<()> {
     // Step 1: Channel
-    let config = add_channel(None).await?;
+    let mut config = add_channel(None).await?;
+
+    // Step 2: Owner
+    setup_first_user_owner(&mut config)?;
 
-    // Step 2: AI Backend
+    // Step 3: AI Backend
     setup_ai_backend(Some(config)).await?;
 
     Ok(())
 }
 
+/// Offer to approve whoever messages the bot first as its owner
+fn setup_first_user_owner(config: &mut Config) -> Result<()> {
+    if config.owner.is_some() || !PairingStore::load()?.approved.is_empty() {
+        return Ok(());
+    }
+
+    println!();
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Approve the first person who messages the bot as its owner?")
+        .items(&[
+            "Yes, I'll message it myself right after setup",
+            "No, I'll approve users with `cica approve`",
+        ])
+        .default(0)
+        .interact()?;
+
+    config.pairing.auto_approve_first_user = selection == 0;
+    config.save()
+}
+
 /// Set up AI backend (Claude Code or Cursor CLI)
 async fn setup_ai_backend(existing_config: Option<Config>) -> Result<()> {
     println!();
@@ -103,13 +210,11 @@ async fn setup_ai_backend(existing_config: Option<Config>) -> Result<()> {
 
     if has_backend {
         let config = existing_config.as_ref().unwrap();
-        let backend_name = match config.backend {
-            AiBackend::Claude => "Claude Code",
-            AiBackend::Cursor => "Cursor CLI",
-        };
+        let backend_name = config.backend.display_name();
         let current_model = match config.backend {
             AiBackend::Claude => config.claude.model.as_deref(),
             AiBackend::Cursor => config.cursor.model.as_deref(),
+            AiBackend::OpenAi => config.openai.model.as_deref(),
         };
         println!(
             "Current: {} (model: {})",
@@ -120,7 +225,7 @@ async fn setup_ai_backend(existing_config: Option<Config>) -> Result<()> {
 
         let choices = vec![
             "Change model",
-            "Reconfigure backend (Claude Code or Cursor CLI)",
+            "Reconfigure backend (Claude Code, Cursor CLI or an OpenAI-compatible API)",
             "Cancel",
         ];
 
@@ -144,32 +249,47 @@ async fn setup_ai_backend(existing_config: Option<Config>) -> Result<()> {
 }
 
 async fn pick_backend(existing_config: Option<Config>) -> Result<()> {
-    println!("Cica can use either Claude Code or Cursor CLI as its AI backend.");
+    println!("Cica can use Claude Code, Cursor CLI or an OpenAI-compatible API as its AI backend.");
     println!();
 
-    let choices = vec![
+    let mut choices = vec![(
+        AiBackend::Claude,
         "Claude Code   Anthropic's official CLI (recommended)",
+    )];
+    #[cfg(feature = "cursor")]
+    choices.push((
+        AiBackend::Cursor,
         "Cursor CLI    Multi-model support (Claude, GPT, Gemini)",
-    ];
+    ));
+    choices.push((
+        AiBackend::OpenAi,
+        "OpenAI API    OpenAI, OpenRouter, or a local server (vLLM, LM Studio); file tools only",
+    ));
 
+    let labels: Vec<&str> = choices.iter().map(|(_, label)| *label).collect();
     let selection = Select::with_theme(&ColorfulTheme::default())
         .with_prompt("Which AI backend would you like to use?")
-        .items(&choices)
+        .items(&labels)
         .default(0)
         .interact()?;
 
-    match selection {
-        0 => setup_claude(existing_config).await,
-        1 => setup_cursor(existing_config).await,
-        _ => unreachable!(),
+    match choices[selection].0 {
+        AiBackend::Claude => setup_claude(existing_config).await,
+        #[cfg(feature = "cursor")]
+        AiBackend::Cursor => setup_cursor(existing_config).await,
+        #[cfg(not(feature = "cursor"))]
+        AiBackend::Cursor => unreachable!(),
+        AiBackend::OpenAi => setup_openai(existing_config).await,
     }
 }
 
 /// Change the model for the active backend
 async fn change_model(mut config: Config) -> Result<()> {
-    let (backend_name, current_model) = match config.backend {
-        AiBackend::Claude => ("Claude Code", config.claude.model.as_deref()),
-        AiBackend::Cursor => ("Cursor CLI", config.cursor.model.as_deref()),
+    let backend_name = config.backend.display_name();
+    let current_model = match config.backend {
+        AiBackend::Claude => config.claude.model.as_deref(),
+        AiBackend::Cursor => config.cursor.model.as_deref(),
+        AiBackend::OpenAi => config.openai.model.as_deref(),
     };
 
     println!();
@@ -185,6 +305,9 @@ async fn change_model(mut config: Config) -> Result<()> {
 
     let new_model = match config.backend {
         AiBackend::Claude => select_model(backend_name, claude::MODELS, current_model)?,
+        #[cfg(not(feature = "cursor"))]
+        AiBackend::Cursor => bail!("This build doesn't include the Cursor CLI backend"),
+        #[cfg(feature = "cursor")]
         AiBackend::Cursor => {
             let api_key = config
                 .cursor
@@ -199,11 +322,22 @@ async fn change_model(mut config: Config) -> Result<()> {
             println!();
             select_model(backend_name, &models, current_model)?
         }
+        AiBackend::OpenAi => {
+            let base_url = config.openai.base_url.clone().unwrap_or_default();
+            let models = fetch_openai_models(&base_url, config.openai.api_key.as_deref()).await?;
+            select_model(backend_name, &models, current_model)?
+        }
     };
 
     match config.backend {
         AiBackend::Claude => config.claude.model = new_model.clone(),
         AiBackend::Cursor => config.cursor.model = new_model.clone(),
+        AiBackend::OpenAi => config.openai.model = new_model.clone(),
+    }
+
+    if !confirm_backend_works(&config).await? {
+        println!("Kept the previous model.");
+        return Ok(());
     }
 
     config.save()?;
@@ -217,6 +351,46 @@ async fn change_model(mut config: Config) -> Result<()> {
     Ok(())
 }
 
+/// Run a test query with the (unsaved) backend settings in `config`.
+/// Returns whether to save them.
+async fn confirm_backend_works(config: &Config) -> Result<bool> {
+    let backend_name = config.backend.display_name();
+    let model = match config.backend {
+        AiBackend::Claude => config.claude.model.as_deref(),
+        AiBackend::Cursor => config.cursor.model.as_deref(),
+        AiBackend::OpenAi => config.openai.model.as_deref(),
+    };
+
+    println!();
+    print!(
+        "Testing {} (model: {})... ",
+        backend_name,
+        model.unwrap_or("default")
+    );
+    std::io::Write::flush(&mut std::io::stdout())?;
+
+    match backends::test_query(config).await {
+        Ok(elapsed) => {
+            println!("OK ({:.1}s)", elapsed.as_secs_f64());
+            Ok(true)
+        }
+        Err(e) => {
+            println!("FAILED");
+            println!();
+            println!("{:#}", e);
+            println!();
+
+            let selection = Select::with_theme(&ColorfulTheme::default())
+                .with_prompt("Save these settings anyway?")
+                .items(&["No, keep the previous settings", "Yes, save anyway"])
+                .default(0)
+                .interact()?;
+
+            Ok(selection == 1)
+        }
+    }
+}
+
 /// Switch between configured AI backends
 async fn switch_ai_backend(mut config: Config) -> Result<()> {
     println!();
@@ -224,19 +398,21 @@ async fn switch_ai_backend(mut config: Config) -> Result<()> {
     println!("─────────────────");
     println!();
 
-    let current = match config.backend {
-        AiBackend::Claude => "Claude Code",
-        AiBackend::Cursor => "Cursor CLI",
-    };
-    let other = match config.backend {
-        AiBackend::Claude => "Cursor CLI",
-        AiBackend::Cursor => "Claude Code",
-    };
+    let current = config.backend.display_name();
+    let others: Vec<AiBackend> = config
+        .configured_backends()
+        .into_iter()
+        .filter(|backend| *backend != config.backend)
+        .collect();
 
     println!("Current backend: {}", current);
     println!();
 
-    let choices = vec![format!("Switch to {}", other), "Cancel".to_string()];
+    let mut choices: Vec<String> = others
+        .iter()
+        .map(|backend| format!("Switch to {}", backend.display_name()))
+        .collect();
+    choices.push("Cancel".to_string());
 
     let selection = Select::with_theme(&ColorfulTheme::default())
         .with_prompt("What would you like to do?")
@@ -244,19 +420,17 @@ async fn switch_ai_backend(mut config: Config) -> Result<()> {
         .default(0)
         .interact()?;
 
-    if selection == 0 {
-        config.backend = match config.backend {
-            AiBackend::Claude => AiBackend::Cursor,
-            AiBackend::Cursor => AiBackend::Claude,
-        };
+    if let Some(backend) = others.get(selection) {
+        config.backend = *backend;
+
+        if !confirm_backend_works(&config).await? {
+            println!("Staying on {}.", current);
+            return Ok(());
+        }
         config.save()?;
 
-        let new_backend = match config.backend {
-            AiBackend::Claude => "Claude Code",
-            AiBackend::Cursor => "Cursor CLI",
-        };
         println!();
-        println!("Switched to {}!", new_backend);
+        println!("Switched to {}!", backend.display_name());
     } else {
         println!("Cancelled.");
     }
@@ -280,12 +454,183 @@ async fn add_channel(existing_config: Option<Config>) -> Result<Config> {
 
     let channel = &channels::SUPPORTED_CHANNELS[selection];
 
-    match channel.name {
-        "telegram" => setup_telegram(existing_config).await,
-        "signal" => setup_signal(existing_config).await,
-        "slack" => setup_slack(existing_config).await,
-        _ => bail!("Channel not yet supported: {}", channel.name),
+    let Some(&(_, setup)) = SETUP_WIZARDS.iter().find(|(name, _)| *name == channel.name) else {
+        bail!("Channel not yet supported: {}", channel.name);
+    };
+    let config = setup(existing_config).await?;
+
+    test_channel(channel, &config).await?;
+
+    Ok(config)
+}
+
+/// Interactive setup for a channel, saving and returning the config
+type SetupWizard = fn(Option<Config>) -> channels::BoxFuture<Result<Config>>;
+
+/// The `cica init` wizard for each channel in [`channels::SUPPORTED_CHANNELS`]
+const SETUP_WIZARDS: &[(&str, SetupWizard)] = &[
+    ("telegram", |config| Box::pin(setup_telegram(config))),
+    #[cfg(feature = "signal")]
+    ("signal", |config| Box::pin(setup_signal(config))),
+    #[cfg(feature = "slack")]
+    ("slack", |config| Box::pin(setup_slack(config))),
+];
+
+/// How long to wait for the owner's reply during the setup check
+const TEST_MESSAGE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Offer to verify a freshly configured channel end to end with a test message
+async fn test_channel(channel: &channels::ChannelInfo, config: &Config) -> Result<()> {
+    let Some(test) = &channel.test_message else {
+        return Ok(());
+    };
+    // Setup may have been cancelled without configuring the channel
+    if !channel.is_configured(config) {
+        return Ok(());
+    }
+
+    println!();
+    let choice = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Send a test message to verify everything works?")
+        .items(&["Yes", "Skip"])
+        .default(0)
+        .interact()?;
+
+    if choice != 0 {
+        return Ok(());
+    }
+
+    println!();
+
+    if let Some(instructions) = test.instructions {
+        println!("{}", instructions(config.clone()).await?);
+        println!();
+    }
+    if let Some(help) = test.recipient_help {
+        println!("{}", help);
+    }
+    let recipient = match test.recipient_prompt {
+        Some(prompt) => {
+            let recipient: String = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt(prompt)
+                .interact_text()?;
+            println!();
+            recipient.trim().to_string()
+        }
+        None => String::new(),
+    };
+    print!("{}", test.waiting);
+    std::io::Write::flush(&mut std::io::stdout())?;
+
+    let result = (test.run)(config.clone(), recipient, TEST_MESSAGE_TIMEOUT).await;
+
+    match result {
+        Ok(true) => {
+            println!("OK");
+            println!("Messages are flowing both ways.");
+        }
+        Ok(false) => {
+            println!("TIMED OUT");
+            println!(
+                "No reply received. Double-check the setup steps above, then run `cica` and message the bot."
+            );
+        }
+        Err(e) => {
+            println!("FAILED");
+            println!("Test message failed: {}", e);
+        }
     }
+
+    Ok(())
+}
+
+/// Remove a configured channel, optionally unregistering it and archiving its user data
+async fn remove_channel(mut config: Config) -> Result<()> {
+    println!();
+    println!("Remove a Channel");
+    println!("────────────────");
+    println!();
+
+    let configured = config.configured_channels();
+    let channel_choices: Vec<&str> = configured
+        .iter()
+        .map(|name| {
+            channels::get_channel_info(name)
+                .map(|c| c.display_name)
+                .unwrap_or(name)
+        })
+        .collect();
+
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Which channel would you like to remove?")
+        .items(&channel_choices)
+        .default(0)
+        .interact()?;
+
+    let channel = configured[selection];
+    let display_name = channel_choices[selection];
+
+    let confirm = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!(
+            "Remove {}? Approved users will need to pair again if it is re-added.",
+            display_name
+        ))
+        .items(&["Yes, remove it", "Cancel"])
+        .default(1)
+        .interact()?;
+
+    if confirm != 0 {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    // Channel-specific unregistration (optional)
+    let cleanup = channels::get_channel_info(channel).and_then(|c| c.remove_cleanup.as_ref());
+    if let Some(cleanup) = cleanup {
+        let choice = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt(cleanup.prompt)
+            .items(&cleanup.choices)
+            .default(if cleanup.default_yes { 0 } else { 1 })
+            .interact()?;
+
+        if choice == 0 {
+            print!("{}", cleanup.progress);
+            std::io::Write::flush(&mut std::io::stdout())?;
+            match (cleanup.run)(config.clone()).await {
+                Ok(()) => {
+                    println!("OK");
+                    if let Some(note) = cleanup.note {
+                        println!("{}", note);
+                    }
+                }
+                Err(e) => println!("FAILED ({})", e),
+            }
+        }
+    }
+
+    config.remove_channel(channel);
+    config.save()?;
+
+    // Drop pairing state and archive per-user data
+    let mut store = PairingStore::load()?;
+    store.remove_channel(channel)?;
+
+    match onboarding::archive_channel_users(channel)? {
+        Some(archive_dir) => {
+            println!();
+            println!("User data archived to: {}", archive_dir.display());
+        }
+        None => {
+            println!();
+            println!("No user data to archive.");
+        }
+    }
+
+    println!();
+    println!("{} removed.", display_name);
+
+    info!("Removed channel {}", channel);
+    Ok(())
 }
 
 /// Set up Telegram
@@ -316,6 +661,25 @@ async fn setup_telegram(existing_config: Option<Config>) -> Result<Config> {
         }
     }
 
+    print!("Configuring bot... ");
+    std::io::Write::flush(&mut std::io::stdout())?;
+
+    match telegram::configure_bot(&token).await {
+        Ok(true) => {
+            println!("OK");
+            println!("Group privacy mode is on: in groups, Cica only sees commands and @mentions.");
+        }
+        Ok(false) => {
+            println!("OK");
+            println!();
+            println!(
+                "Group privacy mode is off: Cica can read every message in groups it's added to."
+            );
+            println!("To turn it on, message @BotFather: /setprivacy → select your bot → Enable");
+        }
+        Err(e) => println!("FAILED ({})", e),
+    }
+
     // Build config
     let mut config = existing_config.unwrap_or_default();
     config.channels.telegram = Some(TelegramConfig::new(token));
@@ -326,6 +690,7 @@ async fn setup_telegram(existing_config: Option<Config>) -> Result<Config> {
 }
 
 /// Set up Signal
+#[cfg(feature = "signal")]
 async fn setup_signal(existing_config: Option<Config>) -> Result<Config> {
     println!();
     println!("Signal Setup");
@@ -334,11 +699,12 @@ async fn setup_signal(existing_config: Option<Config>) -> Result<Config> {
 
     // Download dependencies if needed
     if setup::find_java().is_none() || setup::find_signal_cli().is_none() {
-        print!("Setting up Signal runtime... ");
-        std::io::Write::flush(&mut std::io::stdout())?;
-        setup::ensure_java().await?;
-        setup::ensure_signal_cli().await?;
-        println!("done");
+        setup_runtime(
+            "Setting up Signal runtime",
+            &[Dep::Java, Dep::SignalCli],
+            false,
+        )
+        .await?;
         println!();
     }
 
@@ -550,6 +916,7 @@ async fn setup_signal(existing_config: Option<Config>) -> Result<Config> {
 }
 
 /// Helper to retry Signal setup with a specific phone number
+#[cfg(feature = "signal")]
 async fn setup_signal_with_number(
     existing_config: Option<Config>,
     phone_number: &str,
@@ -648,7 +1015,8 @@ async fn setup_signal_with_number(
 }
 
 /// Link signal-cli as a secondary device to an existing Signal account
-async fn link_signal_device(existing_config: Option<Config>) -> Result<Config> {
+#[cfg(feature = "signal")]
+pub async fn link_signal_device(existing_config: Option<Config>) -> Result<Config> {
     println!();
     println!("Link as Secondary Device");
     println!("─────────────────────────");
@@ -671,94 +1039,95 @@ async fn link_signal_device(existing_config: Option<Config>) -> Result<Config> {
 
     use tokio::io::{AsyncBufReadExt, BufReader};
 
-    // Run signal-cli link command and capture output line by line
-    let mut child = tokio::process::Command::new(&signal_cli)
-        .args([
-            "--config",
-            paths.signal_data_dir.to_str().unwrap(),
-            "link",
-            "-n",
-            "Cica",
-        ])
-        .env("JAVA_HOME", java_home)
-        .env(
-            "PATH",
-            format!(
-                "{}:{}",
-                java.parent().unwrap().display(),
-                std::env::var("PATH").unwrap_or_default()
-            ),
-        )
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn()?;
-
-    let stdout = child.stdout.take().unwrap();
-    let stderr = child.stderr.take().unwrap();
-
-    let mut stdout_reader = BufReader::new(stdout).lines();
-    let mut stderr_reader = BufReader::new(stderr).lines();
-
-    let mut link_url = None;
-
-    // Read output looking for the link URL
+    // Each link URL expires after about a minute; start over with a fresh one on timeout
     loop {
-        tokio::select! {
-            line = stdout_reader.next_line() => {
-                match line {
-                    Ok(Some(text)) => {
-                        if text.starts_with("sgnl://") {
-                            link_url = Some(text.clone());
-                            println!();
-                            println!("Link URL (open on your phone or copy to Signal):");
-                            println!();
-                            println!("  {}", text);
-                            println!();
-                            println!("In Signal app: Settings → Linked Devices → Link New Device");
-                            println!();
-                            println!("Waiting for you to scan...");
+        // Run signal-cli link command and capture output line by line
+        let mut child = tokio::process::Command::new(&signal_cli)
+            .args([
+                "--config",
+                paths.signal_data_dir.to_str().unwrap(),
+                "link",
+                "-n",
+                "Cica",
+            ])
+            .env("JAVA_HOME", java_home)
+            .env(
+                "PATH",
+                format!(
+                    "{}:{}",
+                    java.parent().unwrap().display(),
+                    std::env::var("PATH").unwrap_or_default()
+                ),
+            )
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()?;
+
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+
+        let mut stdout_reader = BufReader::new(stdout).lines();
+        let mut stderr_reader = BufReader::new(stderr).lines();
+
+        let mut link_url = None;
+
+        // Read output looking for the link URL
+        loop {
+            tokio::select! {
+                line = stdout_reader.next_line() => {
+                    match line {
+                        Ok(Some(text)) => {
+                            if text.starts_with("sgnl://") {
+                                print_signal_link(&text);
+                                link_url = Some(text);
+                            }
                         }
+                        Ok(None) => break,
+                        Err(_) => break,
                     }
-                    Ok(None) => break,
-                    Err(_) => break,
                 }
-            }
-            line = stderr_reader.next_line() => {
-                match line {
-                    Ok(Some(text)) => {
-                        if text.starts_with("sgnl://") {
-                            link_url = Some(text.clone());
-                            println!();
-                            println!("Link URL (open on your phone or copy to Signal):");
-                            println!();
-                            println!("  {}", text);
-                            println!();
-                            println!("In Signal app: Settings → Linked Devices → Link New Device");
-                            println!();
-                            println!("Waiting for you to scan...");
-                        } else if text.contains("error") || text.contains("Error") {
-                            // Only print actual errors, not debug output
-                            if !text.contains("DEBUG") && !text.contains("INFO") {
-                                println!("{}", text);
+                line = stderr_reader.next_line() => {
+                    match line {
+                        Ok(Some(text)) => {
+                            if text.starts_with("sgnl://") {
+                                print_signal_link(&text);
+                                link_url = Some(text);
+                            } else if text.contains("error") || text.contains("Error") {
+                                // Only print actual errors, not debug output
+                                if !text.contains("DEBUG") && !text.contains("INFO") {
+                                    println!("{}", text);
+                                }
                             }
                         }
+                        Ok(None) => break,
+                        Err(_) => break,
                     }
-                    Ok(None) => break,
-                    Err(_) => break,
                 }
             }
         }
-    }
 
-    let status = child.wait().await?;
+        let status = child.wait().await?;
 
-    if !status.success() {
-        if link_url.is_some() {
-            println!();
-            println!("Link timed out or was cancelled.");
-            println!("Please try again and scan the QR code within 60 seconds.");
+        if status.success() {
+            break;
+        }
+
+        if link_url.is_none() {
+            bail!("Link command failed");
+        }
+
+        println!();
+        println!("Link timed out or was cancelled.");
+
+        let retry = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Generate a new QR code?")
+            .items(&["Yes", "Cancel"])
+            .default(0)
+            .interact()?;
+
+        if retry != 0 {
+            bail!("Linking cancelled");
         }
-        bail!("Link command failed");
     }
 
     println!();
@@ -805,8 +1174,15 @@ async fn link_signal_device(existing_config: Option<Config>) -> Result<Config> {
         }
     };
 
+    // Keep existing Signal settings (auto-approve, prompts) when relinking
     let mut config = existing_config.unwrap_or_default();
-    config.channels.signal = Some(SignalConfig::new(phone_number.clone()));
+    let mut signal_config = config
+        .channels
+        .signal
+        .take()
+        .unwrap_or_else(|| SignalConfig::new(phone_number.clone()));
+    signal_config.phone_number = phone_number.clone();
+    config.channels.signal = Some(signal_config);
     config.save()?;
 
     println!();
@@ -815,7 +1191,37 @@ async fn link_signal_device(existing_config: Option<Config>) -> Result<Config> {
     Ok(config)
 }
 
+/// Print a Signal device link URL as a scannable QR code, with the raw URL as fallback
+#[cfg(feature = "signal")]
+fn print_signal_link(url: &str) {
+    println!();
+    println!("Scan this QR code with Signal on your phone:");
+    println!("(Settings → Linked Devices → Link New Device)");
+    println!();
+
+    match QrCode::new(url) {
+        Ok(code) => {
+            // Inverted colors so the code reads correctly on dark terminal backgrounds
+            let rendered = code
+                .render::<unicode::Dense1x2>()
+                .dark_color(unicode::Dense1x2::Light)
+                .light_color(unicode::Dense1x2::Dark)
+                .build();
+            println!("{}", rendered);
+        }
+        Err(e) => println!("(Could not render QR code: {})", e),
+    }
+
+    println!();
+    println!("If scanning doesn't work, open this link on your phone instead:");
+    println!();
+    println!("  {}", url);
+    println!();
+    println!("Waiting for you to scan...");
+}
+
 /// Set up Slack
+#[cfg(feature = "slack")]
 async fn setup_slack(existing_config: Option<Config>) -> Result<Config> {
     println!();
     println!("Slack Setup");
@@ -823,10 +1229,13 @@ async fn setup_slack(existing_config: Option<Config>) -> Result<Config> {
     println!();
     println!("You'll need a Slack app with Socket Mode enabled.");
     println!();
-    println!("If you haven't created one yet:");
-    println!("1. Go to https://api.slack.com/apps");
-    println!("2. Click 'Create New App' → 'From scratch'");
-    println!("3. Name it and select your workspace");
+    println!("The quickest way is from a manifest, which sets up everything below:");
+    println!("1. Run: cica print-slack-manifest");
+    println!("2. Go to https://api.slack.com/apps → 'Create New App' → 'From an app manifest'");
+    println!("3. Select your workspace, paste the manifest and create the app");
+    println!("4. Install it, and generate an App-Level Token with 'connections:write' scope");
+    println!();
+    println!("Or create one 'From scratch', name it and select your workspace.");
     println!();
     println!("Required setup in your Slack app:");
     println!("─────────────────────────────────");
@@ -841,17 +1250,25 @@ async fn setup_slack(existing_config: Option<Config>) -> Result<Config> {
     println!();
     println!("3. Subscribe to events:");
     println!("   Features → Event Subscriptions → Enable");
-    println!("   Subscribe to bot events: message.im");
+    println!("   Subscribe to bot events: message.im, reaction_added");
     println!();
     println!("4. Add OAuth scopes:");
     println!("   Features → OAuth & Permissions → Bot Token Scopes:");
     println!("   - chat:write");
+    println!("   - commands");
     println!("   - im:history");
     println!("   - im:read");
     println!("   - im:write");
+    println!("   - reactions:read (for 👍/👎 feedback)");
     println!("   - users:read");
     println!();
-    println!("5. Install the app to your workspace");
+    println!("5. Create a slash command (for /cica help, /cica cron list, ...):");
+    println!(
+        "   Features → Slash Commands → Create New Command: {}",
+        slack::SLASH_COMMAND
+    );
+    println!();
+    println!("6. Install the app to your workspace");
     println!();
 
     // Get Bot Token
@@ -887,24 +1304,50 @@ async fn setup_slack(existing_config: Option<Config>) -> Result<Config> {
     Ok(config)
 }
 
+/// Download `deps` (and the embedding model, with `model`) all at once,
+/// listing each one on the progress line as it's ready
+pub async fn setup_runtime(label: &str, deps: &[Dep], model: bool) -> Result<()> {
+    print!("{}... ", label);
+    std::io::Write::flush(&mut std::io::stdout())?;
+
+    // A default config wants the pinned versions
+    setup::ensure_concurrently(&Config::default(), deps, model, |name| {
+        print!("{} ok, ", name);
+        let _ = std::io::Write::flush(&mut std::io::stdout());
+    })
+    .await?;
+
+    println!("done");
+    Ok(())
+}
+
+/// Download Bun and Claude Code if they're missing
+async fn ensure_claude_runtime() -> Result<()> {
+    if setup::find_bun().is_none() || setup::find_claude_code().is_none() {
+        println!();
+        setup_runtime("Setting up runtime", &[Dep::Bun, Dep::ClaudeCode], true).await?;
+    }
+    Ok(())
+}
+
+/// Download Cursor CLI, and Bun for skills, if they're missing
+#[cfg(feature = "cursor")]
+async fn ensure_cursor_runtime() -> Result<()> {
+    if setup::find_cursor_cli().is_none() || setup::find_bun().is_none() {
+        println!();
+        // Bun is needed for skills
+        setup_runtime("Setting up runtime", &[Dep::Bun, Dep::CursorCli], true).await?;
+    }
+    Ok(())
+}
+
 /// Set up Claude (Bun + Claude Code + API key)
 async fn setup_claude(existing_config: Option<Config>) -> Result<()> {
     println!();
     println!("Claude Setup");
     println!("────────────");
 
-    // Ensure runtime dependencies are available
-    if setup::find_bun().is_none() || setup::find_claude_code().is_none() {
-        println!();
-        print!("Setting up runtime... ");
-        std::io::Write::flush(&mut std::io::stdout())?;
-
-        setup::ensure_bun().await?;
-        setup::ensure_claude_code().await?;
-        setup::ensure_embedding_model()?;
-
-        println!("done");
-    }
+    ensure_claude_runtime().await?;
 
     // Check for existing env token first
     if let Some(env_token) = setup::get_env_oauth_token() {
@@ -919,6 +1362,10 @@ async fn setup_claude(existing_config: Option<Config>) -> Result<()> {
                 // Save config
                 let mut config = existing_config.unwrap_or_default();
                 config.claude.api_key = Some(env_token);
+                if !confirm_backend_works(&config).await? {
+                    println!("Settings not saved. Run `cica init` to try again.");
+                    return Ok(());
+                }
                 config.save()?;
 
                 let paths = config::paths()?;
@@ -956,7 +1403,8 @@ async fn setup_claude(existing_config: Option<Config>) -> Result<()> {
         .interact()?;
 
     let mut config = existing_config.unwrap_or_default();
-    let was_using_cursor = config.backend == AiBackend::Cursor && config.is_cursor_configured();
+    let previous = config.backend;
+    let was_using_other = previous != AiBackend::Claude && config.is_backend_configured();
 
     if provider_selection == 1 {
         // Vertex AI setup
@@ -1122,11 +1570,12 @@ async fn setup_claude(existing_config: Option<Config>) -> Result<()> {
     )?;
 
     // Ask whether to switch if another backend was active
-    if was_using_cursor {
+    if was_using_other {
         println!();
+        let keep = format!("No, keep using {}", previous.display_name());
         let switch = Select::with_theme(&ColorfulTheme::default())
             .with_prompt("Switch to Claude Code as your active backend?")
-            .items(&["Yes", "No, keep using Cursor CLI"])
+            .items(&["Yes", keep.as_str()])
             .default(0)
             .interact()?;
 
@@ -1137,13 +1586,14 @@ async fn setup_claude(existing_config: Option<Config>) -> Result<()> {
         config.backend = AiBackend::Claude;
     }
 
+    if !confirm_backend_works(&config).await? {
+        println!("Settings not saved. Run `cica init` to try again.");
+        return Ok(());
+    }
     config.save()?;
 
     let paths = config::paths()?;
-    let active = match config.backend {
-        AiBackend::Claude => "Claude Code",
-        AiBackend::Cursor => "Cursor CLI",
-    };
+    let active = config.backend.display_name();
     let model_display = config.claude.model.as_deref().unwrap_or("default");
 
     println!();
@@ -1230,23 +1680,13 @@ fn select_model<S: AsRef<str>>(
 }
 
 /// Set up Cursor CLI
+#[cfg(feature = "cursor")]
 async fn setup_cursor(existing_config: Option<Config>) -> Result<()> {
     println!();
     println!("Cursor CLI Setup");
     println!("────────────────");
 
-    // Ensure runtime dependencies are available
-    if setup::find_cursor_cli().is_none() || setup::find_bun().is_none() {
-        println!();
-        print!("Setting up runtime... ");
-        std::io::Write::flush(&mut std::io::stdout())?;
-
-        setup::ensure_bun().await?; // Needed for skills
-        setup::ensure_cursor_cli().await?;
-        setup::ensure_embedding_model()?;
-
-        println!("done");
-    }
+    ensure_cursor_runtime().await?;
 
     // Get API key
     println!();
@@ -1286,16 +1726,18 @@ async fn setup_cursor(existing_config: Option<Config>) -> Result<()> {
 
     // Save config
     let mut config = existing_config.unwrap_or_default();
-    let was_using_claude = config.backend == AiBackend::Claude && config.is_claude_configured();
+    let previous = config.backend;
+    let was_using_other = previous != AiBackend::Cursor && config.is_backend_configured();
     config.cursor.api_key = Some(api_key);
     config.cursor.model = model;
 
     // Ask whether to switch if another backend was active
-    if was_using_claude {
+    if was_using_other {
         println!();
+        let keep = format!("No, keep using {}", previous.display_name());
         let switch = Select::with_theme(&ColorfulTheme::default())
             .with_prompt("Switch to Cursor CLI as your active backend?")
-            .items(&["Yes", "No, keep using Claude Code"])
+            .items(&["Yes", keep.as_str()])
             .default(0)
             .interact()?;
 
@@ -1306,13 +1748,14 @@ async fn setup_cursor(existing_config: Option<Config>) -> Result<()> {
         config.backend = AiBackend::Cursor;
     }
 
+    if !confirm_backend_works(&config).await? {
+        println!("Settings not saved. Run `cica init` to try again.");
+        return Ok(());
+    }
     config.save()?;
 
     let paths = config::paths()?;
-    let active = match config.backend {
-        AiBackend::Claude => "Claude Code",
-        AiBackend::Cursor => "Cursor CLI",
-    };
+    let active = config.backend.display_name();
 
     println!();
     println!("Setup complete! Active backend: {}", active);
@@ -1324,3 +1767,366 @@ async fn setup_cursor(existing_config: Option<Config>) -> Result<()> {
     info!("Cursor CLI setup complete");
     Ok(())
 }
+
+/// OpenAI-compatible servers, with their base URLs
+const OPENAI_PROVIDERS: &[(&str, &str)] = &[
+    ("OpenAI", openai::DEFAULT_BASE_URL),
+    ("OpenRouter", "https://openrouter.ai/api/v1"),
+    ("LM Studio", "http://localhost:1234/v1"),
+    ("vLLM", "http://localhost:8000/v1"),
+];
+
+/// Set up an OpenAI-compatible API
+async fn setup_openai(existing_config: Option<Config>) -> Result<()> {
+    println!();
+    println!("OpenAI-compatible API Setup");
+    println!("───────────────────────────");
+    println!();
+    println!("Cica can talk to any server with an OpenAI-style chat completions API.");
+    println!("The model can read and write files in Cica's data directory, but can't");
+    println!("run commands or search the web.");
+    println!();
+
+    let mut choices: Vec<String> = OPENAI_PROVIDERS
+        .iter()
+        .map(|(name, url)| format!("{:<12} {}", name, url))
+        .collect();
+    choices.push(format!("{:<12} Enter a base URL", "Other"));
+
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Which server would you like to use?")
+        .items(&choices)
+        .default(0)
+        .interact()?;
+
+    let base_url: String = match OPENAI_PROVIDERS.get(selection) {
+        Some((_, url)) => Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Base URL")
+            .default(url.to_string())
+            .interact_text()?,
+        None => Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Base URL (e.g. http://localhost:8080/v1)")
+            .interact_text()?,
+    };
+    let base_url = base_url.trim().trim_end_matches('/').to_string();
+
+    println!();
+    let api_key: String = Password::with_theme(&ColorfulTheme::default())
+        .with_prompt("API key (leave empty if the server doesn't need one)")
+        .allow_empty_password(true)
+        .interact()?;
+    let api_key = Some(api_key.trim().to_string()).filter(|k| !k.is_empty());
+
+    println!();
+    let models = fetch_openai_models(&base_url, api_key.as_deref()).await?;
+    let model = select_model("OpenAI-compatible API", &models, None)?;
+
+    // Save config
+    let mut config = existing_config.unwrap_or_default();
+    let previous = config.backend;
+    let was_using_other = previous != AiBackend::OpenAi && config.is_backend_configured();
+    config.openai.base_url = Some(base_url);
+    config.openai.api_key = api_key;
+    config.openai.model = model;
+
+    // Ask whether to switch if another backend was active
+    if was_using_other {
+        println!();
+        let keep = format!("No, keep using {}", previous.display_name());
+        let switch = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Switch to the OpenAI-compatible API as your active backend?")
+            .items(&["Yes", keep.as_str()])
+            .default(0)
+            .interact()?;
+
+        if switch == 0 {
+            config.backend = AiBackend::OpenAi;
+        }
+    } else {
+        config.backend = AiBackend::OpenAi;
+    }
+
+    if !confirm_backend_works(&config).await? {
+        println!("Settings not saved. Run `cica init` to try again.");
+        return Ok(());
+    }
+    config.save()?;
+
+    let paths = config::paths()?;
+    let model_display = config.openai.model.as_deref().unwrap_or("default");
+
+    println!();
+    println!(
+        "Setup complete! Active backend: {} (model: {})",
+        config.backend.display_name(),
+        model_display
+    );
+    println!();
+    println!("Config saved to: {}", paths.config_file.display());
+    println!();
+    println!("Run `cica` to start your assistant.");
+
+    info!("OpenAI-compatible API setup complete");
+    Ok(())
+}
+
+/// Fetch the models a server offers, or none if it can't list them
+async fn fetch_openai_models(
+    base_url: &str,
+    api_key: Option<&str>,
+) -> Result<Vec<(String, String)>> {
+    print!("Fetching available models... ");
+    std::io::Write::flush(&mut std::io::stdout())?;
+    let models = match openai::list_models(base_url, api_key).await {
+        Ok(models) => {
+            println!("OK ({} models)", models.len());
+            models
+        }
+        Err(e) => {
+            println!("FAILED ({:#})", e);
+            println!("You can still enter a model name manually.");
+            Vec::new()
+        }
+    };
+    println!();
+    Ok(models)
+}
+
+// ============================================================================
+// Setup Without Prompts
+// ============================================================================
+
+/// Set up what the flags ask for, with the same checks as the wizard
+async fn headless_setup(args: InitArgs) -> Result<()> {
+    let paths = config::paths()?;
+    let existing = if paths.config_file.exists() {
+        if !args.yes {
+            bail!(
+                "{} already exists; pass --yes to change it",
+                paths.config_file.display()
+            );
+        }
+        Some(Config::load()?)
+    } else {
+        None
+    };
+    paths.ensure_dirs()?;
+    let mut config = existing.unwrap_or_default();
+
+    if let Some(channel) = &args.channel {
+        headless_channel(&mut config, channel, &args).await?;
+    }
+    if let Some(backend) = &args.backend {
+        headless_backend(&mut config, backend.parse()?, &args).await?;
+    }
+    if args.owner_first_user {
+        config.pairing.auto_approve_first_user = true;
+    }
+    config.save()?;
+
+    println!();
+    println!("Config saved to: {}", paths.config_file.display());
+    if config.configured_channels().is_empty() {
+        println!("No channel is set up yet; add one with --channel.");
+    } else if !config.is_backend_configured() {
+        println!("No AI backend is set up yet; add one with --backend.");
+    } else {
+        println!("Run `cica` to start your assistant.");
+    }
+
+    info!("Setup without prompts complete");
+    Ok(())
+}
+
+async fn headless_channel(config: &mut Config, channel: &str, args: &InitArgs) -> Result<()> {
+    let Some(info) = channels::get_channel_info(channel) else {
+        let names: Vec<&str> = channels::SUPPORTED_CHANNELS
+            .iter()
+            .map(|c| c.name)
+            .collect();
+        bail!(
+            "Unknown channel: {} (expected {})",
+            channel,
+            names.join(", ")
+        );
+    };
+
+    // Linking needs a phone to scan a code, registering an SMS code
+    let Some(secret_setup) = &info.secret_setup else {
+        bail!(
+            "{} can't be set up without prompts; run `cica init` without flags",
+            info.display_name
+        );
+    };
+    let secrets = secret_setup
+        .flags
+        .iter()
+        .map(|flag| {
+            let (value, env) = secret_flag(args, flag)?;
+            required_secret(value, env, flag)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    print!("Validating {} credentials... ", info.display_name);
+    std::io::Write::flush(&mut std::io::stdout())?;
+    match (secret_setup.configure)(config.clone(), secrets).await {
+        Ok((configured, detail)) => {
+            println!("OK ({})", detail);
+            *config = configured;
+        }
+        Err(e) => {
+            println!("FAILED");
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// The flag and its `-env` variant holding a channel secret
+fn secret_flag<'a>(
+    args: &'a InitArgs,
+    flag: &str,
+) -> Result<(&'a Option<String>, &'a Option<String>)> {
+    match flag {
+        "bot-token" => Ok((&args.bot_token, &args.bot_token_env)),
+        "app-token" => Ok((&args.app_token, &args.app_token_env)),
+        _ => bail!("Unknown secret flag --{}", flag),
+    }
+}
+
+async fn headless_backend(config: &mut Config, backend: AiBackend, args: &InitArgs) -> Result<()> {
+    match backend {
+        AiBackend::Claude => {
+            ensure_claude_runtime().await?;
+
+            if let Some(project_id) = &args.vertex_project {
+                let paths = config::paths()?;
+                print!("Validating Vertex config... ");
+                std::io::Write::flush(&mut std::io::stdout())?;
+                match setup::validate_vertex_config(
+                    project_id.trim(),
+                    args.vertex_region.as_deref(),
+                    args.vertex_credentials.as_deref(),
+                    &paths.base,
+                )
+                .await
+                {
+                    Ok(()) => println!("OK"),
+                    Err(e) => {
+                        println!("FAILED");
+                        bail!("Vertex AI setup failed: {}", e);
+                    }
+                }
+
+                config.claude.api_key = None;
+                config.claude.use_vertex = true;
+                config.claude.vertex_project_id = Some(project_id.trim().to_string());
+                config.claude.vertex_region = args.vertex_region.clone();
+                config.claude.vertex_credentials_path = args.vertex_credentials.clone();
+            } else {
+                let credential = secret(&args.api_key, &args.api_key_env)?
+                    .or_else(setup::get_env_oauth_token)
+                    .context(
+                        "Claude Code needs --api-key, --api-key-env or --vertex-project \
+                         (or CLAUDE_CODE_OAUTH_TOKEN in the environment)",
+                    )?;
+
+                print!("Validating... ");
+                std::io::Write::flush(&mut std::io::stdout())?;
+                match setup::validate_credential(&credential).await {
+                    Ok(()) => println!("OK"),
+                    Err(e) => {
+                        println!("FAILED");
+                        bail!("Authentication failed: {}", e);
+                    }
+                }
+
+                config.claude.api_key = Some(credential);
+                config.claude.use_vertex = false;
+                config.claude.vertex_project_id = None;
+                config.claude.vertex_region = None;
+                config.claude.vertex_credentials_path = None;
+            }
+            if args.model.is_some() {
+                config.claude.model = args.model.clone();
+            }
+        }
+        #[cfg(feature = "cursor")]
+        AiBackend::Cursor => {
+            ensure_cursor_runtime().await?;
+            let api_key = required_secret(&args.api_key, &args.api_key_env, "api-key")?;
+
+            print!("Validating... ");
+            std::io::Write::flush(&mut std::io::stdout())?;
+            match setup::validate_cursor_api_key(&api_key).await {
+                Ok(()) => println!("OK"),
+                Err(e) => {
+                    println!("FAILED");
+                    bail!("Invalid API key: {}", e);
+                }
+            }
+
+            config.cursor.api_key = Some(api_key);
+            if args.model.is_some() {
+                config.cursor.model = args.model.clone();
+            }
+        }
+        #[cfg(not(feature = "cursor"))]
+        AiBackend::Cursor => bail!("This build doesn't include the Cursor CLI backend"),
+        AiBackend::OpenAi => {
+            let base_url = args
+                .base_url
+                .as_deref()
+                .unwrap_or(openai::DEFAULT_BASE_URL)
+                .trim()
+                .trim_end_matches('/')
+                .to_string();
+            config.openai.base_url = Some(base_url);
+            config.openai.api_key = secret(&args.api_key, &args.api_key_env)?;
+            if args.model.is_some() {
+                config.openai.model = args.model.clone();
+            }
+        }
+    }
+    config.backend = backend;
+
+    // Unlike the wizard, never save settings that don't work
+    let model = match backend {
+        AiBackend::Claude => config.claude.model.as_deref(),
+        AiBackend::Cursor => config.cursor.model.as_deref(),
+        AiBackend::OpenAi => config.openai.model.as_deref(),
+    };
+    print!(
+        "Testing {} (model: {})... ",
+        backend.display_name(),
+        model.unwrap_or("default")
+    );
+    std::io::Write::flush(&mut std::io::stdout())?;
+    match backends::test_query(config).await {
+        Ok(elapsed) => println!("OK ({:.1}s)", elapsed.as_secs_f64()),
+        Err(e) => {
+            println!("FAILED");
+            bail!(
+                "{} didn't answer a test query: {:#}",
+                backend.display_name(),
+                e
+            );
+        }
+    }
+    Ok(())
+}
+
+/// A secret given as a flag or, to keep it out of shell history, through an
+/// environment variable named by the flag's `-env` variant
+fn secret(value: &Option<String>, env: &Option<String>) -> Result<Option<String>> {
+    let value = match (value, env) {
+        (Some(value), _) => value.clone(),
+        (None, Some(var)) => std::env::var(var).with_context(|| format!("{} isn't set", var))?,
+        (None, None) => return Ok(None),
+    };
+    Ok(Some(value.trim().to_string()).filter(|v| !v.is_empty()))
+}
+
+fn required_secret(value: &Option<String>, env: &Option<String>, flag: &str) -> Result<String> {
+    secret(value, env)?.with_context(|| format!("--{} or --{}-env is required", flag, flag))
+}
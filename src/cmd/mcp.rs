@@ -0,0 +1,142 @@
+use anyhow::{Result, bail};
+use clap::Subcommand;
+use std::collections::BTreeMap;
+use tracing::info;
+
+use cica_core::config::{Config, McpConfig, McpServer};
+use cica_core::mcp;
+
+/// MCP subcommands
+#[derive(Subcommand)]
+pub enum McpCommand {
+    /// Add an MCP server, e.g. `cica mcp add github --env GITHUB_TOKEN=... -- npx -y @modelcontextprotocol/server-github`
+    Add {
+        /// Name of the server (letters, digits, - and _)
+        name: String,
+        /// Environment variable for the server, as KEY=VALUE (repeatable)
+        #[arg(long = "env", value_name = "KEY=VALUE")]
+        env: Vec<String>,
+        /// What the server is for, told to the agent
+        #[arg(long)]
+        description: Option<String>,
+        /// Program to run
+        command: String,
+        /// Arguments to the program
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// List the configured MCP servers
+    List,
+    /// Remove an MCP server
+    Remove {
+        /// Name of the server
+        name: String,
+    },
+}
+
+/// Run an MCP subcommand
+pub async fn run(command: McpCommand) -> Result<()> {
+    match command {
+        McpCommand::Add {
+            name,
+            env,
+            description,
+            command,
+            args,
+        } => add(name, env, description, command, args).await,
+        McpCommand::List => list(),
+        McpCommand::Remove { name } => remove(&name),
+    }
+}
+
+async fn add(
+    name: String,
+    env: Vec<String>,
+    description: Option<String>,
+    command: String,
+    args: Vec<String>,
+) -> Result<()> {
+    if !McpConfig::is_valid_name(&name) {
+        bail!(
+            "Invalid server name: {} (use letters, digits, - and _)",
+            name
+        );
+    }
+    let env = env
+        .iter()
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+            _ => bail!("Expected KEY=VALUE for --env, got {}", pair),
+        })
+        .collect::<Result<BTreeMap<_, _>>>()?;
+
+    let mut server = McpServer {
+        command,
+        args,
+        env,
+        description,
+        tools: BTreeMap::new(),
+    };
+    // Listed in the prompt, so the agent knows what the server can do
+    println!("Asking {} for its tools...", name);
+    match mcp::list_tools(&server).await {
+        Ok(tools) => {
+            println!("{} tool(s) found", tools.len());
+            server.tools = tools;
+        }
+        Err(e) => println!(
+            "Couldn't list its tools ({}); the agent will only see its name",
+            e
+        ),
+    }
+
+    let mut config = Config::load()?;
+    let replaced = config.mcp.servers.insert(name.clone(), server).is_some();
+    config.save()?;
+
+    if replaced {
+        println!("Updated MCP server {}", name);
+    } else {
+        println!("Added MCP server {}", name);
+    }
+    info!("Configured MCP server {}", name);
+    Ok(())
+}
+
+fn list() -> Result<()> {
+    let config = Config::load()?;
+    if config.mcp.servers.is_empty() {
+        println!("No MCP servers configured");
+        return Ok(());
+    }
+
+    for (name, server) in &config.mcp.servers {
+        let mut command = vec![server.command.as_str()];
+        command.extend(server.args.iter().map(String::as_str));
+        println!("{}: {}", name, command.join(" "));
+        if let Some(description) = &server.description {
+            println!("  {}", description);
+        }
+        if !server.tools.is_empty() {
+            let tools: Vec<&str> = server.tools.keys().map(String::as_str).collect();
+            println!("  Tools: {}", tools.join(", "));
+        }
+        if !server.env.is_empty() {
+            let keys: Vec<&str> = server.env.keys().map(String::as_str).collect();
+            println!("  Environment: {}", keys.join(", "));
+        }
+    }
+    Ok(())
+}
+
+fn remove(name: &str) -> Result<()> {
+    let mut config = Config::load()?;
+    if config.mcp.servers.remove(name).is_none() {
+        bail!("No MCP server named {}", name);
+    }
+    config.save()?;
+
+    println!("Removed MCP server {}", name);
+    info!("Removed MCP server {}", name);
+    Ok(())
+}
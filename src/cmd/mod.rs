@@ -1,4 +1,31 @@
 pub mod approve;
+pub mod backup;
+pub mod block;
+pub mod clean;
+pub mod config;
+pub mod cron;
+pub mod deps;
+pub mod dockerfile;
+pub mod features;
+pub mod gc;
+pub mod history;
 pub mod init;
+pub mod mcp;
+pub mod output;
+pub mod owner;
 pub mod paths;
+pub mod pending;
+pub mod persona;
+pub mod prompt;
+pub mod revoke;
+pub mod role;
 pub mod run;
+pub mod secrets;
+pub mod service;
+#[cfg(feature = "signal")]
+pub mod signal;
+pub mod skill;
+#[cfg(feature = "slack")]
+pub mod slack_manifest;
+pub mod status;
+pub mod users;
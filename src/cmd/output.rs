@@ -0,0 +1,8 @@
+use anyhow::Result;
+use serde::Serialize;
+
+/// Print a value as pretty JSON (for `--json`)
+pub fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use anyhow::Result;
+use clap::Subcommand;
+use tracing::info;
+
+use cica_core::channels;
+use cica_core::config::{Config, OwnerConfig};
+use cica_core::pairing::{PairingStore, Role};
+
+/// Owner subcommands
+#[derive(Subcommand)]
+pub enum OwnerCommand {
+    /// List the owners
+    List,
+    /// Make an approved user an owner, who can use owner-only chat commands
+    /// (/approve, /persona set, /cron pause all, ...)
+    Set {
+        /// Channel name (e.g., "telegram")
+        channel: String,
+        /// The user's ID on that channel
+        user_id: String,
+    },
+    /// Make an owner a member again
+    Remove {
+        /// Channel name (e.g., "telegram")
+        channel: String,
+        /// The user's ID on that channel
+        user_id: String,
+    },
+}
+
+/// Run an owner subcommand
+pub fn run(command: OwnerCommand) -> Result<()> {
+    match command {
+        OwnerCommand::List => list(),
+        OwnerCommand::Set { channel, user_id } => set(&channel, &user_id),
+        OwnerCommand::Remove { channel, user_id } => remove(&channel, &user_id),
+    }
+}
+
+fn list() -> Result<()> {
+    let store = PairingStore::load()?;
+    let notified = Config::load()?.owner;
+
+    let mut owners = store.owners();
+    if let Some(owner) = &notified
+        && !owners.contains(&(owner.channel.clone(), owner.user_id.clone()))
+    {
+        owners.insert(0, (owner.channel.clone(), owner.user_id.clone()));
+    }
+
+    if owners.is_empty() {
+        println!("No owner yet; make one with cica owner set <channel> <user-id>");
+        return Ok(());
+    }
+
+    for (channel, user_id) in owners {
+        let gets_notifications = notified
+            .as_ref()
+            .is_some_and(|o| o.channel == channel && o.user_id == user_id);
+        println!(
+            "{} user {}{}",
+            display_name(&channel),
+            user_id,
+            if gets_notifications {
+                " (receives system notifications)"
+            } else {
+                ""
+            }
+        );
+    }
+
+    Ok(())
+}
+
+fn set(channel: &str, user_id: &str) -> Result<()> {
+    let mut store = PairingStore::load()?;
+    store.set_role(channel, user_id, Role::Owner)?;
+
+    println!("{} user {} is now an owner", display_name(channel), user_id);
+    info!("Made {} user {} an owner", channel, user_id);
+
+    // The first owner also gets the system notifications
+    let mut config = Config::load()?;
+    if config.owner.is_none() {
+        config.owner = Some(OwnerConfig {
+            channel: channel.to_string(),
+            user_id: user_id.to_string(),
+        });
+        config.save()?;
+        println!("They'll also receive system notifications.");
+    }
+
+    Ok(())
+}
+
+fn remove(channel: &str, user_id: &str) -> Result<()> {
+    let mut store = PairingStore::load()?;
+    store.set_role(channel, user_id, Role::Member)?;
+
+    println!(
+        "{} user {} is no longer an owner",
+        display_name(channel),
+        user_id
+    );
+    info!("Made {} user {} a member", channel, user_id);
+
+    let config = Config::load()?;
+    if config
+        .owner
+        .is_some_and(|o| o.channel == channel && o.user_id == user_id)
+    {
+        println!(
+            "They're still the owner in config.toml, which receives system notifications; \
+             change [owner] there to hand that over."
+        );
+    }
+
+    Ok(())
+}
+
+fn display_name(channel: &str) -> &str {
+    channels::get_channel_info(channel)
+        .map(|c| c.display_name)
+        .unwrap_or(channel)
+}
@@ -1,18 +1,45 @@
 use anyhow::Result;
+use serde::Serialize;
+use std::path::PathBuf;
 
-use crate::config;
+use cica_core::config;
+
+use super::output::print_json;
+
+/// User-facing data paths, as printed by `cica paths --json`
+#[derive(Serialize)]
+struct PathsOutput {
+    base: PathBuf,
+    config: PathBuf,
+    database: PathBuf,
+    memory: PathBuf,
+    skills: PathBuf,
+    cron: PathBuf,
+}
 
 /// Run the paths command
-pub fn run() -> Result<()> {
+pub fn run(json: bool) -> Result<()> {
     let paths = config::paths()?;
 
+    if json {
+        return print_json(&PathsOutput {
+            base: paths.base,
+            config: paths.config_file,
+            database: paths.db_file,
+            memory: paths.memory_dir,
+            skills: paths.skills_dir,
+            cron: paths.cron_dir,
+        });
+    }
+
     println!("Cica data directories:");
     println!();
     println!("  Base:     {}", paths.base.display());
     println!("  Config:   {}", paths.config_file.display());
-    println!("  Pairing:  {}", paths.pairing_file.display());
+    println!("  Database: {}", paths.db_file.display());
     println!("  Memory:   {}", paths.memory_dir.display());
     println!("  Skills:   {}", paths.skills_dir.display());
+    println!("  Cron:     {}", paths.cron_dir.display());
 
     Ok(())
 }
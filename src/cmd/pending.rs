@@ -0,0 +1,66 @@
+use anyhow::Result;
+use std::time::SystemTime;
+
+use cica_core::channels;
+use cica_core::pairing::PairingStore;
+
+use super::output::print_json;
+
+/// Run the pending command
+pub fn run(json: bool) -> Result<()> {
+    let mut store = PairingStore::load()?;
+    let pending = store.list_pending();
+
+    if json {
+        return print_json(&pending);
+    }
+
+    if pending.is_empty() {
+        println!("No pending pairing requests.");
+        return Ok(());
+    }
+
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_secs();
+
+    println!(
+        "{:<10} {:<10} {:<24} {:<20} AGE",
+        "CODE", "CHANNEL", "USER", "USER ID"
+    );
+    for request in pending {
+        let channel_display = channels::get_channel_info(&request.channel)
+            .map(|c| c.display_name)
+            .unwrap_or(&request.channel);
+
+        let user_display = match (&request.display_name, &request.username) {
+            (Some(name), Some(username)) => format!("{} (@{})", name, username),
+            (Some(name), None) => name.clone(),
+            (None, Some(username)) => format!("@{}", username),
+            (None, None) => "-".to_string(),
+        };
+
+        println!(
+            "{:<10} {:<10} {:<24} {:<20} {}",
+            request.code,
+            channel_display,
+            user_display,
+            request.user_id,
+            format_age(now.saturating_sub(request.created_at))
+        );
+    }
+
+    println!();
+    println!("Approve with: cica approve <code>");
+
+    Ok(())
+}
+
+/// Format an age in seconds as a short human-readable string
+pub fn format_age(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{}s ago", secs),
+        60..3600 => format!("{}m ago", secs / 60),
+        _ => format!("{}h ago", secs / 3600),
+    }
+}
@@ -0,0 +1,80 @@
+use anyhow::{Context, Result, bail};
+use clap::Subcommand;
+use tracing::info;
+
+use cica_core::channels;
+use cica_core::onboarding;
+
+/// Persona subcommands
+#[derive(Subcommand)]
+pub enum PersonaCommand {
+    /// Print the persona
+    Show {
+        /// Show the one a channel's users get, if config.toml overrides it (e.g. "slack")
+        #[arg(long)]
+        channel: Option<String>,
+    },
+    /// Open the persona in $VISUAL or $EDITOR
+    Edit {
+        /// Edit the one a channel's users get, if config.toml overrides it (e.g. "slack")
+        #[arg(long)]
+        channel: Option<String>,
+    },
+}
+
+/// Run a persona subcommand
+pub fn run(command: PersonaCommand) -> Result<()> {
+    match command {
+        PersonaCommand::Show { channel } => show(channel.as_deref()),
+        PersonaCommand::Edit { channel } => edit(channel.as_deref()),
+    }
+}
+
+fn check_channel(channel: Option<&str>) -> Result<()> {
+    if let Some(channel) = channel
+        && channels::get_channel_info(channel).is_none()
+    {
+        bail!("Unknown channel: {}", channel);
+    }
+    Ok(())
+}
+
+fn show(channel: Option<&str>) -> Result<()> {
+    check_channel(channel)?;
+    match onboarding::load_persona(channel)? {
+        Some(content) => print!("{}", content),
+        None => println!("No persona yet; create one with cica persona edit"),
+    }
+    Ok(())
+}
+
+fn edit(channel: Option<&str>) -> Result<()> {
+    check_channel(channel)?;
+    let path = onboarding::persona_path(channel)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    // Editors may come with arguments, e.g. "code --wait"
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status()
+        .with_context(|| format!("Could not start {}", program))?;
+    if !status.success() {
+        bail!("{} exited with {}", program, status);
+    }
+
+    info!("Edited persona {}", path.display());
+    println!("Saved {}", path.display());
+    println!("It applies from the next message; no restart needed.");
+    Ok(())
+}
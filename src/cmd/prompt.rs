@@ -0,0 +1,64 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use cica_core::channels;
+use cica_core::context_sync::ContextUpdate;
+use cica_core::onboarding;
+use cica_core::pairing::PairingStore;
+
+use super::output::print_json;
+
+#[derive(Serialize)]
+struct PromptOutput<'a> {
+    channel: &'a str,
+    user_id: &'a str,
+    /// Whether the user is still onboarding, which uses its own prompt
+    onboarding: bool,
+    system_prompt: String,
+    /// The message with the context sections the session hasn't seen
+    message: String,
+}
+
+/// Run the prompt command: print the system prompt and message a message
+/// would be sent as, without querying the backend
+pub fn run(channel: &str, user_id: &str, message: &str, json: bool) -> Result<()> {
+    if channels::get_channel_info(channel).is_none() {
+        anyhow::bail!("Unknown channel: {}", channel);
+    }
+
+    let onboarding = !onboarding::is_complete_for_user(channel, user_id)?;
+    let (system_prompt, message) = if onboarding {
+        (
+            onboarding::system_prompt_for_user(channel, user_id)?,
+            message.to_string(),
+        )
+    } else {
+        let store = PairingStore::load()?;
+        let prompt = channels::context_prompt_for_user(&store, channel, user_id, message)?;
+
+        // A resumed session only gets the sections it hasn't seen
+        let context = match store.sessions.get(&format!("{}:{}", channel, user_id)) {
+            Some(session_id) => ContextUpdate::resumed_session(&prompt, session_id)?,
+            None => ContextUpdate::new_session(&prompt),
+        };
+        (context.preamble.clone(), context.message(message))
+    };
+
+    if json {
+        return print_json(&PromptOutput {
+            channel,
+            user_id,
+            onboarding,
+            system_prompt,
+            message,
+        });
+    }
+
+    if onboarding {
+        eprintln!("This user hasn't finished onboarding, so this is the onboarding prompt.\n");
+    }
+    println!("=== System prompt ===\n{}\n", system_prompt);
+    println!("=== Message ===\n{}", message);
+
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use anyhow::{Result, bail};
+use tracing::info;
+
+use cica_core::channels;
+use cica_core::pairing::PairingStore;
+
+/// Run the revoke command
+pub fn run(channel: &str, user_id: &str) -> Result<()> {
+    let channel_display = channels::get_channel_info(channel)
+        .map(|c| c.display_name)
+        .unwrap_or(channel);
+
+    let mut store = PairingStore::load()?;
+
+    if !store.revoke(channel, user_id)? {
+        bail!("{} user {} is not approved", channel_display, user_id);
+    }
+
+    println!("Revoked {} user: {}", channel_display, user_id);
+    println!("They will need to pair again to use Cica.");
+
+    info!("Revoked {} user {}", channel, user_id);
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use anyhow::Result;
+use tracing::info;
+
+use cica_core::channels;
+use cica_core::pairing::{PairingStore, Role};
+
+/// Run the role command: show a user's role, or set it when `role` is given
+pub fn run(channel: &str, user_id: &str, role: Option<Role>) -> Result<()> {
+    let channel_display = channels::get_channel_info(channel)
+        .map(|c| c.display_name)
+        .unwrap_or(channel);
+
+    let mut store = PairingStore::load()?;
+
+    let Some(role) = role else {
+        println!(
+            "{} user {}: {}",
+            channel_display,
+            user_id,
+            store.role(channel, user_id).as_str()
+        );
+        return Ok(());
+    };
+
+    store.set_role(channel, user_id, role)?;
+
+    println!(
+        "{} user {} is now a {}",
+        channel_display,
+        user_id,
+        role.as_str()
+    );
+
+    info!(
+        "Set role of {} user {} to {}",
+        channel,
+        user_id,
+        role.as_str()
+    );
+
+    Ok(())
+}
@@ -1,21 +1,32 @@
+use std::collections::HashMap;
 use std::future::Future;
+use std::path::PathBuf;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
-use anyhow::Result;
+use anyhow::{Result, anyhow, bail};
 use tokio::signal;
 use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
 use tracing::{error, info, warn};
 
-use crate::channels::{signal as signal_channel, slack, telegram};
-use crate::config::Config;
-use crate::cron::{CronConfig, CronService, SystemClock};
-use crate::memory::MemoryIndex;
-use crate::pairing::PairingStore;
-use crate::setup;
+use crate::cmd::features;
+use cica_core::channels::{self, ReplyAction, SendQueue};
+use cica_core::config::{Config, paths};
+use cica_core::cron::{CronConfig, CronService, ResultSender, SystemClock};
+use cica_core::memory::MemoryIndex;
+use cica_core::pairing::PairingStore;
+use cica_core::setup;
+use cica_core::transcript::{self, Speaker};
+use cica_core::{
+    attachments, digest, i18n, metrics, monitor, notify, quiet, redact, shutdown, storage,
+};
 
 /// Run the assistant (default command)
 pub async fn run() -> Result<()> {
+    monitor::mark_started();
+
     // Check if configured
     if !Config::exists()? {
         println!("Cica is not configured yet.");
@@ -23,7 +34,31 @@ pub async fn run() -> Result<()> {
         return Ok(());
     }
 
+    // Refuse to start on typos and missing fields rather than run without them
+    let problems = Config::validate()?;
+    if !problems.is_empty() {
+        bail!(
+            "Fix these in config.toml first:\n- {}",
+            problems.join("\n- ")
+        );
+    }
+
     let config = Config::load()?;
+
+    // Mask Cica's own tokens and the configured secrets in replies and logs
+    redact::init(&config);
+
+    // Report what this build can do, and anything configured it can't
+    let enabled: Vec<&str> = features::CAPABILITIES
+        .iter()
+        .filter(|c| c.enabled)
+        .map(|c| c.feature)
+        .collect();
+    info!("Optional features: {}", enabled.join(", "));
+    for problem in features::missing_for_config(&config) {
+        warn!("{}", problem);
+    }
+
     let channels = config.configured_channels();
 
     if channels.is_empty() {
@@ -42,169 +77,635 @@ pub async fn run() -> Result<()> {
     // Index memories for all approved users at startup
     index_all_user_memories();
 
-    // Start cron scheduler service
-    let cron_service = start_cron_service(&config)?;
+    // Shared outbox so channel replies, cron results and notifications share rate limits
+    let outbox = Arc::new(Outbox::new(&config));
+
+    // System notifications go to the owner through the same outbox
+    let notify_outbox = Arc::clone(&outbox);
+    notify::init(
+        config.owner.clone(),
+        Arc::new(move |channel, user_id, message, actions| {
+            let outbox = Arc::clone(&notify_outbox);
+            Box::pin(async move {
+                outbox
+                    .deliver(&channel, &user_id, &message, &[], actions)
+                    .await
+            })
+        }),
+    );
 
-    // Spawn tasks for each configured channel
-    let mut handles = Vec::new();
+    // Messages held during quiet hours go out once they end
+    tokio::spawn(deliver_digests(Arc::clone(&outbox)));
 
-    if let Some(telegram_config) = config.channels.telegram {
-        handles.push(tokio::spawn(async move {
-            if let Err(e) = telegram::run(telegram_config).await {
-                error!("Telegram channel error: {}", e);
-            }
-        }));
-    }
+    // Daily digests for the users who turned them on
+    tokio::spawn(send_daily_digests(Arc::clone(&outbox)));
 
-    if let Some(signal_config) = config.channels.signal {
-        handles.push(tokio::spawn(async move {
-            if let Err(e) = signal_channel::run(signal_config).await {
-                error!("Signal channel error: {}", e);
+    // Start cron scheduler service
+    let cron_service = start_cron_service(&config, outbox_sender(Arc::clone(&outbox)))?;
+
+    // Health and metrics endpoint for monitoring
+    if let Some(listen) = config.monitor.listen.clone() {
+        tokio::spawn(async move {
+            if let Err(e) = monitor::serve(&listen).await {
+                error!("Monitoring endpoint stopped: {:#}", e);
             }
-        }));
+        });
     }
 
-    if let Some(slack_config) = config.channels.slack {
-        handles.push(tokio::spawn(async move {
-            if let Err(e) = slack::run(slack_config).await {
-                error!("Slack channel error: {}", e);
-            }
-        }));
+    // Remove old attachments
+    tokio::spawn(clean_attachments());
+
+    // Warn the owner before the disk fills up
+    let data_dir = paths()?.base;
+    tokio::spawn(async move { notify::watch_disk_space(&data_dir).await });
+
+    // Spawn a supervised task for each configured channel
+    let mut running = HashMap::new();
+    for info in channels::SUPPORTED_CHANNELS {
+        if info.is_configured(&config) {
+            running.insert(info.name, start_channel(info, &config, &outbox)?);
+        }
     }
 
-    // Wait for Ctrl+C
+    // Run until Ctrl+C, applying changes to config.toml as they're saved
     tokio::select! {
-        _ = signal::ctrl_c() => {
-            info!("Received Ctrl+C, shutting down...");
+        _ = shutdown_signal() => {
+            info!("Shutting down...");
         }
-        _ = async {
-            for handle in handles {
-                let _ = handle.await;
-            }
-        } => {}
+        _ = watch_config(config, &outbox, &mut running) => {}
     }
 
-    // Stop cron service
+    // Turn new messages away and start no more cron runs; channels keep
+    // running so the work in progress can still reply
+    shutdown::begin();
     if let Some(service) = cron_service {
         let mut service = service.lock().await;
         service.stop().await;
     }
 
+    if !shutdown::requests().is_empty() {
+        info!(
+            "Waiting up to {:?} for running queries and cron jobs (again to stop now)",
+            SHUTDOWN_GRACE
+        );
+    }
+    let dropped = tokio::select! {
+        dropped = shutdown::drain(SHUTDOWN_GRACE) => dropped,
+        _ = shutdown_signal() => shutdown::requests(),
+    };
+    notify_dropped(&outbox, &dropped).await;
+
+    if let Err(e) = storage::checkpoint() {
+        warn!("Failed to flush the database: {}", e);
+    }
     Ok(())
 }
 
-/// Start the cron scheduler service
-fn start_cron_service(config: &Config) -> Result<Option<Arc<Mutex<CronService<SystemClock>>>>> {
-    let clock = SystemClock;
-    let cron_config = CronConfig::default();
+// ============================================================================
+// Shutdown
+// ============================================================================
+
+/// How long running queries and cron jobs get to finish on shutdown; service
+/// definitions give Cica longer than this before killing it
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(60);
+
+/// Longest spent telling users their requests were dropped
+const NOTIFY_DROPPED_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Wait for Ctrl+C, or SIGTERM from a service manager
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use signal::unix::{SignalKind, signal as unix_signal};
+        match unix_signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(e) => {
+                warn!("Can't listen for SIGTERM: {}", e);
+                let _ = signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = signal::ctrl_c().await;
+}
 
-    let mut service = match CronService::new(clock, cron_config) {
-        Ok(s) => s,
-        Err(e) => {
-            warn!("Failed to initialize cron service: {}", e);
-            return Ok(None);
+/// Tell the people whose requests didn't finish in time to send them again
+async fn notify_dropped(outbox: &Arc<Outbox>, dropped: &[shutdown::Request]) {
+    if dropped.is_empty() {
+        return;
+    }
+    warn!("Stopping with {} request(s) unfinished", dropped.len());
+
+    let mut sends = tokio::task::JoinSet::new();
+    for request in dropped.iter().cloned() {
+        let outbox = Arc::clone(outbox);
+        sends.spawn(async move {
+            let message = format!(
+                "Sorry, I had to shut down before finishing {}. \
+                 Please try again once I'm back.",
+                request.description
+            );
+            if let Err(e) = outbox
+                .send(&request.channel, &request.user_id, &message)
+                .await
+            {
+                warn!(
+                    "Failed to tell {}:{} their request was dropped: {}",
+                    request.channel, request.user_id, e
+                );
+            }
+        });
+    }
+    if tokio::time::timeout(NOTIFY_DROPPED_TIMEOUT, sends.join_all())
+        .await
+        .is_err()
+    {
+        warn!("Gave up telling users about unfinished requests");
+    }
+}
+
+// ============================================================================
+// Config Reloading
+// ============================================================================
+
+/// How often config.toml is checked for changes
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Start a supervised task running a channel
+fn start_channel(
+    info: &'static channels::ChannelInfo,
+    config: &Config,
+    outbox: &Outbox,
+) -> Result<JoinHandle<()>> {
+    let config = config.clone();
+    let send_queue = outbox.queue(info.name)?;
+    Ok(tokio::spawn(supervise(info.name, move || {
+        (info.run)(config.clone(), send_queue.clone())
+    })))
+}
+
+fn config_modified() -> Option<SystemTime> {
+    let path = paths().ok()?.config_file;
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Watch config.toml and apply changes without a restart. Most settings are
+/// read again when they're used; channels whose section changed are
+/// restarted, and added or removed ones started or stopped. Never returns.
+async fn watch_config(
+    mut config: Config,
+    outbox: &Outbox,
+    running: &mut HashMap<&'static str, JoinHandle<()>>,
+) {
+    let mut modified = config_modified();
+    loop {
+        tokio::time::sleep(CONFIG_POLL_INTERVAL).await;
+        let now_modified = config_modified();
+        if now_modified == modified {
+            continue;
         }
-    };
+        modified = now_modified;
+
+        let new_config = match Config::validate().and_then(|problems| {
+            if !problems.is_empty() {
+                bail!("{}", problems.join("; "));
+            }
+            Config::load()
+        }) {
+            Ok(new_config) => new_config,
+            Err(e) => {
+                warn!("Not applying config.toml changes: {:#}", e);
+                continue;
+            }
+        };
+        info!("config.toml changed, applying");
+        redact::init(&new_config);
+        apply_config(&config, &new_config, outbox, running);
+        config = new_config;
+    }
+}
 
-    // Create result sender that routes messages to the appropriate channel
-    let telegram_token = config
-        .channels
-        .telegram
-        .as_ref()
-        .map(|c| c.bot_token.clone());
-    let signal_phone = config
-        .channels
-        .signal
-        .as_ref()
-        .map(|c| c.phone_number.clone());
-    let slack_bot_token = config.channels.slack.as_ref().map(|c| c.bot_token.clone());
-
-    let result_sender: crate::cron::ResultSender = Arc::new(move |channel, user_id, message| {
-        let telegram_token = telegram_token.clone();
-        let signal_phone = signal_phone.clone();
-        let slack_bot_token = slack_bot_token.clone();
+/// Apply the differences between two configs to the running process
+fn apply_config(
+    old: &Config,
+    new: &Config,
+    outbox: &Outbox,
+    running: &mut HashMap<&'static str, JoinHandle<()>>,
+) {
+    outbox.set_config(new);
+
+    if let Some(owner) = &new.owner
+        && old.owner.as_ref() != Some(owner)
+    {
+        notify::set_owner(owner.clone());
+    }
 
-        Box::pin(async move {
-            match channel.as_str() {
-                "telegram" => {
-                    if let Some(token) = telegram_token {
-                        send_telegram_message(&token, &user_id, &message).await
-                    } else {
-                        Err(anyhow::anyhow!("Telegram not configured"))
-                    }
-                }
-                "signal" => {
-                    if let Some(_phone) = signal_phone {
-                        send_signal_message(&user_id, &message).await
-                    } else {
-                        Err(anyhow::anyhow!("Signal not configured"))
-                    }
-                }
-                "slack" => {
-                    if let Some(token) = slack_bot_token {
-                        send_slack_message(&token, &user_id, &message).await
-                    } else {
-                        Err(anyhow::anyhow!("Slack not configured"))
-                    }
+    let (old_channels, new_channels) = (
+        serde_json::to_value(&old.channels).unwrap_or_default(),
+        serde_json::to_value(&new.channels).unwrap_or_default(),
+    );
+    for info in channels::SUPPORTED_CHANNELS {
+        let configured = info.is_configured(new);
+        let changed = old_channels.get(info.name) != new_channels.get(info.name);
+        if !changed && configured == running.contains_key(info.name) {
+            continue;
+        }
+
+        if let Some(handle) = running.remove(info.name) {
+            info!("Stopping the {} channel", info.name);
+            handle.abort();
+        }
+        if configured {
+            info!("Starting the {} channel", info.name);
+            match start_channel(info, new, outbox) {
+                Ok(handle) => {
+                    running.insert(info.name, handle);
                 }
-                _ => Err(anyhow::anyhow!("Unknown channel: {}", channel)),
+                Err(e) => warn!("Failed to start the {} channel: {}", info.name, e),
             }
-        }) as Pin<Box<dyn Future<Output = Result<()>> + Send>>
-    });
+        } else {
+            monitor::channel_removed(info.name);
+        }
+    }
 
-    service.start(result_sender);
-    info!("Cron scheduler started");
+    if old.monitor.listen != new.monitor.listen
+        || old.queries.max_concurrent_jobs != new.queries.max_concurrent_jobs
+    {
+        warn!("Changes to [monitor] and max_concurrent_jobs apply after a restart");
+    }
+}
 
-    Ok(Some(Arc::new(Mutex::new(service))))
+// ============================================================================
+// Channel Supervisor
+// ============================================================================
+
+/// Delay before the first restart of a crashed channel
+const RESTART_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Upper bound for the delay between restarts
+const RESTART_MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// A channel that stays up this long is considered healthy again
+const HEALTHY_UPTIME: Duration = Duration::from_secs(600);
+
+/// Notify the owner after this many consecutive crashes
+const NOTIFY_AFTER_FAILURES: u32 = 3;
+
+/// Run a channel until shutdown, restarting it with exponential backoff when
+/// it errors, panics or exits, and notifying the owner on repeated failures.
+async fn supervise<F, Fut>(channel: &'static str, mut start: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    let mut failures: u32 = 0;
+    let mut backoff = RESTART_INITIAL_BACKOFF;
+
+    loop {
+        let started = Instant::now();
+        monitor::channel_up(channel);
+
+        // Run in its own task so a panic is reported instead of taking us
+        // down, stopped along with this one when the channel is removed
+        let task = tokio::spawn(start());
+        let _stop = AbortOnDrop(task.abort_handle());
+        let reason = match task.await {
+            Ok(Ok(())) => "exited unexpectedly".to_string(),
+            Ok(Err(e)) => format!("{:#}", e),
+            Err(e) => format!("panicked: {}", e),
+        };
+
+        // Stopping along with the rest of Cica isn't a crash
+        if shutdown::is_shutting_down() {
+            info!("{} channel stopped ({})", channel, reason);
+            monitor::channel_stopped(channel);
+            return;
+        }
+
+        // A long healthy run resets the failure streak
+        if started.elapsed() >= HEALTHY_UPTIME {
+            failures = 0;
+            backoff = RESTART_INITIAL_BACKOFF;
+        }
+        failures += 1;
+        monitor::channel_down(channel);
+        metrics::increment(&format!("channel.restarts.{}", channel));
+
+        error!(
+            "{} channel stopped ({}), restarting in {:?} (failure {})",
+            channel, reason, backoff, failures
+        );
+
+        if failures == NOTIFY_AFTER_FAILURES {
+            notify::notify_owner(format!(
+                "The {} channel has crashed {} times in a row. \
+                 Cica keeps retrying in the background.\n\nLast error: {}",
+                channel, failures, reason
+            ));
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(RESTART_MAX_BACKOFF);
+    }
 }
 
-/// Send a message via Telegram
-async fn send_telegram_message(token: &str, user_id: &str, message: &str) -> Result<()> {
-    use teloxide::prelude::*;
+/// Stops a task when dropped
+struct AbortOnDrop(tokio::task::AbortHandle);
 
-    let bot = Bot::new(token);
-    let chat_id: i64 = user_id.parse()?;
-    bot.send_message(ChatId(chat_id), message).await?;
-    Ok(())
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+// ============================================================================
+// Outbox
+// ============================================================================
+
+/// Sends messages to users outside a conversation (cron results, notifications)
+struct Outbox {
+    /// Replaced when config.toml changes
+    config: std::sync::RwLock<Config>,
+    /// Outgoing send queues, one per channel
+    queues: HashMap<&'static str, Arc<SendQueue>>,
 }
 
-/// Send a message via Signal
-async fn send_signal_message(recipient: &str, message: &str) -> Result<()> {
-    use jsonrpsee::core::client::ClientT;
-    use jsonrpsee::core::params::ObjectParams;
-    use jsonrpsee::http_client::HttpClientBuilder;
-    use serde_json::Value;
+impl Outbox {
+    fn new(config: &Config) -> Self {
+        Self {
+            config: std::sync::RwLock::new(config.clone()),
+            queues: channels::SUPPORTED_CHANNELS
+                .iter()
+                .map(|info| (info.name, (info.send_queue)()))
+                .collect(),
+        }
+    }
 
-    // Connect to the signal-cli daemon
-    let url = "http://127.0.0.1:18080/api/v1/rpc";
-    let client = HttpClientBuilder::default().build(url)?;
+    fn config(&self) -> Config {
+        self.config.read().unwrap().clone()
+    }
 
-    let mut params = ObjectParams::new();
-    params.insert("recipient", vec![recipient])?;
-    params.insert("message", message)?;
+    fn set_config(&self, config: &Config) {
+        *self.config.write().unwrap() = config.clone();
+    }
 
-    let _: Value = client.request("send", params).await?;
-    Ok(())
+    /// Get the send queue shared by everything that sends on a channel
+    fn queue(&self, channel: &str) -> Result<Arc<SendQueue>> {
+        self.queues
+            .get(channel)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unknown channel: {}", channel))
+    }
+
+    /// Send a message to a user on the given channel
+    async fn send(&self, channel: &str, user_id: &str, message: &str) -> Result<()> {
+        self.send_with_actions(channel, user_id, message, Vec::new())
+            .await
+    }
+
+    /// Send a message followed by files. Channels that can't send files say
+    /// where they are instead.
+    async fn send_with_files(
+        &self,
+        channel: &str,
+        user_id: &str,
+        message: &str,
+        files: &[PathBuf],
+    ) -> Result<()> {
+        let Some(info) = channels::get_channel_info(channel) else {
+            bail!("Unknown channel: {}", channel);
+        };
+
+        self.send(channel, user_id, message).await?;
+        if files.is_empty() {
+            return Ok(());
+        }
+
+        let channel = (info.open)(self.config(), user_id.to_string(), self.queue(channel)?).await?;
+        for file in files {
+            channel.send_file(file).await?;
+        }
+        Ok(())
+    }
+
+    /// Send a message outside a conversation, or hold it during the user's
+    /// quiet hours for [`deliver_digests`]. Held messages lose their buttons.
+    async fn deliver(
+        &self,
+        channel: &str,
+        user_id: &str,
+        message: &str,
+        files: &[PathBuf],
+        actions: Vec<ReplyAction>,
+    ) -> Result<()> {
+        if quiet::is_quiet_now(channel, user_id) {
+            info!(
+                "Holding a message for {}:{} until their quiet hours end",
+                channel, user_id
+            );
+            return quiet::hold(channel, user_id, message, files);
+        }
+
+        if files.is_empty() {
+            self.send_with_actions(channel, user_id, message, actions)
+                .await
+        } else {
+            self.send_with_files(channel, user_id, message, files).await
+        }
+    }
+
+    /// Send a message with quick-reply buttons (where the channel supports them)
+    async fn send_with_actions(
+        &self,
+        channel: &str,
+        user_id: &str,
+        message: &str,
+        actions: Vec<ReplyAction>,
+    ) -> Result<()> {
+        let Some(info) = channels::get_channel_info(channel) else {
+            bail!("Unknown channel: {}", channel);
+        };
+        let message = redact::redact(message);
+
+        self.queue(channel)?
+            .send(user_id, || {
+                (info.send_to_user)(
+                    self.config(),
+                    user_id.to_string(),
+                    message.to_string(),
+                    actions.clone(),
+                )
+            })
+            .await
+    }
 }
 
-/// Send a message via Slack
-async fn send_slack_message(bot_token: &str, channel_id: &str, message: &str) -> Result<()> {
-    use slack_morphism::prelude::*;
+/// How often old attachments are removed
+const ATTACHMENT_CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
 
-    let client = SlackClient::new(SlackClientHyperConnector::new()?);
-    let token = SlackApiToken::new(bot_token.into());
-    let session = client.open_session(&token);
+/// Remove attachments past the retention policy under [attachments]
+async fn clean_attachments() {
+    let mut interval = tokio::time::interval(ATTACHMENT_CLEANUP_INTERVAL);
+    loop {
+        interval.tick().await;
 
-    let request = SlackApiChatPostMessageRequest::new(
-        channel_id.into(),
-        SlackMessageContent::new().with_text(message.to_string()),
-    );
+        let policy = match Config::load() {
+            Ok(config) => config.attachments,
+            Err(e) => {
+                warn!("Not cleaning up attachments: {}", e);
+                continue;
+            }
+        };
+        match attachments::clean(&policy, false) {
+            Ok(report) if report.files > 0 => info!(
+                "Removed {} old attachment(s), {} bytes",
+                report.files, report.bytes
+            ),
+            Ok(_) => {}
+            Err(e) => warn!("Failed to clean up attachments: {}", e),
+        }
+    }
+}
 
-    session.chat_post_message(&request).await?;
-    Ok(())
+/// How often to check for quiet hours that have ended
+const DIGEST_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Send each user what was held during their quiet hours, once they end
+async fn deliver_digests(outbox: Arc<Outbox>) {
+    let mut interval = tokio::time::interval(DIGEST_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let digests = match quiet::take_due_digests() {
+            Ok(digests) => digests,
+            Err(e) => {
+                warn!("Failed to read held messages: {}", e);
+                continue;
+            }
+        };
+        for digest in digests {
+            let language = i18n::user_language(&digest.channel, &digest.user_id);
+            info!(
+                "Sending {} held message(s) to {}:{}",
+                digest.messages.len(),
+                digest.channel,
+                digest.user_id
+            );
+            if let Err(e) = outbox
+                .send_with_files(
+                    &digest.channel,
+                    &digest.user_id,
+                    &digest.text(language.as_deref()),
+                    &digest.files(),
+                )
+                .await
+            {
+                warn!(
+                    "Failed to send held messages to {}:{}: {}",
+                    digest.channel, digest.user_id, e
+                );
+            }
+        }
+    }
 }
 
-/// Index memories for all approved users
+/// How often to check for daily digests that are due
+const DAILY_DIGEST_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Build and send each user's daily digest once it's due
+async fn send_daily_digests(outbox: Arc<Outbox>) {
+    let mut interval = tokio::time::interval(DAILY_DIGEST_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let mut store = match PairingStore::load() {
+            Ok(store) => store,
+            Err(e) => {
+                warn!("Failed to load pairing store for daily digests: {}", e);
+                continue;
+            }
+        };
+        let sent_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        for (channel, user_id) in digest::due_users(&store) {
+            // Marked first, so a slow or failed digest isn't sent twice
+            if let Err(e) = store.set_digest_sent(&channel, &user_id, sent_at) {
+                warn!("Failed to record the daily digest: {}", e);
+                continue;
+            }
+
+            let outbox = Arc::clone(&outbox);
+            tokio::spawn(async move {
+                match digest::build(&channel, &user_id).await {
+                    Ok(Some(text)) => {
+                        transcript::record(&channel, &user_id, Speaker::Assistant, &text);
+                        if let Err(e) = outbox
+                            .deliver(&channel, &user_id, &text, &[], Vec::new())
+                            .await
+                        {
+                            warn!(
+                                "Failed to send the daily digest to {}:{}: {}",
+                                channel, user_id, e
+                            );
+                        }
+                    }
+                    Ok(None) => info!("Nothing for {}:{}'s daily digest", channel, user_id),
+                    Err(e) => warn!(
+                        "Failed to build the daily digest for {}:{}: {}",
+                        channel, user_id, e
+                    ),
+                }
+            });
+        }
+    }
+}
+
+/// Wrap the outbox in a callback for the cron service
+fn outbox_sender(outbox: Arc<Outbox>) -> ResultSender {
+    Arc::new(move |channel, user_id, message, files| {
+        let outbox = Arc::clone(&outbox);
+
+        Box::pin(async move {
+            outbox
+                .deliver(&channel, &user_id, &message, &files, Vec::new())
+                .await
+        }) as Pin<Box<dyn Future<Output = Result<()>> + Send>>
+    })
+}
+
+/// Start the cron scheduler service
+fn start_cron_service(
+    config: &Config,
+    result_sender: ResultSender,
+) -> Result<Option<Arc<Mutex<CronService<SystemClock>>>>> {
+    let clock = SystemClock;
+    let cron_config = CronConfig {
+        max_concurrent: config.queries.max_concurrent_jobs,
+        ..Default::default()
+    };
+
+    let mut service = match CronService::new(clock, cron_config) {
+        Ok(s) => s,
+        Err(e) => {
+            warn!("Failed to initialize cron service: {}", e);
+            return Ok(None);
+        }
+    };
+
+    service.start(result_sender);
+    info!("Cron scheduler started");
+
+    Ok(Some(Arc::new(Mutex::new(service))))
+}
+
+/// Index memories for all approved users and the shared ones
 fn index_all_user_memories() {
     let store = match PairingStore::load() {
         Ok(s) => s,
@@ -223,21 +724,18 @@ fn index_all_user_memories() {
     };
 
     // Index memories for each approved user
-    for key in store.approved.keys() {
-        // Key format is "channel:user_id"
-        let parts: Vec<&str> = key.splitn(2, ':').collect();
-        if parts.len() != 2 {
-            continue;
-        }
-        let (channel, user_id) = (parts[0], parts[1]);
-
-        if let Err(e) = index.index_user_memories(channel, user_id) {
+    for user in store.approved_users() {
+        if let Err(e) = index.index_user_memories(&user.channel, &user.user_id) {
             warn!(
                 "Failed to index memories for {}:{}: {}",
-                channel, user_id, e
+                user.channel, user.user_id, e
             );
         }
     }
 
+    if let Err(e) = index.index_shared_memories() {
+        warn!("Failed to index shared memories: {}", e);
+    }
+
     info!("Memory indexing complete");
 }
@@ -0,0 +1,68 @@
+use anyhow::Result;
+use dialoguer::{Password, theme::ColorfulTheme};
+use tracing::info;
+
+use cica_core::config::Config;
+use cica_core::secrets::{self, SecretStore};
+
+/// Describe where a store keeps secrets
+fn describe(store: SecretStore) -> &'static str {
+    match store {
+        SecretStore::Plaintext => "in plaintext in config.toml",
+        SecretStore::Keyring => "in the OS keyring",
+        SecretStore::Passphrase => "in config.toml, encrypted with a passphrase",
+    }
+}
+
+/// Run the secrets command: show the current store, or move secrets to another
+pub fn run(store: Option<SecretStore>) -> Result<()> {
+    let mut config = Config::load()?;
+    let current = config.secrets.store;
+
+    let Some(store) = store else {
+        println!("Tokens and API keys are stored {}.", describe(current));
+        return Ok(());
+    };
+
+    if store == current {
+        println!(
+            "Tokens and API keys are already stored {}.",
+            describe(store)
+        );
+        return Ok(());
+    }
+
+    if store == SecretStore::Passphrase {
+        let passphrase = Password::with_theme(&ColorfulTheme::default())
+            .with_prompt("New passphrase")
+            .with_confirmation("Confirm passphrase", "Passphrases don't match")
+            .interact()?;
+        secrets::set_passphrase(passphrase);
+    }
+
+    config.secrets.store = store;
+    config.secrets.salt = None;
+    config.save()?;
+
+    if current == SecretStore::Keyring {
+        for name in config.secret_names() {
+            secrets::forget_keyring(name)?;
+        }
+    }
+
+    println!("Tokens and API keys are now stored {}.", describe(store));
+    if store == SecretStore::Passphrase {
+        println!(
+            "Cica will ask for the passphrase on startup, or read it from {}.",
+            secrets::PASSPHRASE_ENV
+        );
+    }
+
+    info!(
+        "Moved secrets from {} to {}",
+        current.as_str(),
+        store.as_str()
+    );
+
+    Ok(())
+}
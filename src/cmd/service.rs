@@ -0,0 +1,299 @@
+use anyhow::{Context, Result, bail};
+use clap::Subcommand;
+use std::path::PathBuf;
+use std::process::Command;
+
+use cica_core::config;
+use cica_core::secrets;
+
+/// Name of the systemd unit and launchd job
+const SERVICE_NAME: &str = "cica";
+
+/// Service subcommands
+#[derive(Subcommand)]
+pub enum ServiceCommand {
+    /// Run Cica in the background at login, restarting it if it crashes
+    /// (systemd user service on Linux, launchd agent on macOS)
+    Install,
+    /// Stop the service and remove it
+    Uninstall,
+    /// Show whether the service is running
+    Status,
+    /// Stop the service until the next login or `cica service restart`
+    Stop,
+    /// Restart the service, e.g. after upgrading Cica
+    Restart,
+}
+
+/// Run a service subcommand
+pub fn run(command: ServiceCommand) -> Result<()> {
+    let manager = ServiceManager::detect()?;
+    match command {
+        ServiceCommand::Install => install(manager),
+        ServiceCommand::Uninstall => uninstall(manager),
+        ServiceCommand::Status => manager.status(),
+        ServiceCommand::Stop => {
+            manager.stop()?;
+            println!("Stopped Cica");
+            Ok(())
+        }
+        ServiceCommand::Restart => {
+            manager.restart()?;
+            println!("Restarted Cica");
+            Ok(())
+        }
+    }
+}
+
+/// The init system that runs the service
+#[derive(Clone, Copy)]
+enum ServiceManager {
+    Systemd,
+    Launchd,
+}
+
+impl ServiceManager {
+    fn detect() -> Result<Self> {
+        if cfg!(target_os = "macos") {
+            Ok(Self::Launchd)
+        } else if cfg!(target_os = "linux") {
+            Ok(Self::Systemd)
+        } else {
+            bail!("cica service supports systemd (Linux) and launchd (macOS) only")
+        }
+    }
+
+    /// Where the unit or plist goes
+    fn definition_path(self) -> Result<PathBuf> {
+        let home = std::env::var_os("HOME").context("HOME isn't set")?;
+        let home = PathBuf::from(home);
+        Ok(match self {
+            Self::Systemd => home
+                .join(".config/systemd/user")
+                .join(format!("{}.service", SERVICE_NAME)),
+            Self::Launchd => home
+                .join("Library/LaunchAgents")
+                .join(format!("{}.plist", SERVICE_NAME)),
+        })
+    }
+
+    /// The unit or plist that runs `exe`
+    fn definition(self, exe: &str, path_env: &str, log: &str) -> String {
+        match self {
+            Self::Systemd => format!(
+                "[Unit]\n\
+                 Description=Cica personal assistant\n\
+                 After=network-online.target\n\
+                 Wants=network-online.target\n\
+                 \n\
+                 [Service]\n\
+                 ExecStart=\"{exe}\"\n\
+                 Restart=on-failure\n\
+                 RestartSec=10\n\
+                 TimeoutStopSec=90\n\
+                 Environment=\"PATH={path_env}\"\n\
+                 \n\
+                 [Install]\n\
+                 WantedBy=default.target\n"
+            ),
+            Self::Launchd => format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+                 <plist version=\"1.0\">\n\
+                 <dict>\n\
+                 \x20   <key>Label</key>\n\
+                 \x20   <string>{SERVICE_NAME}</string>\n\
+                 \x20   <key>ProgramArguments</key>\n\
+                 \x20   <array>\n\
+                 \x20       <string>{exe}</string>\n\
+                 \x20   </array>\n\
+                 \x20   <key>EnvironmentVariables</key>\n\
+                 \x20   <dict>\n\
+                 \x20       <key>PATH</key>\n\
+                 \x20       <string>{path_env}</string>\n\
+                 \x20   </dict>\n\
+                 \x20   <key>RunAtLoad</key>\n\
+                 \x20   <true/>\n\
+                 \x20   <key>KeepAlive</key>\n\
+                 \x20   <dict>\n\
+                 \x20       <key>SuccessfulExit</key>\n\
+                 \x20       <false/>\n\
+                 \x20   </dict>\n\
+                 \x20   <key>ExitTimeOut</key>\n\
+                 \x20   <integer>90</integer>\n\
+                 \x20   <key>ThrottleInterval</key>\n\
+                 \x20   <integer>10</integer>\n\
+                 \x20   <key>StandardOutPath</key>\n\
+                 \x20   <string>{log}</string>\n\
+                 \x20   <key>StandardErrorPath</key>\n\
+                 \x20   <string>{log}</string>\n\
+                 </dict>\n\
+                 </plist>\n",
+                exe = escape_xml(exe),
+                path_env = escape_xml(path_env),
+                log = escape_xml(log),
+            ),
+        }
+    }
+
+    /// Load the definition and start the service
+    fn enable(self) -> Result<()> {
+        match self {
+            Self::Systemd => {
+                systemctl(&["daemon-reload"])?;
+                systemctl(&["enable", "--now", SERVICE_NAME])
+            }
+            Self::Launchd => {
+                let path = self.definition_path()?;
+                launchctl(&["bootstrap", &gui_domain()?, &path.to_string_lossy()])
+            }
+        }
+    }
+
+    /// Stop the service and unload its definition
+    fn disable(self) -> Result<()> {
+        match self {
+            Self::Systemd => systemctl(&["disable", "--now", SERVICE_NAME]),
+            Self::Launchd => launchctl(&["bootout", &launchd_target()?]),
+        }
+    }
+
+    fn status(self) -> Result<()> {
+        // Both print their own report; a stopped service isn't an error here
+        let status = match self {
+            Self::Systemd => Command::new("systemctl")
+                .args(["--user", "status", "--no-pager", SERVICE_NAME])
+                .status(),
+            Self::Launchd => Command::new("launchctl")
+                .args(["print", &launchd_target()?])
+                .status(),
+        }
+        .context("Couldn't ask the service manager")?;
+        if !status.success() && !self.definition_path()?.exists() {
+            println!("The service isn't installed; run cica service install");
+        }
+        Ok(())
+    }
+
+    fn stop(self) -> Result<()> {
+        match self {
+            Self::Systemd => systemctl(&["stop", SERVICE_NAME]),
+            // Unloading keeps KeepAlive from starting it again
+            Self::Launchd => launchctl(&["bootout", &launchd_target()?]),
+        }
+    }
+
+    fn restart(self) -> Result<()> {
+        match self {
+            Self::Systemd => systemctl(&["restart", SERVICE_NAME]),
+            Self::Launchd => {
+                // Load it again if it was stopped
+                let path = self.definition_path()?;
+                let _ = Command::new("launchctl")
+                    .args(["bootstrap", &gui_domain()?, &path.to_string_lossy()])
+                    .output();
+                launchctl(&["kickstart", "-k", &launchd_target()?])
+            }
+        }
+    }
+}
+
+/// Write the service definition for this binary and start it
+fn install(manager: ServiceManager) -> Result<()> {
+    if !config::Config::exists()? {
+        bail!("Cica isn't set up yet; run cica init first");
+    }
+
+    let exe = std::env::current_exe().context("Couldn't find the cica binary")?;
+    let exe = exe.to_string_lossy();
+    let path_env = std::env::var("PATH").unwrap_or_default();
+    let log = config::paths()?.internal_dir.join("service.log");
+
+    let path = manager.definition_path()?;
+    if path.exists() {
+        // Reinstalling, e.g. after moving the binary
+        let _ = manager.disable();
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(
+        &path,
+        manager.definition(&exe, &path_env, &log.to_string_lossy()),
+    )?;
+    manager.enable()?;
+
+    println!("Installed and started the service: {}", path.display());
+    match manager {
+        ServiceManager::Systemd => {
+            println!("Logs: journalctl --user -u {} -f", SERVICE_NAME);
+            println!("To keep it running while you're logged out: loginctl enable-linger $USER");
+        }
+        ServiceManager::Launchd => println!("Logs: {}", log.display()),
+    }
+    if secrets::config_needs_passphrase() {
+        println!(
+            "Secrets are encrypted with a passphrase, which the service can't ask for; \
+             set {} in its environment.",
+            secrets::PASSPHRASE_ENV
+        );
+    }
+    Ok(())
+}
+
+/// Stop the service and delete its definition
+fn uninstall(manager: ServiceManager) -> Result<()> {
+    let path = manager.definition_path()?;
+    if !path.exists() {
+        println!("The service isn't installed");
+        return Ok(());
+    }
+    let _ = manager.disable();
+    std::fs::remove_file(&path)?;
+    if let ServiceManager::Systemd = manager {
+        systemctl(&["daemon-reload"])?;
+    }
+    println!("Removed the service");
+    Ok(())
+}
+
+fn systemctl(args: &[&str]) -> Result<()> {
+    run_checked(Command::new("systemctl").arg("--user").args(args))
+}
+
+fn launchctl(args: &[&str]) -> Result<()> {
+    run_checked(Command::new("launchctl").args(args))
+}
+
+/// Run a command, failing with its output if it fails
+fn run_checked(command: &mut Command) -> Result<()> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .output()
+        .with_context(|| format!("Couldn't run {}", program))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("{} failed: {}", program, stderr.trim());
+    }
+    Ok(())
+}
+
+/// launchd domain of the current user's GUI session, e.g. "gui/501"
+fn gui_domain() -> Result<String> {
+    let output = Command::new("id").arg("-u").output()?;
+    let uid = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if uid.is_empty() {
+        bail!("Couldn't find the current user's ID");
+    }
+    Ok(format!("gui/{}", uid))
+}
+
+fn launchd_target() -> Result<String> {
+    Ok(format!("{}/{}", gui_domain()?, SERVICE_NAME))
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
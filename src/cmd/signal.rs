@@ -0,0 +1,213 @@
+use anyhow::{Result, bail};
+use clap::Subcommand;
+use dialoguer::{Select, theme::ColorfulTheme};
+use tracing::info;
+
+use crate::cmd::init;
+use cica_core::channels::signal;
+use cica_core::config::{self, Config};
+use cica_core::setup::{self, Dep};
+
+/// Signal account management subcommands
+#[derive(Subcommand)]
+pub enum SignalCommand {
+    /// Show the linked/registered Signal account and its state
+    Status,
+
+    /// Re-link Cica after Signal revoked the device
+    Relink,
+
+    /// Unregister the account and remove local Signal data
+    Unregister,
+}
+
+/// Run a signal subcommand
+pub async fn run(command: SignalCommand) -> Result<()> {
+    match command {
+        SignalCommand::Status => status(),
+        SignalCommand::Relink => relink().await,
+        SignalCommand::Unregister => unregister().await,
+    }
+}
+
+/// Load the configured Signal phone number
+fn configured_number(config: &Config) -> Result<String> {
+    match &config.channels.signal {
+        Some(signal_config) => Ok(signal_config.phone_number.clone()),
+        None => bail!("Signal is not configured. Run `cica init` to set it up."),
+    }
+}
+
+/// Bail if the signal-cli daemon is running (it holds the account data)
+fn ensure_daemon_stopped() -> Result<()> {
+    if let Some(pid) = signal::daemon_pid() {
+        bail!(
+            "signal-cli daemon is running (PID {}). Stop Cica first.",
+            pid
+        );
+    }
+    Ok(())
+}
+
+/// Show the Signal account status
+fn status() -> Result<()> {
+    let config = Config::load()?;
+    let phone_number = configured_number(&config)?;
+    let paths = config::paths()?;
+
+    println!("Signal account: {}", phone_number);
+    println!();
+
+    match signal::account_status(&phone_number)? {
+        Some(account) => {
+            let kind = match account.device_id {
+                Some(id) if account.is_linked_device() => format!("Linked device (device {})", id),
+                Some(_) => "Primary (registered number)".to_string(),
+                None => "Unknown".to_string(),
+            };
+            let state = if account.registered {
+                "Active"
+            } else if account.is_linked_device() {
+                "Revoked - run `cica signal relink`"
+            } else {
+                "Not registered - run `cica init` to register again"
+            };
+
+            println!("  Type:    {}", kind);
+            println!("  State:   {}", state);
+            if let Some(uuid) = &account.uuid {
+                println!("  UUID:    {}", uuid);
+            }
+        }
+        None => {
+            println!("  State:   No local account data - run `cica signal relink` or `cica init`");
+        }
+    }
+
+    match signal::daemon_pid() {
+        Some(pid) => println!("  Daemon:  running (PID {})", pid),
+        None => println!("  Daemon:  not running"),
+    }
+    println!("  Data:    {}", paths.signal_data_dir.display());
+
+    Ok(())
+}
+
+/// Remove stale account data and link Cica as a new device
+async fn relink() -> Result<()> {
+    let config = Config::load()?;
+    let phone_number = configured_number(&config)?;
+    ensure_daemon_stopped()?;
+
+    if let Some(account) = signal::account_status(&phone_number)?
+        && !account.is_linked_device()
+        && account.registered
+    {
+        println!(
+            "{} is registered as a primary device. Relinking will replace it with a linked device.",
+            phone_number
+        );
+        println!();
+    }
+
+    let confirm = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Remove the local Signal data for this account and link again?")
+        .items(&["Yes, relink", "Cancel"])
+        .default(0)
+        .interact()?;
+
+    if confirm != 0 {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    print!("Removing old Signal data... ");
+    std::io::Write::flush(&mut std::io::stdout())?;
+    match signal::remove_account_data() {
+        Ok(()) => println!("OK"),
+        Err(e) => {
+            println!("FAILED");
+            return Err(e);
+        }
+    }
+
+    // Make sure the runtime is available before linking
+    if setup::find_java().is_none() || setup::find_signal_cli().is_none() {
+        init::setup_runtime(
+            "Setting up Signal runtime",
+            &[Dep::Java, Dep::SignalCli],
+            false,
+        )
+        .await?;
+    }
+
+    init::link_signal_device(Some(config)).await?;
+
+    info!("Signal relinked");
+    Ok(())
+}
+
+/// Unregister the account (primary only) and remove local Signal data
+async fn unregister() -> Result<()> {
+    let config = Config::load()?;
+    let phone_number = configured_number(&config)?;
+    ensure_daemon_stopped()?;
+
+    let account = signal::account_status(&phone_number)?;
+    let is_primary = account
+        .as_ref()
+        .is_some_and(|a| a.registered && !a.is_linked_device());
+
+    if is_primary {
+        let choice = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!(
+                "Also unregister {} from the Signal server? Signal will stop delivering messages to this number.",
+                phone_number
+            ))
+            .items(&["No, only remove local data", "Yes, unregister"])
+            .default(0)
+            .interact()?;
+
+        if choice == 1 {
+            print!("Unregistering... ");
+            std::io::Write::flush(&mut std::io::stdout())?;
+            match signal::unregister_account(&phone_number).await {
+                Ok(()) => println!("OK"),
+                Err(e) => {
+                    println!("FAILED");
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    let confirm = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Delete the local signal-cli data directory?")
+        .items(&["Yes, delete it", "Cancel"])
+        .default(0)
+        .interact()?;
+
+    if confirm != 0 {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    print!("Removing Signal data... ");
+    std::io::Write::flush(&mut std::io::stdout())?;
+    match signal::remove_account_data() {
+        Ok(()) => println!("OK"),
+        Err(e) => {
+            println!("FAILED");
+            return Err(e);
+        }
+    }
+
+    println!();
+    if account.as_ref().is_some_and(|a| a.is_linked_device()) {
+        println!("Remember to remove \"Cica\" from Linked Devices in the Signal app.");
+    }
+    println!("Run `cica signal relink` or `cica init` to set Signal up again.");
+
+    info!("Signal account data removed");
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use anyhow::{Result, bail};
+use clap::Subcommand;
+use dialoguer::{Confirm, theme::ColorfulTheme};
+
+use cica_core::skills::{self, SkillHealth};
+
+/// Skill subcommands
+#[derive(Subcommand)]
+pub enum SkillCommand {
+    /// Install a skill from a git URL, or by name from the registry under [skills] in config.toml
+    Install {
+        /// Git URL of the skill's repository, or its name in the registry
+        source: String,
+        /// Install without asking, after showing what it needs
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Run each skill's healthcheck and show which skills work
+    Check,
+}
+
+/// Run a skill subcommand
+pub async fn run(command: SkillCommand) -> Result<()> {
+    match command {
+        SkillCommand::Install { source, yes } => install(&source, yes).await,
+        SkillCommand::Check => check().await,
+    }
+}
+
+/// Show what a skill needs, install it once confirmed and say what was
+/// installed
+async fn install(source: &str, yes: bool) -> Result<()> {
+    let reviewed = skills::review(source).await?;
+    println!("{}\n", reviewed.describe());
+    if !yes
+        && !Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt("Install this skill?")
+            .default(false)
+            .interact()?
+    {
+        println!("Not installed.");
+        return Ok(());
+    }
+
+    let installed = reviewed.install().await?;
+    println!(
+        "Installed skill {}: {}",
+        installed.skill.name, installed.skill.description
+    );
+    println!("  Location: {}", installed.skill.location.display());
+    if installed.dependencies {
+        println!("  Dependencies installed with Bun");
+    }
+    if !installed.missing_env.is_empty() {
+        println!("  Not set: {}", installed.missing_env.join(", "));
+    }
+    Ok(())
+}
+
+/// Check every installed skill, failing if any is broken
+async fn check() -> Result<()> {
+    let checked = skills::check_all().await?;
+    if checked.is_empty() {
+        println!("No skills installed");
+        return Ok(());
+    }
+
+    let mut broken = 0;
+    for (skill, health) in &checked {
+        let status = match health {
+            SkillHealth::Ok => "ok".to_string(),
+            SkillHealth::Broken(reason) => {
+                broken += 1;
+                format!("broken: {}", reason)
+            }
+            SkillHealth::Unchecked => "no healthcheck".to_string(),
+        };
+        println!("{:<20} {}", skill.name, status);
+    }
+
+    if broken > 0 {
+        bail!("{} of {} skills are broken", broken, checked.len());
+    }
+    Ok(())
+}
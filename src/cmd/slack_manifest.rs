@@ -0,0 +1,10 @@
+use anyhow::Result;
+
+use cica_core::channels::slack;
+
+use super::output::print_json;
+
+/// Run the print-slack-manifest command
+pub fn run(name: &str) -> Result<()> {
+    print_json(&slack::app_manifest(name))
+}
@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use cica_core::config::{AiBackend, Config};
+use cica_core::cron::{CronStore, format_timestamp, store};
+use cica_core::metrics;
+use cica_core::pairing::PairingStore;
+use cica_core::secrets::SecretStore;
+
+use super::output::print_json;
+
+/// Output of `cica status`
+#[derive(Serialize)]
+struct Status {
+    version: &'static str,
+    configured: bool,
+    backend: AiBackend,
+    model: Option<String>,
+    channels: Vec<&'static str>,
+    secrets: SecretStore,
+    users: UserCounts,
+    cron_jobs: JobCounts,
+    /// Messages Cica couldn't process, by "channel.kind"
+    unsupported: BTreeMap<String, u64>,
+}
+
+#[derive(Serialize)]
+struct UserCounts {
+    approved: usize,
+    pending: usize,
+    blocked: usize,
+}
+
+#[derive(Serialize)]
+struct JobCounts {
+    total: usize,
+    enabled: usize,
+    /// When all jobs were paused (`cica cron pause-all`), if they are
+    paused_since: Option<u64>,
+}
+
+/// Run the status command
+pub fn run(json: bool) -> Result<()> {
+    let configured = Config::exists()?;
+    let config = if configured {
+        Config::load()?
+    } else {
+        Config::default()
+    };
+
+    let mut pairing = PairingStore::load()?;
+    let cron = CronStore::load()?;
+
+    let status = Status {
+        version: env!("CARGO_PKG_VERSION"),
+        configured,
+        backend: config.backend,
+        model: config.model().map(str::to_string),
+        channels: config.configured_channels(),
+        secrets: config.secrets.store,
+        users: UserCounts {
+            approved: pairing.approved.values().map(Vec::len).sum(),
+            pending: pairing.list_pending().len(),
+            blocked: pairing.blocked.values().map(Vec::len).sum(),
+        },
+        cron_jobs: JobCounts {
+            total: cron.jobs.len(),
+            enabled: cron.get_enabled_jobs().len(),
+            paused_since: store::paused_since()?,
+        },
+        unsupported: metrics::counters("unsupported.")?,
+    };
+
+    if json {
+        return print_json(&status);
+    }
+
+    println!("Cica {}", status.version);
+    println!();
+    if !status.configured {
+        println!("Not set up yet. Run: cica init");
+        return Ok(());
+    }
+
+    let backend = match status.backend {
+        AiBackend::Claude => "Claude",
+        AiBackend::Cursor => "Cursor",
+        AiBackend::OpenAi => "OpenAI-compatible API",
+    };
+    println!(
+        "  Backend:   {} ({})",
+        backend,
+        status.model.as_deref().unwrap_or("default model")
+    );
+    println!(
+        "  Channels:  {}",
+        if status.channels.is_empty() {
+            "none".to_string()
+        } else {
+            status.channels.join(", ")
+        }
+    );
+    println!("  Secrets:   {}", status.secrets.as_str());
+    println!(
+        "  Users:     {} approved, {} pending, {} blocked",
+        status.users.approved, status.users.pending, status.users.blocked
+    );
+    println!(
+        "  Cron jobs: {} ({} enabled){}",
+        status.cron_jobs.total,
+        status.cron_jobs.enabled,
+        status
+            .cron_jobs
+            .paused_since
+            .map(|since| format!(", all paused since {}", format_timestamp(since)))
+            .unwrap_or_default()
+    );
+
+    if !status.unsupported.is_empty() {
+        println!();
+        println!("Unsupported messages received:");
+        for (kind, count) in &status.unsupported {
+            println!("  {:<24} {}", kind, count);
+        }
+    }
+
+    Ok(())
+}
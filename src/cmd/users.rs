@@ -0,0 +1,209 @@
+use anyhow::{Context, Result, bail};
+use clap::Subcommand;
+use std::path::PathBuf;
+use tracing::info;
+
+use cica_core::channels;
+use cica_core::onboarding::{self, UserFile};
+use cica_core::pairing::PairingStore;
+
+use super::output::print_json;
+
+/// User management subcommands
+#[derive(Subcommand)]
+pub enum UsersCommand {
+    /// List approved users
+    List,
+
+    /// Pre-provision a user's IDENTITY.md and/or USER.md so they skip onboarding
+    Import {
+        /// The user as <channel>:<user_id> (e.g., "telegram:123456")
+        user: String,
+
+        /// Markdown file to use as the user's IDENTITY.md
+        #[arg(long)]
+        identity: Option<PathBuf>,
+
+        /// Markdown file to use as the user's USER.md
+        #[arg(long)]
+        profile: Option<PathBuf>,
+
+        /// Overwrite files the user already has
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Make a user go through onboarding again, archiving their IDENTITY.md and USER.md
+    Reset {
+        /// Channel name (e.g., "telegram")
+        channel: String,
+        /// The user's ID on that channel
+        user_id: String,
+        /// Delete the files instead of archiving them
+        #[arg(long)]
+        delete: bool,
+    },
+}
+
+/// Run a users subcommand
+pub fn run(command: UsersCommand, json: bool) -> Result<()> {
+    match command {
+        UsersCommand::List => list(json),
+        UsersCommand::Import {
+            user,
+            identity,
+            profile,
+            force,
+        } => import(&user, identity, profile, force),
+        UsersCommand::Reset {
+            channel,
+            user_id,
+            delete,
+        } => reset(&channel, &user_id, delete),
+    }
+}
+
+/// List approved users with their role and onboarding state
+fn list(json: bool) -> Result<()> {
+    let users = PairingStore::load()?.approved_users();
+
+    if json {
+        return print_json(&users);
+    }
+
+    if users.is_empty() {
+        println!("No approved users.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<10} {:<20} {:<8} {:<10} SESSION",
+        "CHANNEL", "USER ID", "ROLE", "ONBOARDED"
+    );
+    for user in users {
+        let channel_display = channels::get_channel_info(&user.channel)
+            .map(|c| c.display_name)
+            .unwrap_or(&user.channel);
+
+        println!(
+            "{:<10} {:<20} {:<8} {:<10} {}",
+            channel_display,
+            user.user_id,
+            user.role.as_str(),
+            if user.onboarded { "yes" } else { "no" },
+            user.session_id.as_deref().unwrap_or("-")
+        );
+    }
+
+    Ok(())
+}
+
+/// Import onboarding files for a user
+fn import(
+    user: &str,
+    identity: Option<PathBuf>,
+    profile: Option<PathBuf>,
+    force: bool,
+) -> Result<()> {
+    let Some((channel, user_id)) = user.split_once(':') else {
+        bail!("Expected <channel>:<user_id>, got: {}", user);
+    };
+    let Some(info) = channels::get_channel_info(channel) else {
+        bail!("Unknown channel: {}", channel);
+    };
+
+    let files: Vec<(UserFile, PathBuf)> =
+        [(UserFile::Identity, identity), (UserFile::Profile, profile)]
+            .into_iter()
+            .filter_map(|(file, source)| source.map(|source| (file, source)))
+            .collect();
+    if files.is_empty() {
+        bail!("Nothing to import. Pass --identity and/or --profile.");
+    }
+
+    // Check everything before writing anything
+    let mut contents = Vec::new();
+    for (file, source) in files {
+        let target = file.path_for_user(channel, user_id)?;
+        if target.exists() && !force {
+            bail!(
+                "{} already exists. Use --force to overwrite it.",
+                target.display()
+            );
+        }
+        let content = std::fs::read_to_string(&source)
+            .with_context(|| format!("Could not read {}", source.display()))?;
+        contents.push((file, content));
+    }
+
+    for (file, content) in &contents {
+        let missing = onboarding::missing_fields(*file, content);
+        if !missing.is_empty() {
+            println!(
+                "Note: {} has no {}; it's used as written.",
+                file.file_name(),
+                missing.join(", ")
+            );
+        }
+    }
+
+    for (file, content) in contents {
+        let path = onboarding::import_user_file(channel, user_id, file, &content)?;
+        println!("Wrote {}", path.display());
+    }
+
+    if onboarding::is_complete_for_user(channel, user_id)? {
+        PairingStore::load()?.set_onboarded(channel, user_id, true)?;
+        println!(
+            "{} user {} will skip onboarding.",
+            info.display_name, user_id
+        );
+    } else {
+        println!(
+            "{} user {} will still be asked about the missing parts.",
+            info.display_name, user_id
+        );
+    }
+
+    info!("Imported onboarding files for {}:{}", channel, user_id);
+
+    Ok(())
+}
+
+/// Restart a user's onboarding
+fn reset(channel: &str, user_id: &str, delete: bool) -> Result<()> {
+    let Some(info) = channels::get_channel_info(channel) else {
+        bail!("Unknown channel: {}", channel);
+    };
+
+    let mut store = PairingStore::load()?;
+    if !store.is_approved(channel, user_id) {
+        bail!("{} user {} is not approved", info.display_name, user_id);
+    }
+    if !onboarding::is_identity_configured_for_user(channel, user_id)?
+        && !onboarding::is_user_configured_for_user(channel, user_id)?
+    {
+        println!(
+            "{} user {} hasn't been onboarded yet.",
+            info.display_name, user_id
+        );
+        return Ok(());
+    }
+
+    let archived = onboarding::restart_onboarding(channel, user_id, !delete)?;
+    store.reset_session(channel, user_id)?;
+    store.set_onboarded(channel, user_id, false)?;
+
+    match archived {
+        Some(dir) => println!("Archived IDENTITY.md and USER.md to {}", dir.display()),
+        None => println!("Deleted IDENTITY.md and USER.md"),
+    }
+    println!(
+        "{} user {} will be onboarded again on their next message.",
+        info.display_name, user_id
+    );
+
+    info!("Reset onboarding for {}:{}", channel, user_id);
+
+    Ok(())
+}
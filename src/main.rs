@@ -1,16 +1,12 @@
-mod backends;
-mod channels;
 mod cmd;
-mod config;
-mod cron;
-mod memory;
-mod onboarding;
-mod pairing;
-mod setup;
-mod skills;
+
+use std::io::IsTerminal;
+use std::path::PathBuf;
 
 use anyhow::Result;
+use cica_core::{redact, secrets};
 use clap::{Parser, Subcommand};
+use dialoguer::{Password, theme::ColorfulTheme};
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
 
 #[derive(Parser)]
@@ -20,21 +16,213 @@ use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitEx
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// Print machine-readable JSON (status, pending, users list, cron list, history, deps list, paths, features)
+    #[arg(long, global = true)]
+    json: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Set up Cica or add a new channel
-    Init,
+    /// Set up Cica or add a new channel (interactively, or with flags for scripts:
+    /// `cica init --channel telegram --bot-token-env BOT_TOKEN --backend claude --api-key-env ANTHROPIC_API_KEY`)
+    Init {
+        #[command(flatten)]
+        args: cmd::init::InitArgs,
+    },
+
+    /// Show the configured backend, channels, users and cron jobs
+    Status,
 
     /// Approve a pairing request
     Approve {
         /// The pairing code shown to the user
         code: String,
+
+        /// Also make this user the owner, who receives system notifications
+        #[arg(long)]
+        owner: bool,
+    },
+
+    /// List outstanding pairing requests
+    Pending,
+
+    /// Remove an approved user and clear their session
+    Revoke {
+        /// Channel name (e.g., "telegram")
+        channel: String,
+        /// The user's ID on that channel
+        user_id: String,
+    },
+
+    /// Ignore all messages from a user, revoking any approval
+    Block {
+        /// Channel name (e.g., "telegram")
+        channel: String,
+        /// The user's ID on that channel
+        user_id: String,
+    },
+
+    /// Let a blocked user request pairing again
+    Unblock {
+        /// Channel name (e.g., "telegram")
+        channel: String,
+        /// The user's ID on that channel
+        user_id: String,
+    },
+
+    /// Show the conversation with a user
+    History {
+        /// Channel name (e.g., "telegram")
+        channel: String,
+        /// The user's ID on that channel
+        user_id: String,
+        /// Number of most recent messages to show
+        #[arg(long, short = 'n', default_value_t = 50)]
+        limit: usize,
+    },
+
+    /// Print the system prompt and message a message would be sent as, without querying the backend
+    Prompt {
+        /// Channel name (e.g., "telegram")
+        #[arg(long)]
+        channel: String,
+        /// The user's ID on that channel
+        #[arg(long)]
+        user: String,
+        /// Message to find relevant memories for
+        #[arg(long, default_value = "")]
+        message: String,
+    },
+
+    /// Remove attachments past the retention policy under [attachments] in config.toml
+    Clean {
+        /// Only show what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Remove sessions and profiles left behind by users who are no longer approved
+    Gc {
+        /// Only show what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Show or change an approved user's role (owner, member, guest)
+    Role {
+        /// Channel name (e.g., "telegram")
+        channel: String,
+        /// The user's ID on that channel
+        user_id: String,
+        /// New role; omit to show the current one
+        role: Option<cica_core::pairing::Role>,
+    },
+
+    /// List, add or remove owners, who can use owner-only chat commands
+    Owner {
+        #[command(subcommand)]
+        command: cmd::owner::OwnerCommand,
+    },
+
+    /// Manage users (list them, import pre-written identity and profile files)
+    Users {
+        #[command(subcommand)]
+        command: cmd::users::UsersCommand,
+    },
+
+    /// Inspect scheduled cron jobs
+    Cron {
+        #[command(subcommand)]
+        command: cmd::cron::CronCommand,
+    },
+
+    /// Install skills
+    Skill {
+        #[command(subcommand)]
+        command: cmd::skill::SkillCommand,
+    },
+
+    /// Show or edit PERSONA.md, or a channel's persona override
+    Persona {
+        #[command(subcommand)]
+        command: cmd::persona::PersonaCommand,
+    },
+
+    /// Manage MCP servers the agent can use (Claude Code and Cursor)
+    Mcp {
+        #[command(subcommand)]
+        command: cmd::mcp::McpCommand,
+    },
+
+    /// Check config.toml
+    Config {
+        #[command(subcommand)]
+        command: cmd::config::ConfigCommand,
+    },
+
+    /// Run Cica as a background service (install, uninstall, status, stop, restart)
+    Service {
+        #[command(subcommand)]
+        command: cmd::service::ServiceCommand,
+    },
+
+    /// Show or change where tokens and API keys are stored (plaintext, keyring, passphrase)
+    Secrets {
+        /// Store to move secrets to; omit to show the current one
+        store: Option<cica_core::secrets::SecretStore>,
+    },
+
+    /// Back up config, users, cron jobs, memories and skills to one archive
+    Backup {
+        /// Archive to write (e.g., cica-backup.tar.gz)
+        path: PathBuf,
+        /// Include tokens and API keys, in plaintext
+        #[arg(long)]
+        with_secrets: bool,
+    },
+
+    /// Restore a backup made with `cica backup`
+    Restore {
+        /// Archive to restore
+        path: PathBuf,
+        /// Replace an existing setup
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Show, update or clean up the downloaded Bun, Claude Code, Cursor CLI, Java and signal-cli
+    Deps {
+        #[command(subcommand)]
+        command: cmd::deps::DepsCommand,
     },
 
     /// Show where Cica stores its data
     Paths,
+
+    /// Show which optional features this build includes
+    Features,
+
+    /// Print a Dockerfile that runs Cica with the current config
+    /// (dependencies baked in, data on a volume)
+    PrintDockerfile,
+
+    /// Print a Slack app manifest with the scopes, events and /cica slash
+    /// command Cica needs (paste it when creating the app, or into its
+    /// App Manifest page to pick up new commands)
+    #[cfg(feature = "slack")]
+    PrintSlackManifest {
+        /// App and bot name shown in Slack
+        #[arg(long, default_value = "Cica")]
+        name: String,
+    },
+
+    /// Manage the Signal account (status, relink, unregister)
+    #[cfg(feature = "signal")]
+    Signal {
+        #[command(subcommand)]
+        command: cmd::signal::SignalCommand,
+    },
 }
 
 #[tokio::main]
@@ -42,15 +230,66 @@ async fn main() -> Result<()> {
     // Initialize logging
     tracing_subscriber::registry()
         .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
-        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::fmt::layer().with_writer(|| redact::Writer(std::io::stdout())))
         .init();
 
     let cli = Cli::parse();
 
+    // Encrypted secrets need the passphrase before any config is loaded
+    if secrets::config_needs_passphrase()
+        && std::env::var_os(secrets::PASSPHRASE_ENV).is_none()
+        && std::io::stdin().is_terminal()
+    {
+        let passphrase = Password::with_theme(&ColorfulTheme::default())
+            .with_prompt("Passphrase for Cica's secrets")
+            .interact()?;
+        secrets::set_passphrase(passphrase);
+    }
+
     match cli.command {
-        Some(Commands::Init) => cmd::init::run().await,
-        Some(Commands::Approve { code }) => cmd::approve::run(&code),
-        Some(Commands::Paths) => cmd::paths::run(),
+        Some(Commands::Init { args }) => cmd::init::run(args).await,
+        Some(Commands::Approve { code, owner }) => cmd::approve::run(&code, owner),
+        Some(Commands::Status) => cmd::status::run(cli.json),
+        Some(Commands::Pending) => cmd::pending::run(cli.json),
+        Some(Commands::Revoke { channel, user_id }) => cmd::revoke::run(&channel, &user_id),
+        Some(Commands::Block { channel, user_id }) => cmd::block::run(&channel, &user_id),
+        Some(Commands::Unblock { channel, user_id }) => cmd::block::unblock(&channel, &user_id),
+        Some(Commands::History {
+            channel,
+            user_id,
+            limit,
+        }) => cmd::history::run(&channel, &user_id, limit, cli.json),
+        Some(Commands::Prompt {
+            channel,
+            user,
+            message,
+        }) => cmd::prompt::run(&channel, &user, &message, cli.json),
+        Some(Commands::Clean { dry_run }) => cmd::clean::run(dry_run),
+        Some(Commands::Gc { dry_run }) => cmd::gc::run(dry_run),
+        Some(Commands::Role {
+            channel,
+            user_id,
+            role,
+        }) => cmd::role::run(&channel, &user_id, role),
+        Some(Commands::Owner { command }) => cmd::owner::run(command),
+        Some(Commands::Users { command }) => cmd::users::run(command, cli.json),
+        Some(Commands::Cron { command }) => cmd::cron::run(command, cli.json),
+        Some(Commands::Skill { command }) => cmd::skill::run(command).await,
+        Some(Commands::Persona { command }) => cmd::persona::run(command),
+        Some(Commands::Mcp { command }) => cmd::mcp::run(command).await,
+        Some(Commands::Config { command }) => cmd::config::run(command),
+        Some(Commands::Service { command }) => cmd::service::run(command),
+        Some(Commands::Secrets { store }) => cmd::secrets::run(store),
+        Some(Commands::Backup { path, with_secrets }) => cmd::backup::run(&path, with_secrets),
+        Some(Commands::Restore { path, force }) => cmd::backup::restore(&path, force),
+        Some(Commands::Deps { command }) => cmd::deps::run(command, cli.json).await,
+        Some(Commands::Paths) => cmd::paths::run(cli.json),
+        Some(Commands::Features) => cmd::features::run(cli.json),
+        Some(Commands::PrintDockerfile) => cmd::dockerfile::run(),
+        #[cfg(feature = "slack")]
+        Some(Commands::PrintSlackManifest { name }) => cmd::slack_manifest::run(&name),
+        #[cfg(feature = "signal")]
+        Some(Commands::Signal { command }) => cmd::signal::run(command).await,
         None => cmd::run::run().await,
     }
 }